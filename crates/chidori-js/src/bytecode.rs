@@ -281,6 +281,13 @@ pub struct FuncProto {
     /// `undefined` values) that instantiation CLONES instead of re-hashing
     /// and re-inserting every key per evaluation.
     pub obj_tpls: Vec<std::rc::Rc<ObjTemplate>>,
+    /// Binding names for a debugger (see [`crate::debug`]): present only for
+    /// protos compiled by [`crate::compiler::compile_module_debug`], whose
+    /// bindings all stay cells and whose ops are never fused or remapped.
+    pub debug: Option<Box<crate::debug::DebugInfo>>,
+    /// Block counters (see [`crate::coverage`]): present only for protos
+    /// compiled by [`crate::compiler::compile_module_coverage`].
+    pub blocks: Option<Box<crate::coverage::Blocks>>,
 }
 
 /// Compile-time template for an all-static-data-key object literal (see
@@ -372,6 +379,8 @@ impl FuncProto {
             inherit_home: false,
             templates: Vec::new(),
             obj_tpls: Vec::new(),
+            debug: None,
+            blocks: None,
         }
     }
 }
@@ -554,6 +554,44 @@ pub fn compile_module(src: &str) -> Result<crate::module::CompiledModule, String
 pub fn compile_module_labeled(
     src: &str,
     label: Option<&str>,
+) -> Result<crate::module::CompiledModule, String> {
+    compile_module_impl(src, label, Instrument::None)
+}
+
+/// As [`compile_module_labeled`], for a debugger ([`crate::debug`]): every
+/// optimization pass is off — bindings stay cells, ops stay one per source
+/// operation, and everything runs on the stack interpreter the stepping
+/// hook lives in — and each function records its binding table
+/// ([`crate::bytecode::FuncProto::debug`]). Slower; only for debug runs.
+pub fn compile_module_debug(
+    src: &str,
+    label: Option<&str>,
+) -> Result<crate::module::CompiledModule, String> {
+    compile_module_impl(src, label, Instrument::Debug)
+}
+
+/// As [`compile_module_labeled`], counting how often each block of every
+/// function runs ([`crate::coverage`]): the optimization passes are off as
+/// for [`compile_module_debug`], so everything runs on the stack interpreter
+/// that bumps the counters. Slower; only for coverage runs.
+pub fn compile_module_coverage(
+    src: &str,
+    label: Option<&str>,
+) -> Result<crate::module::CompiledModule, String> {
+    compile_module_impl(src, label, Instrument::Coverage)
+}
+
+#[derive(PartialEq)]
+enum Instrument {
+    None,
+    Debug,
+    Coverage,
+}
+
+fn compile_module_impl(
+    src: &str,
+    label: Option<&str>,
+    instrument: Instrument,
 ) -> Result<crate::module::CompiledModule, String> {
     use crate::module::*;
     let allocator = Allocator::default();
@@ -579,6 +617,14 @@ pub fn compile_module_labeled(
     c.source = Rc::from(src);
     c.is_module = true;
     c.source_label = label.map(Rc::from);
+    if instrument != Instrument::None {
+        c.fuse = false;
+        c.localize = false;
+        c.kernelize = false;
+        c.regify = false;
+        c.debug = instrument == Instrument::Debug;
+        c.coverage = instrument == Instrument::Coverage;
+    }
     let (proto, cell_of_name) = c.compile_module_toplevel(&program).map_err(|e| {
         if e.starts_with("SyntaxError") {
             e
@@ -748,6 +794,9 @@ struct FnCtx {
     source_start: Option<u32>,
     /// `[[SourceText]]` byte range — see [`crate::bytecode::FuncProto::source_text`].
     source_text: Option<(u32, u32)>,
+    /// Debug compilation only: every binding declared so far, with the depth
+    /// of the scope it lives in (closed by `exit_scope` at that depth).
+    debug_bindings: Vec<(usize, crate::debug::DebugBinding)>,
 }
 
 impl FnCtx {
@@ -793,6 +842,7 @@ impl FnCtx {
             eval_scopes: Vec::new(),
             source_start: None,
             source_text: None,
+            debug_bindings: Vec::new(),
         }
     }
     fn alloc_cell(&mut self) -> u32 {
@@ -915,6 +965,14 @@ struct Compiler {
     /// differential test and under the `op-histogram` feature (register
     /// execution would hide per-op counts).
     regify: bool,
+    /// Record binding tables for a debugger (`compile_module_debug`). Set only
+    /// together with every optimization pass off, so each binding stays a
+    /// cell and op indices are the ones the tables were recorded against.
+    debug: bool,
+    /// Give every function block counters (`compile_module_coverage`). Set
+    /// only with every optimization pass off, so the stack interpreter runs
+    /// the code the blocks were found in.
+    coverage: bool,
 }
 
 impl Compiler {
@@ -944,6 +1002,8 @@ impl Compiler {
             localize: true,
             kernelize: !cfg!(feature = "op-histogram"),
             regify: !cfg!(feature = "op-histogram"),
+            debug: false,
+            coverage: false,
         }
     }
 
@@ -1142,7 +1202,34 @@ impl Compiler {
         });
     }
     fn exit_scope(&mut self) {
-        self.cur().scopes.pop();
+        let fc = self.cur();
+        fc.scopes.pop();
+        let depth = fc.scopes.len();
+        let end = fc.code.len() as u32;
+        for (d, b) in fc.debug_bindings.iter_mut() {
+            if *d == depth && b.end == u32::MAX {
+                b.end = end;
+            }
+        }
+    }
+
+    /// Debug compilation: note that `name` (in cell `cell`) comes into scope
+    /// here, in the scope at `depth`.
+    fn record_debug_binding(&mut self, depth: usize, name: &str, cell: u32) {
+        if !self.debug {
+            return;
+        }
+        let fc = self.cur();
+        let start = fc.code.len() as u32;
+        fc.debug_bindings.push((
+            depth,
+            crate::debug::DebugBinding {
+                name: name.to_string(),
+                cell,
+                start,
+                end: u32::MAX,
+            },
+        ));
     }
 
     /// Declare a binding in the current (block) scope, or the nearest function
@@ -1182,6 +1269,7 @@ impl Compiler {
             is_const,
             is_fn_name: false,
         });
+        self.record_debug_binding(scope_idx, name, cell);
         cell
     }
 
@@ -1200,6 +1288,7 @@ impl Compiler {
             is_const: false,
             is_fn_name: true,
         });
+        self.record_debug_binding(scope_idx, name, cell);
         cell
     }
 
@@ -2062,6 +2151,23 @@ impl Compiler {
         } else {
             Some(self.source_info().clone())
         };
+        let debug = self.debug.then(|| {
+            let end = code.len() as u32;
+            Box::new(crate::debug::DebugInfo {
+                bindings: fc
+                    .debug_bindings
+                    .drain(..)
+                    .map(|(_, mut b)| {
+                        b.end = b.end.min(end);
+                        b
+                    })
+                    .collect(),
+                upvalues: fc.upvalue_keys.clone(),
+            })
+        });
+        let blocks = self
+            .coverage
+            .then(|| Box::new(crate::coverage::Blocks::build(&code, &pos)));
         FuncProto {
             eval_scopes: fc.eval_scopes.clone(),
             name: fc.name,
@@ -2110,6 +2216,8 @@ impl Compiler {
             inherit_home: fc.inherit_home,
             templates: fc.templates,
             obj_tpls: fc.obj_tpls,
+            debug,
+            blocks,
         }
     }
 
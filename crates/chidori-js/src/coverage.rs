@@ -0,0 +1,299 @@
+//! Block coverage: how many times each straight-line run of a function's
+//! bytecode was entered, for reporting which parts of a program ran.
+//!
+//! Coverage compilation ([`crate::compiler::compile_module_coverage`]) turns
+//! the optimization passes off as debug compilation does, so every frame runs
+//! on the stack interpreter, and gives each function a [`Blocks`] table. A
+//! block is a basic block of the bytecode, split again wherever the source
+//! position changes, so a throw part-way through one overstates at most a
+//! statement. Blocks no path from the function's entry reaches (the `Jump`
+//! after a `return`, an implicit return after a final one) get no counter.
+//! The interpreter bumps a block's counter on reaching its first op.
+//!
+//! A [`Coverage`] installed on [`Vm::coverage`](crate::vm::Vm::coverage)
+//! picks the modules compiled this way and keeps their functions, so the
+//! counters can be read back ([`Coverage::functions`]) once the run is over.
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use crate::bytecode::{Const, FuncProto, Op};
+
+/// Marks an op that does not start a counted block.
+const NOT_A_BLOCK: u32 = u32::MAX;
+
+/// A coverage-compiled function's blocks and their counters.
+#[derive(Debug)]
+pub struct Blocks {
+    /// Per op: the block it is the first op of, or [`NOT_A_BLOCK`].
+    entry: Box<[u32]>,
+    /// Per block: its `[start, end)` op range.
+    spans: Box<[(u32, u32)]>,
+    hits: Box<[Cell<u64>]>,
+}
+
+impl Blocks {
+    /// The reachable blocks of `code`, whose per-op source positions are `pos`.
+    pub(crate) fn build(code: &[Op], pos: &[u32]) -> Blocks {
+        let n = code.len();
+        let mut leader = vec![false; n];
+        let mut targets = Vec::with_capacity(n);
+        for (i, op) in code.iter().enumerate() {
+            let ips = crate::fuse::ip_operands(op);
+            for &t in &ips {
+                if let Some(l) = leader.get_mut(t as usize) {
+                    *l = true;
+                }
+            }
+            if i == 0 || pos.get(i) != pos.get(i - 1) {
+                leader[i] = true;
+            }
+            if (!ips.is_empty() || ends_flow(op)) && i + 1 < n {
+                leader[i + 1] = true;
+            }
+            targets.push(ips);
+        }
+        let starts: Vec<usize> = (0..n).filter(|&i| leader[i]).collect();
+        let mut block_at = vec![0usize; n];
+        for (b, &start) in starts.iter().enumerate() {
+            let end = starts.get(b + 1).copied().unwrap_or(n);
+            block_at[start..end].fill(b);
+        }
+        // Reachability from the entry block. Every op with a target ends its
+        // block, so a block's successors are its last op's targets plus the
+        // next block unless that op never falls through.
+        let mut reachable = vec![false; starts.len()];
+        let mut work = Vec::new();
+        if !starts.is_empty() {
+            reachable[0] = true;
+            work.push(0);
+        }
+        while let Some(b) = work.pop() {
+            let end = starts.get(b + 1).copied().unwrap_or(n);
+            let last = end - 1;
+            let mut next: Vec<usize> = targets[last]
+                .iter()
+                .filter(|&&t| (t as usize) < n)
+                .map(|&t| block_at[t as usize])
+                .collect();
+            if !never_falls_through(&code[last]) && end < n {
+                next.push(b + 1);
+            }
+            for s in next {
+                if !reachable[s] {
+                    reachable[s] = true;
+                    work.push(s);
+                }
+            }
+        }
+        let mut entry = vec![NOT_A_BLOCK; n].into_boxed_slice();
+        let mut spans = Vec::new();
+        for (b, &start) in starts.iter().enumerate() {
+            if reachable[b] {
+                entry[start] = spans.len() as u32;
+                let end = starts.get(b + 1).copied().unwrap_or(n);
+                spans.push((start as u32, end as u32));
+            }
+        }
+        Blocks {
+            entry,
+            hits: spans.iter().map(|_| Cell::new(0)).collect(),
+            spans: spans.into_boxed_slice(),
+        }
+    }
+
+    /// Count the block starting at `ip`, if one does.
+    #[inline(always)]
+    pub(crate) fn enter(&self, ip: usize) {
+        if let Some(&b) = self.entry.get(ip) {
+            if b != NOT_A_BLOCK {
+                let hits = &self.hits[b as usize];
+                hits.set(hits.get() + 1);
+            }
+        }
+    }
+}
+
+/// Ops after which the next op starts a block: every transfer of control
+/// without a code-index operand.
+fn ends_flow(op: &Op) -> bool {
+    matches!(
+        op,
+        Op::Return | Op::ReturnUndefined | Op::Throw | Op::AsyncReturn | Op::EndFinally
+    )
+}
+
+/// Ops whose next op is only reached by jumping to it.
+fn never_falls_through(op: &Op) -> bool {
+    matches!(
+        op,
+        Op::Jump(_)
+            | Op::CompletionJump { .. }
+            | Op::Return
+            | Op::ReturnUndefined
+            | Op::Throw
+            | Op::AsyncReturn
+    )
+}
+
+/// Which modules a [`Vm`](crate::vm::Vm) compiles for coverage, and the
+/// functions it compiled so.
+pub struct Coverage {
+    covers: Box<dyn Fn(&str) -> bool>,
+    /// Each covered module's top-level function, in compilation order.
+    modules: RefCell<Vec<Rc<FuncProto>>>,
+}
+
+impl std::fmt::Debug for Coverage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Coverage")
+            .field("modules", &self.modules.borrow().len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Coverage {
+    /// Cover the modules whose key `covers` accepts.
+    pub fn new(covers: impl Fn(&str) -> bool + 'static) -> Coverage {
+        Coverage {
+            covers: Box::new(covers),
+            modules: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn covers(&self, key: &str) -> bool {
+        (self.covers)(key)
+    }
+
+    pub(crate) fn register(&self, module: &Rc<FuncProto>) {
+        self.modules.borrow_mut().push(module.clone());
+    }
+
+    /// Every function of the covered modules — called or not — with its
+    /// blocks' counts so far.
+    pub fn functions(&self) -> Vec<FunctionCoverage> {
+        let mut out = Vec::new();
+        for module in self.modules.borrow().iter() {
+            collect(module, true, &mut out);
+        }
+        out
+    }
+}
+
+fn collect(proto: &FuncProto, toplevel: bool, out: &mut Vec<FunctionCoverage>) {
+    if let Some(blocks) = &proto.blocks {
+        out.push(FunctionCoverage {
+            label: proto.source_label.clone(),
+            name: proto.name.clone(),
+            source_start: proto.source_start,
+            toplevel,
+            blocks: blocks
+                .spans
+                .iter()
+                .zip(blocks.hits.iter())
+                .map(|(&(start, _), hits)| BlockCoverage {
+                    pos: proto.pos_at(start as usize).unwrap_or(0),
+                    hits: hits.get(),
+                })
+                .collect(),
+        });
+    }
+    for c in &proto.consts {
+        if let Const::Func(inner) = c {
+            collect(inner, false, out);
+        }
+    }
+}
+
+/// One function's coverage.
+#[derive(Debug, Clone)]
+pub struct FunctionCoverage {
+    /// The module key it was compiled under.
+    pub label: Option<Rc<str>>,
+    pub name: String,
+    /// Byte offset of its definition in the module source.
+    pub source_start: u32,
+    /// A module body rather than a function in one.
+    pub toplevel: bool,
+    /// Its blocks, entry block first.
+    pub blocks: Vec<BlockCoverage>,
+}
+
+/// One block's coverage.
+#[derive(Debug, Clone)]
+pub struct BlockCoverage {
+    /// The source position (a byte offset into the module source) its ops
+    /// were emitted for.
+    pub pos: u32,
+    /// How many times it was entered.
+    pub hits: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Coverage;
+    use crate::Engine;
+    use std::collections::BTreeMap;
+    use std::rc::Rc;
+
+    fn run(src: &str) -> (Rc<Coverage>, serde_json::Value) {
+        let mut engine = Engine::new();
+        let coverage = Rc::new(Coverage::new(|key| key == "main.js"));
+        engine.vm.coverage = Some(coverage.clone());
+        let slot = engine.install_entrypoint();
+        let out = engine
+            .run_entrypoint_graph(
+                "main.js",
+                src,
+                &serde_json::json!({}),
+                &slot,
+                "agent",
+                &mut |spec, _| Err(format!("no module {spec}")),
+            )
+            .unwrap();
+        (coverage, out)
+    }
+
+    /// 1-based line → the most any block with an op on it was entered.
+    fn lines(src: &str, coverage: &Coverage) -> BTreeMap<usize, u64> {
+        let mut lines = BTreeMap::new();
+        for f in coverage.functions() {
+            for block in &f.blocks {
+                let line = src[..block.pos as usize].matches('\n').count() + 1;
+                let hits = lines.entry(line).or_insert(0);
+                *hits = block.hits.max(*hits);
+            }
+        }
+        lines
+    }
+
+    #[test]
+    fn blocks_count_the_paths_the_program_took() {
+        let src = "export function classify(n) {\n  if (n > 10) {\n    return 'big';\n  }\n  return 'small';\n}\nfunction unused() {\n  return 1;\n}\nexport function agent() {\n  const out = [];\n  for (const n of [1, 2, 3]) out.push(classify(n));\n  return out.join(',');\n}\n";
+        let (coverage, out) = run(src);
+        assert_eq!(out, "small,small,small");
+        let lines = lines(src, &coverage);
+        assert_eq!(lines.get(&2), Some(&3), "{lines:?}");
+        assert_eq!(lines.get(&3), Some(&0), "{lines:?}");
+        assert_eq!(lines.get(&5), Some(&3), "{lines:?}");
+        assert_eq!(lines.get(&8), Some(&0), "{lines:?}");
+        assert_eq!(lines.get(&13), Some(&1), "{lines:?}");
+        let functions = coverage.functions();
+        assert!(functions[0].toplevel);
+        let unused = functions.iter().find(|f| f.name == "unused").unwrap();
+        assert!(unused.blocks.iter().all(|b| b.hits == 0));
+    }
+
+    #[test]
+    fn code_no_path_reaches_has_no_block() {
+        let src = "function f(x) {\n  if (x) { return 1; } else { return 2; }\n}\nexport function agent() {\n  return f(true) + f(false);\n}\n";
+        let (coverage, out) = run(src);
+        assert_eq!(out, 3);
+        let f = coverage
+            .functions()
+            .into_iter()
+            .find(|f| f.name == "f")
+            .unwrap();
+        assert!(f.blocks.iter().all(|b| b.hits > 0), "{:?}", f.blocks);
+    }
+}
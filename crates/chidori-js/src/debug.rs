@@ -0,0 +1,608 @@
+//! Source-level debugging: a stepping hook the interpreter notifies at every
+//! activation boundary and every statement of debug-compiled code, plus what
+//! a debugger needs to show a paused program — the names of a frame's cells
+//! and a side-effect-free view of values.
+//!
+//! Debug compilation ([`crate::compiler::compile_module_debug`]) turns every
+//! optimization pass off, so each binding stays a cell, each op maps to one
+//! source operation, and every frame runs on the stack interpreter, whose
+//! loop reports each change of source position to [`DebugHook::on_statement`].
+//! Each function records a [`DebugInfo`]: its bindings with the op range they
+//! are in scope over, and its upvalue names.
+//!
+//! The hook gets the [`Vm`] only between jobs ([`DebugHook::on_idle`]), to
+//! image it. A paused debuggee is one blocked inside `on_statement`, and
+//! what it shows is read straight from frames and objects
+//! ([`FrameSnapshot`], [`describe`], [`children`]): no getter, `toString` or
+//! proxy trap runs, so inspecting a value cannot change the program or its
+//! journal.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::value::{BytecodeFunction, FunctionInner, Internal, PropertyKey, PropertyKind, Value};
+use crate::vm::{Flow, Frame, PromiseState, Vm};
+
+/// A debug-compiled function's binding table.
+#[derive(Debug, Default)]
+pub struct DebugInfo {
+    /// Every declared binding, in declaration order. Synthetic compiler
+    /// bindings (`%this`, `%completion`, …) start with `%`.
+    pub bindings: Vec<DebugBinding>,
+    /// Upvalue names, index-parallel to [`BytecodeFunction::upvalues`].
+    pub upvalues: Vec<String>,
+}
+
+/// One binding: its cell, and the `[start, end)` op range of the scope that
+/// declares it.
+#[derive(Debug, Clone)]
+pub struct DebugBinding {
+    pub name: String,
+    pub cell: u32,
+    pub start: u32,
+    pub end: u32,
+}
+
+impl DebugInfo {
+    /// The user-visible bindings in scope at `ip` as `(name, cell)`, outermost
+    /// first; a shadowed name appears once, as its innermost binding.
+    pub fn bindings_at(&self, ip: usize) -> Vec<(&str, u32)> {
+        let ip = ip as u32;
+        let mut out: Vec<(&str, u32)> = Vec::new();
+        for b in &self.bindings {
+            if b.name.starts_with('%') || ip < b.start || ip >= b.end {
+                continue;
+            }
+            // Declaration order puts an inner scope's binding after the outer
+            // one it shadows.
+            match out.iter_mut().find(|(name, _)| *name == b.name) {
+                Some(slot) => slot.1 = b.cell,
+                None => out.push((&b.name, b.cell)),
+            }
+        }
+        out
+    }
+}
+
+/// Identity of a live activation: stable from its first entry to its final
+/// exit, across every suspension and resumption in between. Reused only
+/// after [`DebugHook::on_frame_exit`] reports it finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FrameId(usize);
+
+impl FrameId {
+    pub fn of(frame: &Frame) -> FrameId {
+        // A frame lives in one `Box` from creation to completion — the box
+        // moves into a suspension and back out, never its contents.
+        FrameId(frame as *const Frame as usize)
+    }
+}
+
+/// How an activation segment ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameExit {
+    Returned,
+    Threw,
+    /// Parked at `await`/`yield`; the same [`FrameId`] enters again on resume.
+    Suspended,
+}
+
+/// What the interpreter does after [`DebugHook::on_statement`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugAction {
+    Continue,
+    /// Unwind the run with an uncatchable error (the debugger disconnected,
+    /// or is rewinding a replay to run it again).
+    Abort,
+}
+
+/// A debugger attached to a [`Vm`] through [`Vm::debug_hook`].
+pub trait DebugHook {
+    /// Whether the module registered under `key` compiles for debugging.
+    /// Modules that don't run at full speed and never report statements.
+    fn debug_module(&mut self, _key: &str) -> bool {
+        true
+    }
+
+    /// An activation segment starts: a call (`frame.ip == 0`) or a
+    /// resumption after `await`/`yield`.
+    fn on_frame_enter(&mut self, id: FrameId, frame: &Frame);
+
+    /// The segment that entered as `id` ended.
+    fn on_frame_exit(&mut self, id: FrameId, exit: FrameExit);
+
+    /// The frame is about to run the first op at a new source position
+    /// (`pos`, a byte offset into the function's source). Blocking here
+    /// pauses the program.
+    fn on_statement(&mut self, frame: &Frame, pos: u32) -> DebugAction;
+
+    /// The job loop is between two jobs with no frame on the native stack:
+    /// the quiescent point at which [`Vm::snapshot_image`] can succeed.
+    fn on_idle(&mut self, _vm: &Vm) {}
+}
+
+impl Vm {
+    #[cold]
+    #[inline(never)]
+    pub(crate) fn debug_enter(&mut self, frame: &Frame) -> FrameId {
+        let id = FrameId::of(frame);
+        if let Some(hook) = self.debug_hook.as_mut() {
+            hook.on_frame_enter(id, frame);
+        }
+        id
+    }
+
+    #[cold]
+    #[inline(never)]
+    pub(crate) fn debug_exit(&mut self, id: FrameId, flow: &Flow) {
+        let exit = match flow {
+            Flow::Return(_) => FrameExit::Returned,
+            Flow::Throw(_) => FrameExit::Threw,
+            Flow::Suspend(_) => FrameExit::Suspended,
+        };
+        if let Some(hook) = self.debug_hook.as_mut() {
+            hook.on_frame_exit(id, exit);
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    pub(crate) fn debug_idle(&mut self) {
+        // Lent out for the call, so the hook can read the whole VM.
+        if let Some(mut hook) = self.debug_hook.take() {
+            hook.on_idle(self);
+            self.debug_hook = Some(hook);
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    pub(crate) fn debug_statement(&mut self, frame: &Frame, pos: u32) -> DebugAction {
+        match self.debug_hook.as_mut() {
+            Some(hook) => hook.on_statement(frame, pos),
+            None => DebugAction::Continue,
+        }
+    }
+}
+
+/// What a debugger keeps of a frame to show it later, while the frame itself
+/// sits out of reach further up the native stack: the cells are shared, so
+/// their values stay live; the position is the one it was snapshotted at.
+#[derive(Clone)]
+pub struct FrameSnapshot {
+    pub func: Rc<BytecodeFunction>,
+    pub cells: Vec<Rc<RefCell<Value>>>,
+    pub this: Value,
+    pub ip: usize,
+}
+
+impl FrameSnapshot {
+    pub fn of(frame: &Frame) -> FrameSnapshot {
+        FrameSnapshot {
+            func: frame.func.clone(),
+            cells: frame.cells.clone(),
+            this: frame.this.clone(),
+            ip: frame.ip,
+        }
+    }
+
+    /// Function name, `<anonymous>` when it has none.
+    pub fn name(&self) -> &str {
+        match self.func.proto.name.as_str() {
+            "" => "<anonymous>",
+            name => name,
+        }
+    }
+
+    /// The source label (module key) the function was compiled under.
+    pub fn label(&self) -> Option<&str> {
+        self.func.proto.source_label.as_deref()
+    }
+
+    /// Byte offset into the compiled source of the op at `ip`.
+    pub fn pos(&self) -> Option<u32> {
+        self.func.proto.pos_at(self.ip)
+    }
+
+    /// 1-based `(line, column)` in the compiled source of the op at `ip`.
+    pub fn position(&self) -> Option<(u32, u32)> {
+        let pos = self.pos()?;
+        Some(self.func.proto.source_info.as_ref()?.line_col_of(pos))
+    }
+
+    /// Whether the function was debug-compiled (has names to show).
+    pub fn is_debuggable(&self) -> bool {
+        self.func.proto.debug.is_some()
+    }
+
+    /// The bindings in scope at the snapshot's position, plus `this` for
+    /// functions that have their own.
+    pub fn locals(&self) -> Vec<(String, Value)> {
+        let Some(info) = &self.func.proto.debug else {
+            return Vec::new();
+        };
+        let mut out: Vec<(String, Value)> = info
+            .bindings_at(self.ip)
+            .into_iter()
+            .filter_map(|(name, cell)| {
+                let value = self.cells.get(cell as usize)?.borrow().clone();
+                Some((name.to_string(), value))
+            })
+            .collect();
+        if matches!(self.this, Value::Object(_)) {
+            out.push(("this".to_string(), self.this.clone()));
+        }
+        out
+    }
+
+    /// The function's captured variables, by name.
+    pub fn closure(&self) -> Vec<(String, Value)> {
+        let Some(info) = &self.func.proto.debug else {
+            return Vec::new();
+        };
+        info.upvalues
+            .iter()
+            .zip(&self.func.upvalues)
+            .filter(|(name, _)| !name.starts_with('%'))
+            .map(|(name, cell)| (name.clone(), cell.borrow().clone()))
+            .collect()
+    }
+}
+
+/// How many entries a preview or a child listing shows before eliding.
+const PREVIEW_ENTRIES: usize = 5;
+const MAX_CHILDREN: usize = 1000;
+
+/// A one-line rendering of `value`, with a shallow preview of objects.
+pub fn describe(value: &Value) -> String {
+    describe_depth(value, 1)
+}
+
+fn describe_depth(value: &Value, depth: u32) -> String {
+    match value {
+        Value::Undefined => "undefined".to_string(),
+        Value::Null => "null".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => crate::vm::number_to_string(*n),
+        Value::String(s) => format!("{:?}", s.as_str()),
+        Value::Symbol(s) => format!("Symbol({})", s.description().unwrap_or_default()),
+        Value::BigInt(n) => format!("{n}n"),
+        Value::Uninitialized => "<uninitialized>".to_string(),
+        Value::Hole => "<empty>".to_string(),
+        Value::Object(obj) => {
+            let data = obj.borrow();
+            match &data.internal {
+                Internal::Function(_) => format!("[Function: {}]", function_name(value)),
+                Internal::Array(items) if depth == 0 => format!("Array({})", items.len()),
+                Internal::Array(items) => {
+                    let mut parts: Vec<String> = items
+                        .iter()
+                        .take(PREVIEW_ENTRIES)
+                        .map(|v| describe_depth(v, 0))
+                        .collect();
+                    if items.len() > PREVIEW_ENTRIES {
+                        parts.push("…".to_string());
+                    }
+                    format!("[{}]", parts.join(", "))
+                }
+                Internal::Map(m) => format!("Map({})", m.len()),
+                Internal::Set(s) => format!("Set({})", s.len()),
+                Internal::Promise(p) => match &p.state {
+                    PromiseState::Pending => "Promise { <pending> }".to_string(),
+                    PromiseState::Fulfilled(v) => format!("Promise {{ {} }}", describe_depth(v, 0)),
+                    PromiseState::Rejected(v) => {
+                        format!("Promise {{ <rejected> {} }}", describe_depth(v, 0))
+                    }
+                },
+                Internal::Date(ms) => format!("Date({})", crate::vm::number_to_string(*ms)),
+                Internal::Error => {
+                    let message = data
+                        .own_get(&PropertyKey::str("message"))
+                        .and_then(|p| p.value().and_then(|v| v.as_str().map(str::to_string)))
+                        .unwrap_or_default();
+                    format!("{}: {message}", constructor_name(value))
+                }
+                _ if depth == 0 => "{…}".to_string(),
+                _ => {
+                    let mut parts: Vec<String> = data
+                        .own_iter()
+                        .filter(|(_, p)| p.enumerable)
+                        .take(PREVIEW_ENTRIES)
+                        .map(|(k, p)| match &p.kind {
+                            PropertyKind::Data { value, .. } => {
+                                format!("{}: {}", key_name(k), describe_depth(value, 0))
+                            }
+                            PropertyKind::Accessor { .. } => format!("{}: (accessor)", key_name(k)),
+                        })
+                        .collect();
+                    if data.own_iter().filter(|(_, p)| p.enumerable).count() > PREVIEW_ENTRIES {
+                        parts.push("…".to_string());
+                    }
+                    let name = constructor_name(value);
+                    let prefix = if name == "Object" {
+                        String::new()
+                    } else {
+                        format!("{name} ")
+                    };
+                    format!("{prefix}{{{}}}", parts.join(", "))
+                }
+            }
+        }
+    }
+}
+
+/// Whether `value` has [`children`] worth expanding.
+pub fn has_children(value: &Value) -> bool {
+    match value {
+        Value::Object(obj) => {
+            let data = obj.borrow();
+            match &data.internal {
+                Internal::Array(items) => !items.is_empty() || !data.own_is_empty(),
+                Internal::Map(m) => !m.is_empty(),
+                Internal::Set(s) => !s.is_empty(),
+                Internal::Promise(_) => true,
+                Internal::Function(_) => false,
+                _ => !data.own_is_empty(),
+            }
+        }
+        _ => false,
+    }
+}
+
+/// The named parts of an object, read without running any JS: own
+/// properties (an accessor is `None` — its getter is not invoked), array
+/// elements, Map/Set entries and a promise's state.
+pub fn children(value: &Value) -> Vec<(String, Option<Value>)> {
+    let Value::Object(obj) = value else {
+        return Vec::new();
+    };
+    let data = obj.borrow();
+    let mut out: Vec<(String, Option<Value>)> = Vec::new();
+    match &data.internal {
+        Internal::Array(items) => {
+            for (i, v) in items.iter().enumerate().take(MAX_CHILDREN) {
+                if !matches!(v, Value::Hole) {
+                    out.push((i.to_string(), Some(v.clone())));
+                }
+            }
+        }
+        Internal::Map(m) => {
+            for (i, (k, v)) in m.iter().enumerate().take(MAX_CHILDREN) {
+                out.push((
+                    format!("[{i}] {}", describe_depth(&k.0, 0)),
+                    Some(v.clone()),
+                ));
+            }
+        }
+        Internal::Set(s) => {
+            for (i, k) in s.keys().enumerate().take(MAX_CHILDREN) {
+                out.push((format!("[{i}]"), Some(k.0.clone())));
+            }
+        }
+        Internal::Promise(p) => {
+            let (state, result) = match &p.state {
+                PromiseState::Pending => ("pending", None),
+                PromiseState::Fulfilled(v) => ("fulfilled", Some(v.clone())),
+                PromiseState::Rejected(v) => ("rejected", Some(v.clone())),
+            };
+            out.push(("[[PromiseState]]".to_string(), Some(Value::str(state))));
+            if let Some(result) = result {
+                out.push(("[[PromiseResult]]".to_string(), Some(result)));
+            }
+        }
+        _ => {}
+    }
+    for (k, p) in data.own_iter() {
+        if out.len() >= MAX_CHILDREN {
+            break;
+        }
+        if data.is_array() && k.as_str() == Some("length") {
+            continue;
+        }
+        let value = match &p.kind {
+            PropertyKind::Data { value, .. } => Some(value.clone()),
+            PropertyKind::Accessor { .. } => None,
+        };
+        out.push((key_name(k), value));
+    }
+    out
+}
+
+fn key_name(key: &PropertyKey) -> String {
+    match key {
+        PropertyKey::Str(s) => s.as_str().to_string(),
+        PropertyKey::Sym(s) => format!("[Symbol({})]", s.description().unwrap_or_default()),
+    }
+}
+
+fn function_name(value: &Value) -> String {
+    let Value::Object(obj) = value else {
+        return String::new();
+    };
+    let data = obj.borrow();
+    if let Some(name) = data
+        .own_get(&PropertyKey::str("name"))
+        .and_then(|p| p.value().and_then(|v| v.as_str().map(str::to_string)))
+        .filter(|name| !name.is_empty())
+    {
+        return name;
+    }
+    match &data.internal {
+        Internal::Function(FunctionInner::Bytecode(bf)) if !bf.proto.name.is_empty() => {
+            bf.proto.name.clone()
+        }
+        Internal::Function(FunctionInner::Native(nf)) => nf.name.to_string(),
+        _ => "(anonymous)".to_string(),
+    }
+}
+
+/// The name of the nearest `constructor` on the prototype chain.
+fn constructor_name(value: &Value) -> String {
+    let Value::Object(obj) = value else {
+        return String::new();
+    };
+    let mut next = obj.borrow().proto.clone();
+    for _ in 0..16 {
+        let Some(proto) = next else { break };
+        let ctor = proto
+            .borrow()
+            .own_get(&PropertyKey::str("constructor"))
+            .and_then(|p| p.value().cloned());
+        if let Some(ctor @ Value::Object(_)) = ctor {
+            return function_name(&ctor);
+        }
+        next = proto.borrow().proto.clone();
+    }
+    "Object".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{describe, DebugAction, DebugHook, FrameExit, FrameId, FrameSnapshot};
+    use crate::vm::{Frame, Vm};
+    use crate::Engine;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// Records `line N` per statement event (with the locals in scope when
+    /// asked to) and `exit/suspend` per activation.
+    struct Recorder {
+        events: Rc<RefCell<Vec<String>>>,
+        show_locals_on: Option<u32>,
+    }
+
+    impl DebugHook for Recorder {
+        fn on_frame_enter(&mut self, _id: FrameId, _frame: &Frame) {}
+
+        fn on_frame_exit(&mut self, _id: FrameId, exit: FrameExit) {
+            if exit == FrameExit::Suspended {
+                self.events.borrow_mut().push("suspend".to_string());
+            }
+        }
+
+        fn on_statement(&mut self, frame: &Frame, _pos: u32) -> DebugAction {
+            let snap = FrameSnapshot::of(frame);
+            if !snap.is_debuggable() {
+                return DebugAction::Continue;
+            }
+            let (line, _) = snap.position().expect("debug protos carry positions");
+            let mut events = self.events.borrow_mut();
+            if events.last().is_some_and(|e| e == &format!("line {line}")) {
+                return DebugAction::Continue;
+            }
+            events.push(format!("line {line}"));
+            if self.show_locals_on == Some(line) {
+                let locals: Vec<String> = snap
+                    .locals()
+                    .iter()
+                    .map(|(name, v)| format!("{name}={}", describe(v)))
+                    .collect();
+                events.push(format!("locals {}", locals.join(" ")));
+            }
+            DebugAction::Continue
+        }
+    }
+
+    fn debug_events(src: &str, show_locals_on: Option<u32>) -> Vec<String> {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut engine = Engine::new();
+        engine.vm.debug_hook = Some(Box::new(Recorder {
+            events: events.clone(),
+            show_locals_on,
+        }));
+        let mut load = |spec: &str, _: &str| Err(format!("no module {spec}"));
+        engine
+            .eval_module_export("main.js", src, "out", &mut load)
+            .expect("module evaluates");
+        let out = events.borrow().clone();
+        out
+    }
+
+    #[test]
+    fn statements_report_in_source_order_through_calls() {
+        let ev = debug_events(
+            "function add(a, b) {\n  const sum = a + b;\n  return sum;\n}\nconst x = 1;\nexport const out = add(x, 2);\n",
+            None,
+        );
+        assert_eq!(
+            ev,
+            vec!["line 1", "line 5", "line 6", "line 1", "line 2", "line 3"],
+            "declaration hoisting and the callee's parameter prologue sit on line 1"
+        );
+    }
+
+    #[test]
+    fn locals_name_the_innermost_binding_in_scope() {
+        let ev = debug_events(
+            "const a = 1;\nlet b = { k: [1, 2] };\n{\n  let a = 'inner';\n  b = a;\n}\nexport const out = a;\n",
+            Some(5),
+        );
+        let locals = ev
+            .iter()
+            .find(|e| e.starts_with("locals"))
+            .expect("line 5 ran");
+        assert_eq!(
+            locals, "locals a=\"inner\" b={k: Array(2)} out=<uninitialized>",
+            "the block's `a` shadows the module's; `out` is hoisted but in its TDZ"
+        );
+        let last = debug_events(
+            "const a = 1;\n{\n  let a = 'inner';\n}\nexport const out = a;\n",
+            Some(5),
+        );
+        assert!(
+            last.contains(&"locals a=1 out=<uninitialized>".to_string()),
+            "{last:?}"
+        );
+    }
+
+    #[test]
+    fn idle_points_fall_between_jobs_where_the_vm_images() {
+        struct Idle(Rc<RefCell<Vec<String>>>);
+        impl DebugHook for Idle {
+            fn on_frame_enter(&mut self, _id: FrameId, _frame: &Frame) {}
+            fn on_frame_exit(&mut self, _id: FrameId, _exit: FrameExit) {}
+            fn on_statement(&mut self, _frame: &Frame, _pos: u32) -> DebugAction {
+                DebugAction::Continue
+            }
+            fn on_idle(&mut self, vm: &Vm) {
+                self.0.borrow_mut().push(match vm.snapshot_image() {
+                    Ok(_) => "idle".to_string(),
+                    Err(e) => format!("idle, not imageable: {e}"),
+                });
+            }
+        }
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut engine = Engine::new();
+        let slot = engine.install_entrypoint();
+        engine.vm.mark_image_baseline();
+        engine.vm.debug_hook = Some(Box::new(Idle(events.clone())));
+        let mut load = |spec: &str, _: &str| Err(format!("no module {spec}"));
+        let outcome = engine
+            .run_entrypoint_graph_suspendable(
+                "main.js",
+                "export async function agent() {\n  await null;\n  return 2;\n}\n",
+                &serde_json::json!({}),
+                &slot,
+                "agent",
+                &mut load,
+            )
+            .expect("the entry runs");
+        assert!(matches!(outcome, crate::EntryOutcome::Settled(v) if v == 2));
+        let events = events.borrow();
+        assert!(!events.is_empty(), "the resumption ran as a job");
+        assert!(events.iter().all(|e| e == "idle"), "{events:?}");
+    }
+
+    #[test]
+    fn await_suspends_the_activation_and_resumes_on_its_next_line() {
+        let ev = debug_events(
+            "async function f() {\n  await null;\n  return 2;\n}\nconst p = f();\nexport const out = 1;\n",
+            None,
+        );
+        assert_eq!(
+            ev,
+            vec!["line 1", "line 5", "line 1", "line 2", "suspend", "line 6", "line 2", "line 3"],
+            "the resumed activation reports the `await` it resumes at first"
+        );
+    }
+}
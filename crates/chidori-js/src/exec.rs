@@ -5,6 +5,7 @@ use std::cell::RefCell;
 use std::rc::Rc;
 
 use crate::bytecode::{CmpOp, Const, FuncKind, KOp, Op, UpvalueSource, KWIN, KWIN_MASK};
+use crate::debug::DebugAction;
 use crate::value::*;
 use crate::vm::*;
 
@@ -1138,7 +1139,7 @@ impl Vm {
                                     callee_bfs.clear();
                                     self.kernel_callees = callee_bfs;
                                     self.op_budget = Some(0);
-                                    return Err(self.throw_range("execution interrupted"));
+                                    return Err(self.interrupted());
                                 }
                             }
                         }
@@ -1697,7 +1698,7 @@ impl Vm {
                             callee_bfs.clear();
                             self.kernel_callees = callee_bfs;
                             self.op_budget = Some(0);
-                            return Err(self.throw_range("execution interrupted"));
+                            return Err(self.interrupted());
                         }
                     }
                 }
@@ -1851,7 +1852,7 @@ impl Vm {
                 // Interrupted on a back-edge: latch the zero budget so a JS
                 // catch cannot resume execution.
                 self.op_budget = Some(0);
-                Some(Err(self.throw_range("execution interrupted")))
+                Some(Err(self.interrupted()))
             }
             // Tier bug (an op fn-mode translation never emits). The kernel is
             // pure and nothing was flushed, so declining to the generic call
@@ -2246,7 +2247,7 @@ impl Vm {
             RecOut::Abandon => None,
             RecOut::Interrupted => {
                 self.op_budget = Some(0);
-                Some(Err(self.throw_range("execution interrupted")))
+                Some(Err(self.interrupted()))
             }
         }
     }
@@ -2642,7 +2643,7 @@ impl Vm {
             if let Some(flag) = &p.interrupt {
                 if flag.load(std::sync::atomic::Ordering::Relaxed) {
                     self.op_budget = Some(0);
-                    return Some(Err(self.throw_range("execution interrupted")));
+                    return Some(Err(self.interrupted()));
                 }
             }
         }
@@ -2652,7 +2653,7 @@ impl Vm {
                 // Same latch-and-unwind as an interrupted kernel back-edge:
                 // zero the budget so a JS catch cannot resume execution.
                 self.op_budget = Some(0);
-                Some(Err(self.throw_range("execution interrupted")))
+                Some(Err(self.interrupted()))
             }
             // Tier bug; the kernel is pure, so declining re-runs the callback
             // generically with correct semantics.
@@ -2755,7 +2756,7 @@ impl Vm {
             }
             FnKernelOut::Interrupted => {
                 self.op_budget = Some(0);
-                Err(self.throw_range("execution interrupted"))
+                Err(self.interrupted())
             }
         }
     }
@@ -3403,6 +3404,13 @@ impl Vm {
     /// decline register translation.
     pub fn run_frame(&mut self, frame: Box<Frame>) -> Flow {
         let proto = frame.func.proto.clone();
+        // A debugger sees every activation segment (call or resumption) begin
+        // and end, which is what it keeps its shadow call stack with.
+        let debug_id = if self.debug_hook.is_some() {
+            Some(self.debug_enter(&frame))
+        } else {
+            None
+        };
         let flow = if let Some(reg) = &proto.reg {
             // Budgeted runs (the production op budget, the conformance
             // runner, untrusted eval) stay on the register tier too:
@@ -3430,6 +3438,9 @@ impl Vm {
             let pos = self.throw_pos.take();
             self.record_unwind_frame(e, &proto, pos);
         }
+        if let Some(id) = debug_id {
+            self.debug_exit(id, &flow);
+        }
         flow
     }
 
@@ -3503,9 +3514,36 @@ impl Vm {
         // frame can miss a budget that applies to it. The interrupt latch below
         // zeroes the budget of an already-`counting` frame, and every frame
         // entered afterwards re-samples.
-        let counting = self.op_budget.is_some() || self.interrupt.is_some();
+        //
+        // A debugger rides the same branch: with a hook installed, each op
+        // whose source position differs from the previous one's is a
+        // statement (or call-site) boundary, reported before the op runs.
+        // So do coverage counters, bumped on the first op of each block.
+        let debugging = self.debug_hook.is_some();
+        let counting = self.op_budget.is_some()
+            || self.interrupt.is_some()
+            || debugging
+            || proto.blocks.is_some();
+        let mut stepped_pos = u32::MAX;
         loop {
             if counting {
+                if let Some(blocks) = &proto.blocks {
+                    blocks.enter(frame.ip);
+                }
+                if debugging {
+                    if let Some(pos) = proto.pos_at(frame.ip) {
+                        if pos != stepped_pos {
+                            stepped_pos = pos;
+                            if let DebugAction::Abort = self.debug_statement(&frame, pos) {
+                                self.op_budget = Some(0);
+                                self.throw_pos = Some(pos);
+                                done!(Flow::Throw(
+                                    self.throw_range("execution aborted by the debugger")
+                                ));
+                            }
+                        }
+                    }
+                }
                 if let Some(budget) = self.op_budget.as_mut() {
                     if *budget == 0 {
                         // Uncatchable so execution is guaranteed to terminate.
@@ -3528,7 +3566,7 @@ impl Vm {
                             if flag.load(std::sync::atomic::Ordering::Relaxed) {
                                 self.op_budget = Some(0);
                                 self.throw_pos = proto.pos_at(frame.ip);
-                                done!(Flow::Throw(self.throw_range("execution interrupted")));
+                                done!(Flow::Throw(self.interrupted()));
                             }
                         }
                     }
@@ -4852,7 +4890,7 @@ impl Vm {
                             if flag.load(std::sync::atomic::Ordering::Relaxed) {
                                 self.op_budget = Some(0);
                                 self.throw_pos = reg.pos.get(pc).copied();
-                                done!(Flow::Throw(self.throw_range("execution interrupted")));
+                                done!(Flow::Throw(self.interrupted()));
                             }
                         }
                     }
@@ -77,6 +77,13 @@ fn for_each_ip(op: &mut Op, mut f: impl FnMut(&mut u32)) {
     }
 }
 
+/// The code offsets `op` can transfer control to, by [`for_each_ip`].
+pub(crate) fn ip_operands(op: &Op) -> Vec<u32> {
+    let mut out = Vec::new();
+    for_each_ip(&mut op.clone(), |t| out.push(*t));
+    out
+}
+
 /// The comparison kind a `cmp ; Jump…` pair fuses to, if `op` is one of the
 /// fuseable comparison opcodes.
 fn cmp_of(op: &Op) -> Option<CmpOp> {
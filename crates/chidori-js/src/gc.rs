@@ -124,13 +124,12 @@ impl Vm {
         self.collect_cycles()
     }
 
-    /// Collect unreachable reference cycles. Returns the number of objects
-    /// whose edges were cleared (0 when not at quiescence — an executing
-    /// frame or a queued job makes collection unsound, so we refuse).
-    pub fn collect_cycles(&mut self) -> usize {
-        if self.call_depth > 0 || !self.microtasks.is_empty() {
-            return 0;
-        }
+    /// Passes 1 and 2 of [`Vm::collect_cycles`]: the live registered objects,
+    /// and for each the strong references no traced edge explains. A
+    /// positive count marks an object held from outside the traced graph (a
+    /// root). Read-only, so also sound mid-execution, where the references
+    /// from the native stack's frames are what make their objects roots.
+    pub(crate) fn account_references(&self) -> Accounting {
         // Snapshot the live registered objects. Each snapshot handle adds one
         // strong count, which the accounting below subtracts back out.
         let live: Vec<JsObject> = {
@@ -183,6 +182,27 @@ impl Vm {
                 },
             );
         }
+        Accounting {
+            live,
+            index,
+            gc_refs,
+            root_values,
+        }
+    }
+
+    /// Collect unreachable reference cycles. Returns the number of objects
+    /// whose edges were cleared (0 when not at quiescence — an executing
+    /// frame or a queued job makes collection unsound, so we refuse).
+    pub fn collect_cycles(&mut self) -> usize {
+        if self.call_depth > 0 || !self.microtasks.is_empty() {
+            return 0;
+        }
+        let Accounting {
+            live,
+            index,
+            gc_refs,
+            root_values,
+        } = self.account_references();
 
         // Pass 3: mark everything reachable from the roots. Roots are (a)
         // objects with unexplained strong counts — host handles, realm
@@ -402,6 +422,17 @@ impl Vm {
     }
 }
 
+/// What [`Vm::account_references`] found.
+pub(crate) struct Accounting {
+    pub live: Vec<JsObject>,
+    /// `ptr_id` → index into `live`.
+    pub index: HashMap<usize, usize>,
+    /// Per `live` object: strong references left unexplained by the trace.
+    pub gc_refs: Vec<isize>,
+    /// Contents of the host-shared binding cells, rooted unconditionally.
+    pub root_values: Vec<Value>,
+}
+
 /// Drop every outgoing edge of `o` (props, prototype, internal slots). The
 /// object stays allocated until its own strong count reaches zero, but it can
 /// no longer keep anything else alive.
@@ -424,7 +455,7 @@ pub(crate) fn clear_object_edges(o: &JsObject) {
 /// be explained, or weakly-held objects would look like roots); the mark pass
 /// passes `false` and handles WeakMap values separately with ephemeron
 /// semantics (see `collect_cycles`).
-fn trace_object(
+pub(crate) fn trace_object(
     data: &ObjectData,
     seen_cells: &mut HashSet<usize>,
     seen_frames: &mut HashSet<usize>,
@@ -453,7 +484,19 @@ fn trace_object(
             }
         }
     }
-    match &data.internal {
+    trace_internal(&data.internal, seen_cells, seen_frames, weak_edges, f);
+}
+
+/// The internal-slot half of [`trace_object`]: every strong reference held by
+/// `internal`, with the same sharing and weak-edge rules.
+pub(crate) fn trace_internal(
+    internal: &Internal,
+    seen_cells: &mut HashSet<usize>,
+    seen_frames: &mut HashSet<usize>,
+    weak_edges: bool,
+    f: &mut dyn FnMut(&JsObject),
+) {
+    match internal {
         Internal::Array(v) => {
             for x in v {
                 trace_value(x, f);
@@ -584,7 +627,7 @@ fn trace_object(
     }
 }
 
-fn trace_property(prop: &Property, f: &mut dyn FnMut(&JsObject)) {
+pub(crate) fn trace_property(prop: &Property, f: &mut dyn FnMut(&JsObject)) {
     match &prop.kind {
         PropertyKind::Data { value, .. } => trace_value(value, f),
         PropertyKind::Accessor { get, set } => {
@@ -598,7 +641,7 @@ fn trace_property(prop: &Property, f: &mut dyn FnMut(&JsObject)) {
     }
 }
 
-fn trace_value(v: &Value, f: &mut dyn FnMut(&JsObject)) {
+pub(crate) fn trace_value(v: &Value, f: &mut dyn FnMut(&JsObject)) {
     if let Value::Object(o) = v {
         f(o);
     }
@@ -0,0 +1,853 @@
+//! Heap snapshots: the object graph as Chrome DevTools' `.heapsnapshot`.
+//!
+//! [`Vm::heap_snapshot`] walks the same `Rc` graph the cycle collector does
+//! ([`crate::gc`]), from the same roots: the realm's global object, the
+//! host-shared binding cells, and every registered object holding a strong
+//! reference the trace cannot explain — a host handle, or mid-execution a
+//! value on a native frame. Edges are named where the engine knows a name
+//! (properties, array indices, `__proto__`); every other internal slot is an
+//! `internal` edge, and WeakMap/WeakSet keys are `weak` ones.
+//!
+//! Objects are named the way DevTools groups them: a function by its name,
+//! an instance by its constructor's, and a plain object literal by its shape
+//! (`{id, name, …}`). Sizes are the engine's own footprint estimate (the
+//! object record, its property slots and element storage, string and buffer
+//! payloads it holds), not allocator truth.
+//!
+//! [`HeapSnapshot::dominators`] and [`HeapSnapshot::retained_sizes`] compute
+//! what DevTools computes on load, so a host can report what holds memory
+//! without opening the file.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::rc::Rc;
+
+use crate::gc::{trace_internal, trace_value, Accounting};
+use crate::value::{
+    FunctionInner, Internal, JsObject, ObjectData, Property, PropertyKey, PropertyKind, Value,
+};
+use crate::vm::Vm;
+
+/// `.heapsnapshot` node types, in the order the format's meta lists them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    Hidden = 0,
+    Object = 3,
+    Closure = 5,
+    RegExp = 6,
+    Native = 8,
+    Synthetic = 9,
+}
+
+/// `.heapsnapshot` edge types, in the order the format's meta lists them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    Context = 0,
+    Element = 1,
+    Property = 2,
+    Internal = 3,
+    Weak = 6,
+}
+
+#[derive(Debug, Clone)]
+pub struct HeapNode {
+    pub kind: NodeKind,
+    /// Class name: see the module docs.
+    pub name: String,
+    /// Odd, like V8's object ids; stable within one snapshot only.
+    pub id: u64,
+    pub self_size: usize,
+    first_edge: usize,
+    edge_count: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct HeapEdge {
+    pub kind: EdgeKind,
+    /// Property name, or `None` for an element edge (see `index`).
+    pub name: Option<String>,
+    pub index: u32,
+    /// Target node.
+    pub to: usize,
+}
+
+/// Per-class totals over a snapshot, as DevTools' summary view shows them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassSummary {
+    pub name: String,
+    pub count: usize,
+    pub self_size: usize,
+    /// Retained by the class's outermost instances (one nested inside
+    /// another of the same class is not counted twice).
+    pub retained_size: usize,
+}
+
+/// A walked object graph. Node 0 is the synthetic root.
+#[derive(Debug, Clone)]
+pub struct HeapSnapshot {
+    pub nodes: Vec<HeapNode>,
+    edges: Vec<HeapEdge>,
+}
+
+impl Vm {
+    /// Snapshot the object graph reachable from the VM's roots. Read-only,
+    /// so it can be taken mid-execution — from a native function, or when a
+    /// resource limit interrupts the run ([`Vm::on_interrupt`]).
+    pub fn heap_snapshot(&self) -> HeapSnapshot {
+        let Accounting {
+            live,
+            gc_refs,
+            root_values,
+            ..
+        } = self.account_references();
+        let mut b = Builder {
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            index: HashMap::new(),
+            pending: Vec::new(),
+        };
+        let root = b.synthetic("");
+        let global = b.object_node(&self.realm.global);
+        b.edges.push((root, HeapEdge::element(0, global)));
+        let bindings = b.synthetic("(Module bindings)");
+        b.edges.push((root, HeapEdge::element(1, bindings)));
+        for (i, v) in root_values.iter().enumerate() {
+            if let Value::Object(o) = v {
+                let to = b.object_node(o);
+                b.edges.push((bindings, HeapEdge::element(i as u32, to)));
+            }
+        }
+        let held = b.synthetic("(Host references)");
+        b.edges.push((root, HeapEdge::element(2, held)));
+        let mut n = 0;
+        for (o, _) in live.iter().zip(&gc_refs).filter(|(_, refs)| **refs > 0) {
+            if o.ptr_id() == self.realm.global.ptr_id() {
+                continue;
+            }
+            let to = b.object_node(o);
+            b.edges.push((held, HeapEdge::element(n, to)));
+            n += 1;
+        }
+        drop(live);
+        while let Some((node, o)) = b.pending.pop() {
+            b.describe(node, &o);
+        }
+        b.finish()
+    }
+}
+
+impl HeapNode {
+    /// What DevTools' summary groups the node under: its name, except that
+    /// functions all count as `(closure)`.
+    pub fn class_name(&self) -> &str {
+        match self.kind {
+            NodeKind::Closure => "(closure)",
+            _ => &self.name,
+        }
+    }
+}
+
+impl HeapEdge {
+    fn element(index: u32, to: usize) -> HeapEdge {
+        HeapEdge {
+            kind: EdgeKind::Element,
+            name: None,
+            index,
+            to,
+        }
+    }
+
+    fn named(kind: EdgeKind, name: impl Into<String>, to: usize) -> HeapEdge {
+        HeapEdge {
+            kind,
+            name: Some(name.into()),
+            index: 0,
+            to,
+        }
+    }
+}
+
+struct Builder {
+    nodes: Vec<HeapNode>,
+    /// `(from, edge)`, grouped by `from` in [`Builder::finish`].
+    edges: Vec<(usize, HeapEdge)>,
+    index: HashMap<usize, usize>,
+    /// Objects with a node but not yet their name, size and edges.
+    pending: Vec<(usize, JsObject)>,
+}
+
+impl Builder {
+    fn push(&mut self, kind: NodeKind, name: String) -> usize {
+        let node = self.nodes.len();
+        self.nodes.push(HeapNode {
+            kind,
+            name,
+            id: node as u64 * 2 + 1,
+            self_size: 0,
+            first_edge: 0,
+            edge_count: 0,
+        });
+        node
+    }
+
+    fn synthetic(&mut self, name: &str) -> usize {
+        self.push(NodeKind::Synthetic, name.to_string())
+    }
+
+    /// The node of `o`, queued to be described the first time it is seen.
+    fn object_node(&mut self, o: &JsObject) -> usize {
+        if let Some(&node) = self.index.get(&o.ptr_id()) {
+            return node;
+        }
+        let node = self.push(NodeKind::Object, String::new());
+        self.index.insert(o.ptr_id(), node);
+        self.pending.push((node, o.clone()));
+        node
+    }
+
+    fn value_edge(&mut self, from: usize, v: &Value, edge: impl FnOnce(usize) -> HeapEdge) {
+        if let Value::Object(o) = v {
+            let to = self.object_node(o);
+            self.edges.push((from, edge(to)));
+        }
+    }
+
+    fn describe(&mut self, node: usize, o: &JsObject) {
+        let data = o.borrow();
+        let (kind, name) = classify(&data);
+        self.nodes[node].kind = kind;
+        self.nodes[node].name = name;
+        self.nodes[node].self_size = self_size(&data);
+        if let Some(p) = &data.proto {
+            let to = self.object_node(p);
+            self.edges
+                .push((node, HeapEdge::named(EdgeKind::Property, "__proto__", to)));
+        }
+        for (key, prop) in data.own_iter() {
+            let key = key_name(key);
+            match &prop.kind {
+                PropertyKind::Data { value, .. } => {
+                    self.value_edge(node, value, |to| {
+                        HeapEdge::named(EdgeKind::Property, key.clone(), to)
+                    });
+                }
+                PropertyKind::Accessor { get, set } => {
+                    for (which, f) in [("get", get), ("set", set)] {
+                        if let Some(f) = f {
+                            self.value_edge(node, f, |to| {
+                                HeapEdge::named(EdgeKind::Property, format!("{which} {key}"), to)
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        if let Some(privates) = &data.privates {
+            let mut found = Vec::new();
+            for el in privates.values() {
+                match el {
+                    crate::value::PrivateElement::Field(v)
+                    | crate::value::PrivateElement::Method(v) => {
+                        trace_value(v, &mut |t| found.push(t.clone()))
+                    }
+                    crate::value::PrivateElement::Accessor { get, set } => {
+                        for v in get.iter().chain(set.iter()) {
+                            trace_value(v, &mut |t| found.push(t.clone()));
+                        }
+                    }
+                }
+            }
+            for t in found {
+                let to = self.object_node(&t);
+                self.edges
+                    .push((node, HeapEdge::named(EdgeKind::Internal, "#private", to)));
+            }
+        }
+        match &data.internal {
+            Internal::Array(items) => {
+                for (i, v) in items.iter().enumerate() {
+                    self.value_edge(node, v, |to| HeapEdge::element(i as u32, to));
+                }
+            }
+            Internal::Function(FunctionInner::Bytecode(bf)) => {
+                for (i, cell) in bf.upvalues.iter().enumerate() {
+                    let v = cell.borrow().clone();
+                    self.value_edge(node, &v, |to| {
+                        HeapEdge::named(EdgeKind::Context, format!("upvalue {i}"), to)
+                    });
+                }
+                if let Some(h) = &bf.home_object {
+                    let to = self.object_node(h);
+                    self.edges
+                        .push((node, HeapEdge::named(EdgeKind::Internal, "home_object", to)));
+                }
+                for w in &bf.captured_with {
+                    let to = self.object_node(w);
+                    self.edges
+                        .push((node, HeapEdge::named(EdgeKind::Context, "with", to)));
+                }
+            }
+            Internal::Map(m) => {
+                for (k, v) in m {
+                    self.value_edge(node, &k.0, |to| {
+                        HeapEdge::named(EdgeKind::Internal, "key", to)
+                    });
+                    self.value_edge(node, v, |to| {
+                        HeapEdge::named(EdgeKind::Internal, "value", to)
+                    });
+                }
+            }
+            Internal::WeakMap(m) => {
+                for (k, v) in m {
+                    self.value_edge(node, &k.0, |to| HeapEdge::named(EdgeKind::Weak, "key", to));
+                    self.value_edge(node, v, |to| {
+                        HeapEdge::named(EdgeKind::Internal, "value", to)
+                    });
+                }
+            }
+            Internal::WeakSet(s) => {
+                for (k, _) in s {
+                    self.value_edge(node, &k.0, |to| HeapEdge::named(EdgeKind::Weak, "key", to));
+                }
+            }
+            internal => {
+                // Each holder gets its own edges: a shared binding cell or
+                // frame slot is a reference from every object that holds it.
+                let mut found = Vec::new();
+                trace_internal(
+                    internal,
+                    &mut HashSet::new(),
+                    &mut HashSet::new(),
+                    false,
+                    &mut |t| found.push(t.clone()),
+                );
+                for t in found {
+                    let to = self.object_node(&t);
+                    self.edges
+                        .push((node, HeapEdge::named(EdgeKind::Internal, "internal", to)));
+                }
+            }
+        }
+    }
+
+    fn finish(mut self) -> HeapSnapshot {
+        // Stable: a node's edges keep the order they were found in.
+        self.edges.sort_by_key(|(from, _)| *from);
+        let mut edges = Vec::with_capacity(self.edges.len());
+        for (from, edge) in self.edges {
+            let node = &mut self.nodes[from];
+            if node.edge_count == 0 {
+                node.first_edge = edges.len();
+            }
+            node.edge_count += 1;
+            edges.push(edge);
+        }
+        HeapSnapshot {
+            nodes: self.nodes,
+            edges,
+        }
+    }
+}
+
+/// Node kind and class name of an object.
+fn classify(data: &ObjectData) -> (NodeKind, String) {
+    match &data.internal {
+        Internal::Function(f) => (NodeKind::Closure, function_name(f)),
+        Internal::Array(_) => (NodeKind::Object, "Array".to_string()),
+        Internal::ArrayBuffer(_) => (NodeKind::Object, "ArrayBuffer".to_string()),
+        _ if data.own_contains_key(&PropertyKey::str(crate::regexp::REGEXP_MARK)) => {
+            (NodeKind::RegExp, "RegExp".to_string())
+        }
+        _ => {
+            let ctor = data.proto.as_ref().and_then(constructor_name);
+            match ctor.as_deref() {
+                Some("Object") | None if matches!(data.internal, Internal::Ordinary) => {
+                    (NodeKind::Object, shape_name(data))
+                }
+                Some(name) => (NodeKind::Object, name.to_string()),
+                None => (NodeKind::Object, "Object".to_string()),
+            }
+        }
+    }
+}
+
+fn function_name(f: &FunctionInner) -> String {
+    let name = match f {
+        FunctionInner::Bytecode(bf) => bf.proto.name.clone(),
+        FunctionInner::Native(n) => n.name.to_string(),
+        FunctionInner::Bound(b) => match &b.target.borrow().internal {
+            Internal::Function(target) => format!("bound {}", function_name(target)),
+            _ => "bound".to_string(),
+        },
+    };
+    if name.is_empty() {
+        "(anonymous)".to_string()
+    } else {
+        name
+    }
+}
+
+/// The name of the function `proto.constructor` holds, if it holds one.
+fn constructor_name(proto: &JsObject) -> Option<String> {
+    let proto = proto.borrow();
+    let Some(Property {
+        kind: PropertyKind::Data {
+            value: Value::Object(ctor),
+            ..
+        },
+        ..
+    }) = proto.own_get(&PropertyKey::str("constructor"))
+    else {
+        return None;
+    };
+    let name = match &ctor.borrow().internal {
+        Internal::Function(f) => Some(function_name(f)),
+        _ => None,
+    };
+    name
+}
+
+/// How DevTools names a plain object: by its shape's first keys.
+fn shape_name(data: &ObjectData) -> String {
+    const SHOWN: usize = 4;
+    if data.own_shape().is_none() || data.own_is_empty() {
+        return "Object".to_string();
+    }
+    let keys: Vec<String> = data.own_iter().map(|(k, _)| key_name(k)).collect();
+    let mut name = String::from("{");
+    for (i, key) in keys.iter().take(SHOWN).enumerate() {
+        if i > 0 {
+            name.push_str(", ");
+        }
+        name.push_str(key);
+    }
+    if keys.len() > SHOWN {
+        name.push_str(", …");
+    }
+    name.push('}');
+    name
+}
+
+fn key_name(key: &PropertyKey) -> String {
+    match key {
+        PropertyKey::Str(s) => s.as_str().to_string(),
+        PropertyKey::Sym(s) => match &s.0.description {
+            Some(d) => format!("<symbol {d}>"),
+            None => "<symbol>".to_string(),
+        },
+    }
+}
+
+/// Out-of-line bytes of a string value (short strings live inline).
+fn string_bytes(v: &Value) -> usize {
+    match v {
+        Value::String(s) if s.byte_len() > 16 => s.byte_len(),
+        _ => 0,
+    }
+}
+
+fn self_size(data: &ObjectData) -> usize {
+    use std::mem::size_of;
+    let mut size = size_of::<ObjectData>() + 2 * size_of::<usize>();
+    size += data.own_len() * (size_of::<Property>() + size_of::<PropertyKey>());
+    for (_, prop) in data.own_iter() {
+        if let PropertyKind::Data { value, .. } = &prop.kind {
+            size += string_bytes(value);
+        }
+    }
+    size += match &data.internal {
+        Internal::Array(items) => {
+            items.capacity() * size_of::<Value>() + items.iter().map(string_bytes).sum::<usize>()
+        }
+        Internal::Map(m) | Internal::WeakMap(m) => {
+            m.len() * 2 * size_of::<Value>()
+                + m.iter()
+                    .map(|(k, v)| string_bytes(&k.0) + string_bytes(v))
+                    .sum::<usize>()
+        }
+        Internal::Set(s) | Internal::WeakSet(s) => {
+            s.len() * size_of::<Value>() + s.keys().map(|k| string_bytes(&k.0)).sum::<usize>()
+        }
+        Internal::ArrayBuffer(Some(bytes)) => bytes.len(),
+        Internal::StringObj(s) => s.byte_len(),
+        Internal::Function(FunctionInner::Bytecode(bf)) => {
+            size_of::<crate::value::BytecodeFunction>()
+                + bf.upvalues.len() * (size_of::<Rc<()>>() + size_of::<Value>())
+        }
+        _ => 0,
+    };
+    size
+}
+
+impl HeapSnapshot {
+    pub fn edges_of(&self, node: usize) -> &[HeapEdge] {
+        let n = &self.nodes[node];
+        &self.edges[n.first_edge..n.first_edge + n.edge_count]
+    }
+
+    /// Immediate dominator of each node over the non-weak edges (Cooper,
+    /// Harvey and Kennedy's iterative algorithm). The root, and anything
+    /// reachable only through weak edges, report the root.
+    pub fn dominators(&self) -> Vec<usize> {
+        let (order, post) = self.postorder();
+        let n = self.nodes.len();
+        let mut preds: Vec<Vec<usize>> = vec![Vec::new(); n];
+        for &from in &order {
+            for edge in self.edges_of(from) {
+                if edge.kind != EdgeKind::Weak {
+                    preds[edge.to].push(from);
+                }
+            }
+        }
+        const NONE: usize = usize::MAX;
+        let mut idom = vec![NONE; n];
+        idom[0] = 0;
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &node in order.iter().rev().skip(1) {
+                let mut new = NONE;
+                for &p in &preds[node] {
+                    if idom[p] == NONE {
+                        continue;
+                    }
+                    new = if new == NONE {
+                        p
+                    } else {
+                        let (mut a, mut b) = (p, new);
+                        while a != b {
+                            while post[a] < post[b] {
+                                a = idom[a];
+                            }
+                            while post[b] < post[a] {
+                                b = idom[b];
+                            }
+                        }
+                        a
+                    };
+                }
+                if new != idom[node] {
+                    idom[node] = new;
+                    changed = true;
+                }
+            }
+        }
+        idom.iter()
+            .map(|&d| if d == NONE { 0 } else { d })
+            .collect()
+    }
+
+    /// Each node's size plus everything it dominates: what would be freed
+    /// if it were.
+    pub fn retained_sizes(&self) -> Vec<usize> {
+        self.retained_with(&self.dominators())
+    }
+
+    fn retained_with(&self, idom: &[usize]) -> Vec<usize> {
+        let (order, post) = self.postorder();
+        let mut retained: Vec<usize> = self.nodes.iter().map(|n| n.self_size).collect();
+        // A dominator finishes after everything it dominates.
+        for &node in &order {
+            if node != 0 {
+                retained[idom[node]] += retained[node];
+            }
+        }
+        // Held only weakly: nothing but the root keeps it.
+        for (node, &p) in post.iter().enumerate() {
+            if p == usize::MAX {
+                retained[0] += self.nodes[node].self_size;
+            }
+        }
+        retained
+    }
+
+    /// Non-weak DFS postorder from the root, and each node's position in it
+    /// (`usize::MAX` when unreached).
+    fn postorder(&self) -> (Vec<usize>, Vec<usize>) {
+        let n = self.nodes.len();
+        let mut post = vec![usize::MAX; n];
+        let mut seen = vec![false; n];
+        let mut order = Vec::with_capacity(n);
+        let mut stack = vec![(0usize, 0usize)];
+        seen[0] = true;
+        while let Some((node, next)) = stack.last_mut() {
+            let edges = self.edges_of(*node);
+            match edges[*next..]
+                .iter()
+                .position(|e| e.kind != EdgeKind::Weak && !seen[e.to])
+            {
+                Some(skip) => {
+                    let to = edges[*next + skip].to;
+                    *next += skip + 1;
+                    seen[to] = true;
+                    stack.push((to, 0));
+                }
+                None => {
+                    post[*node] = order.len();
+                    order.push(*node);
+                    stack.pop();
+                }
+            }
+        }
+        (order, post)
+    }
+
+    /// Totals per class name, largest retained first.
+    pub fn class_summary(&self) -> Vec<ClassSummary> {
+        let idom = self.dominators();
+        let retained = self.retained_with(&idom);
+        let mut children: Vec<Vec<usize>> = vec![Vec::new(); self.nodes.len()];
+        for (node, &d) in idom.iter().enumerate().skip(1) {
+            children[d].push(node);
+        }
+        let mut by_class: HashMap<&str, ClassSummary> = HashMap::new();
+        // Walk the dominator tree, counting a class's retained size only at
+        // its outermost instances.
+        let mut open: HashMap<&str, usize> = HashMap::new();
+        let mut stack: Vec<(usize, bool)> = vec![(0, false)];
+        while let Some((node, exiting)) = stack.pop() {
+            let n = &self.nodes[node];
+            let counted = n.kind != NodeKind::Synthetic;
+            let class = n.class_name();
+            if exiting {
+                if counted {
+                    *open.get_mut(class).unwrap() -= 1;
+                }
+                continue;
+            }
+            if counted {
+                let entry = by_class.entry(class).or_insert_with(|| ClassSummary {
+                    name: class.to_string(),
+                    count: 0,
+                    self_size: 0,
+                    retained_size: 0,
+                });
+                entry.count += 1;
+                entry.self_size += n.self_size;
+                let depth = open.entry(class).or_insert(0);
+                if *depth == 0 {
+                    entry.retained_size += retained[node];
+                }
+                *depth += 1;
+            }
+            stack.push((node, true));
+            stack.extend(children[node].iter().map(|&c| (c, false)));
+        }
+        let mut summary: Vec<ClassSummary> = by_class.into_values().collect();
+        summary.sort_by(|a, b| {
+            b.retained_size
+                .cmp(&a.retained_size)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        summary
+    }
+
+    /// The snapshot in Chrome DevTools' `.heapsnapshot` JSON format.
+    pub fn to_json(&self) -> String {
+        let mut strings: Vec<&str> = Vec::new();
+        let mut ids: HashMap<&str, usize> = HashMap::new();
+        let mut out = String::new();
+        out.push_str(
+            "{\"snapshot\":{\"meta\":{\
+             \"node_fields\":[\"type\",\"name\",\"id\",\"self_size\",\"edge_count\",\"trace_node_id\",\"detachedness\"],\
+             \"node_types\":[[\"hidden\",\"array\",\"string\",\"object\",\"code\",\"closure\",\"regexp\",\"number\",\"native\",\"synthetic\",\"concatenated string\",\"sliced string\",\"symbol\",\"bigint\",\"object shape\"],\"string\",\"number\",\"number\",\"number\",\"number\",\"number\"],\
+             \"edge_fields\":[\"type\",\"name_or_index\",\"to_node\"],\
+             \"edge_types\":[[\"context\",\"element\",\"property\",\"internal\",\"hidden\",\"shortcut\",\"weak\"],\"string_or_number\",\"node\"],\
+             \"trace_function_info_fields\":[\"function_id\",\"name\",\"script_name\",\"script_id\",\"line\",\"column\"],\
+             \"trace_node_fields\":[\"id\",\"function_info_index\",\"count\",\"size\",\"children\"],\
+             \"sample_fields\":[\"timestamp_us\",\"last_assigned_id\"],\
+             \"location_fields\":[\"object_index\",\"script_id\",\"line\",\"column\"]},",
+        );
+        let _ = write!(
+            out,
+            "\"node_count\":{},\"edge_count\":{},\"trace_function_count\":0}},\n\"nodes\":[",
+            self.nodes.len(),
+            self.edges.len()
+        );
+        for (i, node) in self.nodes.iter().enumerate() {
+            let name = intern(&node.name, &mut strings, &mut ids);
+            let _ = write!(
+                out,
+                "{}{},{},{},{},{},0,0",
+                if i == 0 { "" } else { ",\n" },
+                node.kind as u8,
+                name,
+                node.id,
+                node.self_size,
+                node.edge_count
+            );
+        }
+        out.push_str("],\n\"edges\":[");
+        for (i, edge) in self.edges.iter().enumerate() {
+            let name = match &edge.name {
+                Some(name) => intern(name, &mut strings, &mut ids),
+                None => edge.index as usize,
+            };
+            let _ = write!(
+                out,
+                "{}{},{},{}",
+                if i == 0 { "" } else { ",\n" },
+                edge.kind as u8,
+                name,
+                edge.to * 7
+            );
+        }
+        out.push_str(
+            "],\n\"trace_function_infos\":[],\"trace_tree\":[],\"samples\":[],\"locations\":[],\n\"strings\":[",
+        );
+        for (i, s) in strings.iter().enumerate() {
+            if i > 0 {
+                out.push_str(",\n");
+            }
+            out.push_str(&serde_json::to_string(s).unwrap_or_else(|_| "\"\"".to_string()));
+        }
+        out.push_str("]}\n");
+        out
+    }
+}
+
+/// Index of `s` in the snapshot's string table, adding it if new.
+fn intern<'a>(s: &'a str, strings: &mut Vec<&'a str>, ids: &mut HashMap<&'a str, usize>) -> usize {
+    *ids.entry(s).or_insert_with(|| {
+        strings.push(s);
+        strings.len() - 1
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EdgeKind, NodeKind};
+    use crate::Engine;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn classes_and_retained_sizes_follow_the_dominator_tree() {
+        let mut engine = Engine::new();
+        engine
+            .eval(
+                "class Session { constructor(id) { this.id = id; this.log = []; } }
+                 globalThis.cache = { sessions: [] };
+                 for (let i = 0; i < 50; i++) {
+                     const s = new Session(i);
+                     for (let j = 0; j < 20; j++) s.log.push({ at: j, text: 'entry ' + j });
+                     cache.sessions.push(s);
+                 }",
+            )
+            .unwrap();
+        let snapshot = engine.vm.heap_snapshot();
+        let summary = snapshot.class_summary();
+        let class = |name: &str| {
+            summary
+                .iter()
+                .find(|c| c.name == name)
+                .unwrap_or_else(|| panic!("no {name} in {summary:?}"))
+        };
+        assert_eq!(class("Session").count, 50);
+        assert_eq!(class("{at, text}").count, 1000);
+        // Each session's log is only reachable through it.
+        assert!(class("Session").retained_size > class("{at, text}").retained_size);
+        assert!(class("{sessions}").retained_size >= class("Session").retained_size);
+
+        let retained = snapshot.retained_sizes();
+        let cache = snapshot
+            .nodes
+            .iter()
+            .position(|n| n.name == "{sessions}")
+            .unwrap();
+        let idom = snapshot.dominators();
+        let session = snapshot
+            .nodes
+            .iter()
+            .position(|n| n.name == "Session")
+            .unwrap();
+        let array = idom[session];
+        assert_eq!(snapshot.nodes[array].name, "Array");
+        assert_eq!(idom[array], cache);
+        assert!(retained[cache] > 50 * retained[session]);
+        assert!(snapshot
+            .edges_of(cache)
+            .iter()
+            .any(|e| e.kind == EdgeKind::Property && e.name.as_deref() == Some("sessions")));
+    }
+
+    #[test]
+    fn json_is_the_devtools_heapsnapshot_format() {
+        let mut engine = Engine::new();
+        engine
+            .eval("globalThis.keep = [function named() {}, /re/g, new Map([[1, {}]])];")
+            .unwrap();
+        let snapshot = engine.vm.heap_snapshot();
+        let json: serde_json::Value = serde_json::from_str(&snapshot.to_json()).unwrap();
+        let meta = &json["snapshot"]["meta"];
+        let node_fields = meta["node_fields"].as_array().unwrap().len();
+        let edge_fields = meta["edge_fields"].as_array().unwrap().len();
+        let nodes = json["nodes"].as_array().unwrap();
+        let edges = json["edges"].as_array().unwrap();
+        let strings = json["strings"].as_array().unwrap();
+        assert_eq!(
+            nodes.len(),
+            json["snapshot"]["node_count"].as_u64().unwrap() as usize * node_fields
+        );
+        assert_eq!(
+            edges.len(),
+            json["snapshot"]["edge_count"].as_u64().unwrap() as usize * edge_fields
+        );
+        let edge_total: u64 = nodes
+            .chunks(node_fields)
+            .map(|n| n[4].as_u64().unwrap())
+            .sum();
+        assert_eq!(edge_total as usize * edge_fields, edges.len());
+        for edge in edges.chunks(edge_fields) {
+            assert_eq!(edge[2].as_u64().unwrap() as usize % node_fields, 0);
+        }
+        let named = |kind: NodeKind, name: &str| {
+            nodes
+                .chunks(node_fields)
+                .any(|n| n[0] == kind as u8 && strings[n[1].as_u64().unwrap() as usize] == name)
+        };
+        assert!(named(NodeKind::Closure, "named"));
+        assert!(named(NodeKind::RegExp, "RegExp"));
+        assert!(named(NodeKind::Object, "Map"));
+    }
+
+    #[test]
+    fn an_interrupt_snapshots_the_frames_it_is_about_to_unwind() {
+        let mut engine = Engine::new();
+        let seen: Rc<RefCell<Option<usize>>> = Rc::new(RefCell::new(None));
+        let record = seen.clone();
+        engine.vm.on_interrupt = Some(Box::new(move |vm| {
+            let held = vm
+                .heap_snapshot()
+                .class_summary()
+                .into_iter()
+                .find(|c| c.name == "Chunk")
+                .map_or(0, |c| c.count);
+            *record.borrow_mut() = Some(held);
+        }));
+        let flag = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        engine.vm.interrupt = Some(flag.clone());
+        let global = engine.vm.realm.global.clone();
+        engine.vm.define_method(&global, "arm", 0, move |_, _, _| {
+            flag.store(true, std::sync::atomic::Ordering::Relaxed);
+            Ok(crate::Value::Undefined)
+        });
+        let err = engine
+            .eval(
+                "class Chunk {}
+                 (function () {
+                     const kept = [];
+                     for (let i = 0; i < 300; i++) kept.push(new Chunk());
+                     arm();
+                     for (;;) { kept.length; }
+                 })()",
+            )
+            .unwrap_err();
+        assert!(err.contains("interrupted"), "{err}");
+        assert_eq!(
+            *seen.borrow(),
+            Some(300),
+            "a local of the running frame is a root"
+        );
+    }
+}
@@ -15,12 +15,15 @@ pub mod builtins;
 pub mod bytecode;
 pub mod compiler;
 pub mod convert;
+pub mod coverage;
+pub mod debug;
 pub mod dom;
 pub mod exec;
 pub mod fuse;
 pub mod fxhash;
 pub mod gc;
 pub mod generator;
+pub mod heap;
 pub mod host;
 pub mod image;
 pub mod iter;
@@ -220,6 +223,24 @@ impl Engine {
                 )
             });
         let d = dispatch.clone();
+        self.vm
+            .define_method(&chidori, "embed", 2, move |vm, _t, args| {
+                let texts = args
+                    .first()
+                    .map(|v| vm.value_to_json(v))
+                    .unwrap_or(serde_json::Value::Null);
+                let opts = args
+                    .get(1)
+                    .map(|v| vm.value_to_json(v))
+                    .unwrap_or(serde_json::Value::Null);
+                forward_effect(
+                    vm,
+                    &d,
+                    "embed",
+                    serde_json::json!({ "texts": texts, "opts": opts }),
+                )
+            });
+        let d = dispatch.clone();
         self.vm
             .define_method(&chidori, "input", 2, move |vm, _t, args| {
                 let prompt = args
@@ -884,6 +905,36 @@ impl Engine {
             });
         self.vm
             .define_value(&chidori, "workspace", Value::Object(workspace));
+        // chidori.mcp.* — resources and prompts on a connected MCP server.
+        // Every method forwards the "mcp" effect tagged with its action and
+        // the server id; the host performs (and journals) the request.
+        let mcp = self.vm.new_object();
+        let mcp_methods: [(&str, &str, &str, u32); 4] = [
+            ("readResource", "uri", "", 2),
+            ("getPrompt", "name", "arguments", 3),
+            ("listResources", "", "", 1),
+            ("listPrompts", "", "", 1),
+        ];
+        for (action, first, second, arity) in mcp_methods {
+            let d = dispatch.clone();
+            self.vm
+                .define_method(&mcp, action, arity, move |vm, _t, args| {
+                    let mut arg = |i: usize| {
+                        args.get(i)
+                            .map(|v| vm.value_to_json(v))
+                            .unwrap_or(serde_json::Value::Null)
+                    };
+                    let mut payload = serde_json::json!({ "action": action, "server": arg(0) });
+                    if !first.is_empty() {
+                        payload[first] = arg(1);
+                    }
+                    if !second.is_empty() {
+                        payload[second] = arg(2);
+                    }
+                    forward_effect(vm, &d, "mcp", payload)
+                });
+        }
+        self.vm.define_value(&chidori, "mcp", Value::Object(mcp));
         // chidori.appData.{write,query}(sql, params?) — the generative-UI
         // agent-run write tool. Each method forwards the "app_data" effect
         // tagged with its action; the host performs the write through the
@@ -1130,6 +1181,31 @@ impl Engine {
         }
     }
 
+    /// Compile one module of an entry graph: for debugging when a debugger
+    /// ([`Vm::debug_hook`]) is attached and wants this module, else with
+    /// block counters when [`Vm::coverage`] covers it.
+    fn compile_graph_module(
+        &mut self,
+        src: &str,
+        key: &str,
+    ) -> Result<module::CompiledModule, String> {
+        let debug = self
+            .vm
+            .debug_hook
+            .as_mut()
+            .is_some_and(|hook| hook.debug_module(key));
+        let coverage = self.vm.coverage.clone().filter(|c| c.covers(key));
+        if debug {
+            compiler::compile_module_debug(src, Some(key))
+        } else if let Some(coverage) = coverage {
+            let compiled = compiler::compile_module_coverage(src, Some(key))?;
+            coverage.register(&compiled.proto);
+            Ok(compiled)
+        } else {
+            compiler::compile_module_labeled(src, Some(key))
+        }
+    }
+
     /// BFS the import graph, compiling each module once and recording how its
     /// requested specifiers resolved (the linker reads `resolved` per record).
     /// Registers each compiled module as an image unit when this VM is imaging,
@@ -1149,7 +1225,8 @@ impl Engine {
             if registry.modules.contains_key(&key) {
                 continue;
             }
-            let compiled = compiler::compile_module_labeled(&src, Some(&key))
+            let compiled = self
+                .compile_graph_module(&src, &key)
                 .map_err(|e| format!("compiling module '{key}': {e}"))?;
             let cell_of_name = compiled.cell_of_name.clone();
             let requested = compiled.requested.clone();
@@ -1226,7 +1303,8 @@ impl Engine {
             if registry.modules.contains_key(&key) {
                 continue;
             }
-            let compiled = compiler::compile_module_labeled(&src, Some(&key))
+            let compiled = self
+                .compile_graph_module(&src, &key)
                 .map_err(|e| format!("compiling module '{key}': {e}"))?;
             let cell_of_name = compiled.cell_of_name.clone();
             let requested = compiled.requested.clone();
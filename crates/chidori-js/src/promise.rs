@@ -442,7 +442,15 @@ impl Vm {
     /// Drain microtasks to quiescence, then report whether we completed or are
     /// blocked on the earliest-registered pending host op.
     pub fn run_jobs_until_blocked(&mut self) -> RunOutcome {
-        while let Some(task) = self.microtasks.pop_front() {
+        loop {
+            // A debugger may image the VM between jobs; only at the outermost
+            // loop is nothing of the program left on the native stack.
+            if self.debug_hook.is_some() && self.call_depth == 0 {
+                self.debug_idle();
+            }
+            let Some(task) = self.microtasks.pop_front() else {
+                break;
+            };
             self.run_microtask(task);
         }
         // The queue is drained and no JS frame is on the Rust stack — the
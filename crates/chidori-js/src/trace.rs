@@ -8,6 +8,9 @@
 //! The token rides on the [`Frame`](crate::vm::Frame), so a function that
 //! suspends at `await`/`yield` and resumes much later (non-LIFO) still reports
 //! [`on_exit`](TraceObserver::on_exit) against the right activation.
+//!
+//! An observer that weighs activations by the instructions they run reads
+//! [`Vm::op_meter`]: the op budget's countdown as of each notification.
 
 use crate::bytecode::FuncProto;
 use crate::vm::Vm;
@@ -16,6 +19,8 @@ use crate::vm::Vm;
 pub struct TraceEnter<'a> {
     /// Function name (empty for anonymous).
     pub name: &'a str,
+    /// Key of the module the function was compiled from (`None` for scripts).
+    pub label: Option<&'a str>,
     /// Byte offset of the function in its module source (for line resolution).
     pub source_start: u32,
     pub is_async: bool,
@@ -40,9 +45,12 @@ impl Vm {
     /// Notify the sink that `proto`'s activation is starting; returns its token
     /// (None when no sink is installed — the zero-cost-off path).
     pub(crate) fn trace_enter(&mut self, proto: &FuncProto) -> Option<u64> {
+        self.trace_sink.as_ref()?;
+        self.meter_ops();
         let sink = self.trace_sink.as_mut()?;
         Some(sink.on_enter(TraceEnter {
             name: &proto.name,
+            label: proto.source_label.as_deref(),
             source_start: proto.source_start,
             is_async: proto.kind.is_async(),
             is_generator: proto.kind.is_generator(),
@@ -50,22 +58,31 @@ impl Vm {
     }
 
     pub(crate) fn trace_exit(&mut self, token: Option<u64>, threw: bool) {
+        self.meter_ops();
         if let (Some(sink), Some(t)) = (self.trace_sink.as_mut(), token) {
             sink.on_exit(t, threw);
         }
     }
 
     pub(crate) fn trace_suspend(&mut self, token: Option<u64>) {
+        self.meter_ops();
         if let (Some(sink), Some(t)) = (self.trace_sink.as_mut(), token) {
             sink.on_suspend(t);
         }
     }
 
     pub(crate) fn trace_resume(&mut self, token: Option<u64>) {
+        self.meter_ops();
         if let (Some(sink), Some(t)) = (self.trace_sink.as_mut(), token) {
             sink.on_resume(t);
         }
     }
+
+    fn meter_ops(&self) {
+        if let (Some(meter), Some(left)) = (&self.op_meter, self.op_budget) {
+            meter.set(left);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -132,6 +149,50 @@ mod tests {
             .collect()
     }
 
+    #[test]
+    fn the_op_meter_counts_down_between_notifications() {
+        struct Ops {
+            meter: Rc<std::cell::Cell<u64>>,
+            seen: Rc<RefCell<Vec<(String, u64)>>>,
+        }
+        impl TraceObserver for Ops {
+            fn on_enter(&mut self, info: TraceEnter<'_>) -> u64 {
+                let label = info.label.unwrap_or("-");
+                self.seen
+                    .borrow_mut()
+                    .push((format!("{}@{label}", info.name), self.meter.get()));
+                0
+            }
+            fn on_exit(&mut self, _: u64, _: bool) {
+                self.seen
+                    .borrow_mut()
+                    .push(("exit".to_string(), self.meter.get()));
+            }
+            fn on_suspend(&mut self, _: u64) {}
+            fn on_resume(&mut self, _: u64) {}
+        }
+        let meter = Rc::new(std::cell::Cell::new(0));
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let mut engine = Engine::new();
+        engine.vm.op_budget = Some(1_000_000);
+        engine.vm.op_meter = Some(meter.clone());
+        engine.vm.trace_sink = Some(Box::new(Ops {
+            meter: meter.clone(),
+            seen: seen.clone(),
+        }));
+        engine
+            .eval("function spin(){ let n = 0; for (let i = 0; i < 50; i++) n += i; return n; } spin();")
+            .unwrap();
+        let seen = seen.borrow();
+        let enter = seen.iter().position(|(e, _)| e == "spin@-").unwrap();
+        let (_, before) = seen[enter];
+        let (_, after) = seen[enter + 1];
+        assert!(
+            before - after > 100,
+            "the loop's ops ran between enter and exit: {seen:?}"
+        );
+    }
+
     #[test]
     fn sync_calls_nest_lifo_with_matched_exits() {
         let ev = trace_events("function b(){ return 1; } function a(){ return b(); } a();");
@@ -269,6 +269,10 @@ pub struct Vm {
     /// uncatchable throw. Used by the conformance runner's per-test timeout so a
     /// slow test stops grinding instead of being abandoned to leak a CPU core.
     pub interrupt: Option<std::sync::Arc<std::sync::atomic::AtomicBool>>,
+    /// Called once, the first time an interrupt is observed, before the
+    /// uncatchable throw unwinds the frames — the last point the run's live
+    /// state can be inspected (a heap snapshot of a run over its memory cap).
+    pub on_interrupt: Option<Box<dyn FnOnce(&Vm)>>,
     /// Wrapping counter so [`Vm::native_tick`] only polls the interrupt flag
     /// every 256 iterations (same cadence as the interpreter loop).
     pub(crate) native_poll: u32,
@@ -292,6 +296,20 @@ pub struct Vm {
     /// replay. `None` (default) makes tracing a single predictable-not-taken
     /// branch per call.
     pub trace_sink: Option<Box<dyn crate::trace::TraceObserver>>,
+    /// Set to [`Vm::op_budget`]'s remaining ops before each trace
+    /// notification, for observers that weigh by instructions run (a
+    /// profiler). Needs a budget to count down; `None` (default) skips it.
+    pub op_meter: Option<std::rc::Rc<std::cell::Cell<u64>>>,
+    /// Optional source-level debugger (see [`crate::debug`]). When installed,
+    /// modules compile with their binding tables and every activation and
+    /// statement boundary is reported to it; the hook may block (a paused
+    /// debuggee) or abort the run. `None` (default) costs one branch per
+    /// frame entry, and nothing per op beyond the existing budget check.
+    pub debug_hook: Option<Box<dyn crate::debug::DebugHook>>,
+    /// Optional block coverage (see [`crate::coverage`]): the modules it
+    /// covers compile with block counters, which their frames bump as they
+    /// run. `None` (default) leaves every module optimized.
+    pub coverage: Option<std::rc::Rc<crate::coverage::Coverage>>,
     /// Host hook for dynamic `import(specifier)`. Receives the coerced specifier
     /// string and must load/link/evaluate the module, returning its namespace
     /// object (`Err` is the thrown error value, which rejects the `import()`
@@ -472,11 +490,15 @@ impl Vm {
             rng_state: 0x2545F4914F6CDD1D,
             op_budget: None,
             interrupt: None,
+            on_interrupt: None,
             native_poll: 0,
             throw_pos: None,
             module_capture_proto: None,
             module_capture: None,
             trace_sink: None,
+            op_meter: None,
+            debug_hook: None,
+            coverage: None,
             dynamic_import: None,
             all_objects: std::cell::RefCell::new(Vec::new()),
             gc_compact_at: std::cell::Cell::new(1 << 12),
@@ -1177,7 +1199,7 @@ impl Vm {
                 if let Some(flag) = &self.interrupt {
                     if flag.load(std::sync::atomic::Ordering::Relaxed) {
                         self.op_budget = Some(0);
-                        return Err(self.throw_range("execution interrupted"));
+                        return Err(self.interrupted());
                     }
                 }
             }
@@ -1185,6 +1207,15 @@ impl Vm {
         Ok(())
     }
 
+    /// The uncatchable throw for an observed interrupt, after running
+    /// [`Vm::on_interrupt`].
+    pub(crate) fn interrupted(&mut self) -> Value {
+        if let Some(hook) = self.on_interrupt.take() {
+            hook(self);
+        }
+        self.throw_range("execution interrupted")
+    }
+
     pub fn to_length(&mut self, v: &Value) -> Result<usize, Value> {
         let n = self.to_number(v)?;
         if n.is_nan() || n <= 0.0 {
@@ -65,6 +65,7 @@ fn record(seq: u64, payload_bytes: usize) -> CallRecord {
         token_usage: None,
         timestamp: chrono::Utc::now(),
         error: None,
+        served_by: None,
     }
 }
 
@@ -150,6 +151,10 @@ fn bench_session_store_put(c: &mut Criterion) {
             pending_approval: None,
             approvals: Vec::new(),
             policy_profile: None,
+            agent: None,
+            recipe: None,
+            scheduled_for: None,
+            tenant: None,
             created_at: chrono::Utc::now(),
         };
         g.bench_function(format!("log_n{n}"), |b| {
@@ -78,6 +78,10 @@ async fn create_thread(
         pending_approval: None,
         approvals: Vec::new(),
         policy_profile: None,
+        agent: None,
+        recipe: None,
+        scheduled_for: None,
+        tenant: None,
         created_at: chrono::Utc::now(),
     };
     if let Err(e) = state.store.put(&session) {
@@ -21,6 +21,13 @@
 //!   - path: /webhooks/github
 //!     agent: triage                  # deliver into this agent's mailbox…
 //!     signal: github-event           # …as this named signal
+//! tenants:
+//!   - id: billing
+//!     keys_env: BILLING_API_KEYS     # comma-separated bearer keys
+//!     max_concurrent_runs: 2
+//!     policy_profile: supervised
+//!     max_cost_usd: 50
+//!     cost_window: 30d
 //! ```
 //!
 //! Semantics:
@@ -36,6 +43,11 @@
 //!   body is delivered to the named agent's durable mailbox as the named
 //!   signal (waking it if it hibernates on that name). Routes sit behind the
 //!   same bearer auth as every other server route.
+//! - `tenants` — each tenant's keys reach the session, agent and recipe API
+//!   scoped to that tenant: its own sessions, run directories, detached
+//!   agents, memory and recipes, under its own concurrency cap, policy
+//!   profile and cost quota. An agent entry with `tenant:` belongs to that
+//!   tenant's fleet and schedules.
 
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
@@ -44,7 +56,7 @@ use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::recipes::Recipe;
+use crate::recipes::{Recipe, ScheduleTiming};
 
 /// Manifest file names probed (in order) in the server's base directory when
 /// no explicit path is given.
@@ -64,6 +76,8 @@ pub struct AppManifest {
     pub agents: Vec<ManifestAgent>,
     #[serde(default)]
     pub routes: Vec<ManifestRoute>,
+    #[serde(default)]
+    pub tenants: Vec<ManifestTenant>,
     /// The directory the manifest was loaded from; every relative `agent`
     /// path resolves against it. Not part of the file format.
     #[serde(skip)]
@@ -100,6 +114,17 @@ pub struct ManifestAgent {
     pub model: Option<String>,
     #[serde(default)]
     pub description: Option<String>,
+    /// Cost budget for each scheduled run (see [`Recipe::budget`]).
+    #[serde(default)]
+    pub budget: Option<crate::runtime::cost::CostBudget>,
+    /// Timezone, catch-up, overlap and jitter of the schedule (see
+    /// [`Recipe::timing`]).
+    #[serde(flatten)]
+    pub timing: ScheduleTiming,
+    /// The tenant the entry belongs to: its detached agent lives in the
+    /// tenant's registry, its schedule runs as the tenant's recipe.
+    #[serde(default)]
+    pub tenant: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -112,6 +137,31 @@ pub struct ManifestRoute {
     pub signal: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestTenant {
+    /// Names the tenant's directory, `.chidori/tenants/<id>/`.
+    pub id: String,
+    /// Env var holding the tenant's bearer keys, comma-separated like
+    /// `CHIDORI_API_KEY` (so they rotate the same way and stay out of
+    /// source control).
+    pub keys_env: String,
+    /// Runs of this tenant executing at once, within the server-wide cap.
+    #[serde(default)]
+    pub max_concurrent_runs: Option<usize>,
+    /// Built-in policy profile layered on the server policy for every run
+    /// the tenant starts.
+    #[serde(default)]
+    pub policy_profile: Option<String>,
+    /// Estimated spend the tenant's sessions may reach before new runs are
+    /// refused.
+    #[serde(default)]
+    pub max_cost_usd: Option<f64>,
+    /// The rolling window `max_cost_usd` covers (`30d`, `12h`); all time
+    /// when unset.
+    #[serde(default)]
+    pub cost_window: Option<String>,
+}
+
 impl AppManifest {
     /// Probe `dir` for a manifest file. `None` when the directory has none —
     /// a manifest is optional; serving without one is the classic behavior.
@@ -145,8 +195,72 @@ impl AppManifest {
     }
 
     fn validate(&self) -> Result<()> {
+        let mut tenant_ids = std::collections::HashSet::new();
+        for tenant in &self.tenants {
+            if tenant.id.is_empty()
+                || tenant
+                    .id
+                    .chars()
+                    .any(|c| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+            {
+                anyhow::bail!(
+                    "app manifest: `{}` is not a valid tenant id \
+                     (allowed: ASCII letters, digits, `-`, `_`)",
+                    tenant.id
+                );
+            }
+            if !tenant_ids.insert(tenant.id.as_str()) {
+                anyhow::bail!("app manifest: duplicate tenant id `{}`", tenant.id);
+            }
+            if tenant.keys_env.is_empty() {
+                anyhow::bail!("app manifest: tenant `{}` needs `keys_env`", tenant.id);
+            }
+            if tenant.max_concurrent_runs == Some(0) {
+                anyhow::bail!(
+                    "app manifest: tenant `{}` has max_concurrent_runs 0 — it could never run",
+                    tenant.id
+                );
+            }
+            if let Some(profile) = &tenant.policy_profile {
+                if crate::policy::builtin_profile(profile).is_none() {
+                    anyhow::bail!(
+                        "app manifest: tenant `{}` has unknown policy profile `{profile}` \
+                         (known: {})",
+                        tenant.id,
+                        crate::policy::BUILTIN_PROFILES.join(", ")
+                    );
+                }
+            }
+            if let Some(max) = tenant.max_cost_usd {
+                if !(max.is_finite() && max > 0.0) {
+                    anyhow::bail!(
+                        "app manifest: tenant `{}` max_cost_usd must be a positive number",
+                        tenant.id
+                    );
+                }
+            }
+            if let Some(window) = &tenant.cost_window {
+                if tenant.max_cost_usd.is_none() {
+                    anyhow::bail!(
+                        "app manifest: tenant `{}` sets cost_window without max_cost_usd",
+                        tenant.id
+                    );
+                }
+                crate::server::parse_age(window).map_err(|msg| {
+                    anyhow::anyhow!("app manifest: tenant `{}` cost_window {msg}", tenant.id)
+                })?;
+            }
+        }
         let mut seen = std::collections::HashSet::new();
         for agent in &self.agents {
+            if let Some(tenant) = &agent.tenant {
+                if !tenant_ids.contains(tenant.as_str()) {
+                    anyhow::bail!(
+                        "app manifest: agent `{}` names undeclared tenant `{tenant}`",
+                        agent.name
+                    );
+                }
+            }
             if agent.name.is_empty()
                 || agent
                     .name
@@ -194,6 +308,15 @@ impl AppManifest {
                         agent.name
                     )
                 })?;
+                agent
+                    .timing
+                    .tz()
+                    .with_context(|| format!("app manifest: agent `{}`", agent.name))?;
+            }
+            if let Some(budget) = &agent.budget {
+                budget.validate().map_err(|msg| {
+                    anyhow::anyhow!("app manifest: agent `{}`: {msg}", agent.name)
+                })?;
             }
         }
         let mut seen_paths = std::collections::HashSet::new();
@@ -247,12 +370,14 @@ impl AppManifest {
         }
     }
 
-    /// The scheduled entries as recipes for the cron scheduler (and
-    /// `GET /recipes`). Paths are absolute so the scheduler is independent of
-    /// the process working directory.
-    pub fn to_recipes(&self) -> Vec<Recipe> {
+    /// `tenant`'s scheduled entries (the untenanted ones for `None`) as
+    /// recipes for the cron scheduler (and `GET /recipes`). Paths are
+    /// absolute so the scheduler is independent of the process working
+    /// directory.
+    pub fn to_recipes(&self, tenant: Option<&str>) -> Vec<Recipe> {
         self.agents
             .iter()
+            .filter(|agent| agent.tenant.as_deref() == tenant)
             .filter_map(|agent| {
                 let schedule = agent.schedule.as_ref()?;
                 let schedule = normalize_cron(schedule).ok()?;
@@ -262,6 +387,8 @@ impl AppManifest {
                     schedule: Some(schedule),
                     inputs: agent.input.clone(),
                     description: agent.description.clone(),
+                    budget: agent.budget,
+                    timing: agent.timing.clone(),
                 })
             })
             .collect()
@@ -277,7 +404,7 @@ impl AppManifest {
 /// Accept both the documented 5-field cron form and the `cron` crate's
 /// 6/7-field form, returning an expression the scheduler's parser accepts
 /// (5-field input gains a `0` seconds column).
-fn normalize_cron(expr: &str) -> Result<String> {
+pub(crate) fn normalize_cron(expr: &str) -> Result<String> {
     if cron::Schedule::from_str(expr).is_ok() {
         return Ok(expr.to_string());
     }
@@ -322,22 +449,37 @@ agents:
   - name: scribe
     agent: agents/scribe.ts
     schedule: "0 9 * * 1-5"
+  - name: ledger
+    agent: agents/scribe.ts
+    schedule: "0 0 * * *"
+    tenant: billing
 routes:
   - path: /webhooks/github
     agent: triage
     signal: github-event
+tenants:
+  - id: billing
+    keys_env: BILLING_API_KEYS
+    max_concurrent_runs: 2
+    policy_profile: supervised
+    max_cost_usd: 50
+    cost_window: 30d
 "#,
         );
         let manifest = AppManifest::load(&path).unwrap();
         assert_eq!(manifest.name.as_deref(), Some("support-desk"));
         assert_eq!(manifest.fleet().count(), 1);
-        let recipes = manifest.to_recipes();
+        let recipes = manifest.to_recipes(None);
         assert_eq!(recipes.len(), 1);
         assert_eq!(recipes[0].name, "scribe");
         // 5-field cron normalized to the scheduler's 6-field parser.
         assert_eq!(recipes[0].schedule.as_deref(), Some("0 0 9 * * 1-5"));
         assert!(recipes[0].agent.is_absolute());
         assert_eq!(manifest.routes.len(), 1);
+        assert_eq!(manifest.tenants[0].max_concurrent_runs, Some(2));
+        let billing = manifest.to_recipes(Some("billing"));
+        assert_eq!(billing.len(), 1);
+        assert_eq!(billing[0].name, "ledger");
     }
 
     #[test]
@@ -405,6 +547,30 @@ routes:
                 "agents: []\nroutes:\n  - path: /sessions/evil\n    agent: a\n    signal: s\n",
                 "collides with the server's built-in",
             ),
+            (
+                "tenants:\n  - id: \"a b\"\n    keys_env: K\n",
+                "not a valid tenant id",
+            ),
+            (
+                "tenants:\n  - id: a\n    keys_env: K\n  - id: a\n    keys_env: L\n",
+                "duplicate tenant id",
+            ),
+            (
+                "tenants:\n  - id: a\n    keys_env: K\n    policy_profile: lax\n",
+                "unknown policy profile",
+            ),
+            (
+                "tenants:\n  - id: a\n    keys_env: K\n    cost_window: 30d\n",
+                "cost_window without max_cost_usd",
+            ),
+            (
+                "tenants:\n  - id: a\n    keys_env: K\n    max_cost_usd: 5\n    cost_window: monthly\n",
+                "cost_window",
+            ),
+            (
+                "agents:\n  - name: a\n    agent: a.ts\n    tenant: ghost\n",
+                "undeclared tenant",
+            ),
         ] {
             let path = write_manifest(dir.path(), "chidori.app.yml", body);
             let err = AppManifest::load(&path).unwrap_err();
@@ -1761,6 +1761,7 @@ mod tests {
             token_usage: None,
             timestamp: chrono::Utc::now(),
             error: None,
+            served_by: None,
         }
     }
 
@@ -0,0 +1,457 @@
+//! Debug Adapter Protocol server for `chidori debug <agent.ts>`.
+//!
+//! Speaks DAP (`Content-Length`-framed JSON) over stdio, or over one TCP
+//! connection with `--port`, and drives a single agent run through its
+//! [`DebugController`]: line and host-call (function) breakpoints, pause,
+//! step in/over/out, stack, scopes, variables and variable-path evaluation.
+//! The agent is thread 1; the run starts once the client has sent both
+//! `launch` and `configurationDone`, so its breakpoints are in place first.
+//!
+//! Serving a replay (`--replay`) adds the reverse requests, `stepBack` and
+//! `reverseContinue`, and a `:seq N` evaluation that runs the replay to just
+//! before its host call N, forward or back.
+//!
+//! The protocol side never touches the VM: every request about a stopped run
+//! is forwarded to the run's thread (see [`crate::runtime::debugger`]).
+
+use std::io::{BufRead, BufReader, Write};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+
+use crate::runtime::debugger::{DebugController, Query, Resume};
+
+/// Runs the agent under `controller` with the launch input (`None` keeps the
+/// command line's), returning the text to report when it finishes.
+pub type Launch = Box<dyn FnOnce(Option<Value>, Arc<DebugController>) -> Result<String> + Send>;
+
+/// Serve one debug session over stdin/stdout. `replay` says the launch
+/// replays a recording, which can be stepped back in.
+pub fn serve_stdio(launch: Launch, replay: bool) -> Result<()> {
+    let stdin = std::io::stdin();
+    serve(stdin.lock(), Box::new(std::io::stdout()), launch, replay)
+}
+
+/// Serve one debug session to the first client that connects to
+/// `127.0.0.1:port`.
+pub fn serve_tcp(port: u16, launch: Launch, replay: bool) -> Result<()> {
+    let listener = std::net::TcpListener::bind(("127.0.0.1", port))
+        .with_context(|| format!("binding the debug adapter to 127.0.0.1:{port}"))?;
+    eprintln!("Debug adapter listening on 127.0.0.1:{port}");
+    let (stream, peer) = listener.accept()?;
+    eprintln!("Debugger connected from {peer}");
+    let writer = stream.try_clone()?;
+    serve(BufReader::new(stream), Box::new(writer), launch, replay)
+}
+
+/// The outgoing half: numbers and frames every message.
+struct Outbox {
+    writer: Mutex<Box<dyn Write + Send>>,
+    seq: AtomicI64,
+}
+
+impl Outbox {
+    fn send(&self, mut message: Value) {
+        message["seq"] = json!(self.seq.fetch_add(1, Ordering::Relaxed) + 1);
+        let body = message.to_string();
+        let mut writer = self.writer.lock().unwrap();
+        let _ = write!(writer, "Content-Length: {}\r\n\r\n{body}", body.len());
+        let _ = writer.flush();
+    }
+
+    fn respond(&self, request: &Value, body: Value) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": true,
+            "body": body,
+        }));
+    }
+
+    fn fail(&self, request: &Value, message: &str) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": false,
+            "message": message,
+        }));
+    }
+
+    fn event(&self, event: &str, body: Value) {
+        self.send(json!({ "type": "event", "event": event, "body": body }));
+    }
+}
+
+/// Read one framed message; `None` at end of stream.
+fn read_message(reader: &mut impl BufRead) -> Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(value) = line.strip_prefix("Content-Length:") {
+            length = Some(value.trim().parse::<usize>()?);
+        }
+    }
+    let length = length.context("DAP message without a Content-Length header")?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+fn serve(
+    mut reader: impl BufRead,
+    writer: Box<dyn Write + Send>,
+    launch: Launch,
+    replay: bool,
+) -> Result<()> {
+    let out = Arc::new(Outbox {
+        writer: Mutex::new(writer),
+        seq: AtomicI64::new(0),
+    });
+    let (controller, stops) = DebugController::new();
+    {
+        let out = out.clone();
+        std::thread::spawn(move || {
+            for stop in stops {
+                out.event(
+                    "stopped",
+                    json!({
+                        "reason": stop.reason,
+                        "description": stop.description,
+                        "threadId": 1,
+                        "allThreadsStopped": true,
+                    }),
+                );
+            }
+        });
+    }
+
+    let mut launch = Some(launch);
+    let mut launch_input: Option<Option<Value>> = None;
+    let mut configured = false;
+    let mut run: Option<std::thread::JoinHandle<()>> = None;
+    while let Some(request) = read_message(&mut reader)? {
+        if request["type"] != "request" {
+            continue;
+        }
+        let args = &request["arguments"];
+        match request["command"].as_str().unwrap_or_default() {
+            "initialize" => {
+                out.respond(
+                    &request,
+                    json!({
+                        "supportsConfigurationDoneRequest": true,
+                        "supportsFunctionBreakpoints": true,
+                        "supportsEvaluateForHovers": true,
+                        "supportsTerminateRequest": true,
+                        "supportsStepBack": replay,
+                    }),
+                );
+                out.event("initialized", json!({}));
+            }
+            "launch" => {
+                controller.set_stop_on_entry(args["stopOnEntry"].as_bool().unwrap_or(false));
+                launch_input = Some(args.get("input").cloned());
+                out.respond(&request, json!({}));
+            }
+            "attach" => out.fail(
+                &request,
+                "chidori debug starts the agent itself; use a launch configuration",
+            ),
+            "setBreakpoints" => {
+                let Some(path) = args["source"]["path"].as_str() else {
+                    out.fail(&request, "setBreakpoints needs source.path");
+                    continue;
+                };
+                let lines: Vec<u32> = args["breakpoints"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|bp| bp["line"].as_u64().map(|line| line as u32))
+                    .collect();
+                let placed = controller.set_breakpoints(std::path::Path::new(path), &lines);
+                let breakpoints: Vec<Value> = placed
+                    .iter()
+                    .zip(&lines)
+                    .map(|(placed, requested)| match placed {
+                        Some(line) => json!({ "verified": true, "line": line }),
+                        None => json!({
+                            "verified": false,
+                            "line": requested,
+                            "message": "no code at or after this line",
+                        }),
+                    })
+                    .collect();
+                out.respond(&request, json!({ "breakpoints": breakpoints }));
+            }
+            "setFunctionBreakpoints" => {
+                let names: Vec<String> = args["breakpoints"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|bp| bp["name"].as_str().map(str::to_string))
+                    .collect();
+                controller.set_function_breakpoints(&names);
+                let breakpoints: Vec<Value> =
+                    names.iter().map(|_| json!({ "verified": true })).collect();
+                out.respond(&request, json!({ "breakpoints": breakpoints }));
+            }
+            "setExceptionBreakpoints" => out.respond(&request, json!({ "breakpoints": [] })),
+            "configurationDone" => {
+                configured = true;
+                out.respond(&request, json!({}));
+            }
+            "threads" => out.respond(
+                &request,
+                json!({ "threads": [{ "id": 1, "name": "agent" }] }),
+            ),
+            "stackTrace" => match controller.query(Query::StackTrace) {
+                Ok(mut body) => {
+                    let start = args["startFrame"].as_u64().unwrap_or(0) as usize;
+                    let levels = args["levels"].as_u64().filter(|&n| n > 0);
+                    if let Some(frames) = body["stackFrames"].as_array_mut() {
+                        let end =
+                            levels.map_or(frames.len(), |n| (start + n as usize).min(frames.len()));
+                        *frames = frames.get(start..end).unwrap_or_default().to_vec();
+                    }
+                    out.respond(&request, body);
+                }
+                Err(e) => out.fail(&request, &e),
+            },
+            "scopes" => answer(
+                &out,
+                &request,
+                &controller,
+                Query::Scopes {
+                    frame: args["frameId"].as_u64().unwrap_or(0) as usize,
+                },
+            ),
+            "variables" => answer(
+                &out,
+                &request,
+                &controller,
+                Query::Variables {
+                    reference: args["variablesReference"].as_u64().unwrap_or(0) as usize,
+                },
+            ),
+            "evaluate" => {
+                let expression = args["expression"].as_str().unwrap_or_default();
+                if let Some(seq) = expression.trim().strip_prefix(":seq") {
+                    let Ok(seq) = seq.trim().parse::<u64>() else {
+                        out.fail(&request, "usage: :seq <journal seq>");
+                        continue;
+                    };
+                    match controller.run_to_seq(seq) {
+                        Ok(()) => out.respond(
+                            &request,
+                            json!({
+                                "result": format!("running to host call seq {seq}"),
+                                "variablesReference": 0,
+                            }),
+                        ),
+                        Err(e) => out.fail(&request, &e),
+                    }
+                    continue;
+                }
+                answer(
+                    &out,
+                    &request,
+                    &controller,
+                    Query::Evaluate {
+                        expression: expression.to_string(),
+                        frame: args["frameId"].as_u64().map(|id| id as usize),
+                    },
+                )
+            }
+            "continue" => {
+                controller.resume(Resume::Continue);
+                out.respond(&request, json!({ "allThreadsContinued": true }));
+            }
+            "next" => {
+                controller.resume(Resume::Next);
+                out.respond(&request, json!({}));
+            }
+            "stepIn" => {
+                controller.resume(Resume::StepIn);
+                out.respond(&request, json!({}));
+            }
+            "stepOut" => {
+                controller.resume(Resume::StepOut);
+                out.respond(&request, json!({}));
+            }
+            "stepBack" | "reverseContinue" if !replay => out.fail(
+                &request,
+                "only a replay steps back: start the session with `chidori debug --replay`",
+            ),
+            "stepBack" => {
+                controller.resume(Resume::StepBack);
+                out.respond(&request, json!({}));
+            }
+            "reverseContinue" => {
+                controller.resume(Resume::ReverseContinue);
+                out.respond(&request, json!({}));
+            }
+            "pause" => {
+                controller.request_pause();
+                out.respond(&request, json!({}));
+            }
+            "terminate" => {
+                controller.disconnect();
+                out.respond(&request, json!({}));
+            }
+            "disconnect" => {
+                controller.disconnect();
+                out.respond(&request, json!({}));
+                break;
+            }
+            other => out.fail(&request, &format!("unsupported request `{other}`")),
+        }
+
+        if configured && run.is_none() {
+            if let (Some(input), Some(launch)) = (launch_input.clone(), launch.take()) {
+                run = Some(start_run(launch, input, controller.clone(), out.clone())?);
+            }
+        }
+    }
+    // Detached, or the client went away: a run still going unwinds at its
+    // next statement, and the session ends when it has.
+    controller.disconnect();
+    if let Some(run) = run {
+        let _ = run.join();
+    }
+    Ok(())
+}
+
+fn answer(out: &Outbox, request: &Value, controller: &DebugController, query: Query) {
+    match controller.query(query) {
+        Ok(body) => out.respond(request, body),
+        Err(e) => out.fail(request, &e),
+    }
+}
+
+/// Start the agent on a JS-sized thread; report how it ended, then end the
+/// session.
+fn start_run(
+    launch: Launch,
+    input: Option<Value>,
+    controller: Arc<DebugController>,
+    out: Arc<Outbox>,
+) -> Result<std::thread::JoinHandle<()>> {
+    let handle = std::thread::Builder::new()
+        .name("chidori-debuggee".to_string())
+        .stack_size(crate::scheduler::JS_THREAD_STACK_BYTES)
+        .spawn(move || {
+            let (text, exit_code) = match launch(input, controller) {
+                Ok(text) => (text, 0),
+                Err(e) => (format!("{e:#}"), 1),
+            };
+            out.event(
+                "output",
+                json!({
+                    "category": if exit_code == 0 { "stdout" } else { "stderr" },
+                    "output": format!("{text}\n"),
+                }),
+            );
+            out.event("exited", json!({ "exitCode": exit_code }));
+            out.event("terminated", json!({}));
+        })?;
+    Ok(handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_message, serve, Launch};
+    use serde_json::{json, Value};
+    use std::io::{BufReader, Cursor, Write};
+    use std::sync::{Arc, Mutex};
+
+    fn frame(message: Value) -> Vec<u8> {
+        let body = message.to_string();
+        format!("Content-Length: {}\r\n\r\n{body}", body.len()).into_bytes()
+    }
+
+    /// A writer the test can read back after the session ends.
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn a_session_configures_then_launches_and_reports_the_result() {
+        let mut input = Vec::new();
+        for (seq, command, arguments) in [
+            (1, "initialize", json!({ "adapterID": "chidori" })),
+            (2, "launch", json!({ "input": { "name": "dap" } })),
+            (
+                3,
+                "setFunctionBreakpoints",
+                json!({ "breakpoints": [{ "name": "chidori.prompt" }] }),
+            ),
+            (4, "threads", json!({})),
+            (5, "stackTrace", json!({ "threadId": 1 })),
+            (6, "configurationDone", json!({})),
+        ] {
+            input.extend(frame(json!({
+                "seq": seq, "type": "request", "command": command, "arguments": arguments,
+            })));
+        }
+        let launch: Launch =
+            Box::new(move |input, _controller| Ok(format!("ran with {}", input.unwrap())));
+        let out = Shared::default();
+        // End of input detaches, and the session ends once the run has.
+        serve(Cursor::new(input), Box::new(out.clone()), launch, false).unwrap();
+        let bytes = out.0.lock().unwrap().clone();
+        let mut reader = BufReader::new(Cursor::new(bytes));
+        let mut messages = Vec::new();
+        while let Some(message) = read_message(&mut reader).unwrap() {
+            messages.push(message);
+        }
+        let summary: Vec<String> = messages
+            .iter()
+            .map(|m| match m["type"].as_str().unwrap() {
+                "response" => format!("{} {}", m["command"].as_str().unwrap(), m["success"]),
+                _ => m["event"].as_str().unwrap().to_string(),
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                "initialize true",
+                "initialized",
+                "launch true",
+                "setFunctionBreakpoints true",
+                "threads true",
+                "stackTrace false",
+                "configurationDone true",
+                "output",
+                "exited",
+                "terminated",
+            ]
+        );
+        let output = messages.iter().find(|m| m["event"] == "output").unwrap();
+        assert_eq!(output["body"]["output"], "ran with {\"name\":\"dap\"}\n");
+        let seqs: Vec<i64> = messages
+            .iter()
+            .map(|m| m["seq"].as_i64().unwrap())
+            .collect();
+        assert!(seqs.windows(2).all(|w| w[1] == w[0] + 1), "{seqs:?}");
+    }
+}
@@ -1,6 +1,7 @@
 mod acp;
 mod app_manifest;
 mod cellstore;
+mod dap;
 mod deploy;
 mod export;
 mod init;
@@ -24,7 +25,9 @@ use clap::{Parser, Subcommand};
 use serde_json::Value;
 
 use crate::providers::ProviderRegistry;
+use crate::runtime::coverage::CoverageRecorder;
 use crate::runtime::engine::Engine;
+use crate::runtime::profiler::{ProfileFormat, ProfileWeight, Profiler};
 
 /// Track live heap usage process-wide so the rust-engine watchdog can enforce a
 /// per-run memory ceiling (see `mem_guard` and `runtime::rust_engine`). The
@@ -80,6 +83,12 @@ enum Commands {
         #[arg(long)]
         model: Option<String>,
 
+        /// Cost budget in USD (equivalent to CHIDORI_MAX_COST_USD). Prompts
+        /// stop once the run's estimated spend reaches it; set
+        /// CHIDORI_BUDGET_ON_EXCEED=ask to be asked instead of failing.
+        #[arg(long, value_name = "USD")]
+        max_cost_usd: Option<f64>,
+
         /// Stream each host-function call as a newline-delimited JSON event to
         /// stdout as it executes. Each line is either:
         ///   {"type":"call","record":{...}}
@@ -115,6 +124,37 @@ enum Commands {
         /// Equivalent to CHIDORI_ISOLATE=off.
         #[arg(long)]
         no_isolate: bool,
+
+        /// Write a CPU profile of the agent's JavaScript to this file: a
+        /// Chrome DevTools `.cpuprofile` (open it in the Performance panel)
+        /// or, with --profile-format folded, folded stacks for flamegraph
+        /// tools. Functions are placed at their .ts lines and host calls
+        /// show as `(host: <op>)` frames. Profiled runs are in-process.
+        #[arg(long, value_name = "PATH", conflicts_with_all = ["stream", "isolate"])]
+        profile: Option<PathBuf>,
+
+        /// Format of the --profile file.
+        #[arg(long, default_value = "cpuprofile", value_parser = ["cpuprofile", "folded"], requires = "profile")]
+        profile_format: String,
+
+        /// What the profile's samples weigh: wall `time`, or interpreter
+        /// `instructions` (deterministic; host calls weigh nothing).
+        #[arg(long, default_value = "time", value_parser = ["time", "instructions"], requires = "profile")]
+        profile_weight: String,
+
+        /// If the run exceeds its memory cap (CHIDORI_JS_MEM_CAP_MB), write a
+        /// heap snapshot to the run's `heap/oom.heapsnapshot` and list the
+        /// largest retainers in the error. Equivalent to
+        /// CHIDORI_HEAP_SNAPSHOT_ON_OOM=1.
+        #[arg(long)]
+        heap_snapshot_on_oom: bool,
+
+        /// Count which blocks and lines of the agent's own code run, and
+        /// merge the counts into DIR: `lcov.info` for CI coverage tools and
+        /// `coverage.json` (counts and summary). Repeated runs into the same
+        /// DIR add up. Covered runs are in-process.
+        #[arg(long, value_name = "DIR", conflicts_with_all = ["stream", "isolate"])]
+        coverage: Option<PathBuf>,
     },
 
     /// Watch an agent and re-run it on every save, replaying recorded calls
@@ -148,6 +188,40 @@ enum Commands {
         trusted: bool,
     },
 
+    /// Debug an agent from an editor over the Debug Adapter Protocol: line
+    /// breakpoints in the .ts source, breakpoints on host calls (`prompt`,
+    /// `http`, …), step in/over/out across `await`, and scopes and variables
+    /// of every frame. Speaks DAP on stdin/stdout, or on 127.0.0.1:<port>
+    /// with --port. The run is in-process and journals like `chidori run`.
+    Debug {
+        /// Path to the agent .ts file
+        file: PathBuf,
+
+        /// Input as key=value pairs or a JSON string (a launch request's
+        /// `input` replaces it). Use @filename to read value from a file.
+        #[arg(short, long)]
+        input: Vec<String>,
+
+        /// Serve DAP on this TCP port (loopback only) instead of stdio.
+        #[arg(long)]
+        port: Option<u16>,
+
+        /// Debug a replay of this recorded run instead of a live run: every
+        /// host call is answered from its journal, as `verify` does, and the
+        /// session can step back and jump to a host call's seq.
+        #[arg(long, value_name = "RUN_ID", conflicts_with_all = ["input", "untrusted", "trusted"])]
+        replay: Option<String>,
+
+        /// Run under the built-in deny-by-default `untrusted` policy profile
+        /// (see `run --untrusted`).
+        #[arg(long, conflicts_with = "trusted")]
+        untrusted: bool,
+
+        /// Opt out of the ask-before-powerful-effects default (see `run --trusted`).
+        #[arg(long)]
+        trusted: bool,
+    },
+
     /// Internal: the isolate worker. Runs one agent over a stdin/stdout frame
     /// protocol on behalf of a parent supervisor; not meant to be invoked
     /// directly. See `crate::runtime::isolate`.
@@ -294,6 +368,13 @@ enum Commands {
         #[arg(long)]
         model: Option<String>,
 
+        /// Cost budget in USD for the resumed run (equivalent to
+        /// CHIDORI_MAX_COST_USD). Defaults to the budget recorded in the
+        /// run's manifest; spend already recorded in the journal counts
+        /// against it.
+        #[arg(long, value_name = "USD")]
+        max_cost_usd: Option<f64>,
+
         /// Deny gated effects (tool calls, network, workspace writes) that
         /// live continuation past the replay frontier would perform.
         #[arg(long, conflicts_with = "trusted")]
@@ -382,6 +463,12 @@ enum Commands {
         /// directory.
         #[arg(long)]
         runs_dir: Option<PathBuf>,
+
+        /// Merge the coverage of the agent code this replay runs into DIR
+        /// (`lcov.info` and `coverage.json`). Verify each fixture into the
+        /// same DIR to get the coverage of them all.
+        #[arg(long, value_name = "DIR")]
+        coverage: Option<PathBuf>,
     },
 
     /// Export a completed run as a minimal, committable verification fixture:
@@ -410,6 +497,12 @@ enum Commands {
         action: CheckpointAction,
     },
 
+    /// Inspect policy files offline.
+    Policy {
+        #[command(subcommand)]
+        action: PolicyAction,
+    },
+
     /// List a run's persisted `chidori.branch` sub-runs and their states.
     Branches {
         /// Run id (subdirectory name under `.chidori/runs/`)
@@ -613,6 +706,66 @@ enum Commands {
         /// CHIDORI_APP_MANIFEST also names one.
         #[arg(long, value_name = "MANIFEST")]
         app: Option<PathBuf>,
+
+        /// Delete completed sessions, and their run directories, once they
+        /// are older than this (`30d`, `12h`; units s, m, h, d, w). A
+        /// background sweeper enforces it every few minutes. Kept forever
+        /// when unset.
+        #[arg(long, value_name = "AGE", value_parser = server::parse_age)]
+        retain_completed: Option<chrono::Duration>,
+
+        /// Like --retain-completed, for failed sessions.
+        #[arg(long, value_name = "AGE", value_parser = server::parse_age)]
+        retain_failed: Option<chrono::Duration>,
+
+        /// Like --retain-completed, for cancelled sessions.
+        #[arg(long, value_name = "AGE", value_parser = server::parse_age)]
+        retain_cancelled: Option<chrono::Duration>,
+    },
+
+    /// Serve the agents in a directory, and the configured recipes, as the
+    /// tools of an MCP server. Each tool call is a durable session in the
+    /// directory's session store, exactly as `chidori serve` runs them; a
+    /// paused run is answered through MCP elicitation when the client
+    /// supports it, or returned as a resumable result for `chidori_resume`.
+    McpServe {
+        /// Directory whose `.ts` agents are published (defaults to current dir)
+        dir: Option<PathBuf>,
+
+        /// Transport: `stdio` (newline-delimited JSON-RPC) or `http`
+        /// (Streamable HTTP on `POST /mcp`).
+        #[arg(long, default_value = "stdio", value_parser = ["stdio", "http"])]
+        transport: String,
+
+        /// Port for the HTTP transport
+        #[arg(short, long, default_value = "8090")]
+        port: u16,
+
+        /// Address the HTTP transport binds. Same rules as `chidori serve
+        /// --host`: loopback by default, and a non-loopback bind requires
+        /// CHIDORI_API_KEY unless CHIDORI_ALLOW_UNAUTHENTICATED=1.
+        #[arg(long)]
+        host: Option<String>,
+
+        /// Directory of recipe YAML/JSON files to publish as tools (defaults
+        /// to CHIDORI_RECIPE_DIR).
+        #[arg(long)]
+        recipes: Option<PathBuf>,
+
+        /// Default model for prompts that don't set one in code (equivalent
+        /// to CHIDORI_MODEL).
+        #[arg(long)]
+        model: Option<String>,
+
+        /// Serve under the built-in deny-by-default `untrusted` policy
+        /// profile, as `chidori serve --untrusted`.
+        #[arg(long, conflicts_with = "trusted")]
+        untrusted: bool,
+
+        /// Opt out of the deny-by-default posture, as `chidori serve
+        /// --trusted`.
+        #[arg(long)]
+        trusted: bool,
     },
 
     /// Serve a self-hosted durable run store — the celld model
@@ -723,6 +876,34 @@ enum CheckpointAction {
     },
 }
 
+#[derive(Subcommand)]
+enum PolicyAction {
+    /// Show which rule of which layer decides a call, and why every other
+    /// rule for the target was passed over. Stateless: rate-limited rules
+    /// are listed but never fire.
+    Explain {
+        /// Policy JSON file (the `CHIDORI_POLICY_FILE` format)
+        policy: PathBuf,
+
+        /// Normalized target, e.g. `http`, `tool:deploy`, `workspace.write`
+        #[arg(long)]
+        target: String,
+
+        /// Call args as JSON (defaults to `{}`)
+        #[arg(long)]
+        args: Option<String>,
+
+        /// Layer a built-in profile over the file, as a session's
+        /// `policy_profile` would
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Print the explanation as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
 fn main() {
     let cli = Cli::parse();
 
@@ -806,6 +987,7 @@ fn display_project_root_of(command: &Commands) -> Option<PathBuf> {
     let file = match command {
         Commands::Run { file, .. }
         | Commands::Dev { file, .. }
+        | Commands::Debug { file, .. }
         | Commands::Check { file }
         | Commands::Resume { file, .. }
         | Commands::Verify { file, .. } => file.clone(),
@@ -826,17 +1008,25 @@ fn dispatch_command(command: Commands) -> (Result<()>, bool) {
             trace,
             verbose,
             model,
+            max_cost_usd,
             stream,
             untrusted,
             trusted,
             isolate,
             no_isolate,
+            profile,
+            profile_format,
+            profile_weight,
+            heap_snapshot_on_oom,
+            coverage,
         } => {
             // `run_agent` reads this env var to decide whether to spawn a worker;
-            // setting it here keeps the isolation decision in one place.
+            // setting it here keeps the isolation decision in one place. The
+            // profiler and coverage observe the VM, which an isolate worker
+            // keeps in another process.
             if isolate {
                 crate::runtime::isolate::enable();
-            } else if no_isolate {
+            } else if no_isolate || profile.is_some() || coverage.is_some() {
                 crate::runtime::isolate::disable();
             }
             // The runtime (and any isolate worker child) resolves the default
@@ -844,6 +1034,12 @@ fn dispatch_command(command: Commands) -> (Result<()>, bool) {
             if let Some(ref model) = model {
                 std::env::set_var("CHIDORI_MODEL", model);
             }
+            if let Some(max_cost_usd) = max_cost_usd {
+                std::env::set_var("CHIDORI_MAX_COST_USD", max_cost_usd.to_string());
+            }
+            if heap_snapshot_on_oom {
+                std::env::set_var("CHIDORI_HEAP_SNAPSHOT_ON_OOM", "1");
+            }
             // Propagate verbosity to the isolate worker child so its sandbox
             // degradation notes surface under -v.
             if verbose {
@@ -853,7 +1049,20 @@ fn dispatch_command(command: Commands) -> (Result<()>, bool) {
             let result = if stream {
                 cmd_run_stream(&file, &input, verbose, untrusted, trusted)
             } else {
-                cmd_run(&file, &input, trace, verbose, untrusted, trusted)
+                let profile = profile.map(|path| {
+                    let format = match profile_format.as_str() {
+                        "folded" => ProfileFormat::Folded,
+                        _ => ProfileFormat::CpuProfile,
+                    };
+                    let weight = match profile_weight.as_str() {
+                        "instructions" => ProfileWeight::Instructions,
+                        _ => ProfileWeight::Time,
+                    };
+                    (path, format, weight)
+                });
+                cmd_run(
+                    &file, &input, trace, verbose, untrusted, trusted, profile, coverage,
+                )
             };
             (result, false)
         }
@@ -870,6 +1079,17 @@ fn dispatch_command(command: Commands) -> (Result<()>, bool) {
             crate::runtime::isolate::warn_if_untrusted_without_isolation(untrusted);
             (cmd_dev(&file, &input, untrusted, trusted), false)
         }
+        Commands::Debug {
+            file,
+            input,
+            port,
+            replay,
+            untrusted,
+            trusted,
+        } => (
+            cmd_debug(&file, &input, port, replay.as_deref(), untrusted, trusted),
+            false,
+        ),
         Commands::RunWorker => unreachable!("handled before the dispatch match"),
         Commands::Demo => (cmd_demo(), false),
         Commands::ModelLogin => (cmd_login(), false),
@@ -913,6 +1133,7 @@ fn dispatch_command(command: Commands) -> (Result<()>, bool) {
             retry_failed,
             allow_source_change,
             model,
+            max_cost_usd,
             untrusted,
             trusted,
             ci,
@@ -927,6 +1148,9 @@ fn dispatch_command(command: Commands) -> (Result<()>, bool) {
             if let Some(ref model) = model {
                 std::env::set_var("CHIDORI_MODEL", model);
             }
+            if let Some(max_cost_usd) = max_cost_usd {
+                std::env::set_var("CHIDORI_MAX_COST_USD", max_cost_usd.to_string());
+            }
             (
                 cmd_resume(
                     &file,
@@ -957,10 +1181,22 @@ fn dispatch_command(command: Commands) -> (Result<()>, bool) {
             run_id,
             dir,
             runs_dir,
-        } => (
-            cmd_verify(&file, &run_id, dir.as_deref(), runs_dir.as_deref()),
-            false,
-        ),
+            coverage,
+        } => {
+            if coverage.is_some() {
+                crate::runtime::isolate::disable();
+            }
+            (
+                cmd_verify(
+                    &file,
+                    &run_id,
+                    dir.as_deref(),
+                    runs_dir.as_deref(),
+                    coverage.as_deref(),
+                ),
+                false,
+            )
+        }
         Commands::Export {
             run_id,
             fixture,
@@ -982,6 +1218,18 @@ fn dispatch_command(command: Commands) -> (Result<()>, bool) {
                 (cmd_checkpoint_import(&archive, dir.as_deref()), false)
             }
         },
+        Commands::Policy { action } => match action {
+            PolicyAction::Explain {
+                policy,
+                target,
+                args,
+                profile,
+                json,
+            } => (
+                cmd_policy_explain(&policy, &target, args.as_deref(), profile.as_deref(), json),
+                false,
+            ),
+        },
         Commands::Branches { run_id, dir } => (cmd_branches(&run_id, dir.as_deref()), false),
         Commands::BranchResume {
             run_id,
@@ -1052,6 +1300,9 @@ fn dispatch_command(command: Commands) -> (Result<()>, bool) {
             isolate,
             no_isolate,
             app,
+            retain_completed,
+            retain_failed,
+            retain_cancelled,
         } => {
             if isolate {
                 crate::runtime::isolate::enable();
@@ -1070,6 +1321,37 @@ fn dispatch_command(command: Commands) -> (Result<()>, bool) {
                     untrusted,
                     trusted,
                     app.as_deref(),
+                    server::RetentionPolicy {
+                        completed: retain_completed,
+                        failed: retain_failed,
+                        cancelled: retain_cancelled,
+                    },
+                ),
+                false,
+            )
+        }
+        Commands::McpServe {
+            dir,
+            transport,
+            port,
+            host,
+            recipes,
+            model,
+            untrusted,
+            trusted,
+        } => {
+            if let Some(ref model) = model {
+                std::env::set_var("CHIDORI_MODEL", model);
+            }
+            (
+                cmd_mcp_serve(
+                    dir.as_deref(),
+                    &transport,
+                    host.as_deref(),
+                    port,
+                    recipes.as_deref(),
+                    untrusted,
+                    trusted,
                 ),
                 false,
             )
@@ -1374,7 +1656,7 @@ fn cmd_demo() -> Result<()> {
             if *stream {
                 cmd_run_stream(&file, &inputs, false, false, true)
             } else {
-                cmd_run(&file, &inputs, *trace, false, false, true)
+                cmd_run(&file, &inputs, *trace, false, false, true, None, None)
             }
         }
         DemoAction::Serve { file, port } => {
@@ -1392,6 +1674,7 @@ fn cmd_demo() -> Result<()> {
                 false,
                 true,
                 None,
+                server::RetentionPolicy::default(),
             )
         }
     }
@@ -1646,6 +1929,7 @@ fn spawn_prompt_progress_listener() -> Option<(
     Some((event_tx, drain))
 }
 
+#[allow(clippy::too_many_arguments)]
 fn cmd_run(
     file: &Path,
     inputs: &[String],
@@ -1653,6 +1937,8 @@ fn cmd_run(
     verbose: bool,
     untrusted: bool,
     trusted: bool,
+    profile: Option<(PathBuf, ProfileFormat, ProfileWeight)>,
+    coverage: Option<PathBuf>,
 ) -> Result<()> {
     // Set up tracing.
     if verbose {
@@ -1698,11 +1984,21 @@ fn cmd_run(
     // externally-sourced tools only (MCP), unused on the plain CLI path.
     let tools = Arc::new(ToolRegistry::new());
 
-    let engine = Engine::new(providers, template_engine, tokio_rt)
+    let mut engine = Engine::new(providers, template_engine, tokio_rt)
         .with_tools(tools)
         .with_policy(cli_policy(untrusted, trusted))
         .with_persist_base(base_dir.join(".chidori").join("runs"))
         .with_workspace_root(abs_dir(&base_dir));
+    let profiler = profile
+        .as_ref()
+        .map(|&(_, _, weight)| Profiler::new(weight));
+    if let Some(ref profiler) = profiler {
+        engine = engine.with_profiler(profiler.clone());
+    }
+    let recorder = coverage.as_ref().map(|_| CoverageRecorder::new());
+    if let Some(ref recorder) = recorder {
+        engine = engine.with_coverage(recorder.clone());
+    }
 
     // Run the agent.
     // Announce the run id up front (stderr): after a crash — where buffered
@@ -1716,11 +2012,22 @@ fn cmd_run(
             // The sender moved into the engine and drops when the run
             // returns; join so the last progress lines land before output.
             drain.join().ok();
-            result?
+            result
         }
-        None => engine.run_announced(file, &input_value)?,
+        None => engine.run_announced(file, &input_value),
     };
 
+    // The profile covers the run up to its pause or failure, and a failed
+    // run still reports the lines it reached.
+    if let (Some(profiler), Some((path, format, _))) = (&profiler, &profile) {
+        profiler.write(path, *format)?;
+        eprintln!("Profile written to {}", path.display());
+    }
+    if let (Some(recorder), Some(dir)) = (&recorder, &coverage) {
+        write_coverage(recorder, dir)?;
+    }
+    let result = result?;
+
     // A `chidori.signal(name)` listen point with an empty mailbox pauses the run
     // (there is no stdin fallback for signals, unlike `input()`). The engine has
     // already persisted the durable pause scaffold under `.chidori/runs/<run_id>`;
@@ -1772,6 +2079,92 @@ fn cmd_run(
     Ok(())
 }
 
+/// `chidori debug`: serve one DAP session whose launch runs `file`
+/// in-process with the debugger attached, or with `replay`, replays that
+/// recorded run under it.
+fn cmd_debug(
+    file: &Path,
+    inputs: &[String],
+    port: Option<u16>,
+    replay: Option<&str>,
+    untrusted: bool,
+    trusted: bool,
+) -> Result<()> {
+    // The debug hook lives in the VM; an isolate worker's VM is in another
+    // process.
+    crate::runtime::isolate::disable();
+    let cli_input = parse_inputs(inputs)?;
+    let file = file.to_path_buf();
+    let base_dir = file
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .to_path_buf();
+    let run_base = base_dir.join(".chidori").join("runs");
+    // A replay is checked before the client connects, as `verify` checks it.
+    let recording = match replay {
+        Some(run_id) => {
+            let run_dir = run_base.join(run_id);
+            use crate::runtime::store::RunStore as _;
+            let records = crate::runtime::store::FsRunStore::new(run_dir.clone())
+                .load_call_log()?
+                .ok_or_else(|| {
+                    anyhow::anyhow!("No checkpoint found under {}", run_dir.display())
+                })?;
+            crate::runtime::snapshot::validate_manifest_for_resume(
+                &run_base,
+                Some(run_id),
+                &file,
+                false,
+            )
+            .context("the agent source no longer matches this run's checkpoint")?;
+            Some((run_id.to_string(), records, recorded_input(&run_dir)?))
+        }
+        None => None,
+    };
+    let replaying = recording.is_some();
+    let launch: dap::Launch = Box::new(move |input, controller| {
+        let result = match recording {
+            Some((run_id, records, recorded)) => replay_engine(&base_dir, &run_base.join(&run_id))?
+                .replay_debugged(&file, &recorded, records, &run_id, controller)?,
+            None => {
+                let tokio_rt = Arc::new(
+                    scheduler::new_tokio_runtime().context("Failed to create tokio runtime")?,
+                );
+                let engine = Engine::new(
+                    Arc::new(ProviderRegistry::from_env()),
+                    Arc::new(TemplateEngine::new(&base_dir)),
+                    tokio_rt,
+                )
+                .with_tools(Arc::new(ToolRegistry::new()))
+                .with_policy(cli_policy(untrusted, trusted))
+                .with_persist_base(run_base)
+                .with_workspace_root(abs_dir(&base_dir));
+                engine.run_debugged(&file, &input.unwrap_or(cli_input), controller)?
+            }
+        };
+        Ok(if let Some(pending) = &result.paused {
+            format!(
+                "Run {} paused at input(): {}",
+                result.run_id, pending.prompt
+            )
+        } else if result.paused_approval.is_some() {
+            format!("Run {} paused awaiting an approval", result.run_id)
+        } else if let Some(signal) = &result.paused_signal {
+            format!(
+                "Run {} paused awaiting signal '{}'",
+                result.run_id,
+                signal.listen_names().join("', '")
+            )
+        } else {
+            serde_json::to_string_pretty(&result.output)?
+        })
+    });
+    match port {
+        Some(port) => dap::serve_tcp(port, launch, replaying),
+        None => dap::serve_stdio(launch, replaying),
+    }
+}
+
 /// `chidori dev` — the edit-and-replay loop as a first-class mode.
 ///
 /// The first run records a journal like plain `chidori run`. After that the
@@ -2612,14 +3005,14 @@ fn print_chat_session_summary(run_base: &Path, session_id: &str, turns: usize) {
     let mut cost: f64 = 0.0;
     let mut any_unpriced = false;
     for r in &records {
-        if r.function != "prompt" {
+        if !r.is_model_call() {
             continue;
         }
-        prompt_calls += 1;
-        let model = r
-            .args
-            .get("model")
-            .and_then(|v| v.as_str())
+        if r.function == "prompt" {
+            prompt_calls += 1;
+        }
+        let model = Some(r.priced_model())
+            .filter(|m| !m.is_empty())
             .unwrap_or("unknown");
         if !is_priced_model(model) {
             any_unpriced = true;
@@ -2828,14 +3221,18 @@ fn cmd_resume(
     // The run's model travels with it: an explicit `--model` (or a
     // pre-existing CHIDORI_MODEL) wins, then the model recorded in the run's
     // manifest — so the README's bare `chidori resume agent.ts <run-id>`
-    // replays a `--model`-started run without re-deriving flags.
-    let manifest_model = crate::runtime::snapshot::SnapshotStore::new(run_dir.clone())
+    // replays a `--model`-started run without re-deriving flags. The cost
+    // budget follows the same precedence (`--max-cost-usd` is a spelling of
+    // CHIDORI_MAX_COST_USD).
+    let manifest = crate::runtime::snapshot::SnapshotStore::new(run_dir.clone())
         .load_manifest()
-        .ok()
-        .and_then(|manifest| manifest.default_model);
+        .ok();
+    let manifest_budget = manifest.as_ref().and_then(|manifest| manifest.budget);
+    let manifest_model = manifest.and_then(|manifest| manifest.default_model);
     let default_model = model
         .or_else(|| std::env::var("CHIDORI_MODEL").ok())
         .or(manifest_model);
+    let budget = crate::runtime::cost::CostBudget::from_env().or(manifest_budget);
 
     let providers = Arc::new(ProviderRegistry::from_env());
     let template_engine = Arc::new(TemplateEngine::new(&base_dir));
@@ -2853,6 +3250,7 @@ fn cmd_resume(
         .with_policy(cli_policy(untrusted, trusted))
         .with_persist_base(run_base.clone())
         .with_default_model(default_model)
+        .with_budget(budget)
         // Both `--until-seq` and `--retry-failed` intentionally hand the
         // engine a journal SHORTER than the durable one; without the opt-in
         // the shorter-log floor would refuse to compact the repaired history
@@ -3045,6 +3443,7 @@ fn cmd_verify(
     run_id: &str,
     dir: Option<&std::path::Path>,
     runs_dir: Option<&std::path::Path>,
+    coverage: Option<&std::path::Path>,
 ) -> Result<()> {
     let base_dir = dir
         .map(|d| d.to_path_buf())
@@ -3067,13 +3466,7 @@ fn cmd_verify(
         .get_blob("output.json")?
         .and_then(|bytes| serde_json::from_slice(&bytes).ok());
 
-    let input_path = run_dir.join("input.json");
-    let input_value: Value = if input_path.exists() {
-        serde_json::from_str(&std::fs::read_to_string(&input_path)?)
-            .unwrap_or(Value::Object(Default::default()))
-    } else {
-        Value::Object(Default::default())
-    };
+    let input_value = recorded_input(&run_dir)?;
 
     // Drift gate 1: the agent source must match the recorded fingerprints.
     // No `--allow-source-change` escape here — a verify against edited code
@@ -3081,29 +3474,21 @@ fn cmd_verify(
     crate::runtime::snapshot::validate_manifest_for_resume(&run_base, Some(run_id), file, false)
         .context("verify refused: the agent source no longer matches this run's checkpoint")?;
 
-    // No providers, deny-all policy, no persistence: the replay must be able
-    // to answer EVERY effect from the journal or fail.
-    let providers = Arc::new(ProviderRegistry::new());
-    let template_engine = Arc::new(TemplateEngine::new(&base_dir));
-    let tokio_rt =
-        Arc::new(scheduler::new_tokio_runtime().context("Failed to create tokio runtime")?);
-    let tools = Arc::new(ToolRegistry::new());
-    let manifest_model = crate::runtime::snapshot::SnapshotStore::new(run_dir.clone())
-        .load_manifest()
-        .ok()
-        .and_then(|manifest| manifest.default_model);
-    let engine = Engine::new(providers, template_engine, tokio_rt)
-        .with_tools(tools)
-        .with_policy(Arc::new(
-            policy::builtin_profile("untrusted").expect("built-in untrusted profile exists"),
-        ))
-        .with_default_model(manifest_model)
-        .with_workspace_root(abs_dir(&base_dir));
+    let mut engine = replay_engine(&base_dir, &run_dir)?;
+    let recorder = coverage.map(|_| CoverageRecorder::new());
+    if let Some(ref recorder) = recorder {
+        engine = engine.with_coverage(recorder.clone());
+    }
 
     let journal_len = records.len() as u64;
     let result = engine
         .resume_run(file, &input_value, records, run_id)
-        .context("verify FAILED: the recorded run did not replay cleanly")?;
+        .context("verify FAILED: the recorded run did not replay cleanly");
+    // What the replay ran counts whether or not it verifies.
+    if let (Some(recorder), Some(dir)) = (&recorder, coverage) {
+        write_coverage(recorder, dir)?;
+    }
+    let result = result?;
 
     if result.paused.is_some() || result.paused_approval.is_some() || result.paused_signal.is_some()
     {
@@ -3157,6 +3542,56 @@ fn cmd_verify(
     Ok(())
 }
 
+/// Merge a run's coverage into `dir` and say where it went.
+fn write_coverage(recorder: &CoverageRecorder, dir: &Path) -> Result<()> {
+    let totals = recorder.write(dir)?;
+    eprintln!(
+        "Coverage: {}/{} lines ({}%), {}/{} blocks, {}/{} functions — merged into {}",
+        totals.lines.hit,
+        totals.lines.found,
+        totals.lines.percent,
+        totals.blocks.hit,
+        totals.blocks.found,
+        totals.functions.hit,
+        totals.functions.found,
+        dir.display()
+    );
+    Ok(())
+}
+
+/// The input a recorded run was started with (`{}` when none was saved).
+fn recorded_input(run_dir: &Path) -> Result<Value> {
+    let input_path = run_dir.join("input.json");
+    Ok(if input_path.exists() {
+        serde_json::from_str(&std::fs::read_to_string(&input_path)?)
+            .unwrap_or(Value::Object(Default::default()))
+    } else {
+        Value::Object(Default::default())
+    })
+}
+
+/// An engine that can only replay the run recorded in `run_dir`. No
+/// providers, deny-all policy, no persistence: the replay must be able to
+/// answer EVERY effect from the journal or fail.
+fn replay_engine(base_dir: &Path, run_dir: &Path) -> Result<Engine> {
+    let providers = Arc::new(ProviderRegistry::new());
+    let template_engine = Arc::new(TemplateEngine::new(base_dir));
+    let tokio_rt =
+        Arc::new(scheduler::new_tokio_runtime().context("Failed to create tokio runtime")?);
+    let tools = Arc::new(ToolRegistry::new());
+    let manifest_model = crate::runtime::snapshot::SnapshotStore::new(run_dir.to_path_buf())
+        .load_manifest()
+        .ok()
+        .and_then(|manifest| manifest.default_model);
+    Ok(Engine::new(providers, template_engine, tokio_rt)
+        .with_tools(tools)
+        .with_policy(Arc::new(
+            policy::builtin_profile("untrusted").expect("built-in untrusted profile exists"),
+        ))
+        .with_default_model(manifest_model)
+        .with_workspace_root(abs_dir(base_dir)))
+}
+
 /// `chidori resume --ci`: replay a checkpoint non-interactively and report
 /// whether the run still replays byte-identically. Prints one JSON object to
 /// stdout and returns a stable exit code:
@@ -3463,8 +3898,8 @@ fn branch_run_dir(run_id: &str, dir: Option<&std::path::Path>) -> Result<PathBuf
 
 /// The engine for out-of-band branch operations, wired like `cmd_resume`'s:
 /// providers from env, `--trusted`/`--untrusted` policy, tools from
-/// `<base>/tools`, and the parent run's recorded model as the default
-/// (`--model` / CHIDORI_MODEL still win).
+/// `<base>/tools`, and the parent run's recorded model and budget as the
+/// defaults (`--model` / CHIDORI_MODEL / CHIDORI_MAX_COST_USD still win).
 fn branch_engine(
     run_dir: &std::path::Path,
     dir: Option<&std::path::Path>,
@@ -3475,10 +3910,11 @@ fn branch_engine(
     let base_dir = dir
         .map(|d| d.to_path_buf())
         .unwrap_or_else(|| PathBuf::from("."));
-    let manifest_model = crate::runtime::snapshot::SnapshotStore::new(run_dir.to_path_buf())
+    let manifest = crate::runtime::snapshot::SnapshotStore::new(run_dir.to_path_buf())
         .load_manifest()
-        .ok()
-        .and_then(|manifest| manifest.default_model);
+        .ok();
+    let manifest_budget = manifest.as_ref().and_then(|manifest| manifest.budget);
+    let manifest_model = manifest.and_then(|manifest| manifest.default_model);
     let default_model = model
         .or_else(|| std::env::var("CHIDORI_MODEL").ok())
         .or(manifest_model);
@@ -3491,9 +3927,46 @@ fn branch_engine(
         .with_tools(tools)
         .with_policy(cli_policy(untrusted, trusted))
         .with_default_model(default_model)
+        .with_budget(crate::runtime::cost::CostBudget::from_env().or(manifest_budget))
         .with_workspace_root(abs_dir(&base_dir)))
 }
 
+/// `chidori policy explain`: evaluate one call against a policy file (plus
+/// an optional built-in profile layer) and print the rule-by-rule account.
+fn cmd_policy_explain(
+    policy_path: &std::path::Path,
+    target: &str,
+    args: Option<&str>,
+    profile: Option<&str>,
+    json: bool,
+) -> Result<()> {
+    let text = std::fs::read_to_string(policy_path)
+        .with_context(|| format!("Failed to read {}", policy_path.display()))?;
+    let mut config = policy::PolicyConfig::parse(&text)
+        .map_err(|e| anyhow::anyhow!("Invalid policy {}: {e}", policy_path.display()))?;
+    config.label = Some(policy_path.display().to_string());
+    if let Some(name) = profile {
+        let layer = policy::builtin_profile(name).with_context(|| {
+            format!(
+                "unknown profile `{name}` (expected one of: {})",
+                policy::BUILTIN_PROFILES.join(", ")
+            )
+        })?;
+        config = config.restricted_by(std::sync::Arc::new(layer));
+    }
+    let args: serde_json::Value = match args {
+        Some(raw) => serde_json::from_str(raw).context("--args must be JSON")?,
+        None => serde_json::json!({}),
+    };
+    let explanation = config.explain(target, &args);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&explanation)?);
+    } else {
+        println!("{explanation}");
+    }
+    Ok(())
+}
+
 fn cmd_branches(run_id: &str, dir: Option<&std::path::Path>) -> Result<()> {
     let run_dir = branch_run_dir(run_id, dir)?;
     let branches = Engine::list_branches(&run_dir)?;
@@ -3658,6 +4131,7 @@ fn cmd_trace(run_id: &str, dir: Option<&std::path::Path>) -> Result<()> {
     let mut total_out = 0u64;
     let mut total_cache_read = 0u64;
     let mut total_cache_write = 0u64;
+    let mut total_reasoning = 0u64;
     let mut total_ms = 0u64;
     let mut total_cost = 0.0;
     let mut unpriced_models: std::collections::BTreeSet<String> = std::collections::BTreeSet::new();
@@ -3737,17 +4211,34 @@ fn cmd_trace(run_id: &str, dir: Option<&std::path::Path>) -> Result<()> {
         } else {
             String::new()
         };
+        // Which route leg answered: shown whenever it differs from what the
+        // prompt asked for, i.e. an alias resolved or a fallback fired.
+        let served_tag = r
+            .served_by
+            .as_ref()
+            .filter(|served| r.args.get("model").and_then(|v| v.as_str()) != Some(&served.model))
+            .map(|served| format!(" (served by {}/{})", served.provider, served.model))
+            .unwrap_or_default();
         println!(
-            "  {:<owner_width$}  {:<8} {:>6}ms  {}  {}{}{}{}",
-            label, seq_disp, r.duration_ms, r.function, args_short, token_tag, signal_tag, err_tag
+            "  {:<owner_width$}  {:<8} {:>6}ms  {}  {}{}{}{}{}",
+            label,
+            seq_disp,
+            r.duration_ms,
+            r.function,
+            args_short,
+            served_tag,
+            token_tag,
+            signal_tag,
+            err_tag
         );
         if let Some(ref u) = r.token_usage {
             total_in += u.input_tokens;
             total_out += u.output_tokens;
             total_cache_read += u.cache_read_tokens.unwrap_or(0);
             total_cache_write += u.cache_creation_tokens.unwrap_or(0);
-            if r.function == "prompt" {
-                let model = r.args.get("model").and_then(|v| v.as_str()).unwrap_or("");
+            total_reasoning += u.reasoning_tokens.unwrap_or(0);
+            if r.is_model_call() {
+                let model = r.priced_model();
                 if crate::runtime::cost::is_priced_model(model) {
                     total_cost += crate::runtime::cost::estimate_cost_usd_with_cache(
                         model,
@@ -3766,7 +4257,14 @@ fn cmd_trace(run_id: &str, dir: Option<&std::path::Path>) -> Result<()> {
 
     println!();
     if total_in > 0 || total_out > 0 {
-        println!("Tokens:   {} in / {} out", total_in, total_out);
+        if total_reasoning > 0 {
+            println!(
+                "Tokens:   {} in / {} out ({} reasoning)",
+                total_in, total_out, total_reasoning
+            );
+        } else {
+            println!("Tokens:   {} in / {} out", total_in, total_out);
+        }
         if total_cache_read > 0 || total_cache_write > 0 {
             println!(
                 "Cache:    {} read / {} written (prompt-cache tokens)",
@@ -4265,16 +4763,18 @@ fn cmd_stats(dir: Option<&std::path::Path>) -> Result<()> {
         run_count += 1;
         let mut log = CallLog::new();
         for r in records {
-            if r.function == "prompt" {
-                prompt_count += 1;
+            if r.is_model_call() {
+                // Embedding batches get a per-model row (their spend is real)
+                // but aren't prompt calls.
+                if r.function == "prompt" {
+                    prompt_count += 1;
+                }
                 // Count the call under its model even when the record carries
                 // no token usage (e.g. a locally-cache-served or zero-usage
                 // prompt) — otherwise the top-line "Prompt calls" and the
                 // per-model rows silently disagree.
-                let model = r
-                    .args
-                    .get("model")
-                    .and_then(|v| v.as_str())
+                let model = Some(r.priced_model())
+                    .filter(|m| !m.is_empty())
                     .unwrap_or("unknown")
                     .to_string();
                 let ms = per_model.entry(model.clone()).or_default();
@@ -4384,6 +4884,7 @@ fn cmd_serve(
     untrusted: bool,
     trusted: bool,
     app: Option<&Path>,
+    retention: server::RetentionPolicy,
 ) -> Result<()> {
     if verbose {
         // Isolate worker children read this to decide whether to print
@@ -4474,11 +4975,55 @@ fn cmd_serve(
         policy,
         policy_posture,
         app_manifest,
+        retention,
     ))?;
 
     Ok(())
 }
 
+fn cmd_mcp_serve(
+    dir: Option<&Path>,
+    transport: &str,
+    host: Option<&str>,
+    port: u16,
+    recipes: Option<&Path>,
+    untrusted: bool,
+    trusted: bool,
+) -> Result<()> {
+    let dir = dir
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."));
+    let providers = Arc::new(ProviderRegistry::from_env());
+    let template_engine = Arc::new(TemplateEngine::new(&dir));
+    let recipe_dir = recipes
+        .map(Path::to_path_buf)
+        .or_else(|| std::env::var("CHIDORI_RECIPE_DIR").ok().map(PathBuf::from));
+    let transport = match transport {
+        "http" => server::McpTransport::Http {
+            host: host
+                .map(str::to_owned)
+                .or_else(|| std::env::var("CHIDORI_HOST").ok())
+                .unwrap_or_else(|| "127.0.0.1".to_string()),
+            port,
+        },
+        _ => server::McpTransport::Stdio,
+    };
+    eprintln!("Agents: {}", dir.display());
+    eprintln!("Isolation: {}", crate::runtime::isolate::describe());
+
+    let (policy, policy_posture) = serve_policy(untrusted, trusted);
+    let tokio_rt = scheduler::new_tokio_runtime().context("Failed to create server runtime")?;
+    tokio_rt.block_on(server::mcp_serve(
+        providers,
+        template_engine,
+        dir,
+        recipe_dir,
+        transport,
+        policy,
+        policy_posture,
+    ))
+}
+
 /// Parse CLI input args into a JSON object.
 ///
 /// Supports:
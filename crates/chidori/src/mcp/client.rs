@@ -1,17 +1,20 @@
 //! Minimal stdio MCP client. Speaks JSON-RPC 2.0 over the child process's
 //! stdin/stdout. Implements only what the framework actually uses:
 //!
-//!   initialize        — handshake + server metadata
+//!   initialize        — handshake + server metadata and capabilities
 //!   tools/list        — discover tools at startup
 //!   tools/call        — invoke a tool on behalf of an agent
+//!   resources/*, prompts/* — generic requests, see [`McpClient::request_with`]
 //!
 //! Each request is written as a single line of JSON terminated by \n; the
 //! server's responses are read the same way. This matches the line-delimited
 //! framing used by the reference MCP servers. A single background reader task
-//! dispatches responses to in-flight requests by their integer id.
+//! dispatches responses to in-flight requests by their integer id, and hands
+//! requests the *server* sends (`sampling/createMessage`, `ping`) to the
+//! in-flight call that accepts them.
 
 use std::process::Stdio;
-use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use anyhow::{anyhow, Context, Result};
@@ -22,6 +25,13 @@ use tokio::process::{Child, ChildStdin};
 use tokio::sync::{oneshot, Mutex};
 
 use super::config::McpServerConfig;
+use super::{
+    answer_server_request, client_capabilities, jsonrpc_reply, ClientSignals, ServerCapabilities,
+    ServerRequestSender,
+};
+
+/// How long a request may go without a reply *or* server-request activity.
+const REQUEST_IDLE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RemoteTool {
@@ -36,11 +46,28 @@ fn default_schema() -> Value {
     json!({"type": "object", "properties": {}})
 }
 
+/// An in-flight call that accepts server requests. Stdio has no way to tie a
+/// server request to the call that provoked it, so requests go to the oldest
+/// such call — exact whenever a server has one call in flight at a time.
+struct RequestRoute {
+    id: i64,
+    sender: ServerRequestSender,
+    /// Bumped when a server request is forwarded to this call and again when
+    /// it is answered (odd: one in progress), so a long sampling round trip
+    /// doesn't read as a stalled server.
+    activity: Arc<AtomicUsize>,
+}
+
+type Routes = Arc<Mutex<Vec<RequestRoute>>>;
+
 pub struct McpClient {
-    stdin: Mutex<ChildStdin>,
+    stdin: Arc<Mutex<ChildStdin>>,
     next_id: AtomicI64,
     pending: Arc<Mutex<std::collections::HashMap<i64, oneshot::Sender<Value>>>>,
+    routes: Routes,
     tools: Vec<RemoteTool>,
+    capabilities: ServerCapabilities,
+    signals: Arc<ClientSignals>,
     /// Holding the child keeps the process alive for the life of the client.
     _child: Mutex<Child>,
 }
@@ -71,9 +98,15 @@ impl McpClient {
 
         let pending: Arc<Mutex<std::collections::HashMap<i64, oneshot::Sender<Value>>>> =
             Arc::new(Mutex::new(std::collections::HashMap::new()));
+        let stdin = Arc::new(Mutex::new(stdin));
+        let routes: Routes = Arc::new(Mutex::new(Vec::new()));
+        let signals = Arc::new(ClientSignals::default());
 
         // Reader task: parse one JSON object per line, dispatch by id.
         let reader_pending = pending.clone();
+        let reader_stdin = stdin.clone();
+        let reader_routes = routes.clone();
+        let reader_signals = signals.clone();
         tokio::spawn(async move {
             let mut lines = BufReader::new(stdout).lines();
             while let Ok(Some(line)) = lines.next_line().await {
@@ -85,35 +118,58 @@ impl McpClient {
                     tracing::warn!("MCP: dropping non-JSON line: {}", trimmed);
                     continue;
                 };
-                if let Some(id) = val.get("id").and_then(|v| v.as_i64()) {
+                // A request from the server carries both a method and an id
+                // (from the server's own id space, so it's checked first).
+                if let (Some(method), Some(id)) =
+                    (val.get("method").and_then(Value::as_str), val.get("id"))
+                {
+                    spawn_server_request_answer(
+                        method.to_string(),
+                        id.clone(),
+                        val.get("params").cloned().unwrap_or(Value::Null),
+                        &reader_routes,
+                        reader_stdin.clone(),
+                    )
+                    .await;
+                } else if let Some(id) = val.get("id").and_then(|v| v.as_i64()) {
                     if let Some(tx) = reader_pending.lock().await.remove(&id) {
                         let _ = tx.send(val);
                     }
                 } else {
-                    // Notification / log / progress — ignore for now.
+                    // Notification / log / progress: only a changed tool
+                    // list matters (to the supervisor).
+                    reader_signals.notification(&val);
                 }
             }
+            // stdout closed: the child exited. Fail the calls still waiting
+            // on it now rather than at their timeout, and tell the supervisor.
+            reader_pending.lock().await.clear();
+            reader_signals.close();
         });
 
         let client = Self {
-            stdin: Mutex::new(stdin),
+            stdin,
             next_id: AtomicI64::new(1),
             pending,
+            routes,
             tools: Vec::new(),
+            capabilities: ServerCapabilities::default(),
+            signals,
             _child: Mutex::new(child),
         };
 
         // Handshake.
-        let _init = client
+        let init = client
             .request(
                 "initialize",
                 json!({
                     "protocolVersion": "2024-11-05",
-                    "capabilities": {},
+                    "capabilities": client_capabilities(),
                     "clientInfo": { "name": "chidori", "version": env!("CARGO_PKG_VERSION") }
                 }),
             )
             .await?;
+        let capabilities = ServerCapabilities::from_initialize(&init);
         client
             .notification("notifications/initialized", Value::Null)
             .await?;
@@ -126,19 +182,64 @@ impl McpClient {
             .and_then(|t| serde_json::from_value(t.clone()).ok())
             .unwrap_or_default();
 
-        Ok(Self { tools, ..client })
+        Ok(Self {
+            tools,
+            capabilities,
+            ..client
+        })
     }
 
     pub fn tools(&self) -> &[RemoteTool] {
         &self.tools
     }
 
+    pub fn capabilities(&self) -> ServerCapabilities {
+        self.capabilities
+    }
+
+    pub fn signals(&self) -> &Arc<ClientSignals> {
+        &self.signals
+    }
+
     /// Send a request/response exchange. Blocks until the server replies.
     async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        self.request_with(method, params, None).await
+    }
+
+    /// [`Self::request`], routing any server requests that arrive while it is
+    /// in flight to `requests`. The timeout counts idle time: a call that is
+    /// busy answering the server's sampling requests is not stalled.
+    pub async fn request_with(
+        &self,
+        method: &str,
+        params: Value,
+        requests: Option<ServerRequestSender>,
+    ) -> Result<Value> {
         let id = self.next_id.fetch_add(1, Ordering::Relaxed);
-        let (tx, rx) = oneshot::channel();
+        let (tx, mut rx) = oneshot::channel();
         self.pending.lock().await.insert(id, tx);
+        let activity = Arc::new(AtomicUsize::new(0));
+        if let Some(sender) = requests {
+            self.routes.lock().await.push(RequestRoute {
+                id,
+                sender,
+                activity: activity.clone(),
+            });
+        }
+        let response = self.exchange(id, method, params, &mut rx, &activity).await;
+        self.routes.lock().await.retain(|route| route.id != id);
+        self.pending.lock().await.remove(&id);
+        response
+    }
 
+    async fn exchange(
+        &self,
+        id: i64,
+        method: &str,
+        params: Value,
+        rx: &mut oneshot::Receiver<Value>,
+        activity: &AtomicUsize,
+    ) -> Result<Value> {
         let payload = json!({
             "jsonrpc": "2.0",
             "id": id,
@@ -149,9 +250,23 @@ impl McpClient {
         line.push('\n');
         self.stdin.lock().await.write_all(line.as_bytes()).await?;
 
-        let response = tokio::time::timeout(std::time::Duration::from_secs(30), rx)
-            .await
-            .map_err(|_| anyhow!("MCP `{}` timed out", method))??;
+        let mut seen = activity.load(Ordering::Relaxed);
+        let response = loop {
+            match tokio::time::timeout(REQUEST_IDLE_TIMEOUT, &mut *rx).await {
+                Ok(response) => {
+                    break response
+                        .map_err(|_| anyhow!("MCP server exited before answering `{}`", method))?
+                }
+                Err(_) => {
+                    // An odd count means a server request is being answered.
+                    let now = activity.load(Ordering::Relaxed);
+                    if now == seen && now.is_multiple_of(2) {
+                        return Err(anyhow!("MCP `{}` timed out", method));
+                    }
+                    seen = now;
+                }
+            }
+        };
         if let Some(err) = response.get("error") {
             return Err(anyhow!("MCP `{}` error: {}", method, err));
         }
@@ -171,14 +286,20 @@ impl McpClient {
         Ok(())
     }
 
-    pub async fn call_tool(&self, name: &str, args: &Value) -> Result<Value> {
+    pub async fn call_tool(
+        &self,
+        name: &str,
+        args: &Value,
+        requests: Option<ServerRequestSender>,
+    ) -> Result<Value> {
         let resp = self
-            .request(
+            .request_with(
                 "tools/call",
                 json!({
                     "name": name,
                     "arguments": args,
                 }),
+                requests,
             )
             .await?;
         // MCP returns `{ content: [{type, text, ...}, ...] }`; collapse it to
@@ -199,3 +320,42 @@ impl McpClient {
         Ok(content)
     }
 }
+
+/// Answer a server request off the reader task (the answer may take a whole
+/// LLM round trip, and the reader must keep draining stdout meanwhile), then
+/// write the reply line back to the server.
+async fn spawn_server_request_answer(
+    method: String,
+    id: Value,
+    params: Value,
+    routes: &Routes,
+    stdin: Arc<Mutex<ChildStdin>>,
+) {
+    let route = {
+        let mut routes = routes.lock().await;
+        routes.retain(|route| !route.sender.is_closed());
+        routes
+            .first()
+            .map(|route| (route.sender.clone(), route.activity.clone()))
+    };
+    tokio::spawn(async move {
+        let (sender, activity) = match route {
+            Some((sender, activity)) => (Some(sender), Some(activity)),
+            None => (None, None),
+        };
+        if let Some(activity) = &activity {
+            activity.fetch_add(1, Ordering::Relaxed);
+        }
+        let answer = answer_server_request(&method, params, sender.as_ref()).await;
+        if let Some(activity) = &activity {
+            activity.fetch_add(1, Ordering::Relaxed);
+        }
+        let Ok(mut line) = serde_json::to_string(&jsonrpc_reply(id, answer)) else {
+            return;
+        };
+        line.push('\n');
+        if let Err(err) = stdin.lock().await.write_all(line.as_bytes()).await {
+            tracing::warn!("MCP: failed to answer server `{}`: {}", method, err);
+        }
+    });
+}
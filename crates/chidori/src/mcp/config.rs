@@ -28,6 +28,7 @@
 //! server's host. See app-agent-builder docs/design/mcp-http-transport-chidori.md.
 
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
@@ -42,7 +43,7 @@ pub enum McpTransport {
     Http,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct McpServerConfig {
     /// stdio transport: the executable to spawn. Empty for http servers.
@@ -101,13 +102,23 @@ pub struct McpServersConfig {
 
 impl McpServersConfig {
     pub fn load_from_env() -> Result<Self> {
-        let Ok(path) = std::env::var("CHIDORI_MCP_CONFIG") else {
-            return Ok(Self::default());
-        };
-        let text = std::fs::read_to_string(&path)
-            .with_context(|| format!("reading CHIDORI_MCP_CONFIG at {}", path))?;
+        match Self::path_from_env() {
+            Some(path) => Self::load(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// The config file named by `CHIDORI_MCP_CONFIG`, if any — also the file
+    /// `chidori serve` watches for hot reload.
+    pub fn path_from_env() -> Option<PathBuf> {
+        std::env::var("CHIDORI_MCP_CONFIG").ok().map(PathBuf::from)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("reading CHIDORI_MCP_CONFIG at {}", path.display()))?;
         let cfg: McpServersConfig = serde_json::from_str(&text)
-            .with_context(|| format!("parsing MCP config at {}", path))?;
+            .with_context(|| format!("parsing MCP config at {}", path.display()))?;
         Ok(cfg)
     }
 }
@@ -3,7 +3,18 @@
 //! — `tools()` + `call_tool()` — but speaks JSON-RPC 2.0 over HTTP POST instead
 //! of a child's stdin/stdout. Only the subset the framework uses is
 //! implemented: `initialize` / `notifications/initialized` / `tools/list` /
-//! `tools/call`.
+//! `tools/call`, plus the generic requests behind resources and prompts.
+//!
+//! A server that needs something from the client mid-call (sampling) sends the
+//! request on the SSE stream answering our POST; the stream is read
+//! incrementally so the request can be answered — POSTed back as its own
+//! JSON-RPC response — before the server finishes the original call.
+//!
+//! Once connected, the client also opens the standalone GET stream, where a
+//! server announces changes between calls (`notifications/tools/list_changed`
+//! wakes the supervisor). A server that answers the GET with anything but an
+//! SSE stream (405 is the spec's "not offered") is left to announce changes
+//! on call responses only; the supervisor's ping picks those up.
 //!
 //! Two cross-repo contracts make this safe (mcp-http-transport-chidori.md §3):
 //!
@@ -18,11 +29,17 @@
 //!   (not an opaque string) so agent-builder's broker can trigger step-up
 //!   re-auth or report the missing scope during generation.
 
+use std::sync::Arc;
+
 use anyhow::{anyhow, Context, Result};
 use serde_json::{json, Value};
 
 use super::client::RemoteTool;
 use super::config::McpServerConfig;
+use super::{
+    answer_server_request, client_capabilities, jsonrpc_reply, ClientSignals, ServerCapabilities,
+    ServerRequestSender,
+};
 
 /// Pinned protocol version for the HTTP transport. Newer than the stdio
 /// client's `2024-11-05`; 2025-03-26 is the revision that introduced Streamable
@@ -41,6 +58,18 @@ pub struct McpHttpClient {
     /// later request via `Mcp-Session-Id`.
     session_id: tokio::sync::Mutex<Option<String>>,
     tools: Vec<RemoteTool>,
+    capabilities: ServerCapabilities,
+    signals: Arc<ClientSignals>,
+    /// The task reading the standalone GET stream; stopped on drop.
+    listener: Option<tokio::task::AbortHandle>,
+}
+
+impl Drop for McpHttpClient {
+    fn drop(&mut self) {
+        if let Some(listener) = &self.listener {
+            listener.abort();
+        }
+    }
 }
 
 impl McpHttpClient {
@@ -86,18 +115,23 @@ impl McpHttpClient {
             auth_header,
             session_id: tokio::sync::Mutex::new(None),
             tools: Vec::new(),
+            capabilities: ServerCapabilities::default(),
+            signals: Arc::new(ClientSignals::default()),
+            listener: None,
         };
 
         // Handshake.
-        this.post_rpc(
-            "initialize",
-            json!({
-                "protocolVersion": PROTOCOL_VERSION,
-                "capabilities": {},
-                "clientInfo": { "name": "chidori", "version": env!("CARGO_PKG_VERSION") }
-            }),
-        )
-        .await?;
+        let init = this
+            .post_rpc(
+                "initialize",
+                json!({
+                    "protocolVersion": PROTOCOL_VERSION,
+                    "capabilities": client_capabilities(),
+                    "clientInfo": { "name": "chidori", "version": env!("CARGO_PKG_VERSION") }
+                }),
+            )
+            .await?;
+        this.capabilities = ServerCapabilities::from_initialize(&init);
         this.post_notification("notifications/initialized", Value::Null)
             .await?;
 
@@ -109,6 +143,17 @@ impl McpHttpClient {
             .and_then(|t| serde_json::from_value(t.clone()).ok())
             .unwrap_or_default();
 
+        let session = this.session_id.lock().await.clone();
+        let get = this.apply_headers(this.client.get(&this.url), &session);
+        this.listener = Some(
+            tokio::spawn(listen_for_notifications(
+                get,
+                this.server_id.clone(),
+                this.signals.clone(),
+            ))
+            .abort_handle(),
+        );
+
         Ok(this)
     }
 
@@ -116,9 +161,26 @@ impl McpHttpClient {
         &self.tools
     }
 
-    pub async fn call_tool(&self, name: &str, args: &Value) -> Result<Value> {
+    pub fn capabilities(&self) -> ServerCapabilities {
+        self.capabilities
+    }
+
+    pub fn signals(&self) -> &Arc<ClientSignals> {
+        &self.signals
+    }
+
+    pub async fn call_tool(
+        &self,
+        name: &str,
+        args: &Value,
+        requests: Option<ServerRequestSender>,
+    ) -> Result<Value> {
         let resp = self
-            .post_rpc("tools/call", json!({ "name": name, "arguments": args }))
+            .post_rpc_with(
+                "tools/call",
+                json!({ "name": name, "arguments": args }),
+                requests.as_ref(),
+            )
             .await?;
         // Collapse `{ content: [{type, text}, ...] }` to a single value for
         // agent ergonomics — identical to the stdio client.
@@ -162,6 +224,17 @@ impl McpHttpClient {
     /// whole `{ jsonrpc, id, result|error }`). Surfaces `401`/`403` as a
     /// structured `mcpError`; a JSON-RPC `error` member becomes a normal error.
     async fn post_rpc(&self, method: &str, params: Value) -> Result<Value> {
+        self.post_rpc_with(method, params, None).await
+    }
+
+    /// [`Self::post_rpc`], answering server requests that arrive on the
+    /// response stream through `requests`.
+    pub async fn post_rpc_with(
+        &self,
+        method: &str,
+        params: Value,
+        requests: Option<&ServerRequestSender>,
+    ) -> Result<Value> {
         let id = 1;
         let payload = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
         let session = self.session_id.lock().await.clone();
@@ -191,6 +264,11 @@ impl McpHttpClient {
                 .map(str::to_owned);
             return Err(self.scope_error(status.as_u16(), www));
         }
+        // Any other failure status is a transport failure, never an empty
+        // (successful) result — the supervisor's ping relies on this.
+        if !status.is_success() {
+            return Err(self.transport_error(method, &format!("HTTP {status}")));
+        }
 
         let content_type = resp
             .headers()
@@ -198,15 +276,19 @@ impl McpHttpClient {
             .and_then(|v| v.to_str().ok())
             .unwrap_or("")
             .to_string();
+        if content_type.contains("text/event-stream") {
+            let response = self.read_sse_response(resp, id, method, requests).await?;
+            if let Some(err) = response.get("error") {
+                return Err(anyhow!("MCP `{}` error: {}", method, err));
+            }
+            return Ok(response);
+        }
         let text = resp
             .text()
             .await
             .map_err(|err| self.transport_error(method, &err.to_string()))?;
 
-        let response = if content_type.contains("text/event-stream") {
-            parse_sse_response(&text, id)
-                .ok_or_else(|| self.transport_error(method, "no JSON-RPC response in SSE stream"))?
-        } else if text.trim().is_empty() {
+        let response = if text.trim().is_empty() {
             // A notification-only POST can legitimately return 202 with no body.
             json!({ "jsonrpc": "2.0", "id": id, "result": {} })
         } else {
@@ -221,16 +303,79 @@ impl McpHttpClient {
         Ok(response)
     }
 
+    /// Read an SSE response stream event by event until the response to
+    /// request `want_id` arrives, answering server requests on the way.
+    /// Servers may interleave notifications before the response; when none
+    /// carries our id, the last response-shaped message wins.
+    async fn read_sse_response(
+        &self,
+        mut resp: reqwest::Response,
+        want_id: i64,
+        method: &str,
+        requests: Option<&ServerRequestSender>,
+    ) -> Result<Value> {
+        let mut events = SseBuffer::default();
+        let mut response = ResponseMatch::default();
+        loop {
+            let chunk = resp
+                .chunk()
+                .await
+                .map_err(|err| self.transport_error(method, &err.to_string()))?;
+            let done = chunk.is_none();
+            let messages = match chunk {
+                Some(bytes) => events.feed(&bytes),
+                None => events.finish(),
+            };
+            for message in messages {
+                if let (Some(server_method), Some(id)) = (
+                    message.get("method").and_then(Value::as_str),
+                    message.get("id"),
+                ) {
+                    let params = message.get("params").cloned().unwrap_or(Value::Null);
+                    let answer = answer_server_request(server_method, params, requests).await;
+                    self.post_message(jsonrpc_reply(id.clone(), answer)).await;
+                    continue;
+                }
+                if message.get("result").is_none() && message.get("error").is_none() {
+                    self.signals.notification(&message);
+                    continue;
+                }
+                if let Some(matched) = response.offer(message, want_id) {
+                    return Ok(matched);
+                }
+            }
+            if done {
+                break;
+            }
+        }
+        response
+            .finish()
+            .ok_or_else(|| self.transport_error(method, "no JSON-RPC response in SSE stream"))
+    }
+
     /// Fire-and-forget notification (no response correlation needed).
     async fn post_notification(&self, method: &str, params: Value) -> Result<()> {
         let payload = json!({ "jsonrpc": "2.0", "method": method, "params": params });
-        let session = self.session_id.lock().await.clone();
-        let req = self.apply_headers(self.client.post(&self.url).json(&payload), &session);
         // Best-effort: a notification failure shouldn't abort the handshake.
-        let _ = req.send().await;
+        self.post_message(payload).await;
         Ok(())
     }
 
+    /// POST a message that expects no response body (a notification, or our
+    /// reply to a server request). Failures are logged, not raised: the
+    /// server notices a missing reply on its own timeout.
+    async fn post_message(&self, payload: Value) {
+        let session = self.session_id.lock().await.clone();
+        let req = self.apply_headers(self.client.post(&self.url).json(&payload), &session);
+        if let Err(err) = req.send().await {
+            tracing::warn!(
+                "MCP `{}`: posting a message failed: {}",
+                self.server_id,
+                err
+            );
+        }
+    }
+
     /// Structured `401`/`403` scope challenge (mcp-http-transport-chidori.md
     /// §3.3), serialized as the error message so it rides the existing
     /// tool-error channel and agent-builder can parse it.
@@ -263,6 +408,40 @@ impl McpHttpClient {
     }
 }
 
+/// Read the standalone GET stream, passing each server notification to
+/// `signals`. Ends quietly when the server does not offer the stream or
+/// closes it. Server requests cannot be answered here — they belong to a
+/// call — so they are dropped.
+async fn listen_for_notifications(
+    get: reqwest::RequestBuilder,
+    server_id: String,
+    signals: Arc<ClientSignals>,
+) {
+    let mut resp = match get.send().await {
+        Ok(resp) => resp,
+        Err(err) => {
+            tracing::debug!("MCP `{server_id}`: opening the notification stream failed: {err}");
+            return;
+        }
+    };
+    let is_stream = resp
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("text/event-stream"));
+    if !resp.status().is_success() || !is_stream {
+        return;
+    }
+    let mut events = SseBuffer::default();
+    while let Ok(Some(bytes)) = resp.chunk().await {
+        for message in events.feed(&bytes) {
+            if message.get("id").is_none() {
+                signals.notification(&message);
+            }
+        }
+    }
+}
+
 /// Pull `scope="a b c"` out of a `WWW-Authenticate` challenge, if present.
 fn parse_required_scopes(www_authenticate: &str) -> Option<Vec<String>> {
     let idx = www_authenticate.find("scope=")?;
@@ -277,27 +456,80 @@ fn parse_required_scopes(www_authenticate: &str) -> Option<Vec<String>> {
     }
 }
 
-/// Find the JSON-RPC response with `id == want_id` in an SSE stream body. Each
-/// event's `data:` payload is a JSON-RPC message; we pick the one correlating
-/// to our request (servers may interleave notifications before the response).
-fn parse_sse_response(body: &str, want_id: i64) -> Option<Value> {
-    let mut last_with_result: Option<Value> = None;
-    for line in body.lines() {
-        let line = line.trim_start();
-        let Some(data) = line.strip_prefix("data:") else {
-            continue;
-        };
-        let Ok(value) = serde_json::from_str::<Value>(data.trim()) else {
-            continue;
-        };
-        if value.get("id").and_then(Value::as_i64) == Some(want_id) {
-            return Some(value);
+/// Correlates SSE messages to our request: the JSON-RPC response with
+/// `id == want_id` wins; failing that, the last message carrying a result or
+/// error (servers may interleave notifications before the response).
+#[derive(Default)]
+struct ResponseMatch {
+    last_with_result: Option<Value>,
+}
+
+impl ResponseMatch {
+    /// Returns the message once it correlates to `want_id`.
+    fn offer(&mut self, message: Value, want_id: i64) -> Option<Value> {
+        if message.get("result").is_none() && message.get("error").is_none() {
+            return None;
+        }
+        if message.get("id").and_then(Value::as_i64) == Some(want_id) {
+            return Some(message);
+        }
+        self.last_with_result = Some(message);
+        None
+    }
+
+    fn finish(self) -> Option<Value> {
+        self.last_with_result
+    }
+}
+
+/// Incremental SSE decoder: bytes in, one JSON-RPC message out per complete
+/// event. Buffers bytes (not text) so a UTF-8 sequence split across chunks
+/// survives, and joins multi-line `data:` fields per the SSE spec.
+#[derive(Default)]
+struct SseBuffer {
+    pending: Vec<u8>,
+    data: Vec<String>,
+}
+
+impl SseBuffer {
+    fn feed(&mut self, bytes: &[u8]) -> Vec<Value> {
+        self.pending.extend_from_slice(bytes);
+        let mut messages = Vec::new();
+        while let Some(newline) = self.pending.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=newline).collect();
+            let line = String::from_utf8_lossy(&line);
+            self.line(line.trim_end_matches(['\r', '\n']), &mut messages);
         }
-        if value.get("result").is_some() || value.get("error").is_some() {
-            last_with_result = Some(value);
+        messages
+    }
+
+    /// End of stream: flush a trailing event that had no closing blank line.
+    fn finish(&mut self) -> Vec<Value> {
+        let mut messages = Vec::new();
+        if !self.pending.is_empty() {
+            let rest = std::mem::take(&mut self.pending);
+            let rest = String::from_utf8_lossy(&rest);
+            self.line(rest.trim_end_matches('\r'), &mut messages);
+        }
+        self.line("", &mut messages);
+        messages
+    }
+
+    fn line(&mut self, line: &str, messages: &mut Vec<Value>) {
+        if line.is_empty() {
+            if !self.data.is_empty() {
+                let data = std::mem::take(&mut self.data).join("\n");
+                if let Ok(value) = serde_json::from_str::<Value>(&data) {
+                    messages.push(value);
+                }
+            }
+            return;
+        }
+        if let Some(data) = line.strip_prefix("data:") {
+            self.data
+                .push(data.strip_prefix(' ').unwrap_or(data).to_string());
         }
     }
-    last_with_result
 }
 
 #[cfg(test)]
@@ -320,14 +552,43 @@ mod tests {
 
     #[test]
     fn sse_picks_matching_id() {
-        let body = "event: message\ndata: {\"jsonrpc\":\"2.0\",\"method\":\"x\"}\n\nevent: message\ndata: {\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{\"ok\":true}}\n\n";
-        let v = parse_sse_response(body, 1).unwrap();
-        assert_eq!(v["result"]["ok"], true);
+        let body = "event: message\ndata: {\"jsonrpc\":\"2.0\",\"id\":7,\"result\":{\"ok\":false}}\n\nevent: message\ndata: {\"jsonrpc\":\"2.0\",\"method\":\"x\"}\n\nevent: message\ndata: {\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{\"ok\":true}}\n\n";
+        let mut buffer = SseBuffer::default();
+        let mut response = ResponseMatch::default();
+        let matched = buffer
+            .feed(body.as_bytes())
+            .into_iter()
+            .find_map(|message| response.offer(message, 1))
+            .unwrap();
+        assert_eq!(matched["result"]["ok"], true);
+
+        // No id match: fall back to the last message with a result.
+        let mut response = ResponseMatch::default();
+        for message in SseBuffer::default().feed(body.as_bytes()) {
+            assert!(response.offer(message, 99).is_none());
+        }
+        assert_eq!(response.finish().unwrap()["id"], 1);
+    }
+
+    #[test]
+    fn sse_buffer_yields_each_event_across_chunk_boundaries() {
+        let body = "event: message\ndata: {\"jsonrpc\":\"2.0\",\"method\":\"x\"}\n\nevent: message\ndata: {\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{\"ok\":\"h\u{e9}\"}}\n\n";
+        let mut buffer = SseBuffer::default();
+        let mut messages = Vec::new();
+        // Split mid-line and mid-codepoint.
+        let split = body.find('\u{e9}').unwrap() + 1;
+        messages.extend(buffer.feed(&body.as_bytes()[..split]));
+        messages.extend(buffer.feed(&body.as_bytes()[split..]));
+        messages.extend(buffer.finish());
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0]["method"], "x");
+        assert_eq!(messages[1]["result"]["ok"], "h\u{e9}");
     }
 
     /// A minimal JSON-RPC-over-HTTP MCP server: one request per connection
     /// (`Connection: close`, so reqwest reconnects), routed by JSON-RPC method.
     /// Replies to initialize / tools/list / tools/call; ignores notifications.
+    /// A GET opens the standalone stream, which carries one `list_changed`.
     fn spawn_mock_mcp_server() -> std::net::SocketAddr {
         use std::io::{Read, Write};
         let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
@@ -338,6 +599,18 @@ mod tests {
                 let mut buf = [0u8; 8192];
                 let n = stream.read(&mut buf).unwrap_or(0);
                 let req = String::from_utf8_lossy(&buf[..n]);
+                if req.starts_with("GET ") {
+                    // The standalone stream: announce a changed tool list.
+                    let notice =
+                        json!({"jsonrpc": "2.0", "method": "notifications/tools/list_changed"});
+                    let events = format!("data: {notice}\n\n");
+                    let resp = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        events.len(), events,
+                    );
+                    let _ = stream.write_all(resp.as_bytes());
+                    continue;
+                }
                 let body = req.split("\r\n\r\n").nth(1).unwrap_or("");
                 let parsed: serde_json::Value = serde_json::from_str(body).unwrap_or(Value::Null);
                 let method = parsed.get("method").and_then(Value::as_str).unwrap_or("");
@@ -387,11 +660,21 @@ mod tests {
             assert_eq!(client.tools().len(), 1);
             assert_eq!(client.tools()[0].name, "echo");
             let out = client
-                .call_tool("echo", &json!({"text": "hi"}))
+                .call_tool("echo", &json!({"text": "hi"}), None)
                 .await
                 .unwrap();
             // Single text content block collapses to a string.
             assert_eq!(out, Value::String("echoed!".to_string()));
+
+            // The standalone stream's notification reaches the signals.
+            let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(5);
+            while !client.signals().take_tools_changed() {
+                assert!(
+                    tokio::time::Instant::now() < deadline,
+                    "no list_changed seen"
+                );
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
         });
     }
 }
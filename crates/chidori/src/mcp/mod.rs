@@ -3,29 +3,186 @@
 //! Spawns MCP servers as child processes, speaks JSON-RPC 2.0 over stdio,
 //! discovers their tools at startup, and exposes them through the framework's
 //! ToolRegistry so agents can invoke them via `tool("name", ...)` or expose
-//! them to the LLM via `prompt(tools=[...])`.
+//! them to the LLM via `prompt(tools=[...])`. Long-lived hosts also supervise
+//! them — restart, ping, tool-list refresh, config reload (`supervisor.rs`).
+//!
+//! Beyond tools, servers that advertise them expose `resources/*` and
+//! `prompts/*` (surfaced to agents as `chidori.mcp.*`), and may call back into
+//! the client with `sampling/createMessage` while a call is in flight — those
+//! server requests are handed to the call's owner (see [`ServerRequest`]),
+//! which answers them through the run's own providers.
 //!
 //! Wire protocol is hand-rolled rather than pulling in a full MCP SDK: the
-//! subset we need (initialize / tools/list / tools/call) is small and the
-//! SDK surface would add a large dependency.
+//! subset we need (initialize / tools / resources / prompts / sampling) is
+//! small and the SDK surface would add a large dependency.
 
 pub mod client;
 pub mod config;
 pub mod http_client;
+mod supervisor;
 
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex as StdMutex, OnceLock};
 
-use anyhow::Result;
-use serde_json::Value;
-use tokio::sync::Mutex;
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tokio::sync::{mpsc, oneshot, Notify};
 
 pub use client::{McpClient, RemoteTool};
-pub use config::{McpServersConfig, McpTransport};
+pub use config::{McpServerConfig, McpServersConfig, McpTransport};
 pub use http_client::McpHttpClient;
+pub use supervisor::SupervisorSettings;
 
 use crate::tools::{ToolDef, ToolParam};
 
+/// The capabilities a server advertised in its `initialize` result. Only the
+/// ones the client acts on are kept; tools are assumed (`tools/list` is always
+/// tried).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ServerCapabilities {
+    pub resources: bool,
+    pub prompts: bool,
+}
+
+impl ServerCapabilities {
+    fn from_initialize(response: &Value) -> Self {
+        let caps = response
+            .get("result")
+            .and_then(|r| r.get("capabilities"))
+            .unwrap_or(&Value::Null);
+        Self {
+            resources: caps.get("resources").is_some_and(Value::is_object),
+            prompts: caps.get("prompts").is_some_and(Value::is_object),
+        }
+    }
+}
+
+/// What the client advertises in `initialize`: it can answer
+/// `sampling/createMessage` (through the calling run's providers).
+fn client_capabilities() -> Value {
+    json!({ "sampling": {} })
+}
+
+/// What a client's transport noticed on its own between the supervisor's
+/// pings: the server announced a changed tool list, or (stdio) the child's
+/// stdout closed because it exited. Either wakes the server's supervisor.
+#[derive(Default)]
+pub struct ClientSignals {
+    tools_changed: AtomicBool,
+    closed: AtomicBool,
+    wake: Notify,
+}
+
+impl ClientSignals {
+    /// Note a server notification; only `notifications/tools/list_changed`
+    /// is acted on.
+    fn notification(&self, message: &Value) {
+        if message.get("method").and_then(Value::as_str) == Some("notifications/tools/list_changed")
+        {
+            self.tools_changed.store(true, Ordering::Relaxed);
+            self.wake.notify_one();
+        }
+    }
+
+    fn close(&self) {
+        self.closed.store(true, Ordering::Relaxed);
+        self.wake.notify_one();
+    }
+
+    fn take_tools_changed(&self) -> bool {
+        self.tools_changed.swap(false, Ordering::Relaxed)
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Relaxed)
+    }
+}
+
+/// A JSON-RPC request the *server* sent while one of our calls was in flight
+/// (today: `sampling/createMessage`). The transport forwards it to whoever
+/// owns the call and writes `reply`'s answer back to the server. An `Err`
+/// becomes a JSON-RPC error response.
+pub struct ServerRequest {
+    pub method: String,
+    pub params: Value,
+    pub reply: oneshot::Sender<std::result::Result<Value, String>>,
+}
+
+/// Where a call's server requests are delivered. Calls made without one
+/// (startup discovery, a bare `call_tool`) answer server requests with
+/// "method not found".
+pub type ServerRequestSender = mpsc::UnboundedSender<ServerRequest>;
+
+/// Answer one server-initiated request: `ping` inline, everything else by the
+/// call's owner. Shared by both transports so they reply identically.
+async fn answer_server_request(
+    method: &str,
+    params: Value,
+    requests: Option<&ServerRequestSender>,
+) -> std::result::Result<Value, (i64, String)> {
+    if method == "ping" {
+        return Ok(json!({}));
+    }
+    let Some(requests) = requests else {
+        return Err((
+            -32601,
+            format!("`{method}` is only answered while a run is waiting on this server"),
+        ));
+    };
+    let (reply, answer) = oneshot::channel();
+    requests
+        .send(ServerRequest {
+            method: method.to_string(),
+            params,
+            reply,
+        })
+        .map_err(|_| (-32603, format!("`{method}`: the calling run went away")))?;
+    match answer.await {
+        Ok(Ok(result)) => Ok(result),
+        Ok(Err(message)) => Err((-32603, message)),
+        Err(_) => Err((-32603, format!("`{method}`: the calling run went away"))),
+    }
+}
+
+/// The JSON-RPC response to request `id`: the client's answer to a server
+/// request here, and `mcp-serve`'s answer to a client's.
+pub(crate) fn jsonrpc_reply(id: Value, answer: std::result::Result<Value, (i64, String)>) -> Value {
+    match answer {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": code, "message": message },
+        }),
+    }
+}
+
+/// Run an async MCP call to completion on `rt` while answering the server
+/// requests it raises with `on_request` **on the calling thread**, between
+/// polls. That thread is free to `block_on` the same runtime (a sampling
+/// answer is a whole provider round trip) and to write to the run's journal,
+/// so whatever it records nests under the call that is in flight.
+pub fn block_on_with_requests<Fut>(
+    rt: &tokio::runtime::Runtime,
+    call: impl FnOnce(ServerRequestSender) -> Fut,
+    mut on_request: impl FnMut(&str, Value) -> std::result::Result<Value, String>,
+) -> Result<Value>
+where
+    Fut: std::future::Future<Output = Result<Value>> + Send + 'static,
+{
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let task = rt.spawn(call(tx));
+    // The channel closes once the call finishes and drops every sender.
+    while let Some(request) = rt.block_on(rx.recv()) {
+        let answer = on_request(&request.method, request.params);
+        let _ = request.reply.send(answer);
+    }
+    rt.block_on(task)
+        .map_err(|err| anyhow!("MCP call task failed: {err}"))?
+}
+
 /// One connected MCP server, behind a single interface regardless of transport.
 /// An stdio server is a spawned child; an http server is a remote endpoint. The
 /// `<server_id>__<tool>` naming, `ToolBackend::Mcp` dispatch, and the catalog
@@ -43,32 +200,144 @@ impl McpClientHandle {
         }
     }
 
-    pub async fn call_tool(&self, name: &str, args: &Value) -> Result<Value> {
+    pub fn capabilities(&self) -> ServerCapabilities {
+        match self {
+            McpClientHandle::Stdio(c) => c.capabilities(),
+            McpClientHandle::Http(c) => c.capabilities(),
+        }
+    }
+
+    pub fn signals(&self) -> &Arc<ClientSignals> {
+        match self {
+            McpClientHandle::Stdio(c) => c.signals(),
+            McpClientHandle::Http(c) => c.signals(),
+        }
+    }
+
+    /// `ping`: the supervisor's liveness probe.
+    async fn ping(&self) -> Result<()> {
+        self.rpc("ping", json!({}), None).await.map(|_| ())
+    }
+
+    pub async fn call_tool(
+        &self,
+        name: &str,
+        args: &Value,
+        requests: Option<ServerRequestSender>,
+    ) -> Result<Value> {
         match self {
-            McpClientHandle::Stdio(c) => c.call_tool(name, args).await,
-            McpClientHandle::Http(c) => c.call_tool(name, args).await,
+            McpClientHandle::Stdio(c) => c.call_tool(name, args, requests).await,
+            McpClientHandle::Http(c) => c.call_tool(name, args, requests).await,
+        }
+    }
+
+    /// One JSON-RPC exchange; returns the response's `result` member.
+    async fn rpc(
+        &self,
+        method: &str,
+        params: Value,
+        requests: Option<ServerRequestSender>,
+    ) -> Result<Value> {
+        let response = match self {
+            McpClientHandle::Stdio(c) => c.request_with(method, params, requests).await?,
+            McpClientHandle::Http(c) => c.post_rpc_with(method, params, requests.as_ref()).await?,
+        };
+        Ok(response.get("result").cloned().unwrap_or(Value::Null))
+    }
+
+    /// Walk a paginated `*/list` method to the end, concatenating `field`.
+    async fn list_all(&self, method: &str, field: &str) -> Result<Value> {
+        let mut items = Vec::new();
+        let mut cursor: Option<Value> = None;
+        // Bounded so a server that keeps returning a cursor can't hang a run.
+        for _ in 0..MAX_LIST_PAGES {
+            let params = match cursor.take() {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),
+            };
+            let page = self.rpc(method, params, None).await?;
+            if let Some(Value::Array(page_items)) = page.get(field) {
+                items.extend(page_items.iter().cloned());
+            }
+            match page.get("nextCursor").filter(|c| !c.is_null()) {
+                Some(next) => cursor = Some(next.clone()),
+                None => break,
+            }
         }
+        Ok(Value::Array(items))
     }
 }
 
+/// Upper bound on `nextCursor` pages followed by one list call.
+const MAX_LIST_PAGES: usize = 100;
+
 /// Runtime manager for all connected MCP servers. Shared across all agent
 /// runs via `HostState.mcp`. Calls are dispatched by `server_id`.
+///
+/// The manager also owns the servers' tool catalog: [`Self::tool_defs`] is
+/// read whenever a run builds its ToolRegistry, so a server restarted or
+/// re-listed by the supervisor (see [`Self::supervise`]) is picked up by the
+/// next run without touching the ones in flight.
 pub struct McpManager {
-    servers: Mutex<HashMap<String, Arc<McpClientHandle>>>,
+    servers: Servers,
+    /// Bumped for every (re)configured server; a supervisor loop exits once
+    /// its server's entry carries a newer generation or is gone.
+    next_generation: AtomicU64,
+    /// Set by [`Self::supervise`]; servers started afterwards (hot reload)
+    /// get a supervisor loop of their own.
+    supervision: OnceLock<SupervisorSettings>,
+}
+
+/// Shared with the supervisor loops, which outlive any one borrow of the
+/// manager.
+type Servers = Arc<StdMutex<HashMap<String, ServerEntry>>>;
+
+/// One configured server: its connection when up, and what `/health` shows.
+struct ServerEntry {
+    config: McpServerConfig,
+    generation: u64,
+    handle: Option<Arc<McpClientHandle>>,
+    tools: Vec<ToolDef>,
+    restarts: u32,
+    last_error: Option<String>,
+}
+
+/// Whether a configured server is currently connected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum McpServerState {
+    Running,
+    /// Failed to start, crashed, or stopped answering pings. A supervised
+    /// server is being restarted with backoff.
+    Down,
+}
+
+/// Per-server status, as reported on the server's `/health`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpServerStatus {
+    pub state: McpServerState,
+    pub transport: McpTransport,
+    pub tools: usize,
+    pub restarts: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
 }
 
 impl McpManager {
     pub fn new() -> Self {
         Self {
-            servers: Mutex::new(HashMap::new()),
+            servers: Arc::new(StdMutex::new(HashMap::new())),
+            next_generation: AtomicU64::new(1),
+            supervision: OnceLock::new(),
         }
     }
 
     /// Load MCP server config and start every enabled server. Returns the
     /// combined list of ToolDefs — one per remote tool — so the caller can
-    /// merge them into the ToolRegistry.
+    /// merge them into the ToolRegistry. A server that fails to start is kept
+    /// (down) so `/health` reports it and a supervisor can retry it.
     pub async fn start_from_config(&self, cfg: &McpServersConfig) -> Result<Vec<ToolDef>> {
-        let mut defs = Vec::new();
         for (id, server) in &cfg.servers {
             if !server.enabled {
                 continue;
@@ -77,46 +346,164 @@ impl McpManager {
                 tracing::warn!("MCP server `{}` skipped: {}", id, reason);
                 continue;
             }
-            // stdio spawns a child; http connects to a remote endpoint. Both
-            // yield the same `McpClientHandle` and the same ToolDefs.
-            let handle = match server.transport {
-                McpTransport::Stdio => McpClient::spawn(server.clone())
-                    .await
-                    .map(McpClientHandle::Stdio),
-                McpTransport::Http => McpHttpClient::connect(id, server.clone())
-                    .await
-                    .map(McpClientHandle::Http),
-            };
-            match handle {
-                Ok(handle) => {
-                    let handle = Arc::new(handle);
-                    for remote in handle.tools().iter() {
-                        defs.push(tool_def_for(id, remote));
-                    }
-                    self.servers.lock().await.insert(id.clone(), handle);
-                }
-                Err(e) => {
-                    tracing::warn!("MCP server `{}` failed to start: {}", id, e);
-                }
-            }
+            self.start_server(id, server.clone()).await;
         }
-        Ok(defs)
+        Ok(self.tool_defs())
+    }
+
+    /// Connect one server and (re)place its entry under a fresh generation.
+    async fn start_server(&self, id: &str, config: McpServerConfig) {
+        let generation = self.next_generation.fetch_add(1, Ordering::Relaxed);
+        let (handle, tools, last_error) = match connect(id, &config).await {
+            Ok(handle) => {
+                let tools = handle
+                    .tools()
+                    .iter()
+                    .map(|remote| tool_def_for(id, remote))
+                    .collect();
+                (Some(Arc::new(handle)), tools, None)
+            }
+            Err(e) => {
+                tracing::warn!("MCP server `{}` failed to start: {}", id, e);
+                (None, Vec::new(), Some(e.to_string()))
+            }
+        };
+        self.servers.lock().unwrap().insert(
+            id.to_string(),
+            ServerEntry {
+                config,
+                generation,
+                handle,
+                tools,
+                restarts: 0,
+                last_error,
+            },
+        );
+        self.spawn_supervisor(id, generation);
+    }
+
+    /// Every connected server's tools, ordered by server id so the catalog
+    /// (and the prompts built from it) is stable across calls.
+    pub fn tool_defs(&self) -> Vec<ToolDef> {
+        let servers = self.servers.lock().unwrap();
+        let mut ids: Vec<&String> = servers.keys().collect();
+        ids.sort();
+        ids.into_iter()
+            .flat_map(|id| servers[id].tools.iter().cloned())
+            .collect()
+    }
+
+    /// Status of every configured server, keyed by server id.
+    pub fn status(&self) -> std::collections::BTreeMap<String, McpServerStatus> {
+        self.servers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, entry)| {
+                let status = McpServerStatus {
+                    state: if entry.handle.is_some() {
+                        McpServerState::Running
+                    } else {
+                        McpServerState::Down
+                    },
+                    transport: entry.config.transport,
+                    tools: entry.tools.len(),
+                    restarts: entry.restarts,
+                    last_error: entry.last_error.clone(),
+                };
+                (id.clone(), status)
+            })
+            .collect()
     }
 
-    /// Invoke `tools/call` on a previously registered server.
+    fn client(&self, server_id: &str) -> Result<Arc<McpClientHandle>> {
+        let map = self.servers.lock().unwrap();
+        let entry = map
+            .get(server_id)
+            .ok_or_else(|| anyhow!("MCP server `{}` is not connected", server_id))?;
+        entry.handle.clone().ok_or_else(|| match &entry.last_error {
+            Some(reason) => anyhow!("MCP server `{}` is down: {}", server_id, reason),
+            None => anyhow!("MCP server `{}` is down", server_id),
+        })
+    }
+
+    /// Invoke `tools/call` on a previously registered server. Server requests
+    /// raised during the call go to `requests` (see [`block_on_with_requests`]).
     pub async fn call_tool(
         &self,
         server_id: &str,
         remote_name: &str,
         args: &Value,
+        requests: Option<ServerRequestSender>,
+    ) -> Result<Value> {
+        self.client(server_id)?
+            .call_tool(remote_name, args, requests)
+            .await
+    }
+
+    /// `resources/list`, every page: `[{ uri, name, mimeType?, … }]`.
+    pub async fn list_resources(&self, server_id: &str) -> Result<Value> {
+        let client = self.capable_client(server_id, "resources").await?;
+        client.list_all("resources/list", "resources").await
+    }
+
+    /// `resources/read`: the server's `contents` array, each entry
+    /// `{ uri, mimeType?, text | blob }`.
+    pub async fn read_resource(
+        &self,
+        server_id: &str,
+        uri: &str,
+        requests: Option<ServerRequestSender>,
+    ) -> Result<Value> {
+        let client = self.capable_client(server_id, "resources").await?;
+        let result = client
+            .rpc("resources/read", json!({ "uri": uri }), requests)
+            .await?;
+        Ok(result.get("contents").cloned().unwrap_or(json!([])))
+    }
+
+    /// `prompts/list`, every page: `[{ name, description?, arguments? }]`.
+    pub async fn list_prompts(&self, server_id: &str) -> Result<Value> {
+        let client = self.capable_client(server_id, "prompts").await?;
+        client.list_all("prompts/list", "prompts").await
+    }
+
+    /// `prompts/get`: `{ description?, messages: [{ role, content }] }`.
+    pub async fn get_prompt(
+        &self,
+        server_id: &str,
+        name: &str,
+        arguments: &Value,
+        requests: Option<ServerRequestSender>,
     ) -> Result<Value> {
-        let client = {
-            let map = self.servers.lock().await;
-            map.get(server_id)
-                .cloned()
-                .ok_or_else(|| anyhow::anyhow!("MCP server `{}` is not connected", server_id))?
+        let client = self.capable_client(server_id, "prompts").await?;
+        client
+            .rpc(
+                "prompts/get",
+                json!({ "name": name, "arguments": arguments }),
+                requests,
+            )
+            .await
+    }
+
+    /// The server's client, provided it advertised `capability` at
+    /// `initialize` — asking anyway would only earn a "method not found".
+    async fn capable_client(
+        &self,
+        server_id: &str,
+        capability: &str,
+    ) -> Result<Arc<McpClientHandle>> {
+        let client = self.client(server_id)?;
+        let caps = client.capabilities();
+        let advertised = match capability {
+            "resources" => caps.resources,
+            "prompts" => caps.prompts,
+            _ => false,
         };
-        client.call_tool(remote_name, args).await
+        if !advertised {
+            anyhow::bail!("MCP server `{server_id}` does not advertise `{capability}`");
+        }
+        Ok(client)
     }
 }
 
@@ -126,6 +513,19 @@ impl Default for McpManager {
     }
 }
 
+/// Open a client for one server config: stdio spawns a child, http connects
+/// to a remote endpoint. Both yield the same `McpClientHandle`.
+async fn connect(id: &str, server: &McpServerConfig) -> Result<McpClientHandle> {
+    match server.transport {
+        McpTransport::Stdio => McpClient::spawn(server.clone())
+            .await
+            .map(McpClientHandle::Stdio),
+        McpTransport::Http => McpHttpClient::connect(id, server.clone())
+            .await
+            .map(McpClientHandle::Http),
+    }
+}
+
 /// Build the `ToolDef` for one remote tool. Transport-agnostic — an http tool
 /// is exposed exactly like an stdio one (`<server_id>__<tool>`, backend
 /// `ToolBackend::Mcp`), so the rest of the runtime never learns the difference.
@@ -172,3 +572,41 @@ fn tool_def_for(server_id: &str, remote: &RemoteTool) -> ToolDef {
         },
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capabilities_come_from_the_initialize_result() {
+        let caps = ServerCapabilities::from_initialize(&json!({
+            "result": { "capabilities": { "tools": {}, "resources": { "subscribe": true } } }
+        }));
+        assert_eq!(
+            caps,
+            ServerCapabilities {
+                resources: true,
+                prompts: false,
+            }
+        );
+        assert_eq!(
+            ServerCapabilities::from_initialize(&json!({ "result": {} })),
+            ServerCapabilities::default()
+        );
+    }
+
+    #[test]
+    fn server_requests_without_an_owner_are_refused_but_ping_is_answered() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let ping = rt.block_on(answer_server_request("ping", Value::Null, None));
+        assert_eq!(jsonrpc_reply(json!(4), ping)["result"], json!({}));
+        let sampling = rt.block_on(answer_server_request(
+            "sampling/createMessage",
+            json!({}),
+            None,
+        ));
+        let reply = jsonrpc_reply(json!("abc"), sampling);
+        assert_eq!(reply["id"], "abc");
+        assert_eq!(reply["error"]["code"], -32601);
+    }
+}
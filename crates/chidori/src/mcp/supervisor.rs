@@ -0,0 +1,472 @@
+//! Lifecycle supervision for connected MCP servers.
+//!
+//! Each configured server gets one supervisor loop once
+//! [`McpManager::supervise`] is called (`chidori serve` and `chidori
+//! mcp-serve` do; one-shot runs don't). The loop pings the server every
+//! `ping_interval`, and wakes early when the transport notices something on
+//! its own — a stdio child's stdout closing, or a
+//! `notifications/tools/list_changed`. A server that exited or stopped
+//! answering is marked down and reconnected with exponential backoff; a
+//! changed tool list is re-fetched into the manager's catalog. Either way the
+//! next run's ToolRegistry sees the current tools (runs already in flight
+//! keep the registry they started with).
+//!
+//! [`McpManager::reload`] applies an edited `CHIDORI_MCP_CONFIG`: servers that
+//! were removed, disabled, or changed are stopped, and new or changed ones
+//! started. [`McpManager::watch_config`] polls the file and reloads on change.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::{
+    connect, tool_def_for, McpClientHandle, McpManager, McpServerConfig, McpServersConfig,
+    RemoteTool, ServerEntry, Servers,
+};
+
+/// How often `watch_config` looks at the config file's (mtime, len).
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Timing of the supervisor loops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SupervisorSettings {
+    /// Time between liveness pings of a running server.
+    pub ping_interval: Duration,
+    /// A ping unanswered for this long marks the server down.
+    pub ping_timeout: Duration,
+    /// Delay before the first reconnect attempt; doubles per failed attempt.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for SupervisorSettings {
+    fn default() -> Self {
+        Self {
+            ping_interval: Duration::from_secs(30),
+            ping_timeout: Duration::from_secs(10),
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+impl SupervisorSettings {
+    /// The defaults, with the ping interval overridable through
+    /// `CHIDORI_MCP_PING_INTERVAL_MS`.
+    pub fn from_env() -> Self {
+        let mut settings = Self::default();
+        if let Some(ms) = std::env::var("CHIDORI_MCP_PING_INTERVAL_MS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|ms| *ms > 0)
+        {
+            settings.ping_interval = Duration::from_millis(ms);
+        }
+        settings
+    }
+}
+
+impl McpManager {
+    /// Start supervising every configured server, and every server a later
+    /// [`Self::reload`] starts. Only the first call has an effect.
+    pub fn supervise(&self, settings: SupervisorSettings) {
+        if self.supervision.set(settings).is_err() {
+            return;
+        }
+        let entries: Vec<(String, u64)> = self
+            .servers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, entry)| (id.clone(), entry.generation))
+            .collect();
+        for (id, generation) in entries {
+            self.spawn_supervisor(&id, generation);
+        }
+    }
+
+    pub(super) fn spawn_supervisor(&self, id: &str, generation: u64) {
+        let Some(settings) = self.supervision.get().copied() else {
+            return;
+        };
+        tokio::spawn(supervise_server(
+            self.servers.clone(),
+            id.to_string(),
+            generation,
+            settings,
+        ));
+    }
+
+    /// Apply a new server config: stop servers that were removed, disabled,
+    /// or changed, and start the new and changed ones. Unchanged servers keep
+    /// their connection.
+    pub async fn reload(&self, cfg: &McpServersConfig) {
+        let wanted: HashMap<&String, &McpServerConfig> = cfg
+            .servers
+            .iter()
+            .filter(|(id, server)| match server.validate() {
+                Ok(()) => server.enabled,
+                Err(reason) => {
+                    tracing::warn!("MCP server `{}` skipped: {}", id, reason);
+                    false
+                }
+            })
+            .collect();
+        let to_start: Vec<(String, McpServerConfig)> = {
+            let mut servers = self.servers.lock().unwrap();
+            servers.retain(|id, entry| {
+                let keep = wanted.get(id).is_some_and(|cfg| **cfg == entry.config);
+                if !keep {
+                    tracing::info!("MCP server `{}` stopped by a config reload", id);
+                    // Its supervisor may hold a clone of the handle while it
+                    // sleeps; wake it so it lets go of the connection now.
+                    if let Some(handle) = &entry.handle {
+                        handle.signals().wake.notify_one();
+                    }
+                }
+                keep
+            });
+            wanted
+                .into_iter()
+                .filter(|(id, _)| !servers.contains_key(*id))
+                .map(|(id, cfg)| (id.clone(), cfg.clone()))
+                .collect()
+        };
+        for (id, config) in to_start {
+            tracing::info!("MCP server `{}` started by a config reload", id);
+            self.start_server(&id, config).await;
+        }
+    }
+
+    /// Poll the config file at `path` and [`Self::reload`] whenever it
+    /// changes. An unreadable or invalid edit is logged and the running
+    /// servers are left as they are.
+    pub fn watch_config(self: &Arc<Self>, path: PathBuf) {
+        let manager = self.clone();
+        tokio::spawn(async move {
+            let mut seen = file_signature(&path);
+            loop {
+                tokio::time::sleep(CONFIG_POLL_INTERVAL).await;
+                let now = file_signature(&path);
+                if now == seen {
+                    continue;
+                }
+                seen = now;
+                match McpServersConfig::load(&path) {
+                    Ok(cfg) => {
+                        tracing::info!("MCP config {} changed; reloading", path.display());
+                        manager.reload(&cfg).await;
+                    }
+                    Err(e) => tracing::warn!("MCP config reload skipped: {:#}", e),
+                }
+            }
+        });
+    }
+}
+
+/// Cheap change signature for the watched config: (mtime, len).
+fn file_signature(path: &std::path::Path) -> Option<(std::time::SystemTime, u64)> {
+    let meta = std::fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+/// The loop for one server generation. Exits once the server's entry is
+/// gone or belongs to a newer generation (a reload replaced it).
+async fn supervise_server(
+    servers: Servers,
+    id: String,
+    generation: u64,
+    settings: SupervisorSettings,
+) {
+    let mut backoff = settings.initial_backoff;
+    loop {
+        let Some((handle, config)) = current(&servers, &id, generation) else {
+            return;
+        };
+        let Some(handle) = handle else {
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(settings.max_backoff);
+            if current(&servers, &id, generation).is_none() {
+                return;
+            }
+            let outcome = connect(&id, &config).await;
+            with_entry(&servers, &id, generation, |entry| match outcome {
+                Ok(handle) => {
+                    tracing::info!("MCP server `{}` reconnected", id);
+                    entry.tools = handle
+                        .tools()
+                        .iter()
+                        .map(|remote| tool_def_for(&id, remote))
+                        .collect();
+                    entry.handle = Some(Arc::new(handle));
+                    entry.restarts += 1;
+                    entry.last_error = None;
+                }
+                Err(e) => {
+                    tracing::warn!("MCP server `{}` failed to restart: {}", id, e);
+                    entry.last_error = Some(e.to_string());
+                }
+            });
+            continue;
+        };
+
+        let signals = handle.signals().clone();
+        tokio::select! {
+            _ = signals.wake.notified() => {}
+            _ = tokio::time::sleep(settings.ping_interval) => {}
+        }
+        if current(&servers, &id, generation).is_none() {
+            return;
+        }
+        let failure = if signals.is_closed() {
+            Some("the server process exited".to_string())
+        } else {
+            match tokio::time::timeout(settings.ping_timeout, handle.ping()).await {
+                Ok(Ok(())) => None,
+                Ok(Err(e)) => Some(format!("ping failed: {e}")),
+                Err(_) => Some("ping timed out".to_string()),
+            }
+        };
+        if let Some(reason) = failure {
+            tracing::warn!("MCP server `{}` is down ({}); restarting", id, reason);
+            with_entry(&servers, &id, generation, |entry| {
+                entry.handle = None;
+                entry.tools.clear();
+                entry.last_error = Some(reason);
+            });
+            continue;
+        }
+        backoff = settings.initial_backoff;
+        if signals.take_tools_changed() {
+            refresh_tools(&servers, &id, generation, &handle).await;
+        }
+    }
+}
+
+/// Re-fetch a server's tool list after it announced a change.
+async fn refresh_tools(servers: &Servers, id: &str, generation: u64, handle: &McpClientHandle) {
+    let listed = handle
+        .list_all("tools/list", "tools")
+        .await
+        .and_then(|tools| Ok(serde_json::from_value::<Vec<RemoteTool>>(tools)?));
+    match listed {
+        Ok(tools) => {
+            tracing::info!("MCP server `{}` now lists {} tool(s)", id, tools.len());
+            with_entry(servers, id, generation, |entry| {
+                entry.tools = tools
+                    .iter()
+                    .map(|remote| tool_def_for(id, remote))
+                    .collect();
+            });
+        }
+        Err(e) => tracing::warn!("MCP server `{}`: refreshing tools failed: {}", id, e),
+    }
+}
+
+/// The entry's connection and config, if it is still this generation's.
+fn current(
+    servers: &Servers,
+    id: &str,
+    generation: u64,
+) -> Option<(Option<Arc<McpClientHandle>>, McpServerConfig)> {
+    let servers = servers.lock().unwrap();
+    let entry = servers.get(id).filter(|e| e.generation == generation)?;
+    Some((entry.handle.clone(), entry.config.clone()))
+}
+
+fn with_entry(servers: &Servers, id: &str, generation: u64, f: impl FnOnce(&mut ServerEntry)) {
+    if let Some(entry) = servers
+        .lock()
+        .unwrap()
+        .get_mut(id)
+        .filter(|e| e.generation == generation)
+    {
+        f(entry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::McpServerState;
+    use serde_json::{json, Value};
+    use std::io::{Read, Write};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    /// A JSON-RPC-over-HTTP MCP server whose behaviour the test steers:
+    /// `down` makes every request fail with a 500, `tools` is how many tools
+    /// `tools/list` returns, and a `ping` answered while `announce` is set
+    /// carries a `notifications/tools/list_changed` on its SSE stream.
+    struct MockServer {
+        down: AtomicBool,
+        announce: AtomicBool,
+        tools: AtomicUsize,
+    }
+
+    fn spawn_mock(mock: Arc<MockServer>) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let mut buf = [0u8; 8192];
+                let n = stream.read(&mut buf).unwrap_or(0);
+                let req = String::from_utf8_lossy(&buf[..n]);
+                let body = req.split("\r\n\r\n").nth(1).unwrap_or("");
+                let parsed: Value = serde_json::from_str(body).unwrap_or(Value::Null);
+                if mock.down.load(Ordering::SeqCst) {
+                    let _ = stream.write_all(
+                        b"HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    );
+                    continue;
+                }
+                let method = parsed.get("method").and_then(Value::as_str).unwrap_or("");
+                let result = match method {
+                    "initialize" => json!({"protocolVersion": "2025-03-26"}),
+                    "tools/list" => {
+                        let count = mock.tools.load(Ordering::SeqCst);
+                        let tools: Vec<Value> = (0..count)
+                            .map(|i| json!({"name": format!("t{i}")}))
+                            .collect();
+                        json!({ "tools": tools })
+                    }
+                    "ping" => json!({}),
+                    _ => {
+                        let _ = stream.write_all(
+                            b"HTTP/1.1 202 Accepted\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                        );
+                        continue;
+                    }
+                };
+                let reply = json!({"jsonrpc": "2.0", "id": 1, "result": result}).to_string();
+                let response = if method == "ping" && mock.announce.swap(false, Ordering::SeqCst) {
+                    let notice = json!({
+                        "jsonrpc": "2.0",
+                        "method": "notifications/tools/list_changed",
+                    });
+                    let events = format!("data: {notice}\n\ndata: {reply}\n\n");
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        events.len(),
+                        events
+                    )
+                } else {
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        reply.len(),
+                        reply
+                    )
+                };
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+        format!("http://{addr}/mcp")
+    }
+
+    async fn eventually(what: &str, mut check: impl FnMut() -> bool) {
+        for _ in 0..200 {
+            if check() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(25)).await;
+        }
+        panic!("timed out waiting for {what}");
+    }
+
+    fn config(url: &str) -> McpServersConfig {
+        serde_json::from_value(json!({
+            "servers": { "notes": { "transport": "http", "url": url } },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn supervisor_restarts_down_servers_and_follows_tool_list_changes() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let mock = Arc::new(MockServer {
+                down: AtomicBool::new(false),
+                announce: AtomicBool::new(false),
+                tools: AtomicUsize::new(1),
+            });
+            let url = spawn_mock(mock.clone());
+            let manager = Arc::new(McpManager::new());
+            let defs = manager.start_from_config(&config(&url)).await.unwrap();
+            assert_eq!(defs.len(), 1);
+            manager.supervise(SupervisorSettings {
+                ping_interval: Duration::from_millis(50),
+                ping_timeout: Duration::from_secs(2),
+                initial_backoff: Duration::from_millis(50),
+                max_backoff: Duration::from_millis(200),
+            });
+
+            // A changed tool list, announced on a ping's stream, is re-listed.
+            mock.tools.store(3, Ordering::SeqCst);
+            mock.announce.store(true, Ordering::SeqCst);
+            eventually("the refreshed catalog", || manager.tool_defs().len() == 3).await;
+            assert_eq!(manager.tool_defs()[2].name, "notes__t2");
+
+            // A server that stops answering goes down, then is reconnected.
+            mock.down.store(true, Ordering::SeqCst);
+            eventually("the server to be marked down", || {
+                manager.status()["notes"].state == McpServerState::Down
+            })
+            .await;
+            assert!(manager.tool_defs().is_empty());
+            let down = manager.status()["notes"].clone();
+            assert!(down.last_error.is_some());
+            let err = manager
+                .call_tool("notes", "t0", &json!({}), None)
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("is down"), "{err}");
+
+            mock.down.store(false, Ordering::SeqCst);
+            eventually("the server to be restarted", || {
+                manager.status()["notes"].state == McpServerState::Running
+            })
+            .await;
+            let status = manager.status()["notes"].clone();
+            assert_eq!(status.restarts, 1);
+            assert_eq!(status.tools, 3);
+            assert_eq!(status.last_error, None);
+        });
+    }
+
+    #[test]
+    fn reload_starts_added_servers_and_stops_removed_ones() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let mock = Arc::new(MockServer {
+                down: AtomicBool::new(false),
+                announce: AtomicBool::new(false),
+                tools: AtomicUsize::new(2),
+            });
+            let url = spawn_mock(mock);
+            let manager = McpManager::new();
+            manager
+                .start_from_config(&McpServersConfig::default())
+                .await
+                .unwrap();
+            assert!(manager.status().is_empty());
+
+            manager.reload(&config(&url)).await;
+            assert_eq!(manager.tool_defs().len(), 2);
+            let generation = manager.servers.lock().unwrap()["notes"].generation;
+
+            // Reloading the same config keeps the connection.
+            manager.reload(&config(&url)).await;
+            assert_eq!(
+                manager.servers.lock().unwrap()["notes"].generation,
+                generation
+            );
+
+            let mut disabled = config(&url);
+            disabled.servers.get_mut("notes").unwrap().enabled = false;
+            manager.reload(&disabled).await;
+            assert!(manager.status().is_empty());
+            assert!(manager.tool_defs().is_empty());
+        });
+    }
+}
@@ -15,10 +15,17 @@
 //!      `--trusted`), `chidori serve` denies them ([`serve_default_profile`],
 //!      relaxed with `--trusted`). Shell keeps the existing
 //!      CHIDORI_SHELL_ALLOW semantics in every posture.
+//!
+//! Rules can be conditional: `match_args` operators (`$regex`, `$glob`,
+//! numeric comparisons), a `time_window`, and a `rate_limit` that only fires
+//! once a target has been called too often. Rate-limit history is kept per
+//! session in this process, so it survives resumes here but starts over on a
+//! restart or on another replica (see [`PolicyVerdict::release`]).
 
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, OnceLock};
 
+use chrono::{DateTime, Datelike, NaiveTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -52,6 +59,18 @@ impl Decision {
 /// given prefix, which is the right shape for scoping `http` to a host
 /// (`{"url_prefix": "https://api.example.com/"}`) since an unanchored
 /// substring would also match that text embedded in a hostile URL's query.
+///
+/// A pattern value that is an object of `$`-operators tests the argument
+/// instead of containing it: `$regex` (unanchored), `$glob` (`*`/`?`, whole
+/// string), `$gt` / `$gte` / `$lt` / `$lte` (numbers), `$eq` / `$ne` (exact
+/// JSON equality). Several operators in one object must all hold, so
+/// `{"amount": {"$gt": 100, "$lte": 1000}}` is a range.
+///
+/// `time_window` restricts the rule to a time of day, and `rate_limit` makes
+/// it apply only once `max_calls` matching calls already went through in the
+/// trailing `per_seconds` — `{"target": "http", "decision": "never_allow",
+/// "rate_limit": {"max_calls": 20, "per_seconds": 60}}` caps egress at 20
+/// calls a minute and leaves calls under the cap to later rules.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PolicyRule {
     pub target: String,
@@ -61,6 +80,173 @@ pub struct PolicyRule {
     pub match_args: Option<Value>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<RateLimit>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_window: Option<TimeWindow>,
+    /// `match_args`' `$regex` / `$glob` patterns, compiled once — at load for
+    /// [`PolicyConfig::parse`], else on the rule's first evaluation.
+    #[serde(skip)]
+    pub compiled: CompiledMatchers,
+}
+
+/// Compiled `$regex` / `$glob` patterns keyed by `(operator, pattern)`. A
+/// pattern that fails to compile is absent and never matches.
+#[derive(Debug, Clone, Default)]
+pub struct CompiledMatchers(OnceLock<HashMap<(String, String), regex::Regex>>);
+
+impl CompiledMatchers {
+    fn get(&self, match_args: Option<&Value>) -> &HashMap<(String, String), regex::Regex> {
+        self.0.get_or_init(|| {
+            let mut compiled = HashMap::new();
+            if let Some(pattern) = match_args {
+                collect_matchers(pattern, &mut compiled);
+            }
+            compiled
+        })
+    }
+}
+
+/// At most `max_calls` matching calls per trailing `per_seconds`, counted
+/// per session across all of its runs and resumes in this process. Only
+/// calls the policy finally admits count.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimit {
+    pub max_calls: u32,
+    pub per_seconds: u64,
+}
+
+/// A daily window `[start, end)` of `HH:MM` wall-clock times in `timezone`
+/// (an IANA name; default UTC). `end` before `start` wraps past midnight.
+/// `days` (`"mon"`…`"sun"`) limits the window to those weekdays; empty
+/// means every day.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeWindow {
+    pub start: String,
+    pub end: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub days: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+}
+
+impl PolicyRule {
+    /// True when the rule holds for every call to its target: no argument,
+    /// time, or rate condition. Preflight treats any other rule as possibly
+    /// not applying.
+    pub fn is_unconditional(&self) -> bool {
+        self.match_args.is_none() && self.rate_limit.is_none() && self.time_window.is_none()
+    }
+
+    /// The rule's time and rate conditions in operator terms, e.g.
+    /// "more than 20 calls per 60s" — appended to denial reasons and printed
+    /// by the server's preflight.
+    pub fn describe_conditions(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if let Some(limit) = &self.rate_limit {
+            parts.push(format!(
+                "more than {} calls per {}s",
+                limit.max_calls, limit.per_seconds
+            ));
+        }
+        if let Some(window) = &self.time_window {
+            let days = if window.days.is_empty() {
+                String::new()
+            } else {
+                format!(" on {}", window.days.join(","))
+            };
+            parts.push(format!(
+                "between {} and {} {}{days}",
+                window.start,
+                window.end,
+                window.timezone.as_deref().unwrap_or("UTC")
+            ));
+        }
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if let Some(pattern) = &self.match_args {
+            validate_pattern(pattern)?;
+            self.compiled.get(Some(pattern));
+        }
+        if let Some(limit) = &self.rate_limit {
+            if limit.per_seconds == 0 {
+                return Err("rate_limit needs a non-zero per_seconds".to_string());
+            }
+        }
+        if let Some(window) = &self.time_window {
+            window.validate()?;
+        }
+        Ok(())
+    }
+
+    /// The reason a matching rule reports: its own `reason`, followed by its
+    /// time/rate conditions so a throttled call says why.
+    fn fired_reason(&self) -> Option<String> {
+        match (self.reason.clone(), self.describe_conditions()) {
+            (Some(reason), Some(conditions)) => {
+                Some(format!("{reason}; rule applies to {conditions}"))
+            }
+            (None, Some(conditions)) => Some(format!("rule applies to {conditions}")),
+            (reason, None) => reason,
+        }
+    }
+}
+
+impl TimeWindow {
+    fn validate(&self) -> Result<(), String> {
+        parse_clock(&self.start)?;
+        parse_clock(&self.end)?;
+        for day in &self.days {
+            parse_weekday(day)?;
+        }
+        if let Some(zone) = &self.timezone {
+            zone.parse::<chrono_tz::Tz>()
+                .map_err(|_| format!("time_window: unknown timezone `{zone}`"))?;
+        }
+        Ok(())
+    }
+
+    /// Whether `now` falls inside the window. A malformed window (rejected by
+    /// [`PolicyConfig::validate`] at load) never matches.
+    fn contains(&self, now: DateTime<Utc>) -> bool {
+        let (Ok(start), Ok(end)) = (parse_clock(&self.start), parse_clock(&self.end)) else {
+            return false;
+        };
+        let zone = match &self.timezone {
+            Some(zone) => match zone.parse::<chrono_tz::Tz>() {
+                Ok(zone) => zone,
+                Err(_) => return false,
+            },
+            None => chrono_tz::UTC,
+        };
+        let local = now.with_timezone(&zone);
+        if !self.days.is_empty()
+            && !self
+                .days
+                .iter()
+                .any(|day| parse_weekday(day) == Ok(local.weekday()))
+        {
+            return false;
+        }
+        let time = local.time();
+        if start <= end {
+            start <= time && time < end
+        } else {
+            time >= start || time < end
+        }
+    }
+}
+
+fn parse_clock(text: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(text, "%H:%M")
+        .map_err(|_| format!("time_window: `{text}` is not an HH:MM time"))
+}
+
+fn parse_weekday(day: &str) -> Result<chrono::Weekday, String> {
+    day.parse::<chrono::Weekday>()
+        .map_err(|_| format!("time_window: `{day}` is not a weekday"))
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -82,6 +268,204 @@ pub struct PolicyConfig {
     /// the overlay is reconstructed from its profile name on every run.
     #[serde(skip)]
     pub overlay: Option<Arc<PolicyConfig>>,
+    /// Where this layer came from (a profile name, `CHIDORI_POLICY_FILE`
+    /// path, ...), for audit entries and `chidori policy explain`.
+    #[serde(skip)]
+    pub label: Option<String>,
+}
+
+/// The outcome of evaluating a call against a (possibly layered) policy:
+/// the decision plus which layer and rule produced it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyVerdict {
+    pub decision: Decision,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// 0 for the base policy, 1.. for each overlay in order.
+    pub layer: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layer_label: Option<String>,
+    /// Index of the deciding rule within its layer; `None` when the layer's
+    /// `default` decided.
+    pub rule: Option<usize>,
+    /// Slots this call reserved in the rate-limited rules it passed under
+    /// their cap. They count as soon as the call is evaluated; a gate that
+    /// doesn't let the call run gives them back ([`PolicyVerdict::release`]).
+    #[serde(skip)]
+    pub rate_admissions: Vec<RateAdmission>,
+}
+
+/// One slot a call holds in a rate-limit window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateAdmission {
+    key: String,
+    at: DateTime<Utc>,
+}
+
+impl PolicyVerdict {
+    /// Give back every rate-limit slot the call reserved. Gates call this
+    /// when the final outcome doesn't let the call run — a denial, a refusal
+    /// at the prompt, or a pause whose resume evaluates the call again — so
+    /// those never use up a window.
+    pub fn release(&self) {
+        release_slots(&self.rate_admissions);
+    }
+}
+
+/// Admission times per rate-limited rule, keyed by session, target and rule.
+/// Process-wide rather than per [`PolicyCache`], which is fresh on every run
+/// and resume: a session's counts must survive its pauses. They live only in
+/// this process, so a restart, or a resume on another replica, starts the
+/// session's windows empty.
+fn rate_windows() -> &'static Mutex<HashMap<String, RateWindow>> {
+    static WINDOWS: OnceLock<Mutex<HashMap<String, RateWindow>>> = OnceLock::new();
+    WINDOWS.get_or_init(Default::default)
+}
+
+#[derive(Debug, Default)]
+struct RateWindow {
+    per_seconds: u64,
+    admitted: VecDeque<DateTime<Utc>>,
+}
+
+impl RateWindow {
+    fn expire(&mut self, now: DateTime<Utc>) {
+        let horizon = now - chrono::Duration::seconds(self.per_seconds as i64);
+        while self.admitted.front().is_some_and(|at| *at <= horizon) {
+            self.admitted.pop_front();
+        }
+    }
+}
+
+fn rate_key(session: &str, target: &str, rule: &PolicyRule) -> String {
+    format!(
+        "{session}::{target}::{}",
+        serde_json::to_string(rule).unwrap_or_default()
+    )
+}
+
+/// Take a slot in `key`'s window at `now`, unless it already holds
+/// `limit.max_calls` calls. The check and the count happen under one lock,
+/// so concurrent calls can't both take the last slot.
+fn reserve_slot(key: &str, limit: &RateLimit, now: DateTime<Utc>) -> Option<RateAdmission> {
+    let mut windows = rate_windows().lock().unwrap();
+    // Drop windows that have fully aged out, so finished sessions don't
+    // accumulate.
+    windows.retain(|_, window| {
+        window.expire(now);
+        !window.admitted.is_empty()
+    });
+    let window = windows.entry(key.to_string()).or_default();
+    window.per_seconds = limit.per_seconds;
+    if window.admitted.len() >= limit.max_calls as usize {
+        return None;
+    }
+    window.admitted.push_back(now);
+    Some(RateAdmission {
+        key: key.to_string(),
+        at: now,
+    })
+}
+
+fn release_slots(admissions: &[RateAdmission]) {
+    if admissions.is_empty() {
+        return;
+    }
+    let mut windows = rate_windows().lock().unwrap();
+    for admission in admissions {
+        let Some(window) = windows.get_mut(&admission.key) else {
+            continue;
+        };
+        if let Some(pos) = window.admitted.iter().position(|at| *at == admission.at) {
+            window.admitted.remove(pos);
+        }
+    }
+}
+
+/// One rule's part in an explained decision.
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleCheck {
+    pub index: usize,
+    pub target: String,
+    pub decision: Decision,
+    /// `matched`, or why the rule was passed over.
+    pub outcome: String,
+}
+
+/// `chidori policy explain`: every layer's rule-by-rule evaluation of one
+/// call, and the verdict the layering settles on.
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyExplanation {
+    pub target: String,
+    pub args: Value,
+    pub layers: Vec<LayerExplanation>,
+    pub verdict: PolicyVerdict,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LayerExplanation {
+    pub verdict: PolicyVerdict,
+    /// The rules whose target names this call (or `*`), in order, up to and
+    /// including the one that matched.
+    pub checks: Vec<RuleCheck>,
+}
+
+impl std::fmt::Display for PolicyExplanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let decision_name = |d: Decision| match d {
+            Decision::AlwaysAllow => "always_allow",
+            Decision::AskBefore => "ask_before",
+            Decision::NeverAllow => "never_allow",
+        };
+        let source = |v: &PolicyVerdict| {
+            let at = match v.rule {
+                Some(rule) => format!("rule {rule}"),
+                None => "default".to_string(),
+            };
+            match &v.reason {
+                Some(reason) => format!("{at}: {reason}"),
+                None => at,
+            }
+        };
+        writeln!(f, "`{}` with args {}", self.target, self.args)?;
+        for layer in &self.layers {
+            writeln!(
+                f,
+                "layer {} ({}):",
+                layer.verdict.layer,
+                layer.verdict.layer_label.as_deref().unwrap_or("policy")
+            )?;
+            for check in &layer.checks {
+                writeln!(
+                    f,
+                    "  rule {:<3} {:<20} {:<13} {}",
+                    check.index,
+                    check.target,
+                    decision_name(check.decision),
+                    check.outcome
+                )?;
+            }
+            writeln!(
+                f,
+                "  -> {} ({})",
+                decision_name(layer.verdict.decision),
+                source(&layer.verdict)
+            )?;
+        }
+        let v = &self.verdict;
+        write!(
+            f,
+            "decision: {} from layer {} ({}) {}",
+            decision_name(v.decision),
+            v.layer,
+            v.layer_label.as_deref().unwrap_or("policy"),
+            source(v)
+        )?;
+        if self.layers.len() > 1 {
+            write!(f, " — the strictest layer wins")?;
+        }
+        Ok(())
+    }
 }
 
 impl PolicyConfig {
@@ -100,15 +484,25 @@ impl PolicyConfig {
     pub fn from_env_configured() -> Option<Arc<Self>> {
         if let Ok(path) = std::env::var("CHIDORI_POLICY_FILE") {
             if let Ok(text) = std::fs::read_to_string(&path) {
-                match serde_json::from_str::<PolicyConfig>(&text) {
-                    Ok(cfg) => return Some(Arc::new(cfg)),
+                match Self::parse(&text) {
+                    Ok(cfg) => {
+                        return Some(Arc::new(PolicyConfig {
+                            label: Some(format!("CHIDORI_POLICY_FILE {path}")),
+                            ..cfg
+                        }))
+                    }
                     Err(e) => tracing::warn!("CHIDORI_POLICY_FILE parse error: {}", e),
                 }
             }
         }
         if let Ok(inline) = std::env::var("CHIDORI_POLICY") {
-            match serde_json::from_str::<PolicyConfig>(&inline) {
-                Ok(cfg) => return Some(Arc::new(cfg)),
+            match Self::parse(&inline) {
+                Ok(cfg) => {
+                    return Some(Arc::new(PolicyConfig {
+                        label: Some("CHIDORI_POLICY".to_string()),
+                        ..cfg
+                    }))
+                }
                 Err(e) => tracing::warn!("CHIDORI_POLICY parse error: {}", e),
             }
         }
@@ -128,38 +522,184 @@ impl PolicyConfig {
         None
     }
 
+    /// Parse a policy document and reject rules that could never evaluate
+    /// as written (a bad regex, an unknown timezone), so a typo fails at load
+    /// rather than silently skipping a deny rule.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let cfg: PolicyConfig = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        cfg.validate()?;
+        Ok(cfg)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for (index, rule) in self.rules.iter().enumerate() {
+            rule.validate()
+                .map_err(|e| format!("rule {index} (`{}`): {e}", rule.target))?;
+        }
+        Ok(())
+    }
+
     /// Resolve a call against the policy. `target` is the normalized target
     /// string (see PolicyRule.target). Rules are tried in order; the first
     /// matching rule wins. Wildcard "*" always matches target. When an
     /// overlay is present, the stricter of the two resolutions wins.
+    ///
+    /// Stateless: no call history is consulted, so `rate_limit` rules never
+    /// fire. Gates that actually admit calls use [`evaluate`].
+    ///
+    /// [`evaluate`]: PolicyConfig::evaluate
     pub fn decide(&self, target: &str, args: &Value) -> (Decision, Option<String>) {
-        let base = self.decide_local(target, args);
+        let verdict = self.evaluate_at(target, args, Utc::now(), None, 0);
+        (verdict.decision, verdict.reason)
+    }
+
+    /// [`decide`](PolicyConfig::decide) for a call about to run, reporting
+    /// which layer and rule decided. With a `session` (the run id, which a
+    /// session keeps across resumes), each rate-limited rule fires once the
+    /// session's window is full; a call it lets through takes a slot right
+    /// away, which the gate [releases](PolicyVerdict::release) if the call
+    /// doesn't run.
+    pub fn evaluate(&self, target: &str, args: &Value, session: Option<&str>) -> PolicyVerdict {
+        self.evaluate_at(target, args, Utc::now(), session, 0)
+    }
+
+    /// Walk every layer for one call and record why each relevant rule did
+    /// or didn't apply. Stateless like [`decide`](PolicyConfig::decide):
+    /// rate-limited rules are reported but never fire.
+    pub fn explain(&self, target: &str, args: &Value) -> PolicyExplanation {
+        let now = Utc::now();
+        let mut layers = Vec::new();
+        let mut layer = Some(self);
+        while let Some(cfg) = layer {
+            let mut checks = Vec::new();
+            let verdict =
+                cfg.evaluate_layer(target, args, now, None, layers.len(), Some(&mut checks));
+            layers.push(LayerExplanation { verdict, checks });
+            layer = cfg.overlay.as_deref();
+        }
+        let verdict = layers
+            .iter()
+            .map(|layer| &layer.verdict)
+            .fold(None::<&PolicyVerdict>, |acc, v| match acc {
+                Some(acc) if v.decision.strictness() <= acc.decision.strictness() => Some(acc),
+                _ => Some(v),
+            })
+            .cloned()
+            .expect("a policy has at least one layer");
+        PolicyExplanation {
+            target: target.to_string(),
+            args: args.clone(),
+            layers,
+            verdict,
+        }
+    }
+
+    fn evaluate_at(
+        &self,
+        target: &str,
+        args: &Value,
+        now: DateTime<Utc>,
+        session: Option<&str>,
+        layer: usize,
+    ) -> PolicyVerdict {
+        let base = self.evaluate_layer(target, args, now, session, layer, None);
         match &self.overlay {
             None => base,
             Some(overlay) => {
-                let layered = overlay.decide(target, args);
-                if layered.0.strictness() > base.0.strictness() {
+                let layered = overlay.evaluate_at(target, args, now, session, layer + 1);
+                let mut admissions = base.rate_admissions.clone();
+                admissions.extend(layered.rate_admissions.iter().cloned());
+                let mut verdict = if layered.decision.strictness() > base.decision.strictness() {
                     layered
                 } else {
                     base
-                }
+                };
+                verdict.rate_admissions = admissions;
+                verdict
             }
         }
     }
 
-    fn decide_local(&self, target: &str, args: &Value) -> (Decision, Option<String>) {
-        for rule in &self.rules {
+    fn evaluate_layer(
+        &self,
+        target: &str,
+        args: &Value,
+        now: DateTime<Utc>,
+        session: Option<&str>,
+        layer: usize,
+        mut trace: Option<&mut Vec<RuleCheck>>,
+    ) -> PolicyVerdict {
+        let mut rate_admissions = Vec::new();
+        let mut note = |index: usize, rule: &PolicyRule, outcome: String| {
+            if let Some(trace) = trace.as_deref_mut() {
+                trace.push(RuleCheck {
+                    index,
+                    target: rule.target.clone(),
+                    decision: rule.decision,
+                    outcome,
+                });
+            }
+        };
+        for (index, rule) in self.rules.iter().enumerate() {
             if rule.target != target && rule.target != "*" {
                 continue;
             }
             if let Some(ref pat) = rule.match_args {
-                if !value_contains(args, pat) {
+                if !value_contains(args, pat, rule.compiled.get(Some(pat))) {
+                    note(index, rule, "skipped: match_args not satisfied".to_string());
                     continue;
                 }
             }
-            return (rule.decision, rule.reason.clone());
+            if let Some(window) = &rule.time_window {
+                if !window.contains(now) {
+                    note(index, rule, "skipped: outside its time window".to_string());
+                    continue;
+                }
+            }
+            if let Some(limit) = &rule.rate_limit {
+                let over = match session {
+                    Some(session) => {
+                        let key = rate_key(session, target, rule);
+                        match reserve_slot(&key, limit, now) {
+                            Some(admission) => {
+                                rate_admissions.push(admission);
+                                false
+                            }
+                            None => true,
+                        }
+                    }
+                    None => false,
+                };
+                if !over {
+                    note(
+                        index,
+                        rule,
+                        format!(
+                            "skipped: applies only past {} calls per {}s",
+                            limit.max_calls, limit.per_seconds
+                        ),
+                    );
+                    continue;
+                }
+            }
+            note(index, rule, "matched".to_string());
+            return PolicyVerdict {
+                decision: rule.decision,
+                reason: rule.fired_reason(),
+                layer,
+                layer_label: self.label.clone(),
+                rule: Some(index),
+                rate_admissions,
+            };
+        }
+        PolicyVerdict {
+            decision: self.default,
+            reason: self.default_reason.clone(),
+            layer,
+            layer_label: self.label.clone(),
+            rule: None,
+            rate_admissions,
         }
-        (self.default, self.default_reason.clone())
     }
 
     /// Layer `profile` on top of this policy: every decision becomes the
@@ -196,6 +736,10 @@ pub fn builtin_profile(name: &str) -> Option<PolicyConfig> {
         "supervised" => Some(supervised_profile()),
         _ => None,
     }
+    .map(|cfg| PolicyConfig {
+        label: Some(name.to_string()),
+        ..cfg
+    })
 }
 
 /// Deny-by-default profile for running code you do not trust.
@@ -219,6 +763,7 @@ fn untrusted_profile() -> PolicyConfig {
         default: Decision::NeverAllow,
         default_reason: None,
         overlay: None,
+        label: None,
     }
 }
 
@@ -230,6 +775,7 @@ fn untrusted_profile() -> PolicyConfig {
 /// [`run_default_profile`].)
 pub fn serve_default_profile() -> PolicyConfig {
     let mut cfg = untrusted_profile();
+    cfg.label = Some("serve default (untrusted)".to_string());
     cfg.default_reason = Some(
         "chidori serve is deny-by-default: configure CHIDORI_POLICY / CHIDORI_POLICY_FILE / \
          CHIDORI_POLICY_PROFILE, or start the server with --trusted, to allow this effect"
@@ -246,6 +792,7 @@ pub fn serve_default_profile() -> PolicyConfig {
 /// the call fails closed with this reason telling the operator how to relax it.
 pub fn run_default_profile() -> PolicyConfig {
     let mut cfg = supervised_profile();
+    cfg.label = Some("run default (supervised)".to_string());
     cfg.default_reason = Some(
         "chidori run asks before powerful effects by default: approve at the prompt, pass \
          --trusted for the historical allow-all behavior, or configure CHIDORI_POLICY / \
@@ -267,6 +814,7 @@ fn supervised_profile() -> PolicyConfig {
         default: Decision::AskBefore,
         default_reason: None,
         overlay: None,
+        label: None,
     }
 }
 
@@ -276,6 +824,9 @@ fn read_only_workspace_allowlist() -> Vec<PolicyRule> {
         decision: Decision::AlwaysAllow,
         match_args: None,
         reason: Some("read-only workspace introspection".to_string()),
+        rate_limit: None,
+        time_window: None,
+        compiled: CompiledMatchers::default(),
     };
     vec![
         allow_read_only("workspace:list"),
@@ -377,8 +928,18 @@ pub fn prompt_operator_approval(
 }
 
 /// True when `args` contains all keys/values in `pattern` (shallow for scalars,
-/// recursive for objects). Lists require equality.
-fn value_contains(args: &Value, pattern: &Value) -> bool {
+/// recursive for objects). Lists require equality. An all-`$` object is an
+/// operator test (see [`PolicyRule`]).
+fn value_contains(
+    args: &Value,
+    pattern: &Value,
+    compiled: &HashMap<(String, String), regex::Regex>,
+) -> bool {
+    if let Some(ops) = operator_object(pattern) {
+        return ops
+            .iter()
+            .all(|(op, operand)| operator_holds(args, op, operand, compiled));
+    }
     match (args, pattern) {
         (Value::Object(a), Value::Object(p)) => p.iter().all(|(k, pv)| {
             // Reserved key: `url_prefix` anchors against the call's `url`
@@ -391,13 +952,113 @@ fn value_contains(args: &Value, pattern: &Value) -> bool {
                     _ => false,
                 };
             }
-            a.get(k).map(|av| value_contains(av, pv)).unwrap_or(false)
+            a.get(k)
+                .map(|av| value_contains(av, pv, compiled))
+                .unwrap_or(false)
         }),
         (Value::String(a), Value::String(p)) => a.contains(p.as_str()),
         (a, p) => a == p,
     }
 }
 
+/// `pattern` as an operator object: non-empty, every key `$`-prefixed.
+fn operator_object(pattern: &Value) -> Option<&serde_json::Map<String, Value>> {
+    pattern
+        .as_object()
+        .filter(|map| !map.is_empty() && map.keys().all(|k| k.starts_with('$')))
+}
+
+fn operator_holds(
+    value: &Value,
+    op: &str,
+    operand: &Value,
+    compiled: &HashMap<(String, String), regex::Regex>,
+) -> bool {
+    let compare = |f: fn(f64, f64) -> bool| match (value.as_f64(), operand.as_f64()) {
+        (Some(v), Some(o)) => f(v, o),
+        _ => false,
+    };
+    match op {
+        "$regex" | "$glob" => match (value.as_str(), operand.as_str()) {
+            (Some(text), Some(pattern)) => compiled
+                .get(&(op.to_string(), pattern.to_string()))
+                .is_some_and(|regex| regex.is_match(text)),
+            _ => false,
+        },
+        "$gt" => compare(|v, o| v > o),
+        "$gte" => compare(|v, o| v >= o),
+        "$lt" => compare(|v, o| v < o),
+        "$lte" => compare(|v, o| v <= o),
+        "$eq" => value == operand,
+        "$ne" => value != operand,
+        _ => false,
+    }
+}
+
+/// `$regex` compiles as written; `$glob` becomes an anchored regex where `*`
+/// is any run of characters and `?` is one character.
+fn compile_matcher(op: &str, operand: &Value) -> Result<regex::Regex, String> {
+    let pattern = operand
+        .as_str()
+        .ok_or_else(|| format!("`{op}` takes a string"))?;
+    let source = if op == "$glob" {
+        let mut source = String::from("^");
+        for ch in pattern.chars() {
+            match ch {
+                '*' => source.push_str(".*"),
+                '?' => source.push('.'),
+                ch => source.push_str(&regex::escape(&ch.to_string())),
+            }
+        }
+        source.push('$');
+        source
+    } else {
+        pattern.to_string()
+    };
+    regex::Regex::new(&source).map_err(|e| format!("`{op}` pattern `{pattern}`: {e}"))
+}
+
+/// Compile every well-formed `$regex` / `$glob` in `pattern` into `out`.
+fn collect_matchers(pattern: &Value, out: &mut HashMap<(String, String), regex::Regex>) {
+    if let Some(ops) = operator_object(pattern) {
+        for (op, operand) in ops {
+            if let (Some(text), Ok(regex)) = (operand.as_str(), compile_matcher(op, operand)) {
+                out.insert((op.clone(), text.to_string()), regex);
+            }
+        }
+        return;
+    }
+    if let Value::Object(map) = pattern {
+        for value in map.values() {
+            collect_matchers(value, out);
+        }
+    }
+}
+
+fn validate_pattern(pattern: &Value) -> Result<(), String> {
+    if let Some(ops) = operator_object(pattern) {
+        for (op, operand) in ops {
+            match op.as_str() {
+                "$regex" | "$glob" => {
+                    compile_matcher(op, operand)?;
+                }
+                "$gt" | "$gte" | "$lt" | "$lte" if !operand.is_number() => {
+                    return Err(format!("`{op}` takes a number"));
+                }
+                "$gt" | "$gte" | "$lt" | "$lte" | "$eq" | "$ne" => {}
+                other => return Err(format!("unknown match_args operator `{other}`")),
+            }
+        }
+        return Ok(());
+    }
+    if let Value::Object(map) = pattern {
+        for value in map.values() {
+            validate_pattern(value)?;
+        }
+    }
+    Ok(())
+}
+
 /// A remembered user decision for the remainder of this run. After the user
 /// approves an AskBefore call once, we cache (target, canonical_args) → Allow
 /// so repeated calls in the same agent pass through.
@@ -542,10 +1203,14 @@ mod tests {
                 decision: Decision::NeverAllow,
                 match_args: None,
                 reason: Some("operator denies egress".to_string()),
+                rate_limit: None,
+                time_window: None,
+                compiled: Default::default(),
             }],
             default: Decision::AlwaysAllow,
             default_reason: None,
             overlay: None,
+            label: None,
         };
         let layered = base.restricted_by(Arc::new(builtin_profile("supervised").unwrap()));
 
@@ -672,6 +1337,287 @@ mod tests {
         assert_eq!(decision, Decision::NeverAllow);
     }
 
+    #[test]
+    fn match_args_operators_test_regex_glob_and_numbers() {
+        let cfg = PolicyConfig::parse(
+            &json!({
+                "rules": [
+                    { "target": "tool:pay", "decision": "ask_before",
+                      "match_args": { "amount": { "$gt": 100, "$lte": 1000 } } },
+                    { "target": "tool:pay", "decision": "never_allow",
+                      "match_args": { "amount": { "$gt": 1000 } }, "reason": "too large" },
+                    { "target": "http", "decision": "always_allow",
+                      "match_args": { "url": { "$glob": "https://*.example.com/*" } } },
+                    { "target": "tool:sql", "decision": "never_allow",
+                      "match_args": { "query": { "$regex": "(?i)\\bdrop\\s+table\\b" } } },
+                    { "target": "tool:tag", "decision": "never_allow",
+                      "match_args": { "label": { "$ne": "ok" } } }
+                ],
+                "default": "always_allow"
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let decide = |target: &str, args: Value| cfg.decide(target, &args).0;
+        assert_eq!(
+            decide("tool:pay", json!({ "amount": 50 })),
+            Decision::AlwaysAllow
+        );
+        assert_eq!(
+            decide("tool:pay", json!({ "amount": 500 })),
+            Decision::AskBefore
+        );
+        assert_eq!(
+            decide("tool:pay", json!({ "amount": 5000 })),
+            Decision::NeverAllow
+        );
+        // A non-number never satisfies a comparison.
+        assert_eq!(
+            decide("tool:pay", json!({ "amount": "5000" })),
+            Decision::AlwaysAllow
+        );
+
+        let strict = PolicyConfig {
+            default: Decision::NeverAllow,
+            ..cfg.clone()
+        };
+        let http = |url: &str| strict.decide("http", &json!({ "url": url })).0;
+        assert_eq!(http("https://api.example.com/v1"), Decision::AlwaysAllow);
+        assert_eq!(http("https://example.com.evil.io/x"), Decision::NeverAllow);
+
+        assert_eq!(
+            decide(
+                "tool:sql",
+                json!({ "query": "select 1; DROP  TABLE users" })
+            ),
+            Decision::NeverAllow
+        );
+        assert_eq!(
+            decide("tool:sql", json!({ "query": "select * from drops" })),
+            Decision::AlwaysAllow
+        );
+        assert_eq!(
+            decide("tool:tag", json!({ "label": "ok" })),
+            Decision::AlwaysAllow
+        );
+        assert_eq!(
+            decide("tool:tag", json!({ "label": "bad" })),
+            Decision::NeverAllow
+        );
+    }
+
+    #[test]
+    fn invalid_conditions_are_rejected_at_load() {
+        for rule in [
+            json!({ "target": "http", "match_args": { "url": { "$regex": "(" } } }),
+            json!({ "target": "http", "match_args": { "n": { "$gt": "ten" } } }),
+            json!({ "target": "http", "match_args": { "n": { "$near": 1 } } }),
+            json!({ "target": "http", "time_window": { "start": "9am", "end": "17:00" } }),
+            json!({ "target": "http", "time_window": { "start": "09:00", "end": "17:00", "timezone": "Mars/Olympus" } }),
+            json!({ "target": "http", "rate_limit": { "max_calls": 1, "per_seconds": 0 } }),
+        ] {
+            let doc = json!({ "rules": [rule] }).to_string();
+            assert!(PolicyConfig::parse(&doc).is_err(), "accepted {doc}");
+        }
+    }
+
+    #[test]
+    fn concurrent_calls_cannot_share_the_last_rate_limit_slot() {
+        let cfg = PolicyConfig::parse(
+            &json!({
+                "rules": [{
+                    "target": "http",
+                    "decision": "never_allow",
+                    "rate_limit": { "max_calls": 3, "per_seconds": 60 }
+                }],
+                "default": "always_allow"
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let args = json!({ "url": "https://example.com" });
+        let barrier = std::sync::Barrier::new(16);
+        let allowed = std::thread::scope(|scope| {
+            let calls: Vec<_> = (0..16)
+                .map(|_| {
+                    scope.spawn(|| {
+                        barrier.wait();
+                        cfg.evaluate("http", &args, Some("rate-limit-race-session"))
+                            .decision
+                            == Decision::AlwaysAllow
+                    })
+                })
+                .collect();
+            calls
+                .into_iter()
+                .map(|call| call.join().unwrap())
+                .filter(|allowed| *allowed)
+                .count()
+        });
+        assert_eq!(allowed, 3);
+    }
+
+    #[test]
+    fn rate_limit_rule_fires_once_the_window_is_full() {
+        let cfg = PolicyConfig::parse(
+            &json!({
+                "rules": [{
+                    "target": "http",
+                    "decision": "never_allow",
+                    "rate_limit": { "max_calls": 2, "per_seconds": 60 },
+                    "reason": "egress cap"
+                }],
+                "default": "always_allow"
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let session = "rate-limit-test-session";
+        let t0 = Utc::now();
+        let at = |secs: i64| t0 + chrono::Duration::seconds(secs);
+        let args = json!({ "url": "https://example.com" });
+        let admitted = |secs: i64| cfg.evaluate_at("http", &args, at(secs), Some(session), 0);
+
+        assert_eq!(admitted(0).decision, Decision::AlwaysAllow);
+        // A call the gate never lets run (denied by a later layer, refused at
+        // the prompt) gives its slot back.
+        let unadmitted = admitted(5);
+        assert_eq!(unadmitted.decision, Decision::AlwaysAllow);
+        unadmitted.release();
+        assert_eq!(admitted(10).decision, Decision::AlwaysAllow);
+        let verdict = admitted(20);
+        assert_eq!(verdict.decision, Decision::NeverAllow);
+        assert_eq!(verdict.rule, Some(0));
+        assert_eq!(
+            verdict.reason.as_deref(),
+            Some("egress cap; rule applies to more than 2 calls per 60s")
+        );
+        // Counts belong to the session, not to one run's cache: another
+        // session has its own budget, and this one is still full.
+        assert_eq!(
+            cfg.evaluate_at("http", &args, at(21), Some("another-session"), 0)
+                .decision,
+            Decision::AlwaysAllow
+        );
+        assert_eq!(
+            cfg.evaluate_at("http", &args, at(22), Some(session), 0)
+                .decision,
+            Decision::NeverAllow
+        );
+        // The first call ages out of the trailing window.
+        assert_eq!(admitted(61).decision, Decision::AlwaysAllow);
+        // Other targets have their own budget, and a stateless decide never
+        // counts or fires.
+        assert_eq!(
+            cfg.evaluate_at("tool:x", &args, at(62), Some(session), 0)
+                .decision,
+            Decision::AlwaysAllow
+        );
+        assert_eq!(cfg.decide("http", &args).0, Decision::AlwaysAllow);
+    }
+
+    #[test]
+    fn time_window_rule_applies_only_inside_the_window() {
+        let cfg = PolicyConfig::parse(
+            &json!({
+                "rules": [{
+                    "target": "tool:deploy",
+                    "decision": "never_allow",
+                    "time_window": { "start": "18:00", "end": "08:00", "timezone": "America/New_York" },
+                    "reason": "no deploys after hours"
+                }, {
+                    "target": "tool:deploy",
+                    "decision": "never_allow",
+                    "time_window": { "start": "00:00", "end": "23:59", "days": ["sat", "sun"] }
+                }],
+                "default": "always_allow"
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let at = |rfc3339: &str| {
+            DateTime::parse_from_rfc3339(rfc3339)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        let decide = |now| {
+            cfg.evaluate_at("tool:deploy", &json!({}), now, None, 0)
+                .decision
+        };
+
+        // Wednesday 2026-03-11: 14:00 UTC is 10:00 in New York.
+        assert_eq!(decide(at("2026-03-11T14:00:00Z")), Decision::AlwaysAllow);
+        // 23:30 UTC is 19:30 in New York — past 18:00.
+        assert_eq!(decide(at("2026-03-11T23:30:00Z")), Decision::NeverAllow);
+        // 11:00 UTC is 07:00 in New York — before 08:00 (wrapped window).
+        assert_eq!(decide(at("2026-03-11T11:00:00Z")), Decision::NeverAllow);
+        // Saturday midday (UTC weekday window).
+        assert_eq!(decide(at("2026-03-14T15:00:00Z")), Decision::NeverAllow);
+    }
+
+    #[test]
+    fn verdicts_and_explanations_name_the_deciding_layer_and_rule() {
+        let base = PolicyConfig::parse(
+            &json!({
+                "rules": [
+                    { "target": "tool:deploy", "decision": "never_allow" },
+                    { "target": "http", "decision": "always_allow",
+                      "match_args": { "url": { "$glob": "https://internal.*" } } },
+                    { "target": "http", "decision": "never_allow",
+                      "rate_limit": { "max_calls": 5, "per_seconds": 60 } },
+                    { "target": "http", "decision": "ask_before", "reason": "external egress" }
+                ],
+                "default": "always_allow"
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let base = PolicyConfig {
+            label: Some("ops.json".to_string()),
+            ..base
+        };
+
+        let internal = json!({ "url": "https://internal.example/x" });
+        let verdict = base.evaluate("http", &internal, None);
+        assert_eq!(
+            (verdict.decision, verdict.layer, verdict.rule),
+            (Decision::AlwaysAllow, 0, Some(1))
+        );
+
+        let external = json!({ "url": "https://example.com" });
+        let explained = base.explain("http", &external);
+        assert_eq!(explained.verdict.rule, Some(3));
+        assert_eq!(explained.verdict.reason.as_deref(), Some("external egress"));
+        let checks = &explained.layers[0].checks;
+        // Only rules for this target are listed; the rate-limited rule is
+        // passed over because explain keeps no call history.
+        assert_eq!(
+            checks.iter().map(|c| c.index).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert!(checks[0].outcome.contains("match_args"));
+        assert!(checks[1].outcome.contains("5 calls per 60s"));
+        assert_eq!(checks[2].outcome, "matched");
+
+        // Layering a profile: the stricter layer decides, and an unlisted
+        // target falls to a layer default (`rule: None`).
+        let layered = base.restricted_by(Arc::new(builtin_profile("untrusted").unwrap()));
+        let verdict = layered.evaluate("http", &internal, None);
+        assert_eq!(verdict.decision, Decision::NeverAllow);
+        assert_eq!(verdict.layer, 1);
+        assert_eq!(verdict.layer_label.as_deref(), Some("untrusted"));
+        let explained = layered.explain("http", &internal);
+        assert_eq!(explained.layers.len(), 2);
+        assert_eq!(explained.verdict, verdict);
+        let text = explained.to_string();
+        assert!(text.contains("layer 0 (ops.json)"), "{text}");
+        assert!(text.contains("from layer 1 (untrusted)"), "{text}");
+
+        let verdict = layered.evaluate("tool:deploy", &json!({}), None);
+        assert_eq!((verdict.layer, verdict.rule), (0, Some(0)));
+    }
+
     #[test]
     fn default_profile_allows_everything() {
         // The permissive posture (`--trusted`, or an explicit empty policy)
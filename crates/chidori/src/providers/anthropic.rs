@@ -1,4 +1,4 @@
-use anyhow::{bail, Context, Result};
+use anyhow::{Context, Result};
 use reqwest::Client;
 use serde::Deserialize;
 use serde_json::{json, Value};
@@ -7,7 +7,10 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use super::rate_limit::RateLimiter;
-use super::{CacheTtl, ContentBlock, LlmProvider, LlmRequest, LlmResponse, TokenSink, ToolCall};
+use super::{
+    CacheTtl, ContentBlock, LlmProvider, LlmRequest, LlmResponse, MediaSource, ProviderHttpError,
+    ReasoningConfig, TokenSink, ToolCall,
+};
 
 const ANTHROPIC_API_URL: &str = "https://api.anthropic.com/v1/messages";
 const ANTHROPIC_VERSION: &str = "2023-06-01";
@@ -16,6 +19,12 @@ const ANTHROPIC_VERSION: &str = "2023-06-01";
 const ANTHROPIC_EXTENDED_CACHE_TTL_BETA: &str = "extended-cache-ttl-2025-04-11";
 /// Anthropic allows at most 4 cache breakpoints per request.
 const ANTHROPIC_MAX_CACHE_BREAKPOINTS: usize = 4;
+/// The tool a structured-output request forces the model to call: Anthropic
+/// has no response-format switch, so the schema becomes this tool's input
+/// schema and the tool input becomes the reply.
+const STRUCTURED_OUTPUT_TOOL: &str = "structured_output";
+/// Smallest extended-thinking budget the API accepts.
+const ANTHROPIC_MIN_THINKING_BUDGET: u64 = 1024;
 
 pub struct AnthropicProvider {
     api_key: String,
@@ -57,6 +66,13 @@ enum AnthropicResponseBlock {
         name: String,
         input: Value,
     },
+    Thinking {
+        thinking: String,
+        signature: String,
+    },
+    RedactedThinking {
+        data: String,
+    },
     #[serde(other)]
     Other,
 }
@@ -161,6 +177,13 @@ fn build_request_body(request: &LlmRequest, stream: bool) -> Result<(Value, bool
             })
         })
         .collect();
+    if let Some(ref schema) = request.response_schema {
+        tools_json.push(json!({
+            "name": STRUCTURED_OUTPUT_TOOL,
+            "description": "Respond with the final answer, shaped by this input schema.",
+            "input_schema": structured_output_input_schema(schema).0,
+        }));
+    }
     if let Some(ttl) = request.cache.tools {
         if let Some(last) = tools_json.last_mut() {
             last["cache_control"] = note_ttl(ttl);
@@ -189,9 +212,72 @@ fn build_request_body(request: &LlmRequest, stream: bool) -> Result<(Value, bool
     if !tools_json.is_empty() {
         body["tools"] = Value::Array(tools_json);
     }
+    let thinking_budget = request
+        .reasoning
+        .as_ref()
+        .and_then(ReasoningConfig::budget)
+        .map(|budget| budget.max(ANTHROPIC_MIN_THINKING_BUDGET));
+    if let Some(budget) = thinking_budget {
+        // `max_tokens` caps thinking and answer together; the budget rides on
+        // top so the visible answer keeps the author's `maxTokens`.
+        body["max_tokens"] = json!(request.max_tokens + budget);
+        body["thinking"] = json!({ "type": "enabled", "budget_tokens": budget });
+    }
+    if request.response_schema.is_some() {
+        // Thinking rejects a forced tool choice; the model is left to pick
+        // the structured-output tool and the runtime's validation re-asks
+        // when it answers in prose instead.
+        body["tool_choice"] = match thinking_budget {
+            Some(_) => json!({ "type": "auto" }),
+            None => json!({ "type": "tool", "name": STRUCTURED_OUTPUT_TOOL }),
+        };
+    }
     Ok((body, needs_one_hour))
 }
 
+/// Tool input schemas must describe an object; any other root (an array, a
+/// string enum) is wrapped as the `value` property. Reports whether it was.
+fn structured_output_input_schema(schema: &Value) -> (Value, bool) {
+    if schema.get("type").and_then(Value::as_str) == Some("object") {
+        return (schema.clone(), false);
+    }
+    let wrapped = json!({
+        "type": "object",
+        "properties": { "value": schema },
+        "required": ["value"],
+    });
+    (wrapped, true)
+}
+
+/// Turn the forced `structured_output` call back into a plain JSON reply:
+/// its input (unwrapped) becomes the response text, so callers see the same
+/// shape as a provider with native structured output. Returns whether it did.
+fn lift_structured_output(request: &LlmRequest, response: &mut LlmResponse) -> bool {
+    let Some(ref schema) = request.response_schema else {
+        return false;
+    };
+    let Some(pos) = response
+        .tool_calls
+        .iter()
+        .position(|call| call.name == STRUCTURED_OUTPUT_TOOL)
+    else {
+        return false;
+    };
+    let call = response.tool_calls.remove(pos);
+    let value = match structured_output_input_schema(schema) {
+        (_, true) => call.input.get("value").cloned().unwrap_or(Value::Null),
+        (_, false) => call.input,
+    };
+    response.content = value.to_string();
+    response.blocks = vec![ContentBlock::Text {
+        text: response.content.clone(),
+    }];
+    if response.stop_reason == "tool_use" {
+        response.stop_reason = "end_turn".to_string();
+    }
+    true
+}
+
 /// Map common short aliases to canonical Anthropic model ids. Agents
 /// in the wild often write `model = "claude-sonnet"` rather than the
 /// full versioned id (`claude-sonnet-4-6`); without this resolution
@@ -216,6 +302,10 @@ pub(crate) fn resolve_alias(model: &str) -> &str {
 
 #[async_trait::async_trait]
 impl LlmProvider for AnthropicProvider {
+    fn name(&self) -> &str {
+        "anthropic"
+    }
+
     fn supports_model(&self, model: &str) -> bool {
         model.starts_with("claude")
     }
@@ -249,7 +339,12 @@ impl LlmProvider for AnthropicProvider {
             if status.as_u16() == 429 {
                 attempt += 1;
                 if attempt >= 8 {
-                    bail!("Anthropic rate limit: exceeded max retries after 429");
+                    return Err(ProviderHttpError::new(
+                        "Anthropic rate limit",
+                        status,
+                        "exceeded max retries",
+                    )
+                    .into());
                 }
                 let wait = retry_after_duration(resp.headers(), attempt);
                 tracing::warn!(attempt, ?wait, "Anthropic 429 — backing off");
@@ -263,15 +358,18 @@ impl LlmProvider for AnthropicProvider {
                 .context("Failed to read Anthropic response")?;
             if !status.is_success() {
                 if let Ok(err) = serde_json::from_str::<AnthropicError>(&resp_text) {
-                    bail!("Anthropic API error ({}): {}", status, err.error.message);
+                    return Err(
+                        ProviderHttpError::new("Anthropic API", status, err.error.message).into(),
+                    );
                 }
-                bail!("Anthropic API error ({}): {}", status, resp_text);
+                return Err(ProviderHttpError::new("Anthropic API", status, resp_text).into());
             }
 
             let parsed: AnthropicResponseBody =
                 serde_json::from_str(&resp_text).context("Failed to parse Anthropic response")?;
 
             let mut text_parts = Vec::new();
+            let mut thinking_parts = Vec::new();
             let mut tool_calls = Vec::new();
             let mut blocks = Vec::new();
             for block in parsed.content {
@@ -280,19 +378,37 @@ impl LlmProvider for AnthropicProvider {
                         text_parts.push(text.clone());
                         blocks.push(ContentBlock::Text { text });
                     }
+                    AnthropicResponseBlock::Thinking {
+                        thinking,
+                        signature,
+                    } => {
+                        thinking_parts.push(thinking.clone());
+                        blocks.push(ContentBlock::Thinking {
+                            thinking,
+                            signature,
+                        });
+                    }
+                    AnthropicResponseBlock::RedactedThinking { data } => {
+                        blocks.push(ContentBlock::RedactedThinking { data });
+                    }
                     AnthropicResponseBlock::ToolUse { id, name, input } => {
                         tool_calls.push(ToolCall {
                             id: id.clone(),
                             name: name.clone(),
                             input: input.clone(),
                         });
-                        blocks.push(ContentBlock::ToolUse { id, name, input });
+                        blocks.push(ContentBlock::ToolUse {
+                            id,
+                            name,
+                            input,
+                            thought_signature: None,
+                        });
                     }
                     AnthropicResponseBlock::Other => {}
                 }
             }
 
-            return Ok(LlmResponse {
+            let mut response = LlmResponse {
                 content: text_parts.join(""),
                 blocks,
                 tool_calls,
@@ -301,8 +417,12 @@ impl LlmProvider for AnthropicProvider {
                 output_tokens: parsed.usage.output_tokens,
                 cache_creation_tokens: parsed.usage.cache_creation_input_tokens,
                 cache_read_tokens: parsed.usage.cache_read_input_tokens,
-                reasoning: None,
-            });
+                reasoning_tokens: 0,
+                reasoning: (!thinking_parts.is_empty()).then(|| thinking_parts.join("\n\n")),
+                served_by: None,
+            };
+            lift_structured_output(request, &mut response);
+            return Ok(response);
         }
     }
 
@@ -346,7 +466,12 @@ impl LlmProvider for AnthropicProvider {
                 if r.status().as_u16() == 429 {
                     attempt += 1;
                     if attempt >= 8 {
-                        bail!("Anthropic rate limit: exceeded max retries after 429");
+                        return Err(ProviderHttpError::new(
+                            "Anthropic rate limit",
+                            r.status(),
+                            "exceeded max retries",
+                        )
+                        .into());
                     }
                     let wait = retry_after_duration(r.headers(), attempt);
                     tracing::warn!(attempt, ?wait, "Anthropic 429 (stream) — backing off");
@@ -361,7 +486,7 @@ impl LlmProvider for AnthropicProvider {
         if !resp.status().is_success() {
             let status = resp.status();
             let text = resp.text().await.unwrap_or_default();
-            bail!("Anthropic stream error ({}): {}", status, text);
+            return Err(ProviderHttpError::new("Anthropic stream", status, text).into());
         }
 
         // Accumulators for the final response. Text chunks land in
@@ -381,6 +506,12 @@ impl LlmProvider for AnthropicProvider {
         let mut pending_text: String = String::new();
         let mut pending_tool: Option<(String, String, String)> = None; // (id, name, json_buf)
 
+        // Thinking text and signature both arrive as deltas and are kept
+        // verbatim: the block is echoed back on the next tool-loop turn.
+        let mut pending_thinking: Option<(String, String)> = None;
+        let mut pending_redacted: Option<String> = None;
+        let mut thinking_parts: Vec<String> = Vec::new();
+
         let mut stream = resp.bytes_stream();
         let mut buffer = String::new();
 
@@ -447,6 +578,18 @@ impl LlmProvider for AnthropicProvider {
                                     .to_string();
                                 pending_tool = Some((id, name, String::new()));
                             }
+                            Some("thinking") => {
+                                pending_thinking = Some((String::new(), String::new()));
+                            }
+                            Some("redacted_thinking") => {
+                                pending_redacted = Some(
+                                    block
+                                        .and_then(|b| b.get("data"))
+                                        .and_then(|v| v.as_str())
+                                        .unwrap_or("")
+                                        .to_string(),
+                                );
+                            }
                             _ => {}
                         }
                     }
@@ -468,11 +611,39 @@ impl LlmProvider for AnthropicProvider {
                                         tool.2.push_str(partial);
                                     }
                                 }
+                                // Thinking never reaches the token sink: it is
+                                // not part of the visible answer.
+                                Some("thinking_delta") => {
+                                    if let (Some(thinking), Some(text)) = (
+                                        pending_thinking.as_mut(),
+                                        delta.get("thinking").and_then(|v| v.as_str()),
+                                    ) {
+                                        thinking.0.push_str(text);
+                                    }
+                                }
+                                Some("signature_delta") => {
+                                    if let (Some(thinking), Some(signature)) = (
+                                        pending_thinking.as_mut(),
+                                        delta.get("signature").and_then(|v| v.as_str()),
+                                    ) {
+                                        thinking.1.push_str(signature);
+                                    }
+                                }
                                 _ => {}
                             }
                         }
                     }
                     "content_block_stop" => {
+                        if let Some((thinking, signature)) = pending_thinking.take() {
+                            thinking_parts.push(thinking.clone());
+                            blocks.push(ContentBlock::Thinking {
+                                thinking,
+                                signature,
+                            });
+                        }
+                        if let Some(data) = pending_redacted.take() {
+                            blocks.push(ContentBlock::RedactedThinking { data });
+                        }
                         if pending_text_idx.is_some() {
                             blocks.push(ContentBlock::Text {
                                 text: pending_text.clone(),
@@ -498,7 +669,12 @@ impl LlmProvider for AnthropicProvider {
                                 name: name.clone(),
                                 input: input.clone(),
                             });
-                            blocks.push(ContentBlock::ToolUse { id, name, input });
+                            blocks.push(ContentBlock::ToolUse {
+                                id,
+                                name,
+                                input,
+                                thought_signature: None,
+                            });
                         }
                     }
                     "message_delta" => {
@@ -521,7 +697,7 @@ impl LlmProvider for AnthropicProvider {
             }
         }
 
-        Ok(LlmResponse {
+        let mut response = LlmResponse {
             content: text_buf,
             blocks,
             tool_calls,
@@ -530,14 +706,22 @@ impl LlmProvider for AnthropicProvider {
             output_tokens,
             cache_creation_tokens,
             cache_read_tokens,
-            reasoning: None,
-        })
+            reasoning_tokens: 0,
+            reasoning: (!thinking_parts.is_empty()).then(|| thinking_parts.join("\n\n")),
+            served_by: None,
+        };
+        // The structured reply streamed as tool-input JSON, not text deltas:
+        // surface it as one delta once assembled.
+        if lift_structured_output(request, &mut response) {
+            on_delta(&response.content);
+        }
+        Ok(response)
     }
 }
 
 /// Pick a wait duration for a 429 retry. Uses the `retry-after` header
 /// (seconds) when present; otherwise exponential backoff: 1s, 2s, 4s, 8s…
-fn retry_after_duration(headers: &reqwest::header::HeaderMap, attempt: u32) -> Duration {
+pub(super) fn retry_after_duration(headers: &reqwest::header::HeaderMap, attempt: u32) -> Duration {
     if let Some(v) = headers.get("retry-after").and_then(|v| v.to_str().ok()) {
         if let Ok(secs) = v.parse::<f64>() {
             return Duration::from_secs_f64(secs.max(0.5));
@@ -546,10 +730,51 @@ fn retry_after_duration(headers: &reqwest::header::HeaderMap, attempt: u32) -> D
     Duration::from_secs(1u64 << attempt.min(6))
 }
 
+/// Anthropic's `source` object for an image or document block. Plain-text
+/// documents use the `text` source form (the API rejects base64 text/plain);
+/// everything else is sent as base64. File sources are resolved by the host
+/// before send, so one reaching here is a wiring bug surfaced as a request
+/// the API will reject, not a panic.
+fn media_source_to_anthropic_json(source: &MediaSource) -> Value {
+    match source {
+        MediaSource::Base64 { media_type, data } if media_type == "text/plain" => {
+            use base64::Engine as _;
+            let text = base64::engine::general_purpose::STANDARD
+                .decode(data)
+                .ok()
+                .and_then(|bytes| String::from_utf8(bytes).ok())
+                .unwrap_or_else(|| data.clone());
+            json!({ "type": "text", "media_type": "text/plain", "data": text })
+        }
+        MediaSource::Base64 { media_type, data } => json!({
+            "type": "base64",
+            "media_type": media_type,
+            "data": data,
+        }),
+        MediaSource::File { path, .. } => json!({ "type": "file", "path": path }),
+    }
+}
+
 fn content_block_to_anthropic_json(block: &ContentBlock) -> Value {
     match block {
         ContentBlock::Text { text } => json!({ "type": "text", "text": text }),
-        ContentBlock::ToolUse { id, name, input } => {
+        ContentBlock::Image { source } => json!({
+            "type": "image",
+            "source": media_source_to_anthropic_json(source),
+        }),
+        ContentBlock::Document { source, title } => {
+            let mut block = json!({
+                "type": "document",
+                "source": media_source_to_anthropic_json(source),
+            });
+            if let Some(title) = title {
+                block["title"] = json!(title);
+            }
+            block
+        }
+        ContentBlock::ToolUse {
+            id, name, input, ..
+        } => {
             // Anthropic requires `input` to be an object. Defense in depth
             // against any already-stored history (or non-streaming producer)
             // that left a null / non-object here; see the parse-time guard
@@ -576,6 +801,18 @@ fn content_block_to_anthropic_json(block: &ContentBlock) -> Value {
             "content": content,
             "is_error": is_error,
         }),
+        ContentBlock::Thinking {
+            thinking,
+            signature,
+        } => json!({
+            "type": "thinking",
+            "thinking": thinking,
+            "signature": signature,
+        }),
+        ContentBlock::RedactedThinking { data } => json!({
+            "type": "redacted_thinking",
+            "data": data,
+        }),
     }
 }
 
@@ -597,6 +834,8 @@ mod tests {
                 input_schema: json!({ "type": "object", "properties": {} }),
             }],
             cache: CacheLayout::default(),
+            response_schema: None,
+            reasoning: None,
         }
     }
 
@@ -672,6 +911,148 @@ mod tests {
         assert_eq!(marked, vec![false, true, true]);
     }
 
+    #[test]
+    fn response_schema_forces_the_structured_output_tool_and_lifts_its_input() {
+        let mut request = base_request();
+        request.tools.clear();
+        request.response_schema = Some(json!({ "type": "array", "items": { "type": "string" } }));
+        let (body, _) = build_request_body(&request, false).unwrap();
+        assert_eq!(
+            body["tool_choice"],
+            json!({ "type": "tool", "name": STRUCTURED_OUTPUT_TOOL })
+        );
+        assert_eq!(body["tools"][0]["input_schema"]["type"], "object");
+        assert_eq!(
+            body["tools"][0]["input_schema"]["properties"]["value"]["type"],
+            "array"
+        );
+
+        let input = json!({ "value": ["a", "b"] });
+        let mut response = LlmResponse {
+            blocks: vec![ContentBlock::ToolUse {
+                id: "toolu_1".to_string(),
+                name: STRUCTURED_OUTPUT_TOOL.to_string(),
+                input: input.clone(),
+                thought_signature: None,
+            }],
+            tool_calls: vec![ToolCall {
+                id: "toolu_1".to_string(),
+                name: STRUCTURED_OUTPUT_TOOL.to_string(),
+                input,
+            }],
+            stop_reason: "tool_use".to_string(),
+            ..LlmResponse::default()
+        };
+        assert!(lift_structured_output(&request, &mut response));
+        assert_eq!(response.content, r#"["a","b"]"#);
+        assert!(response.tool_calls.is_empty());
+        assert_eq!(response.stop_reason, "end_turn");
+    }
+
+    #[test]
+    fn image_and_document_blocks_use_anthropic_source_shapes() {
+        let mut request = base_request();
+        request.messages = vec![crate::providers::Message {
+            role: "user".to_string(),
+            content: vec![
+                ContentBlock::Text {
+                    text: "compare".to_string(),
+                },
+                ContentBlock::Image {
+                    source: MediaSource::Base64 {
+                        media_type: "image/png".to_string(),
+                        data: "iVBORw0=".to_string(),
+                    },
+                },
+                ContentBlock::Document {
+                    source: MediaSource::Base64 {
+                        media_type: "application/pdf".to_string(),
+                        data: "JVBERi0=".to_string(),
+                    },
+                    title: Some("report".to_string()),
+                },
+                ContentBlock::Document {
+                    source: MediaSource::Base64 {
+                        media_type: "text/plain".to_string(),
+                        data: "aGVsbG8=".to_string(),
+                    },
+                    title: None,
+                },
+            ],
+            cache_control: None,
+        }];
+        let (body, _) = build_request_body(&request, false).unwrap();
+        let content = &body["messages"][0]["content"];
+        assert_eq!(
+            content[1],
+            json!({ "type": "image", "source": { "type": "base64", "media_type": "image/png", "data": "iVBORw0=" } })
+        );
+        assert_eq!(content[2]["type"], "document");
+        assert_eq!(content[2]["title"], "report");
+        assert_eq!(content[2]["source"]["media_type"], "application/pdf");
+        // Plain-text documents are sent in the decoded `text` source form.
+        assert_eq!(
+            content[3]["source"],
+            json!({ "type": "text", "media_type": "text/plain", "data": "hello" })
+        );
+    }
+
+    #[test]
+    fn reasoning_enables_thinking_and_echoes_thinking_blocks_verbatim() {
+        let mut request = base_request();
+        request.reasoning = Some(ReasoningConfig {
+            budget_tokens: Some(4000),
+            effort: None,
+        });
+        request.response_schema = Some(json!({ "type": "object" }));
+        request
+            .messages
+            .push(crate::providers::Message::assistant_blocks(vec![
+                ContentBlock::Thinking {
+                    thinking: "check the file first".to_string(),
+                    signature: "sig==".to_string(),
+                },
+                ContentBlock::RedactedThinking {
+                    data: "opaque".to_string(),
+                },
+                ContentBlock::ToolUse {
+                    id: "toolu_1".to_string(),
+                    name: "read".to_string(),
+                    input: json!({}),
+                    thought_signature: None,
+                },
+            ]));
+        let (body, _) = build_request_body(&request, false).unwrap();
+        assert_eq!(
+            body["thinking"],
+            json!({ "type": "enabled", "budget_tokens": 4000 })
+        );
+        assert_eq!(body["max_tokens"], 4016);
+        // A forced tool choice is rejected alongside thinking.
+        assert_eq!(body["tool_choice"], json!({ "type": "auto" }));
+        let echoed = &body["messages"][1]["content"];
+        assert_eq!(
+            echoed[0],
+            json!({ "type": "thinking", "thinking": "check the file first", "signature": "sig==" })
+        );
+        assert_eq!(
+            echoed[1],
+            json!({ "type": "redacted_thinking", "data": "opaque" })
+        );
+
+        // An explicit budget below the API minimum is raised to it.
+        request.reasoning = Some(ReasoningConfig {
+            budget_tokens: Some(10),
+            effort: None,
+        });
+        let (body, _) = build_request_body(&request, false).unwrap();
+        assert_eq!(
+            body["thinking"]["budget_tokens"],
+            ANTHROPIC_MIN_THINKING_BUDGET
+        );
+        assert!(build_request_body(&base_request(), false).unwrap().0["thinking"].is_null());
+    }
+
     #[test]
     fn usage_cache_token_fields_parse_and_default() {
         let with_cache: AnthropicUsage = serde_json::from_str(
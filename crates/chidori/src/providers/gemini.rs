@@ -0,0 +1,923 @@
+//! Google Gemini provider, speaking the native `generateContent` API rather
+//! than the OpenAI-compat shim: system instructions, function declarations,
+//! inline media, safety blocks, and `cachedContents` context caching all map
+//! directly.
+//!
+//! Caching works differently from Anthropic's inline breakpoints. Gemini
+//! caches a *prefix resource* — system instruction, tools, and leading
+//! contents — created up front and referenced by name. The request's
+//! [`CacheLayout`] and message marks pick that prefix; created resources are
+//! remembered in-process by content digest until their TTL lapses, so a loop
+//! re-sending the same head creates it once. Creation failing (most often
+//! because the prefix is under the model's minimum cacheable size) falls back
+//! to an uncached request: caching is a billing optimization, never a reason
+//! to fail a call.
+
+use anyhow::{bail, Context, Result};
+use reqwest::Client;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::anthropic::retry_after_duration;
+use super::rate_limit::RateLimiter;
+use super::{
+    CacheTtl, ContentBlock, LlmProvider, LlmRequest, LlmResponse, MediaSource, Message,
+    ProviderHttpError, ReasoningConfig, TokenSink, ToolCall,
+};
+
+const GEMINI_API_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
+
+/// A cached-content name is dropped this long before its server-side expiry,
+/// so a request never references a resource that lapses in flight.
+const CACHE_EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+
+/// Finish reasons meaning the candidate was withheld or cut by a content
+/// filter rather than ending naturally.
+const SAFETY_FINISH_REASONS: &[&str] = &[
+    "SAFETY",
+    "RECITATION",
+    "BLOCKLIST",
+    "PROHIBITED_CONTENT",
+    "SPII",
+    "IMAGE_SAFETY",
+];
+
+pub struct GeminiProvider {
+    api_key: String,
+    base_url: String,
+    client: Client,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    /// Prefix digest → `cachedContents/…` name and local expiry. `None`
+    /// records a failed creation, so an uncacheable prefix is not retried on
+    /// every call of a loop.
+    cached_contents: Mutex<HashMap<String, (Option<String>, Instant)>>,
+}
+
+impl GeminiProvider {
+    pub fn new(api_key: String) -> Self {
+        Self::with_base_url(api_key, GEMINI_API_URL.to_string())
+    }
+
+    /// Point the provider at a different API root (a regional endpoint, a
+    /// proxy, or a local mock). The root is the segment `models/…` and
+    /// `cachedContents` hang off, e.g. `…/v1beta`.
+    pub fn with_base_url(api_key: String, base_url: String) -> Self {
+        Self {
+            api_key,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client: Client::new(),
+            rate_limiter: None,
+            cached_contents: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn with_rate_limit(mut self, rpm: u32) -> Self {
+        self.rate_limiter = Some(Arc::new(RateLimiter::new(rpm)));
+        self
+    }
+
+    /// POST `body` to `url`, backing off on 429 the same way the Anthropic
+    /// provider does. Non-success statuses other than 429 are returned to the
+    /// caller, which owns the error message.
+    async fn post(&self, url: &str, body: &Value, accept_sse: bool) -> Result<reqwest::Response> {
+        let mut attempt = 0u32;
+        loop {
+            if let Some(ref rl) = self.rate_limiter {
+                rl.acquire().await;
+            }
+            let mut req = self
+                .client
+                .post(url)
+                .header("x-goog-api-key", &self.api_key)
+                .header("content-type", "application/json");
+            if accept_sse {
+                req = req.header("accept", "text/event-stream");
+            }
+            let resp = req
+                .json(body)
+                .send()
+                .await
+                .context("Failed to send request to Gemini API")?;
+            if resp.status().as_u16() == 429 {
+                attempt += 1;
+                if attempt >= 8 {
+                    return Err(ProviderHttpError::new(
+                        "Gemini rate limit",
+                        resp.status(),
+                        "exceeded max retries",
+                    )
+                    .into());
+                }
+                let wait = retry_after_duration(resp.headers(), attempt);
+                tracing::warn!(attempt, ?wait, "Gemini 429 — backing off");
+                tokio::time::sleep(wait).await;
+                continue;
+            }
+            return Ok(resp);
+        }
+    }
+
+    /// Build the `generateContent` body, creating (or reusing) a cached
+    /// prefix when the request asks for caching. Returns the body and the
+    /// tokens written to a cache *by this call* (zero on reuse).
+    async fn prepare(&self, request: &LlmRequest) -> Result<(Value, u64)> {
+        let model = model_path(&request.model);
+        let system = request
+            .system
+            .as_ref()
+            .map(|text| json!({ "parts": [{ "text": text }] }));
+        let tools = tools_json(request);
+        let contents = contents_json(&request.messages);
+
+        let mut body = json!({
+            "generationConfig": {
+                "temperature": request.temperature,
+                "maxOutputTokens": request.max_tokens,
+            },
+        });
+        if let Some(ref schema) = request.response_schema {
+            // Full JSON Schema, like `parametersJsonSchema` for tools.
+            body["generationConfig"]["responseMimeType"] = json!("application/json");
+            body["generationConfig"]["responseJsonSchema"] = schema.clone();
+        }
+        if let Some(budget) = request.reasoning.as_ref().and_then(ReasoningConfig::budget) {
+            body["generationConfig"]["thinkingConfig"] = json!({
+                "thinkingBudget": budget,
+                "includeThoughts": true,
+            });
+        }
+
+        let mut cache_written = 0;
+        let mut uncached_from = 0;
+        if let Some((prefix_len, ttl)) = cache_prefix(request) {
+            let mut cache_body = json!({
+                "model": model,
+                "contents": non_empty(&contents[..prefix_len]),
+                "ttl": format!("{}s", ttl_secs(ttl)),
+            });
+            if let Some(ref system) = system {
+                cache_body["systemInstruction"] = system.clone();
+            }
+            if let Some(ref tools) = tools {
+                cache_body["tools"] = tools.clone();
+            }
+            if let Some((name, written)) = self.cached_content(&cache_body, ttl).await {
+                body["cachedContent"] = Value::String(name);
+                cache_written = written;
+                uncached_from = prefix_len;
+            }
+        }
+
+        // A request naming a cached content must not repeat what the cache
+        // holds: the system instruction, the tools, and the prefix contents.
+        body["contents"] = Value::Array(non_empty(&contents[uncached_from..]));
+        if body.get("cachedContent").is_none() {
+            if let Some(system) = system {
+                body["systemInstruction"] = system;
+            }
+            if let Some(tools) = tools {
+                body["tools"] = tools;
+            }
+        }
+        Ok((body, cache_written))
+    }
+
+    /// Look up or create the cached content for `cache_body`. Returns the
+    /// resource name and the token count written if this call created it.
+    async fn cached_content(&self, cache_body: &Value, ttl: CacheTtl) -> Option<(String, u64)> {
+        let digest = hex::encode(Sha256::digest(cache_body.to_string().as_bytes()));
+        let now = Instant::now();
+        if let Some((name, expires)) = self.cached_contents.lock().unwrap().get(&digest) {
+            if *expires > now {
+                return name.clone().map(|name| (name, 0));
+            }
+        }
+
+        let lifetime = Duration::from_secs(ttl_secs(ttl)).saturating_sub(CACHE_EXPIRY_MARGIN);
+        let created = self.create_cached_content(cache_body).await;
+        let entry = match &created {
+            Ok((name, _)) => Some(name.clone()),
+            Err(err) => {
+                tracing::debug!(error = %err, "Gemini context cache unavailable; sending uncached");
+                None
+            }
+        };
+        self.cached_contents
+            .lock()
+            .unwrap()
+            .insert(digest, (entry, now + lifetime));
+        created.ok()
+    }
+
+    async fn create_cached_content(&self, cache_body: &Value) -> Result<(String, u64)> {
+        let url = format!("{}/cachedContents", self.base_url);
+        let resp = self.post(&url, cache_body, false).await?;
+        let status = resp.status();
+        let text = resp
+            .text()
+            .await
+            .context("Failed to read Gemini cachedContents response")?;
+        if !status.is_success() {
+            return Err(ProviderHttpError::new(
+                "Gemini cachedContents",
+                status,
+                error_message(&text),
+            )
+            .into());
+        }
+        let parsed: Value = serde_json::from_str(&text)
+            .context("Failed to parse Gemini cachedContents response")?;
+        let name = parsed
+            .get("name")
+            .and_then(Value::as_str)
+            .context("Gemini cachedContents response has no name")?
+            .to_string();
+        let tokens = parsed
+            .get("usageMetadata")
+            .and_then(|u| u.get("totalTokenCount"))
+            .and_then(Value::as_u64)
+            .unwrap_or(0);
+        Ok((name, tokens))
+    }
+}
+
+/// `models/<id>`, the resource form both `generateContent` URLs and
+/// `cachedContents` bodies use. Accepts ids already carrying the prefix.
+fn model_path(model: &str) -> String {
+    if model.starts_with("models/") {
+        model.to_string()
+    } else {
+        format!("models/{model}")
+    }
+}
+
+fn ttl_secs(ttl: CacheTtl) -> u64 {
+    match ttl {
+        CacheTtl::FiveMinutes => 300,
+        CacheTtl::OneHour => 3600,
+    }
+}
+
+/// The cacheable prefix a request asks for: how many leading messages it
+/// covers and the TTL. Gemini caches one prefix per request, so the latest
+/// message mark wins (its prefix subsumes earlier ones), and the system
+/// instruction and tools always ride along — a request referencing a cache
+/// cannot carry them separately. The longest requested TTL applies.
+fn cache_prefix(request: &LlmRequest) -> Option<(usize, CacheTtl)> {
+    let mut ttl: Option<CacheTtl> = None;
+    let mut note = |t: CacheTtl| {
+        if ttl != Some(CacheTtl::OneHour) {
+            ttl = Some(t);
+        }
+    };
+    if let Some(t) = request.cache.system.filter(|_| request.system.is_some()) {
+        note(t);
+    }
+    if let Some(t) = request.cache.tools.filter(|_| !request.tools.is_empty()) {
+        note(t);
+    }
+    let mut prefix_len = 0;
+    for (i, message) in request.messages.iter().enumerate() {
+        if let Some(t) = message.cache_control {
+            note(t);
+            prefix_len = i + 1;
+        }
+    }
+    // The request itself must keep at least one content to generate from.
+    if prefix_len == request.messages.len() {
+        prefix_len = prefix_len.saturating_sub(1);
+    }
+    ttl.map(|ttl| (prefix_len, ttl))
+}
+
+fn tools_json(request: &LlmRequest) -> Option<Value> {
+    if request.tools.is_empty() {
+        return None;
+    }
+    // `parametersJsonSchema` takes full JSON Schema; the older `parameters`
+    // field only accepts an OpenAPI subset and rejects common keywords such
+    // as `additionalProperties`.
+    let declarations: Vec<Value> = request
+        .tools
+        .iter()
+        .map(|t| {
+            json!({
+                "name": t.name,
+                "description": t.description,
+                "parametersJsonSchema": t.input_schema,
+            })
+        })
+        .collect();
+    Some(json!([{ "functionDeclarations": declarations }]))
+}
+
+/// Translate messages into Gemini `contents`, one entry per message so
+/// message indices (and therefore cache marks) line up with content indices.
+/// Gemini function responses are keyed by function *name*, not call id, so
+/// names are recovered from the `ToolUse` blocks earlier in the conversation.
+fn contents_json(messages: &[Message]) -> Vec<Value> {
+    let mut names_by_id: HashMap<&str, &str> = HashMap::new();
+    messages
+        .iter()
+        .map(|m| {
+            let role = if m.role == "assistant" {
+                "model"
+            } else {
+                "user"
+            };
+            let parts: Vec<Value> =
+                m.content
+                    .iter()
+                    .filter_map(|block| {
+                        Some(match block {
+                            ContentBlock::Text { text } => json!({ "text": text }),
+                            ContentBlock::Image { source }
+                            | ContentBlock::Document { source, .. } => inline_data_json(source),
+                            ContentBlock::ToolUse {
+                                id,
+                                name,
+                                input,
+                                thought_signature,
+                            } => {
+                                names_by_id.insert(id.as_str(), name.as_str());
+                                let mut part =
+                                    json!({ "functionCall": { "name": name, "args": input } });
+                                // Thinking models reject a replayed call
+                                // without the signature they issued with it.
+                                if let Some(signature) = thought_signature {
+                                    part["thoughtSignature"] = json!(signature);
+                                }
+                                part
+                            }
+                            ContentBlock::ToolResult {
+                                tool_use_id,
+                                content,
+                                is_error,
+                            } => {
+                                let name = names_by_id
+                                    .get(tool_use_id.as_str())
+                                    .copied()
+                                    .unwrap_or(tool_use_id.as_str());
+                                let key = if *is_error { "error" } else { "output" };
+                                json!({
+                                    "functionResponse": {
+                                        "name": name,
+                                        "response": { key: content },
+                                    }
+                                })
+                            }
+                            // Anthropic thinking is signed for Anthropic alone.
+                            ContentBlock::Thinking { .. }
+                            | ContentBlock::RedactedThinking { .. } => return None,
+                        })
+                    })
+                    .collect();
+            json!({ "role": role, "parts": parts })
+        })
+        .collect()
+}
+
+/// Gemini rejects contents with no parts; a message that carried nothing
+/// translatable is dropped rather than sent empty.
+fn non_empty(contents: &[Value]) -> Vec<Value> {
+    contents
+        .iter()
+        .filter(|c| c["parts"].as_array().is_some_and(|p| !p.is_empty()))
+        .cloned()
+        .collect()
+}
+
+/// Inline media part. File sources are resolved by the host before send; one
+/// reaching here passes its path through and the API rejects it.
+fn inline_data_json(source: &MediaSource) -> Value {
+    match source {
+        MediaSource::Base64 { media_type, data } => {
+            json!({ "inlineData": { "mimeType": media_type, "data": data } })
+        }
+        MediaSource::File { path, media_type } => json!({
+            "fileData": {
+                "mimeType": media_type.as_deref().unwrap_or("application/octet-stream"),
+                "fileUri": path,
+            }
+        }),
+    }
+}
+
+fn error_message(body: &str) -> String {
+    serde_json::from_str::<Value>(body)
+        .ok()
+        .and_then(|v| v["error"]["message"].as_str().map(str::to_string))
+        .unwrap_or_else(|| body.to_string())
+}
+
+/// Folds `GenerateContentResponse` objects into an [`LlmResponse`]. The
+/// non-streaming path absorbs one; the SSE path absorbs one per frame, each
+/// carrying the next slice of parts plus cumulative usage.
+#[derive(Default)]
+struct Accumulator {
+    text: String,
+    reasoning: String,
+    blocks: Vec<ContentBlock>,
+    tool_calls: Vec<ToolCall>,
+    finish_reason: Option<String>,
+    block_reason: Option<String>,
+    prompt_tokens: u64,
+    cached_tokens: u64,
+    output_tokens: u64,
+    thought_tokens: u64,
+}
+
+impl Accumulator {
+    fn absorb(&mut self, chunk: &Value, mut on_delta: Option<&mut TokenSink>) {
+        if let Some(reason) = chunk["promptFeedback"]["blockReason"].as_str() {
+            self.block_reason = Some(reason.to_string());
+        }
+        if let Some(usage) = chunk.get("usageMetadata") {
+            let count = |key: &str| usage.get(key).and_then(Value::as_u64).unwrap_or(0);
+            self.prompt_tokens = count("promptTokenCount");
+            self.cached_tokens = count("cachedContentTokenCount");
+            // Thinking tokens are billed as output.
+            self.thought_tokens = count("thoughtsTokenCount");
+            self.output_tokens = count("candidatesTokenCount") + self.thought_tokens;
+        }
+        let Some(candidate) = chunk["candidates"].get(0) else {
+            return;
+        };
+        if let Some(reason) = candidate["finishReason"].as_str() {
+            self.finish_reason = Some(reason.to_string());
+        }
+        for part in candidate["content"]["parts"]
+            .as_array()
+            .into_iter()
+            .flatten()
+        {
+            if let Some(call) = part.get("functionCall") {
+                let name = call["name"].as_str().unwrap_or_default().to_string();
+                let id = call["id"]
+                    .as_str()
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("call_{}", uuid::Uuid::new_v4().simple()));
+                let input = match call.get("args") {
+                    Some(args) if args.is_object() => args.clone(),
+                    _ => json!({}),
+                };
+                self.tool_calls.push(ToolCall {
+                    id: id.clone(),
+                    name: name.clone(),
+                    input: input.clone(),
+                });
+                self.blocks.push(ContentBlock::ToolUse {
+                    id,
+                    name,
+                    input,
+                    thought_signature: part["thoughtSignature"].as_str().map(str::to_string),
+                });
+            } else if let Some(text) = part["text"].as_str() {
+                if part["thought"].as_bool() == Some(true) {
+                    self.reasoning.push_str(text);
+                    continue;
+                }
+                if text.is_empty() {
+                    continue;
+                }
+                self.text.push_str(text);
+                if let Some(sink) = on_delta.as_deref_mut() {
+                    sink(text);
+                }
+                // Consecutive text parts (one per SSE frame) merge into one
+                // block, matching the shape the other providers return.
+                match self.blocks.last_mut() {
+                    Some(ContentBlock::Text { text: last }) => last.push_str(text),
+                    _ => self.blocks.push(ContentBlock::Text {
+                        text: text.to_string(),
+                    }),
+                }
+            }
+        }
+    }
+
+    fn finish(self, cache_creation_tokens: u64) -> Result<LlmResponse> {
+        if let Some(reason) = self.block_reason {
+            bail!("Gemini blocked the prompt ({reason})");
+        }
+        let finish = self.finish_reason.unwrap_or_default();
+        let safety = SAFETY_FINISH_REASONS.contains(&finish.as_str());
+        if safety && self.text.is_empty() && self.tool_calls.is_empty() {
+            bail!("Gemini withheld the response (finishReason {finish})");
+        }
+        let stop_reason = if !self.tool_calls.is_empty() {
+            "tool_use".to_string()
+        } else if safety {
+            "safety".to_string()
+        } else {
+            match finish.as_str() {
+                "STOP" | "" => "end_turn".to_string(),
+                "MAX_TOKENS" => "max_tokens".to_string(),
+                other => other.to_ascii_lowercase(),
+            }
+        };
+        Ok(LlmResponse {
+            content: self.text,
+            blocks: self.blocks,
+            tool_calls: self.tool_calls,
+            stop_reason,
+            // `promptTokenCount` includes the cached share; split it out so
+            // input_tokens means fresh input, as for the other providers.
+            input_tokens: self.prompt_tokens.saturating_sub(self.cached_tokens),
+            output_tokens: self.output_tokens,
+            cache_creation_tokens,
+            cache_read_tokens: self.cached_tokens,
+            reasoning_tokens: self.thought_tokens,
+            reasoning: (!self.reasoning.is_empty()).then_some(self.reasoning),
+            served_by: None,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl LlmProvider for GeminiProvider {
+    fn name(&self) -> &str {
+        "gemini"
+    }
+
+    fn supports_model(&self, model: &str) -> bool {
+        model.starts_with("gemini") || model.starts_with("models/gemini")
+    }
+
+    async fn send(&self, request: &LlmRequest) -> Result<LlmResponse> {
+        let (body, cache_written) = self.prepare(request).await?;
+        let url = format!(
+            "{}/{}:generateContent",
+            self.base_url,
+            model_path(&request.model)
+        );
+        let resp = self.post(&url, &body, false).await?;
+        let status = resp.status();
+        let text = resp
+            .text()
+            .await
+            .context("Failed to read Gemini response")?;
+        if !status.is_success() {
+            return Err(ProviderHttpError::new("Gemini API", status, error_message(&text)).into());
+        }
+        let parsed: Value =
+            serde_json::from_str(&text).context("Failed to parse Gemini response")?;
+        let mut acc = Accumulator::default();
+        acc.absorb(&parsed, None);
+        acc.finish(cache_written)
+    }
+
+    /// Gemini SSE streaming via `streamGenerateContent?alt=sse`. Every data
+    /// frame is a complete `GenerateContentResponse` holding the next parts;
+    /// text parts invoke `on_delta`, function calls arrive whole, and usage
+    /// is cumulative (the last frame's totals win).
+    async fn stream(&self, request: &LlmRequest, on_delta: &mut TokenSink) -> Result<LlmResponse> {
+        use futures::StreamExt;
+
+        let (body, cache_written) = self.prepare(request).await?;
+        let url = format!(
+            "{}/{}:streamGenerateContent?alt=sse",
+            self.base_url,
+            model_path(&request.model)
+        );
+        let resp = self.post(&url, &body, true).await?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let text = resp.text().await.unwrap_or_default();
+            return Err(
+                ProviderHttpError::new("Gemini stream", status, error_message(&text)).into(),
+            );
+        }
+
+        let mut acc = Accumulator::default();
+        let mut stream = resp.bytes_stream();
+        let mut buffer = String::new();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.context("reading Gemini stream")?;
+            // Google's SSE frames are CRLF-delimited; normalize so the frame
+            // split below is the same as the other providers'.
+            buffer.push_str(&String::from_utf8_lossy(&chunk).replace('\r', ""));
+
+            while let Some(idx) = buffer.find("\n\n") {
+                let frame = buffer[..idx].to_string();
+                buffer.drain(..idx + 2);
+                let data_line = frame
+                    .lines()
+                    .find_map(|l| l.strip_prefix("data: "))
+                    .unwrap_or("");
+                if data_line.is_empty() {
+                    continue;
+                }
+                let Ok(event): std::result::Result<Value, _> = serde_json::from_str(data_line)
+                else {
+                    continue;
+                };
+                acc.absorb(&event, Some(on_delta));
+            }
+        }
+        acc.finish(cache_written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{CacheLayout, ToolSchema};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn base_request() -> LlmRequest {
+        LlmRequest {
+            model: "gemini-2.5-flash".to_string(),
+            messages: vec![Message::user_text("hello")],
+            system: Some("be brief".to_string()),
+            temperature: 0.2,
+            max_tokens: 64,
+            tools: Vec::new(),
+            cache: CacheLayout::default(),
+            response_schema: None,
+            reasoning: None,
+        }
+    }
+
+    /// A mock Gemini API on an ephemeral loopback port. Records every request
+    /// body by path and answers `generateContent` / `streamGenerateContent`
+    /// with the given bodies; `cachedContents` creation counts calls.
+    struct MockGemini {
+        url: String,
+        requests: Arc<Mutex<Vec<(String, Value)>>>,
+        cache_creations: Arc<AtomicUsize>,
+    }
+
+    fn spawn_mock(generate: Value, stream_body: &'static str) -> MockGemini {
+        use axum::extract::{Path, State};
+        use axum::routing::post;
+
+        #[derive(Clone)]
+        struct Mock {
+            requests: Arc<Mutex<Vec<(String, Value)>>>,
+            cache_creations: Arc<AtomicUsize>,
+            generate: Value,
+            stream_body: &'static str,
+        }
+
+        async fn model_call(
+            State(mock): State<Mock>,
+            Path(rest): Path<String>,
+            headers: axum::http::HeaderMap,
+            axum::Json(body): axum::Json<Value>,
+        ) -> axum::response::Response {
+            use axum::response::IntoResponse;
+            assert_eq!(headers["x-goog-api-key"], "test-key");
+            mock.requests.lock().unwrap().push((rest.clone(), body));
+            if rest.ends_with(":streamGenerateContent") {
+                ([("content-type", "text/event-stream")], mock.stream_body).into_response()
+            } else {
+                axum::Json(mock.generate.clone()).into_response()
+            }
+        }
+
+        async fn create_cache(
+            State(mock): State<Mock>,
+            axum::Json(body): axum::Json<Value>,
+        ) -> axum::Json<Value> {
+            let n = mock.cache_creations.fetch_add(1, Ordering::SeqCst);
+            mock.requests
+                .lock()
+                .unwrap()
+                .push(("cachedContents".to_string(), body));
+            axum::Json(json!({
+                "name": format!("cachedContents/c{n}"),
+                "usageMetadata": { "totalTokenCount": 2048 },
+            }))
+        }
+
+        let mock = Mock {
+            requests: Arc::default(),
+            cache_creations: Arc::default(),
+            generate,
+            stream_body,
+        };
+        let requests = mock.requests.clone();
+        let cache_creations = mock.cache_creations.clone();
+        let app = axum::Router::new()
+            .route("/models/{*rest}", post(model_call))
+            .route("/cachedContents", post(create_cache))
+            .with_state(mock);
+
+        let (addr_tx, addr_rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            rt.block_on(async move {
+                let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+                addr_tx.send(listener.local_addr().unwrap()).unwrap();
+                axum::serve(listener, app).await.unwrap();
+            });
+        });
+        MockGemini {
+            url: format!("http://{}", addr_rx.recv().unwrap()),
+            requests,
+            cache_creations,
+        }
+    }
+
+    #[test]
+    fn tool_results_become_function_responses_named_by_their_call() {
+        let messages = vec![
+            Message::assistant_blocks(vec![ContentBlock::ToolUse {
+                id: "call_1".to_string(),
+                name: "read".to_string(),
+                input: json!({ "path": "a.txt" }),
+                thought_signature: None,
+            }]),
+            Message {
+                role: "user".to_string(),
+                content: vec![ContentBlock::ToolResult {
+                    tool_use_id: "call_1".to_string(),
+                    content: "contents".to_string(),
+                    is_error: false,
+                }],
+                cache_control: None,
+            },
+        ];
+        let contents = contents_json(&messages);
+        assert_eq!(
+            contents[0],
+            json!({ "role": "model", "parts": [{ "functionCall": { "name": "read", "args": { "path": "a.txt" } } }] })
+        );
+        assert_eq!(
+            contents[1]["parts"][0],
+            json!({ "functionResponse": { "name": "read", "response": { "output": "contents" } } })
+        );
+    }
+
+    #[test]
+    fn send_maps_request_and_parses_function_calls_and_usage() {
+        let mock = spawn_mock(
+            json!({
+                "candidates": [{
+                    "content": { "role": "model", "parts": [
+                        { "text": "thinking it over", "thought": true },
+                        { "text": "Let me look." },
+                        {
+                            "functionCall": { "name": "read", "args": { "path": "a.txt" } },
+                            "thoughtSignature": "sig-1",
+                        },
+                    ]},
+                    "finishReason": "STOP",
+                }],
+                "usageMetadata": {
+                    "promptTokenCount": 100,
+                    "cachedContentTokenCount": 40,
+                    "candidatesTokenCount": 12,
+                    "thoughtsTokenCount": 5,
+                },
+            }),
+            "",
+        );
+        let provider = GeminiProvider::with_base_url("test-key".to_string(), mock.url.clone());
+        let mut request = base_request();
+        request.tools = vec![ToolSchema {
+            name: "read".to_string(),
+            description: "Read a file".to_string(),
+            input_schema: json!({ "type": "object", "properties": { "path": { "type": "string" } } }),
+        }];
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let response = rt.block_on(provider.send(&request)).unwrap();
+
+        assert_eq!(response.content, "Let me look.");
+        assert_eq!(response.stop_reason, "tool_use");
+        assert_eq!(response.tool_calls.len(), 1);
+        assert_eq!(response.tool_calls[0].name, "read");
+        assert_eq!(response.tool_calls[0].input["path"], "a.txt");
+        assert_eq!(response.reasoning.as_deref(), Some("thinking it over"));
+        assert_eq!(response.input_tokens, 60);
+        assert_eq!(response.cache_read_tokens, 40);
+        assert_eq!(response.output_tokens, 17);
+        // The call's signature goes back with it on the next turn.
+        let replayed = contents_json(&[Message::assistant_blocks(response.blocks.clone())]);
+        assert_eq!(
+            replayed[0]["parts"][1],
+            json!({
+                "functionCall": { "name": "read", "args": { "path": "a.txt" } },
+                "thoughtSignature": "sig-1",
+            })
+        );
+
+        let requests = mock.requests.lock().unwrap();
+        let (path, body) = &requests[0];
+        assert_eq!(path, "gemini-2.5-flash:generateContent");
+        assert_eq!(body["systemInstruction"]["parts"][0]["text"], "be brief");
+        assert_eq!(body["contents"][0]["parts"][0]["text"], "hello");
+        assert_eq!(body["generationConfig"]["maxOutputTokens"], 64);
+        assert_eq!(
+            body["tools"][0]["functionDeclarations"][0]["name"],
+            json!("read")
+        );
+    }
+
+    #[test]
+    fn stream_emits_text_deltas_from_sse_frames() {
+        let mock = spawn_mock(
+            Value::Null,
+            "data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"Hel\"}]}}]}\r\n\r\n\
+             data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"lo\"}]},\"finishReason\":\"MAX_TOKENS\"}],\
+             \"usageMetadata\":{\"promptTokenCount\":7,\"candidatesTokenCount\":2}}\r\n\r\n",
+        );
+        let provider = GeminiProvider::with_base_url("test-key".to_string(), mock.url.clone());
+        let deltas = Arc::new(Mutex::new(Vec::<String>::new()));
+        let sink_deltas = deltas.clone();
+        let mut sink: TokenSink = Box::new(move |d| sink_deltas.lock().unwrap().push(d.into()));
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let response = rt
+            .block_on(provider.stream(&base_request(), &mut sink))
+            .unwrap();
+
+        assert_eq!(*deltas.lock().unwrap(), vec!["Hel", "lo"]);
+        assert_eq!(response.content, "Hello");
+        assert_eq!(response.blocks.len(), 1);
+        assert_eq!(response.stop_reason, "max_tokens");
+        assert_eq!((response.input_tokens, response.output_tokens), (7, 2));
+        assert_eq!(
+            mock.requests.lock().unwrap()[0].0,
+            "gemini-2.5-flash:streamGenerateContent"
+        );
+    }
+
+    #[test]
+    fn cache_layout_creates_cached_content_once_and_reuses_it() {
+        let mock = spawn_mock(
+            json!({
+                "candidates": [{ "content": { "parts": [{ "text": "ok" }] }, "finishReason": "STOP" }],
+                "usageMetadata": { "promptTokenCount": 2100, "cachedContentTokenCount": 2048, "candidatesTokenCount": 1 },
+            }),
+            "",
+        );
+        let provider = GeminiProvider::with_base_url("test-key".to_string(), mock.url.clone());
+        let mut request = base_request();
+        request.cache.system = Some(CacheTtl::OneHour);
+        request.messages = vec![
+            Message {
+                cache_control: Some(CacheTtl::FiveMinutes),
+                ..Message::user_text("long document")
+            },
+            Message::assistant_blocks(vec![ContentBlock::Text {
+                text: "read it".to_string(),
+            }]),
+            Message::user_text("summarize"),
+        ];
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let first = rt.block_on(provider.send(&request)).unwrap();
+        let second = rt.block_on(provider.send(&request)).unwrap();
+
+        assert_eq!(mock.cache_creations.load(Ordering::SeqCst), 1);
+        assert_eq!(first.cache_creation_tokens, 2048);
+        assert_eq!(second.cache_creation_tokens, 0);
+        assert_eq!(second.cache_read_tokens, 2048);
+
+        let requests = mock.requests.lock().unwrap();
+        let (_, cache_body) = &requests[0];
+        assert_eq!(cache_body["model"], "models/gemini-2.5-flash");
+        assert_eq!(cache_body["ttl"], "3600s");
+        assert_eq!(
+            cache_body["systemInstruction"]["parts"][0]["text"],
+            "be brief"
+        );
+        assert_eq!(cache_body["contents"].as_array().unwrap().len(), 1);
+
+        // The generate call references the cache and carries only the tail.
+        let (_, body) = &requests[1];
+        assert_eq!(body["cachedContent"], "cachedContents/c0");
+        assert!(body.get("systemInstruction").is_none());
+        let contents = body["contents"].as_array().unwrap();
+        assert_eq!(contents.len(), 2);
+        assert_eq!(contents[1]["parts"][0]["text"], "summarize");
+    }
+
+    #[test]
+    fn blocked_prompt_and_withheld_candidate_are_errors() {
+        let mut blocked = Accumulator::default();
+        blocked.absorb(
+            &json!({ "promptFeedback": { "blockReason": "SAFETY" } }),
+            None,
+        );
+        let err = blocked.finish(0).unwrap_err().to_string();
+        assert!(err.contains("blocked the prompt (SAFETY)"), "{err}");
+
+        let mut withheld = Accumulator::default();
+        withheld.absorb(
+            &json!({ "candidates": [{ "finishReason": "PROHIBITED_CONTENT" }] }),
+            None,
+        );
+        assert!(withheld.finish(0).is_err());
+    }
+}
@@ -1,11 +1,16 @@
 pub mod anthropic;
+pub mod gemini;
 pub mod openai;
 pub mod openrouter;
 pub mod rate_limit;
+pub mod routing;
 
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 
-use anyhow::{bail, Result};
+use anyhow::Result;
+
+use routing::{ModelRoutes, RouteLeg, ServedBy};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -29,6 +34,34 @@ pub struct CacheLayout {
     pub tools: Option<CacheTtl>,
 }
 
+/// Where the bytes of an [`ContentBlock::Image`] / [`ContentBlock::Document`]
+/// come from. Agents may name a workspace file; the host resolves it to
+/// `Base64` before the request reaches a provider, so providers only ever
+/// translate inline data.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MediaSource {
+    Base64 {
+        media_type: String,
+        data: String,
+    },
+    File {
+        path: String,
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        media_type: Option<String>,
+    },
+}
+
+impl MediaSource {
+    /// The declared media type, when known without reading the file.
+    pub fn media_type(&self) -> Option<&str> {
+        match self {
+            MediaSource::Base64 { media_type, .. } => Some(media_type),
+            MediaSource::File { media_type, .. } => media_type.as_deref(),
+        }
+    }
+}
+
 /// A content block inside a message. Mirrors Anthropic's block-based content model;
 /// providers translate to their own wire format on send.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,10 +70,23 @@ pub enum ContentBlock {
     Text {
         text: String,
     },
+    Image {
+        source: MediaSource,
+    },
+    Document {
+        source: MediaSource,
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        title: Option<String>,
+    },
     ToolUse {
         id: String,
         name: String,
         input: Value,
+        /// Gemini's `thoughtSignature` on a function call from a thinking
+        /// model, echoed back with the call on the next turn; `None`
+        /// everywhere else.
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        thought_signature: Option<String>,
     },
     ToolResult {
         tool_use_id: String,
@@ -48,6 +94,78 @@ pub enum ContentBlock {
         #[serde(default)]
         is_error: bool,
     },
+    /// Anthropic extended-thinking output. The signature authenticates the
+    /// text, so the block must be echoed back byte-for-byte on the next
+    /// assistant turn of a tool loop; other providers drop it on send.
+    Thinking {
+        thinking: String,
+        signature: String,
+    },
+    /// Thinking the provider flagged and encrypted; opaque, echoed back like
+    /// [`ContentBlock::Thinking`].
+    RedactedThinking {
+        data: String,
+    },
+}
+
+/// How much hidden reasoning to ask for (`chidori.prompt(text, { reasoning })`).
+/// Each provider reads the form it speaks natively — Anthropic and Gemini a
+/// token budget, OpenAI an effort level — and derives it from the other when
+/// only that one is given.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReasoningConfig {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub budget_tokens: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub effort: Option<ReasoningEffort>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReasoningEffort {
+    Minimal,
+    Low,
+    Medium,
+    High,
+}
+
+impl ReasoningEffort {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReasoningEffort::Minimal => "minimal",
+            ReasoningEffort::Low => "low",
+            ReasoningEffort::Medium => "medium",
+            ReasoningEffort::High => "high",
+        }
+    }
+}
+
+impl ReasoningConfig {
+    /// The thinking budget in tokens: the explicit one, else the effort
+    /// level's conventional size. `None` when neither is set.
+    pub fn budget(&self) -> Option<u64> {
+        self.budget_tokens.or_else(|| {
+            Some(match self.effort? {
+                ReasoningEffort::Minimal => 1024,
+                ReasoningEffort::Low => 2048,
+                ReasoningEffort::Medium => 8192,
+                ReasoningEffort::High => 24576,
+            })
+        })
+    }
+
+    /// The effort level: the explicit one, else the band the budget falls in.
+    pub fn effort(&self) -> Option<ReasoningEffort> {
+        self.effort.or_else(|| {
+            Some(match self.budget_tokens? {
+                0..=1024 => ReasoningEffort::Minimal,
+                1025..=4096 => ReasoningEffort::Low,
+                4097..=16384 => ReasoningEffort::Medium,
+                _ => ReasoningEffort::High,
+            })
+        })
+    }
 }
 
 /// A chat message.
@@ -113,6 +231,16 @@ pub struct LlmRequest {
     /// ignore this; an empty layout emits a request byte-identical to before
     /// caching existed.
     pub cache: CacheLayout,
+    /// JSON Schema the reply must conform to (`chidori.prompt(text, { schema })`).
+    /// Providers with native structured output enforce it — Anthropic by
+    /// forcing a single tool, OpenAI via `response_format`, Gemini via
+    /// `responseJsonSchema` — and return the JSON as the response text. The
+    /// runtime validates the reply either way.
+    pub response_schema: Option<Value>,
+    /// Extended thinking / reasoning effort (see [`ReasoningConfig`]). `None`
+    /// leaves the provider's default and sends a request identical to before
+    /// the option existed.
+    pub reasoning: Option<ReasoningConfig>,
 }
 
 /// A response from an LLM provider.
@@ -138,11 +266,20 @@ pub struct LlmResponse {
     /// `cache_read_input_tokens` / OpenAI `cached_tokens`; billed at a steep
     /// discount).
     pub cache_read_tokens: u64,
+    /// The share of `output_tokens` spent on hidden reasoning (OpenAI
+    /// `reasoning_tokens`, Gemini `thoughtsTokenCount`). Already included in
+    /// `output_tokens`, which is what providers bill it as; 0 when the
+    /// provider does not break it out (Anthropic).
+    pub reasoning_tokens: u64,
     /// Hidden reasoning text reported by reasoning models (OpenAI-compatible
-    /// `reasoning_content`, e.g. DeepSeek). Never fed back into the
-    /// conversation; surfaced so authors can inspect why a model spent
-    /// output budget before its visible answer.
+    /// `reasoning_content`, e.g. DeepSeek, or Anthropic thinking blocks).
+    /// This string is never fed back into the conversation — the Anthropic
+    /// blocks travel in `blocks` — and is surfaced so authors can inspect why
+    /// a model spent output budget before its visible answer.
     pub reasoning: Option<String>,
+    /// The provider and concrete model that answered, set by
+    /// [`ProviderRegistry`] once a route leg succeeds.
+    pub served_by: Option<ServedBy>,
 }
 
 impl Default for LlmResponse {
@@ -156,19 +293,82 @@ impl Default for LlmResponse {
             output_tokens: 0,
             cache_creation_tokens: 0,
             cache_read_tokens: 0,
+            reasoning_tokens: 0,
             reasoning: None,
+            served_by: None,
         }
     }
 }
 
+/// A batch of texts to embed with one model.
+#[derive(Debug, Clone)]
+pub struct EmbeddingRequest {
+    pub model: String,
+    pub inputs: Vec<String>,
+}
+
+/// Embedding vectors, one per input and in input order.
+#[derive(Debug, Clone, Default)]
+pub struct EmbeddingResponse {
+    pub embeddings: Vec<Vec<f32>>,
+    /// Input tokens billed for the batch (embeddings have no output tokens).
+    pub input_tokens: u64,
+    /// The provider and model that produced the vectors, set by
+    /// [`ProviderRegistry::embed`].
+    pub served_by: Option<ServedBy>,
+}
+
 /// Sink for streaming token deltas while an LLM response is in flight.
 /// `Box<dyn FnMut(&str) + Send>` rather than a concrete channel so different
 /// callers can route deltas to SSE / tracing / discard as they wish.
 pub type TokenSink = Box<dyn FnMut(&str) + Send>;
 
+/// A provider's non-success HTTP response. Typed so routing decides fallback
+/// from the status ([`routing::is_fallback_eligible`]) rather than from the
+/// rendered message; renders as `<context> error (<status>): <message>`.
+#[derive(Debug)]
+pub struct ProviderHttpError {
+    /// What failed, e.g. `Anthropic API` or `OpenAI stream`.
+    pub context: String,
+    pub status: reqwest::StatusCode,
+    pub message: String,
+}
+
+impl ProviderHttpError {
+    pub fn new(
+        context: impl Into<String>,
+        status: reqwest::StatusCode,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            context: context.into(),
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for ProviderHttpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} error ({}): {}",
+            self.context, self.status, self.message
+        )
+    }
+}
+
+impl std::error::Error for ProviderHttpError {}
+
 /// Trait for LLM provider implementations.
 #[async_trait::async_trait]
 pub trait LlmProvider: Send + Sync {
+    /// Short stable name (`anthropic`, `openai`, `gemini`, ...) that model
+    /// routes pin legs to and that `CallRecord::served_by` reports.
+    fn name(&self) -> &str {
+        "custom"
+    }
+
     /// Check if this provider can handle the given model name.
     fn supports_model(&self, model: &str) -> bool;
 
@@ -188,6 +388,20 @@ pub trait LlmProvider: Send + Sync {
         }
         Ok(response)
     }
+
+    /// Check if this provider serves embeddings for the given model name.
+    /// Embedding models have their own namespace (`text-embedding-3-small`),
+    /// so this is separate from [`supports_model`](Self::supports_model);
+    /// providers without an embeddings API keep the default.
+    fn supports_embedding_model(&self, _model: &str) -> bool {
+        false
+    }
+
+    /// Embed a batch of texts. Providers split the batch to their own
+    /// per-request limits.
+    async fn embed(&self, _request: &EmbeddingRequest) -> Result<EmbeddingResponse> {
+        anyhow::bail!("provider `{}` does not support embeddings", self.name())
+    }
 }
 
 /// Normalize a user-supplied base URL to its chat-completions endpoint:
@@ -208,9 +422,12 @@ fn rpm_env(name: &str) -> Option<u32> {
         .filter(|n| *n > 0)
 }
 
-/// Registry of LLM providers. Routes requests to the right provider based on model name.
+/// Registry of LLM providers. Routes requests to the right provider based on
+/// model name, walking the model's fallback chain (see [`routing`]) when one
+/// is configured.
 pub struct ProviderRegistry {
     providers: Vec<Box<dyn LlmProvider>>,
+    routes: ModelRoutes,
 }
 
 struct StaticProvider {
@@ -221,6 +438,10 @@ struct StaticProvider {
 
 #[async_trait::async_trait]
 impl LlmProvider for StaticProvider {
+    fn name(&self) -> &str {
+        "test"
+    }
+
     fn supports_model(&self, _model: &str) -> bool {
         true
     }
@@ -246,6 +467,7 @@ impl LlmProvider for StaticProvider {
                         id: id.clone(),
                         name: name.clone(),
                         input: input.clone(),
+                        thought_signature: None,
                     }],
                     tool_calls: vec![ToolCall { id, name, input }],
                     stop_reason: "tool_use".to_string(),
@@ -263,6 +485,97 @@ impl LlmProvider for StaticProvider {
     }
 }
 
+/// Offline embedding provider for tests and demos (`CHIDORI_TEST_EMBEDDINGS`,
+/// or alongside `CHIDORI_TEST_LLM_RESPONSE`). Vectors are feature-hashed
+/// word counts, L2-normalized: deterministic across runs and platforms, and
+/// texts sharing words score a higher cosine similarity, so retrieval code
+/// behaves plausibly without a network.
+pub(crate) struct HashEmbeddingProvider {
+    dims: usize,
+}
+
+impl HashEmbeddingProvider {
+    pub(crate) fn new(dims: usize) -> Self {
+        Self { dims: dims.max(1) }
+    }
+}
+
+/// Default vector width for [`HashEmbeddingProvider`].
+const TEST_EMBEDDING_DIMS: usize = 64;
+
+#[async_trait::async_trait]
+impl LlmProvider for HashEmbeddingProvider {
+    fn name(&self) -> &str {
+        "test"
+    }
+
+    fn supports_model(&self, _model: &str) -> bool {
+        false
+    }
+
+    async fn send(&self, _request: &LlmRequest) -> Result<LlmResponse> {
+        anyhow::bail!("the test embedding provider does not answer prompts")
+    }
+
+    fn supports_embedding_model(&self, _model: &str) -> bool {
+        true
+    }
+
+    async fn embed(&self, request: &EmbeddingRequest) -> Result<EmbeddingResponse> {
+        let mut input_tokens = 0;
+        let embeddings = request
+            .inputs
+            .iter()
+            .map(|text| {
+                let (vector, words) = hash_embedding(text, self.dims);
+                input_tokens += words;
+                vector
+            })
+            .collect();
+        Ok(EmbeddingResponse {
+            embeddings,
+            input_tokens,
+            served_by: None,
+        })
+    }
+}
+
+/// Feature-hash `text`'s lowercased words into `dims` signed buckets (FNV-1a,
+/// so the result never depends on the process's hasher seed). Returns the
+/// normalized vector and the word count.
+fn hash_embedding(text: &str, dims: usize) -> (Vec<f32>, u64) {
+    let mut vector = vec![0f32; dims.max(1)];
+    let mut words = 0;
+    for word in text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+    {
+        let hash = word
+            .to_lowercase()
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325u64, |h, b| {
+                (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+            });
+        let bucket = (hash % vector.len() as u64) as usize;
+        vector[bucket] += if hash >> 63 == 0 { 1.0 } else { -1.0 };
+        words += 1;
+    }
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+    (vector, words)
+}
+
+fn test_embedding_provider() -> HashEmbeddingProvider {
+    let dims = std::env::var("CHIDORI_TEST_EMBEDDINGS")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(TEST_EMBEDDING_DIMS);
+    HashEmbeddingProvider::new(dims)
+}
+
 fn request_has_tool_result(request: &LlmRequest) -> bool {
     request.messages.iter().any(|message| {
         message
@@ -282,6 +595,7 @@ impl ProviderRegistry {
     pub fn new() -> Self {
         Self {
             providers: Vec::new(),
+            routes: ModelRoutes::default(),
         }
     }
 
@@ -289,10 +603,18 @@ impl ProviderRegistry {
         self.providers.push(provider);
     }
 
+    /// Install a model routing table (see [`routing`]).
+    pub fn with_routes(mut self, routes: ModelRoutes) -> Self {
+        self.routes = routes;
+        self
+    }
+
     /// Build a registry from environment variables, registering all available providers.
     ///
     /// Checks for:
     ///   ANTHROPIC_API_KEY — registers the Anthropic provider
+    ///   GEMINI_API_KEY — registers the Gemini provider (`gemini-*` models);
+    ///     GEMINI_BASE_URL overrides the API root
     ///   OPENAI_API_KEY — registers the OpenAI provider; OPENAI_BASE_URL
     ///     (the de-facto ecosystem convention) redirects it at any
     ///     OpenAI-compatible endpoint and widens it to match all model names
@@ -300,8 +622,12 @@ impl ProviderRegistry {
     ///     OpenAI-compatible provider (DeepSeek, Groq, Ollama, vLLM, LiteLLM,
     ///     …) that matches all model names (acts as a catch-all fallback).
     ///     LITELLM_API_URL + LITELLM_API_KEY are accepted as legacy aliases.
+    ///   CHIDORI_MODEL_ROUTES — per-model fallback chains (see [`routing`])
+    ///   CHIDORI_TEST_EMBEDDINGS — registers the offline hash embedder ahead
+    ///     of any real embeddings API; the value is the vector width
+    ///     (default 64)
     pub fn from_env() -> Self {
-        let mut registry = Self::new();
+        let mut registry = Self::new().with_routes(ModelRoutes::from_env());
 
         if let Ok(response) = std::env::var("CHIDORI_TEST_LLM_RESPONSE") {
             let tool_call = std::env::var("CHIDORI_TEST_LLM_TOOL_CALL")
@@ -312,9 +638,14 @@ impl ProviderRegistry {
                 tool_call,
                 calls: AtomicUsize::new(0),
             }));
+            registry.register(Box::new(test_embedding_provider()));
             return registry;
         }
 
+        if std::env::var_os("CHIDORI_TEST_EMBEDDINGS").is_some() {
+            registry.register(Box::new(test_embedding_provider()));
+        }
+
         if let Ok(api_key) = std::env::var("ANTHROPIC_API_KEY") {
             let mut p = anthropic::AnthropicProvider::new(api_key);
             if let Some(rpm) = rpm_env("CHIDORI_ANTHROPIC_RPM") {
@@ -323,6 +654,19 @@ impl ProviderRegistry {
             registry.register(Box::new(p));
         }
 
+        if let Ok(api_key) = std::env::var("GEMINI_API_KEY") {
+            let mut p = match std::env::var("GEMINI_BASE_URL") {
+                Ok(base_url) if !base_url.trim().is_empty() => {
+                    gemini::GeminiProvider::with_base_url(api_key, base_url)
+                }
+                _ => gemini::GeminiProvider::new(api_key),
+            };
+            if let Some(rpm) = rpm_env("CHIDORI_GEMINI_RPM") {
+                p = p.with_rate_limit(rpm);
+            }
+            registry.register(Box::new(p));
+        }
+
         if let Ok(api_key) = std::env::var("OPENAI_API_KEY") {
             // OPENAI_BASE_URL is the ecosystem-wide convention for pointing
             // OpenAI clients at a compatible endpoint (DeepSeek, Groq, Ollama,
@@ -383,38 +727,172 @@ impl ProviderRegistry {
         registry
     }
 
-    /// Send a request, routing to the appropriate provider based on model name.
+    /// Send a request, routing to the appropriate provider based on model
+    /// name. A routed model tries each leg in turn, falling back on
+    /// transient failures; the answering leg is reported in `served_by`.
     pub async fn send(&self, request: &LlmRequest) -> Result<LlmResponse> {
-        for provider in &self.providers {
-            if provider.supports_model(&request.model) {
-                return provider.send(request).await;
+        let mut last_err = None;
+        for leg in self.routes.legs(&request.model) {
+            let Some(provider) = self.provider_for(&leg) else {
+                last_err = Some(self.no_provider_error(&leg));
+                continue;
+            };
+            let leg_request = LlmRequest {
+                model: leg.model.clone(),
+                ..request.clone()
+            };
+            let result = match leg.timeout() {
+                Some(limit) => tokio::time::timeout(limit, provider.send(&leg_request))
+                    .await
+                    .unwrap_or_else(|elapsed| Err(elapsed.into())),
+                None => provider.send(&leg_request).await,
+            };
+            match result {
+                Ok(response) => return Ok(served(response, provider, &leg)),
+                Err(err) if routing::is_fallback_eligible(&err) => {
+                    tracing::warn!(model = %request.model, leg = %leg.model, provider = provider.name(), error = %err, "model route leg failed; trying the next");
+                    last_err = Some(err);
+                }
+                Err(err) => return Err(err),
             }
         }
-        bail!(
-            "No provider found for model '{}'. Set ANTHROPIC_API_KEY or OPENAI_API_KEY, point CHIDORI_OPENAI_COMPAT_URL + CHIDORI_OPENAI_COMPAT_KEY at any OpenAI-compatible endpoint (DeepSeek, Groq, Ollama, vLLM, ...), or run `chidori model-login` to sign in with OpenRouter.",
-            request.model
-        );
+        Err(last_err.expect("a route has at least one leg"))
     }
 
     /// Streaming send: routes to the provider's `stream()` implementation
     /// (which falls back to `send()` for providers that don't override it).
+    /// A leg that already streamed text to `on_delta` is never retried on
+    /// another — the caller has shown those tokens — so only failures before
+    /// the first delta fall back.
     pub async fn stream(
         &self,
         request: &LlmRequest,
         on_delta: &mut TokenSink,
     ) -> Result<LlmResponse> {
-        for provider in &self.providers {
-            if provider.supports_model(&request.model) {
-                return provider.stream(request, on_delta).await;
+        let mut last_err = None;
+        for leg in self.routes.legs(&request.model) {
+            let Some(provider) = self.provider_for(&leg) else {
+                last_err = Some(self.no_provider_error(&leg));
+                continue;
+            };
+            let leg_request = LlmRequest {
+                model: leg.model.clone(),
+                ..request.clone()
+            };
+            // The caller's sink is borrowed, but a `TokenSink` is `'static`:
+            // lend it to the leg behind a shared slot and take it back once
+            // the leg settles, noting whether any text went out. Taking it
+            // out of the slot always succeeds, even if the provider leaked
+            // its copy of the leg sink; a leaked copy then finds the slot
+            // empty and drops whatever it still emits.
+            let emitted = Arc::new(AtomicBool::new(false));
+            let slot = Arc::new(Mutex::new(Some(std::mem::replace(
+                on_delta,
+                Box::new(|_: &str| {}),
+            ))));
+            let result = {
+                let emitted = emitted.clone();
+                let slot = slot.clone();
+                let mut leg_sink: TokenSink = Box::new(move |delta: &str| {
+                    if let Some(sink) = slot.lock().unwrap().as_mut() {
+                        emitted.store(true, Ordering::SeqCst);
+                        sink(delta);
+                    }
+                });
+                match leg.timeout() {
+                    Some(limit) => {
+                        tokio::time::timeout(limit, provider.stream(&leg_request, &mut leg_sink))
+                            .await
+                            .unwrap_or_else(|elapsed| Err(elapsed.into()))
+                    }
+                    None => provider.stream(&leg_request, &mut leg_sink).await,
+                }
+            };
+            let sink = slot
+                .lock()
+                .unwrap_or_else(|poison| poison.into_inner())
+                .take();
+            if let Some(sink) = sink {
+                *on_delta = sink;
+            }
+            match result {
+                Ok(response) => return Ok(served(response, provider, &leg)),
+                Err(err)
+                    if !emitted.load(Ordering::SeqCst) && routing::is_fallback_eligible(&err) =>
+                {
+                    tracing::warn!(model = %request.model, leg = %leg.model, provider = provider.name(), error = %err, "model route leg failed; trying the next");
+                    last_err = Some(err);
+                }
+                Err(err) => return Err(err),
             }
         }
-        bail!(
-            "No provider found for model '{}'. Set ANTHROPIC_API_KEY or OPENAI_API_KEY, point CHIDORI_OPENAI_COMPAT_URL + CHIDORI_OPENAI_COMPAT_KEY at any OpenAI-compatible endpoint (DeepSeek, Groq, Ollama, vLLM, ...), or run `chidori model-login` to sign in with OpenRouter.",
-            request.model
-        );
+        Err(last_err.expect("a route has at least one leg"))
+    }
+
+    /// Embed a batch with the first provider serving `request.model`.
+    /// Embeddings are never routed: vectors from different models are not
+    /// comparable, so a fallback would silently corrupt any index built
+    /// from them.
+    pub async fn embed(&self, request: &EmbeddingRequest) -> Result<EmbeddingResponse> {
+        let provider = self
+            .providers
+            .iter()
+            .find(|p| p.supports_embedding_model(&request.model))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No embedding provider found for model '{}'. Set OPENAI_API_KEY, point CHIDORI_OPENAI_COMPAT_URL at an OpenAI-compatible endpoint that serves embeddings, or set CHIDORI_TEST_EMBEDDINGS for offline test vectors.",
+                    request.model
+                )
+            })?;
+        let mut response = provider.embed(request).await?;
+        if response.embeddings.len() != request.inputs.len() {
+            anyhow::bail!(
+                "provider `{}` returned {} embeddings for {} inputs",
+                provider.name(),
+                response.embeddings.len(),
+                request.inputs.len()
+            );
+        }
+        response.served_by = Some(ServedBy {
+            provider: provider.name().to_string(),
+            model: request.model.clone(),
+        });
+        Ok(response)
+    }
+
+    /// The provider a leg runs on: the named one when the leg pins a
+    /// provider, otherwise the first whose `supports_model` matches.
+    fn provider_for(&self, leg: &RouteLeg) -> Option<&dyn LlmProvider> {
+        let provider = match &leg.provider {
+            Some(name) => self.providers.iter().find(|p| p.name() == name),
+            None => self.providers.iter().find(|p| p.supports_model(&leg.model)),
+        };
+        provider.map(|p| p.as_ref())
+    }
+
+    fn no_provider_error(&self, leg: &RouteLeg) -> anyhow::Error {
+        match &leg.provider {
+            Some(name) => anyhow::anyhow!(
+                "No provider named '{}' is configured for model '{}'.",
+                name,
+                leg.model
+            ),
+            None => anyhow::anyhow!(
+                "No provider found for model '{}'. Set ANTHROPIC_API_KEY, GEMINI_API_KEY, or OPENAI_API_KEY, point CHIDORI_OPENAI_COMPAT_URL + CHIDORI_OPENAI_COMPAT_KEY at any OpenAI-compatible endpoint (DeepSeek, Groq, Ollama, vLLM, ...), or run `chidori model-login` to sign in with OpenRouter.",
+                leg.model
+            ),
+        }
     }
 }
 
+fn served(mut response: LlmResponse, provider: &dyn LlmProvider, leg: &RouteLeg) -> LlmResponse {
+    response.served_by = Some(ServedBy {
+        provider: provider.name().to_string(),
+        model: leg.model.clone(),
+    });
+    response
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -434,6 +912,8 @@ mod tests {
             max_tokens: 10,
             tools: Vec::new(),
             cache: CacheLayout::default(),
+            response_schema: None,
+            reasoning: None,
         };
         let rt = tokio::runtime::Runtime::new().unwrap();
         let response = rt.block_on(provider.send(&request)).unwrap();
@@ -442,6 +922,72 @@ mod tests {
         assert_eq!(response.stop_reason, "end_turn");
     }
 
+    #[test]
+    fn reasoning_config_derives_budget_and_effort_from_each_other() {
+        let by_effort = ReasoningConfig {
+            budget_tokens: None,
+            effort: Some(ReasoningEffort::Medium),
+        };
+        assert_eq!(by_effort.budget(), Some(8192));
+        assert_eq!(by_effort.effort(), Some(ReasoningEffort::Medium));
+
+        let by_budget = ReasoningConfig {
+            budget_tokens: Some(3000),
+            effort: None,
+        };
+        assert_eq!(by_budget.budget(), Some(3000));
+        assert_eq!(by_budget.effort(), Some(ReasoningEffort::Low));
+
+        let explicit = ReasoningConfig {
+            budget_tokens: Some(500),
+            effort: Some(ReasoningEffort::High),
+        };
+        assert_eq!(explicit.budget(), Some(500));
+        assert_eq!(explicit.effort(), Some(ReasoningEffort::High));
+    }
+
+    #[test]
+    fn hash_embeddings_are_deterministic_and_rank_shared_words_higher() {
+        let mut registry = ProviderRegistry::new();
+        registry.register(Box::new(HashEmbeddingProvider::new(TEST_EMBEDDING_DIMS)));
+        let request = EmbeddingRequest {
+            model: "text-embedding-3-small".to_string(),
+            inputs: vec![
+                "the cat sat on the mat".to_string(),
+                "a cat on a mat".to_string(),
+                "quarterly revenue forecast".to_string(),
+            ],
+        };
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let first = rt.block_on(registry.embed(&request)).unwrap();
+        let again = rt.block_on(registry.embed(&request)).unwrap();
+        assert_eq!(first.embeddings, again.embeddings);
+        assert_eq!(first.input_tokens, 14);
+        assert_eq!(first.served_by.as_ref().unwrap().provider, "test");
+
+        let dot = |a: &[f32], b: &[f32]| a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>();
+        let v = &first.embeddings;
+        assert_eq!(v[0].len(), TEST_EMBEDDING_DIMS);
+        assert!(dot(&v[0], &v[1]) > dot(&v[0], &v[2]));
+    }
+
+    #[test]
+    fn embed_without_an_embedding_provider_names_the_model() {
+        let mut registry = ProviderRegistry::new();
+        registry.register(Box::new(StaticProvider {
+            response: "chat only".to_string(),
+            tool_call: None,
+            calls: AtomicUsize::new(0),
+        }));
+        let request = EmbeddingRequest {
+            model: "text-embedding-3-small".to_string(),
+            inputs: vec!["hello".to_string()],
+        };
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let err = rt.block_on(registry.embed(&request)).unwrap_err();
+        assert!(err.to_string().contains("text-embedding-3-small"), "{err}");
+    }
+
     #[test]
     fn static_provider_returns_configured_tool_call_once() {
         let provider = StaticProvider {
@@ -461,6 +1007,8 @@ mod tests {
             max_tokens: 10,
             tools: Vec::new(),
             cache: CacheLayout::default(),
+            response_schema: None,
+            reasoning: None,
         };
         let rt = tokio::runtime::Runtime::new().unwrap();
 
@@ -477,4 +1025,184 @@ mod tests {
         assert_eq!(second.stop_reason, "end_turn");
         assert!(second.tool_calls.is_empty());
     }
+
+    /// A named provider that fails with a fixed HTTP status, or answers with
+    /// its own name, counting calls. With `partial`, its stream emits one
+    /// delta before failing.
+    struct LegProvider {
+        name: &'static str,
+        failure: Option<u16>,
+        partial: bool,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl LegProvider {
+        fn failure(&self) -> Option<anyhow::Error> {
+            self.failure.map(|status| {
+                ProviderHttpError::new(
+                    format!("{} API", self.name),
+                    reqwest::StatusCode::from_u16(status).unwrap(),
+                    "leg failed",
+                )
+                .into()
+            })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl LlmProvider for LegProvider {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn supports_model(&self, model: &str) -> bool {
+            model.starts_with(self.name)
+        }
+
+        async fn send(&self, request: &LlmRequest) -> Result<LlmResponse> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if let Some(err) = self.failure() {
+                return Err(err);
+            }
+            Ok(LlmResponse {
+                content: format!("{} answered {}", self.name, request.model),
+                ..LlmResponse::default()
+            })
+        }
+
+        async fn stream(
+            &self,
+            request: &LlmRequest,
+            on_delta: &mut TokenSink,
+        ) -> Result<LlmResponse> {
+            if self.partial {
+                on_delta("partial");
+            }
+            let response = self.send(request).await?;
+            on_delta(&response.content);
+            Ok(response)
+        }
+    }
+
+    fn routed_registry(
+        primary_failure: Option<u16>,
+        primary_partial: bool,
+    ) -> (ProviderRegistry, Arc<AtomicUsize>) {
+        let backup_calls = Arc::new(AtomicUsize::new(0));
+        let mut registry = ProviderRegistry::new().with_routes(
+            ModelRoutes::from_json(
+                r#"{"fast": ["primary-small", {"model": "backup-large", "provider": "backup"}]}"#,
+            )
+            .unwrap(),
+        );
+        registry.register(Box::new(LegProvider {
+            name: "primary",
+            failure: primary_failure,
+            partial: primary_partial,
+            calls: Arc::new(AtomicUsize::new(0)),
+        }));
+        registry.register(Box::new(LegProvider {
+            name: "backup",
+            failure: None,
+            partial: false,
+            calls: backup_calls.clone(),
+        }));
+        (registry, backup_calls)
+    }
+
+    fn request_for(model: &str) -> LlmRequest {
+        LlmRequest {
+            model: model.to_string(),
+            messages: vec![Message::user_text("hello")],
+            system: None,
+            temperature: 0.0,
+            max_tokens: 10,
+            tools: Vec::new(),
+            cache: CacheLayout::default(),
+            response_schema: None,
+            reasoning: None,
+        }
+    }
+
+    #[test]
+    fn routed_model_reports_the_leg_that_answered() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let (registry, backup_calls) = routed_registry(None, false);
+        let response = rt.block_on(registry.send(&request_for("fast"))).unwrap();
+        assert_eq!(response.content, "primary answered primary-small");
+        assert_eq!(
+            response.served_by,
+            Some(ServedBy {
+                provider: "primary".to_string(),
+                model: "primary-small".to_string(),
+            })
+        );
+        assert_eq!(backup_calls.load(Ordering::SeqCst), 0);
+
+        // Unrouted models keep first-match routing and still report the leg.
+        let direct = rt
+            .block_on(registry.send(&request_for("backup-x")))
+            .unwrap();
+        assert_eq!(direct.served_by.unwrap().provider, "backup");
+    }
+
+    #[test]
+    fn transient_failures_fall_back_and_hard_failures_do_not() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let (registry, backup_calls) = routed_registry(Some(503), false);
+        let response = rt.block_on(registry.send(&request_for("fast"))).unwrap();
+        assert_eq!(response.content, "backup answered backup-large");
+        assert_eq!(response.served_by.unwrap().model, "backup-large");
+        assert_eq!(backup_calls.load(Ordering::SeqCst), 1);
+
+        let (registry, backup_calls) = routed_registry(Some(400), false);
+        let err = rt
+            .block_on(registry.send(&request_for("fast")))
+            .unwrap_err();
+        assert!(err.to_string().contains("400 Bad Request"));
+        assert_eq!(backup_calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn streaming_falls_back_only_before_the_first_delta() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let (registry, _) = routed_registry(Some(429), false);
+        let deltas = Arc::new(Mutex::new(Vec::new()));
+        let sink_deltas = deltas.clone();
+        let mut sink: TokenSink = Box::new(move |delta: &str| {
+            sink_deltas.lock().unwrap().push(delta.to_string());
+        });
+        let response = rt
+            .block_on(registry.stream(&request_for("fast"), &mut sink))
+            .unwrap();
+        assert_eq!(response.served_by.unwrap().provider, "backup");
+        // The caller's sink survives the hand-off between legs.
+        sink("after");
+        assert_eq!(
+            *deltas.lock().unwrap(),
+            vec![
+                "backup answered backup-large".to_string(),
+                "after".to_string()
+            ]
+        );
+
+        // Once the primary has streamed text, its failure is final: the
+        // caller already showed those tokens.
+        let (registry, backup_calls) = routed_registry(Some(503), true);
+        let deltas = Arc::new(Mutex::new(Vec::new()));
+        let sink_deltas = deltas.clone();
+        let mut sink: TokenSink = Box::new(move |delta: &str| {
+            sink_deltas.lock().unwrap().push(delta.to_string());
+        });
+        let err = rt
+            .block_on(registry.stream(&request_for("fast"), &mut sink))
+            .unwrap_err();
+        assert!(err.to_string().contains("503 Service Unavailable"));
+        assert_eq!(backup_calls.load(Ordering::SeqCst), 0);
+        sink("after");
+        assert_eq!(
+            *deltas.lock().unwrap(),
+            vec!["partial".to_string(), "after".to_string()]
+        );
+    }
 }
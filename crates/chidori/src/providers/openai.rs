@@ -1,4 +1,4 @@
-use anyhow::{bail, Context, Result};
+use anyhow::{Context, Result};
 use reqwest::Client;
 use serde::Deserialize;
 use serde_json::{json, Value};
@@ -6,9 +6,14 @@ use serde_json::{json, Value};
 use std::sync::Arc;
 
 use super::rate_limit::RateLimiter;
-use super::{ContentBlock, LlmProvider, LlmRequest, LlmResponse, TokenSink, ToolCall};
+use super::{
+    ContentBlock, EmbeddingRequest, EmbeddingResponse, LlmProvider, LlmRequest, LlmResponse,
+    MediaSource, ProviderHttpError, ReasoningConfig, TokenSink, ToolCall,
+};
 
 const OPENAI_API_URL: &str = "https://api.openai.com/v1/chat/completions";
+/// OpenAI accepts at most 2048 inputs per embeddings request.
+const OPENAI_EMBED_BATCH: usize = 2048;
 
 pub struct OpenAiProvider {
     api_key: String,
@@ -119,6 +124,9 @@ struct OpenAiUsage {
     /// backends, so default to none.
     #[serde(default)]
     prompt_tokens_details: Option<OpenAiPromptTokensDetails>,
+    /// Reasoning models report the hidden share of `completion_tokens` here.
+    #[serde(default)]
+    completion_tokens_details: Option<OpenAiCompletionTokensDetails>,
 }
 
 #[derive(Deserialize, Default)]
@@ -127,6 +135,38 @@ struct OpenAiPromptTokensDetails {
     cached_tokens: u64,
 }
 
+#[derive(Deserialize, Default)]
+struct OpenAiCompletionTokensDetails {
+    #[serde(default)]
+    reasoning_tokens: u64,
+}
+
+#[derive(Deserialize)]
+struct OpenAiEmbeddingBody {
+    data: Vec<OpenAiEmbedding>,
+    #[serde(default)]
+    usage: Option<OpenAiEmbeddingUsage>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiEmbedding {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiEmbeddingUsage {
+    prompt_tokens: u64,
+}
+
+/// The embeddings endpoint beside a chat-completions URL.
+fn embeddings_url(chat_url: &str) -> String {
+    match chat_url.strip_suffix("/chat/completions") {
+        Some(root) => format!("{root}/embeddings"),
+        None => format!("{}/embeddings", chat_url.trim_end_matches('/')),
+    }
+}
+
 #[derive(Deserialize)]
 struct OpenAiError {
     error: OpenAiErrorBody,
@@ -139,6 +179,14 @@ struct OpenAiErrorBody {
 
 #[async_trait::async_trait]
 impl LlmProvider for OpenAiProvider {
+    fn name(&self) -> &str {
+        if self.label == "OpenAI" {
+            "openai"
+        } else {
+            "openai-compatible"
+        }
+    }
+
     fn supports_model(&self, model: &str) -> bool {
         if !self.model_prefixes.is_empty() {
             return self
@@ -149,6 +197,55 @@ impl LlmProvider for OpenAiProvider {
         model.starts_with("gpt") || model.starts_with("o1") || model.starts_with("o3")
     }
 
+    /// The real endpoint serves `text-embedding-*`; a compatible endpoint
+    /// configured to match every model is trusted to serve whatever it names.
+    fn supports_embedding_model(&self, model: &str) -> bool {
+        if !self.model_prefixes.is_empty() {
+            return self.supports_model(model);
+        }
+        model.starts_with("text-embedding")
+    }
+
+    async fn embed(&self, request: &EmbeddingRequest) -> Result<EmbeddingResponse> {
+        let url = embeddings_url(&self.base_url);
+        let mut out = EmbeddingResponse::default();
+        for batch in request.inputs.chunks(OPENAI_EMBED_BATCH) {
+            if let Some(ref rl) = self.rate_limiter {
+                rl.acquire().await;
+            }
+            let resp = self
+                .client
+                .post(&url)
+                .header("Authorization", format!("Bearer {}", self.api_key))
+                .header("Content-Type", "application/json")
+                .json(&json!({ "model": request.model, "input": batch }))
+                .send()
+                .await
+                .with_context(|| format!("Failed to send request to {url}"))?;
+            let status = resp.status();
+            let resp_text = resp
+                .text()
+                .await
+                .with_context(|| format!("Failed to read {} response", self.label))?;
+            if !status.is_success() {
+                let message = match serde_json::from_str::<OpenAiError>(&resp_text) {
+                    Ok(err) => err.error.message,
+                    Err(_) => resp_text,
+                };
+                return Err(
+                    ProviderHttpError::new(format!("{} API", self.label), status, message).into(),
+                );
+            }
+            let mut parsed: OpenAiEmbeddingBody = serde_json::from_str(&resp_text)
+                .with_context(|| format!("Failed to parse {} embeddings response", self.label))?;
+            parsed.data.sort_by_key(|item| item.index);
+            out.embeddings
+                .extend(parsed.data.into_iter().map(|item| item.embedding));
+            out.input_tokens += parsed.usage.map(|u| u.prompt_tokens).unwrap_or(0);
+        }
+        Ok(out)
+    }
+
     async fn send(&self, request: &LlmRequest) -> Result<LlmResponse> {
         if let Some(ref rl) = self.rate_limiter {
             rl.acquire().await;
@@ -188,6 +285,10 @@ impl LlmProvider for OpenAiProvider {
                 .collect();
             body["tools"] = Value::Array(tools_json);
         }
+        if let Some(ref schema) = request.response_schema {
+            body["response_format"] = response_format_json(schema);
+        }
+        apply_reasoning(&mut body, request);
 
         let resp = self
             .client
@@ -206,15 +307,13 @@ impl LlmProvider for OpenAiProvider {
             .with_context(|| format!("Failed to read {} response", self.label))?;
 
         if !status.is_success() {
-            if let Ok(err) = serde_json::from_str::<OpenAiError>(&resp_text) {
-                bail!(
-                    "{} API error ({}): {}",
-                    self.label,
-                    status,
-                    err.error.message
-                );
-            }
-            bail!("{} API error ({}): {}", self.label, status, resp_text);
+            let message = match serde_json::from_str::<OpenAiError>(&resp_text) {
+                Ok(err) => err.error.message,
+                Err(_) => resp_text,
+            };
+            return Err(
+                ProviderHttpError::new(format!("{} API", self.label), status, message).into(),
+            );
         }
 
         let parsed: OpenAiResponseBody = serde_json::from_str(&resp_text)
@@ -252,6 +351,7 @@ impl LlmProvider for OpenAiProvider {
                 id: call.id,
                 name: call.function.name,
                 input,
+                thought_signature: None,
             });
         }
 
@@ -261,7 +361,8 @@ impl LlmProvider for OpenAiProvider {
             other => other.to_string(),
         };
 
-        let (input_tokens, output_tokens, cache_read_tokens) = match parsed.usage {
+        let (input_tokens, output_tokens, cache_read_tokens, reasoning_tokens) = match parsed.usage
+        {
             Some(usage) => {
                 let cached = usage
                     .prompt_tokens_details
@@ -273,9 +374,13 @@ impl LlmProvider for OpenAiProvider {
                     usage.prompt_tokens.saturating_sub(cached),
                     usage.completion_tokens,
                     cached,
+                    usage
+                        .completion_tokens_details
+                        .map(|d| d.reasoning_tokens)
+                        .unwrap_or(0),
                 )
             }
-            None => (0, 0, 0),
+            None => (0, 0, 0, 0),
         };
 
         Ok(LlmResponse {
@@ -287,7 +392,9 @@ impl LlmProvider for OpenAiProvider {
             output_tokens,
             cache_creation_tokens: 0,
             cache_read_tokens,
+            reasoning_tokens,
             reasoning,
+            served_by: None,
         })
     }
 
@@ -337,6 +444,10 @@ impl LlmProvider for OpenAiProvider {
                 .collect();
             body["tools"] = Value::Array(tools_json);
         }
+        if let Some(ref schema) = request.response_schema {
+            body["response_format"] = response_format_json(schema);
+        }
+        apply_reasoning(&mut body, request);
 
         let resp = self
             .client
@@ -352,7 +463,9 @@ impl LlmProvider for OpenAiProvider {
         if !resp.status().is_success() {
             let status = resp.status();
             let text = resp.text().await.unwrap_or_default();
-            bail!("{} stream error ({}): {}", self.label, status, text);
+            return Err(
+                ProviderHttpError::new(format!("{} stream", self.label), status, text).into(),
+            );
         }
 
         // Accumulators.
@@ -366,6 +479,7 @@ impl LlmProvider for OpenAiProvider {
         let mut input_tokens: u64 = 0;
         let mut output_tokens: u64 = 0;
         let mut cache_read_tokens: u64 = 0;
+        let mut reasoning_tokens: u64 = 0;
 
         let mut stream = resp.bytes_stream();
         let mut buffer = String::new();
@@ -408,6 +522,13 @@ impl LlmProvider for OpenAiProvider {
                     {
                         cache_read_tokens = t;
                     }
+                    if let Some(t) = usage
+                        .get("completion_tokens_details")
+                        .and_then(|d| d.get("reasoning_tokens"))
+                        .and_then(|v| v.as_u64())
+                    {
+                        reasoning_tokens = t;
+                    }
                 }
 
                 let Some(choice) = event.get("choices").and_then(|c| c.get(0)) else {
@@ -470,7 +591,12 @@ impl LlmProvider for OpenAiProvider {
                 name: name.clone(),
                 input: input.clone(),
             });
-            blocks.push(ContentBlock::ToolUse { id, name, input });
+            blocks.push(ContentBlock::ToolUse {
+                id,
+                name,
+                input,
+                thought_signature: None,
+            });
         }
 
         let stop_reason = match finish_reason.as_str() {
@@ -490,11 +616,44 @@ impl LlmProvider for OpenAiProvider {
             output_tokens,
             cache_creation_tokens: 0,
             cache_read_tokens,
+            reasoning_tokens,
             reasoning: (!reasoning_buf.is_empty()).then_some(reasoning_buf),
+            served_by: None,
         })
     }
 }
 
+/// `response_format` for a structured-output request. Non-strict: strict mode
+/// rejects schemas without `additionalProperties: false` on every object, and
+/// the runtime validates the reply against the full schema anyway.
+fn response_format_json(schema: &Value) -> Value {
+    json!({
+        "type": "json_schema",
+        "json_schema": { "name": "response", "schema": schema, "strict": false },
+    })
+}
+
+/// Reasoning options for o-series / GPT-5-style models: `reasoning_effort`,
+/// plus the two request changes those models require — the completion cap
+/// moves to `max_completion_tokens` (which also covers hidden reasoning, so
+/// the reasoning budget is added on top of the answer's `max_tokens`) and
+/// `temperature` is left at the default, the only value they accept.
+fn apply_reasoning(body: &mut Value, request: &LlmRequest) {
+    let Some(reasoning) = request.reasoning else {
+        return;
+    };
+    let Some(effort) = reasoning.effort() else {
+        return;
+    };
+    if let Some(fields) = body.as_object_mut() {
+        fields.remove("max_tokens");
+        fields.remove("temperature");
+    }
+    body["reasoning_effort"] = json!(effort.as_str());
+    body["max_completion_tokens"] =
+        json!(request.max_tokens + ReasoningConfig::budget(&reasoning).unwrap_or(0));
+}
+
 /// Translate our unified Message (Anthropic-style blocks) into one or more
 /// OpenAI chat messages. Assistant messages may contain text + tool_calls in a
 /// single message; tool_result blocks become separate role="tool" messages.
@@ -507,7 +666,9 @@ fn message_to_openai_json(m: &super::Message) -> Vec<Value> {
         for block in &m.content {
             match block {
                 ContentBlock::Text { text: t } => text.push_str(t),
-                ContentBlock::ToolUse { id, name, input } => {
+                ContentBlock::ToolUse {
+                    id, name, input, ..
+                } => {
                     tool_calls.push(json!({
                         "id": id,
                         "type": "function",
@@ -517,7 +678,11 @@ fn message_to_openai_json(m: &super::Message) -> Vec<Value> {
                         }
                     }));
                 }
-                ContentBlock::ToolResult { .. } => {}
+                ContentBlock::ToolResult { .. }
+                | ContentBlock::Image { .. }
+                | ContentBlock::Document { .. }
+                | ContentBlock::Thinking { .. }
+                | ContentBlock::RedactedThinking { .. } => {}
             }
         }
         let mut msg = json!({ "role": "assistant", "content": text });
@@ -528,9 +693,12 @@ fn message_to_openai_json(m: &super::Message) -> Vec<Value> {
     }
 
     // User messages: emit text as a single user message, tool_results as
-    // separate role="tool" messages.
+    // separate role="tool" messages. Text-only turns keep the plain string
+    // `content`; a turn carrying images or documents switches to the
+    // content-part array, the only form those can ride in.
     let mut out: Vec<Value> = Vec::new();
     let mut text = String::new();
+    let mut media_parts: Vec<Value> = Vec::new();
     for block in &m.content {
         match block {
             ContentBlock::Text { text: t } => {
@@ -550,11 +718,149 @@ fn message_to_openai_json(m: &super::Message) -> Vec<Value> {
                     "content": content,
                 }));
             }
-            ContentBlock::ToolUse { .. } => {}
+            ContentBlock::Image { source } => media_parts.push(json!({
+                "type": "image_url",
+                "image_url": { "url": data_url(source) },
+            })),
+            ContentBlock::Document { source, title } => {
+                let filename = title.clone().unwrap_or_else(|| {
+                    match source.media_type() {
+                        Some("application/pdf") => "document.pdf",
+                        _ => "document.txt",
+                    }
+                    .to_string()
+                });
+                media_parts.push(json!({
+                    "type": "file",
+                    "file": { "filename": filename, "file_data": data_url(source) },
+                }));
+            }
+            ContentBlock::ToolUse { .. }
+            | ContentBlock::Thinking { .. }
+            | ContentBlock::RedactedThinking { .. } => {}
         }
     }
-    if !text.is_empty() {
+    if !media_parts.is_empty() {
+        let mut parts = Vec::with_capacity(media_parts.len() + 1);
+        if !text.is_empty() {
+            parts.push(json!({ "type": "text", "text": text }));
+        }
+        parts.extend(media_parts);
+        out.insert(0, json!({ "role": role, "content": parts }));
+    } else if !text.is_empty() {
         out.insert(0, json!({ "role": role, "content": text }));
     }
     out
 }
+
+/// `data:` URL for an inline media source, the form OpenAI accepts for both
+/// `image_url` and `file_data`. File sources are resolved by the host before
+/// send; one reaching here passes its path through and the API rejects it.
+fn data_url(source: &MediaSource) -> String {
+    match source {
+        MediaSource::Base64 { media_type, data } => format!("data:{media_type};base64,{data}"),
+        MediaSource::File { path, .. } => path.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::Message;
+
+    #[test]
+    fn embeddings_url_sits_beside_chat_completions() {
+        assert_eq!(
+            embeddings_url(OPENAI_API_URL),
+            "https://api.openai.com/v1/embeddings"
+        );
+        assert_eq!(
+            embeddings_url("http://localhost:11434/v1/"),
+            "http://localhost:11434/v1/embeddings"
+        );
+    }
+
+    #[test]
+    fn reasoning_sets_effort_and_moves_the_completion_cap() {
+        let mut request = LlmRequest {
+            model: "o3-mini".to_string(),
+            messages: vec![Message::user_text("hello")],
+            system: None,
+            temperature: 0.2,
+            max_tokens: 100,
+            tools: Vec::new(),
+            cache: crate::providers::CacheLayout::default(),
+            response_schema: None,
+            reasoning: None,
+        };
+        let plain = json!({ "max_tokens": 100, "temperature": 0.2 });
+        let mut body = plain.clone();
+        apply_reasoning(&mut body, &request);
+        assert_eq!(body, plain);
+
+        request.reasoning = Some(ReasoningConfig {
+            budget_tokens: Some(3000),
+            effort: None,
+        });
+        apply_reasoning(&mut body, &request);
+        assert_eq!(
+            body,
+            json!({ "reasoning_effort": "low", "max_completion_tokens": 3100 })
+        );
+
+        let usage: OpenAiUsage = serde_json::from_str(
+            r#"{ "prompt_tokens": 10, "completion_tokens": 50,
+                 "completion_tokens_details": { "reasoning_tokens": 40 } }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            usage.completion_tokens_details.unwrap().reasoning_tokens,
+            40
+        );
+    }
+
+    #[test]
+    fn text_only_user_turn_keeps_string_content() {
+        let out = message_to_openai_json(&Message::user_text("hello"));
+        assert_eq!(out, vec![json!({ "role": "user", "content": "hello" })]);
+    }
+
+    #[test]
+    fn media_user_turn_becomes_content_parts() {
+        let message = Message {
+            role: "user".to_string(),
+            content: vec![
+                ContentBlock::Text {
+                    text: "what is this?".to_string(),
+                },
+                ContentBlock::Image {
+                    source: MediaSource::Base64 {
+                        media_type: "image/jpeg".to_string(),
+                        data: "/9j/".to_string(),
+                    },
+                },
+                ContentBlock::Document {
+                    source: MediaSource::Base64 {
+                        media_type: "application/pdf".to_string(),
+                        data: "JVBERi0=".to_string(),
+                    },
+                    title: None,
+                },
+            ],
+            cache_control: None,
+        };
+        let out = message_to_openai_json(&message);
+        assert_eq!(out.len(), 1);
+        let parts = out[0]["content"].as_array().unwrap();
+        assert_eq!(parts[0], json!({ "type": "text", "text": "what is this?" }));
+        assert_eq!(
+            parts[1]["image_url"]["url"],
+            json!("data:image/jpeg;base64,/9j/")
+        );
+        assert_eq!(parts[2]["file"]["filename"], json!("document.pdf"));
+        assert_eq!(
+            parts[2]["file"]["file_data"],
+            json!("data:application/pdf;base64,JVBERi0=")
+        );
+    }
+}
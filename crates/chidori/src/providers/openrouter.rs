@@ -62,6 +62,10 @@ impl OpenRouterProvider {
 
 #[async_trait::async_trait]
 impl LlmProvider for OpenRouterProvider {
+    fn name(&self) -> &str {
+        "openrouter"
+    }
+
     fn supports_model(&self, _model: &str) -> bool {
         true
     }
@@ -0,0 +1,207 @@
+//! Declarative model routing: an ordered fallback chain per model alias.
+//!
+//! `CHIDORI_MODEL_ROUTES` maps a model name (an alias like `fast`, or a real
+//! model id) to the legs to try in order:
+//!
+//! ```json
+//! {"fast": ["claude-haiku-4-5",
+//!           {"model": "deepseek-chat", "provider": "openai-compatible", "timeout_ms": 30000}]}
+//! ```
+//!
+//! A leg is a model id, optionally pinned to a provider by name (see
+//! [`super::LlmProvider::name`]) and bounded by a timeout. The registry moves
+//! to the next leg only when a leg fails transiently — a 429 that outlived
+//! the provider's own retries, a 5xx, a timeout, a connection failure — or
+//! has no provider configured; a 400 or an auth error fails the prompt
+//! as-is, since another leg would fail the same way or mask the bug. Which
+//! leg answered is reported as [`ServedBy`] on the response and journaled on
+//! the prompt's `CallRecord`.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::ProviderHttpError;
+
+/// The provider and concrete model that produced a response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServedBy {
+    pub provider: String,
+    pub model: String,
+}
+
+/// One step of a fallback chain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "LegSpec")]
+pub struct RouteLeg {
+    pub model: String,
+    /// Pin the leg to the provider with this name; `None` routes by model
+    /// name like an unrouted request.
+    pub provider: Option<String>,
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LegSpec {
+    Model(String),
+    Full {
+        model: String,
+        #[serde(default)]
+        provider: Option<String>,
+        #[serde(default, alias = "timeoutMs")]
+        timeout_ms: Option<u64>,
+    },
+}
+
+impl From<LegSpec> for RouteLeg {
+    fn from(spec: LegSpec) -> Self {
+        match spec {
+            LegSpec::Model(model) => Self {
+                model,
+                provider: None,
+                timeout_ms: None,
+            },
+            LegSpec::Full {
+                model,
+                provider,
+                timeout_ms,
+            } => Self {
+                model,
+                provider,
+                timeout_ms,
+            },
+        }
+    }
+}
+
+impl RouteLeg {
+    /// The implicit single leg for a model with no configured route.
+    pub fn direct(model: &str) -> Self {
+        Self {
+            model: model.to_string(),
+            provider: None,
+            timeout_ms: None,
+        }
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout_ms.map(Duration::from_millis)
+    }
+}
+
+/// Routing table: model name → ordered legs.
+#[derive(Debug, Clone, Default)]
+pub struct ModelRoutes {
+    routes: HashMap<String, Vec<RouteLeg>>,
+}
+
+impl ModelRoutes {
+    /// Parse a routing table from its JSON form (see the module docs).
+    pub fn from_json(text: &str) -> anyhow::Result<Self> {
+        let routes: HashMap<String, Vec<RouteLeg>> = serde_json::from_str(text)?;
+        if let Some((name, _)) = routes.iter().find(|(_, legs)| legs.is_empty()) {
+            anyhow::bail!("model route `{name}` has no legs");
+        }
+        Ok(Self { routes })
+    }
+
+    /// The table configured by `CHIDORI_MODEL_ROUTES`. A malformed value
+    /// warns and routes nothing, like a malformed `CHIDORI_PRICING`.
+    pub fn from_env() -> Self {
+        let Ok(text) = std::env::var("CHIDORI_MODEL_ROUTES") else {
+            return Self::default();
+        };
+        match Self::from_json(&text) {
+            Ok(routes) => routes,
+            Err(err) => {
+                tracing::warn!(error = %err, "ignoring invalid CHIDORI_MODEL_ROUTES");
+                Self::default()
+            }
+        }
+    }
+
+    /// The legs to try for `model`, in order.
+    pub fn legs(&self, model: &str) -> Vec<RouteLeg> {
+        self.routes
+            .get(model)
+            .cloned()
+            .unwrap_or_else(|| vec![RouteLeg::direct(model)])
+    }
+}
+
+/// Whether a failed leg should hand the request to the next one: timeouts,
+/// connection failures, and HTTP 429 / 5xx. Providers surface HTTP failures
+/// — including a 429 that exhausted their own backoff — as a
+/// [`ProviderHttpError`] carrying the status.
+pub fn is_fallback_eligible(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        if let Some(err) = cause.downcast_ref::<ProviderHttpError>() {
+            return err.status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                || err.status.is_server_error();
+        }
+        if let Some(err) = cause.downcast_ref::<reqwest::Error>() {
+            return err.is_timeout() || err.is_connect();
+        }
+        cause.is::<tokio::time::error::Elapsed>()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes_parse_bare_and_pinned_legs() {
+        let routes = ModelRoutes::from_json(
+            r#"{"fast": ["claude-haiku-4-5",
+                         {"model": "deepseek-chat", "provider": "openai-compatible", "timeoutMs": 500}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            routes.legs("fast"),
+            vec![
+                RouteLeg::direct("claude-haiku-4-5"),
+                RouteLeg {
+                    model: "deepseek-chat".to_string(),
+                    provider: Some("openai-compatible".to_string()),
+                    timeout_ms: Some(500),
+                },
+            ]
+        );
+        assert_eq!(routes.legs("gpt-4o"), vec![RouteLeg::direct("gpt-4o")]);
+        assert!(ModelRoutes::from_json(r#"{"fast": []}"#).is_err());
+    }
+
+    #[test]
+    fn only_transient_failures_fall_back() {
+        let eligible = |status: u16| {
+            is_fallback_eligible(&anyhow::Error::new(ProviderHttpError::new(
+                "Anthropic API",
+                reqwest::StatusCode::from_u16(status).unwrap(),
+                "failed",
+            )))
+        };
+        assert!(eligible(529));
+        assert!(eligible(503));
+        assert!(eligible(429));
+        assert!(!eligible(400));
+        assert!(!eligible(401));
+        // Context wrapping doesn't hide the status.
+        let wrapped = anyhow::Error::new(ProviderHttpError::new(
+            "OpenAI stream",
+            reqwest::StatusCode::SERVICE_UNAVAILABLE,
+            "down",
+        ))
+        .context("prompt failed");
+        assert!(is_fallback_eligible(&wrapped));
+        // Only the typed status counts, never the message text.
+        assert!(!is_fallback_eligible(&anyhow::anyhow!(
+            "Anthropic API error (503 Service Unavailable): quoted from a tool"
+        )));
+        assert!(!is_fallback_eligible(&anyhow::anyhow!(
+            "Gemini blocked the prompt (SAFETY)"
+        )));
+    }
+}
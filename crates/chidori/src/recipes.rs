@@ -12,6 +12,10 @@
 //!   channel: "#general"
 //!   lookback_hours: 24
 //! description: "Summarize yesterday's activity and post to Slack"
+//! timezone: Europe/Berlin      # cron fields read in this zone (default UTC)
+//! catch_up: latest             # none | latest | all — ticks missed while down
+//! overlap: skip                # skip | queue | allow — a tick during a run
+//! jitter_secs: 30              # random 0–30s delay per tick
 //! ```
 
 use std::path::{Path, PathBuf};
@@ -34,6 +38,76 @@ pub struct Recipe {
     pub inputs: Value,
     #[serde(default)]
     pub description: Option<String>,
+    /// Cost ceiling for each scheduled run (`max_cost_usd`, optional
+    /// `on_exceed: fail|ask`). Overrides the server's CHIDORI_MAX_COST_USD.
+    /// Scheduled runs have nobody to ask, so `ask` fails closed like `fail`.
+    #[serde(default)]
+    pub budget: Option<crate::runtime::cost::CostBudget>,
+    /// How the schedule fires: timezone, catch-up, overlap, jitter.
+    #[serde(flatten)]
+    pub timing: ScheduleTiming,
+}
+
+/// Scheduling options for a recipe with a `schedule`. Every field defaults to
+/// the scheduler's original behavior: UTC, missed ticks dropped, a tick that
+/// lands during a run skipped, no jitter.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduleTiming {
+    /// IANA timezone the cron fields are read in (`America/New_York`), so
+    /// `0 9 * * *` follows local 09:00 across DST changes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// Ticks missed while no server was running, counted from the last
+    /// scheduled run recorded in the session store.
+    #[serde(default)]
+    pub catch_up: CatchUp,
+    /// What a tick does while the previous run is still going.
+    #[serde(default)]
+    pub overlap: Overlap,
+    /// Upper bound of a random delay added to each tick, spreading recipes
+    /// that share a schedule.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jitter_secs: Option<u64>,
+}
+
+impl ScheduleTiming {
+    /// The parsed `timezone` (UTC when unset).
+    pub fn tz(&self) -> Result<chrono_tz::Tz> {
+        match self.timezone.as_deref() {
+            None => Ok(chrono_tz::UTC),
+            Some(zone) => zone
+                .parse::<chrono_tz::Tz>()
+                .map_err(|_| anyhow::anyhow!("unknown timezone `{zone}`")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CatchUp {
+    /// Missed ticks are dropped.
+    #[default]
+    None,
+    /// Run once for the most recent missed tick.
+    Latest,
+    /// Run every missed tick, oldest first (at most [`MAX_CATCH_UP_TICKS`]).
+    All,
+}
+
+/// Cap on the ticks `catch_up: all` replays, so a long outage of a
+/// frequent schedule doesn't queue thousands of runs.
+pub const MAX_CATCH_UP_TICKS: usize = 100;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Overlap {
+    /// Drop the tick.
+    #[default]
+    Skip,
+    /// Run it once the previous run finishes; ticks run one at a time.
+    Queue,
+    /// Run it concurrently.
+    Allow,
 }
 
 impl Recipe {
@@ -50,6 +124,15 @@ impl Recipe {
             serde_yaml::from_str(&text)
                 .with_context(|| format!("parsing recipe YAML {}", path.display()))?
         };
+        if let Some(budget) = &recipe.budget {
+            budget
+                .validate()
+                .map_err(|msg| anyhow::anyhow!("recipe {}: {}", path.display(), msg))?;
+        }
+        recipe
+            .timing
+            .tz()
+            .with_context(|| format!("recipe {}", path.display()))?;
         Ok(recipe)
     }
 
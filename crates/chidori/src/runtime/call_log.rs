@@ -29,6 +29,32 @@ pub struct CallRecord {
     /// Error message if the call failed.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// For prompts: the provider and concrete model that answered — the
+    /// route leg that succeeded when the requested model has a fallback
+    /// chain. Pricing and `chidori trace` prefer it over `args.model`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub served_by: Option<crate::providers::routing::ServedBy>,
+}
+
+impl CallRecord {
+    /// The model a prompt record is billed as: the leg that served it, else
+    /// the model named in its args.
+    pub fn priced_model(&self) -> &str {
+        match &self.served_by {
+            Some(served) => &served.model,
+            None => self
+                .args
+                .get("model")
+                .and_then(|v| v.as_str())
+                .unwrap_or(""),
+        }
+    }
+
+    /// Whether the record is a billed model call — a `prompt` or an `embed`
+    /// batch — whose `token_usage` prices into the run's cost.
+    pub fn is_model_call(&self) -> bool {
+        matches!(self.function.as_str(), "prompt" | "embed")
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,6 +70,11 @@ pub struct TokenUsage {
     /// discount).
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub cache_read_tokens: Option<u64>,
+    /// The share of `output_tokens` the model spent on hidden reasoning.
+    /// Already counted (and priced) in `output_tokens`; kept apart so `stats`
+    /// can show what thinking cost. Omitted when the provider reported none.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub reasoning_tokens: Option<u64>,
 }
 
 impl TokenUsage {
@@ -57,6 +88,7 @@ impl TokenUsage {
                 .then_some(response.cache_creation_tokens),
             cache_read_tokens: (response.cache_read_tokens > 0)
                 .then_some(response.cache_read_tokens),
+            reasoning_tokens: (response.reasoning_tokens > 0).then_some(response.reasoning_tokens),
         }
     }
 }
@@ -112,19 +144,19 @@ impl CallLog {
         self.records.iter().map(|r| r.duration_ms).sum()
     }
 
-    /// Walk LLM call records and sum an estimated USD cost based on the
+    /// Walk LLM call records (prompts and embeddings) and sum an estimated USD cost based on the
     /// model name stored in each record's args.
     pub fn total_cost_usd(&self) -> f64 {
         use crate::runtime::cost::estimate_cost_usd_with_cache;
         let mut total = 0.0;
         for r in &self.records {
-            if r.function != "prompt" {
+            if !r.is_model_call() {
                 continue;
             }
             let Some(usage) = r.token_usage.as_ref() else {
                 continue;
             };
-            let model = r.args.get("model").and_then(|v| v.as_str()).unwrap_or("");
+            let model = r.priced_model();
             total += estimate_cost_usd_with_cache(
                 model,
                 usage.input_tokens,
@@ -161,6 +161,7 @@ mod tests {
             token_usage: None,
             timestamp: Utc::now(),
             error: None,
+            served_by: None,
         }
     }
 
@@ -175,6 +176,7 @@ mod tests {
             token_usage: None,
             timestamp: Utc::now(),
             error: None,
+            served_by: None,
         }
     }
 
@@ -301,6 +301,16 @@ struct RuntimeContextInner {
     /// and attributes — shipping automatically to any OTLP backend (tael,
     /// Jaeger, Honeycomb, Datadog, ...). None disables OTEL export.
     pub otel_run: Option<Arc<RunSpan>>,
+    /// The source-level debugger attached to this run (`chidori debug`), if
+    /// any. Not inherited by branch or actor contexts: they run on other
+    /// threads, and a debug session steps one thread.
+    pub debugger: Option<Arc<crate::runtime::debugger::DebugController>>,
+    /// The CPU profile this run's JavaScript feeds (`chidori run --profile`).
+    /// Not inherited by branch or actor contexts either.
+    pub profiler: Option<Arc<crate::runtime::profiler::Profiler>>,
+    /// The coverage this run's agent code feeds (`--coverage`). Not
+    /// inherited by branch or actor contexts either.
+    pub coverage: Option<Arc<crate::runtime::coverage::CoverageRecorder>>,
     /// Optional durable safepoint invoked after a pending host operation is
     /// persisted and before the corresponding live side effect executes.
     pub host_operation_safepoint: Option<HostOperationSafepoint>,
@@ -546,6 +556,10 @@ pub struct AgentConfig {
     pub temperature: f64,
     pub max_tokens: u64,
     pub max_turns: u64,
+    /// Spend ceiling checked before each live prompt (see
+    /// [`crate::runtime::cost::CostBudget`]). Defaults from
+    /// `CHIDORI_MAX_COST_USD`; `None` is unlimited.
+    pub budget: Option<crate::runtime::cost::CostBudget>,
 }
 
 impl Default for AgentConfig {
@@ -558,6 +572,7 @@ impl Default for AgentConfig {
             temperature: 0.7,
             max_tokens: 4096,
             max_turns: 10,
+            budget: crate::runtime::cost::CostBudget::from_env(),
         }
     }
 }
@@ -593,6 +608,9 @@ impl RuntimeContext {
                 event_sender: None,
                 emit_call_events: true,
                 otel_run: None,
+                debugger: None,
+                profiler: None,
+                coverage: None,
                 host_operation_safepoint: None,
                 host_operation_completion_safepoint: None,
                 actor_signal_waiter: None,
@@ -670,6 +688,9 @@ impl RuntimeContext {
                 event_sender: None,
                 emit_call_events: true,
                 otel_run: None,
+                debugger: None,
+                profiler: None,
+                coverage: None,
                 host_operation_safepoint: None,
                 host_operation_completion_safepoint: None,
                 actor_signal_waiter: None,
@@ -719,6 +740,9 @@ impl RuntimeContext {
                 event_sender: None,
                 emit_call_events: true,
                 otel_run: None,
+                debugger: None,
+                profiler: None,
+                coverage: None,
                 host_operation_safepoint: None,
                 host_operation_completion_safepoint: None,
                 actor_signal_waiter: None,
@@ -776,6 +800,9 @@ impl RuntimeContext {
                 event_sender: parent_inner.event_sender.clone(),
                 emit_call_events: parent_inner.emit_call_events,
                 otel_run: parent_inner.otel_run.clone(),
+                debugger: None,
+                profiler: None,
+                coverage: None,
                 host_operation_safepoint: None,
                 host_operation_completion_safepoint: None,
                 actor_signal_waiter: None,
@@ -834,6 +861,9 @@ impl RuntimeContext {
                 event_sender: None,
                 emit_call_events: true,
                 otel_run: None,
+                debugger: None,
+                profiler: None,
+                coverage: None,
                 host_operation_safepoint: None,
                 host_operation_completion_safepoint: None,
                 actor_signal_waiter: None,
@@ -903,6 +933,9 @@ impl RuntimeContext {
                 event_sender: parent_inner.event_sender.clone(),
                 emit_call_events: parent_inner.emit_call_events,
                 otel_run: parent_inner.otel_run.clone(),
+                debugger: None,
+                profiler: None,
+                coverage: None,
                 host_operation_safepoint: None,
                 host_operation_completion_safepoint: None,
                 actor_signal_waiter: None,
@@ -1053,6 +1086,18 @@ impl RuntimeContext {
         self.inner.lock().unwrap().config.model = model;
     }
 
+    /// Override the run's cost budget — the per-session/recipe budget, or the
+    /// one recorded in the manifest on resume.
+    pub fn set_budget(&self, budget: crate::runtime::cost::CostBudget) {
+        self.inner.lock().unwrap().config.budget = Some(budget);
+    }
+
+    /// Estimated spend so far: every prompt record in the call log, replayed
+    /// legs included, priced by [`CallLog::total_cost_usd`].
+    pub fn cost_usd(&self) -> f64 {
+        self.inner.lock().unwrap().call_log.total_cost_usd()
+    }
+
     pub fn next_seq(&self) -> u64 {
         let mut inner = self.inner.lock().unwrap();
         inner.seq += 1;
@@ -1098,6 +1143,18 @@ impl RuntimeContext {
             .is_some_and(|journal| journal.by_seq.contains_key(&next))
     }
 
+    /// The recorded arguments of the NEXT durable call when it will be served
+    /// from the replay journal. Lets a host call reuse what it journaled the
+    /// first time (e.g. prompt attachment hashes) instead of re-deriving it
+    /// from inputs that may no longer exist.
+    pub(crate) fn next_replayed_args(&self) -> Option<serde_json::Value> {
+        let inner = self.inner.lock().unwrap();
+        let next = inner.seq + 1;
+        let journal = inner.replay_log.as_ref()?;
+        let &i = journal.by_seq.get(&next)?;
+        Some(journal.records[i].args.clone())
+    }
+
     /// The records this context would replay, in journal order. Read-only —
     /// the mainline VM-image resume (`runtime::mainline_image`, doc §5.2) uses
     /// it to decide whether an image lines up with the journal it is being
@@ -1135,6 +1192,25 @@ impl RuntimeContext {
         inner.call_log_dirty = true;
     }
 
+    /// Continue the replay from just past `seq`, with the filesystem as it
+    /// stood there: the recorded calls up to it become history, as
+    /// [`Self::adopt_replay_log_as_history`] does for the whole journal.
+    /// The debugger (`runtime::debugger`) calls this when it restores a VM
+    /// image taken at that point, since nothing will re-execute those calls.
+    pub(crate) fn skip_replay_through(&self, seq: u64, vfs: Vfs) {
+        let mut inner = self.inner.lock().unwrap();
+        let inner = &mut *inner;
+        if let Some(journal) = &inner.replay_log {
+            for record in journal.records.iter().filter(|r| r.seq <= seq) {
+                inner.call_log.push(record.clone());
+                inner.replay_hits += 1;
+            }
+        }
+        inner.seq = seq;
+        inner.vfs = vfs;
+        inner.call_log_dirty = true;
+    }
+
     pub fn try_replay(&self, seq: u64) -> Option<CallRecord> {
         let mut inner = self.inner.lock().unwrap();
         let mut record = {
@@ -1288,6 +1364,7 @@ impl RuntimeContext {
     }
 
     pub fn record_call(&self, mut record: CallRecord) {
+        crate::runtime::metrics::record_call(&record);
         let mut inner = self.inner.lock().unwrap();
         // Stamp the enclosing call (the live-call stack top) as the parent,
         // unless the record already carries one — replayed records keep the
@@ -1548,6 +1625,30 @@ impl RuntimeContext {
         self.inner.lock().unwrap().otel_run.clone()
     }
 
+    pub fn set_debugger(&self, debugger: Arc<crate::runtime::debugger::DebugController>) {
+        self.inner.lock().unwrap().debugger = Some(debugger);
+    }
+
+    pub fn debugger(&self) -> Option<Arc<crate::runtime::debugger::DebugController>> {
+        self.inner.lock().unwrap().debugger.clone()
+    }
+
+    pub fn set_profiler(&self, profiler: Arc<crate::runtime::profiler::Profiler>) {
+        self.inner.lock().unwrap().profiler = Some(profiler);
+    }
+
+    pub fn profiler(&self) -> Option<Arc<crate::runtime::profiler::Profiler>> {
+        self.inner.lock().unwrap().profiler.clone()
+    }
+
+    pub fn set_coverage(&self, coverage: Arc<crate::runtime::coverage::CoverageRecorder>) {
+        self.inner.lock().unwrap().coverage = Some(coverage);
+    }
+
+    pub fn coverage(&self) -> Option<Arc<crate::runtime::coverage::CoverageRecorder>> {
+        self.inner.lock().unwrap().coverage.clone()
+    }
+
     /// Stamp this context's calls with a `chidori.branch` variant identity.
     /// Called by `run_branches` on each freshly forked branch context so the
     /// variant's spans carry `chidori.branch_id` / `chidori.branch_label`.
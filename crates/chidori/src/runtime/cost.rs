@@ -179,25 +179,78 @@ const PRICING: &[Pricing] = &[
         input_per_mtok: 2.50,
         output_per_mtok: 10.00,
     },
+    // OpenAI embeddings (input only)
+    Pricing {
+        prefix: "text-embedding-3-small",
+        input_per_mtok: 0.02,
+        output_per_mtok: 0.0,
+    },
+    Pricing {
+        prefix: "text-embedding-3-large",
+        input_per_mtok: 0.13,
+        output_per_mtok: 0.0,
+    },
+    Pricing {
+        prefix: "text-embedding-ada-002",
+        input_per_mtok: 0.10,
+        output_per_mtok: 0.0,
+    },
+    // Google (standard tier, prompts up to 200k tokens)
+    Pricing {
+        prefix: "gemini-2.5-pro",
+        input_per_mtok: 1.25,
+        output_per_mtok: 10.00,
+    },
+    Pricing {
+        prefix: "gemini-2.5-flash-lite",
+        input_per_mtok: 0.10,
+        output_per_mtok: 0.40,
+    },
+    Pricing {
+        prefix: "gemini-2.5-flash",
+        input_per_mtok: 0.30,
+        output_per_mtok: 2.50,
+    },
+    Pricing {
+        prefix: "gemini-2.0-flash",
+        input_per_mtok: 0.10,
+        output_per_mtok: 0.40,
+    },
+    Pricing {
+        prefix: "gemini",
+        input_per_mtok: 0.30,
+        output_per_mtok: 2.50,
+    },
 ];
 
 /// Prompt-cache price multipliers relative to the base input rate. Anthropic
 /// bills cache writes at 1.25x and reads at 0.1x base input; OpenAI has no
-/// separate write charge and bills cached reads at 0.5x. Matched by model
-/// prefix like the base table.
+/// separate write charge and bills cached reads at 0.5x; Gemini bills the
+/// tokens written to a `cachedContents` resource at the base rate and reads
+/// at 0.25x (hourly storage is not modeled). Matched by model prefix like the
+/// base table.
 fn cache_multipliers(model: &str) -> (f64, f64) {
     if model.starts_with("claude") {
         (1.25, 0.10)
+    } else if model.starts_with("gemini") {
+        (1.0, 0.25)
     } else {
         (1.0, 0.50)
     }
 }
 
+/// The name prices are keyed by: Gemini also accepts its resource form
+/// `models/gemini-…`, which bills the same as the bare name.
+fn pricing_name(model: &str) -> &str {
+    model.strip_prefix("models/").unwrap_or(model)
+}
+
 /// Whether the pricing table (user-supplied `CHIDORI_PRICING` or built-in)
 /// knows this model. Callers displaying costs should distinguish "$0 because
 /// free" from "$0 because unpriced" — an unpriced model's cost is unknown,
 /// not zero.
 pub fn is_priced_model(model: &str) -> bool {
+    let model = pricing_name(model);
     user_pricing_for(model).is_some() || PRICING.iter().any(|p| model.starts_with(p.prefix))
 }
 
@@ -217,6 +270,7 @@ pub fn estimate_cost_usd_with_cache(
     cache_creation_tokens: u64,
     cache_read_tokens: u64,
 ) -> f64 {
+    let model = pricing_name(model);
     let (input_per_mtok, output_per_mtok, write_mult, read_mult) =
         if let Some(user) = user_pricing_for(model) {
             (
@@ -238,10 +292,157 @@ pub fn estimate_cost_usd_with_cache(
     input + cache_write + cache_read + output
 }
 
+/// A spend ceiling for one run, checked by the prompt host path before each
+/// live provider call against the run's recorded `TokenUsage` (priced with
+/// [`estimate_cost_usd_with_cache`], so unpriced models count as $0).
+///
+/// Set per run with `--max-cost-usd` / `CHIDORI_MAX_COST_USD`, per session
+/// with `budget` on `POST /sessions`, or per recipe. Recorded in the run's
+/// snapshot manifest so a resume keeps the same ceiling and — because the
+/// replayed journal carries the earlier legs' usage — keeps counting from
+/// where the run left off.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CostBudget {
+    #[serde(alias = "maxCostUsd")]
+    pub max_cost_usd: f64,
+    #[serde(default, alias = "onExceed")]
+    pub on_exceed: BudgetAction,
+}
+
+/// What happens when a prompt would start with the budget already spent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetAction {
+    /// Fail the prompt with a `budget:` error ([`RunErrorKind::BudgetExceeded`]).
+    ///
+    /// [`RunErrorKind::BudgetExceeded`]: crate::runtime::errors::RunErrorKind::BudgetExceeded
+    #[default]
+    Fail,
+    /// Gate the prompt like an `AskBefore` policy decision on the `budget`
+    /// target: the session server pauses for approval, a terminal run asks
+    /// the operator, anything else fails closed.
+    Ask,
+}
+
+/// Policy-approval target used when an over-budget prompt asks to proceed.
+pub const BUDGET_APPROVAL_TARGET: &str = "budget";
+
+impl CostBudget {
+    /// The budget configured by `CHIDORI_MAX_COST_USD` (and
+    /// `CHIDORI_BUDGET_ON_EXCEED=ask|fail`). A malformed or non-positive
+    /// value warns and is ignored rather than failing the run.
+    pub fn from_env() -> Option<Self> {
+        let raw = std::env::var("CHIDORI_MAX_COST_USD").ok()?;
+        let max_cost_usd = match raw.trim().parse::<f64>() {
+            Ok(value) if value.is_finite() && value > 0.0 => value,
+            _ => {
+                tracing::warn!(value = %raw, "ignoring invalid CHIDORI_MAX_COST_USD");
+                return None;
+            }
+        };
+        let on_exceed = match std::env::var("CHIDORI_BUDGET_ON_EXCEED").ok().as_deref() {
+            Some("ask") => BudgetAction::Ask,
+            _ => BudgetAction::Fail,
+        };
+        Some(Self {
+            max_cost_usd,
+            on_exceed,
+        })
+    }
+
+    /// Reject a ceiling that could never be meaningfully enforced (zero,
+    /// negative, NaN, infinite) — used where a budget arrives as JSON.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_cost_usd.is_finite() && self.max_cost_usd > 0.0 {
+            Ok(())
+        } else {
+            Err(format!(
+                "budget max_cost_usd must be a positive number, got {}",
+                self.max_cost_usd
+            ))
+        }
+    }
+
+    /// Whether `spent_usd` leaves no room for another call.
+    pub fn is_exhausted(&self, spent_usd: f64) -> bool {
+        spent_usd >= self.max_cost_usd
+    }
+
+    /// The stable error text for an exhausted budget. `budget: ` is the
+    /// prefix [`RunErrorKind::classify`] keys on.
+    ///
+    /// [`RunErrorKind::classify`]: crate::runtime::errors::RunErrorKind::classify
+    pub fn exceeded_message(&self, spent_usd: f64) -> String {
+        format!(
+            "budget: run cost ${spent_usd:.4} has reached the ${:.2} limit; raise it with \
+             --max-cost-usd / CHIDORI_MAX_COST_USD to continue",
+            self.max_cost_usd
+        )
+    }
+}
+
+/// A spend ceiling shared by many runs — a server tenant's — over a rolling
+/// `window` (all time when `None`). Each new run's budget is capped at what
+/// the quota has left, so no single run can overshoot it by more than one
+/// call.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CostQuota {
+    pub max_cost_usd: f64,
+    pub window: Option<chrono::Duration>,
+}
+
+impl CostQuota {
+    /// Where the window that ends at `now` starts.
+    pub fn since(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.window.map(|window| now - window)
+    }
+
+    /// The budget for a new run after `spent_usd` of the window is gone:
+    /// `requested` (else the `CHIDORI_MAX_COST_USD` default) capped at what
+    /// is left. `None` when the quota is spent.
+    pub fn budget_for(&self, spent_usd: f64, requested: Option<CostBudget>) -> Option<CostBudget> {
+        let left = self.max_cost_usd - spent_usd;
+        if left <= 0.0 {
+            return None;
+        }
+        Some(match requested.or_else(CostBudget::from_env) {
+            Some(budget) if budget.max_cost_usd <= left => budget,
+            Some(budget) => CostBudget {
+                max_cost_usd: left,
+                ..budget
+            },
+            None => CostBudget {
+                max_cost_usd: left,
+                on_exceed: BudgetAction::Fail,
+            },
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn quota_caps_each_run_at_what_is_left() {
+        let quota = CostQuota {
+            max_cost_usd: 10.0,
+            window: None,
+        };
+        let ask = CostBudget {
+            max_cost_usd: 2.0,
+            on_exceed: BudgetAction::Ask,
+        };
+        assert_eq!(quota.budget_for(1.0, Some(ask)), Some(ask));
+        let capped = quota.budget_for(9.5, Some(ask)).unwrap();
+        assert!((capped.max_cost_usd - 0.5).abs() < f64::EPSILON);
+        assert_eq!(capped.on_exceed, BudgetAction::Ask);
+        assert_eq!(quota.budget_for(10.0, Some(ask)), None);
+    }
+
     #[test]
     fn test_sonnet_cost() {
         // 1M input + 1M output on claude-sonnet-4-6 ≈ $3 + $15 = $18
@@ -289,5 +490,43 @@ mod tests {
             (openai_read - 1.25).abs() < 0.001,
             "openai read cost was {openai_read}"
         );
+        // gemini-2.5-flash input is $0.30/MTok; cached reads bill at 0.25x.
+        let gemini_read = estimate_cost_usd_with_cache("gemini-2.5-flash", 0, 0, 0, 1_000_000);
+        assert!(
+            (gemini_read - 0.075).abs() < 0.001,
+            "gemini read cost was {gemini_read}"
+        );
+        // The flash-lite row wins over the shorter flash prefix.
+        let lite = estimate_cost_usd("gemini-2.5-flash-lite", 1_000_000, 1_000_000);
+        assert!((lite - 0.50).abs() < 0.001, "flash-lite cost was {lite}");
+        // Gemini's resource name prices like the bare model.
+        assert!(is_priced_model("models/gemini-2.5-flash-lite"));
+        assert_eq!(
+            estimate_cost_usd("models/gemini-2.5-flash-lite", 1_000_000, 1_000_000),
+            lite
+        );
+    }
+
+    #[test]
+    fn test_budget_parses_wire_forms_and_reports_exhaustion() {
+        let budget: CostBudget =
+            serde_json::from_str(r#"{"maxCostUsd":2.5,"onExceed":"ask"}"#).unwrap();
+        assert_eq!(budget.on_exceed, BudgetAction::Ask);
+        let defaulted: CostBudget = serde_json::from_str(r#"{"max_cost_usd":1}"#).unwrap();
+        assert_eq!(defaulted.on_exceed, BudgetAction::Fail);
+
+        assert!(budget.validate().is_ok());
+        assert!(CostBudget {
+            max_cost_usd: 0.0,
+            on_exceed: BudgetAction::Fail
+        }
+        .validate()
+        .is_err());
+
+        assert!(!budget.is_exhausted(2.49));
+        assert!(budget.is_exhausted(2.5));
+        assert!(budget
+            .exceeded_message(2.5)
+            .starts_with("budget: run cost $2.5000 has reached the $2.50 limit"));
     }
 }
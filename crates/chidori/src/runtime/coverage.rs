@@ -0,0 +1,517 @@
+//! Coverage of agent code (`chidori run --coverage`, `chidori verify
+//! --coverage`).
+//!
+//! Each engine of the run compiles the agent's own modules — not `node:`
+//! shims, vendored packages or `node_modules` — with block counters
+//! ([`chidori_js::coverage`]). When the engine is done its counts are placed
+//! at `.ts` lines through the module's source map and added to the run's
+//! [`CoverageRecorder`].
+//!
+//! [`CoverageRecorder::write`] merges them into a coverage directory.
+//! `coverage.json` there holds the counts so far plus their summary. The next
+//! invocation adds to it, so a CI job running `chidori verify --coverage` once
+//! per fixture ends with the coverage of all of them. `lcov.info` is
+//! regenerated from the merged counts on every write. A line's count is the
+//! most times any block on it was entered. Writers hold an exclusive lock on
+//! `coverage.lock` across the merge and replace each file by renaming a
+//! finished temp file over it, so parallel invocations neither lose each
+//! other's counts nor leave a torn report.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::typescript::transpile::ModuleMap;
+
+/// The merged counts in a coverage directory.
+pub const REPORT_FILE: &str = "coverage.json";
+/// LCOV tracefile of the merged counts, for CI coverage tooling.
+pub const LCOV_FILE: &str = "lcov.info";
+
+/// Held exclusively while a write merges into the directory.
+const LOCK_FILE: &str = "coverage.lock";
+
+const REPORT_VERSION: u32 = 1;
+
+/// The coverage of one run, fed by each engine it drives
+/// ([`CoverageRecorder::instrument`], [`CoverageRecorder::absorb`]).
+pub struct CoverageRecorder {
+    inner: Mutex<Recorder>,
+}
+
+impl std::fmt::Debug for CoverageRecorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CoverageRecorder").finish_non_exhaustive()
+    }
+}
+
+#[derive(Default)]
+struct Recorder {
+    files: BTreeMap<String, FileCounts>,
+    /// Module key → its map, `None` for modules without one.
+    maps: HashMap<String, Option<ModuleMap>>,
+    /// Sources to map instead of the file on disk (entry modules).
+    sources: HashMap<String, String>,
+}
+
+impl Recorder {
+    fn map(&mut self, key: &str) -> Option<&ModuleMap> {
+        let sources = &self.sources;
+        self.maps
+            .entry(key.to_string())
+            .or_insert_with(|| {
+                ModuleMap::load(Path::new(key), sources.get(key).map(String::as_str))
+            })
+            .as_ref()
+    }
+}
+
+/// One file's counts, keyed so that runs of the same source line up.
+#[derive(Debug, Default, Clone)]
+struct FileCounts {
+    /// `"<line>:<column>#<n>"` — where the block's function starts and its
+    /// index in it — to the block's line and entries.
+    blocks: BTreeMap<String, BlockCount>,
+    /// `"<line>:<column>"` of each function to its name and calls.
+    functions: BTreeMap<String, FunctionCount>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+struct BlockCount {
+    line: u32,
+    hits: u64,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+struct FunctionCount {
+    name: String,
+    line: u32,
+    hits: u64,
+}
+
+impl FileCounts {
+    fn add(&mut self, other: &FileCounts) {
+        for (key, block) in &other.blocks {
+            let into = self.blocks.entry(key.clone()).or_insert(BlockCount {
+                line: block.line,
+                hits: 0,
+            });
+            into.hits += block.hits;
+        }
+        for (key, function) in &other.functions {
+            let into = self
+                .functions
+                .entry(key.clone())
+                .or_insert_with(|| FunctionCount {
+                    hits: 0,
+                    ..function.clone()
+                });
+            into.hits += function.hits;
+        }
+    }
+
+    /// Line → the most entries of a block on it.
+    fn lines(&self) -> BTreeMap<u32, u64> {
+        let mut lines = BTreeMap::new();
+        for block in self.blocks.values() {
+            let hits = lines.entry(block.line).or_insert(0);
+            *hits = block.hits.max(*hits);
+        }
+        lines
+    }
+
+    fn summary(&self) -> FileSummary {
+        let lines = self.lines();
+        FileSummary {
+            lines: Totals::of(lines.values()),
+            blocks: Totals::of(self.blocks.values().map(|b| &b.hits)),
+            functions: Totals::of(self.functions.values().map(|f| &f.hits)),
+        }
+    }
+}
+
+/// Found and hit counts of one kind of thing.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Totals {
+    pub found: u64,
+    pub hit: u64,
+    pub percent: f64,
+}
+
+impl Totals {
+    fn of<'a>(hits: impl Iterator<Item = &'a u64>) -> Totals {
+        let mut totals = Totals::default();
+        for &n in hits {
+            totals.found += 1;
+            totals.hit += u64::from(n > 0);
+        }
+        totals.with_percent()
+    }
+
+    fn add(&mut self, other: Totals) {
+        self.found += other.found;
+        self.hit += other.hit;
+        *self = self.with_percent();
+    }
+
+    fn with_percent(self) -> Totals {
+        let percent = match self.found {
+            0 => 100.0,
+            found => (self.hit as f64 * 1000.0 / found as f64).round() / 10.0,
+        };
+        Totals { percent, ..self }
+    }
+}
+
+/// Line, block and function totals of a file or a whole report.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FileSummary {
+    pub lines: Totals,
+    pub blocks: Totals,
+    pub functions: Totals,
+}
+
+/// `coverage.json`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Report {
+    version: u32,
+    /// Invocations merged in.
+    runs: u64,
+    totals: FileSummary,
+    files: BTreeMap<String, FileReport>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct FileReport {
+    lines: Totals,
+    blocks: Totals,
+    functions: Totals,
+    /// Derived from `block_hits` on every write.
+    line_hits: BTreeMap<u32, u64>,
+    block_hits: BTreeMap<String, BlockCount>,
+    function_hits: BTreeMap<String, FunctionCount>,
+}
+
+impl CoverageRecorder {
+    pub fn new() -> Arc<CoverageRecorder> {
+        Arc::new(CoverageRecorder {
+            inner: Mutex::new(Recorder::default()),
+        })
+    }
+
+    /// Install block coverage on an engine of the run, before it compiles
+    /// anything. `entry` is the key and `.ts` source of its entry module,
+    /// which is what its lines map against. Hand the result to
+    /// [`CoverageRecorder::absorb`] once the engine is done.
+    pub(crate) fn instrument(
+        self: &Arc<Self>,
+        vm: &mut chidori_js::Vm,
+        entry: (&str, &str),
+    ) -> Rc<chidori_js::coverage::Coverage> {
+        self.inner
+            .lock()
+            .unwrap()
+            .sources
+            .insert(entry.0.to_string(), entry.1.to_string());
+        let recorder = self.clone();
+        let coverage = Rc::new(chidori_js::coverage::Coverage::new(move |key| {
+            recorder.inner.lock().unwrap().map(key).is_some()
+        }));
+        vm.coverage = Some(coverage.clone());
+        coverage
+    }
+
+    /// Add an engine's counts to the run's.
+    pub(crate) fn absorb(&self, coverage: &chidori_js::coverage::Coverage) {
+        let mut recorder = self.inner.lock().unwrap();
+        let mut counts: BTreeMap<String, FileCounts> = BTreeMap::new();
+        for function in coverage.functions() {
+            let Some(label) = function.label.as_deref() else {
+                continue;
+            };
+            let Some(map) = recorder.map(label) else {
+                continue;
+            };
+            let Some((line, column)) = map.original(function.source_start) else {
+                continue;
+            };
+            let file = counts.entry(report_path(&map.path)).or_default();
+            if !function.toplevel {
+                let name = match function.name.as_str() {
+                    "" => "(anonymous)",
+                    name => name,
+                };
+                file.functions.insert(
+                    format!("{line}:{column}"),
+                    FunctionCount {
+                        name: name.to_string(),
+                        line,
+                        hits: function.blocks.first().map_or(0, |b| b.hits),
+                    },
+                );
+            }
+            for (n, block) in function.blocks.iter().enumerate() {
+                let Some((block_line, _)) = map.original(block.pos) else {
+                    continue;
+                };
+                file.blocks.insert(
+                    format!("{line}:{column}#{n}"),
+                    BlockCount {
+                        line: block_line,
+                        hits: block.hits,
+                    },
+                );
+            }
+        }
+        for (path, file) in counts {
+            recorder.files.entry(path).or_default().add(&file);
+        }
+    }
+
+    /// Merge the run's counts into `dir`'s `coverage.json` and rewrite
+    /// `lcov.info` from the result. Returns the merged totals.
+    pub fn write(&self, dir: &Path) -> Result<FileSummary> {
+        std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+        let lock_path = dir.join(LOCK_FILE);
+        let lock = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .with_context(|| format!("opening {}", lock_path.display()))?;
+        lock.lock()
+            .with_context(|| format!("locking {}", lock_path.display()))?;
+        let report_path = dir.join(REPORT_FILE);
+        let mut report: Report = match std::fs::read(&report_path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .with_context(|| format!("reading {}", report_path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Report::default(),
+            Err(e) => {
+                return Err(e).with_context(|| format!("reading {}", report_path.display()));
+            }
+        };
+        if report.version > REPORT_VERSION {
+            anyhow::bail!(
+                "{} was written by a newer chidori (version {})",
+                report_path.display(),
+                report.version
+            );
+        }
+        let mut files: BTreeMap<String, FileCounts> = std::mem::take(&mut report.files)
+            .into_iter()
+            .map(|(path, file)| {
+                let counts = FileCounts {
+                    blocks: file.block_hits,
+                    functions: file.function_hits,
+                };
+                (path, counts)
+            })
+            .collect();
+        for (path, counts) in &self.inner.lock().unwrap().files {
+            files.entry(path.clone()).or_default().add(counts);
+        }
+        report.version = REPORT_VERSION;
+        report.runs += 1;
+        report.totals = FileSummary::default();
+        for (path, counts) in files {
+            let summary = counts.summary();
+            report.totals.lines.add(summary.lines);
+            report.totals.blocks.add(summary.blocks);
+            report.totals.functions.add(summary.functions);
+            report.files.insert(
+                path,
+                FileReport {
+                    lines: summary.lines,
+                    blocks: summary.blocks,
+                    functions: summary.functions,
+                    line_hits: counts.lines(),
+                    block_hits: counts.blocks,
+                    function_hits: counts.functions,
+                },
+            );
+        }
+        replace(&report_path, serde_json::to_string_pretty(&report)?)?;
+        replace(&dir.join(LCOV_FILE), lcov(&report))?;
+        Ok(report.totals)
+    }
+}
+
+/// Write `contents` to a temp file beside `path`, then rename it over `path`.
+fn replace(path: &Path, contents: String) -> Result<()> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = path.with_file_name(format!(".{name}.{}.tmp", std::process::id()));
+    std::fs::write(&tmp, contents).with_context(|| format!("writing {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).map_err(|e| {
+        let _ = std::fs::remove_file(&tmp);
+        anyhow::Error::from(e).context(format!("replacing {}", path.display()))
+    })
+}
+
+/// A file as the report names it: relative to the working directory — the
+/// repository root, in CI — when it is under it.
+fn report_path(path: &Path) -> String {
+    let cwd = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| cwd.canonicalize().ok())
+        .unwrap_or_default();
+    path.strip_prefix(&cwd)
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn lcov(report: &Report) -> String {
+    let mut out = String::new();
+    for (path, file) in &report.files {
+        let _ = writeln!(out, "TN:\nSF:{path}");
+        // LCOV names functions, so a name used twice in the file gets its
+        // position appended.
+        let mut uses: HashMap<&str, usize> = HashMap::new();
+        for f in file.function_hits.values() {
+            *uses.entry(f.name.as_str()).or_default() += 1;
+        }
+        let functions: Vec<(String, &FunctionCount)> = file
+            .function_hits
+            .iter()
+            .map(|(key, f)| match uses[f.name.as_str()] {
+                1 => (f.name.clone(), f),
+                _ => (format!("{}:{key}", f.name), f),
+            })
+            .collect();
+        for (name, f) in &functions {
+            let _ = writeln!(out, "FN:{},{name}", f.line);
+        }
+        for (name, f) in &functions {
+            let _ = writeln!(out, "FNDA:{},{name}", f.hits);
+        }
+        let _ = writeln!(
+            out,
+            "FNF:{}\nFNH:{}",
+            file.functions.found, file.functions.hit
+        );
+        for (line, hits) in &file.line_hits {
+            let _ = writeln!(out, "DA:{line},{hits}");
+        }
+        let _ = writeln!(
+            out,
+            "LF:{}\nLH:{}\nend_of_record",
+            file.lines.found, file.lines.hit
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CoverageRecorder;
+    use std::sync::{Arc, Mutex as StdMutex};
+
+    use crate::mcp::McpManager;
+    use crate::policy::{PolicyCache, PolicyConfig};
+    use crate::providers::ProviderRegistry;
+    use crate::runtime::context::RuntimeContext;
+    use crate::runtime::rust_engine::run_agent;
+    use crate::runtime::snapshot::RuntimePolicy;
+    use crate::runtime::template::TemplateEngine;
+    use crate::runtime::typescript::bindings::HostBindingBackend;
+    use crate::tools::ToolRegistry;
+
+    const AGENT: &str = "import { sep } from 'node:path';\n\nfunction label(n: number): string {\n  if (n > 10) {\n    return 'big';\n  }\n  return 'small' + sep.length;\n}\n\nfunction unused(): number {\n  return 1;\n}\n\nexport async function agent(input: { n: number }) {\n  return label(input.n);\n}\n";
+
+    fn run(recorder: &Arc<CoverageRecorder>, path: &std::path::Path, n: u32) {
+        let ctx = RuntimeContext::new();
+        ctx.set_coverage(recorder.clone());
+        let path = path.to_path_buf();
+        std::thread::Builder::new()
+            .stack_size(crate::scheduler::JS_THREAD_STACK_BYTES)
+            .spawn(move || {
+                let backend = HostBindingBackend::for_runtime(
+                    ctx,
+                    Arc::new(ProviderRegistry::new()),
+                    Arc::new(TemplateEngine::new(".")),
+                    Arc::new(tokio::runtime::Runtime::new().unwrap()),
+                    PolicyConfig::from_env(),
+                    Arc::new(StdMutex::new(PolicyCache::default())),
+                    RuntimePolicy::durable_default("coverage-test"),
+                    Arc::new(ToolRegistry::new()),
+                    Arc::new(McpManager::new()),
+                );
+                run_agent(&path, AGENT, &serde_json::json!({ "n": n }), &backend)
+            })
+            .unwrap()
+            .join()
+            .unwrap()
+            .unwrap();
+    }
+
+    #[test]
+    fn invocations_merge_into_lcov_and_a_summary() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("agent.ts");
+        std::fs::write(&path, AGENT).unwrap();
+        let out = dir.path().join("coverage");
+
+        let recorder = CoverageRecorder::new();
+        run(&recorder, &path, 1);
+        let first = recorder.write(&out).unwrap();
+        assert!(first.lines.hit < first.lines.found, "{first:?}");
+
+        let recorder = CoverageRecorder::new();
+        run(&recorder, &path, 20);
+        let merged = recorder.write(&out).unwrap();
+        assert!(merged.lines.hit > first.lines.hit, "{merged:?}");
+        assert_eq!(merged.functions.found, 3);
+        assert_eq!(merged.functions.hit, 2);
+
+        let report: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(out.join(super::REPORT_FILE)).unwrap()).unwrap();
+        assert_eq!(report["runs"], 2);
+        let files = report["files"].as_object().unwrap();
+        assert_eq!(files.len(), 1, "only the agent's own module: {files:?}");
+        let file = files.values().next().unwrap();
+        assert_eq!(file["line_hits"]["4"], 2);
+        assert_eq!(file["line_hits"]["5"], 1);
+        assert_eq!(file["line_hits"]["7"], 1);
+        assert_eq!(file["line_hits"]["11"], 0);
+
+        let lcov = std::fs::read_to_string(out.join(super::LCOV_FILE)).unwrap();
+        assert!(lcov.contains("FN:3,label\n"), "{lcov}");
+        assert!(lcov.contains("FNDA:0,unused\n"), "{lcov}");
+        assert!(lcov.contains("DA:5,1\n"), "{lcov}");
+        assert!(lcov.contains("DA:11,0\n"), "{lcov}");
+        assert!(lcov.ends_with("end_of_record\n"), "{lcov}");
+    }
+
+    #[test]
+    fn parallel_writes_all_land_in_the_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("coverage");
+        let writers: Vec<_> = (0..8)
+            .map(|_| {
+                let out = out.clone();
+                std::thread::spawn(move || CoverageRecorder::new().write(&out).unwrap())
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        let report: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(out.join(super::REPORT_FILE)).unwrap()).unwrap();
+        assert_eq!(report["runs"], 8);
+    }
+
+    #[test]
+    fn an_unreadable_report_is_an_error_not_a_fresh_start() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join(super::REPORT_FILE)).unwrap();
+        assert!(CoverageRecorder::new().write(dir.path()).is_err());
+    }
+}
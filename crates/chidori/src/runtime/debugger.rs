@@ -0,0 +1,1648 @@
+//! Source-level debugging of agent runs (`chidori debug`), run side.
+//!
+//! A [`DebugController`] attached to a run's [`RuntimeContext`] is the
+//! debugger front end's handle: breakpoints, pause, and the commands a paused
+//! run answers. When the rust engine sees one it debug-compiles the agent's
+//! own modules and installs a [`chidori_js::debug::DebugHook`] whose session
+//! lives on the JS thread:
+//!
+//! - a shadow call stack, kept from the interpreter's frame enter/exit
+//!   reports, with a pseudo-frame for each host call in flight (its op,
+//!   journal seq and arguments);
+//! - every statement position mapped through the transpiler's source map to
+//!   its `.ts` line, which is what breakpoints and stepping compare;
+//! - stepping that follows one activation across `await`: a suspended frame
+//!   keeps its identity, so "next" over an `await` stops on the following
+//!   line of the same function once it resumes.
+//!
+//! A stopped run blocks inside the hook and answers [`Query`]s until told to
+//! [`Resume`]; all inspection reads frames and objects without running JS, so
+//! a debug session never adds to (or reorders) the journal.
+//!
+//! A recording replayed under the debugger ([`replay`]) can also be travelled
+//! back in. Replay is deterministic, so a point in the run is its clock — the
+//! statements reported so far — and going back is re-running the replay to an
+//! earlier clock, or to just before a host call's journal seq. Each run notes
+//! the new lines and host calls it passes, which is what step back and
+//! reverse continue search, and images the VM every so often between jobs:
+//! a re-run restores the last keyframe before its target rather than
+//! replaying from the top.
+
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+
+use anyhow::Result;
+use chidori_js::debug::{DebugAction, DebugHook, FrameExit, FrameId, FrameSnapshot};
+use serde_json::{json, Value};
+
+use super::call_log::CallRecord;
+use super::context::RuntimeContext;
+use super::rust_engine::RunHost;
+use super::typescript::transpile::ModuleMap;
+use super::vfs::Vfs;
+
+/// Statements between keyframes of a replay being debugged.
+pub const KEYFRAME_EVERY: u64 = 20_000;
+
+/// Keyframes kept; past this every other one goes and the spacing doubles.
+const MAX_KEYFRAMES: usize = 32;
+
+/// Why a run stopped, sent to the front end as it happens.
+#[derive(Debug, Clone)]
+pub struct Stopped {
+    /// DAP stop reason: `entry`, `breakpoint`, `function breakpoint`, `step`,
+    /// `pause` or `goto`.
+    pub reason: &'static str,
+    pub description: String,
+}
+
+/// What a stopped run can be asked. Frame indices count from the top of the
+/// stack (0); variable references are those handed out since the stop.
+#[derive(Debug, Clone)]
+pub enum Query {
+    StackTrace,
+    Scopes {
+        frame: usize,
+    },
+    Variables {
+        reference: usize,
+    },
+    Evaluate {
+        expression: String,
+        frame: Option<usize>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resume {
+    Continue,
+    Next,
+    StepIn,
+    StepOut,
+    /// Back to the previous line (replays only).
+    StepBack,
+    /// Back to the previous breakpoint hit, or the start (replays only).
+    ReverseContinue,
+    /// To just before the host call with this journal seq, in either
+    /// direction (replays only; see [`DebugController::run_to_seq`]).
+    RunToSeq(u64),
+}
+
+enum Command {
+    Query(Query, mpsc::Sender<Result<Value, String>>),
+    Resume(Resume),
+    Disconnect,
+}
+
+/// What replaying a recording has shown of the run, kept across the re-runs
+/// that travel back in it.
+struct Timeline {
+    /// The top-level journal seqs of the recording.
+    recorded: BTreeSet<u64>,
+    keyframe_every: u64,
+    keyframes: Vec<Arc<Keyframe>>,
+    /// Where the re-run under way is headed.
+    target: Option<Target>,
+    /// Each new line and host call a run has passed, by clock.
+    marks: BTreeSet<(u64, Mark)>,
+    /// The files of line marks, by index.
+    files: Vec<PathBuf>,
+}
+
+/// A VM image taken between jobs, and the replay position it belongs to.
+struct Keyframe {
+    clock: u64,
+    /// The journal seq of the last host call made before it.
+    seq: u64,
+    vfs: Vfs,
+    image: chidori_js::image::VmImage,
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Mark {
+    /// Execution reached a new line of `files[file]`.
+    Line { file: usize, line: u32 },
+    /// A host call is about to be made (ordered by seq: a statement can
+    /// make several).
+    Host { seq: u64, op: String },
+}
+
+#[derive(Clone, Copy)]
+struct Target {
+    at: Position,
+    /// The stop reason reported on arrival.
+    reason: &'static str,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Position {
+    /// The statement the clock reaches `n` at.
+    Clock(u64),
+    /// Just before the host call with journal seq `n`.
+    Seq(u64),
+}
+
+#[derive(Default)]
+struct Breakpoints {
+    /// Canonical `.ts` path → 1-based lines.
+    lines: HashMap<PathBuf, BTreeSet<u32>>,
+    /// Host op names (`prompt`, `http`, …) to stop at the call of.
+    functions: Vec<String>,
+}
+
+/// The front end's handle on a debugged run. Shared between the thread
+/// speaking the debug protocol and the run's JS thread.
+pub struct DebugController {
+    breakpoints: Mutex<Breakpoints>,
+    /// Bumped on every breakpoint change, so the run re-reads them lazily.
+    revision: AtomicU64,
+    stop_on_entry: AtomicBool,
+    pause_requested: AtomicBool,
+    disconnected: AtomicBool,
+    /// The run is unwinding to be replayed again, to an earlier point.
+    rewinding: AtomicBool,
+    paused: AtomicBool,
+    /// Set for a replayed recording, which can be travelled back in.
+    timeline: Mutex<Option<Timeline>>,
+    sender: mpsc::Sender<Command>,
+    commands: Mutex<mpsc::Receiver<Command>>,
+    events: mpsc::Sender<Stopped>,
+}
+
+impl std::fmt::Debug for DebugController {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DebugController")
+            .field("paused", &self.is_paused())
+            .finish_non_exhaustive()
+    }
+}
+
+impl DebugController {
+    /// A controller, and the stream of stops its run reports.
+    pub fn new() -> (Arc<DebugController>, mpsc::Receiver<Stopped>) {
+        let (sender, commands) = mpsc::channel();
+        let (events, stops) = mpsc::channel();
+        let controller = DebugController {
+            breakpoints: Mutex::new(Breakpoints::default()),
+            revision: AtomicU64::new(0),
+            stop_on_entry: AtomicBool::new(false),
+            pause_requested: AtomicBool::new(false),
+            disconnected: AtomicBool::new(false),
+            rewinding: AtomicBool::new(false),
+            paused: AtomicBool::new(false),
+            timeline: Mutex::new(None),
+            sender,
+            commands: Mutex::new(commands),
+            events,
+        };
+        (Arc::new(controller), stops)
+    }
+
+    /// Replace `path`'s line breakpoints. Each requested line moves to the
+    /// first line at or after it that has code; the result is where each one
+    /// landed, `None` where none does.
+    pub fn set_breakpoints(&self, path: &Path, lines: &[u32]) -> Vec<Option<u32>> {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let breakable = ModuleMap::load(&path, None)
+            .map(|map| map.breakable_lines())
+            .unwrap_or_default();
+        let placed: Vec<Option<u32>> = lines
+            .iter()
+            .map(|&line| breakable.range(line..).next().copied())
+            .collect();
+        let mut bps = self.breakpoints.lock().unwrap();
+        bps.lines
+            .insert(path, placed.iter().flatten().copied().collect());
+        self.revision.fetch_add(1, Ordering::Release);
+        placed
+    }
+
+    /// Replace the host-call breakpoints: `prompt`, `chidori.prompt` and
+    /// `__chidori_prompt` all name the `prompt` op.
+    pub fn set_function_breakpoints(&self, names: &[String]) {
+        let mut bps = self.breakpoints.lock().unwrap();
+        bps.functions = names.iter().map(|name| host_op_name(name)).collect();
+        self.revision.fetch_add(1, Ordering::Release);
+    }
+
+    pub fn set_stop_on_entry(&self, stop: bool) {
+        self.stop_on_entry.store(stop, Ordering::Release);
+    }
+
+    /// Stop at the next statement (or host call) the run reaches.
+    pub fn request_pause(&self) {
+        self.pause_requested.store(true, Ordering::Release);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Acquire)
+    }
+
+    /// Ask the stopped run `query`.
+    pub fn query(&self, query: Query) -> Result<Value, String> {
+        if !self.is_paused() {
+            return Err("the agent is running; pause it first".to_string());
+        }
+        let (reply, answer) = mpsc::channel();
+        self.sender
+            .send(Command::Query(query, reply))
+            .map_err(|_| "the agent has finished".to_string())?;
+        answer
+            .recv()
+            .map_err(|_| "the agent resumed before answering".to_string())?
+    }
+
+    pub fn resume(&self, how: Resume) {
+        let _ = self.sender.send(Command::Resume(how));
+    }
+
+    /// Make the run a replay of `recorded` that can be travelled back in,
+    /// imaging it every `keyframe_every` statements. Call before it starts,
+    /// and run it through [`replay`].
+    pub fn enable_time_travel(&self, recorded: &[CallRecord], keyframe_every: u64) {
+        *self.timeline.lock().unwrap() = Some(Timeline {
+            recorded: recorded
+                .iter()
+                .filter(|r| r.parent_seq.is_none())
+                .map(|r| r.seq)
+                .collect(),
+            keyframe_every: keyframe_every.max(1),
+            keyframes: Vec::new(),
+            target: None,
+            marks: BTreeSet::new(),
+            files: Vec::new(),
+        });
+    }
+
+    /// Move the stopped replay to just before the recorded host call `seq`.
+    pub fn run_to_seq(&self, seq: u64) -> Result<(), String> {
+        match &*self.timeline.lock().unwrap() {
+            None => return Err("only a replay (`--replay`) can jump to a seq".to_string()),
+            Some(timeline) if !timeline.recorded.contains(&seq) => {
+                return Err(format!("the recording has no top-level host call {seq}"))
+            }
+            Some(_) => {}
+        }
+        if !self.is_paused() {
+            return Err("the agent is running; pause it first".to_string());
+        }
+        self.resume(Resume::RunToSeq(seq));
+        Ok(())
+    }
+
+    /// Whether the run that just ended was unwound to be replayed again.
+    fn take_rewind(&self) -> bool {
+        self.rewinding.swap(false, Ordering::AcqRel)
+    }
+
+    /// Whether the run should unwind rather than go on.
+    fn ending(&self) -> bool {
+        self.disconnected.load(Ordering::Acquire) || self.rewinding.load(Ordering::Acquire)
+    }
+
+    /// Detach: a stopped run, and a running one at its next statement,
+    /// unwinds with an uncatchable error.
+    pub fn disconnect(&self) {
+        self.disconnected.store(true, Ordering::Release);
+        let _ = self.sender.send(Command::Disconnect);
+    }
+}
+
+/// Run a replay being debugged ([`DebugController::enable_time_travel`]),
+/// again each time the debugger rewinds it; the last run's result.
+pub(crate) fn replay<T>(
+    controller: &DebugController,
+    mut run: impl FnMut() -> Result<T>,
+) -> Result<T> {
+    loop {
+        let result = run();
+        if !controller.take_rewind() {
+            return result;
+        }
+    }
+}
+
+/// `chidori.prompt` / `__chidori_prompt` / `prompt` → `prompt`.
+pub(crate) fn host_op_name(name: &str) -> String {
+    let name = name.trim();
+    let name = name.strip_prefix("chidori.").unwrap_or(name);
+    name.strip_prefix("__chidori_").unwrap_or(name).to_string()
+}
+
+/// Where an activation last reported: its original line, and the
+/// instruction it was at (a jump back to an earlier one on the same line is
+/// a loop iteration, so a new line again).
+#[derive(Clone, Copy, Default)]
+struct Cursor {
+    line: Option<u32>,
+    ip: usize,
+}
+
+enum Shadow {
+    Js {
+        id: FrameId,
+        snap: FrameSnapshot,
+        cursor: Cursor,
+    },
+    Host {
+        op: String,
+        args: Value,
+        seq: u64,
+    },
+}
+
+enum Step {
+    None,
+    /// Stop at the next new line anywhere.
+    Into,
+    /// Stop at the next new line of `frame`, or anywhere once it has finished.
+    Over {
+        frame: FrameId,
+        finished: bool,
+    },
+    /// Stop at the next new line once `frame` has finished.
+    Out {
+        frame: FrameId,
+        finished: bool,
+    },
+}
+
+enum VarRef {
+    Locals(usize),
+    Closure(usize),
+    HostArgs(usize),
+    Js(chidori_js::Value),
+    Json(Value),
+}
+
+struct Session {
+    controller: Arc<DebugController>,
+    stack: Vec<Shadow>,
+    /// Cursors of suspended activations, restored when they resume.
+    parked: HashMap<FrameId, Cursor>,
+    /// Module key → its map, `None` for modules not debugged.
+    modules: HashMap<String, Option<Rc<ModuleMap>>>,
+    /// Sources to map instead of the file on disk (the entry module).
+    sources: HashMap<String, String>,
+    revision: u64,
+    lines: HashMap<PathBuf, BTreeSet<u32>>,
+    functions: Vec<String>,
+    step: Step,
+    refs: Vec<VarRef>,
+    ctx: RuntimeContext,
+    /// Whether this is a replay that can be travelled back in.
+    travels: bool,
+    /// Statements reported so far: where a replay is.
+    clock: u64,
+    /// Where the run is headed; it stops nowhere else on the way.
+    target: Option<Target>,
+    /// How a rewound replay got back to its target, said on arrival.
+    origin: Option<String>,
+    /// The clock to image the VM at next.
+    next_keyframe: u64,
+}
+
+thread_local! {
+    /// The session of the debugged run on this thread, shared by the engines
+    /// of the modules and tool files it evaluates.
+    static SESSION: RefCell<Option<Rc<RefCell<Session>>>> = const { RefCell::new(None) };
+}
+
+/// A run's hold on the debug session of its thread. The outermost run on
+/// the thread owns it and ends it on drop.
+pub(crate) struct Attachment {
+    session: Rc<RefCell<Session>>,
+    ctx: RuntimeContext,
+    outermost: bool,
+    /// Images the run's VM as keyframes: the outermost run of a replay.
+    keyframing: bool,
+    /// The keyframe a rewound replay restarts from.
+    keyframe: Option<Arc<Keyframe>>,
+}
+
+/// Attach a run under `ctx` to its debugger, if it has one. `entry` is the
+/// entry module's key and `.ts` source, which is what its lines map against.
+pub(crate) fn attach(ctx: &RuntimeContext, entry: (&str, &str)) -> Option<Attachment> {
+    let controller = ctx.debugger()?;
+    let existing = SESSION.with(|s| s.borrow().clone());
+    let (session, outermost, keyframe) = match existing {
+        Some(session) if Arc::ptr_eq(&session.borrow().controller, &controller) => {
+            (session, false, None)
+        }
+        _ => {
+            // A rewound replay starts from the last keyframe before its
+            // target, or from the top.
+            let (travels, target, keyframe, every) = match &mut *controller.timeline.lock().unwrap()
+            {
+                Some(timeline) => {
+                    let target = timeline.target.take();
+                    let keyframe = target.and_then(|target| {
+                        let before = |kf: &&Arc<Keyframe>| match target.at {
+                            Position::Clock(clock) => kf.clock < clock,
+                            Position::Seq(seq) => kf.seq < seq,
+                        };
+                        timeline.keyframes.iter().rev().find(before).cloned()
+                    });
+                    (true, target, keyframe, timeline.keyframe_every)
+                }
+                None => (false, None, None, u64::MAX),
+            };
+            let origin = target.map(|_| match &keyframe {
+                Some(kf) => format!("replayed from the keyframe at statement {}", kf.clock),
+                None => "replayed from the start".to_string(),
+            });
+            let clock = keyframe.as_ref().map_or(0, |kf| kf.clock);
+            let session = Rc::new(RefCell::new(Session {
+                controller,
+                stack: Vec::new(),
+                parked: HashMap::new(),
+                modules: HashMap::new(),
+                sources: HashMap::new(),
+                revision: u64::MAX,
+                lines: HashMap::new(),
+                functions: Vec::new(),
+                step: Step::None,
+                refs: Vec::new(),
+                ctx: ctx.clone(),
+                travels,
+                clock,
+                target,
+                origin,
+                next_keyframe: clock.saturating_add(every),
+            }));
+            SESSION.with(|s| *s.borrow_mut() = Some(session.clone()));
+            (session, true, keyframe)
+        }
+    };
+    let keyframing = outermost && session.borrow().travels;
+    session
+        .borrow_mut()
+        .sources
+        .insert(entry.0.to_string(), entry.1.to_string());
+    Some(Attachment {
+        session,
+        ctx: ctx.clone(),
+        outermost,
+        keyframing,
+        keyframe,
+    })
+}
+
+impl Attachment {
+    /// The hook to install on the run's VM.
+    pub(crate) fn hook(&self) -> Box<dyn DebugHook> {
+        Box::new(VmHook {
+            session: self.session.clone(),
+            keyframes: self.keyframing,
+        })
+    }
+
+    /// Whether the run's engine images for keyframes, and so must mark its
+    /// image baseline and run the entry through [`Attachment::drive`].
+    pub(crate) fn keyframing(&self) -> bool {
+        self.keyframing
+    }
+
+    /// Run the entry of a keyframing run: the keyframe the replay was
+    /// rewound to restored, and the run finished from it, or the whole
+    /// entry from the top.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn drive(
+        &self,
+        engine: &mut chidori_js::Engine,
+        entry_key: &str,
+        js: &str,
+        input: &Value,
+        slot: &Rc<RefCell<Option<chidori_js::Value>>>,
+        fallback_export: &str,
+        load: &mut dyn FnMut(&str, &str) -> std::result::Result<(String, String), String>,
+    ) -> std::result::Result<Value, String> {
+        let outcome = match &self.keyframe {
+            Some(keyframe) => {
+                if let Err(err) = engine
+                    .prepare_image_units(entry_key, js, load)
+                    .and_then(|()| {
+                        engine
+                            .vm
+                            .restore_image(&keyframe.image)
+                            .map_err(|e| e.to_string())
+                    })
+                {
+                    return Err(self.keyframe_rejected(err));
+                }
+                // The calls before the keyframe are behind the restored VM.
+                self.ctx
+                    .skip_replay_through(keyframe.seq, keyframe.vfs.clone());
+                engine.finish_entry()
+            }
+            None => engine.run_entrypoint_graph_suspendable(
+                entry_key,
+                js,
+                input,
+                slot,
+                fallback_export,
+                load,
+            ),
+        };
+        match outcome? {
+            chidori_js::EntryOutcome::Settled(value) => Ok(value),
+            // Nothing suspends: the run has no effect_suspend.
+            chidori_js::EntryOutcome::Suspended => {
+                Err("the entry suspended under the debugger".to_string())
+            }
+        }
+    }
+
+    /// A keyframe that did not restore: the replay is run again from the
+    /// top to the same target, and goes on without keyframes.
+    fn keyframe_rejected(&self, err: String) -> String {
+        tracing::debug!(error = %err, "debugger keyframe did not restore; replaying from the start");
+        let session = self.session.borrow();
+        if let Some(timeline) = &mut *session.controller.timeline.lock().unwrap() {
+            timeline.keyframes.clear();
+            timeline.keyframe_every = u64::MAX;
+            timeline.target = session.target;
+        }
+        session.controller.rewinding.store(true, Ordering::Release);
+        format!("keyframe did not restore: {err}")
+    }
+
+    /// `host`, reporting each host call to the session as it crosses.
+    pub(crate) fn wrap_host(&self, host: Rc<dyn RunHost>) -> Rc<dyn RunHost> {
+        Rc::new(DebuggedHost {
+            inner: host,
+            session: self.session.clone(),
+            ctx: self.ctx.clone(),
+        })
+    }
+}
+
+impl Drop for Attachment {
+    fn drop(&mut self) {
+        if self.outermost {
+            SESSION.with(|s| s.borrow_mut().take());
+        }
+    }
+}
+
+struct VmHook {
+    session: Rc<RefCell<Session>>,
+    keyframes: bool,
+}
+
+impl DebugHook for VmHook {
+    fn debug_module(&mut self, key: &str) -> bool {
+        self.session.borrow_mut().module(key).is_some()
+    }
+
+    fn on_frame_enter(&mut self, id: FrameId, frame: &chidori_js::vm::Frame) {
+        let mut session = self.session.borrow_mut();
+        // A fresh call at a reused address is a new activation; a resumed
+        // one picks up on its line.
+        let cursor = match session.parked.remove(&id) {
+            Some(parked) if frame.ip != 0 => parked,
+            _ => Cursor::default(),
+        };
+        session.stack.push(Shadow::Js {
+            id,
+            snap: FrameSnapshot::of(frame),
+            cursor,
+        });
+    }
+
+    fn on_frame_exit(&mut self, id: FrameId, exit: FrameExit) {
+        self.session.borrow_mut().exit(id, exit);
+    }
+
+    fn on_statement(&mut self, frame: &chidori_js::vm::Frame, pos: u32) -> DebugAction {
+        self.session.borrow_mut().statement(frame, pos)
+    }
+
+    fn on_idle(&mut self, vm: &chidori_js::vm::Vm) {
+        if self.keyframes {
+            self.session.borrow_mut().keyframe(vm);
+        }
+    }
+}
+
+/// The run's host, with each call shown as a pseudo-frame while in flight.
+struct DebuggedHost {
+    inner: Rc<dyn RunHost>,
+    session: Rc<RefCell<Session>>,
+    ctx: RuntimeContext,
+}
+
+impl RunHost for DebuggedHost {
+    fn call(&self, op: &str, args: &Value) -> std::result::Result<Value, String> {
+        if op == "__module_load" {
+            return self.inner.call(op, args);
+        }
+        // The session is released across the call: the host may run nested
+        // agent code that reports to it.
+        let seq = self.ctx.current_seq() + 1;
+        let action = self.session.borrow_mut().host_enter(op, args, seq);
+        let result = match action {
+            DebugAction::Continue => self.inner.call(op, args),
+            DebugAction::Abort => Err("execution aborted by the debugger".to_string()),
+        };
+        self.session.borrow_mut().host_exit();
+        result
+    }
+
+    fn prelude(&self) -> Option<String> {
+        self.inner.prelude()
+    }
+
+    fn trace_sink(&self, js: &str) -> Option<Box<dyn chidori_js::TraceObserver>> {
+        self.inner.trace_sink(js)
+    }
+
+    // No mainline imaging: a restored image would resume without the
+    // frames the debugger is tracking.
+
+    fn entrypoint_overlay(&self) -> Option<&'static str> {
+        self.inner.entrypoint_overlay()
+    }
+}
+
+impl Session {
+    fn module(&mut self, key: &str) -> Option<Rc<ModuleMap>> {
+        if let Some(map) = self.modules.get(key) {
+            return map.clone();
+        }
+        let source = self.sources.get(key).map(String::as_str);
+        let map = ModuleMap::load(Path::new(key), source).map(Rc::new);
+        self.modules.insert(key.to_string(), map.clone());
+        map
+    }
+
+    fn refresh_breakpoints(&mut self) {
+        let revision = self.controller.revision.load(Ordering::Acquire);
+        if revision != self.revision {
+            let bps = self.controller.breakpoints.lock().unwrap();
+            self.lines = bps.lines.clone();
+            self.functions = bps.functions.clone();
+            self.revision = revision;
+        }
+    }
+
+    fn exit(&mut self, id: FrameId, exit: FrameExit) {
+        let Some(idx) = self
+            .stack
+            .iter()
+            .rposition(|s| matches!(s, Shadow::Js { id: top, .. } if *top == id))
+        else {
+            return;
+        };
+        if let Shadow::Js { cursor, .. } = &self.stack[idx] {
+            if exit == FrameExit::Suspended {
+                self.parked.insert(id, *cursor);
+            } else {
+                self.parked.remove(&id);
+            }
+        }
+        self.stack.truncate(idx);
+        if exit != FrameExit::Suspended {
+            if let Step::Over { frame, finished } | Step::Out { frame, finished } = &mut self.step {
+                if *frame == id {
+                    *finished = true;
+                }
+            }
+        }
+    }
+
+    fn statement(&mut self, frame: &chidori_js::vm::Frame, pos: u32) -> DebugAction {
+        if self.controller.ending() {
+            return DebugAction::Abort;
+        }
+        let proto = &frame.func.proto;
+        if proto.debug.is_none() {
+            return DebugAction::Continue;
+        }
+        self.clock += 1;
+        let id = FrameId::of(frame);
+        let Some(Shadow::Js { id: top, snap, .. }) = self.stack.last_mut() else {
+            return DebugAction::Continue;
+        };
+        if *top != id {
+            return DebugAction::Continue;
+        }
+        snap.ip = frame.ip;
+        snap.cells.clone_from(&frame.cells);
+        let label = proto.source_label.clone();
+        let Some(map) = label.and_then(|label| {
+            let key: &str = &label;
+            self.modules.get(key).cloned().flatten()
+        }) else {
+            return DebugAction::Continue;
+        };
+        let Some((line, _)) = map.original(pos) else {
+            return DebugAction::Continue;
+        };
+        let Some(Shadow::Js { cursor, .. }) = self.stack.last_mut() else {
+            return DebugAction::Continue;
+        };
+        let new_line = cursor.line != Some(line) || frame.ip < cursor.ip;
+        *cursor = Cursor {
+            line: Some(line),
+            ip: frame.ip,
+        };
+        if new_line && self.travels {
+            self.mark_line(&map.path, line);
+        }
+        let file = map.path.file_name().unwrap_or_default().to_string_lossy();
+        if let Some(target) = self.target {
+            if !matches!(target.at, Position::Clock(clock) if self.clock >= clock) {
+                return DebugAction::Continue;
+            }
+            self.target = None;
+            return self.arrive(target.reason, format!("{file}:{line}"));
+        }
+        if !new_line {
+            return DebugAction::Continue;
+        }
+        self.refresh_breakpoints();
+        let controller = &self.controller;
+        let reason = if controller.stop_on_entry.swap(false, Ordering::AcqRel) {
+            "entry"
+        } else if controller.pause_requested.swap(false, Ordering::AcqRel) {
+            "pause"
+        } else if match self.step {
+            Step::None => false,
+            Step::Into => true,
+            Step::Over { frame, finished } => finished || frame == id,
+            Step::Out { finished, .. } => finished,
+        } {
+            "step"
+        } else if self
+            .lines
+            .get(&map.path)
+            .is_some_and(|lines| lines.contains(&line))
+        {
+            "breakpoint"
+        } else {
+            return DebugAction::Continue;
+        };
+        self.stop(reason, format!("{file}:{line}"))
+    }
+
+    fn mark_line(&self, path: &Path, line: u32) {
+        let mut timeline = self.controller.timeline.lock().unwrap();
+        let Some(timeline) = timeline.as_mut() else {
+            return;
+        };
+        let file = match timeline.files.iter().position(|f| f == path) {
+            Some(file) => file,
+            None => {
+                timeline.files.push(path.to_path_buf());
+                timeline.files.len() - 1
+            }
+        };
+        timeline
+            .marks
+            .insert((self.clock, Mark::Line { file, line }));
+    }
+
+    /// Image the VM as a keyframe if one is due. Only between jobs with the
+    /// entry parked on the global is there a continuation to restore.
+    fn keyframe(&mut self, vm: &chidori_js::vm::Vm) {
+        if self.clock < self.next_keyframe || self.controller.ending() {
+            return;
+        }
+        let entry = chidori_js::value::PropertyKey::str(chidori_js::ENTRY_PROMISE_GLOBAL);
+        if vm.realm.global.borrow().own_get(&entry).is_none() {
+            return;
+        }
+        let mut timeline = self.controller.timeline.lock().unwrap();
+        let Some(timeline) = timeline.as_mut() else {
+            return;
+        };
+        self.next_keyframe = self.clock.saturating_add(timeline.keyframe_every);
+        // A rewound run passes the keyframes it started behind again.
+        if timeline
+            .keyframes
+            .last()
+            .is_some_and(|kf| kf.clock >= self.clock)
+        {
+            return;
+        }
+        let image = match vm.snapshot_image() {
+            Ok(image) => image,
+            Err(err) => {
+                tracing::debug!(error = %err, clock = self.clock, "no debugger keyframe here");
+                return;
+            }
+        };
+        timeline.keyframes.push(Arc::new(Keyframe {
+            clock: self.clock,
+            seq: self.ctx.current_seq(),
+            vfs: self.ctx.vfs_snapshot(),
+            image,
+        }));
+        if timeline.keyframes.len() > MAX_KEYFRAMES {
+            // Thin to every other one, keeping the latest.
+            let len = timeline.keyframes.len();
+            let mut i = 0;
+            timeline.keyframes.retain(|_| {
+                i += 1;
+                (len - i) % 2 == 0
+            });
+            timeline.keyframe_every = timeline.keyframe_every.saturating_mul(2);
+            self.next_keyframe = self.clock.saturating_add(timeline.keyframe_every);
+        }
+    }
+
+    fn host_enter(&mut self, op: &str, args: &Value, seq: u64) -> DebugAction {
+        self.stack.push(Shadow::Host {
+            op: op.to_string(),
+            args: args.clone(),
+            seq,
+        });
+        if self.controller.ending() {
+            return DebugAction::Abort;
+        }
+        let name = host_op_name(op);
+        if self.travels {
+            if let Some(timeline) = &mut *self.controller.timeline.lock().unwrap() {
+                let mark = Mark::Host {
+                    seq,
+                    op: name.clone(),
+                };
+                timeline.marks.insert((self.clock, mark));
+            }
+        }
+        if let Some(target) = self.target {
+            if target.at != Position::Seq(seq) {
+                return DebugAction::Continue;
+            }
+            self.target = None;
+            return self.arrive(target.reason, format!("host call {name} (seq {seq})"));
+        }
+        self.refresh_breakpoints();
+        let reason = if self
+            .controller
+            .pause_requested
+            .swap(false, Ordering::AcqRel)
+        {
+            "pause"
+        } else if matches!(self.step, Step::Into) {
+            "step"
+        } else if self.functions.contains(&name) {
+            "function breakpoint"
+        } else {
+            return DebugAction::Continue;
+        };
+        self.stop(reason, format!("host call {name} (seq {seq})"))
+    }
+
+    fn host_exit(&mut self) {
+        if matches!(self.stack.last(), Some(Shadow::Host { .. })) {
+            self.stack.pop();
+        }
+    }
+
+    /// Report the stop and serve the front end until it resumes the run.
+    fn stop(&mut self, reason: &'static str, description: String) -> DebugAction {
+        let controller = self.controller.clone();
+        let receiver = controller.commands.lock().unwrap();
+        // Anything sent while the run was going is stale.
+        while let Ok(command) = receiver.try_recv() {
+            match command {
+                Command::Query(_, reply) => {
+                    let _ = reply.send(Err("the agent was running".to_string()));
+                }
+                Command::Resume(_) => {}
+                Command::Disconnect => return DebugAction::Abort,
+            }
+        }
+        self.refs.clear();
+        self.step = Step::None;
+        self.controller.paused.store(true, Ordering::Release);
+        let _ = self.controller.events.send(Stopped {
+            reason,
+            description,
+        });
+        let action = loop {
+            match receiver.recv() {
+                Ok(Command::Query(query, reply)) => {
+                    let _ = reply.send(self.answer(query));
+                }
+                Ok(Command::Resume(how)) => {
+                    if let Some(action) = self.travel(how) {
+                        break action;
+                    }
+                    self.step = self.step_for(how);
+                    break DebugAction::Continue;
+                }
+                Ok(Command::Disconnect) | Err(_) => break DebugAction::Abort,
+            }
+        };
+        self.controller.paused.store(false, Ordering::Release);
+        self.refs.clear();
+        action
+    }
+
+    /// A stop at a rewound replay's target, which says where the re-run
+    /// started.
+    fn arrive(&mut self, reason: &'static str, place: String) -> DebugAction {
+        let description = match self.origin.take() {
+            Some(origin) => format!("{place}, {origin}"),
+            None => place,
+        };
+        self.stop(reason, description)
+    }
+
+    /// Take a stopped replay where `how` travels to: forward by running on
+    /// to the target, back by unwinding the run for [`replay`] to start
+    /// again towards it. `None` for a plain resume.
+    fn travel(&mut self, how: Resume) -> Option<DebugAction> {
+        if !self.travels {
+            return None;
+        }
+        let target = match how {
+            Resume::Continue | Resume::Next | Resume::StepIn | Resume::StepOut => return None,
+            Resume::StepBack => self.earlier(|_, mark| matches!(mark, Mark::Line { .. }), "step"),
+            Resume::ReverseContinue => {
+                self.refresh_breakpoints();
+                let (lines, functions) = (&self.lines, &self.functions);
+                self.earlier(
+                    |files, mark| match mark {
+                        Mark::Line { file, line } => lines
+                            .get(&files[*file])
+                            .is_some_and(|lines| lines.contains(line)),
+                        Mark::Host { op, .. } => functions.contains(op),
+                    },
+                    "breakpoint",
+                )
+            }
+            Resume::RunToSeq(seq) => {
+                let target = Target {
+                    at: Position::Seq(seq),
+                    reason: "goto",
+                };
+                let here =
+                    matches!(self.stack.last(), Some(Shadow::Host { seq: s, .. }) if *s == seq);
+                if seq > self.ctx.current_seq() && !here {
+                    self.target = Some(target);
+                    return Some(DebugAction::Continue);
+                }
+                target
+            }
+        };
+        if let Some(timeline) = &mut *self.controller.timeline.lock().unwrap() {
+            timeline.target = Some(target);
+        }
+        self.controller.rewinding.store(true, Ordering::Release);
+        Some(DebugAction::Abort)
+    }
+
+    /// The last mark before where the run is stopped that `pick` accepts,
+    /// as a target; the first statement when there is none.
+    fn earlier(&self, pick: impl Fn(&[PathBuf], &Mark) -> bool, reason: &'static str) -> Target {
+        // Host marks sort after the line marks of their statement.
+        let here = match self.stack.last() {
+            Some(Shadow::Host { seq, .. }) => Mark::Host {
+                seq: *seq,
+                op: String::new(),
+            },
+            _ => Mark::Line { file: 0, line: 0 },
+        };
+        let timeline = self.controller.timeline.lock().unwrap();
+        let found = timeline.as_ref().and_then(|timeline| {
+            timeline
+                .marks
+                .range(..(self.clock, here))
+                .rev()
+                .find(|(_, mark)| pick(&timeline.files, mark))
+                .cloned()
+        });
+        match found {
+            Some((clock, Mark::Line { .. })) => Target {
+                at: Position::Clock(clock),
+                reason,
+            },
+            Some((_, Mark::Host { seq, .. })) => Target {
+                at: Position::Seq(seq),
+                reason: "function breakpoint",
+            },
+            None => Target {
+                at: Position::Clock(1),
+                reason: "entry",
+            },
+        }
+    }
+
+    fn step_for(&self, how: Resume) -> Step {
+        let top_js = self.stack.iter().rev().find_map(|s| match s {
+            Shadow::Js { id, .. } => Some(*id),
+            Shadow::Host { .. } => None,
+        });
+        let at_host = matches!(self.stack.last(), Some(Shadow::Host { .. }));
+        match (how, top_js) {
+            (Resume::Continue, _) => Step::None,
+            // Only a replay travels (see `travel`).
+            (Resume::StepBack | Resume::ReverseContinue | Resume::RunToSeq(_), _) => Step::None,
+            (Resume::StepIn, _) | (_, None) => Step::Into,
+            // Over or out of a host call: back to the next line of its caller.
+            (Resume::Next, Some(frame)) | (Resume::StepOut, Some(frame)) if at_host => Step::Over {
+                frame,
+                finished: false,
+            },
+            (Resume::Next, Some(frame)) => Step::Over {
+                frame,
+                finished: false,
+            },
+            (Resume::StepOut, Some(frame)) => Step::Out {
+                frame,
+                finished: false,
+            },
+        }
+    }
+
+    fn answer(&mut self, query: Query) -> Result<Value, String> {
+        match query {
+            Query::StackTrace => {
+                let frames: Vec<Value> = self
+                    .stack
+                    .iter()
+                    .rev()
+                    .enumerate()
+                    .map(|(id, shadow)| self.stack_frame(id, shadow))
+                    .collect();
+                Ok(json!({ "stackFrames": frames, "totalFrames": frames.len() }))
+            }
+            Query::Scopes { frame } => {
+                let idx = self.frame_index(frame)?;
+                let scopes = match &self.stack[idx] {
+                    Shadow::Js { snap, .. } => {
+                        let captures = !snap.closure().is_empty();
+                        let mut scopes = vec![self.scope("Locals", VarRef::Locals(idx))];
+                        if captures {
+                            scopes.push(self.scope("Closure", VarRef::Closure(idx)));
+                        }
+                        scopes
+                    }
+                    Shadow::Host { .. } => vec![self.scope("Arguments", VarRef::HostArgs(idx))],
+                };
+                Ok(json!({ "scopes": scopes }))
+            }
+            Query::Variables { reference } => {
+                let entries = self.entries(reference)?;
+                let variables: Vec<Value> = entries
+                    .into_iter()
+                    .map(|(name, value)| {
+                        let (value, reference) = self.render(value);
+                        json!({ "name": name, "value": value, "variablesReference": reference })
+                    })
+                    .collect();
+                Ok(json!({ "variables": variables }))
+            }
+            Query::Evaluate { expression, frame } => {
+                let idx = self.frame_index(frame.unwrap_or(0))?;
+                let value = self.evaluate(idx, &expression).ok_or_else(|| {
+                    format!(
+                        "cannot evaluate `{expression}`: a paused agent evaluates variable \
+                         paths (`name`, `a.b`, `a[0]`) only"
+                    )
+                })?;
+                let (result, reference) = self.render(value);
+                Ok(json!({ "result": result, "variablesReference": reference }))
+            }
+        }
+    }
+
+    fn frame_index(&self, frame: usize) -> Result<usize, String> {
+        self.stack
+            .len()
+            .checked_sub(frame + 1)
+            .ok_or_else(|| format!("no frame {frame}"))
+    }
+
+    fn stack_frame(&self, id: usize, shadow: &Shadow) -> Value {
+        match shadow {
+            Shadow::Js { snap, .. } => {
+                let map = snap
+                    .label()
+                    .and_then(|label| self.modules.get(label).cloned().flatten());
+                let position = map
+                    .as_ref()
+                    .zip(snap.pos())
+                    .and_then(|(map, pos)| map.original(pos));
+                match (map, position) {
+                    (Some(map), Some((line, column))) => json!({
+                        "id": id,
+                        "name": snap.name(),
+                        "line": line,
+                        "column": column,
+                        "source": {
+                            "name": map.path.file_name().unwrap_or_default().to_string_lossy(),
+                            "path": map.path,
+                        },
+                    }),
+                    _ => json!({
+                        "id": id,
+                        "name": snap.name(),
+                        "line": 0,
+                        "column": 0,
+                        "presentationHint": "subtle",
+                    }),
+                }
+            }
+            Shadow::Host { op, seq, .. } => json!({
+                "id": id,
+                "name": format!("host call {} (seq {seq})", host_op_name(op)),
+                "line": 0,
+                "column": 0,
+                "presentationHint": "label",
+            }),
+        }
+    }
+
+    fn scope(&mut self, name: &str, var: VarRef) -> Value {
+        self.refs.push(var);
+        json!({ "name": name, "variablesReference": self.refs.len(), "expensive": false })
+    }
+
+    fn entries(&self, reference: usize) -> Result<Vec<(String, Entry)>, String> {
+        let var = reference
+            .checked_sub(1)
+            .and_then(|i| self.refs.get(i))
+            .ok_or_else(|| format!("no variables reference {reference}"))?;
+        let js = |pairs: Vec<(String, chidori_js::Value)>| {
+            pairs
+                .into_iter()
+                .map(|(name, value)| (name, Entry::Js(Some(value))))
+                .collect()
+        };
+        Ok(match var {
+            VarRef::Locals(idx) | VarRef::Closure(idx) => match &self.stack[*idx] {
+                Shadow::Js { snap, .. } if matches!(var, VarRef::Locals(_)) => js(snap.locals()),
+                Shadow::Js { snap, .. } => js(snap.closure()),
+                Shadow::Host { .. } => Vec::new(),
+            },
+            VarRef::HostArgs(idx) => match &self.stack[*idx] {
+                Shadow::Host { args, .. } => json_children(args),
+                Shadow::Js { .. } => Vec::new(),
+            },
+            VarRef::Js(value) => chidori_js::debug::children(value)
+                .into_iter()
+                .map(|(name, value)| (name, Entry::Js(value)))
+                .collect(),
+            VarRef::Json(value) => json_children(value),
+        })
+    }
+
+    /// The display string for `entry`, and the reference that expands it.
+    fn render(&mut self, entry: Entry) -> (String, usize) {
+        match entry {
+            Entry::Js(None) => ("(accessor)".to_string(), 0),
+            Entry::Js(Some(value)) => {
+                let shown = chidori_js::debug::describe(&value);
+                if chidori_js::debug::has_children(&value) {
+                    self.refs.push(VarRef::Js(value));
+                    (shown, self.refs.len())
+                } else {
+                    (shown, 0)
+                }
+            }
+            Entry::Json(value) => match &value {
+                Value::Array(items) if !items.is_empty() => {
+                    let shown = format!("Array({})", items.len());
+                    self.refs.push(VarRef::Json(value));
+                    (shown, self.refs.len())
+                }
+                Value::Object(fields) if !fields.is_empty() => {
+                    self.refs.push(VarRef::Json(value));
+                    ("{…}".to_string(), self.refs.len())
+                }
+                _ => (value.to_string(), 0),
+            },
+        }
+    }
+
+    /// Resolve a variable path against the frame at `idx`.
+    fn evaluate(&self, idx: usize, expression: &str) -> Option<Entry> {
+        let mut segments = parse_path(expression)?.into_iter();
+        let root = segments.next()?;
+        let mut entry = match &self.stack[idx] {
+            Shadow::Js { snap, .. } => snap
+                .locals()
+                .into_iter()
+                .rev()
+                .chain(snap.closure())
+                .find(|(name, _)| *name == root)
+                .map(|(_, value)| Entry::Js(Some(value)))?,
+            Shadow::Host { args, .. } => json_children(args)
+                .into_iter()
+                .find(|(name, _)| *name == root)
+                .map(|(_, entry)| entry)?,
+        };
+        for segment in segments {
+            entry = match entry {
+                Entry::Js(Some(value)) => chidori_js::debug::children(&value)
+                    .into_iter()
+                    .find(|(name, _)| *name == segment)
+                    .map(|(_, value)| Entry::Js(value))?,
+                Entry::Json(value) => json_children(&value)
+                    .into_iter()
+                    .find(|(name, _)| *name == segment)
+                    .map(|(_, entry)| entry)?,
+                Entry::Js(None) => return None,
+            };
+        }
+        Some(entry)
+    }
+}
+
+/// A value shown in the variables view: a JS value (`None` for an accessor,
+/// which is never invoked) or a host call's JSON argument.
+enum Entry {
+    Js(Option<chidori_js::Value>),
+    Json(Value),
+}
+
+fn json_children(value: &Value) -> Vec<(String, Entry)> {
+    match value {
+        Value::Object(fields) => fields
+            .iter()
+            .map(|(k, v)| (k.clone(), Entry::Json(v.clone())))
+            .collect(),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (i.to_string(), Entry::Json(v.clone())))
+            .collect(),
+        Value::Null => Vec::new(),
+        other => vec![("args".to_string(), Entry::Json(other.clone()))],
+    }
+}
+
+/// `a.b[0]["c d"]` → `["a", "b", "0", "c d"]`; `None` for anything else.
+fn parse_path(expression: &str) -> Option<Vec<String>> {
+    let mut chars = expression.trim().chars().peekable();
+    let mut segments = Vec::new();
+    let ident = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+        let mut name = String::new();
+        while let Some(&c) = chars.peek() {
+            if c.is_alphanumeric() || c == '_' || c == '$' {
+                name.push(c);
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        (!name.is_empty()).then_some(name)
+    };
+    segments.push(ident(&mut chars)?);
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => segments.push(ident(&mut chars)?),
+            '[' => {
+                let rest: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                let rest = rest.trim();
+                let segment = match rest.strip_prefix('"').and_then(|r| r.strip_suffix('"')) {
+                    Some(quoted) => quoted.to_string(),
+                    None => match rest.strip_prefix('\'').and_then(|r| r.strip_suffix('\'')) {
+                        Some(quoted) => quoted.to_string(),
+                        None => {
+                            rest.parse::<u64>().ok()?;
+                            rest.to_string()
+                        }
+                    },
+                };
+                segments.push(segment);
+            }
+            _ => return None,
+        }
+    }
+    Some(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{host_op_name, parse_path, replay, DebugController, ModuleMap, Query, Resume};
+    use std::sync::{Arc, Mutex as StdMutex};
+
+    use crate::mcp::McpManager;
+    use crate::policy::{PolicyCache, PolicyConfig};
+    use crate::providers::ProviderRegistry;
+    use crate::runtime::context::RuntimeContext;
+    use crate::runtime::rust_engine::run_agent;
+    use crate::runtime::snapshot::RuntimePolicy;
+    use crate::runtime::template::TemplateEngine;
+    use crate::runtime::typescript::bindings::HostBindingBackend;
+    use crate::tools::ToolRegistry;
+
+    /// Run the agent at `path` under `ctx`.
+    fn run_in(
+        path: &std::path::Path,
+        input: &serde_json::Value,
+        ctx: RuntimeContext,
+    ) -> anyhow::Result<serde_json::Value> {
+        let source = std::fs::read_to_string(path).unwrap();
+        let backend = HostBindingBackend::for_runtime(
+            ctx,
+            Arc::new(ProviderRegistry::new()),
+            Arc::new(TemplateEngine::new(".")),
+            Arc::new(tokio::runtime::Runtime::new().unwrap()),
+            PolicyConfig::from_env(),
+            Arc::new(StdMutex::new(PolicyCache::default())),
+            RuntimePolicy::durable_default("debugger-test"),
+            Arc::new(ToolRegistry::new()),
+            Arc::new(McpManager::new()),
+        );
+        run_agent(path, &source, input, &backend)
+    }
+
+    /// Run the agent at `path` on a JS-sized thread with `controller`
+    /// attached.
+    fn spawn_debugged(
+        path: &std::path::Path,
+        input: serde_json::Value,
+        controller: &Arc<DebugController>,
+    ) -> std::thread::JoinHandle<anyhow::Result<serde_json::Value>> {
+        let ctx = RuntimeContext::new();
+        ctx.set_debugger(controller.clone());
+        let path = path.to_path_buf();
+        std::thread::Builder::new()
+            .stack_size(crate::scheduler::JS_THREAD_STACK_BYTES)
+            .spawn(move || run_in(&path, &input, ctx))
+            .unwrap()
+    }
+
+    #[test]
+    fn a_host_call_breakpoint_shows_the_call_as_a_frame_with_its_arguments() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("agent.ts");
+        std::fs::write(
+            &path,
+            "export async function agent() {\n  const note = \"hello\";\n  await chidori.log(note);\n  return note;\n}\n",
+        )
+        .unwrap();
+        let (controller, stops) = DebugController::new();
+        controller.set_function_breakpoints(&["chidori.log".to_string()]);
+        let run = spawn_debugged(&path, serde_json::json!({}), &controller);
+
+        let stop = stops.recv().unwrap();
+        assert_eq!(stop.reason, "function breakpoint");
+        let trace = controller.query(Query::StackTrace).unwrap();
+        let frames = trace["stackFrames"].as_array().unwrap();
+        assert!(
+            frames[0]["name"]
+                .as_str()
+                .unwrap()
+                .starts_with("host call log (seq "),
+            "{frames:?}"
+        );
+        assert_eq!(frames[1]["name"], "agent");
+        assert_eq!(frames[1]["line"], 3);
+        let scopes = controller.query(Query::Scopes { frame: 0 }).unwrap();
+        assert_eq!(scopes["scopes"][0]["name"], "Arguments");
+        let reference = scopes["scopes"][0]["variablesReference"].as_u64().unwrap();
+        let vars = controller
+            .query(Query::Variables {
+                reference: reference as usize,
+            })
+            .unwrap();
+        assert!(
+            vars.to_string().contains("hello"),
+            "the journaled arguments carry the message: {vars}"
+        );
+
+        // Over the host call: back in the agent, on the next line.
+        controller.resume(Resume::Next);
+        assert_eq!(stops.recv().unwrap().reason, "step");
+        let trace = controller.query(Query::StackTrace).unwrap();
+        assert_eq!(trace["stackFrames"][0]["line"], 4, "{trace}");
+        controller.resume(Resume::Continue);
+        assert_eq!(run.join().unwrap().unwrap(), serde_json::json!("hello"));
+    }
+
+    #[test]
+    fn variable_paths_parse_and_everything_else_is_refused() {
+        assert_eq!(
+            parse_path("a.b[0][\"c d\"]").unwrap(),
+            vec!["a", "b", "0", "c d"]
+        );
+        assert_eq!(parse_path(" x ").unwrap(), vec!["x"]);
+        assert!(parse_path("a + b").is_none());
+        assert!(parse_path("f()").is_none());
+        assert!(parse_path("a[i]").is_none());
+    }
+
+    #[test]
+    fn host_breakpoint_names_normalize_to_the_op() {
+        for name in ["prompt", "chidori.prompt", "__chidori_prompt"] {
+            assert_eq!(host_op_name(name), "prompt");
+        }
+    }
+
+    #[test]
+    fn ts_lines_map_through_the_transpiled_module() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("agent.ts");
+        let source = "interface In {\n  n: number;\n}\n\nconst double = (x: number): number => x * 2;\nexport const out: number = double(21);\n";
+        std::fs::write(&path, source).unwrap();
+        let map = ModuleMap::load(&path, None).expect("a .ts module maps");
+        let breakable = map.breakable_lines();
+        assert!(!breakable.contains(&2), "the interface is erased");
+        assert!(breakable.contains(&5) && breakable.contains(&6));
+        let pos = map.js.find("double(21)").unwrap() as u32;
+        assert_eq!(map.original(pos).map(|(line, _)| line), Some(6));
+
+        let (controller, _stops) = DebugController::new();
+        assert_eq!(
+            controller.set_breakpoints(&path, &[1, 6, 40]),
+            vec![Some(5), Some(6), None],
+            "a breakpoint moves down to the next line with code"
+        );
+    }
+
+    #[test]
+    fn a_breakpoint_stops_the_run_with_its_locals_and_stepping_follows_await() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("agent.ts");
+        std::fs::write(
+            &path,
+            "async function work(n: number): Promise<number> {\n  const a = n + 1;\n  await null;\n  const b = a * 2;\n  return b;\n}\n\nexport async function agent(input: { n: number }) {\n  const r = await work(input.n);\n  return { r };\n}\n",
+        )
+        .unwrap();
+        let (controller, stops) = DebugController::new();
+        controller.set_breakpoints(&path, &[3]);
+        let run = spawn_debugged(&path, serde_json::json!({ "n": 4 }), &controller);
+
+        let locals = |frame: usize| {
+            let scopes = controller.query(Query::Scopes { frame }).unwrap();
+            let reference = scopes["scopes"][0]["variablesReference"].as_u64().unwrap();
+            let vars = controller
+                .query(Query::Variables {
+                    reference: reference as usize,
+                })
+                .unwrap();
+            vars["variables"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|v| {
+                    format!(
+                        "{}={}",
+                        v["name"].as_str().unwrap(),
+                        v["value"].as_str().unwrap()
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let top_line = || {
+            let trace = controller.query(Query::StackTrace).unwrap();
+            let top = &trace["stackFrames"][0];
+            (
+                top["name"].as_str().unwrap().to_string(),
+                top["line"].as_u64().unwrap(),
+            )
+        };
+
+        let stop = stops.recv().unwrap();
+        assert_eq!(stop.reason, "breakpoint");
+        assert_eq!(top_line(), ("work".to_string(), 3));
+        assert_eq!(locals(0), vec!["n=4", "a=5", "b=<uninitialized>"]);
+        let trace = controller.query(Query::StackTrace).unwrap();
+        assert_eq!(trace["stackFrames"][1]["name"], "agent");
+        assert_eq!(trace["stackFrames"][1]["line"], 9);
+        let a = controller
+            .query(Query::Evaluate {
+                expression: "a".to_string(),
+                frame: None,
+            })
+            .unwrap();
+        assert_eq!(a["result"], "5");
+
+        // Over the `await`: the activation suspends, its caller runs on, and
+        // the stop is the next line of `work` once it resumes.
+        controller.resume(Resume::Next);
+        let stop = stops.recv().unwrap();
+        assert_eq!(stop.reason, "step");
+        assert_eq!(top_line(), ("work".to_string(), 4));
+
+        controller.resume(Resume::StepOut);
+        let stop = stops.recv().unwrap();
+        assert_eq!(stop.reason, "step");
+        assert_eq!(top_line(), ("agent".to_string(), 10));
+        assert_eq!(locals(0), vec!["input={n: 4}", "r=10"]);
+
+        controller.resume(Resume::Continue);
+        let output = run.join().unwrap().expect("the run completes");
+        assert_eq!(output, serde_json::json!({ "r": 10 }));
+    }
+
+    #[test]
+    fn a_replay_steps_back_reverse_continues_and_jumps_to_a_host_call() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("agent.ts");
+        std::fs::write(
+            &path,
+            "export async function agent() {\n  let total = 0;\n  for (let i = 0; i < 3; i++) {\n    total += i;\n    await chidori.log(`step ${i}`);\n  }\n  return total;\n}\n",
+        )
+        .unwrap();
+        let ctx = RuntimeContext::new();
+        let recorded = {
+            let (path, ctx) = (path.clone(), ctx.clone());
+            std::thread::Builder::new()
+                .stack_size(crate::scheduler::JS_THREAD_STACK_BYTES)
+                .spawn(move || run_in(&path, &serde_json::json!({}), ctx))
+                .unwrap()
+                .join()
+                .unwrap()
+                .unwrap()
+        };
+        assert_eq!(recorded, serde_json::json!(3));
+        let records = ctx.call_log().records().to_vec();
+        let logs: Vec<u64> = records
+            .iter()
+            .filter(|r| r.parent_seq.is_none())
+            .map(|r| r.seq)
+            .collect();
+        assert_eq!(logs.len(), 3, "one journaled call per log: {records:?}");
+
+        let (controller, stops) = DebugController::new();
+        controller.enable_time_travel(&records, 1);
+        controller.set_breakpoints(&path, &[7]);
+        let run = {
+            let (path, controller) = (path.clone(), controller.clone());
+            std::thread::Builder::new()
+                .stack_size(crate::scheduler::JS_THREAD_STACK_BYTES)
+                .spawn(move || {
+                    replay(&controller, || {
+                        let ctx = RuntimeContext::with_replay(records.clone());
+                        ctx.set_debugger(controller.clone());
+                        run_in(&path, &serde_json::json!({}), ctx)
+                    })
+                })
+                .unwrap()
+        };
+        let top = || {
+            let trace = controller.query(Query::StackTrace).unwrap();
+            let top = &trace["stackFrames"][0];
+            (
+                top["name"].as_str().unwrap().to_string(),
+                top["line"].as_u64().unwrap_or(0),
+            )
+        };
+        let locals = || {
+            let scopes = controller.query(Query::Scopes { frame: 0 }).unwrap();
+            let reference = scopes["scopes"][0]["variablesReference"].as_u64().unwrap();
+            controller
+                .query(Query::Variables {
+                    reference: reference as usize,
+                })
+                .unwrap()
+                .to_string()
+        };
+
+        assert_eq!(stops.recv().unwrap().reason, "breakpoint");
+        assert_eq!(top(), ("agent".to_string(), 7));
+
+        // Back to the loop test that ended the loop, by replaying again.
+        controller.resume(Resume::StepBack);
+        let stop = stops.recv().unwrap();
+        assert_eq!(stop.reason, "step");
+        assert!(
+            stop.description
+                .contains("replayed from the keyframe at statement"),
+            "{}",
+            stop.description
+        );
+        assert_eq!(top(), ("agent".to_string(), 3));
+
+        // The last time the loop body's first line ran: the third iteration.
+        controller.set_breakpoints(&path, &[4, 7]);
+        controller.resume(Resume::ReverseContinue);
+        assert_eq!(stops.recv().unwrap().reason, "breakpoint");
+        assert_eq!(top(), ("agent".to_string(), 4));
+        let vars = locals();
+        assert!(
+            vars.contains("\"total\"") && vars.contains("\"1\""),
+            "{vars}"
+        );
+
+        // Forward to the third log, then back to the first.
+        controller.run_to_seq(logs[2]).unwrap();
+        let stop = stops.recv().unwrap();
+        assert_eq!(stop.reason, "goto");
+        assert_eq!(stop.description, format!("host call log (seq {})", logs[2]));
+        assert!(controller.run_to_seq(9_999).is_err());
+        controller.run_to_seq(logs[0]).unwrap();
+        let stop = stops.recv().unwrap();
+        assert_eq!(stop.reason, "goto");
+        assert!(top().0.starts_with("host call log"), "{:?}", top());
+        assert!(locals().contains("step 0"), "{}", locals());
+
+        controller.set_breakpoints(&path, &[]);
+        controller.resume(Resume::Continue);
+        let output = run.join().unwrap().expect("the replay completes");
+        assert_eq!(output, serde_json::json!(3));
+    }
+}
@@ -55,11 +55,20 @@ pub struct Engine {
     /// Default model applied to every run's context, overriding the
     /// environment-derived default (see [`Engine::with_default_model`]).
     default_model: Option<String>,
+    /// Cost budget applied to every run's context, overriding the
+    /// `CHIDORI_MAX_COST_USD` default (see [`Engine::with_budget`]).
+    budget: Option<crate::runtime::cost::CostBudget>,
     /// Allow this run to persist a call log SHORTER than the durable one —
     /// the explicit opt-in for intentional history rewrites
     /// (`resume --until-seq` time travel). Off by default so an
     /// early-diverged resume attempt can never truncate a journal.
     allow_history_rewrite: bool,
+    /// CPU profile every run's agent JavaScript feeds (`chidori run
+    /// --profile`, see [`crate::runtime::profiler`]). None runs unprofiled.
+    profiler: Option<Arc<crate::runtime::profiler::Profiler>>,
+    /// Coverage every run's agent code feeds (`--coverage`, see
+    /// [`crate::runtime::coverage`]). None runs uninstrumented.
+    coverage: Option<Arc<crate::runtime::coverage::CoverageRecorder>>,
 }
 
 pub struct RunResult {
@@ -273,6 +282,7 @@ impl ScaffoldPersister {
         .with_capabilities(ctx.capabilities())
         .with_vfs(ctx.vfs_snapshot())
         .with_default_model(Some(ctx.config().model))
+        .with_budget(ctx.config().budget)
         .with_pricing(std::env::var("CHIDORI_PRICING").ok());
         // The embedded host-promise table has the same freshness contract as
         // checkpoint.json: a compaction-time snapshot. Runtime resume never
@@ -422,7 +432,10 @@ impl Engine {
             warm_input_bridge: None,
             workspace_root: None,
             default_model: None,
+            budget: None,
             allow_history_rewrite: false,
+            profiler: None,
+            coverage: None,
         }
     }
 
@@ -485,6 +498,30 @@ impl Engine {
         self
     }
 
+    /// Cost budget for prompts (see [`crate::runtime::cost::CostBudget`]).
+    /// `None` keeps the context's `CHIDORI_MAX_COST_USD` default. Sessions
+    /// and recipes pass their own budget here; resume passes the one
+    /// recorded in the run's manifest so the ceiling survives the restart.
+    pub fn with_budget(mut self, budget: Option<crate::runtime::cost::CostBudget>) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Profile the run's JavaScript into `profiler`.
+    pub fn with_profiler(mut self, profiler: Arc<crate::runtime::profiler::Profiler>) -> Self {
+        self.profiler = Some(profiler);
+        self
+    }
+
+    /// Count which blocks of the run's agent code execute into `coverage`.
+    pub fn with_coverage(
+        mut self,
+        coverage: Arc<crate::runtime::coverage::CoverageRecorder>,
+    ) -> Self {
+        self.coverage = Some(coverage);
+        self
+    }
+
     /// Opt this run into intentional journal truncation (`resume --until-seq`
     /// time travel). See the `allow_history_rewrite` field.
     pub fn with_history_rewrite_allowed(mut self, allowed: bool) -> Self {
@@ -565,6 +602,15 @@ impl Engine {
         )
     }
 
+    /// The JSON Schema an agent file declares with `run(handler, {
+    /// inputSchema })`, without running the handler or any effect. `None`
+    /// when the agent declares none.
+    pub fn input_schema(&self, path: &Path) -> Result<Option<Value>> {
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        crate::runtime::rust_engine::describe_input_schema(path, &source)
+    }
+
     /// Run a TypeScript agent file with the given JSON inputs.
     pub fn run(&self, path: &Path, inputs: &Value) -> Result<RunResult> {
         let ctx = RuntimeContext::new();
@@ -694,6 +740,44 @@ impl Engine {
         self.run_with_context(path, inputs, ctx)
     }
 
+    /// `run_announced` with a source-level debugger attached
+    /// (`chidori debug`, see [`crate::runtime::debugger`]). Pausing effects
+    /// pause the run rather than read stdin, which may be the debug
+    /// protocol's stream.
+    pub fn run_debugged(
+        &self,
+        path: &Path,
+        inputs: &Value,
+        debugger: Arc<crate::runtime::debugger::DebugController>,
+    ) -> Result<RunResult> {
+        let ctx = RuntimeContext::new();
+        eprintln!("Run id: {}", ctx.run_id());
+        ctx.set_input_mode(InputMode::Pause);
+        ctx.set_debugger(debugger);
+        self.run_with_context(path, inputs, ctx)
+    }
+
+    /// `run_debugged` over a recording (`chidori debug --replay`): the run
+    /// replays `records` under `run_id`, and the debugger can travel back in
+    /// it, each rewind a fresh replay from the nearest keyframe.
+    pub fn replay_debugged(
+        &self,
+        path: &Path,
+        inputs: &Value,
+        records: Vec<CallRecord>,
+        run_id: &str,
+        debugger: Arc<crate::runtime::debugger::DebugController>,
+    ) -> Result<RunResult> {
+        debugger.enable_time_travel(&records, crate::runtime::debugger::KEYFRAME_EVERY);
+        crate::runtime::debugger::replay(&debugger, || {
+            let ctx = RuntimeContext::with_replay(records.clone());
+            ctx.set_run_id(run_id.to_string());
+            ctx.set_input_mode(InputMode::Pause);
+            ctx.set_debugger(debugger.clone());
+            self.run_with_context(path, inputs, ctx)
+        })
+    }
+
     /// `run_with_replay_streaming` under a caller-owned run id — `resume_run`'s
     /// streaming twin. With persistence configured, live continuation past the
     /// replay frontier journals into that run's directory. The `chidori chat`
@@ -925,9 +1009,18 @@ impl Engine {
         if let Some(ref model) = self.default_model {
             ctx.set_default_model(model.clone());
         }
+        if let Some(budget) = self.budget {
+            ctx.set_budget(budget);
+        }
         if let Some(ref bridge) = self.warm_input_bridge {
             ctx.set_warm_input_bridge(bridge.clone());
         }
+        if let Some(ref profiler) = self.profiler {
+            ctx.set_profiler(profiler.clone());
+        }
+        if let Some(ref coverage) = self.coverage {
+            ctx.set_coverage(coverage.clone());
+        }
 
         // Enable persistence if configured: the filesystem run dir, teed with
         // the durable mirror when one is set up (`docs/durable-storage.md`).
@@ -1505,6 +1598,7 @@ mod tests {
             token_usage: None,
             timestamp: chrono::Utc::now(),
             error: None,
+            served_by: None,
         }];
         let resumed = engine()
             .run_replay_pausable(&path, &serde_json::json!({}), replay)
@@ -1725,10 +1819,14 @@ mod tests {
                 decision: crate::policy::Decision::AskBefore,
                 match_args: None,
                 reason: Some("test approval".to_string()),
+                rate_limit: None,
+                time_window: None,
+                compiled: Default::default(),
             }],
             default: crate::policy::Decision::AlwaysAllow,
             default_reason: None,
             overlay: None,
+            label: None,
         });
 
         let engine = Engine::new(
@@ -2752,6 +2850,7 @@ def agent(value):
             token_usage: None,
             timestamp: chrono::Utc::now(),
             error: None,
+            served_by: None,
         });
         std::fs::write(
             &path,
@@ -2882,6 +2981,7 @@ def agent(value):
             token_usage: None,
             timestamp: chrono::Utc::now(),
             error: None,
+            served_by: None,
         };
 
         // Live run: records flow through `record_call`'s O(1) append.
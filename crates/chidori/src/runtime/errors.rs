@@ -154,6 +154,10 @@ pub enum RunErrorKind {
     /// A policy rule denied the call, the operator declined an approval, or
     /// an approval was required in a context that could not pause for one.
     PolicyDenied,
+    /// The run's cost budget (`--max-cost-usd`, a session/recipe `budget`)
+    /// was spent before a prompt, and the budget fails rather than asks (or
+    /// the operator declined to continue past it).
+    BudgetExceeded,
     /// A resume re-executed the agent and it called something other than what
     /// the journal recorded at that position (`Replay divergence at seq ...`).
     ReplayDivergence,
@@ -207,6 +211,9 @@ impl RunErrorKind {
         {
             return Self::SourceMismatch;
         }
+        if text.contains("budget: run cost ") {
+            return Self::BudgetExceeded;
+        }
         if text.contains("policy: `") {
             return Self::PolicyDenied;
         }
@@ -316,6 +323,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn classify_recognizes_budget_exhaustion() {
+        let budget = crate::runtime::cost::CostBudget {
+            max_cost_usd: 1.0,
+            on_exceed: crate::runtime::cost::BudgetAction::Fail,
+        };
+        let err = anyhow::anyhow!(
+            "JavaScript exception: Error: {}",
+            budget.exceeded_message(1.2)
+        );
+        assert_eq!(RunErrorKind::classify(&err), RunErrorKind::BudgetExceeded);
+    }
+
     #[test]
     fn classify_recognizes_policy_denials() {
         let denied = anyhow::anyhow!("policy: `http:https://example.test` denied (no network)");
@@ -0,0 +1,285 @@
+//! Heap snapshots of agent JavaScript (`v8.writeHeapSnapshot`,
+//! `chidori run --heap-snapshot-on-oom`).
+//!
+//! The engine walks its own object graph ([`chidori_js::Vm::heap_snapshot`])
+//! wherever the VM runs, and the snapshot reaches disk as a host op: chunks of
+//! the `.heapsnapshot` JSON under [`SNAPSHOT_OP`], appended to a file in the
+//! run's `heap/` directory. In-process that is a direct call; an isolate worker,
+//! which has no filesystem, brokers the same calls to its parent over the pipe,
+//! each chunk well under the protocol's frame cap.
+//!
+//! The write is not journaled — a snapshot is diagnostic output, far too large
+//! for the call log — so what the agent sees must not depend on it: the VM
+//! names every snapshot before shipping it, and a replayed call rewrites the
+//! same file and returns the same path. The host caps what one run can write,
+//! since an isolate worker can send the op directly.
+
+use std::io::Write as _;
+use std::path::PathBuf;
+
+use chidori_js::heap::HeapSnapshot;
+use serde_json::{json, Value};
+
+use super::context::RuntimeContext;
+use super::rust_engine::RunHost;
+
+/// The host op each chunk of a snapshot travels as.
+pub(crate) const SNAPSHOT_OP: &str = "__chidori_heap_snapshot";
+
+/// Bytes of snapshot JSON per host op.
+const CHUNK_BYTES: usize = 8 * 1024 * 1024;
+
+/// Most chunks the host appends to one snapshot file (2 GiB).
+const MAX_CHUNKS: u64 = 256;
+
+/// Most bytes of snapshots the host keeps in one run's `heap/` directory.
+const MAX_HEAP_DIR_BYTES: u64 = 4 << 30;
+
+/// Classes listed in the report of a run stopped at its memory cap.
+const REPORTED_CLASSES: usize = 10;
+
+/// The file an agent's `n`th unnamed snapshot (from 1) is written to.
+pub(crate) fn default_name(n: u32) -> String {
+    format!("Heap.{n}.heapsnapshot")
+}
+
+/// Ship `snapshot` to the host as `file`, a name in the run's `heap/`
+/// directory. Returns the path the host wrote.
+pub(crate) fn ship(
+    host: &dyn RunHost,
+    file: &str,
+    snapshot: &HeapSnapshot,
+) -> Result<String, String> {
+    let json = snapshot.to_json();
+    let mut file = file.to_string();
+    let mut rest = json.as_str();
+    let mut chunk = 0;
+    loop {
+        let mut end = rest.len().min(CHUNK_BYTES);
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (data, tail) = rest.split_at(end);
+        let reply = host.call(
+            SNAPSHOT_OP,
+            &json!({ "file": file, "chunk": chunk, "data": data }),
+        )?;
+        if tail.is_empty() {
+            return reply
+                .get("path")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .ok_or_else(|| format!("{SNAPSHOT_OP}: reply missing `path`"));
+        }
+        // The first chunk settles the name the rest append to.
+        if let Some(name) = reply.get("file").and_then(Value::as_str) {
+            file = name.to_string();
+        }
+        rest = tail;
+        chunk += 1;
+    }
+}
+
+/// The host half of [`SNAPSHOT_OP`]: create (chunk 0) or append to the
+/// snapshot file under `<run dir>/heap/`, refusing chunks past
+/// [`MAX_CHUNKS`] or past [`MAX_HEAP_DIR_BYTES`] for the directory.
+pub(crate) fn write_chunk(ctx: &RuntimeContext, args: &Value) -> Result<Value, String> {
+    let dir = ctx
+        .persist_dir()
+        .ok_or("heap snapshots are written to the run directory; this run has none")?
+        .join("heap");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("creating {}: {e}", dir.display()))?;
+    let chunk = args.get("chunk").and_then(Value::as_u64).unwrap_or(0);
+    let data = args
+        .get("data")
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("{SNAPSHOT_OP}: missing `data`"))?;
+    let name = args
+        .get("file")
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("{SNAPSHOT_OP}: chunk {chunk} names no file"))?;
+    // Only ever a name inside `heap/`: the agent does not pick the directory.
+    let file = PathBuf::from(name)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .ok_or_else(|| format!("`{name}` is not a file name"))?;
+    let path = dir.join(&file);
+    if chunk >= MAX_CHUNKS || data.len() > CHUNK_BYTES {
+        return Err(format!(
+            "{SNAPSHOT_OP}: a heap snapshot is capped at {MAX_CHUNKS} chunks of {CHUNK_BYTES} bytes"
+        ));
+    }
+    // Chunk 0 truncates, so the file's current bytes do not count against it.
+    let replaced = match chunk {
+        0 => std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0),
+        _ => 0,
+    };
+    let held = dir_bytes(&dir)?.saturating_sub(replaced);
+    if held + data.len() as u64 > MAX_HEAP_DIR_BYTES {
+        return Err(format!(
+            "{SNAPSHOT_OP}: the run's heap snapshots are capped at {MAX_HEAP_DIR_BYTES} bytes"
+        ));
+    }
+    let mut out = std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(chunk > 0)
+        .truncate(chunk == 0)
+        .open(&path)
+        .map_err(|e| format!("opening {}: {e}", path.display()))?;
+    out.write_all(data.as_bytes())
+        .map_err(|e| format!("writing {}: {e}", path.display()))?;
+    Ok(json!({ "file": file, "path": path.display().to_string() }))
+}
+
+/// Bytes held by the files directly in `dir`.
+fn dir_bytes(dir: &std::path::Path) -> Result<u64, String> {
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("reading {}: {e}", dir.display()))?;
+    Ok(entries
+        .filter_map(|entry| entry.ok()?.metadata().ok())
+        .filter(|meta| meta.is_file())
+        .map(|meta| meta.len())
+        .sum())
+}
+
+/// What a run stopped at its memory cap leaves in its error: where the
+/// snapshot went and the classes retaining the most.
+pub(crate) fn oom_report(path: &str, snapshot: &HeapSnapshot) -> String {
+    let mut report = format!(
+        "the run exceeded its memory cap; heap snapshot written to {path}\nlargest retainers:"
+    );
+    for class in snapshot.class_summary().iter().take(REPORTED_CLASSES) {
+        report.push_str(&format!(
+            "\n  {:>10}  {:>6} × {}",
+            human_bytes(class.retained_size),
+            class.count,
+            class.name
+        ));
+    }
+    report
+}
+
+fn human_bytes(bytes: usize) -> String {
+    match bytes {
+        b if b >= 1 << 30 => format!("{:.1} GiB", b as f64 / (1u64 << 30) as f64),
+        b if b >= 1 << 20 => format!("{:.1} MiB", b as f64 / (1u64 << 20) as f64),
+        b if b >= 1 << 10 => format!("{:.1} KiB", b as f64 / 1024.0),
+        b => format!("{b} B"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_chunk, CHUNK_BYTES, MAX_CHUNKS};
+    use std::sync::{Arc, Mutex as StdMutex};
+
+    use serde_json::json;
+
+    use crate::mcp::McpManager;
+    use crate::policy::{PolicyCache, PolicyConfig};
+    use crate::providers::ProviderRegistry;
+    use crate::runtime::context::RuntimeContext;
+    use crate::runtime::rust_engine::run_agent;
+    use crate::runtime::snapshot::RuntimePolicy;
+    use crate::runtime::template::TemplateEngine;
+    use crate::runtime::typescript::bindings::HostBindingBackend;
+    use crate::tools::ToolRegistry;
+
+    const AGENT: &str = "import v8 from 'node:v8';\n\nclass Session { constructor(public id: number) {} }\n\nexport async function agent() {\n  const sessions = [];\n  for (let i = 0; i < 20; i++) sessions.push(new Session(i));\n  const first = v8.writeHeapSnapshot('sessions.heapsnapshot');\n  const second = v8.writeHeapSnapshot();\n  return { first, second, live: sessions.length };\n}\n";
+
+    #[test]
+    fn write_heap_snapshot_lands_in_the_run_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("agent.ts");
+        std::fs::write(&path, AGENT).unwrap();
+        let ctx = RuntimeContext::new();
+        let run_dir = ctx.enable_persistence(dir.path().join("runs"));
+        let output = std::thread::Builder::new()
+            .stack_size(crate::scheduler::JS_THREAD_STACK_BYTES)
+            .spawn(move || {
+                let backend = HostBindingBackend::for_runtime(
+                    ctx,
+                    Arc::new(ProviderRegistry::new()),
+                    Arc::new(TemplateEngine::new(".")),
+                    Arc::new(tokio::runtime::Runtime::new().unwrap()),
+                    PolicyConfig::from_env(),
+                    Arc::new(StdMutex::new(PolicyCache::default())),
+                    RuntimePolicy::durable_default("heap-snapshot-test"),
+                    Arc::new(ToolRegistry::new()),
+                    Arc::new(McpManager::new()),
+                );
+                run_agent(&path, AGENT, &json!({}), &backend)
+            })
+            .unwrap()
+            .join()
+            .unwrap()
+            .unwrap();
+
+        let heap = run_dir.join("heap");
+        assert_eq!(
+            output["first"],
+            heap.join("sessions.heapsnapshot").display().to_string()
+        );
+        assert_eq!(
+            output["second"],
+            heap.join("Heap.1.heapsnapshot").display().to_string()
+        );
+        let snapshot: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(heap.join("sessions.heapsnapshot")).unwrap(),
+        )
+        .unwrap();
+        let strings = snapshot["strings"].as_array().unwrap();
+        let session = strings
+            .iter()
+            .position(|s| s == "Session")
+            .expect("the agent's class is named in the snapshot") as u64;
+        let nodes = snapshot["nodes"].as_array().unwrap();
+        let instances = nodes
+            .chunks(7)
+            .filter(|node| node[0] == 3 && node[1] == session)
+            .count();
+        assert_eq!(instances, 20);
+    }
+
+    #[test]
+    fn chunks_append_to_a_file_named_inside_heap() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = RuntimeContext::new();
+        let run_dir = ctx.enable_persistence(dir.path().to_path_buf());
+        let first = write_chunk(
+            &ctx,
+            &json!({ "file": "../../escape.heapsnapshot", "chunk": 0, "data": "{\"a\":" }),
+        )
+        .unwrap();
+        assert_eq!(first["file"], "escape.heapsnapshot");
+        write_chunk(
+            &ctx,
+            &json!({ "file": first["file"], "chunk": 1, "data": "1}" }),
+        )
+        .unwrap();
+        let written =
+            std::fs::read_to_string(run_dir.join("heap").join("escape.heapsnapshot")).unwrap();
+        assert_eq!(written, "{\"a\":1}");
+        assert!(write_chunk(&ctx, &json!({ "chunk": 0, "data": "" })).is_err());
+    }
+
+    #[test]
+    fn the_host_refuses_chunks_past_the_cap() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = RuntimeContext::new();
+        ctx.enable_persistence(dir.path().to_path_buf());
+        let err = write_chunk(
+            &ctx,
+            &json!({ "file": "big.heapsnapshot", "chunk": MAX_CHUNKS, "data": "" }),
+        )
+        .unwrap_err();
+        assert!(err.contains("capped"), "{err}");
+        let oversized = "x".repeat(CHUNK_BYTES + 1);
+        let err = write_chunk(
+            &ctx,
+            &json!({ "file": "big.heapsnapshot", "chunk": 0, "data": oversized }),
+        )
+        .unwrap_err();
+        assert!(err.contains("capped"), "{err}");
+    }
+}
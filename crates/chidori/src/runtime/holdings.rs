@@ -173,6 +173,7 @@ mod tests {
             token_usage: None,
             timestamp: Utc::now(),
             error: None,
+            served_by: None,
         }
     }
 
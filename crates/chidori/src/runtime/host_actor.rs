@@ -1763,6 +1763,7 @@ fn supervise(
                             token_usage: None,
                             timestamp: Utc::now(),
                             error: None,
+                            served_by: None,
                         });
                     }
                 }
@@ -2501,6 +2502,7 @@ mod tests {
                 token_usage: None,
                 timestamp: chrono::Utc::now(),
                 error: error.map(str::to_string),
+                served_by: None,
             };
         // Recording order: children settle before their parent.
         let records = vec![
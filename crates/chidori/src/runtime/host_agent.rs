@@ -171,10 +171,11 @@ impl AgentEntry {
 }
 
 /// Process-global supervisor for detached agents. One per process; agents are
-/// identified by their registered (or generated) name.
+/// identified by their registered (or generated) name within their run base —
+/// each server tenant keeps its own registry under its own run base.
 pub struct DetachedAgentHub {
     parts: Mutex<Option<AgentRuntimeParts>>,
-    entries: Mutex<HashMap<String, Arc<AgentEntry>>>,
+    entries: Mutex<HashMap<(PathBuf, String), Arc<AgentEntry>>>,
     timer_started: Mutex<bool>,
 }
 
@@ -196,13 +197,10 @@ impl DetachedAgentHub {
         *self.parts.lock().unwrap() = Some(parts);
     }
 
-    /// The parts installed at boot — how code without a spawning backend
-    /// (the server's HTTP handlers) addresses the hub.
+    /// The parts installed last — the server installs each fleet's as it
+    /// re-arms it; its handlers build their own per tenant.
+    #[cfg(test)]
     pub fn installed_parts(&self) -> Result<AgentRuntimeParts, String> {
-        self.parts()
-    }
-
-    fn parts(&self) -> Result<AgentRuntimeParts, String> {
         self.parts
             .lock()
             .unwrap()
@@ -217,7 +215,8 @@ impl DetachedAgentHub {
         parts: &AgentRuntimeParts,
         name: &str,
     ) -> Result<Arc<AgentEntry>, String> {
-        if let Some(entry) = self.entries.lock().unwrap().get(name).cloned() {
+        let key = (parts.run_base.clone(), name.to_string());
+        if let Some(entry) = self.entries.lock().unwrap().get(&key).cloned() {
             return Ok(entry);
         }
         let parts = parts.clone();
@@ -240,10 +239,7 @@ impl DetachedAgentHub {
             }),
             signal: Condvar::new(),
         });
-        self.entries
-            .lock()
-            .unwrap()
-            .insert(name.to_string(), entry.clone());
+        self.entries.lock().unwrap().insert(key, entry.clone());
         Ok(entry)
     }
 
@@ -352,7 +348,7 @@ impl DetachedAgentHub {
         self.entries
             .lock()
             .unwrap()
-            .insert(name.clone(), entry.clone());
+            .insert((entry.parts.run_base.clone(), name.clone()), entry.clone());
         self.start_thread(entry)?;
         Ok(json!({ "name": name, "runId": run_id }))
     }
@@ -895,6 +891,7 @@ fn supervise_detached(hub: &DetachedAgentHub, entry: &Arc<AgentEntry>) {
                     token_usage: None,
                     timestamp: Utc::now(),
                     error: None,
+                    served_by: None,
                 });
             } else if !matched {
                 // Spuriously started with nothing to do — go back to sleep.
@@ -1065,6 +1062,11 @@ fn supervise_detached(hub: &DetachedAgentHub, entry: &Arc<AgentEntry>) {
                         && state.descriptor.restarts < descriptor.max_restarts;
                     if left {
                         state.descriptor.restarts += 1;
+                        crate::runtime::metrics::inc(
+                            crate::runtime::metrics::DETACHED_RESTARTS,
+                            &[("agent", &descriptor.name)],
+                            1.0,
+                        );
                     }
                     (state.descriptor.restarts, left)
                 };
@@ -1160,7 +1162,7 @@ fn persist_descriptor(factory: &RunStoreFactory, descriptor: &AgentDescriptor) {
 
 /// The process-stable lease owner id: one per OS process, so every agent this
 /// process drives is leased under the same identity.
-fn process_lease_owner() -> &'static str {
+pub(crate) fn process_lease_owner() -> &'static str {
     static OWNER: OnceLock<String> = OnceLock::new();
     OWNER.get_or_init(|| format!("chidori-{}", uuid::Uuid::new_v4()))
 }
@@ -520,6 +520,7 @@ pub(crate) fn resume_branch(
         token_usage: None,
         timestamp: Utc::now(),
         error: None,
+        served_by: None,
     });
 
     run_persisted_branch(
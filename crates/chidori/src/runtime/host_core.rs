@@ -4,7 +4,8 @@ use serde_json::{json, Value};
 use sha2::{Digest, Sha256};
 
 use crate::providers::{
-    CacheTtl, ContentBlock, LlmRequest, LlmResponse, ProviderRegistry, TokenSink, ToolCall,
+    CacheTtl, ContentBlock, EmbeddingRequest, LlmRequest, LlmResponse, ProviderRegistry,
+    ReasoningConfig, TokenSink, ToolCall,
 };
 use crate::runtime::call_log::{CallRecord, TokenUsage};
 use crate::runtime::context::{
@@ -95,6 +96,7 @@ pub fn execute_durable_json_call_at_seq(
                 token_usage: None,
                 timestamp: started,
                 error: None,
+                served_by: None,
             });
             if let Some(id) = host_operation {
                 ctx.run_host_operation_completion_safepoint(id)?;
@@ -123,6 +125,7 @@ pub fn execute_durable_json_call_at_seq(
                 token_usage: None,
                 timestamp: started,
                 error: Some(message.clone()),
+                served_by: None,
             });
             if let Some(id) = host_operation {
                 ctx.run_host_operation_completion_safepoint(id)?;
@@ -184,6 +187,7 @@ fn replay_completed_host_operation(
                 token_usage: None,
                 timestamp: completed_at,
                 error: None,
+                served_by: None,
             });
             Ok(Some(value))
         }
@@ -201,6 +205,7 @@ fn replay_completed_host_operation(
                 token_usage: None,
                 timestamp: completed_at,
                 error: Some(error.clone()),
+                served_by: None,
             });
             Err(anyhow::anyhow!(error))
         }
@@ -229,6 +234,7 @@ pub fn execute_log(args: &Value) -> Result<Value> {
 fn host_operation_kind(function: &str) -> Option<PendingHostOperationKind> {
     match function {
         "prompt" => Some(PendingHostOperationKind::Prompt),
+        "embed" => Some(PendingHostOperationKind::Embed),
         "input" => Some(PendingHostOperationKind::Input),
         "tool" => Some(PendingHostOperationKind::Tool),
         "call_agent" => Some(PendingHostOperationKind::CallAgent),
@@ -330,6 +336,7 @@ pub fn execute_input(ctx: &RuntimeContext, args: &Value) -> Result<Value> {
                 token_usage: None,
                 timestamp: Utc::now(),
                 error: None,
+                served_by: None,
             });
             ctx.run_host_operation_completion_safepoint(host_operation)?;
             Ok(Value::String(response))
@@ -364,6 +371,7 @@ pub fn execute_input(ctx: &RuntimeContext, args: &Value) -> Result<Value> {
                             token_usage: None,
                             timestamp: Utc::now(),
                             error: None,
+                            served_by: None,
                         });
                         ctx.run_host_operation_completion_safepoint(host_operation)?;
                         return Ok(result);
@@ -480,6 +488,7 @@ fn settle_signal_listen(
         token_usage: None,
         timestamp: Utc::now(),
         error: None,
+        served_by: None,
     });
     ctx.run_host_operation_completion_safepoint(host_operation)?;
     Ok(result)
@@ -592,6 +601,7 @@ pub fn execute_signal(ctx: &RuntimeContext, args: &Value) -> Result<Value> {
             token_usage: None,
             timestamp: Utc::now(),
             error: None,
+            served_by: None,
         });
         ctx.run_host_operation_completion_safepoint(host_operation)?;
         return Ok(result);
@@ -674,6 +684,7 @@ pub fn execute_signal_any(ctx: &RuntimeContext, args: &Value) -> Result<Value> {
             token_usage: None,
             timestamp: Utc::now(),
             error: None,
+            served_by: None,
         });
         ctx.run_host_operation_completion_safepoint(host_operation)?;
         return Ok(result);
@@ -757,6 +768,7 @@ pub fn execute_poll_signal(ctx: &RuntimeContext, args: &Value) -> Result<Value>
         token_usage: None,
         timestamp: Utc::now(),
         error: None,
+        served_by: None,
     });
     ctx.run_host_operation_completion_safepoint(host_operation)?;
     Ok(result)
@@ -858,6 +870,7 @@ pub fn execute_step_end(ctx: &RuntimeContext, args: &Value) -> Result<Value> {
         token_usage: None,
         timestamp: step.started,
         error: error.map(str::to_string),
+        served_by: None,
     });
     Ok(result)
 }
@@ -904,6 +917,27 @@ pub fn cache_posture_from_options(options: &Value) -> CachePosture {
     }
 }
 
+/// Parse the `reasoning` prompt option, `{ budgetTokens?, effort? }`. Absent
+/// or `null` leaves the provider default; a malformed value is an error rather
+/// than a silently ignored setting.
+pub fn reasoning_from_options(options: &Value) -> Result<Option<ReasoningConfig>, String> {
+    let Some(value) = options.get("reasoning").filter(|v| !v.is_null()) else {
+        return Ok(None);
+    };
+    let config = serde_json::from_value::<ReasoningConfig>(value.clone()).map_err(|err| {
+        format!(
+            "invalid `reasoning` option ({err}): expected {{ budgetTokens?: number, effort?: \
+             \"minimal\" | \"low\" | \"medium\" | \"high\" }}"
+        )
+    })?;
+    if config == ReasoningConfig::default() {
+        return Err(
+            "invalid `reasoning` option: set `budgetTokens` or `effort` (or omit it)".to_string(),
+        );
+    }
+    Ok(Some(config))
+}
+
 /// Default auto-marking of cacheable prefix boundaries (the zero-author win):
 /// the system block and the tool schemas are stable for a whole run/loop, so
 /// they are always marked; the conversation head (the latest message) is
@@ -940,7 +974,7 @@ pub fn auto_mark_prompt_cache(request: &mut LlmRequest, posture: CachePosture) {
 /// and ignores it. Computed over canonical JSON (serde_json sorts object keys)
 /// and versioned so a future canonicalization change can't silently collide.
 pub fn prompt_request_digest(request: &LlmRequest) -> String {
-    let canonical = json!({
+    let mut canonical = json!({
         "v": 1,
         "model": request.model,
         "system": request.system,
@@ -953,6 +987,14 @@ pub fn prompt_request_digest(request: &LlmRequest) -> String {
         "max_tokens": request.max_tokens,
         "temperature": request.temperature,
     });
+    // Added only when set so digests of schema-less prompts (every
+    // checkpoint written before structured output) stay unchanged.
+    if let Some(schema) = &request.response_schema {
+        canonical["response_schema"] = schema.clone();
+    }
+    if let Some(reasoning) = &request.reasoning {
+        canonical["reasoning"] = json!(reasoning);
+    }
     let mut hasher = Sha256::new();
     hasher.update(canonical.to_string().as_bytes());
     hex::encode(hasher.finalize())
@@ -1012,6 +1054,7 @@ fn complete_prompt_from_local_cache(
         token_usage: None,
         timestamp: Utc::now(),
         error: None,
+        served_by: None,
     });
     ctx.run_host_operation_completion_safepoint(host_operation)?;
     Ok(())
@@ -1067,6 +1110,9 @@ pub fn execute_prompt_text(
         return Ok(result);
     }
 
+    if let Some(path) = crate::runtime::media::unresolved_file(&request.messages) {
+        anyhow::bail!("attachment `{path}` could not be read for a live prompt call");
+    }
     if let Some(cached) = local_prompt_cache_lookup(&request) {
         let result = Value::String(cached.content);
         complete_prompt_from_local_cache(ctx, seq, args, result.clone())?;
@@ -1108,6 +1154,7 @@ pub fn execute_prompt_text(
                 token_usage: Some(TokenUsage::from_response(&response)),
                 timestamp: started,
                 error: None,
+                served_by: response.served_by.clone(),
             });
             ctx.run_host_operation_completion_safepoint(host_operation)?;
             Ok(result)
@@ -1125,6 +1172,7 @@ pub fn execute_prompt_text(
                 token_usage: None,
                 timestamp: started,
                 error: Some(message.clone()),
+                served_by: None,
             });
             ctx.run_host_operation_completion_safepoint(host_operation)?;
             Err(anyhow::anyhow!(message))
@@ -1163,6 +1211,9 @@ pub fn execute_prompt_response(
         });
     }
 
+    if let Some(path) = crate::runtime::media::unresolved_file(&request.messages) {
+        anyhow::bail!("attachment `{path}` could not be read for a live prompt call");
+    }
     if let Some(cached) = local_prompt_cache_lookup(&request) {
         complete_prompt_from_local_cache(ctx, seq, args, llm_response_to_json(&cached))?;
         return Ok(cached);
@@ -1200,6 +1251,7 @@ pub fn execute_prompt_response(
                 token_usage: Some(TokenUsage::from_response(&response)),
                 timestamp: started,
                 error: None,
+                served_by: response.served_by.clone(),
             });
             ctx.run_host_operation_completion_safepoint(host_operation)?;
             Ok(response)
@@ -1217,6 +1269,101 @@ pub fn execute_prompt_response(
                 token_usage: None,
                 timestamp: started,
                 error: Some(message.clone()),
+                served_by: None,
+            });
+            ctx.run_host_operation_completion_safepoint(host_operation)?;
+            Err(anyhow::anyhow!(message))
+        }
+    }
+}
+
+/// `chidori.embed(texts, { model })`: one durable record per batch, replayed
+/// like a prompt. The args carry the model, the batch size, and a digest of
+/// the texts — enough to detect an edited batch on resume without copying
+/// every text into the journal — and the result is the vectors themselves,
+/// so a replay never re-embeds. Usage lands in `token_usage` for `cost`.
+pub fn execute_embed(
+    ctx: &RuntimeContext,
+    providers: &ProviderRegistry,
+    tokio_rt: &tokio::runtime::Runtime,
+    request: EmbeddingRequest,
+) -> Result<Value> {
+    let mut hasher = Sha256::new();
+    for input in &request.inputs {
+        hasher.update((input.len() as u64).to_le_bytes());
+        hasher.update(input.as_bytes());
+    }
+    let args = json!({
+        "model": request.model,
+        "count": request.inputs.len(),
+        "inputs_digest": hex::encode(hasher.finalize()),
+    });
+    let seq = ctx.next_seq();
+    if let Some(record) = ctx
+        .try_replay_checked(seq, "embed", &args)
+        .map_err(|err| anyhow::anyhow!(err))?
+    {
+        return Ok(record.result);
+    }
+    if let Some(result) =
+        replay_completed_host_operation(ctx, seq, "embed", PendingHostOperationKind::Embed, &args)?
+    {
+        return Ok(result);
+    }
+
+    let host_operation = ctx.begin_host_operation_with_function(
+        seq,
+        PendingHostOperationKind::Embed,
+        Some("embed".to_string()),
+        args.clone(),
+    );
+    ctx.run_host_operation_safepoint(host_operation)?;
+    let started = Utc::now();
+    let response = tokio_rt.block_on(providers.embed(&request));
+    let duration_ms = Utc::now()
+        .signed_duration_since(started)
+        .num_milliseconds()
+        .max(0) as u64;
+
+    match response {
+        Ok(response) => {
+            let result = json!(response.embeddings);
+            ctx.resolve_host_operation(host_operation, result.clone())?;
+            ctx.record_call(CallRecord {
+                seq,
+                parent_seq: None,
+                function: "embed".to_string(),
+                args,
+                result: result.clone(),
+                duration_ms,
+                token_usage: Some(TokenUsage {
+                    input_tokens: response.input_tokens,
+                    output_tokens: 0,
+                    cache_creation_tokens: None,
+                    cache_read_tokens: None,
+                    reasoning_tokens: None,
+                }),
+                timestamp: started,
+                error: None,
+                served_by: response.served_by,
+            });
+            ctx.run_host_operation_completion_safepoint(host_operation)?;
+            Ok(result)
+        }
+        Err(err) => {
+            let message = err.to_string();
+            ctx.reject_host_operation(host_operation, message.clone())?;
+            ctx.record_call(CallRecord {
+                seq,
+                parent_seq: None,
+                function: "embed".to_string(),
+                args,
+                result: Value::Null,
+                duration_ms,
+                token_usage: None,
+                timestamp: started,
+                error: Some(message.clone()),
+                served_by: None,
             });
             ctx.run_host_operation_completion_safepoint(host_operation)?;
             Err(anyhow::anyhow!(message))
@@ -1282,6 +1429,9 @@ pub fn llm_response_to_json(response: &LlmResponse) -> Value {
     if let Some(ref reasoning) = response.reasoning {
         value["reasoning"] = json!(reasoning);
     }
+    if response.reasoning_tokens > 0 {
+        value["reasoningTokens"] = json!(response.reasoning_tokens);
+    }
     value
 }
 
@@ -1312,10 +1462,15 @@ pub fn llm_response_from_json(value: &Value) -> Option<LlmResponse> {
             .get("cacheReadTokens")
             .and_then(Value::as_u64)
             .unwrap_or(0),
+        reasoning_tokens: value
+            .get("reasoningTokens")
+            .and_then(Value::as_u64)
+            .unwrap_or(0),
         reasoning: value
             .get("reasoning")
             .and_then(Value::as_str)
             .map(str::to_string),
+        served_by: None,
     })
 }
 
@@ -2013,6 +2168,7 @@ mod tests {
             token_usage: None,
             timestamp: Utc::now(),
             error: None,
+            served_by: None,
         }];
         let ctx = RuntimeContext::with_replay(replay);
 
@@ -2044,6 +2200,7 @@ mod tests {
             token_usage: None,
             timestamp: Utc::now(),
             error: None,
+            served_by: None,
         }];
         let ctx = RuntimeContext::with_replay(replay);
 
@@ -2327,6 +2484,8 @@ mod tests {
             max_tokens: 16,
             tools: Vec::new(),
             cache: crate::providers::CacheLayout::default(),
+            response_schema: None,
+            reasoning: None,
         };
         let _ =
             execute_prompt_response(&ctx, &providers, &tokio_rt, request, json!({}), None).unwrap();
@@ -2372,6 +2531,8 @@ mod tests {
                 max_tokens: 16,
                 tools: Vec::new(),
                 cache: crate::providers::CacheLayout::default(),
+                response_schema: None,
+                reasoning: None,
             },
             args,
             Some("progress".to_string()),
@@ -2991,6 +3152,8 @@ mod tests {
             max_tokens: 16,
             tools: Vec::new(),
             cache: crate::providers::CacheLayout::default(),
+            response_schema: None,
+            reasoning: None,
         }
     }
 
@@ -3155,6 +3318,7 @@ mod tests {
             token_usage: None,
             timestamp: Utc::now(),
             error: None,
+            served_by: None,
         }];
         // Replay context with a DIFFERENT same-name signal sitting in the inbox.
         // The recorded value must win and the inbox must stay undrained.
@@ -3251,6 +3415,7 @@ mod tests {
             token_usage: None,
             timestamp: Utc::now(),
             error: None,
+            served_by: None,
         }];
         let ctx = RuntimeContext::with_replay(null_record);
         // Even with a queued same-name signal, the recorded null wins.
@@ -3280,6 +3445,7 @@ mod tests {
             token_usage: None,
             timestamp: Utc::now(),
             error: None,
+            served_by: None,
         }];
         let ctx2 = RuntimeContext::with_replay(value_record);
         let replayed_value = execute_poll_signal(&ctx2, &json!({ "name": "steer" })).unwrap();
@@ -3381,6 +3547,7 @@ mod tests {
             token_usage: None,
             timestamp: Utc::now(),
             error: None,
+            served_by: None,
         }];
         let ctx = RuntimeContext::with_replay(recorded);
         ctx.set_signal_inbox(vec![queued_signal(
@@ -3541,6 +3708,7 @@ mod tests {
                 token_usage: None,
                 timestamp: Utc::now(),
                 error: None,
+                served_by: None,
             },
             CallRecord {
                 seq: 2,
@@ -3552,6 +3720,7 @@ mod tests {
                 token_usage: None,
                 timestamp: Utc::now(),
                 error: Some("Error: bad parse".to_string()),
+                served_by: None,
             },
         ];
         let ctx = RuntimeContext::with_replay(records);
@@ -3581,6 +3750,7 @@ mod tests {
             token_usage: None,
             timestamp: Utc::now(),
             error: None,
+            served_by: None,
         }];
         let ctx = RuntimeContext::with_replay(records);
         let err = execute_step_begin(&ctx, &json!({ "name": "renamed" })).unwrap_err();
@@ -286,6 +286,7 @@ mod tests {
             token_usage: None,
             timestamp: chrono::Utc::now(),
             error: None,
+            served_by: None,
         }
     }
 
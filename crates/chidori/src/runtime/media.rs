@@ -0,0 +1,324 @@
+//! Multimodal prompt content: images and documents attached to
+//! `chidori.prompt(text, { attachments })` and `chidori.context().user([...])`.
+//!
+//! Two invariants:
+//!
+//! - Providers only see inline base64. A part naming a workspace file is read
+//!   here (path-sanitized through [`crate::runtime::workspace`]) and converted
+//!   before the request is assembled, so `request_digest` covers the actual
+//!   bytes sent.
+//! - Raw bytes never reach the journal. The prompt `CallRecord.args` carries
+//!   one content-hash reference per attachment ([`journal_refs`]), which is
+//!   enough for divergence detection on replay — an edited image is a changed
+//!   hash — without growing `records.jsonl` by megabytes per call.
+
+use std::path::Path;
+
+use base64::Engine as _;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::providers::{ContentBlock, MediaSource, Message};
+
+/// Upper bound on a single attachment. Provider limits are lower (Anthropic
+/// caps images at 5 MB, PDFs at 32 MB); this only stops an agent from pulling
+/// an arbitrarily large file into memory and the request body.
+const MAX_ATTACHMENT_BYTES: u64 = 32 * 1024 * 1024;
+
+/// Parse author-facing content parts into content blocks. Accepts a bare
+/// string (one text part) or an array of parts:
+///
+/// - `"text"` or `{ type: "text", text }`
+/// - `{ type: "image", path | data, mediaType? }`
+/// - `{ type: "document", path | data, mediaType?, title? }`
+///
+/// `data` is base64 and requires `mediaType`; `path` is relative to the
+/// workspace root and infers the media type from the extension when omitted.
+pub(crate) fn blocks_from_parts(parts: &Value) -> Result<Vec<ContentBlock>, String> {
+    match parts {
+        Value::String(text) => Ok(vec![ContentBlock::Text { text: text.clone() }]),
+        Value::Array(items) => items.iter().map(block_from_part).collect(),
+        Value::Null => Ok(Vec::new()),
+        _ => Err("prompt content must be a string or an array of content parts".to_string()),
+    }
+}
+
+fn block_from_part(part: &Value) -> Result<ContentBlock, String> {
+    if let Some(text) = part.as_str() {
+        return Ok(ContentBlock::Text {
+            text: text.to_string(),
+        });
+    }
+    let kind = part.get("type").and_then(Value::as_str).unwrap_or("");
+    match kind {
+        "text" => Ok(ContentBlock::Text {
+            text: part
+                .get("text")
+                .and_then(Value::as_str)
+                .unwrap_or("")
+                .to_string(),
+        }),
+        "image" => Ok(ContentBlock::Image {
+            source: source_from_part(part, "image")?,
+        }),
+        "document" => Ok(ContentBlock::Document {
+            source: source_from_part(part, "document")?,
+            title: part
+                .get("title")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+        }),
+        other => Err(format!(
+            "unknown content part type `{other}` (expected text, image, or document)"
+        )),
+    }
+}
+
+fn source_from_part(part: &Value, kind: &str) -> Result<MediaSource, String> {
+    let media_type = part
+        .get("mediaType")
+        .or_else(|| part.get("media_type"))
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    if let Some(path) = part.get("path").and_then(Value::as_str) {
+        return Ok(MediaSource::File {
+            path: path.to_string(),
+            media_type,
+        });
+    }
+    if let Some(data) = part.get("data").and_then(Value::as_str) {
+        let media_type = media_type
+            .ok_or_else(|| format!("{kind} part with inline `data` requires `mediaType`"))?;
+        return Ok(MediaSource::Base64 {
+            media_type,
+            data: data.to_string(),
+        });
+    }
+    Err(format!(
+        "{kind} part requires a workspace `path` or base64 `data`"
+    ))
+}
+
+/// Media type for a workspace file, from its extension.
+fn media_type_for_path(path: &str) -> Option<&'static str> {
+    let ext = Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+    Some(match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "pdf" => "application/pdf",
+        "txt" | "md" => "text/plain",
+        _ => return None,
+    })
+}
+
+/// Resolve every `MediaSource::File` in `messages` to inline base64, reading
+/// the file under `root`. Messages without media are untouched, so a
+/// text-only request is byte-identical to before attachments existed.
+pub(crate) fn resolve_files(messages: &mut [Message], root: Option<&Path>) -> Result<(), String> {
+    for message in messages {
+        for block in &mut message.content {
+            let source = match block {
+                ContentBlock::Image { source } | ContentBlock::Document { source, .. } => source,
+                _ => continue,
+            };
+            let MediaSource::File { path, media_type } = source else {
+                continue;
+            };
+            let root = root.ok_or_else(|| {
+                format!("attachment `{path}` needs a workspace root to resolve against")
+            })?;
+            let media_type = match media_type.clone() {
+                Some(media_type) => media_type,
+                None => media_type_for_path(path)
+                    .ok_or_else(|| {
+                        format!("cannot infer the media type of `{path}`; pass `mediaType`")
+                    })?
+                    .to_string(),
+            };
+            let bytes = crate::runtime::workspace::read_bytes(root, path, MAX_ATTACHMENT_BYTES)?;
+            *source = MediaSource::Base64 {
+                media_type,
+                data: base64::engine::general_purpose::STANDARD.encode(&bytes),
+            };
+        }
+    }
+    Ok(())
+}
+
+/// [`resolve_files`] then [`journal_refs`], for a prompt about to be
+/// journaled. When the call replays from the journal (`replayed` is its
+/// recorded args) and a file can no longer be read — deleted or moved since
+/// the original run — the recorded references stand in: a replayed call
+/// sends nothing to the provider, so it never needed the bytes. A file that
+/// still exists is hashed as usual, so an edit is still a divergence.
+pub(crate) fn resolve_for_journal(
+    messages: &mut [Message],
+    root: Option<&Path>,
+    replayed: Option<&Value>,
+) -> Result<Vec<Value>, String> {
+    match resolve_files(messages, root) {
+        Ok(()) => Ok(journal_refs(messages)),
+        Err(err) => replayed
+            .and_then(|args| args.get("attachments"))
+            .and_then(Value::as_array)
+            .cloned()
+            .ok_or(err),
+    }
+}
+
+/// The path of the first attachment still naming a workspace file, if any.
+/// A live provider call must not go out with one: only a replayed turn can
+/// skip resolution ([`resolve_for_journal`]).
+pub(crate) fn unresolved_file(messages: &[Message]) -> Option<&str> {
+    messages
+        .iter()
+        .flat_map(|m| &m.content)
+        .find_map(|block| match block {
+            ContentBlock::Image {
+                source: MediaSource::File { path, .. },
+            }
+            | ContentBlock::Document {
+                source: MediaSource::File { path, .. },
+                ..
+            } => Some(path.as_str()),
+            _ => None,
+        })
+}
+
+/// Content-hash references for every attachment in `messages`, in order —
+/// what the prompt call log records in place of the bytes. Hashes the decoded
+/// payload, so the same image inlined or read from a file journals the same
+/// reference. Empty for text-only requests (callers then omit the key).
+pub(crate) fn journal_refs(messages: &[Message]) -> Vec<Value> {
+    let mut refs = Vec::new();
+    for message in messages {
+        for block in &message.content {
+            let (kind, source) = match block {
+                ContentBlock::Image { source } => ("image", source),
+                ContentBlock::Document { source, .. } => ("document", source),
+                _ => continue,
+            };
+            refs.push(match source {
+                MediaSource::Base64 { media_type, data } => {
+                    let bytes = base64::engine::general_purpose::STANDARD
+                        .decode(data)
+                        .unwrap_or_else(|_| data.as_bytes().to_vec());
+                    json!({
+                        "type": kind,
+                        "media_type": media_type,
+                        "sha256": hex::encode(Sha256::digest(&bytes)),
+                        "bytes": bytes.len(),
+                    })
+                }
+                // Unresolved (callers resolve before journaling); the path is
+                // the only stable identity available.
+                MediaSource::File { path, media_type } => json!({
+                    "type": kind,
+                    "media_type": media_type,
+                    "path": path,
+                }),
+            });
+        }
+    }
+    refs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parts_parse_text_image_and_document() {
+        let blocks = blocks_from_parts(&json!([
+            "look at this",
+            { "type": "image", "path": "shot.png" },
+            { "type": "document", "data": "aGk=", "mediaType": "application/pdf", "title": "Q3" },
+        ]))
+        .unwrap();
+        assert_eq!(blocks.len(), 3);
+        assert!(matches!(&blocks[0], ContentBlock::Text { text } if text == "look at this"));
+        assert!(matches!(
+            &blocks[1],
+            ContentBlock::Image { source: MediaSource::File { path, media_type: None } }
+                if path == "shot.png"
+        ));
+        assert!(matches!(
+            &blocks[2],
+            ContentBlock::Document { source: MediaSource::Base64 { .. }, title: Some(t) } if t == "Q3"
+        ));
+        assert!(blocks_from_parts(&json!([{ "type": "image", "data": "aGk=" }])).is_err());
+        assert!(blocks_from_parts(&json!([{ "type": "video", "path": "x.mp4" }])).is_err());
+    }
+
+    #[test]
+    fn file_sources_resolve_to_base64_and_journal_by_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("pixel.png"), b"\x89PNG fake").unwrap();
+        let mut messages = vec![Message {
+            role: "user".to_string(),
+            content: blocks_from_parts(&json!([{ "type": "image", "path": "pixel.png" }])).unwrap(),
+            cache_control: None,
+        }];
+        resolve_files(&mut messages, Some(dir.path())).unwrap();
+        let ContentBlock::Image {
+            source: MediaSource::Base64 { media_type, data },
+        } = &messages[0].content[0]
+        else {
+            panic!("file source was not resolved");
+        };
+        assert_eq!(media_type, "image/png");
+
+        // The journal reference hashes the bytes and never carries them.
+        let refs = journal_refs(&messages);
+        assert_eq!(refs.len(), 1);
+        assert_eq!(refs[0]["bytes"], 9);
+        assert_eq!(
+            refs[0]["sha256"],
+            hex::encode(Sha256::digest(b"\x89PNG fake"))
+        );
+        assert!(!refs[0].to_string().contains(data.as_str()));
+    }
+
+    #[test]
+    fn oversized_files_are_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("big.pdf"), vec![0u8; 17]).unwrap();
+        assert!(crate::runtime::workspace::read_bytes(dir.path(), "big.pdf", 16).is_err());
+        assert_eq!(
+            crate::runtime::workspace::read_bytes(dir.path(), "big.pdf", 17)
+                .unwrap()
+                .len(),
+            17
+        );
+    }
+
+    #[test]
+    fn replay_uses_journaled_refs_when_the_file_is_gone() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut messages = vec![Message {
+            role: "user".to_string(),
+            content: blocks_from_parts(&json!([{ "type": "image", "path": "gone.png" }])).unwrap(),
+            cache_control: None,
+        }];
+        assert!(resolve_for_journal(&mut messages, Some(dir.path()), None).is_err());
+
+        let recorded = json!({ "attachments": [{ "type": "image", "sha256": "abc" }] });
+        let refs = resolve_for_journal(&mut messages, Some(dir.path()), Some(&recorded)).unwrap();
+        assert_eq!(refs, vec![recorded["attachments"][0].clone()]);
+        assert_eq!(unresolved_file(&messages), Some("gone.png"));
+    }
+
+    #[test]
+    fn file_sources_refuse_workspace_escape() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut messages = vec![Message {
+            role: "user".to_string(),
+            content: blocks_from_parts(&json!([{ "type": "image", "path": "../etc/passwd.png" }]))
+                .unwrap(),
+            cache_control: None,
+        }];
+        assert!(resolve_files(&mut messages, Some(dir.path())).is_err());
+    }
+}
@@ -1,6 +1,6 @@
 use std::path::Path;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use serde_json::{json, Value};
 
 /// Persist memory under `<base>/.chidori/memory/`. `base` is the run's
@@ -61,7 +61,8 @@ pub fn execute_memory_action(
             let mut map = load()?;
             let existed = map.remove(key).is_some();
             save(&map)?;
-            Ok(Value::Bool(existed))
+            let indexed = delete_memory_vectors(&dir, namespace, Some(key))? > 0;
+            Ok(Value::Bool(existed || indexed))
         }
         "list" => {
             let map = load()?;
@@ -74,6 +75,7 @@ pub fn execute_memory_action(
         }
         "clear" => {
             save(&serde_json::Map::new())?;
+            delete_memory_vectors(&dir, namespace, None)?;
             Ok(Value::Null)
         }
         other => Err(anyhow::anyhow!(
@@ -82,6 +84,156 @@ pub fn execute_memory_action(
     }
 }
 
+/// One `chidori.memory.upsert` entry: the text and its metadata, plus the
+/// vector the caller already embedded (and journaled) for it.
+pub struct VectorEntry<'a> {
+    pub key: &'a str,
+    pub text: &'a str,
+    pub metadata: &'a Value,
+    pub model: &'a str,
+    pub vector: &'a [f32],
+}
+
+/// The semantic half of a namespace: `<base>/.chidori/memory/<ns>.vectors.sqlite3`,
+/// one row per key. Vectors are stored as little-endian `f32` blobs and
+/// searched exactly — a cosine scan over the namespace's rows — which is
+/// plenty at agent scale and keeps results independent of index build order.
+fn open_vector_index(dir: &Path, namespace: &str) -> Result<rusqlite::Connection> {
+    std::fs::create_dir_all(dir)?;
+    let path = vector_index_path(dir, namespace);
+    let conn = rusqlite::Connection::open(&path)
+        .with_context(|| format!("opening memory index at {}", path.display()))?;
+    conn.pragma_update(None, "journal_mode", "WAL").ok();
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS vectors (
+             key TEXT PRIMARY KEY,
+             text TEXT NOT NULL,
+             metadata TEXT NOT NULL,
+             model TEXT NOT NULL,
+             vector BLOB NOT NULL
+         );",
+    )?;
+    Ok(conn)
+}
+
+fn vector_index_path(dir: &Path, namespace: &str) -> std::path::PathBuf {
+    dir.join(format!("{}.vectors.sqlite3", sanitize_namespace(namespace)))
+}
+
+/// Insert or replace `entry` in the namespace's vector index.
+pub fn upsert_memory_vector(base: &Path, namespace: &str, entry: &VectorEntry) -> Result<Value> {
+    let conn = open_vector_index(&base.join(".chidori").join("memory"), namespace)?;
+    let blob: Vec<u8> = entry.vector.iter().flat_map(|x| x.to_le_bytes()).collect();
+    conn.execute(
+        "INSERT INTO vectors (key, text, metadata, model, vector) VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(key) DO UPDATE SET
+             text = excluded.text, metadata = excluded.metadata,
+             model = excluded.model, vector = excluded.vector",
+        rusqlite::params![
+            entry.key,
+            entry.text,
+            serde_json::to_string(entry.metadata)?,
+            entry.model,
+            blob
+        ],
+    )?;
+    Ok(Value::Null)
+}
+
+/// The `k` entries most similar to `query`, as `[{ key, text, metadata,
+/// score }]` by descending cosine similarity (ties by key). Only entries
+/// embedded with `model` are candidates — vectors from different models
+/// aren't comparable — and `filter` keeps entries whose metadata matches
+/// every field: equal to the value, or, for an array, equal to any element.
+pub fn search_memory_vectors(
+    base: &Path,
+    namespace: &str,
+    model: &str,
+    query: &[f32],
+    k: usize,
+    filter: &Value,
+) -> Result<Value> {
+    let dir = base.join(".chidori").join("memory");
+    if !vector_index_path(&dir, namespace).exists() {
+        return Ok(json!([]));
+    }
+    let conn = open_vector_index(&dir, namespace)?;
+    let mut stmt =
+        conn.prepare("SELECT key, text, metadata, vector FROM vectors WHERE model = ?1")?;
+    let rows = stmt.query_map([model], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, Vec<u8>>(3)?,
+        ))
+    })?;
+    let query_norm = norm(query);
+    let mut hits = Vec::new();
+    for row in rows {
+        let (key, text, metadata, blob) = row?;
+        let metadata: Value = serde_json::from_str(&metadata).unwrap_or(Value::Null);
+        if !metadata_matches(&metadata, filter) {
+            continue;
+        }
+        let vector: Vec<f32> = blob
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        if vector.len() != query.len() {
+            anyhow::bail!(
+                "memory index entry `{key}` has {} dimensions but the query has {}",
+                vector.len(),
+                query.len()
+            );
+        }
+        let dot: f32 = vector.iter().zip(query).map(|(a, b)| a * b).sum();
+        let denom = norm(&vector) * query_norm;
+        let score = if denom > 0.0 { dot / denom } else { 0.0 };
+        hits.push((score, key, text, metadata));
+    }
+    hits.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    Ok(Value::Array(
+        hits.into_iter()
+            .take(k)
+            .map(|(score, key, text, metadata)| {
+                json!({ "key": key, "text": text, "metadata": metadata, "score": score })
+            })
+            .collect(),
+    ))
+}
+
+/// Remove one key (or, with `None`, every entry) from the namespace's vector
+/// index, returning how many rows went. A namespace never upserted into has
+/// no index file and is left alone.
+fn delete_memory_vectors(dir: &Path, namespace: &str, key: Option<&str>) -> Result<usize> {
+    if !vector_index_path(dir, namespace).exists() {
+        return Ok(0);
+    }
+    let conn = open_vector_index(dir, namespace)?;
+    Ok(match key {
+        Some(key) => conn.execute("DELETE FROM vectors WHERE key = ?1", [key])?,
+        None => conn.execute("DELETE FROM vectors", [])?,
+    })
+}
+
+fn norm(vector: &[f32]) -> f32 {
+    vector.iter().map(|x| x * x).sum::<f32>().sqrt()
+}
+
+fn metadata_matches(metadata: &Value, filter: &Value) -> bool {
+    let Some(filter) = filter.as_object() else {
+        return true;
+    };
+    filter.iter().all(|(field, wanted)| {
+        let actual = metadata.get(field).unwrap_or(&Value::Null);
+        match wanted {
+            Value::Array(options) => options.contains(actual),
+            wanted => actual == wanted,
+        }
+    })
+}
+
 pub fn sanitize_namespace(namespace: &str) -> String {
     namespace
         .chars()
@@ -146,6 +298,69 @@ mod tests {
         let _ = std::fs::remove_dir_all(base);
     }
 
+    #[test]
+    fn vector_search_ranks_by_cosine_and_filters_on_metadata() {
+        let base = std::env::temp_dir().join(format!("chidori-mem-vec-{}", uuid::Uuid::new_v4()));
+        let upsert = |key: &str, vector: &[f32], metadata: Value, model: &str| {
+            upsert_memory_vector(
+                &base,
+                "docs",
+                &VectorEntry {
+                    key,
+                    text: key,
+                    metadata: &metadata,
+                    model,
+                    vector,
+                },
+            )
+            .unwrap();
+        };
+        upsert("east", &[1.0, 0.0], json!({ "lang": "en" }), "m");
+        upsert("north", &[0.0, 1.0], json!({ "lang": "de" }), "m");
+        upsert("north-east", &[0.7, 0.7], json!({ "lang": "en" }), "m");
+        upsert("other-model", &[1.0, 0.0], json!({}), "other");
+        // A re-upsert replaces the entry in place.
+        upsert("north", &[0.0, 2.0], json!({ "lang": "fr" }), "m");
+
+        let keys = |hits: Value| -> Vec<String> {
+            hits.as_array()
+                .unwrap()
+                .iter()
+                .map(|h| h["key"].as_str().unwrap().to_string())
+                .collect()
+        };
+        let all = search_memory_vectors(&base, "docs", "m", &[1.0, 0.1], 10, &Value::Null).unwrap();
+        assert_eq!(keys(all.clone()), ["east", "north-east", "north"]);
+        assert_eq!(all[2]["metadata"], json!({ "lang": "fr" }));
+
+        let top = search_memory_vectors(&base, "docs", "m", &[1.0, 0.1], 1, &Value::Null).unwrap();
+        assert_eq!(keys(top), ["east"]);
+        let filtered = search_memory_vectors(
+            &base,
+            "docs",
+            "m",
+            &[1.0, 0.1],
+            10,
+            &json!({ "lang": ["fr", "de"] }),
+        )
+        .unwrap();
+        assert_eq!(keys(filtered), ["north"]);
+
+        // delete/clear reach the index as well as the JSON map.
+        let deleted =
+            execute_memory_action(&base, "delete", "docs", Some("east"), None, "").unwrap();
+        assert_eq!(deleted, json!(true));
+        execute_memory_action(&base, "clear", "docs", None, None, "").unwrap();
+        let empty =
+            search_memory_vectors(&base, "docs", "m", &[1.0, 0.0], 10, &Value::Null).unwrap();
+        assert_eq!(empty, json!([]));
+        let missing =
+            search_memory_vectors(&base, "never-used", "m", &[1.0, 0.0], 10, &Value::Null).unwrap();
+        assert_eq!(missing, json!([]));
+
+        let _ = std::fs::remove_dir_all(base);
+    }
+
     #[test]
     fn memory_is_anchored_to_the_base_dir_not_cwd() {
         // The store lives under `<base>/.chidori/memory`, so two different
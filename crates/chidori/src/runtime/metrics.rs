@@ -0,0 +1,355 @@
+//! Process-wide Prometheus metrics, served as text by `GET /metrics`.
+//!
+//! Counters and histograms live in one registry that the code bumps where
+//! the event happens: [`RuntimeContext::record_call`] for host calls and
+//! model spend, the policy audit for decisions, the server's run-slot
+//! acquire for concurrency rejections, the scheduler for tick outcomes and
+//! the detached-agent supervisor for restarts. Gauges describing current
+//! state (how many sessions are paused) are not kept here — the endpoint
+//! reads them at scrape time and hands them to [`render`].
+//!
+//! Values are per process and start from zero on restart; Prometheus'
+//! `rate()` and `increase()` absorb the reset. With several replicas, each
+//! serves its own `/metrics` and the scraper sums across them — except the
+//! session gauges, which come from the shared session store and so read the
+//! same on every replica.
+//!
+//! [`RuntimeContext::record_call`]: crate::runtime::context::RuntimeContext::record_call
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::{Mutex, OnceLock};
+
+use crate::runtime::call_log::CallRecord;
+use crate::runtime::cost::estimate_cost_usd_with_cache;
+
+pub const HOST_CALLS: &str = "chidori_host_calls_total";
+pub const HOST_CALL_SECONDS: &str = "chidori_host_call_duration_seconds";
+pub const MODEL_TOKENS: &str = "chidori_model_tokens_total";
+pub const MODEL_COST_USD: &str = "chidori_model_cost_usd_total";
+pub const POLICY_DECISIONS: &str = "chidori_policy_decisions_total";
+pub const CONCURRENCY_REJECTIONS: &str = "chidori_concurrency_rejections_total";
+pub const SCHEDULER_TICKS: &str = "chidori_scheduler_ticks_total";
+pub const DETACHED_RESTARTS: &str = "chidori_detached_agent_restarts_total";
+
+/// Type and help text of every registry metric, in exposition order.
+/// Families with no samples yet still print their `HELP`/`TYPE` header, so a
+/// scraper sees the full schema from the first scrape.
+const FAMILIES: &[(&str, &str, &str)] = &[
+    (
+        HOST_CALLS,
+        "counter",
+        "Live host calls by op and outcome (ok or error).",
+    ),
+    (HOST_CALL_SECONDS, "histogram", "Host-call latency by op."),
+    (
+        MODEL_TOKENS,
+        "counter",
+        "Provider tokens by model and kind (input, output, cache_write, cache_read).",
+    ),
+    (
+        MODEL_COST_USD,
+        "counter",
+        "Estimated provider spend in USD by model, from the pricing table.",
+    ),
+    (
+        POLICY_DECISIONS,
+        "counter",
+        "Policy-gated calls by outcome.",
+    ),
+    (
+        CONCURRENCY_REJECTIONS,
+        "counter",
+        "Runs refused with 503 because every concurrent-session slot stayed taken.",
+    ),
+    (
+        SCHEDULER_TICKS,
+        "counter",
+        "Scheduled recipe ticks by recipe and outcome.",
+    ),
+    (
+        DETACHED_RESTARTS,
+        "counter",
+        "Detached-agent restarts after a crash, by agent name.",
+    ),
+];
+
+/// Upper bounds (seconds) of the host-call latency buckets: millisecond tool
+/// calls through minute-long model calls.
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0,
+];
+
+type Labels = Vec<(&'static str, String)>;
+
+#[derive(Default)]
+struct Histogram {
+    /// Per-bucket (not cumulative) counts; the `+Inf` overflow is the last slot.
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+/// Counter and histogram samples keyed by metric name and label set.
+#[derive(Default)]
+pub struct Registry {
+    counters: BTreeMap<(&'static str, Labels), f64>,
+    histograms: BTreeMap<(&'static str, Labels), Histogram>,
+}
+
+/// A gauge family read at scrape time.
+pub struct Gauge {
+    pub name: &'static str,
+    pub help: &'static str,
+    pub samples: Vec<(Labels, f64)>,
+}
+
+fn labels(pairs: &[(&'static str, &str)]) -> Labels {
+    pairs.iter().map(|(k, v)| (*k, v.to_string())).collect()
+}
+
+impl Registry {
+    pub fn inc(&mut self, name: &'static str, pairs: &[(&'static str, &str)], by: f64) {
+        *self.counters.entry((name, labels(pairs))).or_default() += by;
+    }
+
+    pub fn observe(&mut self, name: &'static str, pairs: &[(&'static str, &str)], value: f64) {
+        let histogram = self.histograms.entry((name, labels(pairs))).or_default();
+        if histogram.buckets.is_empty() {
+            histogram.buckets = vec![0; LATENCY_BUCKETS.len() + 1];
+        }
+        let slot = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        histogram.buckets[slot] += 1;
+        histogram.sum += value;
+        histogram.count += 1;
+    }
+
+    /// Count one live host call: its op and outcome, its latency and, for a
+    /// model call, its tokens and estimated cost.
+    pub fn record_call(&mut self, record: &CallRecord) {
+        let op = record.function.as_str();
+        let outcome = if record.error.is_some() {
+            "error"
+        } else {
+            "ok"
+        };
+        self.inc(HOST_CALLS, &[("op", op), ("outcome", outcome)], 1.0);
+        self.observe(
+            HOST_CALL_SECONDS,
+            &[("op", op)],
+            record.duration_ms as f64 / 1000.0,
+        );
+        let Some(usage) = record
+            .token_usage
+            .as_ref()
+            .filter(|_| record.is_model_call())
+        else {
+            return;
+        };
+        let model = record.priced_model();
+        let cache_write = usage.cache_creation_tokens.unwrap_or(0);
+        let cache_read = usage.cache_read_tokens.unwrap_or(0);
+        for (kind, tokens) in [
+            ("input", usage.input_tokens),
+            ("output", usage.output_tokens),
+            ("cache_write", cache_write),
+            ("cache_read", cache_read),
+        ] {
+            if tokens > 0 {
+                self.inc(
+                    MODEL_TOKENS,
+                    &[("model", model), ("kind", kind)],
+                    tokens as f64,
+                );
+            }
+        }
+        let cost = estimate_cost_usd_with_cache(
+            model,
+            usage.input_tokens,
+            usage.output_tokens,
+            cache_write,
+            cache_read,
+        );
+        self.inc(MODEL_COST_USD, &[("model", model)], cost);
+    }
+
+    /// The Prometheus text exposition (format 0.0.4) of every registry
+    /// family, followed by `gauges`.
+    pub fn render(&self, gauges: &[Gauge]) -> String {
+        let mut out = String::new();
+        for (name, kind, help) in FAMILIES {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} {kind}");
+            for ((_, labels), value) in self.counters.iter().filter(|((n, _), _)| n == name) {
+                let _ = writeln!(out, "{name}{} {value}", format_labels(labels, None));
+            }
+            for ((_, labels), histogram) in self.histograms.iter().filter(|((n, _), _)| n == name) {
+                let mut cumulative = 0;
+                for (i, count) in histogram.buckets.iter().enumerate() {
+                    cumulative += count;
+                    let le = match LATENCY_BUCKETS.get(i) {
+                        Some(bound) => bound.to_string(),
+                        None => "+Inf".to_string(),
+                    };
+                    let _ = writeln!(
+                        out,
+                        "{name}_bucket{} {cumulative}",
+                        format_labels(labels, Some(&le))
+                    );
+                }
+                let labels = format_labels(labels, None);
+                let _ = writeln!(out, "{name}_sum{labels} {}", histogram.sum);
+                let _ = writeln!(out, "{name}_count{labels} {}", histogram.count);
+            }
+        }
+        for gauge in gauges {
+            let _ = writeln!(out, "# HELP {} {}", gauge.name, gauge.help);
+            let _ = writeln!(out, "# TYPE {} gauge", gauge.name);
+            for (labels, value) in &gauge.samples {
+                let _ = writeln!(out, "{}{} {value}", gauge.name, format_labels(labels, None));
+            }
+        }
+        out
+    }
+}
+
+/// `{k="v",…}` with values escaped per the exposition format, or nothing for
+/// an unlabelled sample. `le` appends a histogram bucket bound.
+fn format_labels(labels: &Labels, le: Option<&str>) -> String {
+    let mut pairs: Vec<String> = labels
+        .iter()
+        .map(|(k, v)| format!("{k}=\"{}\"", escape_label(v)))
+        .collect();
+    if let Some(le) = le {
+        pairs.push(format!("le=\"{le}\""));
+    }
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", pairs.join(","))
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn global() -> &'static Mutex<Registry> {
+    static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
+}
+
+/// Add `by` to a counter in the process registry.
+pub fn inc(name: &'static str, pairs: &[(&'static str, &str)], by: f64) {
+    global().lock().unwrap().inc(name, pairs, by);
+}
+
+/// Count one live host call in the process registry.
+pub fn record_call(record: &CallRecord) {
+    global().lock().unwrap().record_call(record);
+}
+
+/// Render the process registry plus `gauges` as Prometheus text.
+pub fn render(gauges: &[Gauge]) -> String {
+    global().lock().unwrap().render(gauges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::call_log::TokenUsage;
+    use serde_json::json;
+
+    fn call(function: &str, duration_ms: u64, error: Option<&str>) -> CallRecord {
+        CallRecord {
+            seq: 1,
+            parent_seq: None,
+            function: function.to_string(),
+            args: json!({"model": "gpt-4o"}),
+            result: json!(null),
+            duration_ms,
+            token_usage: None,
+            timestamp: chrono::Utc::now(),
+            error: error.map(str::to_string),
+            served_by: None,
+        }
+    }
+
+    #[test]
+    fn host_calls_render_as_counters_and_cumulative_histograms() {
+        let mut registry = Registry::default();
+        registry.record_call(&call("tool", 3, None));
+        registry.record_call(&call("tool", 700, Some("boom")));
+        let text = registry.render(&[]);
+
+        assert!(
+            text.contains("# TYPE chidori_host_calls_total counter\n"),
+            "{text}"
+        );
+        assert!(text.contains("chidori_host_calls_total{op=\"tool\",outcome=\"ok\"} 1\n"));
+        assert!(text.contains("chidori_host_calls_total{op=\"tool\",outcome=\"error\"} 1\n"));
+        assert!(text
+            .contains("chidori_host_call_duration_seconds_bucket{op=\"tool\",le=\"0.005\"} 1\n"));
+        assert!(
+            text.contains("chidori_host_call_duration_seconds_bucket{op=\"tool\",le=\"0.5\"} 1\n")
+        );
+        assert!(
+            text.contains("chidori_host_call_duration_seconds_bucket{op=\"tool\",le=\"1\"} 2\n")
+        );
+        assert!(
+            text.contains("chidori_host_call_duration_seconds_bucket{op=\"tool\",le=\"+Inf\"} 2\n")
+        );
+        assert!(text.contains("chidori_host_call_duration_seconds_count{op=\"tool\"} 2\n"));
+        // Families without samples still announce themselves.
+        assert!(text.contains("# TYPE chidori_scheduler_ticks_total counter\n"));
+    }
+
+    #[test]
+    fn model_calls_add_tokens_and_cost_by_model() {
+        let mut registry = Registry::default();
+        let mut record = call("prompt", 1200, None);
+        record.token_usage = Some(TokenUsage {
+            input_tokens: 1000,
+            output_tokens: 200,
+            cache_creation_tokens: None,
+            cache_read_tokens: None,
+            reasoning_tokens: None,
+        });
+        registry.record_call(&record);
+        // Tokens on a non-model call are not spend.
+        let mut tool = call("tool", 1, None);
+        tool.token_usage = record.token_usage.clone();
+        registry.record_call(&tool);
+        let text = registry.render(&[]);
+
+        assert!(text.contains("chidori_model_tokens_total{model=\"gpt-4o\",kind=\"input\"} 1000\n"));
+        assert!(text.contains("chidori_model_tokens_total{model=\"gpt-4o\",kind=\"output\"} 200\n"));
+        assert!(!text.contains("kind=\"cache_read\""), "{text}");
+        let expected = estimate_cost_usd_with_cache("gpt-4o", 1000, 200, 0, 0);
+        assert!(expected > 0.0);
+        assert!(text.contains(&format!(
+            "chidori_model_cost_usd_total{{model=\"gpt-4o\"}} {expected}\n"
+        )));
+    }
+
+    #[test]
+    fn gauges_render_after_the_registry_with_escaped_labels() {
+        let registry = Registry::default();
+        let text = registry.render(&[Gauge {
+            name: "chidori_sessions",
+            help: "Sessions by status.",
+            samples: vec![(vec![("status", "pa\"used\n".to_string())], 2.0)],
+        }]);
+        assert!(text.ends_with(
+            "# HELP chidori_sessions Sessions by status.\n\
+             # TYPE chidori_sessions gauge\n\
+             chidori_sessions{status=\"pa\\\"used\\n\"} 2\n"
+        ));
+    }
+}
@@ -5,10 +5,18 @@ pub mod compensation;
 pub mod compress;
 pub mod context;
 pub mod cost;
+/// Block and line coverage of agent code (`--coverage`): LCOV and a JSON
+/// summary, merged across invocations.
+pub mod coverage;
 pub mod crypto;
+/// Source-level debugging of agent runs (`chidori debug`), run side.
+pub mod debugger;
 pub mod engine;
 /// Typed error taxonomy: the pause interrupt and run-failure classification.
 pub mod errors;
+/// Heap snapshots of agent JavaScript (`v8.writeHeapSnapshot`,
+/// `chidori run --heap-snapshot-on-oom`).
+pub mod heap_snapshot;
 pub mod holdings;
 pub mod host_actor;
 /// Detached, durable, addressable agent processes (`chidori.agents.*`).
@@ -19,7 +27,12 @@ pub mod host_core;
 /// host effects back over a pipe (see `docs/os-isolation-plan.md`).
 pub mod isolate;
 pub mod mainline_image;
+/// Image/document prompt attachments: workspace-file resolution and
+/// content-hash journaling.
+pub mod media;
 pub mod memory;
+/// Process-wide Prometheus counters and histograms behind `GET /metrics`.
+pub mod metrics;
 pub mod native;
 // OTLP span export (tael/Jaeger/Tempo). The real implementation carries the
 // heavy OTLP/gRPC dependency tree, so it is feature-gated; without `otel` a
@@ -31,6 +44,10 @@ pub mod otel;
 #[cfg(not(feature = "otel"))]
 #[path = "otel_noop.rs"]
 pub mod otel;
+/// Durable record of every live policy decision (`policy/audit.jsonl`).
+pub mod policy_audit;
+/// CPU profiles of agent JavaScript (`chidori run --profile`).
+pub mod profiler;
 pub mod prompt_cache;
 /// Pure-Rust JS engine integration — the only JavaScript engine.
 pub mod rust_engine;
@@ -43,6 +60,8 @@ pub mod ssrf;
 pub mod store;
 /// S3-compatible blob backend for the run store (S3 / R2 / GCS / MinIO).
 pub mod store_blob;
+/// Postgres backend for the run store, shared by replicas behind a load balancer.
+pub mod store_pg;
 pub mod template;
 pub mod typescript;
 pub mod vfs;
@@ -14,6 +14,7 @@ use crate::providers::{ContentBlock, LlmRequest, Message, ProviderRegistry, Tool
 use crate::runtime::call_log::{CallLog, CallRecord};
 use crate::runtime::context::{InputMode, PendingApproval, PendingInput, RuntimeContext};
 use crate::runtime::host_core::{execute_native_tool_call_at_seq, execute_prompt_response};
+use crate::runtime::policy_audit::{self, PolicyAuditEntry};
 use crate::runtime::snapshot::PendingHostOperationKind;
 use crate::tools::ToolRegistry;
 
@@ -286,6 +287,7 @@ impl NativeAgentRunner {
             token_usage: None,
             timestamp: Utc::now(),
             error: None,
+            served_by: None,
         });
         self.resume_with_tool_result(checkpoint, ctx, pending.call, tool_result, pending.batch)
     }
@@ -388,6 +390,8 @@ impl NativeAgentRunner {
                 max_tokens: request.max_tokens,
                 tools: current_tools.clone(),
                 cache: crate::providers::CacheLayout::default(),
+                response_schema: None,
+                reasoning: None,
             };
             // Cache the stable head (system + tools + conversation prefix) so
             // every turn after the first reads the prefix at the discounted
@@ -512,10 +516,24 @@ impl NativeAgentRunner {
             }
 
             let target = format!("tool:{}", call.name);
-            let (decision, reason) = self.policy.decide(&target, &call.input);
-            match decision {
-                Decision::AlwaysAllow => {}
+            let verdict = self
+                .policy
+                .evaluate(&target, &call.input, Some(&ctx.run_id()));
+            let audit = |outcome: &str, via: Option<&str>| {
+                let entry =
+                    PolicyAuditEntry::for_call(ctx, seq, &target, &call.input, &verdict, outcome);
+                let entry = match via {
+                    Some(via) => entry.with_approval(via),
+                    None => entry,
+                };
+                policy_audit::record(ctx, &entry);
+            };
+            let reason = verdict.reason.clone();
+            match verdict.decision {
+                Decision::AlwaysAllow => audit("allowed", None),
                 Decision::NeverAllow => {
+                    audit("denied", None);
+                    verdict.release();
                     anyhow::bail!(
                         "policy: `{}` denied{}",
                         target,
@@ -527,7 +545,12 @@ impl NativeAgentRunner {
                         .lock()
                         .unwrap()
                         .is_approved(&target, &call.input);
-                    if !approved {
+                    if approved {
+                        audit("approved", Some("approval_cache"));
+                    } else {
+                        // Keeps its slots: the approved call runs on resume
+                        // without being evaluated again.
+                        audit("paused", None);
                         let approval = PendingApproval {
                             target,
                             args: call.input.clone(),
@@ -651,6 +674,7 @@ mod tests {
                         id: "call_1".to_string(),
                         name: "echo".to_string(),
                         input: serde_json::json!({ "value": 42 }),
+                        thought_signature: None,
                     }],
                     tool_calls: vec![ToolCall {
                         id: "call_1".to_string(),
@@ -708,6 +732,7 @@ mod tests {
                         id: "ask_1".to_string(),
                         name: "ask_user".to_string(),
                         input: serde_json::json!({ "question": "Which file?" }),
+                        thought_signature: None,
                     }],
                     tool_calls: vec![ToolCall {
                         id: "ask_1".to_string(),
@@ -795,6 +820,7 @@ mod tests {
                         id: "call_1".to_string(),
                         name: "echo".to_string(),
                         input: serde_json::json!({ "value": 1 }),
+                        thought_signature: None,
                     }],
                     tool_calls: vec![ToolCall {
                         id: "call_1".to_string(),
@@ -898,6 +924,7 @@ mod tests {
                         id: "call_1".to_string(),
                         name: "echo".to_string(),
                         input: serde_json::json!({ "value": 1 }),
+                        thought_signature: None,
                     }],
                     tool_calls: vec![ToolCall {
                         id: "call_1".to_string(),
@@ -1006,10 +1033,14 @@ mod tests {
                 decision: Decision::AskBefore,
                 match_args: None,
                 reason: Some("test approval".to_string()),
+                rate_limit: None,
+                time_window: None,
+                compiled: Default::default(),
             }],
             default: Decision::AlwaysAllow,
             default_reason: None,
             overlay: None,
+            label: None,
         }));
 
         let request = NativeAgentRequest {
@@ -1112,16 +1143,19 @@ mod tests {
                         id: "tu_read_a".to_string(),
                         name: "read".to_string(),
                         input: serde_json::json!({ "path": "a.txt" }),
+                        thought_signature: None,
                     },
                     ContentBlock::ToolUse {
                         id: "tu_edit".to_string(),
                         name: "edit".to_string(),
                         input: serde_json::json!({ "path": "b.txt", "content": "x" }),
+                        thought_signature: None,
                     },
                     ContentBlock::ToolUse {
                         id: "tu_read_c".to_string(),
                         name: "read".to_string(),
                         input: serde_json::json!({ "path": "c.txt" }),
+                        thought_signature: None,
                     },
                 ];
                 let tool_calls = vec![
@@ -1229,10 +1263,14 @@ mod tests {
                 decision: Decision::AskBefore,
                 match_args: None,
                 reason: Some("write tool requires approval".to_string()),
+                rate_limit: None,
+                time_window: None,
+                compiled: Default::default(),
             }],
             default: Decision::AlwaysAllow,
             default_reason: None,
             overlay: None,
+            label: None,
         }));
 
         let request = NativeAgentRequest {
@@ -345,6 +345,12 @@ impl RunSpan {
         if let Some(model) = record.args.get("model").and_then(|v| v.as_str()) {
             attrs.push(KeyValue::new("gen_ai.request.model", model.to_string()));
         }
+        // The route leg that actually answered (a fallback chain may have
+        // moved past the requested model).
+        if let Some(served) = &record.served_by {
+            attrs.push(KeyValue::new("gen_ai.response.model", served.model.clone()));
+            attrs.push(KeyValue::new("gen_ai.system", served.provider.clone()));
+        }
         // Mirror the prompt's content-addressed request digest (already in the
         // call-log args) onto the span: a stable join key for "the same prompt
         // across runs" in a backend's attribute/SQL layer.
@@ -375,6 +381,12 @@ impl RunSpan {
             if let Some(read) = usage.cache_read_tokens {
                 attrs.push(KeyValue::new("gen_ai.usage.cache_read_tokens", read as i64));
             }
+            if let Some(reasoning) = usage.reasoning_tokens {
+                attrs.push(KeyValue::new(
+                    "gen_ai.usage.reasoning_tokens",
+                    reasoning as i64,
+                ));
+            }
             self.total_input_tokens
                 .fetch_add(usage.input_tokens, Ordering::Relaxed);
             self.total_output_tokens
@@ -755,6 +767,7 @@ mod tests {
             token_usage: None,
             timestamp: Utc::now(),
             error: None,
+            served_by: None,
         };
 
         assert_eq!(span_name_for(&record), "tool.call");
@@ -772,6 +785,7 @@ mod tests {
             token_usage: None,
             timestamp: Utc::now(),
             error: None,
+            served_by: None,
         };
         let mut attrs = Vec::new();
 
@@ -806,6 +820,7 @@ mod tests {
             token_usage: None,
             timestamp: Utc::now(),
             error: None,
+            served_by: None,
         };
         let mut attrs = Vec::new();
         append_signal_attributes(&mut attrs, &record);
@@ -830,6 +845,7 @@ mod tests {
             token_usage: None,
             timestamp: Utc::now(),
             error: None,
+            served_by: None,
         };
         let mut attrs = Vec::new();
         append_signal_attributes(&mut attrs, &timeout);
@@ -952,6 +968,7 @@ mod tests {
             token_usage: None,
             timestamp: Utc::now(),
             error: None,
+            served_by: None,
         }
     }
 
@@ -1048,6 +1065,7 @@ mod tests {
             output_tokens: 7,
             cache_creation_tokens: None,
             cache_read_tokens: None,
+            reasoning_tokens: None,
         });
 
         let spans = emit_and_collect(std::slice::from_ref(&r));
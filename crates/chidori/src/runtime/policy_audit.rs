@@ -0,0 +1,297 @@
+//! Policy decision audit log — why each gated call was allowed or refused.
+//!
+//! The call log records what a run *did*; it says nothing about calls a
+//! policy refused, and nothing about which rule let the rest through. Every
+//! live policy gate (`http`, `tool:*`, `workspace:*`, `app_data:*`; not the
+//! cost budget) appends one [`PolicyAuditEntry`] to `policy/audit.jsonl` in
+//! the run's store: the target, the call args with secrets and credential-shaped
+//! fields redacted, the deciding layer and rule, and how the call resolved —
+//! including the operator's answer when the rule asked.
+//!
+//! Replayed calls are not re-evaluated (policy gates guard live effects), so
+//! a resumed run appends only the decisions it makes after the replay
+//! frontier; the log is the union over every leg of the run. Writes are
+//! best-effort: an audit failure warns and never fails the gated call.
+//! Served by `GET /sessions/{id}/audit`.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::policy::{Decision, PolicyVerdict};
+use crate::runtime::context::RuntimeContext;
+use crate::runtime::secret_env::SecretStore;
+use crate::runtime::store::RunStore;
+
+/// Append-only audit stream, one JSON [`PolicyAuditEntry`] per line.
+pub const POLICY_AUDIT_FILE: &str = "policy/audit.jsonl";
+
+/// Longest string arg kept verbatim; longer values (request bodies, file
+/// contents) are cut so the audit log stays a log.
+const MAX_ARG_STRING: usize = 512;
+
+/// Argument keys whose values are withheld regardless of content.
+const SENSITIVE_KEYS: &[&str] = &[
+    "authorization",
+    "api_key",
+    "apikey",
+    "password",
+    "secret",
+    "token",
+    "cookie",
+];
+
+/// One evaluated policy decision.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolicyAuditEntry {
+    pub timestamp: DateTime<Utc>,
+    pub run_id: String,
+    /// Seq of the call the decision gated.
+    pub seq: u64,
+    pub target: String,
+    /// The call args, redacted (see [`redact_args`]).
+    pub args: Value,
+    pub decision: Decision,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Policy layer that decided: 0 is the base policy, 1.. the session
+    /// profiles layered over it.
+    pub layer: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layer_label: Option<String>,
+    /// Deciding rule index within the layer; absent when the layer's
+    /// `default` decided.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<usize>,
+    /// How the call resolved: `allowed`, `denied`, `approved`,
+    /// `operator_denied`, `paused` (parked for a server approval),
+    /// `unavailable` (asked with nobody to answer), and — appended when the
+    /// operator answers a parked approval — `operator_allowed`.
+    pub outcome: String,
+    /// Who approved an `ask_before` call: `approval_cache`, `auto_approve`,
+    /// `terminal`, `terminal_all` or `server`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval: Option<String>,
+}
+
+impl PolicyAuditEntry {
+    /// An entry for the call at `seq` in the run under `ctx`, args redacted.
+    pub fn for_call(
+        ctx: &RuntimeContext,
+        seq: u64,
+        target: &str,
+        args: &Value,
+        verdict: &PolicyVerdict,
+        outcome: &str,
+    ) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            run_id: ctx.run_id(),
+            seq,
+            target: target.to_string(),
+            args: redact_args(args),
+            decision: verdict.decision,
+            reason: verdict.reason.clone(),
+            layer: verdict.layer,
+            layer_label: verdict.layer_label.clone(),
+            rule: verdict.rule,
+            outcome: outcome.to_string(),
+            approval: None,
+        }
+    }
+
+    pub fn with_approval(mut self, via: &str) -> Self {
+        self.approval = Some(via.to_string());
+        self
+    }
+}
+
+/// Strip what must not land in a durable log: values of known secrets
+/// (through the run's [`SecretStore`]), credential-named fields, and the
+/// tail of oversized strings.
+pub fn redact_args(args: &Value) -> Value {
+    let mut args = args.clone();
+    SecretStore::global().redact_value(&mut args);
+    scrub(&mut args);
+    args
+}
+
+fn scrub(value: &mut Value) {
+    match value {
+        Value::String(text) if text.len() > MAX_ARG_STRING => {
+            let mut cut = MAX_ARG_STRING;
+            while !text.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            let dropped = text.len() - cut;
+            text.truncate(cut);
+            text.push_str(&format!("…[{dropped} bytes truncated]"));
+        }
+        Value::Array(items) => items.iter_mut().for_each(scrub),
+        Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                if is_sensitive_key(key) {
+                    *item = Value::String("[REDACTED]".to_string());
+                } else {
+                    scrub(item);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Whether a field name names a credential: one of [`SENSITIVE_KEYS`] as the
+/// whole key or as its last `_`/`-`-delimited part (`token`, `access_token`,
+/// `X-Api-Key`, `accessToken`). Matching whole parts keeps counters like
+/// `max_tokens` and `input_tokens` in the log.
+fn is_sensitive_key(key: &str) -> bool {
+    let mut normalized = String::with_capacity(key.len() + 4);
+    let mut prev_lower = false;
+    for ch in key.chars() {
+        if ch.is_ascii_uppercase() && prev_lower {
+            normalized.push('_');
+        }
+        prev_lower = ch.is_ascii_lowercase() || ch.is_ascii_digit();
+        normalized.push(if ch == '-' {
+            '_'
+        } else {
+            ch.to_ascii_lowercase()
+        });
+    }
+    SENSITIVE_KEYS.iter().any(|term| {
+        normalized == *term
+            || normalized
+                .strip_suffix(term)
+                .is_some_and(|head| head.ends_with('_'))
+    })
+}
+
+/// Append one entry to the run's audit stream. Best-effort: a run without a
+/// store (embedded, tests) records nothing, and write failures only warn.
+pub fn record(ctx: &RuntimeContext, entry: &PolicyAuditEntry) {
+    crate::runtime::metrics::inc(
+        crate::runtime::metrics::POLICY_DECISIONS,
+        &[("outcome", &entry.outcome)],
+        1.0,
+    );
+    let Some(store) = ctx.store() else {
+        return;
+    };
+    if let Err(err) = append(store.as_ref(), entry) {
+        tracing::warn!("policy audit: {err:#}");
+    }
+}
+
+pub fn append(store: &dyn RunStore, entry: &PolicyAuditEntry) -> Result<()> {
+    store
+        .append_blob_line(POLICY_AUDIT_FILE, &serde_json::to_vec(entry)?)
+        .with_context(|| format!("appending {POLICY_AUDIT_FILE}"))
+}
+
+/// Record the operator's answer to a parked approval (`POST
+/// /sessions/{id}/approve`) against the `paused` entry the gate wrote, so
+/// the answer carries that entry's layer and rule. Approvals that never went
+/// through a policy gate (the cost budget) have no such entry and are not
+/// audited.
+pub fn record_operator_answer(
+    store: &dyn RunStore,
+    target: &str,
+    args: &Value,
+    allowed: bool,
+) -> Result<()> {
+    let args = redact_args(args);
+    let Some(paused) = load(store)?
+        .into_iter()
+        .rev()
+        .find(|e| e.outcome == "paused" && e.target == target && e.args == args)
+    else {
+        return Ok(());
+    };
+    let outcome = if allowed {
+        "operator_allowed"
+    } else {
+        "operator_denied"
+    };
+    let entry = PolicyAuditEntry {
+        timestamp: Utc::now(),
+        outcome: outcome.to_string(),
+        approval: Some("server".to_string()),
+        ..paused
+    };
+    crate::runtime::metrics::inc(
+        crate::runtime::metrics::POLICY_DECISIONS,
+        &[("outcome", outcome)],
+        1.0,
+    );
+    append(store, &entry)
+}
+
+/// Load a run's audit stream, oldest first. A run that never hit a policy
+/// gate loads as empty; a crash-truncated trailing line is skipped.
+pub fn load(store: &dyn RunStore) -> Result<Vec<PolicyAuditEntry>> {
+    let Some(bytes) = store.get_blob(POLICY_AUDIT_FILE)? else {
+        return Ok(Vec::new());
+    };
+    let mut entries = Vec::new();
+    for line in bytes.split(|b| *b == b'\n') {
+        if line.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        match serde_json::from_slice::<PolicyAuditEntry>(line) {
+            Ok(entry) => entries.push(entry),
+            Err(err) => tracing::warn!("skipping unreadable policy audit line: {err}"),
+        }
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn redaction_withholds_credential_fields_and_truncates_long_strings() {
+        let body = "x".repeat(MAX_ARG_STRING + 10);
+        let redacted = redact_args(&json!({
+            "url": "https://api.example.com/v1",
+            "headers": { "Authorization": "Bearer abc", "Accept": "text/plain" },
+            "body": body,
+        }));
+        assert_eq!(redacted["url"], "https://api.example.com/v1");
+        assert_eq!(redacted["headers"]["Authorization"], "[REDACTED]");
+        assert_eq!(redacted["headers"]["Accept"], "text/plain");
+        let body = redacted["body"].as_str().unwrap();
+        assert!(body.ends_with("…[10 bytes truncated]"), "{body}");
+    }
+
+    #[test]
+    fn redaction_matches_whole_key_parts_only() {
+        let redacted = redact_args(&json!({
+            "token": "t",
+            "access_token": "a",
+            "id_token": "i",
+            "accessToken": "c",
+            "X-Api-Key": "k",
+            "client_secret": "s",
+            "max_tokens": 512,
+            "input_tokens": 10,
+            "tokenizer": "cl100k",
+        }));
+        for key in [
+            "token",
+            "access_token",
+            "id_token",
+            "accessToken",
+            "X-Api-Key",
+            "client_secret",
+        ] {
+            assert_eq!(redacted[key], "[REDACTED]", "{key}");
+        }
+        assert_eq!(redacted["max_tokens"], 512);
+        assert_eq!(redacted["input_tokens"], 10);
+        assert_eq!(redacted["tokenizer"], "cl100k");
+    }
+}
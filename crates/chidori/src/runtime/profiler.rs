@@ -0,0 +1,613 @@
+//! CPU profiles of agent JavaScript (`chidori run --profile`).
+//!
+//! Nothing samples. A [`TraceObserver`] on each engine of the run sees every
+//! activation enter, exit, suspend and resume, and what passed between two
+//! notifications — wall time, or with [`ProfileWeight::Instructions`] the
+//! ops counted down on [`chidori_js::Vm::op_meter`] — is charged to the
+//! activation on top of the stack then. Host calls sit on that stack as
+//! synthetic `(host: <op>)` frames, so waiting on a model or a tool shows
+//! apart from the code that made the call, and an agent or tool file run by
+//! the call nests under it. Functions are placed at their `.ts` line through
+//! the module's source map.
+//!
+//! The profile is written as a Chrome DevTools `.cpuprofile` (one sample per
+//! charged stretch, its time delta the weight) or as folded stacks for
+//! flamegraph tools.
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use chidori_js::{TraceEnter, TraceObserver};
+use serde_json::{json, Value};
+
+use super::rust_engine::RunHost;
+use super::typescript::transpile::ModuleMap;
+
+/// What a profile's samples weigh.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileWeight {
+    /// Wall time, in microseconds.
+    Time,
+    /// Interpreter ops, in the op budget's units. Deterministic: a replay
+    /// profiles the same. Written as a microsecond each, and host calls
+    /// weigh nothing.
+    Instructions,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileFormat {
+    /// Chrome DevTools `.cpuprofile` JSON.
+    CpuProfile,
+    /// `frame;frame;frame weight` lines (flamegraph.pl, inferno, speedscope).
+    Folded,
+}
+
+/// A frame of the call tree, as `.cpuprofile` describes one.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct CallFrame {
+    name: String,
+    /// `file://` URL of the source, empty when not known.
+    url: String,
+    /// 0-based; -1 when not known.
+    line: i64,
+    column: i64,
+}
+
+impl CallFrame {
+    fn synthetic(name: &str) -> CallFrame {
+        CallFrame {
+            name: name.to_string(),
+            url: String::new(),
+            line: -1,
+            column: -1,
+        }
+    }
+
+    /// `name (file.ts:line)`, for folded stacks.
+    fn label(&self) -> String {
+        let name = self.name.replace(';', ":");
+        match self.url.rsplit('/').next() {
+            Some(file) if !file.is_empty() && self.line >= 0 => {
+                format!("{name} ({file}:{})", self.line + 1)
+            }
+            _ => name,
+        }
+    }
+}
+
+struct Node {
+    frame: CallFrame,
+    children: Vec<usize>,
+    /// Weight charged to the node itself.
+    weight: u64,
+    hits: u64,
+}
+
+enum Entry {
+    Js { token: u64, node: usize },
+    Host { node: usize },
+}
+
+const ROOT: usize = 0;
+/// Where weight goes with no frame on the stack: engine setup, the job
+/// loop, the host between runs.
+const PROGRAM: usize = 1;
+
+struct Recorder {
+    nodes: Vec<Node>,
+    child: HashMap<(usize, CallFrame), usize>,
+    stack: Vec<Entry>,
+    /// Frames of the live activations, suspended ones included, by token.
+    frames: HashMap<u64, CallFrame>,
+    next_token: u64,
+    /// `(node, weight)` per charged stretch; consecutive ones on a node merge.
+    samples: Vec<(usize, u64)>,
+    /// Module key → its map, `None` for modules without one.
+    maps: HashMap<String, Option<ModuleMap>>,
+    /// Sources to map instead of the file on disk (entry modules).
+    sources: HashMap<String, String>,
+    last: Instant,
+    /// Nanoseconds not yet charged as a whole microsecond.
+    carry_nanos: u128,
+}
+
+impl Recorder {
+    fn node(&mut self, parent: usize, frame: CallFrame) -> usize {
+        if let Some(&node) = self.child.get(&(parent, frame.clone())) {
+            return node;
+        }
+        let node = self.nodes.len();
+        self.nodes.push(Node {
+            frame: frame.clone(),
+            children: Vec::new(),
+            weight: 0,
+            hits: 0,
+        });
+        self.nodes[parent].children.push(node);
+        self.child.insert((parent, frame), node);
+        node
+    }
+
+    fn top(&self) -> Option<usize> {
+        self.stack.last().map(|entry| match entry {
+            Entry::Js { node, .. } | Entry::Host { node } => *node,
+        })
+    }
+
+    fn push(&mut self, frame: CallFrame) -> usize {
+        let parent = self.top().unwrap_or(ROOT);
+        self.node(parent, frame)
+    }
+
+    fn charge(&mut self, weight: u64) {
+        if weight == 0 {
+            return;
+        }
+        let node = self.top().unwrap_or(PROGRAM);
+        self.nodes[node].weight += weight;
+        match self.samples.last_mut() {
+            Some((last, total)) if *last == node => *total += weight,
+            _ => {
+                self.nodes[node].hits += 1;
+                self.samples.push((node, weight));
+            }
+        }
+    }
+
+    fn charge_time(&mut self) {
+        let now = Instant::now();
+        let nanos = now.duration_since(self.last).as_nanos() + self.carry_nanos;
+        self.last = now;
+        self.carry_nanos = nanos % 1000;
+        self.charge((nanos / 1000) as u64);
+    }
+
+    fn remove(&mut self, token: u64) {
+        if let Some(i) = self
+            .stack
+            .iter()
+            .rposition(|entry| matches!(entry, Entry::Js { token: t, .. } if *t == token))
+        {
+            self.stack.remove(i);
+        }
+    }
+
+    /// Where `info`'s function is: its `.ts` position when its module maps.
+    fn frame_of(&mut self, info: &TraceEnter<'_>) -> CallFrame {
+        let name = if info.name.is_empty() {
+            "(anonymous)"
+        } else {
+            info.name
+        };
+        let mut frame = CallFrame::synthetic(name);
+        let Some(label) = info.label else {
+            return frame;
+        };
+        let sources = &self.sources;
+        let map = self.maps.entry(label.to_string()).or_insert_with(|| {
+            ModuleMap::load(Path::new(label), sources.get(label).map(String::as_str))
+        });
+        if let Some(map) = map {
+            frame.url = format!("file://{}", map.path.display());
+            if let Some((line, column)) = map.original(info.source_start) {
+                frame.line = i64::from(line) - 1;
+                frame.column = i64::from(column) - 1;
+            }
+        }
+        frame
+    }
+}
+
+/// The profile of one run, fed by the observer and host of each engine it
+/// drives ([`Profiler::observe`], [`Profiler::wrap_host`]).
+pub struct Profiler {
+    weight: ProfileWeight,
+    started: SystemTime,
+    recorder: Mutex<Recorder>,
+}
+
+impl std::fmt::Debug for Profiler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Profiler")
+            .field("weight", &self.weight)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Profiler {
+    pub fn new(weight: ProfileWeight) -> Arc<Profiler> {
+        let mut recorder = Recorder {
+            nodes: Vec::new(),
+            child: HashMap::new(),
+            stack: Vec::new(),
+            frames: HashMap::new(),
+            next_token: 0,
+            samples: Vec::new(),
+            maps: HashMap::new(),
+            sources: HashMap::new(),
+            last: Instant::now(),
+            carry_nanos: 0,
+        };
+        recorder.nodes.push(Node {
+            frame: CallFrame::synthetic("(root)"),
+            children: Vec::new(),
+            weight: 0,
+            hits: 0,
+        });
+        recorder.node(ROOT, CallFrame::synthetic("(program)"));
+        Arc::new(Profiler {
+            weight,
+            started: SystemTime::now(),
+            recorder: Mutex::new(recorder),
+        })
+    }
+
+    /// The trace observer for an engine of the run. `entry` is the key and
+    /// `.ts` source of its entry module, which is what its lines map against.
+    /// An instruction-weighted profile needs the VM's op budget to count
+    /// down, so installs an unlimited one when there is none.
+    pub(crate) fn observe(
+        self: &Arc<Self>,
+        vm: &mut chidori_js::Vm,
+        entry: (&str, &str),
+    ) -> Box<dyn TraceObserver> {
+        self.recorder
+            .lock()
+            .unwrap()
+            .sources
+            .insert(entry.0.to_string(), entry.1.to_string());
+        let meter = match self.weight {
+            ProfileWeight::Time => None,
+            ProfileWeight::Instructions => {
+                let left = *vm.op_budget.get_or_insert(u64::MAX);
+                let meter = Rc::new(Cell::new(left));
+                vm.op_meter = Some(meter.clone());
+                Some(meter)
+            }
+        };
+        Box::new(ProfileObserver {
+            profiler: self.clone(),
+            last_ops: meter.as_ref().map_or(0, |meter| meter.get()),
+            meter,
+        })
+    }
+
+    /// `host`, with each call on the profiled stack while it is in flight.
+    pub(crate) fn wrap_host(self: &Arc<Self>, host: Rc<dyn RunHost>) -> Rc<dyn RunHost> {
+        Rc::new(ProfiledHost {
+            inner: host,
+            profiler: self.clone(),
+        })
+    }
+
+    /// Charge the time since the last notification, when weighing by time.
+    fn lock(&self) -> MutexGuard<'_, Recorder> {
+        let mut recorder = self.recorder.lock().unwrap();
+        if self.weight == ProfileWeight::Time {
+            recorder.charge_time();
+        }
+        recorder
+    }
+
+    pub fn write(&self, path: &Path, format: ProfileFormat) -> Result<()> {
+        let text = match format {
+            ProfileFormat::CpuProfile => serde_json::to_string(&self.cpuprofile())?,
+            ProfileFormat::Folded => self.folded(),
+        };
+        std::fs::write(path, text).with_context(|| format!("writing {}", path.display()))
+    }
+
+    /// The profile as Chrome DevTools `.cpuprofile` JSON.
+    pub fn cpuprofile(&self) -> Value {
+        let recorder = self.recorder.lock().unwrap();
+        let mut scripts: HashMap<&str, usize> = HashMap::new();
+        let nodes: Vec<Value> = recorder
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| {
+                let script = match node.frame.url.as_str() {
+                    "" => 0,
+                    url => {
+                        let next = scripts.len() + 1;
+                        *scripts.entry(url).or_insert(next)
+                    }
+                };
+                json!({
+                    "id": i + 1,
+                    "callFrame": {
+                        "functionName": node.frame.name,
+                        "scriptId": script.to_string(),
+                        "url": node.frame.url,
+                        "lineNumber": node.frame.line,
+                        "columnNumber": node.frame.column,
+                    },
+                    "hitCount": node.hits,
+                    "children": node.children.iter().map(|c| c + 1).collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        let start = match self.weight {
+            ProfileWeight::Time => self
+                .started
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_micros() as u64),
+            ProfileWeight::Instructions => 0,
+        };
+        let total: u64 = recorder.samples.iter().map(|(_, w)| w).sum();
+        json!({
+            "nodes": nodes,
+            "startTime": start,
+            "endTime": start + total,
+            "samples": recorder.samples.iter().map(|(node, _)| node + 1).collect::<Vec<_>>(),
+            "timeDeltas": recorder.samples.iter().map(|(_, w)| w).collect::<Vec<_>>(),
+        })
+    }
+
+    /// The profile as folded stacks, one line per stack with weight of its own.
+    pub fn folded(&self) -> String {
+        let recorder = self.recorder.lock().unwrap();
+        let mut lines = Vec::new();
+        let mut path = Vec::new();
+        fold(&recorder.nodes, ROOT, &mut path, &mut lines);
+        lines.sort();
+        let mut out = String::new();
+        for (stack, weight) in lines {
+            let _ = writeln!(out, "{stack} {weight}");
+        }
+        out
+    }
+}
+
+fn fold(nodes: &[Node], node: usize, path: &mut Vec<String>, out: &mut Vec<(String, u64)>) {
+    if node != ROOT {
+        path.push(nodes[node].frame.label());
+        if nodes[node].weight > 0 {
+            out.push((path.join(";"), nodes[node].weight));
+        }
+    }
+    for &child in &nodes[node].children {
+        fold(nodes, child, path, out);
+    }
+    if node != ROOT {
+        path.pop();
+    }
+}
+
+/// One engine's view onto the run's profile.
+struct ProfileObserver {
+    profiler: Arc<Profiler>,
+    /// The engine's op budget countdown, for an instruction-weighted profile.
+    meter: Option<Rc<Cell<u64>>>,
+    last_ops: u64,
+}
+
+impl ProfileObserver {
+    /// The recorder, with what ran since the last notification charged.
+    fn charged(&mut self) -> MutexGuard<'_, Recorder> {
+        let mut recorder = self.profiler.lock();
+        if let Some(meter) = &self.meter {
+            // A budget installed or reset since only resynchronizes.
+            let left = meter.get();
+            recorder.charge(self.last_ops.saturating_sub(left));
+            self.last_ops = left;
+        }
+        recorder
+    }
+}
+
+impl TraceObserver for ProfileObserver {
+    fn on_enter(&mut self, info: TraceEnter<'_>) -> u64 {
+        let mut recorder = self.charged();
+        let frame = recorder.frame_of(&info);
+        let token = recorder.next_token;
+        recorder.next_token += 1;
+        let node = recorder.push(frame.clone());
+        recorder.frames.insert(token, frame);
+        recorder.stack.push(Entry::Js { token, node });
+        token
+    }
+
+    fn on_exit(&mut self, token: u64, _threw: bool) {
+        let mut recorder = self.charged();
+        recorder.remove(token);
+        recorder.frames.remove(&token);
+    }
+
+    fn on_suspend(&mut self, token: u64) {
+        self.charged().remove(token);
+    }
+
+    fn on_resume(&mut self, token: u64) {
+        let mut recorder = self.charged();
+        // A resumed activation runs under whatever resumed it: the job loop,
+        // usually.
+        if let Some(frame) = recorder.frames.get(&token).cloned() {
+            let node = recorder.push(frame);
+            recorder.stack.push(Entry::Js { token, node });
+        }
+    }
+}
+
+/// The run's host, with each call a `(host: <op>)` frame while in flight.
+struct ProfiledHost {
+    inner: Rc<dyn RunHost>,
+    profiler: Arc<Profiler>,
+}
+
+impl RunHost for ProfiledHost {
+    fn call(&self, op: &str, args: &Value) -> std::result::Result<Value, String> {
+        if op == "__module_load" {
+            return self.inner.call(op, args);
+        }
+        {
+            let mut recorder = self.profiler.lock();
+            let name = super::debugger::host_op_name(op);
+            let node = recorder.push(CallFrame::synthetic(&format!("(host: {name})")));
+            recorder.stack.push(Entry::Host { node });
+        }
+        // Released across the call: the host may run agent code that is
+        // profiled too.
+        let result = self.inner.call(op, args);
+        let mut recorder = self.profiler.lock();
+        if let Some(i) = recorder
+            .stack
+            .iter()
+            .rposition(|entry| matches!(entry, Entry::Host { .. }))
+        {
+            recorder.stack.truncate(i);
+        }
+        result
+    }
+
+    fn prelude(&self) -> Option<String> {
+        self.inner.prelude()
+    }
+
+    fn trace_sink(&self, js: &str) -> Option<Box<dyn TraceObserver>> {
+        self.inner.trace_sink(js)
+    }
+
+    fn image_ctx(&self) -> Option<super::context::RuntimeContext> {
+        self.inner.image_ctx()
+    }
+
+    fn entrypoint_overlay(&self) -> Option<&'static str> {
+        self.inner.entrypoint_overlay()
+    }
+
+    fn debug_ctx(&self) -> Option<super::context::RuntimeContext> {
+        self.inner.debug_ctx()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ProfileFormat, ProfileWeight, Profiler};
+    use std::sync::{Arc, Mutex as StdMutex};
+
+    use crate::mcp::McpManager;
+    use crate::policy::{PolicyCache, PolicyConfig};
+    use crate::providers::ProviderRegistry;
+    use crate::runtime::context::RuntimeContext;
+    use crate::runtime::rust_engine::run_agent;
+    use crate::runtime::snapshot::RuntimePolicy;
+    use crate::runtime::template::TemplateEngine;
+    use crate::runtime::typescript::bindings::HostBindingBackend;
+    use crate::tools::ToolRegistry;
+
+    const AGENT: &str = "function busy(n: number): number {\n  let t = 0;\n  for (let i = 0; i < n; i++) t += i % 7;\n  return t;\n}\n\nexport async function agent() {\n  const t = busy(2000);\n  await chidori.log(`t=${t}`);\n  return t;\n}\n";
+
+    fn profile(weight: ProfileWeight) -> (Arc<Profiler>, std::path::PathBuf, tempfile::TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("agent.ts");
+        std::fs::write(&path, AGENT).unwrap();
+        let profiler = Profiler::new(weight);
+        let ctx = RuntimeContext::new();
+        ctx.set_profiler(profiler.clone());
+        let run_path = path.clone();
+        std::thread::Builder::new()
+            .stack_size(crate::scheduler::JS_THREAD_STACK_BYTES)
+            .spawn(move || {
+                let backend = HostBindingBackend::for_runtime(
+                    ctx,
+                    Arc::new(ProviderRegistry::new()),
+                    Arc::new(TemplateEngine::new(".")),
+                    Arc::new(tokio::runtime::Runtime::new().unwrap()),
+                    PolicyConfig::from_env(),
+                    Arc::new(StdMutex::new(PolicyCache::default())),
+                    RuntimePolicy::durable_default("profiler-test"),
+                    Arc::new(ToolRegistry::new()),
+                    Arc::new(McpManager::new()),
+                );
+                run_agent(&run_path, AGENT, &serde_json::json!({}), &backend)
+            })
+            .unwrap()
+            .join()
+            .unwrap()
+            .unwrap();
+        (profiler, path, dir)
+    }
+
+    #[test]
+    fn instruction_weights_land_on_the_ts_function_that_ran_them() {
+        let (profiler, path, _dir) = profile(ProfileWeight::Instructions);
+        let cpu = profiler.cpuprofile();
+        let nodes = cpu["nodes"].as_array().unwrap();
+        let busy = nodes
+            .iter()
+            .find(|n| n["callFrame"]["functionName"] == "busy")
+            .expect("busy is in the call tree");
+        assert_eq!(busy["callFrame"]["lineNumber"], 0, "{busy}");
+        assert!(busy["callFrame"]["url"]
+            .as_str()
+            .unwrap()
+            .ends_with("agent.ts"));
+        assert!(
+            nodes
+                .iter()
+                .any(|n| n["callFrame"]["functionName"] == "(host: log)"),
+            "the host call is a frame of its own"
+        );
+        let samples = cpu["samples"].as_array().unwrap();
+        let deltas = cpu["timeDeltas"].as_array().unwrap();
+        assert_eq!(samples.len(), deltas.len());
+        let busy_weight: u64 = samples
+            .iter()
+            .zip(deltas)
+            .filter(|(s, _)| **s == busy["id"])
+            .map(|(_, d)| d.as_u64().unwrap())
+            .sum();
+        assert!(busy_weight > 10_000, "the loop's ops: {busy_weight}");
+
+        let folded = profiler.folded();
+        let line = folded
+            .lines()
+            .find(|l| l.contains("busy (agent.ts:1)"))
+            .unwrap_or_else(|| panic!("{folded}"));
+        assert!(
+            line.contains("agent (agent.ts:7);busy (agent.ts:1) "),
+            "{line}"
+        );
+
+        let out = path.with_extension("cpuprofile");
+        profiler.write(&out, ProfileFormat::CpuProfile).unwrap();
+        let written: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&out).unwrap()).unwrap();
+        assert_eq!(written["nodes"][0]["callFrame"]["functionName"], "(root)");
+    }
+
+    #[test]
+    fn time_weights_charge_host_calls_to_their_own_frame() {
+        let (profiler, _path, _dir) = profile(ProfileWeight::Time);
+        let folded = profiler.folded();
+        assert!(
+            folded.lines().all(|l| !l.starts_with("(host: log)")),
+            "the host call is under the function that made it: {folded}"
+        );
+        let cpu = profiler.cpuprofile();
+        let host = cpu["nodes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|n| n["callFrame"]["functionName"] == "(host: log)")
+            .expect("the host call is a frame of its own");
+        let host_weight: u64 = cpu["samples"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .zip(cpu["timeDeltas"].as_array().unwrap())
+            .filter(|(s, _)| **s == host["id"])
+            .map(|(_, d)| d.as_u64().unwrap())
+            .sum();
+        assert!(host_weight > 0, "the host call's wall time: {cpu}");
+        assert!(cpu["startTime"].as_u64().unwrap() > 0);
+        assert!(cpu["endTime"].as_u64() >= cpu["startTime"].as_u64());
+    }
+}
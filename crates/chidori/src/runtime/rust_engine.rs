@@ -415,6 +415,33 @@ pub(crate) trait RunHost {
     fn image_ctx(&self) -> Option<RuntimeContext> {
         None
     }
+
+    /// A script evaluated right after the `run(handler, { inputSchema })`
+    /// wrapper is installed, before the module graph evaluates. `None` — the
+    /// default — leaves `run` as installed; [`DescribeHost`] uses it to swap
+    /// the registered handler for one that reports the schema.
+    fn entrypoint_overlay(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// The run's context when a debugger is attached to it
+    /// ([`crate::runtime::debugger`]). `None` — the default — runs at full
+    /// speed with no hook installed.
+    fn debug_ctx(&self) -> Option<RuntimeContext> {
+        None
+    }
+
+    /// The CPU profile the run feeds ([`crate::runtime::profiler`]). `None`
+    /// — the default — leaves the trace sink to [`RunHost::trace_sink`].
+    fn profiler(&self) -> Option<Arc<crate::runtime::profiler::Profiler>> {
+        None
+    }
+
+    /// The coverage the run feeds ([`crate::runtime::coverage`]). `None` —
+    /// the default — compiles the agent's modules optimized.
+    fn coverage(&self) -> Option<Arc<crate::runtime::coverage::CoverageRecorder>> {
+        None
+    }
 }
 
 /// Route a host op against an in-process [`HostBindingBackend`]. Shared by
@@ -441,6 +468,12 @@ pub(crate) fn route_host_op(
         let (key, source) = load_module_source(specifier, importer)?;
         return Ok(serde_json::json!({ "key": key, "source": source }));
     }
+    if op == crate::runtime::heap_snapshot::SNAPSHOT_OP {
+        let ctx = backend
+            .runtime_ctx()
+            .ok_or("heap snapshot: no runtime context")?;
+        return crate::runtime::heap_snapshot::write_chunk(ctx, args);
+    }
     if op == "__chidori_dom_render" {
         let ctx = backend
             .runtime_ctx()
@@ -502,6 +535,85 @@ impl RunHost for InProcessHost {
     fn image_ctx(&self) -> Option<RuntimeContext> {
         self.backend.runtime_ctx().cloned()
     }
+
+    fn debug_ctx(&self) -> Option<RuntimeContext> {
+        self.backend
+            .runtime_ctx()
+            .filter(|ctx| ctx.debugger().is_some())
+            .cloned()
+    }
+
+    fn profiler(&self) -> Option<Arc<crate::runtime::profiler::Profiler>> {
+        self.backend.runtime_ctx()?.profiler()
+    }
+
+    fn coverage(&self) -> Option<Arc<crate::runtime::coverage::CoverageRecorder>> {
+        self.backend.runtime_ctx()?.coverage()
+    }
+}
+
+/// Replaces `run` so the registered handler reports the JSON Schema of the
+/// `inputSchema` it was registered with (or `null`) instead of running.
+const DESCRIBE_ENTRYPOINT_SCRIPT: &str = r#"
+(() => {
+  const validatingRun = globalThis.run;
+  globalThis.run = function run(_handler, options) {
+    const schema = options && options.inputSchema;
+    return validatingRun(async () => ({
+      inputSchema: schema == null ? null : globalThis.__chidori_json_schema_of(schema),
+    }));
+  };
+})();
+"#;
+
+/// The host a describe-only evaluation runs against: module loads resolve as
+/// usual, every effect is refused. Top-level agent code that performs an
+/// effect therefore fails the describe rather than running it outside a
+/// durable session.
+struct DescribeHost;
+
+impl RunHost for DescribeHost {
+    fn call(&self, op: &str, args: &Value) -> std::result::Result<Value, String> {
+        if op != "__module_load" {
+            return Err(format!(
+                "`{op}` is not available while describing an agent's entrypoint"
+            ));
+        }
+        let field = |key: &str| {
+            args.get(key)
+                .and_then(Value::as_str)
+                .ok_or_else(|| format!("__module_load: missing `{key}`"))
+        };
+        let (key, source) = load_module_source(field("specifier")?, field("importer")?)?;
+        Ok(serde_json::json!({ "key": key, "source": source }))
+    }
+
+    fn prelude(&self) -> Option<String> {
+        None
+    }
+
+    fn entrypoint_overlay(&self) -> Option<&'static str> {
+        Some(DESCRIBE_ENTRYPOINT_SCRIPT)
+    }
+}
+
+/// The JSON Schema an agent declares with `run(handler, { inputSchema })`,
+/// read by evaluating its module graph without invoking the handler. `None`
+/// when the agent registers no schema, or only a Standard Schema validator
+/// that exposes no JSON Schema. Agents that export the legacy `agent`
+/// function have no registered handler and fail to describe.
+pub(crate) fn describe_input_schema(path: &Path, source: &str) -> Result<Option<Value>> {
+    let described = run_module(
+        path,
+        source,
+        "__chidori_describe_entrypoint",
+        &Value::Null,
+        Rc::new(DescribeHost),
+    )?;
+    Ok(described
+        .get("inputSchema")
+        .filter(|schema| schema.is_object())
+        .cloned())
 }
 
 /// Resource limits applied to every rust-engine agent run, read from the
@@ -532,6 +644,10 @@ struct ExecutionLimits {
     /// Enable it only where host effects are known-fast (e.g. confining untrusted
     /// code with a short hard limit).
     deadline: Option<Duration>,
+    /// Snapshot the heap when the memory cap stops the run, into the run's
+    /// `heap/oom.heapsnapshot` (see [`crate::runtime::heap_snapshot`]). Env
+    /// `CHIDORI_HEAP_SNAPSHOT_ON_OOM` (`chidori run --heap-snapshot-on-oom`).
+    heap_snapshot_on_oom: bool,
 }
 
 impl ExecutionLimits {
@@ -555,11 +671,14 @@ impl ExecutionLimits {
         };
         let poll_interval =
             Duration::from_millis(env_u64("CHIDORI_JS_MEM_POLL_MS").unwrap_or(10).max(1));
+        let heap_snapshot_on_oom = std::env::var("CHIDORI_HEAP_SNAPSHOT_ON_OOM")
+            .is_ok_and(|v| !matches!(v.trim(), "" | "0" | "false" | "off"));
         ExecutionLimits {
             op_budget,
             mem_cap,
             poll_interval,
             deadline,
+            heap_snapshot_on_oom,
         }
     }
 }
@@ -571,6 +690,9 @@ impl ExecutionLimits {
 /// run or leaks a thread.
 struct ExecutionGuard {
     done: Arc<AtomicBool>,
+    /// Set by the watchdog when it is the memory cap that tripped the VM.
+    mem_tripped: Arc<AtomicBool>,
+    heap_snapshot_on_oom: bool,
     watchdog: Option<JoinHandle<()>>,
     /// Keeps the per-run allocation meter registered on the run thread for the
     /// lifetime of the run. Declared after `watchdog` is irrelevant — `Drop`
@@ -596,10 +718,12 @@ impl ExecutionGuard {
             .map(|_| crate::mem_guard::RunMeterGuard::install());
 
         let done = Arc::new(AtomicBool::new(false));
+        let mem_tripped = Arc::new(AtomicBool::new(false));
         // Only spend a thread when there is something time- or memory-based to
         // watch; the opcode budget is enforced inline by the VM and needs none.
         let watchdog = if limits.mem_cap.is_some() || limits.deadline.is_some() {
             let done_w = done.clone();
+            let mem_tripped = mem_tripped.clone();
             let deadline_at = limits.deadline.map(|d| Instant::now() + d);
             let mem_cap = limits.mem_cap;
             let meter = meter_guard.as_ref().map(|g| g.handle());
@@ -616,6 +740,7 @@ impl ExecutionGuard {
                 }
                 if let (Some(cap), Some(meter)) = (mem_cap, meter.as_ref()) {
                     if crate::mem_guard::run_meter_bytes(meter) > cap {
+                        mem_tripped.store(true, Ordering::Relaxed);
                         interrupt.store(true, Ordering::Relaxed);
                         return;
                     }
@@ -627,10 +752,44 @@ impl ExecutionGuard {
         };
         ExecutionGuard {
             done,
+            mem_tripped,
+            heap_snapshot_on_oom: limits.heap_snapshot_on_oom && limits.mem_cap.is_some(),
             watchdog,
             _meter: meter_guard,
         }
     }
+
+    /// With `CHIDORI_HEAP_SNAPSHOT_ON_OOM`, snapshot the heap through `host`
+    /// when the memory cap interrupts the run — before the unwind drops the
+    /// running frames. The returned cell receives the report for the run's
+    /// error.
+    fn snapshot_on_oom(
+        &self,
+        vm: &mut chidori_js::Vm,
+        host: Rc<dyn RunHost>,
+    ) -> Rc<std::cell::RefCell<Option<String>>> {
+        let report = Rc::new(std::cell::RefCell::new(None));
+        if self.heap_snapshot_on_oom {
+            let tripped = self.mem_tripped.clone();
+            let sink = report.clone();
+            vm.on_interrupt = Some(Box::new(move |vm| {
+                if !tripped.load(Ordering::Relaxed) {
+                    return;
+                }
+                let snapshot = vm.heap_snapshot();
+                let note = match crate::runtime::heap_snapshot::ship(
+                    host.as_ref(),
+                    "oom.heapsnapshot",
+                    &snapshot,
+                ) {
+                    Ok(path) => crate::runtime::heap_snapshot::oom_report(&path, &snapshot),
+                    Err(e) => format!("the run exceeded its memory cap; heap snapshot failed: {e}"),
+                };
+                *sink.borrow_mut() = Some(note);
+            }));
+        }
+        report
+    }
 }
 
 impl Drop for ExecutionGuard {
@@ -729,6 +888,29 @@ fn run_module_inner(
         import_policy: TypeScriptImportPolicy::Node,
     };
     let js = transpile_module(path, source, &opts)?;
+    let entry_key = path.to_string_lossy().to_string();
+
+    // Asked before the host is wrapped below: the wrappers answer for the
+    // debugger and the profiler only.
+    let coverage_recorder = host.coverage();
+    // A debugger (`chidori debug`) sees every host call cross, and steps the
+    // debug-compiled agent modules through the hook installed below.
+    let debug = host
+        .debug_ctx()
+        .and_then(|ctx| crate::runtime::debugger::attach(&ctx, (&entry_key, source)));
+    let host = match &debug {
+        Some(debug) => debug.wrap_host(host),
+        None => host,
+    };
+    // A profiled run (`chidori run --profile`) shows each host call as a
+    // frame of its own.
+    let profiler = host.profiler();
+    let host = match &profiler {
+        Some(profiler) => profiler.wrap_host(host),
+        None => host,
+    };
+    // A replay being debugged images its VM as keyframes to rewind to.
+    let keyframing = debug.as_ref().is_some_and(|debug| debug.keyframing());
 
     // Mainline pause imaging (§5.2), off unless `CHIDORI_MAINLINE_IMAGE` says
     // otherwise. `_claim` keeps it to the outermost module of the run; the
@@ -747,6 +929,13 @@ fn run_module_inner(
     if let Some(sink) = host.trace_sink(&js) {
         engine.vm.trace_sink = Some(sink);
     }
+    if let Some(debug) = &debug {
+        engine.vm.debug_hook = Some(debug.hook());
+    }
+    // `--coverage`: the agent's own modules compile with block counters.
+    let coverage = coverage_recorder
+        .as_ref()
+        .map(|recorder| recorder.instrument(&mut engine.vm, (&entry_key, source)));
     // Captured-effect natives (`node:` crypto/fs) + the determinism prelude
     // (process env, TextEncoder/atob, Web Crypto, virtual timers). Installed only
     // when the host exposes a runtime policy — the recorder/metadata backend has
@@ -759,6 +948,36 @@ fn run_module_inner(
         let sync: Rc<dyn Fn(&str, &Value) -> std::result::Result<Value, String>> =
             Rc::new(move |name, args| h.call(name, args));
         engine.install_sync_natives(SYNC_NATIVE_NAMES, sync);
+        // `v8.writeHeapSnapshot`: the graph is walked here, next to the VM, and
+        // shipped to the host in chunks (see `heap_snapshot`). Unnamed
+        // snapshots are numbered in the order this execution takes them, so a
+        // replay of the run names them the same.
+        {
+            let h = host.clone();
+            let global = engine.vm.realm.global.clone();
+            let unnamed = std::cell::Cell::new(0u32);
+            engine.vm.define_method(
+                &global,
+                crate::runtime::heap_snapshot::SNAPSHOT_OP,
+                1,
+                move |vm, _t, args| {
+                    let file = match args.first() {
+                        Some(chidori_js::Value::String(name)) => name.as_str().to_string(),
+                        _ => {
+                            unnamed.set(unnamed.get() + 1);
+                            crate::runtime::heap_snapshot::default_name(unnamed.get())
+                        }
+                    };
+                    let snapshot = vm.heap_snapshot();
+                    match crate::runtime::heap_snapshot::ship(h.as_ref(), &file, &snapshot) {
+                        Ok(path) => Ok(chidori_js::Value::String(
+                            chidori_js::value::JsString::new(&path),
+                        )),
+                        Err(e) => Err(vm.make_error(chidori_js::vm::ErrorKind::Error, &e)),
+                    }
+                },
+            );
+        }
         // `eval_cached`: these setup scripts are evaluated verbatim on every
         // fresh engine (each run, resume re-execution, tool file, sub-agent),
         // so their compile step is memoized per thread; execution — which must
@@ -834,6 +1053,11 @@ fn run_module_inner(
     engine
         .eval_cached(crate::runtime::typescript::helpers::INPUT_SCHEMA_SCRIPT)
         .map_err(|e| anyhow::anyhow!("installing input-schema validation: {e}"))?;
+    if let Some(overlay) = host.entrypoint_overlay() {
+        engine
+            .eval(overlay)
+            .map_err(|e| anyhow::anyhow!("installing entrypoint overlay: {e}"))?;
+    }
 
     // §5.2: from here on the engine is at the state both sides of an image can
     // reproduce for free — fresh realm plus the preludes, effect natives, SDK
@@ -849,9 +1073,10 @@ fn run_module_inner(
             crate::runtime::mainline_image::is_suspendable_pause,
         ));
         engine.vm.mark_image_baseline();
+    } else if keyframing {
+        engine.vm.mark_image_baseline();
     }
 
-    let entry_key = path.to_string_lossy().to_string();
     // Resolve each `(specifier, importer)` to a sibling `.ts`/`.js` file (or, for
     // `node:` specifiers, the synthetic builtin shim) and hand the linker its
     // transpiled ES module source. `node:` shims and vendored packages are pure
@@ -900,26 +1125,46 @@ fn run_module_inner(
     // before any agent code runs, and isolate the host from an engine panic: a
     // bug in the interpreter must surface as an error, not unwind into the server.
     let guard = ExecutionGuard::install(&mut engine.vm);
-    let outcome = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| match &image_ctx {
-        None => ModuleOutcome::Done(engine.run_entrypoint_graph(
-            &entry_key,
-            &js,
-            input,
-            &slot,
-            fallback_export,
-            &mut load,
-        )),
-        Some(ctx) => drive_imaged(
-            &mut engine,
-            ctx,
-            &entry_key,
-            &js,
-            input,
-            &slot,
-            fallback_export,
-            &mut load,
-            allow_image_restore,
-        ),
+    // `--heap-snapshot-on-oom`: a run stopped at its memory cap snapshots the
+    // heap as it unwinds, and names what retained it in the error.
+    let oom_report = guard.snapshot_on_oom(&mut engine.vm, host.clone());
+    // After the limits: an instruction-weighted profile counts down the op
+    // budget they install. It replaces any tracing sink.
+    if let Some(profiler) = &profiler {
+        let sink = profiler.observe(&mut engine.vm, (&entry_key, source));
+        engine.vm.trace_sink = Some(sink);
+    }
+    let outcome = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        match (&image_ctx, &debug) {
+            (None, Some(debug)) if keyframing => ModuleOutcome::Done(debug.drive(
+                &mut engine,
+                &entry_key,
+                &js,
+                input,
+                &slot,
+                fallback_export,
+                &mut load,
+            )),
+            (None, _) => ModuleOutcome::Done(engine.run_entrypoint_graph(
+                &entry_key,
+                &js,
+                input,
+                &slot,
+                fallback_export,
+                &mut load,
+            )),
+            (Some(ctx), _) => drive_imaged(
+                &mut engine,
+                ctx,
+                &entry_key,
+                &js,
+                input,
+                &slot,
+                fallback_export,
+                &mut load,
+                allow_image_restore,
+            ),
+        }
     }));
     // The image has to be taken while the engine is still alive, and only at
     // the quiescent point a suspension leaves behind.
@@ -930,6 +1175,11 @@ fn run_module_inner(
     // a host `serde_json::Value`, and without this every agent run leaks its
     // realm + agent object graph in a long-lived server process.
     engine.vm.dispose();
+    // The counters outlive the heap: the coverage holds the compiled
+    // functions, not their closures.
+    if let (Some(recorder), Some(coverage)) = (&coverage_recorder, &coverage) {
+        recorder.absorb(coverage);
+    }
     let outcome = match outcome {
         Ok(outcome) => outcome,
         Err(panic) => {
@@ -951,10 +1201,16 @@ fn run_module_inner(
                     let _ = crate::runtime::mainline_image::clear(ctx);
                 }
             }
+            let oom_report = oom_report.borrow_mut().take();
             result.map_err(
                 |e| match crate::runtime::errors::RunInterrupt::from_message(&e) {
                     Some(interrupt) => anyhow::Error::new(interrupt),
-                    None => anyhow::anyhow!(js_exception_message(&e)),
+                    None => match oom_report {
+                        Some(report) => {
+                            anyhow::anyhow!("{}\n{report}", js_exception_message(&e))
+                        }
+                        None => anyhow::anyhow!(js_exception_message(&e)),
+                    },
                 },
             )
         }
@@ -1144,6 +1400,7 @@ fn execute_captured_random(
             token_usage: None,
             timestamp: chrono::Utc::now(),
             error: None,
+            served_by: None,
         });
     }
     ctx.note_capability(Capability::CryptoRandom, seq);
@@ -1756,6 +2013,7 @@ mod tests {
                         id: "call-1".to_string(),
                         name: "lookup".to_string(),
                         input: serde_json::json!({ "key": "answer" }),
+                        thought_signature: None,
                     }],
                     tool_calls: vec![ToolCall {
                         id: "call-1".to_string(),
@@ -2069,6 +2327,9 @@ mod tests {
                 decision: crate::policy::Decision::NeverAllow,
                 match_args: None,
                 reason: Some("network disabled in this test".to_string()),
+                rate_limit: None,
+                time_window: None,
+                compiled: Default::default(),
             }],
             ..PolicyConfig::default()
         };
@@ -2293,6 +2554,49 @@ mod tests {
         let _ = std::fs::remove_dir_all(dir);
     }
 
+    #[test]
+    fn describe_input_schema_reads_the_schema_without_running_the_handler() {
+        let dir =
+            std::env::temp_dir().join(format!("chidori-rust-describe-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("agent.ts");
+        let src = r#"
+            import { chidori, run } from "chidori:agent";
+            run(async () => {
+                throw new Error("the handler must not run");
+            }, {
+                inputSchema: { type: "object", properties: { topic: { type: "string" } } },
+            });
+        "#;
+        assert_eq!(
+            describe_input_schema(&path, src).unwrap(),
+            Some(serde_json::json!({
+                "type": "object",
+                "properties": { "topic": { "type": "string" } },
+            }))
+        );
+
+        let bare = r#"
+            import { run } from "chidori:agent";
+            run(async () => ({}));
+        "#;
+        assert_eq!(describe_input_schema(&path, bare).unwrap(), None);
+
+        // A top-level effect is refused, not performed outside a session.
+        let effectful = r#"
+            import { chidori, run } from "chidori:agent";
+            await chidori.log("at load");
+            run(async () => ({}), { inputSchema: { type: "object" } });
+        "#;
+        let err = describe_input_schema(&path, effectful).unwrap_err();
+        assert!(
+            format!("{err:#}").contains("not available while describing"),
+            "{err:#}"
+        );
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
     #[test]
     fn run_input_schema_additional_properties_false_works_without_properties() {
         // `additionalProperties: false` must reject unexpected keys even when
@@ -2747,6 +3051,69 @@ mod tests {
         let _ = std::fs::remove_dir_all(dir);
     }
 
+    #[test]
+    fn context_user_parts_send_media_and_journal_only_hashes() {
+        let _env = PROMPT_ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = std::env::temp_dir().join(format!("chidori-rust-media-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("chart.png"), b"not-really-a-png").unwrap();
+        let src = r#"
+            export async function agent() {
+                const r = await chidori.context()
+                    .user(["what does this show?", { type: "image", path: "chart.png" }])
+                    .prompt({ model: "test-model" });
+                const plain = await chidori.prompt("and this?", {
+                    model: "test-model",
+                    attachments: [{ type: "document", data: "aGVsbG8=", mediaType: "text/plain" }],
+                });
+                return [r.text, plain];
+            }
+        "#;
+        let path = dir.join("agent.ts");
+        std::fs::write(&path, src).unwrap();
+
+        let ctx = RuntimeContext::new();
+        ctx.set_workspace_root(&dir);
+        let requests = Arc::new(StdMutex::new(Vec::new()));
+        let mut providers = crate::providers::ProviderRegistry::new();
+        providers.register(Box::new(SequenceProvider {
+            responses: vec!["a bar chart".to_string(), "a greeting".to_string()],
+            calls: std::sync::atomic::AtomicUsize::new(0),
+            requests: Arc::clone(&requests),
+        }));
+        let backend = context_test_backend(ctx.clone(), providers);
+        let output = run_agent(&path, src, &serde_json::json!({}), &backend).unwrap();
+        assert_eq!(output, serde_json::json!(["a bar chart", "a greeting"]));
+
+        // The provider saw the file resolved to inline base64.
+        let requests = requests.lock().unwrap();
+        let crate::providers::ContentBlock::Image {
+            source: crate::providers::MediaSource::Base64 { media_type, .. },
+        } = &requests[0].messages[0].content[1]
+        else {
+            panic!("image block was not resolved to base64");
+        };
+        assert_eq!(media_type, "image/png");
+        assert!(matches!(
+            requests[1].messages[0].content[1],
+            crate::providers::ContentBlock::Document { .. }
+        ));
+
+        // The journal carries content-hash references, never the bytes.
+        let records = ctx.call_log().into_records();
+        let prompts: Vec<_> = records.iter().filter(|r| r.function == "prompt").collect();
+        assert_eq!(prompts.len(), 2);
+        let image_ref = &prompts[0].args["attachments"][0];
+        assert_eq!(image_ref["type"], "image");
+        assert_eq!(image_ref["bytes"], 16);
+        assert_eq!(image_ref["sha256"].as_str().unwrap().len(), 64);
+        assert_eq!(prompts[1].args["attachments"][0]["type"], "document");
+        let journal = serde_json::to_string(&records).unwrap();
+        assert!(!journal.contains("aGVsbG8="));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
     #[test]
     fn context_conversation_replays_without_provider_calls() {
         let _env = PROMPT_ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
@@ -2782,6 +3149,426 @@ mod tests {
         let _ = std::fs::remove_dir_all(dir);
     }
 
+    const EMBED_AGENT_SRC: &str = r#"
+        export async function agent(input: {}) {
+            const one = await chidori.embed("the cat sat on the mat");
+            const batch = await chidori.embed(["a cat on a mat", "quarterly revenue"], {
+                model: "text-embedding-3-large",
+            });
+            const dot = (a: number[], b: number[]) => a.reduce((s, x, i) => s + x * b[i], 0);
+            return { dims: one.length, closer: dot(one, batch[0]) > dot(one, batch[1]), batch };
+        }
+    "#;
+
+    #[test]
+    fn embed_batches_journal_usage_and_replay_without_a_provider() {
+        let dir = std::env::temp_dir().join(format!("chidori-rust-embed-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("agent.ts");
+        std::fs::write(&path, EMBED_AGENT_SRC).unwrap();
+        let input = serde_json::json!({});
+
+        let live_ctx = RuntimeContext::new();
+        let mut providers = crate::providers::ProviderRegistry::new();
+        providers.register(Box::new(crate::providers::HashEmbeddingProvider::new(64)));
+        let live_backend = context_test_backend(live_ctx.clone(), providers);
+        let output = run_agent(&path, EMBED_AGENT_SRC, &input, &live_backend).unwrap();
+        assert_eq!(output["dims"], 64);
+        assert_eq!(output["closer"], true);
+        assert_eq!(output["batch"].as_array().unwrap().len(), 2);
+
+        // One record per call, not per text; the texts themselves stay out
+        // of the journal, and usage prices against the embedding model.
+        let records = live_ctx.call_log().into_records();
+        let embeds: Vec<_> = records.iter().filter(|r| r.function == "embed").collect();
+        assert_eq!(embeds.len(), 2);
+        assert_eq!(embeds[1].args["model"], "text-embedding-3-large");
+        assert_eq!(embeds[1].args["count"], 2);
+        assert_eq!(embeds[1].token_usage.as_ref().unwrap().input_tokens, 7);
+        assert!(!serde_json::to_string(&records)
+            .unwrap()
+            .contains("quarterly"));
+        assert!(live_ctx.call_log().total_cost_usd() > 0.0);
+
+        let replay_ctx = RuntimeContext::with_replay(records);
+        let replay_backend =
+            context_test_backend(replay_ctx, crate::providers::ProviderRegistry::new());
+        let replayed = run_agent(&path, EMBED_AGENT_SRC, &input, &replay_backend).unwrap();
+        assert_eq!(output, replayed);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    const SEMANTIC_MEMORY_AGENT_SRC: &str = r#"
+        export async function agent(input: {}) {
+            await chidori.memory.upsert("cats", "cats sleep on warm mats", { metadata: { topic: "pets" } });
+            await chidori.memory.upsert("dogs", "dogs fetch sticks in the park", { metadata: { topic: "pets" } });
+            await chidori.memory.upsert("tax", "quarterly tax filing deadlines", { metadata: { topic: "money" } });
+            const hits = await chidori.memory.search("where do cats sleep", { k: 2 });
+            const money = await chidori.memory.search("cats", { filter: { topic: "money" } });
+            return { hits: hits.map((h: any) => h.key), top: hits[0], money: money.map((h: any) => h.key) };
+        }
+    "#;
+
+    #[test]
+    fn semantic_memory_search_replays_recorded_hits_not_the_live_index() {
+        let dir = std::env::temp_dir().join(format!(
+            "chidori-rust-semantic-memory-{}",
+            uuid::Uuid::new_v4()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("agent.ts");
+        std::fs::write(&path, SEMANTIC_MEMORY_AGENT_SRC).unwrap();
+        let input = serde_json::json!({});
+
+        let live_ctx = RuntimeContext::new();
+        live_ctx.set_workspace_root(&dir);
+        let mut providers = crate::providers::ProviderRegistry::new();
+        providers.register(Box::new(crate::providers::HashEmbeddingProvider::new(64)));
+        let live_backend = context_test_backend(live_ctx.clone(), providers);
+        let output = run_agent(&path, SEMANTIC_MEMORY_AGENT_SRC, &input, &live_backend).unwrap();
+        assert_eq!(output["hits"][0], "cats");
+        assert_eq!(output["hits"].as_array().unwrap().len(), 2);
+        assert_eq!(output["top"]["metadata"]["topic"], "pets");
+        assert_eq!(output["money"], serde_json::json!(["tax"]));
+        let index = dir.join(".chidori/memory/default.vectors.sqlite3");
+        assert!(index.exists());
+
+        // Five embeds (three upserts, two queries), each costed, then five
+        // memory records carrying the writes and the hits.
+        let records = live_ctx.call_log().into_records();
+        let count = |f: &str| records.iter().filter(|r| r.function == f).count();
+        assert_eq!(count("embed"), 5);
+        assert_eq!(count("memory"), 5);
+
+        // Wipe the index: replay must serve the journaled hits anyway.
+        std::fs::remove_file(&index).unwrap();
+        let replay_ctx = RuntimeContext::with_replay(records);
+        replay_ctx.set_workspace_root(&dir);
+        let replay_backend =
+            context_test_backend(replay_ctx, crate::providers::ProviderRegistry::new());
+        let replayed =
+            run_agent(&path, SEMANTIC_MEMORY_AGENT_SRC, &input, &replay_backend).unwrap();
+        assert_eq!(output, replayed);
+        assert!(!index.exists());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    const MCP_AGENT_SRC: &str = r#"
+        export async function agent(input: {}) {
+            const summary = await chidori.tool("notes__summarize", { text: "a long report" });
+            const resources = await chidori.mcp.listResources("notes");
+            const contents = await chidori.mcp.readResource("notes", resources[0].uri);
+            const prompt = await chidori.mcp.getPrompt("notes", "greet", { who: "Ada" });
+            return { summary, text: contents[0].text, greeting: prompt.messages[0].content.text };
+        }
+    "#;
+
+    /// Read one HTTP/1.1 request off `stream` and return its body.
+    fn read_http_body(stream: &mut std::net::TcpStream) -> String {
+        use std::io::Read;
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 4096];
+        loop {
+            let n = stream.read(&mut chunk).unwrap_or(0);
+            if n == 0 {
+                break;
+            }
+            buf.extend_from_slice(&chunk[..n]);
+            let text = String::from_utf8_lossy(&buf);
+            if let Some(split) = text.find("\r\n\r\n") {
+                let length = text[..split]
+                    .lines()
+                    .find_map(|line| {
+                        let (name, value) = line.split_once(':')?;
+                        name.eq_ignore_ascii_case("content-length")
+                            .then(|| value.trim().parse::<usize>().ok())?
+                    })
+                    .unwrap_or(0);
+                if buf.len() >= split + 4 + length {
+                    return String::from_utf8_lossy(&buf[split + 4..split + 4 + length])
+                        .into_owned();
+                }
+            }
+        }
+        String::new()
+    }
+
+    /// A Streamable-HTTP MCP server advertising resources and prompts, whose
+    /// `summarize` tool samples from the client before answering: the
+    /// `tools/call` response is an SSE stream that opens with a
+    /// `sampling/createMessage` request and finishes only once the client has
+    /// POSTed its answer back. One thread per connection, so that answer can
+    /// arrive while the tool call's stream is still open.
+    fn spawn_sampling_mcp_server() -> std::net::SocketAddr {
+        use std::io::Write;
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (answer_tx, answer_rx) = std::sync::mpsc::channel::<serde_json::Value>();
+        let answer_rx = Arc::new(StdMutex::new(answer_rx));
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let answer_tx = answer_tx.clone();
+                let answer_rx = answer_rx.clone();
+                std::thread::spawn(move || {
+                    let body = read_http_body(&mut stream);
+                    let message: serde_json::Value =
+                        serde_json::from_str(&body).unwrap_or(serde_json::Value::Null);
+                    let params = message.get("params").cloned().unwrap_or_default();
+                    let result = match message.get("method").and_then(|m| m.as_str()) {
+                        Some("initialize") => serde_json::json!({
+                            "protocolVersion": "2025-03-26",
+                            "capabilities": { "tools": {}, "resources": {}, "prompts": {} },
+                            "serverInfo": { "name": "notes" },
+                        }),
+                        Some("tools/list") => serde_json::json!({ "tools": [{
+                            "name": "summarize",
+                            "inputSchema": { "type": "object", "properties": { "text": { "type": "string" } } },
+                        }]}),
+                        Some("tools/call") => {
+                            let _ = stream.write_all(
+                                b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n",
+                            );
+                            let sampling = serde_json::json!({
+                                "jsonrpc": "2.0",
+                                "id": 77,
+                                "method": "sampling/createMessage",
+                                "params": {
+                                    "messages": [{ "role": "user", "content": {
+                                        "type": "text",
+                                        "text": format!("Summarize: {}", params["arguments"]["text"].as_str().unwrap_or("")),
+                                    }}],
+                                    "systemPrompt": "Be brief.",
+                                    "maxTokens": 50,
+                                    "modelPreferences": { "hints": [{ "name": "claude-haiku" }] },
+                                },
+                            });
+                            let _ = write!(stream, "event: message\ndata: {sampling}\n\n");
+                            let _ = stream.flush();
+                            let answer = answer_rx
+                                .lock()
+                                .unwrap()
+                                .recv_timeout(std::time::Duration::from_secs(20))
+                                .unwrap_or_default();
+                            let text = answer["result"]["content"]["text"]
+                                .as_str()
+                                .unwrap_or("no answer");
+                            let done = serde_json::json!({ "jsonrpc": "2.0", "id": 1, "result": {
+                                "content": [{ "type": "text", "text": format!("summary: {text}") }],
+                            }});
+                            let _ = write!(stream, "event: message\ndata: {done}\n\n");
+                            return;
+                        }
+                        Some("resources/list") => serde_json::json!({
+                            "resources": [{ "uri": "file:///notes.txt", "name": "notes" }],
+                        }),
+                        Some("resources/read") => serde_json::json!({ "contents": [{
+                            "uri": params["uri"],
+                            "mimeType": "text/plain",
+                            "text": "remember the milk",
+                        }]}),
+                        Some("prompts/get") => serde_json::json!({
+                            "description": "greeting",
+                            "messages": [{ "role": "user", "content": {
+                                "type": "text",
+                                "text": format!("Hello {}", params["arguments"]["who"].as_str().unwrap_or("")),
+                            }}],
+                        }),
+                        _ => {
+                            // Our answer to the sampling request, a notification,
+                            // or the client opening its GET stream.
+                            if message.get("result").is_some() || message.get("error").is_some() {
+                                let _ = answer_tx.send(message);
+                            }
+                            let _ = stream.write_all(
+                                b"HTTP/1.1 202 Accepted\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                            );
+                            return;
+                        }
+                    };
+                    let payload =
+                        serde_json::json!({ "jsonrpc": "2.0", "id": 1, "result": result })
+                            .to_string();
+                    let _ = write!(
+                        stream,
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        payload.len(),
+                        payload
+                    );
+                });
+            }
+        });
+        addr
+    }
+
+    fn mcp_test_backend(
+        ctx: RuntimeContext,
+        providers: ProviderRegistry,
+        url: &str,
+    ) -> HostBindingBackend {
+        let rt = Arc::new(tokio::runtime::Runtime::new().unwrap());
+        let mcp = Arc::new(McpManager::new());
+        let cfg: crate::mcp::McpServersConfig = serde_json::from_value(serde_json::json!({
+            "servers": { "notes": { "transport": "http", "url": url } },
+        }))
+        .unwrap();
+        let mut tools = ToolRegistry::new();
+        for def in rt.block_on(mcp.start_from_config(&cfg)).unwrap() {
+            tools.register(def);
+        }
+        HostBindingBackend::for_runtime(
+            ctx,
+            Arc::new(providers),
+            Arc::new(TemplateEngine::new(".")),
+            rt,
+            PolicyConfig::from_env(),
+            Arc::new(StdMutex::new(PolicyCache::default())),
+            RuntimePolicy::durable_default("rust-engine-test"),
+            Arc::new(tools),
+            mcp,
+        )
+    }
+
+    #[test]
+    fn mcp_sampling_nests_under_the_tool_call_and_resources_prompts_replay() {
+        let dir = std::env::temp_dir().join(format!("chidori-rust-mcp-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("agent.ts");
+        std::fs::write(&path, MCP_AGENT_SRC).unwrap();
+        let input = serde_json::json!({});
+        let url = format!("http://{}/mcp", spawn_sampling_mcp_server());
+
+        let live_ctx = RuntimeContext::new();
+        let requests = Arc::new(StdMutex::new(Vec::new()));
+        let mut providers = ProviderRegistry::new();
+        providers.register(Box::new(SequenceProvider {
+            responses: vec!["a short report".to_string()],
+            calls: std::sync::atomic::AtomicUsize::new(0),
+            requests: Arc::clone(&requests),
+        }));
+        let live_backend = mcp_test_backend(live_ctx.clone(), providers, &url);
+        let output = run_agent(&path, MCP_AGENT_SRC, &input, &live_backend).unwrap();
+        assert_eq!(output["summary"], "summary: a short report");
+        assert_eq!(output["text"], "remember the milk");
+        assert_eq!(output["greeting"], "Hello Ada");
+
+        // The server's sampling request reached the provider as-is...
+        let sent = requests.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].system.as_deref(), Some("Be brief."));
+        assert_eq!(sent[0].max_tokens, 50);
+        drop(sent);
+
+        // ...and its prompt record nests under the tool call that caused it.
+        let records = live_ctx.call_log().into_records();
+        let tool = records.iter().find(|r| r.function == "tool").unwrap();
+        let sampled = records.iter().find(|r| r.function == "prompt").unwrap();
+        assert_eq!(sampled.parent_seq, Some(tool.seq));
+        assert_eq!(sampled.args["type"], "mcp_sampling");
+        assert_eq!(sampled.args["mcp_server"], "notes");
+        assert_eq!(sampled.args["model_hints"][0], "claude-haiku");
+        let mcp_calls: Vec<_> = records.iter().filter(|r| r.function == "mcp").collect();
+        assert_eq!(mcp_calls.len(), 3);
+        assert_eq!(mcp_calls[1].args["action"], "readResource");
+        assert_eq!(mcp_calls[1].args["uri"], "file:///notes.txt");
+
+        // Replay needs neither the provider nor the server.
+        let replay_ctx = RuntimeContext::with_replay(records);
+        let replay_backend = context_test_backend(replay_ctx, ProviderRegistry::new());
+        let replayed = run_agent(&path, MCP_AGENT_SRC, &input, &replay_backend).unwrap();
+        assert_eq!(output, replayed);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    const STRUCTURED_AGENT_SRC: &str = r#"
+        export async function agent(input: {}) {
+            const schema = {
+                type: "object",
+                properties: { score: { type: "integer", minimum: 0, maximum: 10 } },
+                required: ["score"],
+            };
+            const rated = await chidori.prompt("Rate it.", { model: "test-model", schema });
+            let error = null;
+            try {
+                await chidori.prompt("Rate again.", { model: "test-model", schema, schemaRetries: 0 });
+            } catch (e: any) {
+                error = { name: e.name, issues: e.issues };
+            }
+            return { rated, error };
+        }
+    "#;
+
+    #[test]
+    fn prompt_schema_re_asks_until_the_reply_validates_and_replays() {
+        let _env = PROMPT_ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let dir =
+            std::env::temp_dir().join(format!("chidori-rust-structured-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("agent.ts");
+        std::fs::write(&path, STRUCTURED_AGENT_SRC).unwrap();
+
+        let live_ctx = RuntimeContext::new();
+        let requests = Arc::new(StdMutex::new(Vec::new()));
+        let mut providers = crate::providers::ProviderRegistry::new();
+        providers.register(Box::new(SequenceProvider {
+            responses: vec![
+                "a solid eight".to_string(),
+                r#"{"score": 42}"#.to_string(),
+                "```json\n{\"score\": 7}\n```".to_string(),
+                r#"{"grade": "A"}"#.to_string(),
+            ],
+            calls: std::sync::atomic::AtomicUsize::new(0),
+            requests: Arc::clone(&requests),
+        }));
+        let live_backend = context_test_backend(live_ctx.clone(), providers);
+        let output = run_agent(
+            &path,
+            STRUCTURED_AGENT_SRC,
+            &serde_json::json!({}),
+            &live_backend,
+        )
+        .unwrap();
+        assert_eq!(output["rated"], serde_json::json!({ "score": 7 }));
+        assert_eq!(output["error"]["name"], "StructuredOutputError");
+        assert_eq!(
+            output["error"]["issues"],
+            serde_json::json!(["reply.score: required"])
+        );
+
+        // Every attempt carried the schema to the provider; each re-ask
+        // replays the bad reply and names what was wrong with it.
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 4);
+        assert!(requests.iter().all(|r| r.response_schema.is_some()));
+        let feedback = |request: &crate::providers::LlmRequest| {
+            serde_json::to_string(&request.messages.last().unwrap().content).unwrap()
+        };
+        assert_eq!(requests[2].messages.len(), 5);
+        assert!(feedback(&requests[1]).contains("not valid JSON"));
+        assert!(feedback(&requests[2]).contains("score: above maximum 10"));
+
+        // Replay walks the same attempts to the same object with no provider.
+        let records = live_ctx.call_log().into_records();
+        assert_eq!(records.iter().filter(|r| r.function == "prompt").count(), 4);
+        let replay_backend = context_test_backend(
+            RuntimeContext::with_replay(records),
+            crate::providers::ProviderRegistry::new(),
+        );
+        let replayed = run_agent(
+            &path,
+            STRUCTURED_AGENT_SRC,
+            &serde_json::json!({}),
+            &replay_backend,
+        )
+        .unwrap();
+        assert_eq!(replayed, output);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
     const CONVERSATION_AGENT_SRC: &str = r#"
         export async function agent(input: { messages: string[] }) {
             const chat = chidori.conversation({
@@ -3129,6 +3916,62 @@ mod tests {
         let _ = std::fs::remove_dir_all(dir);
     }
 
+    #[test]
+    fn prompt_budget_stops_live_calls_once_recorded_spend_reaches_it() {
+        // The budget gate prices the call log's recorded TokenUsage before
+        // each live prompt: the first prompt (nothing spent) goes out, the
+        // second finds the ceiling reached and fails with a `budget:` error
+        // instead of reaching the provider.
+        let _env = PROMPT_ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let ctx = RuntimeContext::new();
+        ctx.set_budget(crate::runtime::cost::CostBudget {
+            max_cost_usd: 0.0001,
+            on_exceed: crate::runtime::cost::BudgetAction::Fail,
+        });
+        let dir =
+            std::env::temp_dir().join(format!("chidori-rust-budget-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("agent.ts");
+        let src = r#"
+            export async function agent(input: {}) {
+                const first = await chidori.prompt("budget-first?", { model: "claude-sonnet-4-6" });
+                let error = "";
+                try {
+                    await chidori.prompt("budget-second?", { model: "claude-sonnet-4-6" });
+                } catch (e: any) {
+                    error = String(e && e.message ? e.message : e);
+                }
+                return { first, error };
+            }
+        "#;
+        std::fs::write(&path, src).unwrap();
+
+        let requests = Arc::new(StdMutex::new(Vec::new()));
+        let mut providers = crate::providers::ProviderRegistry::new();
+        providers.register(Box::new(SequenceProvider {
+            responses: vec!["one".to_string(), "two".to_string()],
+            calls: std::sync::atomic::AtomicUsize::new(0),
+            requests: Arc::clone(&requests),
+        }));
+        let backend = context_test_backend(ctx.clone(), providers);
+        let output = run_agent(&path, src, &serde_json::json!({}), &backend).unwrap();
+
+        assert_eq!(output["first"], "one");
+        let error = output["error"].as_str().unwrap();
+        assert!(
+            error.contains("budget: run cost $0.0005 has reached the $0.00 limit"),
+            "unexpected error: {error}"
+        );
+        assert_eq!(
+            crate::runtime::errors::RunErrorKind::classify(&anyhow::anyhow!(error.to_string())),
+            crate::runtime::errors::RunErrorKind::BudgetExceeded
+        );
+        assert_eq!(requests.lock().unwrap().len(), 1);
+        assert!(ctx.cost_usd() > 0.0001);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
     #[test]
     fn prompt_non_empty_option_rejects_blank_reply_and_defaults_stay_lenient() {
         // Round-4 usability finding: a response truncated to emptiness flows
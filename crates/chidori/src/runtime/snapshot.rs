@@ -238,6 +238,8 @@ pub struct HostOperationId(pub u64);
 #[serde(rename_all = "snake_case")]
 pub enum PendingHostOperationKind {
     Prompt,
+    /// A `chidori.embed(texts)` batch.
+    Embed,
     Input,
     PolicyApproval,
     Tool,
@@ -817,6 +819,12 @@ pub struct SnapshotManifest {
     /// manifests written before this field existed).
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub pricing: Option<String>,
+    /// The run's cost budget, so `resume` and the session server's
+    /// resume/approve re-runs enforce the same ceiling. Spend itself is not
+    /// stored: it is re-derived from the replayed journal's token usage.
+    /// `None` for unbudgeted runs (and manifests written before budgets).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<crate::runtime::cost::CostBudget>,
     pub call_log_len: usize,
     pub snapshot_file: String,
     pub created_at: DateTime<Utc>,
@@ -847,6 +855,7 @@ impl SnapshotManifest {
             vfs: crate::runtime::vfs::Vfs::new(),
             default_model: None,
             pricing: None,
+            budget: None,
             call_log_len,
             snapshot_file: SNAPSHOT_BLOB_FILE.to_string(),
             created_at: Utc::now(),
@@ -863,6 +872,11 @@ impl SnapshotManifest {
         self
     }
 
+    pub fn with_budget(mut self, budget: Option<crate::runtime::cost::CostBudget>) -> Self {
+        self.budget = budget;
+        self
+    }
+
     pub fn with_host_promises(mut self, host_promises: Vec<HostPromiseRecord>) -> Self {
         self.host_promises = host_promises;
         self
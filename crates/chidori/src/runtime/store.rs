@@ -11,6 +11,10 @@
 //!     of an O(history) rewrite of `checkpoint.json`.
 //!   * [`SqliteRunStore`] — records + blobs in a shared SQLite database
 //!     (`CHIDORI_RUN_STORE=sqlite`, path from `CHIDORI_RUN_DB`).
+//!   * [`PgRunStore`](crate::runtime::store_pg::PgRunStore) — records, blobs
+//!     and leases in tables of a shared Postgres database
+//!     (`CHIDORI_RUN_STORE=postgres://...`), for replicas behind a load
+//!     balancer.
 //!   * [`HttpRunStore`] — records + blobs relayed to a remote store speaking
 //!     the small REST protocol in `integrations/cloudflare-durable-objects/`
 //!     (`CHIDORI_RUN_STORE=https://...`). One Durable Object per run gives the
@@ -117,6 +121,16 @@ pub trait RunStore: Send + Sync + std::fmt::Debug {
     /// Keys of every stored blob (relative paths). Used by hydration.
     fn list_blobs(&self) -> Result<Vec<String>>;
 
+    /// Remove the whole run — journal and every blob. Removing a run that
+    /// does not exist is Ok. The default deletes blob by blob and empties the
+    /// journal, for backends with no whole-run delete.
+    fn delete_run(&self) -> Result<()> {
+        for key in self.list_blobs()? {
+            self.delete_blob(&key)?;
+        }
+        self.write_call_log(&[])
+    }
+
     /// Compare-and-swap one blob: apply `new` (`None` = delete) only when the
     /// stored value is byte-identical to `expected` (`None` = the key must be
     /// absent). `Ok(false)` means the precondition failed — another writer got
@@ -129,9 +143,9 @@ pub trait RunStore: Send + Sync + std::fmt::Debug {
     /// atomic: two callers can observe the same prior value and both write.
     /// That is the historical advisory behavior, correct for backends with no
     /// serialization point (`fs`, object stores). Backends that have one
-    /// override this: [`SqliteRunStore`] wraps it in a transaction,
-    /// [`HttpRunStore`] hands the precondition to the server as HTTP
-    /// conditional headers. See `docs/durable-storage.md` §Leases.
+    /// override this: [`SqliteRunStore`] wraps it in a transaction, the
+    /// Postgres store locks the row, [`HttpRunStore`] hands the precondition
+    /// to the server as HTTP conditional headers. See `docs/durable-storage.md` §Leases.
     fn compare_and_swap_blob(
         &self,
         key: &str,
@@ -339,6 +353,14 @@ impl RunStore for FsRunStore {
         }
     }
 
+    fn delete_run(&self) -> Result<()> {
+        match std::fs::remove_dir_all(&self.run_dir) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err).with_context(|| format!("removing {}", self.run_dir.display())),
+        }
+    }
+
     fn list_blobs(&self) -> Result<Vec<String>> {
         let mut keys = Vec::new();
         let mut stack = vec![self.run_dir.clone()];
@@ -606,6 +628,22 @@ impl RunStore for SqliteRunStore {
         Ok(())
     }
 
+    fn delete_run(&self) -> Result<()> {
+        let mut conn = self.shared.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM run_records WHERE run_id = ?1",
+            rusqlite::params![self.run_id],
+        )?;
+        tx.execute(
+            "DELETE FROM run_blobs WHERE run_id = ?1",
+            rusqlite::params![self.run_id],
+        )?;
+        tx.commit()?;
+        *self.next_pos.lock().unwrap() = None;
+        Ok(())
+    }
+
     fn list_blobs(&self) -> Result<Vec<String>> {
         let conn = self.shared.conn.lock().unwrap();
         let mut stmt = conn.prepare("SELECT key FROM run_blobs WHERE run_id = ?1 ORDER BY key")?;
@@ -1430,6 +1468,11 @@ impl RunStore for TeeRunStore {
         self.secondary.delete_blob(key)
     }
 
+    fn delete_run(&self) -> Result<()> {
+        self.primary.delete_run()?;
+        self.secondary.delete_run()
+    }
+
     fn list_blobs(&self) -> Result<Vec<String>> {
         let mut keys = self.primary.list_blobs()?;
         for key in self.secondary.list_blobs()? {
@@ -1588,6 +1631,8 @@ pub enum RunStoreBackend {
     Http(Arc<HttpRelay>),
     /// Mirror to an S3-compatible object store (S3, R2, GCS, MinIO, ...).
     Blob(Arc<crate::runtime::store_blob::S3BlobStore>),
+    /// Mirror to a shared Postgres database.
+    Postgres(Arc<crate::runtime::store_pg::PgClient>),
 }
 
 /// Hands out per-run [`RunStore`] handles and owns backend-wide operations
@@ -1611,6 +1656,8 @@ impl RunStoreFactory {
     ///   * unset / `fs` → filesystem only (the default)
     ///   * `sqlite` → mirror to `CHIDORI_RUN_DB` (default `<run_base>/runs.sqlite3`)
     ///   * `http(s)://...` → mirror to a remote relay
+    ///   * `s3://bucket[/prefix]` → mirror to an S3-compatible object store
+    ///   * `postgres://...` → mirror to a shared Postgres database
     pub fn from_env(run_base: impl Into<PathBuf>) -> Self {
         let run_base = run_base.into();
         let backend = match std::env::var("CHIDORI_RUN_STORE") {
@@ -1652,6 +1699,21 @@ impl RunStoreFactory {
                     }
                 }
             }
+            Ok(value) if value.starts_with("postgres://") || value.starts_with("postgresql://") => {
+                match crate::runtime::store_pg::open_run_store(&value) {
+                    Ok(client) => {
+                        // The URL carries credentials; don't log it.
+                        tracing::info!("run store: postgres mirror");
+                        RunStoreBackend::Postgres(client)
+                    }
+                    Err(err) => {
+                        tracing::warn!(
+                            "run store postgres mirror failed ({err:#}); falling back to fs only"
+                        );
+                        RunStoreBackend::Fs
+                    }
+                }
+            }
             Ok(value) if !value.is_empty() && !value.eq_ignore_ascii_case("fs") => {
                 tracing::warn!("unknown CHIDORI_RUN_STORE `{value}`; using fs only");
                 RunStoreBackend::Fs
@@ -1709,6 +1771,13 @@ impl RunStoreFactory {
                     run_id,
                 )),
             )),
+            RunStoreBackend::Postgres(client) => Arc::new(TeeRunStore::new(
+                primary,
+                Arc::new(crate::runtime::store_pg::PgRunStore::new(
+                    client.clone(),
+                    run_id,
+                )),
+            )),
         }
     }
 
@@ -1725,6 +1794,9 @@ impl RunStoreFactory {
             RunStoreBackend::Blob(store) => Some(Arc::new(
                 crate::runtime::store_blob::BlobRunStore::new(store.clone(), run_id),
             )),
+            RunStoreBackend::Postgres(client) => Some(Arc::new(
+                crate::runtime::store_pg::PgRunStore::new(client.clone(), run_id),
+            )),
         }
     }
 
@@ -1753,6 +1825,9 @@ impl RunStoreFactory {
             RunStoreBackend::Blob(store) => {
                 ids.extend(crate::runtime::store_blob::list_runs(store)?)
             }
+            RunStoreBackend::Postgres(client) => {
+                ids.extend(crate::runtime::store_pg::list_runs(client)?)
+            }
         }
         Ok(ids.into_iter().collect())
     }
@@ -1831,6 +1906,9 @@ impl RunStoreFactory {
             RunStoreBackend::Blob(store) => {
                 crate::runtime::store_blob::registry_put(store, name, &entry)?;
             }
+            RunStoreBackend::Postgres(client) => {
+                crate::runtime::store_pg::registry_put(client, name, run_id, &entry)?;
+            }
         }
         Ok(())
     }
@@ -1865,6 +1943,9 @@ impl RunStoreFactory {
                 }
             }
             RunStoreBackend::Blob(store) => crate::runtime::store_blob::registry_get(store, name),
+            RunStoreBackend::Postgres(client) => {
+                crate::runtime::store_pg::registry_get(client, name)
+            }
         }
     }
 
@@ -1903,6 +1984,13 @@ impl RunStoreFactory {
                     }
                 }
             }
+            RunStoreBackend::Postgres(client) => {
+                for entry in crate::runtime::store_pg::registry_list(client)? {
+                    if let Some(name) = entry.get("name").and_then(|v| v.as_str()) {
+                        by_name.insert(name.to_string(), entry.clone());
+                    }
+                }
+            }
         }
         // Local entries win: they reflect this machine's latest state.
         let agents_dir = self.run_base.join("agents");
@@ -1963,8 +2051,9 @@ const LEASE_CAS_ATTEMPTS: usize = 8;
 /// exact bytes read, and the write is conditional on those bytes still being
 /// current ([`RunStore::compare_and_swap_blob`]); a lost swap re-reads and
 /// re-decides instead of clobbering the winner. How atomic that is depends on
-/// the backend — enforced on SQLite (one `IMMEDIATE` transaction) and on the
-/// relay backends (server-side preconditions), advisory on the filesystem and
+/// the backend — enforced on SQLite (one `IMMEDIATE` transaction), on
+/// Postgres (a row lock) and on the relay backends (server-side
+/// preconditions), advisory on the filesystem and
 /// on object stores. `docs/durable-storage.md` §Leases has the table.
 ///
 /// **Fleet state, so it addresses the shared store**
@@ -2079,6 +2168,7 @@ mod tests {
             token_usage: None,
             timestamp: chrono::Utc::now(),
             error: None,
+            served_by: None,
         }
     }
 
@@ -2182,6 +2272,24 @@ mod tests {
         let _ = std::fs::remove_dir_all(dir);
     }
 
+    #[test]
+    fn delete_run_clears_the_run_from_both_tee_layers() {
+        let base = std::env::temp_dir().join(format!("chidori-store-del-{}", uuid::Uuid::new_v4()));
+        let shared = SqliteRunStoreShared::open(&base.join("runs.sqlite3")).unwrap();
+        let mirror = Arc::new(SqliteRunStore::new(shared.clone(), "run-1"));
+        let tee = TeeRunStore::new(FsRunStore::new(base.join("run-1")), mirror.clone());
+        tee.append_record(&record(1, "prompt")).unwrap();
+        tee.put_blob("signals/inbox.json", b"[]").unwrap();
+
+        tee.delete_run().unwrap();
+        assert!(!base.join("run-1").exists());
+        assert!(mirror.load_call_log().unwrap().is_none());
+        assert!(mirror.list_blobs().unwrap().is_empty());
+        assert!(!shared.list_runs().unwrap().contains(&"run-1".to_string()));
+        tee.delete_run().unwrap(); // absent delete is Ok
+        let _ = std::fs::remove_dir_all(base);
+    }
+
     #[test]
     fn tee_run_store_mirrors_and_hydrates() {
         let base = std::env::temp_dir().join(format!("chidori-store-tee-{}", uuid::Uuid::new_v4()));
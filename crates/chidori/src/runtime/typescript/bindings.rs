@@ -8,13 +8,15 @@ use sha2::{Digest, Sha256};
 use crate::mcp::McpManager;
 use crate::policy::{Decision, PolicyCache, PolicyConfig};
 use crate::providers::{
-    CacheLayout, CacheTtl, ContentBlock, LlmRequest, Message as LlmMessage, ProviderRegistry,
-    ToolSchema,
+    CacheLayout, CacheTtl, ContentBlock, EmbeddingRequest, LlmRequest, Message as LlmMessage,
+    ProviderRegistry, ToolSchema,
 };
 use crate::runtime::call_log::CallRecord;
 use crate::runtime::context::{InputMode, PendingApproval, RuntimeContext};
+use crate::runtime::cost::{BudgetAction, BUDGET_APPROVAL_TARGET};
 use crate::runtime::errors::RunInterrupt;
 use crate::runtime::host_core;
+use crate::runtime::policy_audit::{self, PolicyAuditEntry};
 use crate::runtime::snapshot::RuntimePolicy;
 use crate::runtime::template::TemplateEngine;
 /// A recorded host effect call (function name + JSON args). Used by the
@@ -106,7 +108,7 @@ impl HostBindingBackend {
         // deny or gate `workspace:write` / `workspace:delete` (or any action)
         // by target. Enforcing before recording means a denied or paused call
         // never lands in the journal.
-        self.enforce_policy(&format!("workspace:{action}"), &args)?;
+        self.enforce_policy_before_call(&format!("workspace:{action}"), &args)?;
         let call_args = serde_json::json!({
             "action": action,
             "args": args,
@@ -137,6 +139,7 @@ impl HostBindingBackend {
                             token_usage: None,
                             timestamp: started,
                             error: None,
+                            served_by: None,
                         });
                         Ok(result)
                     }
@@ -151,6 +154,7 @@ impl HostBindingBackend {
                             token_usage: None,
                             timestamp: started,
                             error: Some(err.clone()),
+                            served_by: None,
                         });
                         Err(err)
                     }
@@ -230,6 +234,15 @@ impl HostBindingBackend {
             })
             .unwrap_or_default();
         let posture = host_core::cache_posture_from_options(&options);
+        // The JSON Schema form of `chidori.prompt(text, { schema })`. The JS
+        // wrapper owns validation and re-asking; here the schema only asks
+        // the provider for native enforcement, which a forced answer shape
+        // would break for turns that may call tools — so tool turns omit it.
+        let response_schema = options.get("schema").filter(|v| v.is_object()).cloned();
+        // Extended thinking / reasoning effort. Applies to every turn of a
+        // tool loop, whose thinking blocks ride back verbatim in the
+        // assistant turns below.
+        let reasoning = host_core::reasoning_from_options(&options)?;
 
         // `chidori.context(...).prompt()/.respond()` forwards its flattened
         // segment chain in `__context`; everything below the seed differs from
@@ -275,6 +288,11 @@ impl HostBindingBackend {
                         .to_string(),
                 );
             }
+            let attachments = crate::runtime::media::resolve_for_journal(
+                &mut messages,
+                self.workspace_root().as_deref(),
+                runtime_ctx.next_replayed_args().as_ref(),
+            )?;
             let seed_len = messages.len();
             let build_request = |messages: &[LlmMessage]| {
                 let mut request = LlmRequest {
@@ -285,12 +303,14 @@ impl HostBindingBackend {
                     max_tokens,
                     tools: tool_schemas.clone(),
                     cache: parts.cache.clone(),
+                    response_schema: response_schema.clone().filter(|_| tool_schemas.is_empty()),
+                    reasoning,
                 };
                 host_core::auto_mark_prompt_cache(&mut request, posture);
                 request
             };
             let call_args = |request: &LlmRequest, turn: Option<u64>| {
-                serde_json::json!({
+                let mut args = serde_json::json!({
                     "model": model,
                     "type": prompt_type,
                     "tools": all_tool_names,
@@ -303,7 +323,11 @@ impl HostBindingBackend {
                     "max_tokens": request.max_tokens,
                     "temperature": request.temperature,
                     "request_digest": host_core::prompt_request_digest(request),
-                })
+                });
+                if !attachments.is_empty() {
+                    args["attachments"] = serde_json::Value::Array(attachments.clone());
+                }
+                args
             };
 
             if respond {
@@ -311,6 +335,7 @@ impl HostBindingBackend {
                 // explicitly by appending toolResult segments.
                 let request = build_request(&messages);
                 let args = call_args(&request, None);
+                self.enforce_budget()?;
                 let response = host_core::execute_prompt_response(
                     runtime_ctx,
                     providers,
@@ -331,6 +356,7 @@ impl HostBindingBackend {
             if tool_schemas.is_empty() {
                 let request = build_request(&messages);
                 let args = call_args(&request, None);
+                self.enforce_budget()?;
                 let result = host_core::execute_prompt_text(
                     runtime_ctx,
                     providers,
@@ -357,6 +383,7 @@ impl HostBindingBackend {
             for turn in 0..max_turns {
                 let request = build_request(&messages);
                 let args = call_args(&request, Some(turn));
+                self.enforce_budget()?;
                 let response = host_core::execute_prompt_response(
                     runtime_ctx,
                     providers,
@@ -393,8 +420,28 @@ impl HostBindingBackend {
             tool_schemas.push(tool_def_to_schema(tool_def));
         }
 
+        // `attachments` rides alongside the text in the single user turn;
+        // files resolve against the workspace and journal by content hash.
+        let mut first_turn = vec![LlmMessage::user_text(text.clone())];
+        if let Some(parts) = options.get("attachments").filter(|v| !v.is_null()) {
+            first_turn[0]
+                .content
+                .extend(crate::runtime::media::blocks_from_parts(parts)?);
+        }
+        let attachments = crate::runtime::media::resolve_for_journal(
+            &mut first_turn,
+            self.workspace_root().as_deref(),
+            runtime_ctx.next_replayed_args().as_ref(),
+        )?;
+        let with_attachments = |mut args: serde_json::Value| {
+            if !attachments.is_empty() {
+                args["attachments"] = serde_json::Value::Array(attachments.clone());
+            }
+            args
+        };
+
         if !tool_schemas.is_empty() {
-            let mut messages = vec![LlmMessage::user_text(text.clone())];
+            let mut messages = first_turn;
             let mut final_text = String::new();
             for turn in 0..max_turns {
                 let mut request = LlmRequest {
@@ -405,15 +452,18 @@ impl HostBindingBackend {
                     max_tokens,
                     tools: tool_schemas.clone(),
                     cache: CacheLayout::default(),
+                    response_schema: None,
+                    reasoning,
                 };
                 host_core::auto_mark_prompt_cache(&mut request, posture);
                 let request_digest = host_core::prompt_request_digest(&request);
+                self.enforce_budget()?;
                 let response = host_core::execute_prompt_response(
                     runtime_ctx,
                     providers,
                     tokio_rt,
                     request,
-                    serde_json::json!({
+                    with_attachments(serde_json::json!({
                         "text": text,
                         "model": model,
                         "type": prompt_type,
@@ -423,7 +473,7 @@ impl HostBindingBackend {
                         "max_tokens": max_tokens,
                         "temperature": temperature,
                         "request_digest": request_digest,
-                    }),
+                    })),
                     prompt_type.clone(),
                 )
                 .map_err(|err| err.to_string())?;
@@ -448,28 +498,31 @@ impl HostBindingBackend {
 
         let mut request = LlmRequest {
             model: model.clone(),
-            messages: vec![LlmMessage::user_text(text.clone())],
+            messages: first_turn,
             system: system.clone(),
             temperature,
             max_tokens,
             tools: Vec::new(),
             cache: CacheLayout::default(),
+            response_schema,
+            reasoning,
         };
         host_core::auto_mark_prompt_cache(&mut request, posture);
         let request_digest = host_core::prompt_request_digest(&request);
+        self.enforce_budget()?;
         let result = host_core::execute_prompt_text(
             runtime_ctx,
             providers,
             tokio_rt,
             request,
-            serde_json::json!({
+            with_attachments(serde_json::json!({
                 "text": text,
                 "model": model,
                 "type": prompt_type,
                 "max_tokens": max_tokens,
                 "temperature": temperature,
                 "request_digest": request_digest,
-            }),
+            })),
             prompt_type.clone(),
         )
         .map_err(|err| err.to_string())?;
@@ -485,6 +538,165 @@ impl HostBindingBackend {
         }
     }
 
+    /// `chidori.embed(texts, { model })`. A single string embeds to one
+    /// vector; an array embeds as one batch (one journal record) and returns
+    /// the vectors in input order. The model defaults to
+    /// `CHIDORI_EMBEDDING_MODEL`, then `text-embedding-3-small`; the run's
+    /// chat model is deliberately not used.
+    fn embed(
+        &self,
+        texts: &serde_json::Value,
+        options: &serde_json::Value,
+    ) -> std::result::Result<serde_json::Value, String> {
+        let HostBindingBackend::Runtime {
+            runtime_ctx,
+            providers,
+            tokio_rt,
+            ..
+        } = self
+        else {
+            return Err("chidori.embed requires the runtime host backend".to_string());
+        };
+        let (inputs, single) = match texts {
+            serde_json::Value::String(text) => (vec![text.clone()], true),
+            serde_json::Value::Array(items) => (
+                items
+                    .iter()
+                    .map(|item| {
+                        item.as_str().map(ToOwned::to_owned).ok_or_else(|| {
+                            "chidori.embed expects a string or an array of strings".to_string()
+                        })
+                    })
+                    .collect::<std::result::Result<Vec<_>, _>>()?,
+                false,
+            ),
+            _ => return Err("chidori.embed expects a string or an array of strings".to_string()),
+        };
+        if inputs.is_empty() {
+            return Ok(serde_json::json!([]));
+        }
+        let model = embedding_model(options);
+
+        self.enforce_budget()?;
+        let vectors = host_core::execute_embed(
+            runtime_ctx,
+            providers,
+            tokio_rt,
+            EmbeddingRequest { model, inputs },
+        )
+        .map_err(|err| err.to_string())?;
+        if single {
+            return Ok(vectors
+                .as_array()
+                .and_then(|vectors| vectors.first())
+                .cloned()
+                .unwrap_or(serde_json::Value::Null));
+        }
+        Ok(vectors)
+    }
+
+    /// `chidori.memory.upsert(key, text)` / `chidori.memory.search(query)`.
+    /// The text is embedded first — its own journaled `embed` record, so
+    /// usage is costed — and then one `memory` record either writes the
+    /// entry or captures the hits. Replay returns the recorded hits, never
+    /// re-querying an index that other runs may have changed since.
+    fn memory_semantic(
+        &self,
+        action: &str,
+        key: Option<serde_json::Value>,
+        text: Option<serde_json::Value>,
+        options: &serde_json::Value,
+    ) -> std::result::Result<serde_json::Value, String> {
+        let text = text
+            .as_ref()
+            .and_then(serde_json::Value::as_str)
+            .ok_or_else(|| format!("chidori.memory.{action} requires text"))?
+            .to_string();
+        let namespace = options
+            .get("namespace")
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or("default")
+            .to_string();
+        let model = embedding_model(options);
+        let vector: Vec<f32> = self
+            .embed(
+                &serde_json::Value::String(text.clone()),
+                &serde_json::json!({ "model": model }),
+            )?
+            .as_array()
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(serde_json::Value::as_f64)
+                    .map(|x| x as f32)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let base = memory_base(self);
+        let opt_null = |v: Option<serde_json::Value>| v.unwrap_or(serde_json::Value::Null);
+
+        if action == "upsert" {
+            let key = key
+                .as_ref()
+                .and_then(serde_json::Value::as_str)
+                .ok_or("chidori.memory.upsert requires a string key")?
+                .to_string();
+            let metadata = options
+                .get("metadata")
+                .cloned()
+                .filter(|v| !v.is_null())
+                .unwrap_or_else(|| serde_json::json!({}));
+            let args = serde_json::json!({
+                "action": "upsert",
+                "key": key,
+                "namespace": namespace,
+                "text": text,
+                "metadata": metadata,
+                "model": model,
+            });
+            return self
+                .durable_call("memory", args, || {
+                    crate::runtime::memory::upsert_memory_vector(
+                        &base,
+                        &namespace,
+                        &crate::runtime::memory::VectorEntry {
+                            key: &key,
+                            text: &text,
+                            metadata: &metadata,
+                            model: &model,
+                            vector: &vector,
+                        },
+                    )
+                    .map_err(|err| err.to_string())
+                })
+                .map(opt_null);
+        }
+
+        let k = options
+            .get("k")
+            .and_then(serde_json::Value::as_u64)
+            .unwrap_or(5) as usize;
+        let filter = options
+            .get("filter")
+            .cloned()
+            .unwrap_or(serde_json::Value::Null);
+        let args = serde_json::json!({
+            "action": "search",
+            "query": text,
+            "namespace": namespace,
+            "k": k,
+            "filter": filter,
+            "model": model,
+        });
+        self.durable_call("memory", args, || {
+            crate::runtime::memory::search_memory_vectors(
+                &base, &namespace, &model, &vector, k, &filter,
+            )
+            .map_err(|err| err.to_string())
+        })
+        .map(opt_null)
+    }
+
     /// Execute the tool calls from one assistant turn and frame each result as
     /// a `tool_result` block. A pause inside a tool propagates; other errors
     /// land in the block as `is_error` so the model can react.
@@ -541,13 +753,15 @@ impl HostBindingBackend {
             tool_schemas.push(tool_def_to_schema(tool_def));
         }
         tool_schemas.extend(parts.inline_tool_schemas.iter().cloned());
+        let mut messages = parts.messages;
+        crate::runtime::media::resolve_files(&mut messages, self.workspace_root().as_deref())?;
         let mut request = LlmRequest {
             model: opts
                 .get("model")
                 .and_then(serde_json::Value::as_str)
                 .unwrap_or(&config.model)
                 .to_string(),
-            messages: parts.messages,
+            messages,
             system: parts.system,
             temperature: opts
                 .get("temperature")
@@ -559,6 +773,8 @@ impl HostBindingBackend {
                 .unwrap_or(config.max_tokens),
             tools: tool_schemas,
             cache: parts.cache,
+            response_schema: None,
+            reasoning: host_core::reasoning_from_options(opts)?,
         };
         host_core::auto_mark_prompt_cache(
             &mut request,
@@ -607,15 +823,34 @@ impl HostBindingBackend {
         host_core::execute_signal_any(runtime_ctx, a).map_err(|err| err.to_string())
     }
 
+    /// Policy gate for a call that has already taken its seq (the gate runs
+    /// inside the call's durable wrapper: `http`, `tool:`, `app_data:`).
     fn enforce_policy(
         &self,
         target: &str,
         args: &serde_json::Value,
+    ) -> std::result::Result<(), String> {
+        self.enforce_policy_at(target, args, false)
+    }
+
+    /// Policy gate run before the gated call takes its seq (`workspace:`).
+    fn enforce_policy_before_call(
+        &self,
+        target: &str,
+        args: &serde_json::Value,
+    ) -> std::result::Result<(), String> {
+        self.enforce_policy_at(target, args, true)
+    }
+
+    fn enforce_policy_at(
+        &self,
+        target: &str,
+        args: &serde_json::Value,
+        before_call: bool,
     ) -> std::result::Result<(), String> {
         let HostBindingBackend::Runtime {
             runtime_ctx,
             policy,
-            policy_cache,
             ..
         } = self
         else {
@@ -630,10 +865,27 @@ impl HostBindingBackend {
             return Ok(());
         }
 
-        let (decision, reason) = policy.decide(target, args);
-        match decision {
-            Decision::AlwaysAllow => Ok(()),
+        let session = runtime_ctx.run_id();
+        let verdict = policy.evaluate(target, args, Some(&session));
+        let audit = |outcome: &str, via: Option<&str>| {
+            let seq = runtime_ctx.current_seq() + u64::from(before_call);
+            let entry =
+                PolicyAuditEntry::for_call(runtime_ctx, seq, target, args, &verdict, outcome);
+            let entry = match via {
+                Some(via) => entry.with_approval(via),
+                None => entry,
+            };
+            policy_audit::record(runtime_ctx, &entry);
+        };
+        let reason = verdict.reason.clone();
+        match verdict.decision {
+            Decision::AlwaysAllow => {
+                audit("allowed", None);
+                Ok(())
+            }
             Decision::NeverAllow => {
+                audit("denied", None);
+                verdict.release();
                 let message = format!(
                     "policy: `{}` denied{}",
                     target,
@@ -647,65 +899,149 @@ impl HostBindingBackend {
                 Err(message)
             }
             Decision::AskBefore => {
-                {
-                    let cache = policy_cache.lock().unwrap();
-                    if cache.is_approved(target, args) {
-                        return Ok(());
+                let outcome = match self.request_approval(target, args, reason.clone()) {
+                    Ok(outcome) => outcome,
+                    Err(pause) => {
+                        // Parked for the server's approval flow; the answer is
+                        // audited by the approve endpoint.
+                        audit("paused", None);
+                        verdict.release();
+                        return Err(pause);
                     }
+                };
+                if !matches!(outcome, ApprovalOutcome::Approved { .. }) {
+                    verdict.release();
                 }
-                if std::env::var("CHIDORI_POLICY_AUTO_APPROVE").ok().as_deref() == Some("1") {
-                    policy_cache.lock().unwrap().approve(target, args);
-                    return Ok(());
-                }
-                if runtime_ctx.input_mode() == InputMode::Pause {
-                    runtime_ctx.set_pending_approval(PendingApproval {
-                        target: target.to_string(),
-                        args: args.clone(),
-                        reason: reason.clone(),
-                    });
-                    // This error crosses into the VM as a plain string, so it
-                    // is raised in wire form (a Rust enum can't survive the
-                    // JS hop); the approval payload rides in the pending slot
-                    // set above.
-                    return Err(RunInterrupt::Approval.to_wire());
-                }
-                // Bare CLI: ask the operator on the controlling terminal.
-                // "y" is remembered per (target, args); "a" allows every
-                // further call to this target for the rest of the run.
-                if let Some(answer) =
-                    crate::policy::prompt_operator_approval(target, args, reason.as_deref())
-                {
-                    use crate::policy::OperatorAnswer;
-                    match answer {
-                        OperatorAnswer::Approve => {
-                            policy_cache.lock().unwrap().approve(target, args);
-                            return Ok(());
-                        }
-                        OperatorAnswer::ApproveTarget => {
-                            policy_cache.lock().unwrap().approve_target(target);
-                            return Ok(());
-                        }
-                        OperatorAnswer::Deny => {
-                            return Err(format!(
-                                "policy: `{}` denied at the operator prompt",
+                match outcome {
+                    ApprovalOutcome::Approved { via } => {
+                        audit("approved", Some(via));
+                        Ok(())
+                    }
+                    ApprovalOutcome::Denied => {
+                        audit("operator_denied", Some("terminal"));
+                        Err(format!(
+                            "policy: `{}` denied at the operator prompt",
+                            target
+                        ))
+                    }
+                    // Non-interactive and nothing to answer the prompt: fail
+                    // closed. A policy-supplied reason already tells the
+                    // operator how to relax the posture; otherwise fall back
+                    // to the generic help.
+                    ApprovalOutcome::Unavailable => {
+                        audit("unavailable", None);
+                        Err(match reason {
+                            Some(r) => format!("policy: `{}` requires approval - {}", target, r),
+                            None => format!(
+                                "policy: `{}` requires approval. Approve interactively at a \
+                                 terminal, set CHIDORI_POLICY_AUTO_APPROVE=1 to auto-approve, \
+                                 or run through the server so the approval flow can pause.",
                                 target
-                            ));
-                        }
+                            ),
+                        })
                     }
                 }
-                // Non-interactive and nothing to answer the prompt: fail closed.
-                // A policy-supplied reason already tells the operator how to
-                // relax the posture; otherwise fall back to the generic help.
-                Err(match reason {
-                    Some(r) => format!("policy: `{}` requires approval - {}", target, r),
-                    None => format!(
-                        "policy: `{}` requires approval. Approve interactively at a terminal, \
-                         set CHIDORI_POLICY_AUTO_APPROVE=1 to auto-approve, or run through the \
-                         server so the approval flow can pause.",
-                        target
-                    ),
+            }
+        }
+    }
+
+    /// The `AskBefore` approval flow, shared by policy gates and the cost
+    /// budget: a prior approval (or `CHIDORI_POLICY_AUTO_APPROVE=1`) passes;
+    /// in Pause mode the run parks on the approval (the `Err` is the pause
+    /// wire string); at a terminal the operator answers. Callers own the
+    /// denial and fail-closed messages.
+    fn request_approval(
+        &self,
+        target: &str,
+        args: &serde_json::Value,
+        reason: Option<String>,
+    ) -> std::result::Result<ApprovalOutcome, String> {
+        let HostBindingBackend::Runtime {
+            runtime_ctx,
+            policy_cache,
+            ..
+        } = self
+        else {
+            return Ok(ApprovalOutcome::Approved { via: "embedded" });
+        };
+        {
+            let cache = policy_cache.lock().unwrap();
+            if cache.is_approved(target, args) {
+                return Ok(ApprovalOutcome::Approved {
+                    via: "approval_cache",
+                });
+            }
+        }
+        if std::env::var("CHIDORI_POLICY_AUTO_APPROVE").ok().as_deref() == Some("1") {
+            policy_cache.lock().unwrap().approve(target, args);
+            return Ok(ApprovalOutcome::Approved {
+                via: "auto_approve",
+            });
+        }
+        if runtime_ctx.input_mode() == InputMode::Pause {
+            runtime_ctx.set_pending_approval(PendingApproval {
+                target: target.to_string(),
+                args: args.clone(),
+                reason,
+            });
+            // This error crosses into the VM as a plain string, so it is
+            // raised in wire form (a Rust enum can't survive the JS hop); the
+            // approval payload rides in the pending slot set above.
+            return Err(RunInterrupt::Approval.to_wire());
+        }
+        // Bare CLI: ask the operator on the controlling terminal. "y" is
+        // remembered per (target, args); "a" allows every further call to
+        // this target for the rest of the run.
+        use crate::policy::OperatorAnswer;
+        match crate::policy::prompt_operator_approval(target, args, reason.as_deref()) {
+            Some(OperatorAnswer::Approve) => {
+                policy_cache.lock().unwrap().approve(target, args);
+                Ok(ApprovalOutcome::Approved { via: "terminal" })
+            }
+            Some(OperatorAnswer::ApproveTarget) => {
+                policy_cache.lock().unwrap().approve_target(target);
+                Ok(ApprovalOutcome::Approved {
+                    via: "terminal_all",
                 })
             }
+            Some(OperatorAnswer::Deny) => Ok(ApprovalOutcome::Denied),
+            None => Ok(ApprovalOutcome::Unavailable),
+        }
+    }
+
+    /// Gate a live prompt on the run's cost budget. Spend is the estimated
+    /// cost of every prompt already in the call log (replayed legs included),
+    /// so the check happens before the provider call that would overrun, not
+    /// after. Approval args carry the spend at the gate, so an approval
+    /// covers one over-budget call (the terminal's "[a]ll" covers the rest of
+    /// the run) and replays to the same decision on resume.
+    fn enforce_budget(&self) -> std::result::Result<(), String> {
+        let HostBindingBackend::Runtime { runtime_ctx, .. } = self else {
+            return Ok(());
+        };
+        let Some(budget) = runtime_ctx.config().budget else {
+            return Ok(());
+        };
+        // Replayed prompts spend nothing; see `enforce_policy`.
+        if runtime_ctx.next_call_is_replayed() {
+            return Ok(());
+        }
+        let spent = runtime_ctx.cost_usd();
+        if !budget.is_exhausted(spent) {
+            return Ok(());
+        }
+        let message = budget.exceeded_message(spent);
+        if budget.on_exceed == BudgetAction::Fail {
+            eprintln!("chidori: {message}");
+            return Err(message);
+        }
+        let args = serde_json::json!({
+            "max_cost_usd": budget.max_cost_usd,
+            "spent_usd": spent,
+        });
+        match self.request_approval(BUDGET_APPROVAL_TARGET, &args, Some(message.clone()))? {
+            ApprovalOutcome::Approved { .. } => Ok(()),
+            ApprovalOutcome::Denied | ApprovalOutcome::Unavailable => Err(message),
         }
     }
 
@@ -716,7 +1052,6 @@ impl HostBindingBackend {
     ) -> std::result::Result<serde_json::Value, String> {
         let HostBindingBackend::Runtime {
             runtime_ctx,
-            tokio_rt,
             tools,
             mcp,
             ..
@@ -769,10 +1104,16 @@ impl HostBindingBackend {
                     ToolBackend::Mcp {
                         server_id,
                         remote_name,
-                    } => tokio_rt.block_on(async {
-                        mcp.call_tool(server_id, remote_name, &serde_json::Value::Object(kwargs))
-                            .await
-                    }),
+                    } => {
+                        let mcp = mcp.clone();
+                        let server = server_id.clone();
+                        let remote = remote_name.clone();
+                        let kwargs = serde_json::Value::Object(kwargs);
+                        self.block_on_mcp(server_id, |requests| async move {
+                            mcp.call_tool(&server, &remote, &kwargs, Some(requests))
+                                .await
+                        })
+                    }
                     ToolBackend::Native => {
                         tools.dispatch_native(tool_name, serde_json::Value::Object(kwargs))
                     }
@@ -782,6 +1123,215 @@ impl HostBindingBackend {
         .map_err(|err| err.to_string())
     }
 
+    /// Run one MCP request on the tokio runtime, answering the server's
+    /// requests back to us (sampling) on this thread as they arrive. A pause
+    /// raised while answering (a sampling policy approval) fails the server's
+    /// request but is re-raised here, so the enclosing durable call pauses
+    /// instead of recording a failure — and re-runs on resume.
+    fn block_on_mcp<Fut>(
+        &self,
+        server_id: &str,
+        call: impl FnOnce(crate::mcp::ServerRequestSender) -> Fut,
+    ) -> anyhow::Result<serde_json::Value>
+    where
+        Fut: std::future::Future<Output = anyhow::Result<serde_json::Value>> + Send + 'static,
+    {
+        let HostBindingBackend::Runtime { tokio_rt, .. } = self else {
+            anyhow::bail!("MCP calls require the runtime host backend");
+        };
+        let mut interrupt = None;
+        let result = crate::mcp::block_on_with_requests(tokio_rt, call, |method, params| {
+            let answer = match method {
+                "sampling/createMessage" => self.mcp_sample(server_id, &params),
+                other => Err(format!("unsupported MCP server request `{other}`")),
+            };
+            if let Err(message) = &answer {
+                if let Some(pause) = RunInterrupt::from_message(message) {
+                    interrupt = Some(pause);
+                }
+            }
+            answer
+        });
+        match interrupt {
+            Some(pause) => Err(anyhow::Error::new(pause)),
+            None => result,
+        }
+    }
+
+    /// Answer an MCP server's `sampling/createMessage` with the run's own
+    /// providers and default model (the server's `modelPreferences` hints are
+    /// journaled, not followed). The LLM call is an ordinary journaled
+    /// `prompt` made while the originating tool call executes, so it nests
+    /// under that call — and a replayed tool call never samples again.
+    fn mcp_sample(
+        &self,
+        server_id: &str,
+        params: &serde_json::Value,
+    ) -> std::result::Result<serde_json::Value, String> {
+        let HostBindingBackend::Runtime {
+            runtime_ctx,
+            providers,
+            tokio_rt,
+            ..
+        } = self
+        else {
+            return Err("MCP sampling requires the runtime host backend".to_string());
+        };
+        let config = runtime_ctx.config();
+        let messages = params
+            .get("messages")
+            .and_then(serde_json::Value::as_array)
+            .ok_or("sampling/createMessage requires `messages`")?
+            .iter()
+            .map(mcp_sampling_message)
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let model = config.model.clone();
+        let max_tokens = params
+            .get("maxTokens")
+            .and_then(serde_json::Value::as_u64)
+            .unwrap_or(config.max_tokens);
+        let temperature = params
+            .get("temperature")
+            .and_then(serde_json::Value::as_f64)
+            .unwrap_or(config.temperature);
+        let hints: Vec<&str> = params
+            .pointer("/modelPreferences/hints")
+            .and_then(serde_json::Value::as_array)
+            .map(|hints| {
+                hints
+                    .iter()
+                    .filter_map(|hint| hint.get("name").and_then(serde_json::Value::as_str))
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.enforce_policy_before_call(
+            &format!("mcp_sampling:{server_id}"),
+            &serde_json::json!({ "model": model, "max_tokens": max_tokens }),
+        )?;
+        let request = LlmRequest {
+            model: model.clone(),
+            messages,
+            system: params
+                .get("systemPrompt")
+                .and_then(serde_json::Value::as_str)
+                .map(ToOwned::to_owned),
+            temperature,
+            max_tokens,
+            tools: Vec::new(),
+            cache: CacheLayout::default(),
+            response_schema: None,
+            reasoning: None,
+        };
+        let args = serde_json::json!({
+            "model": model,
+            "type": "mcp_sampling",
+            "mcp_server": server_id,
+            "model_hints": hints,
+            "max_tokens": max_tokens,
+            "temperature": temperature,
+            "request_digest": host_core::prompt_request_digest(&request),
+        });
+        self.enforce_budget()?;
+        let response = host_core::execute_prompt_response(
+            runtime_ctx,
+            providers,
+            tokio_rt,
+            request,
+            args,
+            None,
+        )
+        .map_err(|err| err.to_string())?;
+        let stop_reason = match response.stop_reason.as_str() {
+            "end_turn" => "endTurn",
+            "max_tokens" => "maxTokens",
+            "stop_sequence" => "stopSequence",
+            other => other,
+        };
+        Ok(serde_json::json!({
+            "role": "assistant",
+            "content": { "type": "text", "text": response.content },
+            "model": response
+                .served_by
+                .as_ref()
+                .map(|served| served.model.clone())
+                .unwrap_or(model),
+            "stopReason": stop_reason,
+        }))
+    }
+
+    /// `chidori.mcp.*` — resources and prompts on a connected MCP server. One
+    /// durable `mcp` record per call (policy target `mcp:<server>`), replayed
+    /// without contacting the server.
+    fn mcp(&self, a: &serde_json::Value) -> std::result::Result<serde_json::Value, String> {
+        let HostBindingBackend::Runtime { mcp, .. } = self else {
+            return Err("chidori.mcp requires the runtime host backend".to_string());
+        };
+        let action = a
+            .get("action")
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or("")
+            .to_string();
+        let server = a
+            .get("server")
+            .and_then(serde_json::Value::as_str)
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| format!("chidori.mcp.{action} requires a server id"))?
+            .to_string();
+        let mut args = serde_json::json!({ "action": action, "server": server });
+        match action.as_str() {
+            "readResource" => {
+                let uri = a
+                    .get("uri")
+                    .and_then(serde_json::Value::as_str)
+                    .ok_or("chidori.mcp.readResource requires a resource uri")?;
+                args["uri"] = serde_json::Value::String(uri.to_string());
+            }
+            "getPrompt" => {
+                let name = a
+                    .get("name")
+                    .and_then(serde_json::Value::as_str)
+                    .ok_or("chidori.mcp.getPrompt requires a prompt name")?;
+                args["name"] = serde_json::Value::String(name.to_string());
+                args["arguments"] = a
+                    .get("arguments")
+                    .cloned()
+                    .filter(|v| !v.is_null())
+                    .unwrap_or_else(|| serde_json::json!({}));
+            }
+            "listResources" | "listPrompts" => {}
+            other => return Err(format!("unknown chidori.mcp action `{other}`")),
+        }
+        self.durable_call("mcp", args.clone(), || {
+            self.enforce_policy(&format!("mcp:{server}"), &args)?;
+            let mcp = mcp.clone();
+            let call_server = server.clone();
+            let str_arg = |key: &str| {
+                args.get(key)
+                    .and_then(serde_json::Value::as_str)
+                    .unwrap_or("")
+                    .to_string()
+            };
+            let (uri, name) = (str_arg("uri"), str_arg("name"));
+            let arguments = args
+                .get("arguments")
+                .cloned()
+                .unwrap_or(serde_json::Value::Null);
+            self.block_on_mcp(&server, |requests| async move {
+                match action.as_str() {
+                    "readResource" => mcp.read_resource(&call_server, &uri, Some(requests)).await,
+                    "getPrompt" => {
+                        mcp.get_prompt(&call_server, &name, &arguments, Some(requests))
+                            .await
+                    }
+                    "listResources" => mcp.list_resources(&call_server).await,
+                    _ => mcp.list_prompts(&call_server).await,
+                }
+            })
+            .map_err(|err| err.to_string())
+        })
+        .map(|result| result.unwrap_or(serde_json::Value::Null))
+    }
+
     fn call_agent(
         &self,
         path: String,
@@ -1178,6 +1728,11 @@ impl HostBindingBackend {
                     .unwrap_or_else(|| serde_json::json!({}));
                 self.prompt(text, options)
             }
+            "embed" => {
+                let texts = a.get("texts").cloned().unwrap_or(serde_json::Value::Null);
+                let options = a.get("opts").cloned().unwrap_or(serde_json::Value::Null);
+                self.embed(&texts, &options)
+            }
             "tool" => {
                 let name = a
                     .get("name")
@@ -1191,6 +1746,7 @@ impl HostBindingBackend {
                     .unwrap_or_else(|| serde_json::json!({}));
                 self.tool(name, kwargs)
             }
+            "mcp" => self.mcp(a),
             "memory" => {
                 let action = a
                     .get("action")
@@ -1200,6 +1756,9 @@ impl HostBindingBackend {
                 let key = a.get("key").cloned().filter(|v| !v.is_null());
                 let value = a.get("value").cloned().filter(|v| !v.is_null());
                 let options = a.get("opts").cloned().unwrap_or(serde_json::Value::Null);
+                if matches!(action.as_str(), "upsert" | "search") {
+                    return self.memory_semantic(&action, key, value, &options);
+                }
                 let namespace = options
                     .get("namespace")
                     .and_then(serde_json::Value::as_str)
@@ -1508,6 +2067,17 @@ struct ContextRequestParts {
     cache: CacheLayout,
 }
 
+/// How an `AskBefore`-style approval request resolved, short of pausing.
+enum ApprovalOutcome {
+    /// `via` names who approved, for the policy audit log.
+    Approved {
+        via: &'static str,
+    },
+    Denied,
+    /// No approval already granted and nobody to ask (non-interactive).
+    Unavailable,
+}
+
 /// Parse a `format:"json"` model reply. Tolerates the common markdown-fence
 /// wrapping (```json ... ```). On unparseable output: strict mode (the
 /// default) fails loudly with the likely cause and the reply head, so a
@@ -1633,7 +2203,17 @@ fn context_request_parts(
                 "<conversation-summary>\n{}\n</conversation-summary>",
                 str_field(seg, "text")
             ))),
-            "user" => messages.push(LlmMessage::user_text(str_field(seg, "text"))),
+            // `.user([...parts])` carries multimodal content (text, images,
+            // documents) in `content`; the plain `.user(text)` form keeps
+            // the single text block.
+            "user" => match seg.get("content").filter(|c| c.is_array()) {
+                Some(parts) => messages.push(LlmMessage {
+                    role: "user".to_string(),
+                    content: crate::runtime::media::blocks_from_parts(parts)?,
+                    cache_control: None,
+                }),
+                None => messages.push(LlmMessage::user_text(str_field(seg, "text"))),
+            },
             "assistant" => messages.push(LlmMessage::assistant_blocks(vec![ContentBlock::Text {
                 text: str_field(seg, "text"),
             }])),
@@ -1713,6 +2293,62 @@ fn workspace_root(backend: &HostBindingBackend) -> std::result::Result<PathBuf,
     })
 }
 
+/// One MCP `SamplingMessage` (`{ role, content }`, content a single block or
+/// an array of them) as a provider message. Text and images translate;
+/// anything else (audio) is refused rather than silently dropped.
+fn mcp_sampling_message(message: &serde_json::Value) -> std::result::Result<LlmMessage, String> {
+    let role = match message.get("role").and_then(serde_json::Value::as_str) {
+        Some("assistant") => "assistant",
+        Some("user") => "user",
+        other => return Err(format!("sampling message has unsupported role {other:?}")),
+    };
+    let blocks = match message.get("content") {
+        Some(serde_json::Value::Array(blocks)) => blocks.iter().collect(),
+        Some(block) => vec![block],
+        None => Vec::new(),
+    };
+    let content = blocks
+        .into_iter()
+        .map(|block| {
+            let field = |key: &str| {
+                block
+                    .get(key)
+                    .and_then(serde_json::Value::as_str)
+                    .map(ToOwned::to_owned)
+            };
+            match block.get("type").and_then(serde_json::Value::as_str) {
+                Some("text") => Ok(ContentBlock::Text {
+                    text: field("text").unwrap_or_default(),
+                }),
+                Some("image") => Ok(ContentBlock::Image {
+                    source: crate::providers::MediaSource::Base64 {
+                        media_type: field("mimeType").unwrap_or_default(),
+                        data: field("data").unwrap_or_default(),
+                    },
+                }),
+                other => Err(format!("sampling content type {other:?} is not supported")),
+            }
+        })
+        .collect::<std::result::Result<Vec<_>, String>>()?;
+    Ok(LlmMessage {
+        role: role.to_string(),
+        content,
+        cache_control: None,
+    })
+}
+
+/// The embedding model for `chidori.embed` and semantic memory: `model`,
+/// else `CHIDORI_EMBEDDING_MODEL`, else `text-embedding-3-small`.
+fn embedding_model(options: &serde_json::Value) -> String {
+    options
+        .get("model")
+        .and_then(serde_json::Value::as_str)
+        .map(ToOwned::to_owned)
+        .or_else(|| std::env::var("CHIDORI_EMBEDDING_MODEL").ok())
+        .filter(|model| !model.is_empty())
+        .unwrap_or_else(|| "text-embedding-3-small".to_string())
+}
+
 /// The directory under which `chidori.memory` stores `.chidori/memory/`.
 /// Precedence: `CHIDORI_MEMORY_DIR`, then the run's workspace root (the agent
 /// file's directory / `CHIDORI_WORKSPACE_ROOT`), then the process cwd as a
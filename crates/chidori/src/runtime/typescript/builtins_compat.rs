@@ -1509,7 +1509,9 @@ export default {
 
 // node:v8 — engine-introspection surface. The embedded engine is not V8;
 // statistics report fixed zeros (deterministic), and the serialization API —
-// whose byte format is V8-proprietary — throws.
+// whose byte format is V8-proprietary — throws. `writeHeapSnapshot` writes a
+// DevTools `.heapsnapshot` of the engine's own heap into the run's `heap/`
+// directory and returns its path; the stream form (`getHeapSnapshot`) throws.
 const V8_SHIM: &str = r#"
 function unsupported(name) {
     return function () {
@@ -1533,7 +1535,9 @@ export function cachedDataVersionTag() { return 0; }
 export function setFlagsFromString() {}
 export const serialize = unsupported("serialize");
 export const deserialize = unsupported("deserialize");
-export const writeHeapSnapshot = unsupported("writeHeapSnapshot");
+export function writeHeapSnapshot(filename) {
+    return globalThis.__chidori_heap_snapshot(filename === undefined ? null : String(filename));
+}
 export const getHeapSnapshot = unsupported("getHeapSnapshot");
 export class Serializer { constructor() { unsupported("Serializer")(); } }
 export class Deserializer { constructor() { unsupported("Deserializer")(); } }
@@ -113,6 +113,12 @@ pub(crate) const CHIDORI_JS_HELPERS_SCRIPT: &str = r#"
             clear(options) {
                 return call("clear", null, null, options);
             },
+            upsert(key, text, options) {
+                return call("upsert", key, text, options);
+            },
+            search(query, options) {
+                return call("search", null, query, options);
+            },
         };
         return null;
     };
@@ -275,9 +281,19 @@ pub(crate) const CHIDORI_JS_HELPERS_SCRIPT: &str = r#"
             if (block.type === "tool_result") {
                 return "[tool result: " + block.content + "]";
             }
+            if (block.type === "image") return "[image]";
+            if (block.type === "thinking" || block.type === "redacted_thinking") return "";
+            if (block.type === "document") {
+                return "[document" + (block.title ? " " + block.title : "") + "]";
+            }
             return JSON.stringify(block);
         }
         function renderSegment(segment) {
+            if (segment.kind === "user" && Array.isArray(segment.content)) {
+                return "User: " + segment.content
+                    .map((part) => typeof part === "string" ? part : renderBlock(part))
+                    .join("\n");
+            }
             if (segment.kind === "user") return "User: " + segment.text;
             if (segment.kind === "assistant") return "Assistant: " + segment.text;
             if (segment.kind === "summary") {
@@ -346,8 +362,23 @@ pub(crate) const CHIDORI_JS_HELPERS_SCRIPT: &str = r#"
                     text: String(text),
                 });
             },
-            user(text) {
-                return append(this, { kind: "user", text: String(text) });
+            // `user(text)` or `user([...parts])`: parts are strings or
+            // `{ type: "text" | "image" | "document", ... }` objects
+            // (see runtime/media.rs). `text` keeps the joined text parts so
+            // token estimates and compaction transcripts still read it.
+            user(content) {
+                if (Array.isArray(content)) {
+                    const parts = content.map((part) =>
+                        part && typeof part === "object" ? Object.assign({}, part) : String(part),
+                    );
+                    const text = parts
+                        .map((part) => typeof part === "string" ? part
+                            : part.type === "text" ? String(part.text) : "")
+                        .filter((t) => t !== "")
+                        .join("\n");
+                    return append(this, { kind: "user", text, content: parts });
+                }
+                return append(this, { kind: "user", text: String(content) });
             },
             assistant(text) {
                 return append(this, { kind: "assistant", text: String(text) });
@@ -780,11 +811,91 @@ pub(crate) const CHIDORI_JS_HELPERS_SCRIPT: &str = r#"
         globalThis.chidori.__splitTools = splitTools;
         globalThis.chidori.__parseJsonReply = parseJsonReply;
 
+        // Structured output: `schema` is a JSON Schema or a Standard Schema
+        // validator. Its JSON Schema form rides to the host, which asks the
+        // provider to enforce it natively; the reply is then parsed and
+        // validated here, and on failure re-asked with the issues — the
+        // original turn, the bad reply, and the feedback as a context — up to
+        // `schemaRetries` times. Every attempt is its own journaled prompt
+        // call and validation is pure, so replay walks the same attempts to
+        // the same validated object.
+        function schemaFeedback(issues, jsonSchema) {
+            let feedback =
+                "Your reply did not match the required schema:\n" +
+                issues.map((issue) => "- " + issue).join("\n") +
+                "\nReply again with only the corrected JSON value.";
+            if (jsonSchema) feedback += "\nSchema: " + JSON.stringify(jsonSchema);
+            return feedback;
+        }
+        async function structuredPrompt(text, opts) {
+            const schema = opts.schema;
+            const jsonSchema = globalThis.__chidori_json_schema_of(schema);
+            const retries = Number.isFinite(Number(opts.schemaRetries))
+                ? Math.max(0, Math.floor(Number(opts.schemaRetries)))
+                : 2;
+            const attemptOptions = Object.assign({}, opts);
+            delete attemptOptions.schema;
+            delete attemptOptions.schemaRetries;
+            delete attemptOptions.format;
+            delete attemptOptions.strict;
+            delete attemptOptions.nonEmpty;
+            if (jsonSchema) attemptOptions.schema = jsonSchema;
+            const check = async (reply) => {
+                let parsed;
+                try {
+                    parsed = parseJsonReply(reply, true);
+                } catch (e) {
+                    return { issues: [String(e && e.message ? e.message : e)] };
+                }
+                return globalThis.__chidori_schema_issues(schema, parsed, "reply");
+            };
+
+            let reply = await promptOnce(text, attemptOptions);
+            let result = await check(reply);
+            let ctx = null;
+            for (let attempt = 0; result.issues.length && attempt < retries; attempt++) {
+                if (ctx === null) {
+                    ctx = globalThis.chidori.context();
+                    if (typeof opts.system === "string") ctx = ctx.system(opts.system);
+                    ctx = ctx.user(
+                        Array.isArray(opts.attachments)
+                            ? [String(text == null ? "" : text)].concat(opts.attachments)
+                            : String(text == null ? "" : text),
+                    ).assistant(reply);
+                }
+                // Re-asks carry no tools: the model only has to restate its
+                // answer, and the transcript holds no tool turns.
+                const retryOptions = Object.assign({}, attemptOptions);
+                delete retryOptions.system;
+                delete retryOptions.tools;
+                delete retryOptions.attachments;
+                // The returned context already ends with the new reply.
+                ctx = ctx.user(schemaFeedback(result.issues, jsonSchema));
+                const out = await ctx.prompt(retryOptions);
+                ctx = out.context;
+                reply = out.text;
+                result = await check(reply);
+            }
+            if (result.issues.length) {
+                const err = new Error(
+                    "prompt: the reply does not match the schema after " +
+                        (retries + 1) +
+                        " attempt(s):\n" +
+                        result.issues.map((issue) => "  - " + issue).join("\n"),
+                );
+                err.name = "StructuredOutputError";
+                err.issues = result.issues;
+                err.reply = reply;
+                throw err;
+            }
+            return result.value;
+        }
+
         // Wrap chidori.prompt: when options.tools contains defineTool handles,
         // lower to a context + tool loop entirely in-VM. Name-only tools (and
         // tool-less prompts) delegate to the native host path unchanged.
         const nativePrompt = globalThis.chidori.prompt;
-        globalThis.chidori.prompt = function prompt(text, options) {
+        function promptOnce(text, options) {
             const opts = options || {};
             const list = Array.isArray(opts.tools) ? opts.tools : [];
             if (!list.some(isHandle)) {
@@ -826,6 +937,18 @@ pub(crate) const CHIDORI_JS_HELPERS_SCRIPT: &str = r#"
                 }
                 return reply;
             })();
+        }
+        globalThis.chidori.prompt = function prompt(text, options) {
+            const opts = options || {};
+            if (opts.schema == null) return promptOnce(text, options);
+            if (typeof opts.schema !== "object") {
+                return Promise.reject(
+                    new Error(
+                        "prompt: `schema` must be a JSON Schema object or a Standard Schema validator",
+                    ),
+                );
+            }
+            return structuredPrompt(text, opts);
         };
     })();
 
@@ -833,7 +956,8 @@ pub(crate) const CHIDORI_JS_HELPERS_SCRIPT: &str = r#"
 })()
 "#;
 
-/// Input-schema validation for the `run(handler, { inputSchema })` form.
+/// Schema validation for the `run(handler, { inputSchema })` form and for
+/// structured output (`chidori.prompt(text, { schema })`).
 ///
 /// Wraps the native `run` registrar (so it MUST be evaluated after
 /// `install_entrypoint`): when the second argument carries an `inputSchema`,
@@ -850,11 +974,13 @@ pub(crate) const CHIDORI_JS_HELPERS_SCRIPT: &str = r#"
 /// it happens before any host call and replays identically. A failure throws
 /// an `InputValidationError` listing every issue — the server maps it to a
 /// 400 carrying the issue list (the failed session is still stored).
+///
+/// The same checker backs `chidori.prompt`'s structured output through
+/// `__chidori_schema_issues` (issues without throwing) and
+/// `__chidori_json_schema_of` (the JSON Schema to hand the provider, when a
+/// Standard Schema validator exposes one).
 pub(crate) const INPUT_SCHEMA_SCRIPT: &str = r#"
 (() => {
-  const nativeRun = globalThis.run;
-  if (typeof nativeRun !== "function") return;
-
   const fmtPath = (path) =>
     path.map((p) => (typeof p === "object" && p !== null ? p.key : p)).join(".");
 
@@ -866,14 +992,14 @@ pub(crate) const INPUT_SCHEMA_SCRIPT: &str = r#"
     return t;
   };
 
-  function checkJsonSchema(schema, value, path, issues) {
+  function checkJsonSchema(schema, value, path, issues, root) {
     if (schema === true || schema == null) return;
     if (schema === false) {
-      issues.push(`${path || "input"}: schema forbids this value`);
+      issues.push(`${path || root}: schema forbids this value`);
       return;
     }
     if (typeof schema !== "object") return;
-    const where = path || "input";
+    const where = path || root;
     if (schema.const !== undefined && JSON.stringify(value) !== JSON.stringify(schema.const)) {
       issues.push(`${where}: expected const ${JSON.stringify(schema.const)}`);
       return;
@@ -911,7 +1037,7 @@ pub(crate) const INPUT_SCHEMA_SCRIPT: &str = r#"
       if (schema.maxItems != null && value.length > schema.maxItems)
         issues.push(`${where}: more than maxItems ${schema.maxItems}`);
       if (schema.items)
-        value.forEach((v, i) => checkJsonSchema(schema.items, v, `${where}[${i}]`, issues));
+        value.forEach((v, i) => checkJsonSchema(schema.items, v, `${where}[${i}]`, issues, root));
     }
     if (value !== null && typeof value === "object" && !Array.isArray(value)) {
       if (Array.isArray(schema.required)) {
@@ -922,7 +1048,7 @@ pub(crate) const INPUT_SCHEMA_SCRIPT: &str = r#"
       if (schema.properties) {
         for (const key of Object.keys(schema.properties)) {
           if (key in value)
-            checkJsonSchema(schema.properties[key], value[key], path ? `${path}.${key}` : key, issues);
+            checkJsonSchema(schema.properties[key], value[key], path ? `${path}.${key}` : key, issues, root);
         }
       }
       if (schema.additionalProperties === false) {
@@ -934,36 +1060,68 @@ pub(crate) const INPUT_SCHEMA_SCRIPT: &str = r#"
     }
   }
 
-  const fail = (lines) => {
-    const err = new Error("invalid input:\n" + lines.map((l) => "  - " + l).join("\n"));
-    err.name = "InputValidationError";
-    throw err;
-  };
+  const isStandardSchema = (schema) =>
+    schema &&
+    typeof schema === "object" &&
+    schema["~standard"] &&
+    typeof schema["~standard"].validate === "function";
 
-  globalThis.__chidori_validate_input = async function (schema, input) {
-    if (
-      schema &&
-      typeof schema === "object" &&
-      schema["~standard"] &&
-      typeof schema["~standard"].validate === "function"
-    ) {
-      let result = schema["~standard"].validate(input);
+  // Every issue `value` has against `schema` (either form), plus the value a
+  // Standard Schema validator may have defaulted/coerced. `root` names the
+  // value in issues that point at it directly.
+  async function schemaIssues(schema, value, root) {
+    if (isStandardSchema(schema)) {
+      let result = schema["~standard"].validate(value);
       if (result && typeof result.then === "function") result = await result;
       if (result.issues) {
-        fail(
-          result.issues.map((i) =>
+        return {
+          value,
+          issues: result.issues.map((i) =>
             i.path && i.path.length ? `${i.message} (at ${fmtPath(i.path)})` : i.message,
           ),
-        );
+        };
       }
-      return result.value;
+      return { value: result.value, issues: [] };
     }
     const issues = [];
-    checkJsonSchema(schema, input, "", issues);
+    checkJsonSchema(schema, value, "", issues, root || "value");
+    return { value, issues };
+  }
+  globalThis.__chidori_schema_issues = schemaIssues;
+
+  // The JSON Schema for `schema`: itself when it is one, else what a Standard
+  // Schema validator exposes through `~standard.jsonSchema` (Standard JSON
+  // Schema), else null — the reply is then validated but not enforced.
+  globalThis.__chidori_json_schema_of = function (schema) {
+    if (!isStandardSchema(schema)) {
+      return schema && typeof schema === "object" ? schema : null;
+    }
+    const converter = schema["~standard"].jsonSchema;
+    if (converter && typeof converter.output === "function") {
+      try {
+        return converter.output({ target: "draft-2020-12" });
+      } catch (_) {
+        return null;
+      }
+    }
+    return null;
+  };
+
+  const fail = (lines) => {
+    const err = new Error("invalid input:\n" + lines.map((l) => "  - " + l).join("\n"));
+    err.name = "InputValidationError";
+    throw err;
+  };
+
+  globalThis.__chidori_validate_input = async function (schema, input) {
+    const { value, issues } = await schemaIssues(schema, input, "input");
     if (issues.length) fail(issues);
-    return input;
+    return value;
   };
 
+  const nativeRun = globalThis.run;
+  if (typeof nativeRun !== "function") return;
+
   globalThis.run = function run(handler, options) {
     const schema = options && options.inputSchema;
     if (typeof handler !== "function" || schema == null) return nativeRun(handler);
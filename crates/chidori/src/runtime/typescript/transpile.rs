@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::path::{Path, PathBuf};
 
 use anyhow::Result;
@@ -397,6 +397,81 @@ pub fn remap_to_original(
     })
 }
 
+/// A module's transpiled text and the source map back to its `.ts`,
+/// flattened for lookups by byte offset into the transpiled text: how the
+/// debugger and the profiler place engine positions in the original source.
+pub(crate) struct ModuleMap {
+    /// Canonical path of the original source.
+    pub(crate) path: PathBuf,
+    pub(crate) js: String,
+    line_starts: Vec<usize>,
+    /// Per transpiled line: `(utf16 column, original line0, original utf16
+    /// column)` for each mapping, by column.
+    tokens: Vec<Vec<(u32, u32, u32)>>,
+}
+
+impl ModuleMap {
+    /// The map for the module at `path`, transpiling `source` (read from
+    /// disk when `None`) the way the loader does.
+    pub(crate) fn load(path: &Path, source: Option<&str>) -> Option<ModuleMap> {
+        let ext = path.extension()?.to_str()?;
+        if !matches!(ext, "ts" | "tsx" | "mts" | "cts" | "js" | "jsx" | "mjs") {
+            return None;
+        }
+        if path.components().any(|c| c.as_os_str() == "node_modules") {
+            return None;
+        }
+        let source = match source {
+            Some(source) => source.to_string(),
+            None => std::fs::read_to_string(path).ok()?,
+        };
+        let (js, map) = transpile_source_with_map(path, &source).ok()?;
+        let mut line_starts = vec![0];
+        line_starts.extend(js.match_indices('\n').map(|(i, _)| i + 1));
+        let mut tokens: Vec<Vec<(u32, u32, u32)>> = vec![Vec::new(); line_starts.len()];
+        for token in map.get_tokens() {
+            if let Some(line) = tokens.get_mut(token.get_dst_line() as usize) {
+                line.push((
+                    token.get_dst_col(),
+                    token.get_src_line(),
+                    token.get_src_col(),
+                ));
+            }
+        }
+        for line in &mut tokens {
+            line.sort_unstable();
+        }
+        Some(ModuleMap {
+            path: path.canonicalize().unwrap_or_else(|_| path.to_path_buf()),
+            js,
+            line_starts,
+            tokens,
+        })
+    }
+
+    /// 1-based original lines some transpiled code maps to.
+    pub(crate) fn breakable_lines(&self) -> BTreeSet<u32> {
+        self.tokens.iter().flatten().map(|t| t.1 + 1).collect()
+    }
+
+    /// 1-based original `(line, column)` of byte offset `pos` in the
+    /// transpiled text.
+    pub(crate) fn original(&self, pos: u32) -> Option<(u32, u32)> {
+        let pos = pos as usize;
+        let line0 = self.line_starts.partition_point(|&start| start <= pos) - 1;
+        let start = self.line_starts[line0];
+        let col16 = self.js.get(start..pos)?.encode_utf16().count() as u32;
+        let tokens = self.tokens.get(line0)?;
+        let i = tokens.partition_point(|t| t.0 <= col16);
+        let token = if i == 0 {
+            tokens.first()?
+        } else {
+            &tokens[i - 1]
+        };
+        Some((token.1 + 1, token.2 + 1))
+    }
+}
+
 /// Byte offset of the first dynamic `import(...)` expression in `source`, or
 /// `None` if there is none — or if the source fails to parse (the transpile
 /// step owns parse-error reporting).
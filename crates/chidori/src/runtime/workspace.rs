@@ -95,6 +95,30 @@ pub fn read(root: &Path, path: &str) -> Result<String, String> {
         .map_err(|err| format!("workspace.read {}: {err}", relative.display()))
 }
 
+/// Binary counterpart of [`read`], for media attached to prompts (images,
+/// PDFs) — same sanitization and symlink refusal. Refuses a file over `limit`
+/// bytes without reading more than one byte past it.
+pub fn read_bytes(root: &Path, path: &str, limit: u64) -> Result<Vec<u8>, String> {
+    use std::io::Read as _;
+
+    let relative = sanitize_path(path)?;
+    let absolute = workspace_path(root, &relative)?;
+    ensure_no_symlink_path(root, &absolute)?;
+    let file = std::fs::File::open(&absolute)
+        .map_err(|err| format!("workspace.read {}: {err}", relative.display()))?;
+    let mut bytes = Vec::new();
+    file.take(limit + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|err| format!("workspace.read {}: {err}", relative.display()))?;
+    if bytes.len() as u64 > limit {
+        return Err(format!(
+            "workspace.read {}: over the {limit}-byte limit",
+            relative.display()
+        ));
+    }
+    Ok(bytes)
+}
+
 pub fn write(root: &Path, path: &str, content: &str, options: &Value) -> Result<Value, String> {
     let relative = sanitize_path(path)?;
     let absolute = workspace_path(root, &relative)?;
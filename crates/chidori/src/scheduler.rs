@@ -3,22 +3,46 @@
 //! engine pipeline. Results are persisted through the session store so they
 //! show up under `GET /sessions` just like interactive runs.
 //!
-//! Cron expression semantics are delegated to the `cron` crate. A missing
-//! schedule on a recipe simply means "no scheduling", and the scheduler
-//! skips it.
+//! Cron expression semantics are delegated to the `cron` crate, evaluated in
+//! the recipe's `timezone`. A missing schedule on a recipe simply means "no
+//! scheduling", and the scheduler skips it. Each scheduled session records
+//! the tick it ran for (`StoredSession::scheduled_for`); on startup a recipe
+//! with `catch_up` resumes from the latest one, and `overlap` decides what a
+//! tick does while the previous run is still going.
+//!
+//! Several `chidori serve` replicas may schedule the same recipes. Each tick
+//! has a deterministic id ([`tick_id`]) — the session id of its run, and the
+//! run store key its lease lives under. A replica runs a tick only while it
+//! holds that lease (`crate::runtime::store::acquire_lease`), and a finished
+//! tick leaves a marker in the same store, so every other replica — and any
+//! retry of the tick — stands down instead of running it again. A replica
+//! whose lease lapsed mid-run is fenced at settle: it records the tick only if
+//! it still holds the lease and its marker is the first, so a late finisher
+//! never overwrites the session of the replica that took over. How strong
+//! that exclusion is follows the run store backend's lease guarantee
+//! (`docs/durable-storage.md` §Leases).
 
+use std::collections::VecDeque;
+use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, OnceLock};
+use std::time::Duration;
 
-use anyhow::{Context, Result};
-use chrono::Utc;
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
 use cron::Schedule;
+use rand::Rng;
+use tokio::sync::mpsc;
 
 use crate::mcp::McpManager;
 use crate::policy::PolicyConfig;
 use crate::providers::ProviderRegistry;
-use crate::recipes::Recipe;
+use crate::recipes::{CatchUp, Overlap, Recipe, MAX_CATCH_UP_TICKS};
+use crate::runtime::cost::CostQuota;
 use crate::runtime::engine::Engine;
+use crate::runtime::metrics;
+use crate::runtime::store::{RunLease, RunStore, RunStoreFactory};
 use crate::runtime::template::TemplateEngine;
 use crate::storage::{SessionStatus, SessionStore, StoredSession};
 use crate::tools::ToolRegistry;
@@ -73,92 +97,555 @@ pub struct SchedulerDeps {
     pub template_engine: Arc<TemplateEngine>,
     pub session_store: Arc<dyn SessionStore>,
     pub policy: Arc<PolicyConfig>,
-    pub mcp: Arc<McpManager>,
-    /// Extra tool defs loaded from MCP servers — injected into each scheduled
+    /// MCP servers — their current tools are injected into each scheduled
     /// run's ToolRegistry so cron-launched agents can use the same MCP tools
     /// as interactive sessions.
-    pub mcp_tools: Vec<crate::tools::ToolDef>,
+    pub mcp: Arc<McpManager>,
+    /// The server's run directory. Tick leases live in its run store, so
+    /// replicas sharing a durable mirror (`CHIDORI_RUN_STORE`) agree on
+    /// which of them runs each tick.
+    pub run_base: PathBuf,
+    /// Workspace (and memory) root of the runs; a server tenant's runs stay
+    /// in the tenant's directory. `None` leaves the context default.
+    pub workspace_root: Option<PathBuf>,
+    /// Spend ceiling shared with the rest of the tenant's sessions; each run's
+    /// budget is capped at what it has left.
+    pub quota: Option<CostQuota>,
 }
 
 /// Spawn a background task for every recipe that has a schedule set.
 pub fn spawn_all(recipes: Vec<Recipe>, deps: SchedulerDeps) {
     for recipe in recipes {
-        let Some(cron_expr) = recipe.schedule.clone() else {
-            continue;
-        };
-        let schedule = match Schedule::from_str(&cron_expr) {
-            Ok(s) => s,
+        let schedule = match RecipeSchedule::for_recipe(&recipe) {
+            Ok(Some(schedule)) => schedule,
+            Ok(None) => continue,
             Err(e) => {
-                tracing::warn!(
-                    "recipe `{}`: invalid cron `{}`: {}",
-                    recipe.name,
-                    cron_expr,
-                    e
-                );
+                tracing::warn!("recipe `{}`: invalid schedule: {:#}", recipe.name, e);
                 continue;
             }
         };
-        let deps = deps.clone();
-        let recipe_for_task = recipe.clone();
-        tokio::spawn(async move {
-            run_loop(recipe_for_task, schedule, deps).await;
-        });
-        tracing::info!("scheduled recipe `{}` with `{}`", recipe.name, cron_expr);
+        tracing::info!(
+            "scheduled recipe `{}` with `{}` ({})",
+            recipe.name,
+            recipe.schedule.as_deref().unwrap_or_default(),
+            schedule.tz
+        );
+        tokio::spawn(run_loop(recipe, schedule, deps.clone()));
     }
 }
 
-async fn run_loop(recipe: Recipe, schedule: Schedule, deps: SchedulerDeps) {
+/// A recipe's cron schedule, read in the recipe's timezone.
+pub struct RecipeSchedule {
+    schedule: Schedule,
+    tz: chrono_tz::Tz,
+}
+
+impl RecipeSchedule {
+    /// `None` when the recipe has no schedule. Accepts the documented 5-field
+    /// cron form as well as the `cron` crate's 6/7-field one.
+    pub fn for_recipe(recipe: &Recipe) -> Result<Option<Self>> {
+        let Some(expr) = recipe.schedule.as_deref() else {
+            return Ok(None);
+        };
+        let expr = crate::app_manifest::normalize_cron(expr)?;
+        let schedule = Schedule::from_str(&expr).map_err(|e| anyhow!("`{expr}`: {e}"))?;
+        Ok(Some(Self {
+            schedule,
+            tz: recipe.timing.tz()?,
+        }))
+    }
+
+    /// The first tick strictly after `after`.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.schedule
+            .after(&after.with_timezone(&self.tz))
+            .next()
+            .map(|tick| tick.with_timezone(&Utc))
+    }
+
+    /// Ticks in `(after, until]`, oldest first — only the newest `limit`.
+    fn ticks_between(
+        &self,
+        after: DateTime<Utc>,
+        until: DateTime<Utc>,
+        limit: usize,
+    ) -> Vec<DateTime<Utc>> {
+        let mut ticks = VecDeque::new();
+        for tick in self.schedule.after(&after.with_timezone(&self.tz)) {
+            let tick = tick.with_timezone(&Utc);
+            if tick > until {
+                break;
+            }
+            if ticks.len() == limit {
+                ticks.pop_front();
+            }
+            ticks.push_back(tick);
+        }
+        ticks.into()
+    }
+}
+
+async fn run_loop(recipe: Recipe, schedule: RecipeSchedule, deps: SchedulerDeps) {
+    let runner = TickRunner::spawn(recipe.clone(), deps.clone());
+    // Catch-up resumes after the last tick any previous process ran. Without
+    // one — or with `catch_up: none` — scheduling simply starts from now.
+    let mut cursor = Utc::now();
+    if recipe.timing.catch_up != CatchUp::None {
+        match deps.session_store.latest_scheduled(&recipe.name) {
+            Ok(last) => {
+                if let Some(last) = last.and_then(|s| s.scheduled_for) {
+                    cursor = cursor.min(last);
+                }
+            }
+            Err(e) => tracing::warn!("recipe `{}`: reading its last tick: {}", recipe.name, e),
+        }
+    }
     loop {
-        let Some(next) = schedule.upcoming(Utc).next() else {
+        // Ticks already due: missed while the server was down, or while this
+        // loop was stalled past them.
+        let overdue = schedule.ticks_between(cursor, Utc::now(), MAX_CATCH_UP_TICKS);
+        if let Some(&latest) = overdue.last() {
+            let replay = match recipe.timing.catch_up {
+                CatchUp::None => &overdue[..0],
+                CatchUp::Latest => &overdue[overdue.len() - 1..],
+                CatchUp::All => &overdue[..],
+            };
+            tracing::info!(
+                "recipe `{}`: {} missed tick(s), catching up {}",
+                recipe.name,
+                overdue.len(),
+                replay.len()
+            );
+            for tick in replay {
+                runner.fire(*tick, true);
+            }
+            cursor = latest;
+            continue;
+        }
+
+        let Some(next) = schedule.next_after(cursor) else {
             tracing::warn!("recipe `{}`: schedule has no future ticks", recipe.name);
             return;
         };
-        let wait = (next - Utc::now()).num_milliseconds().max(0) as u64;
-        tokio::time::sleep(std::time::Duration::from_millis(wait)).await;
+        let wait = (next - Utc::now()).to_std().unwrap_or_default();
+        tokio::time::sleep(wait + jitter(recipe.timing.jitter_secs)).await;
+        runner.fire(next, false);
+        cursor = next;
+    }
+}
+
+/// A random delay in `[0, max_secs]`.
+fn jitter(max_secs: Option<u64>) -> Duration {
+    match max_secs {
+        Some(max) if max > 0 => Duration::from_millis(rand::rng().random_range(0..=max * 1000)),
+        _ => Duration::ZERO,
+    }
+}
+
+/// Runs one recipe's ticks under its `overlap` policy. Skipped and queued
+/// ticks go through a single worker, so they never run concurrently.
+struct TickRunner {
+    recipe: Recipe,
+    deps: SchedulerDeps,
+    queue: mpsc::UnboundedSender<DateTime<Utc>>,
+    /// Ticks queued on, or running in, the worker.
+    in_flight: Arc<AtomicUsize>,
+}
+
+impl TickRunner {
+    fn spawn(recipe: Recipe, deps: SchedulerDeps) -> Self {
+        let (queue, mut ticks) = mpsc::unbounded_channel::<DateTime<Utc>>();
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let worker = (recipe.clone(), deps.clone(), in_flight.clone());
+        tokio::spawn(async move {
+            let (recipe, deps, in_flight) = worker;
+            while let Some(tick) = ticks.recv().await {
+                run_tick(&recipe, &deps, tick).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+            }
+        });
+        Self {
+            recipe,
+            deps,
+            queue,
+            in_flight,
+        }
+    }
+
+    /// Run `tick`. Catch-up ticks are never skipped — they are the backlog
+    /// — but they still run one at a time unless the recipe allows overlap.
+    fn fire(&self, tick: DateTime<Utc>, catching_up: bool) {
+        match self.recipe.timing.overlap {
+            Overlap::Allow => {
+                let (recipe, deps) = (self.recipe.clone(), self.deps.clone());
+                tokio::spawn(async move { run_tick(&recipe, &deps, tick).await });
+            }
+            Overlap::Skip if !catching_up && self.in_flight.load(Ordering::SeqCst) > 0 => {
+                tracing::info!(
+                    "recipe `{}`: skipping the {} tick; the previous run is still going",
+                    self.recipe.name,
+                    tick
+                );
+                count_tick(&self.recipe, "skipped_overlap");
+            }
+            Overlap::Skip | Overlap::Queue => {
+                self.in_flight.fetch_add(1, Ordering::SeqCst);
+                let _ = self.queue.send(tick);
+            }
+        }
+    }
+}
+
+/// How long a replica's claim on a tick lasts unrenewed. The claim is renewed
+/// at a third of this while the run goes, so only a replica that died
+/// mid-tick lets it lapse — and then another replica may take the tick over.
+const TICK_LEASE_TTL_SECS: i64 = 300;
+
+/// Written to a tick's run store once the tick has run: the session it
+/// produced. Its presence is what makes the tick done for every replica.
+const TICK_FILE: &str = "tick.json";
+
+/// The deterministic id of `recipe`'s run for `tick`: every replica derives
+/// the same one. It is the session id of the tick's run and the run store key
+/// the tick's lease and done-marker live under. Bytes other than ASCII
+/// alphanumerics, `-` and `.` are escaped as `_XX` (hex; `_` itself too), so
+/// distinct recipe names never share an id.
+pub fn tick_id(recipe: &str, tick: DateTime<Utc>) -> String {
+    let mut name = String::with_capacity(recipe.len());
+    for byte in recipe.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'.' {
+            name.push(byte as char);
+        } else {
+            name.push_str(&format!("_{byte:02X}"));
+        }
+    }
+    format!("tick-{name}-{}", tick.format("%Y%m%dT%H%M%SZ"))
+}
+
+/// What a replica found when it went to run a tick.
+enum TickClaim {
+    /// This replica holds the tick's lease and should run it.
+    Claimed,
+    /// The tick already ran (here or on another replica) as this session.
+    Done(String),
+    /// Another replica holds the tick's lease.
+    Held(RunLease),
+}
+
+async fn run_tick(recipe: &Recipe, deps: &SchedulerDeps, tick: DateTime<Utc>) {
+    run_tick_as(
+        recipe,
+        deps,
+        tick,
+        crate::runtime::host_agent::process_lease_owner(),
+    )
+    .await
+}
+
+/// Run `tick` unless another replica has it or already ran it. `owner`
+/// identifies this replica in the tick's lease.
+async fn run_tick_as(recipe: &Recipe, deps: &SchedulerDeps, tick: DateTime<Utc>, owner: &str) {
+    let id = tick_id(&recipe.name, tick);
+    let store = RunStoreFactory::shared(&deps.run_base).store_for(&id);
+    let ttl = chrono::Duration::seconds(TICK_LEASE_TTL_SECS);
+
+    // Store calls may block (the SQLite mirror, the HTTP relay), so they run
+    // off the async workers.
+    let claim = {
+        let (store, owner, sessions, id) = (
+            store.clone(),
+            owner.to_string(),
+            deps.session_store.clone(),
+            id.clone(),
+        );
+        tokio::task::spawn_blocking(move || {
+            claim_tick(store.as_ref(), &owner, ttl, &*sessions, &id)
+        })
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|claim| claim)
+    };
+    match claim {
+        Ok(TickClaim::Claimed) => {}
+        Ok(TickClaim::Done(session)) => {
+            tracing::info!(
+                "recipe `{}`: the {} tick already ran as session `{}`",
+                recipe.name,
+                tick,
+                session
+            );
+            count_tick(recipe, "already_ran");
+            return;
+        }
+        Ok(TickClaim::Held(holder)) => {
+            tracing::info!(
+                "recipe `{}`: the {} tick is leased to `{}` until {}; leaving it to them",
+                recipe.name,
+                tick,
+                holder.owner,
+                holder.expires_at
+            );
+            count_tick(recipe, "leased_elsewhere");
+            recheck_after(recipe, deps, tick, owner, holder.expires_at);
+            return;
+        }
+        // Same stance as the detached-agent supervisor: the lease guards
+        // against a second runner, not against running at all.
+        Err(e) => tracing::warn!(
+            "recipe `{}`: claiming the {} tick: {:#}; running it anyway",
+            recipe.name,
+            tick,
+            e
+        ),
+    }
+
+    let renew = {
+        let (store, owner, name) = (store.clone(), owner.to_string(), recipe.name.clone());
+        tokio::spawn(async move {
+            let mut every = tokio::time::interval((ttl / 3).to_std().unwrap_or_default());
+            every.tick().await;
+            loop {
+                every.tick().await;
+                let (store, owner) = (store.clone(), owner.clone());
+                let renewed = tokio::task::spawn_blocking(move || {
+                    crate::runtime::store::acquire_lease(store.as_ref(), &owner, ttl)
+                })
+                .await;
+                if let Ok(Ok(Err(holder))) = renewed {
+                    tracing::warn!(
+                        "recipe `{}`: lost the {} tick's lease to `{}` mid-run",
+                        name,
+                        tick,
+                        holder.owner
+                    );
+                    return;
+                }
+            }
+        })
+    };
+    let outcome = execute_recipe(recipe, deps, Some(tick)).await;
+    renew.abort();
+
+    let session = match outcome {
+        Ok(session) => {
+            if let Some(error) = &session.error {
+                tracing::warn!("recipe `{}` run failed: {}", recipe.name, error);
+            }
+            Some(session)
+        }
+        Err(e) => {
+            tracing::warn!("recipe `{}` run failed: {:#}", recipe.name, e);
+            None
+        }
+    };
+    // Failed runs are done too: their session records the failure, and a
+    // retry would only repeat it on another replica.
+    let settled = {
+        let (owner, sessions, session) = (
+            owner.to_string(),
+            deps.session_store.clone(),
+            session.clone(),
+        );
+        tokio::task::spawn_blocking(move || {
+            settle_tick(
+                store.as_ref(),
+                &owner,
+                ttl,
+                &*sessions,
+                &id,
+                session.as_ref(),
+            )
+        })
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|settled| settled)
+    };
+    match settled {
+        Ok(true) if session.is_some_and(|s| s.error.is_none()) => count_tick(recipe, "completed"),
+        Ok(true) => count_tick(recipe, "failed"),
+        Ok(false) => {
+            tracing::warn!(
+                "recipe `{}`: lost the {} tick to another replica before it settled; \
+                 discarding this run's result",
+                recipe.name,
+                tick
+            );
+            count_tick(recipe, "fenced");
+        }
+        Err(e) => {
+            tracing::warn!(
+                "recipe `{}`: recording the {} tick as done: {:#}",
+                recipe.name,
+                tick,
+                e
+            );
+            count_tick(recipe, "failed");
+        }
+    }
+}
+
+/// A replica that stood down from a leased tick looks again once the lease
+/// would expire: if its holder died mid-tick, nobody else may be left to
+/// notice, since every other replica stood down too. A live holder keeps
+/// renewing, so the recheck finds it held (and waits again) or done.
+fn recheck_after(
+    recipe: &Recipe,
+    deps: &SchedulerDeps,
+    tick: DateTime<Utc>,
+    owner: &str,
+    expires_at: DateTime<Utc>,
+) {
+    let (recipe, deps, owner) = (recipe.clone(), deps.clone(), owner.to_string());
+    let wait = (expires_at - Utc::now()).to_std().unwrap_or_default() + Duration::from_secs(1);
+    tokio::spawn(async move {
+        tokio::time::sleep(wait).await;
+        run_tick_as(&recipe, &deps, tick, &owner).await;
+    });
+}
+
+/// Record a run of the tick, fenced against a replica that took it over:
+/// only a replica still holding the lease, whose done-marker is the first,
+/// writes the session. False when this replica lost the tick.
+fn settle_tick(
+    store: &dyn RunStore,
+    owner: &str,
+    ttl: chrono::Duration,
+    sessions: &dyn SessionStore,
+    id: &str,
+    session: Option<&StoredSession>,
+) -> Result<bool> {
+    match crate::runtime::store::acquire_lease(store, owner, ttl) {
+        Ok(Ok(_)) => {}
+        Ok(Err(_)) => return Ok(false),
+        // As at claim: an unreadable lease does not stop the run; the
+        // marker's compare-and-swap still admits only one settler.
+        Err(e) => tracing::warn!("tick `{id}`: renewing its lease to settle: {e:#}"),
+    }
+    let marker = serde_json::to_vec(&serde_json::json!({ "session_id": id }))?;
+    if !store.compare_and_swap_blob(TICK_FILE, None, Some(&marker))? {
+        return Ok(false);
+    }
+    // The marker goes first: a crash before the session is written leaves
+    // the tick done without a session, rather than open to a second run.
+    if let Some(session) = session {
+        sessions.put(session)?;
+    }
+    crate::runtime::store::release_lease(store, owner)?;
+    Ok(true)
+}
 
-        if let Err(e) = run_once(&recipe, &deps).await {
-            tracing::warn!("recipe `{}` run failed: {}", recipe.name, e);
+/// Count one tick of `recipe` in `chidori_scheduler_ticks_total`.
+fn count_tick(recipe: &Recipe, outcome: &str) {
+    metrics::inc(
+        metrics::SCHEDULER_TICKS,
+        &[("recipe", &recipe.name), ("outcome", outcome)],
+        1.0,
+    );
+}
+
+/// Decide whether this replica runs the tick: done if its marker (or its
+/// session) exists, else whoever wins the lease. Checked again after the
+/// lease is won, because the previous holder may have finished in between.
+fn claim_tick(
+    store: &dyn RunStore,
+    owner: &str,
+    ttl: chrono::Duration,
+    sessions: &dyn SessionStore,
+    id: &str,
+) -> Result<TickClaim> {
+    let done = || -> Result<Option<String>> {
+        let target = store.coordination_target().unwrap_or(store);
+        if let Some(bytes) = target.get_blob(TICK_FILE)? {
+            let marker: serde_json::Value = serde_json::from_slice(&bytes)?;
+            let session = marker["session_id"].as_str().unwrap_or(id);
+            return Ok(Some(session.to_string()));
         }
+        Ok(sessions.get(id)?.map(|session| session.id))
+    };
+    if let Some(session) = done()? {
+        return Ok(TickClaim::Done(session));
+    }
+    if let Err(holder) = crate::runtime::store::acquire_lease(store, owner, ttl)? {
+        return Ok(TickClaim::Held(holder));
     }
+    if let Some(session) = done()? {
+        crate::runtime::store::release_lease(store, owner)?;
+        return Ok(TickClaim::Done(session));
+    }
+    Ok(TickClaim::Claimed)
 }
 
-/// One-shot invocation of a recipe. Exposed for an explicit "trigger now"
-/// endpoint; the scheduler loop also funnels through here.
+/// One-shot invocation of a recipe, for the explicit "trigger now" endpoint.
+/// Its session is recorded — failed runs included, so `GET /recipes` can
+/// report the outcome.
 pub async fn run_once(recipe: &Recipe, deps: &SchedulerDeps) -> Result<String> {
+    let session = execute_recipe(recipe, deps, None).await?;
+    deps.session_store.put(&session)?;
+    match session.error {
+        Some(error) => Err(anyhow!(error)),
+        None => Ok(session.id),
+    }
+}
+
+/// Run `recipe` into the session that records it, without storing it. A
+/// scheduled run's session id is its [`tick_id`].
+async fn execute_recipe(
+    recipe: &Recipe,
+    deps: &SchedulerDeps,
+    scheduled_for: Option<DateTime<Utc>>,
+) -> Result<StoredSession> {
     let recipe = recipe.clone();
     let agent_path = recipe.agent.clone();
     let inputs = recipe.inputs.clone();
     let deps = deps.clone();
     let recipe_name = recipe.name.clone();
+    let quota_store = deps.session_store.clone();
 
-    let (id, session) = tokio::task::spawn_blocking(move || -> Result<(String, StoredSession)> {
+    let session = tokio::task::spawn_blocking(move || -> Result<StoredSession> {
         let rt = crate::scheduler::shared_tokio_runtime()?;
         let providers = deps.providers.clone();
 
         // The registry holds only externally-sourced tools (MCP servers the
         // server handed us). Agent tools are defined in-VM with `defineTool`.
         let mut registry = ToolRegistry::new();
-        for def in &deps.mcp_tools {
-            registry.register(def.clone());
+        for def in deps.mcp.tool_defs() {
+            registry.register(def);
         }
 
-        let engine = Engine::new(providers, deps.template_engine.clone(), rt)
+        let budget = match deps.quota {
+            Some(quota) => {
+                let spent = crate::storage::spend_since(&*quota_store, quota.since(Utc::now()))?;
+                quota
+                    .budget_for(spent, recipe.budget)
+                    .map(Some)
+                    .ok_or_else(|| {
+                        anyhow!("budget: the ${:.2} cost quota is spent", quota.max_cost_usd)
+                    })
+            }
+            None => Ok(recipe.budget),
+        };
+        let mut engine = Engine::new(providers, deps.template_engine.clone(), rt)
             .with_tools(Arc::new(registry))
             .with_policy(deps.policy.clone())
             .with_mcp(deps.mcp.clone());
+        if let Some(root) = deps.workspace_root.clone() {
+            engine = engine.with_workspace_root(root);
+        }
 
-        let result = engine
-            .run(&agent_path, &inputs)
-            .with_context(|| format!("recipe `{}` execution", recipe_name))?;
+        let outcome = budget
+            .and_then(|budget| engine.with_budget(budget).run(&agent_path, &inputs))
+            .with_context(|| format!("recipe `{}` execution", recipe_name));
 
-        let id = uuid::Uuid::new_v4().to_string();
-        let session = StoredSession {
-            id: id.clone(),
-            run_id: Some(result.run_id),
+        let mut session = StoredSession {
+            id: match scheduled_for {
+                Some(tick) => tick_id(&recipe_name, tick),
+                None => uuid::Uuid::new_v4().to_string(),
+            },
+            run_id: None,
             status: SessionStatus::Completed,
             input: inputs,
-            output: Some(result.output),
-            call_log: result.call_log.into_records(),
+            output: None,
+            call_log: Vec::new(),
             error: None,
             pending_seq: None,
             pending_prompt: None,
@@ -169,14 +656,27 @@ pub async fn run_once(recipe: &Recipe, deps: &SchedulerDeps) -> Result<String> {
             pending_approval: None,
             approvals: Vec::new(),
             policy_profile: None,
+            agent: Some(agent_path.display().to_string()),
+            recipe: Some(recipe_name),
+            scheduled_for,
+            tenant: None,
             created_at: Utc::now(),
         };
-        Ok((id, session))
+        match outcome {
+            Ok(result) => {
+                session.run_id = Some(result.run_id);
+                session.output = Some(result.output);
+                session.call_log = result.call_log.into_records();
+            }
+            Err(e) => {
+                session.status = SessionStatus::Failed;
+                session.error = Some(format!("{e:#}"));
+            }
+        }
+        Ok(session)
     })
     .await??;
-
-    deps.session_store.put(&session)?;
-    Ok(id)
+    Ok(session)
 }
 
 #[cfg(test)]
@@ -210,3 +710,279 @@ mod stack_tests {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recipes::ScheduleTiming;
+    use crate::runtime::template::TemplateEngine;
+    use crate::storage::MemoryStore;
+    use chrono::{DurationRound, TimeDelta};
+
+    fn recipe(dir: &std::path::Path, schedule: &str, timing: ScheduleTiming) -> Recipe {
+        let agent = dir.join("agent.ts");
+        std::fs::write(&agent, "run(async () => ({ ok: true }));\n").unwrap();
+        Recipe {
+            name: "digest".to_string(),
+            agent,
+            schedule: Some(schedule.to_string()),
+            inputs: serde_json::json!({}),
+            description: None,
+            budget: None,
+            timing,
+        }
+    }
+
+    fn deps(run_base: &std::path::Path) -> SchedulerDeps {
+        SchedulerDeps {
+            providers: Arc::new(ProviderRegistry::new()),
+            template_engine: Arc::new(TemplateEngine::new(".")),
+            session_store: Arc::new(MemoryStore::new()),
+            policy: PolicyConfig::from_env(),
+            mcp: Arc::new(McpManager::new()),
+            run_base: run_base.join("runs"),
+            workspace_root: None,
+            quota: None,
+        }
+    }
+
+    fn utc(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    async fn scheduled_ticks(deps: &SchedulerDeps, want: usize) -> Vec<DateTime<Utc>> {
+        for _ in 0..400 {
+            let mut ticks: Vec<_> = deps
+                .session_store
+                .list()
+                .unwrap()
+                .into_iter()
+                .filter_map(|s| s.scheduled_for)
+                .collect();
+            if ticks.len() >= want {
+                ticks.sort();
+                return ticks;
+            }
+            tokio::time::sleep(Duration::from_millis(25)).await;
+        }
+        panic!("expected {want} scheduled sessions");
+    }
+
+    #[test]
+    fn schedules_follow_the_recipe_timezone_across_dst() {
+        let dir = tempfile::tempdir().unwrap();
+        let timing = ScheduleTiming {
+            timezone: Some("America/New_York".to_string()),
+            ..Default::default()
+        };
+        // 5-field cron, as documented.
+        let schedule = RecipeSchedule::for_recipe(&recipe(dir.path(), "0 9 * * *", timing))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            schedule.next_after(utc("2026-01-15T00:00:00Z")),
+            Some(utc("2026-01-15T14:00:00Z"))
+        );
+        assert_eq!(
+            schedule.next_after(utc("2026-07-15T00:00:00Z")),
+            Some(utc("2026-07-15T13:00:00Z"))
+        );
+
+        let bad = ScheduleTiming {
+            timezone: Some("Mars/Olympus".to_string()),
+            ..Default::default()
+        };
+        assert!(RecipeSchedule::for_recipe(&recipe(dir.path(), "0 9 * * *", bad)).is_err());
+    }
+
+    #[test]
+    fn ticks_between_keeps_only_the_newest_ticks() {
+        let dir = tempfile::tempdir().unwrap();
+        let schedule =
+            RecipeSchedule::for_recipe(&recipe(dir.path(), "0 * * * *", Default::default()))
+                .unwrap()
+                .unwrap();
+        let from = utc("2026-05-17T09:00:00Z");
+        let ticks = schedule.ticks_between(from, utc("2026-05-17T12:30:00Z"), 2);
+        assert_eq!(
+            ticks,
+            vec![utc("2026-05-17T11:00:00Z"), utc("2026-05-17T12:00:00Z")]
+        );
+        assert!(schedule
+            .ticks_between(from, utc("2026-05-17T09:59:59Z"), 10)
+            .is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn catch_up_replays_ticks_missed_since_the_last_scheduled_run() {
+        for (catch_up, expected) in [(CatchUp::All, 3), (CatchUp::Latest, 1)] {
+            let dir = tempfile::tempdir().unwrap();
+            let timing = ScheduleTiming {
+                catch_up,
+                ..Default::default()
+            };
+            let recipe = recipe(dir.path(), "0 * * * * *", timing);
+            let deps = deps(dir.path());
+            // The last scheduled run was three minutes ago: the ticks at -2,
+            // -1 and 0 minutes were missed.
+            let minute = Utc::now().duration_trunc(TimeDelta::minutes(1)).unwrap();
+            let last: StoredSession = serde_json::from_value(serde_json::json!({
+                "id": "previous", "status": "completed", "input": {}, "output": null,
+                "call_log": [], "error": null, "pending_seq": null, "pending_prompt": null,
+                "recipe": "digest",
+                "scheduled_for": minute - TimeDelta::minutes(3),
+                "created_at": minute - TimeDelta::minutes(3),
+            }))
+            .unwrap();
+            deps.session_store.put(&last).unwrap();
+
+            spawn_all(vec![recipe], deps.clone());
+            let ticks = scheduled_ticks(&deps, 1 + expected).await;
+            let caught_up: Vec<_> = ticks[1..=expected].to_vec();
+            let want: Vec<_> = (0..expected as i64)
+                .rev()
+                .map(|ago| minute - TimeDelta::minutes(ago))
+                .collect();
+            assert_eq!(caught_up, want, "{catch_up:?}");
+            let sessions = deps.session_store.list().unwrap();
+            assert!(sessions
+                .iter()
+                .filter(|s| s.id != "previous")
+                .all(|s| s.status == SessionStatus::Completed
+                    && s.recipe.as_deref() == Some("digest")));
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn overlapping_ticks_are_skipped_or_queued_by_policy() {
+        for (overlap, expected) in [(Overlap::Skip, 1), (Overlap::Queue, 2)] {
+            let dir = tempfile::tempdir().unwrap();
+            let timing = ScheduleTiming {
+                overlap,
+                ..Default::default()
+            };
+            let deps = deps(dir.path());
+            let runner = TickRunner::spawn(recipe(dir.path(), "0 9 * * *", timing), deps.clone());
+            runner.fire(utc("2026-05-17T09:00:00Z"), false);
+            runner.fire(utc("2026-05-18T09:00:00Z"), false);
+            let ticks = scheduled_ticks(&deps, expected).await;
+            assert_eq!(ticks[0], utc("2026-05-17T09:00:00Z"), "{overlap:?}");
+            while runner.in_flight.load(Ordering::SeqCst) > 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            assert_eq!(deps.session_store.list().unwrap().len(), expected);
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn each_tick_runs_once_across_replicas() {
+        let dir = tempfile::tempdir().unwrap();
+        let recipe = recipe(dir.path(), "0 9 * * *", Default::default());
+        // Two replicas: their own session stores, one shared run store.
+        let (a, b) = (deps(dir.path()), deps(dir.path()));
+        let tick = utc("2026-05-17T09:00:00Z");
+        let id = tick_id("digest", tick);
+        let store = RunStoreFactory::shared(&a.run_base).store_for(&id);
+
+        // Leased to a third replica: neither runs it.
+        let ttl = chrono::Duration::minutes(5);
+        crate::runtime::store::acquire_lease(store.as_ref(), "replica-c", ttl)
+            .unwrap()
+            .unwrap();
+        run_tick_as(&recipe, &a, tick, "replica-a").await;
+        assert!(a.session_store.list().unwrap().is_empty());
+        crate::runtime::store::release_lease(store.as_ref(), "replica-c").unwrap();
+
+        run_tick_as(&recipe, &a, tick, "replica-a").await;
+        let ran = a.session_store.get(&id).unwrap().expect("tick session");
+        assert_eq!(ran.scheduled_for, Some(tick));
+
+        // The tick is done: a retry on either replica does not run it again.
+        run_tick_as(&recipe, &b, tick, "replica-b").await;
+        run_tick_as(&recipe, &a, tick, "replica-a").await;
+        assert!(b.session_store.list().unwrap().is_empty());
+        assert_eq!(a.session_store.list().unwrap().len(), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn a_replica_that_lost_the_tick_does_not_record_it() {
+        let dir = tempfile::tempdir().unwrap();
+        let recipe = recipe(dir.path(), "0 9 * * *", Default::default());
+        let a = deps(dir.path());
+        let tick = utc("2026-05-17T09:00:00Z");
+        let id = tick_id("digest", tick);
+        let store = RunStoreFactory::shared(&a.run_base).store_for(&id);
+        let ttl = chrono::Duration::minutes(5);
+        let session = execute_recipe(&recipe, &a, Some(tick)).await.unwrap();
+
+        // Replica-a's lease lapsed mid-run and replica-c took the tick over.
+        crate::runtime::store::acquire_lease(store.as_ref(), "replica-c", ttl)
+            .unwrap()
+            .unwrap();
+        let settled = settle_tick(
+            store.as_ref(),
+            "replica-a",
+            ttl,
+            &*a.session_store,
+            &id,
+            Some(&session),
+        )
+        .unwrap();
+        assert!(!settled);
+        assert!(a.session_store.list().unwrap().is_empty());
+
+        // Replica-c settles first; a late settle finds its marker.
+        assert!(settle_tick(
+            store.as_ref(),
+            "replica-c",
+            ttl,
+            &*a.session_store,
+            &id,
+            Some(&session),
+        )
+        .unwrap());
+        assert!(!settle_tick(
+            store.as_ref(),
+            "replica-a",
+            ttl,
+            &*a.session_store,
+            &id,
+            Some(&session),
+        )
+        .unwrap());
+        assert_eq!(a.session_store.list().unwrap().len(), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn a_tick_whose_holder_died_runs_once_its_lease_expires() {
+        let dir = tempfile::tempdir().unwrap();
+        let recipe = recipe(dir.path(), "0 9 * * *", Default::default());
+        let a = deps(dir.path());
+        let tick = utc("2026-05-17T09:00:00Z");
+        let id = tick_id("digest", tick);
+        let store = RunStoreFactory::shared(&a.run_base).store_for(&id);
+
+        // Replica-c claimed the tick and died without renewing.
+        crate::runtime::store::acquire_lease(store.as_ref(), "replica-c", TimeDelta::seconds(1))
+            .unwrap()
+            .unwrap();
+        run_tick_as(&recipe, &a, tick, "replica-a").await;
+        assert!(a.session_store.list().unwrap().is_empty());
+        let ticks = scheduled_ticks(&a, 1).await;
+        assert_eq!(ticks, vec![tick]);
+    }
+
+    #[test]
+    fn tick_ids_keep_distinct_recipe_names_apart() {
+        let tick = utc("2026-05-17T09:00:00Z");
+        assert_eq!(tick_id("digest", tick), "tick-digest-20260517T090000Z");
+        let ids: std::collections::HashSet<_> = ["a b", "a-b", "a_b", "a_20b", "a/b", "a\u{e9}b"]
+            .iter()
+            .map(|name| tick_id(name, tick))
+            .collect();
+        assert_eq!(ids.len(), 6);
+        assert_eq!(tick_id("a b", tick), "tick-a_20b-20260517T090000Z");
+    }
+}
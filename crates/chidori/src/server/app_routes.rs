@@ -1,7 +1,8 @@
 //! Webhook routes declared by the application manifest (`chidori.app.yml`):
 //! each route delivers its request body into a detached agent's durable
 //! mailbox as a named signal, waking the agent if it hibernates on that name.
-//! Routes sit behind the same bearer auth as every other server route.
+//! Routes sit behind the same bearer auth as every other server route; a
+//! route whose agent belongs to a tenant is reached with that tenant's keys.
 
 use std::sync::Arc;
 
@@ -10,17 +11,23 @@ use axum::http::StatusCode;
 use axum::response::{IntoResponse, Json, Response};
 use serde_json::{json, Value};
 
+use super::detached::hub_parts;
+use super::AppState;
+
 /// One manifest route's target, captured as the per-route router state.
 pub(crate) struct AppRouteTarget {
     pub path: String,
     pub agent: String,
     pub signal: String,
+    /// The state whose fleet the agent runs in (a tenant's, for a tenant
+    /// agent).
+    pub(super) state: AppState,
 }
 
 /// Deliver a request to the route's agent. Any JSON body becomes the signal
 /// payload verbatim; a non-JSON body arrives as a string; an empty body is
 /// `null`. 202 on delivery (the agent consumes asynchronously), 404 for an
-/// unknown agent name, 503 when the fleet runtime isn't installed.
+/// unknown agent name, 503 when the fleet runtime can't be built.
 pub(crate) async fn deliver_app_route(
     State(target): State<Arc<AppRouteTarget>>,
     body: axum::body::Bytes,
@@ -37,7 +44,7 @@ pub(crate) async fn deliver_app_route(
     // the async worker threads.
     let delivered = tokio::task::spawn_blocking(move || {
         let hub = crate::runtime::host_agent::hub();
-        let parts = hub.installed_parts()?;
+        let parts = hub_parts(&route.state)?;
         hub.send(
             &parts,
             &route.agent,
@@ -62,7 +69,7 @@ pub(crate) async fn deliver_app_route(
         Ok(Err(err)) => {
             let status = if err.contains("unknown agent") {
                 StatusCode::NOT_FOUND
-            } else if err.contains("no runtime parts") {
+            } else if err.contains("agent runtime") {
                 StatusCode::SERVICE_UNAVAILABLE
             } else {
                 StatusCode::INTERNAL_SERVER_ERROR
@@ -2,19 +2,53 @@
 //! status, mailbox delivery (which wakes a hibernating agent), and
 //! cooperative stop.
 
-use axum::extract::Path;
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Json, Response};
 use serde_json::{json, Value};
 
+use crate::runtime::host_agent::AgentRuntimeParts;
+use crate::tools::ToolRegistry;
+
+use super::sessions::session_policy;
+use super::AppState;
+
 // ---------------------------------------------------------------------------
 // Detached durable agents (docs/detached-agents.md)
 // ---------------------------------------------------------------------------
 
-pub(super) async fn list_detached_agents() -> Response {
-    match tokio::task::spawn_blocking(|| {
+/// The hub runtime parts for `state`'s fleet: its providers and MCP tools,
+/// its session policy, and its run base — whose registry is the fleet's, so
+/// a tenant's state reaches only the tenant's agents.
+pub(super) fn agent_runtime_parts(state: &AppState) -> anyhow::Result<AgentRuntimeParts> {
+    // The registry holds only externally-sourced tools (MCP servers).
+    // Agent tools are defined in-VM with `defineTool` and never registered.
+    let mut registry = ToolRegistry::new();
+    for def in state.mcp.tool_defs() {
+        registry.register(def);
+    }
+    Ok(AgentRuntimeParts {
+        providers: state.providers.clone(),
+        template_engine: state.template_engine.clone(),
+        tokio_rt: crate::scheduler::shared_tokio_runtime()?,
+        policy: session_policy(state, None),
+        tools: Arc::new(registry),
+        mcp: state.mcp.clone(),
+        run_base: state.run_base.clone(),
+    })
+}
+
+/// `agent_runtime_parts` with its error as the hub's string errors.
+pub(super) fn hub_parts(state: &AppState) -> Result<AgentRuntimeParts, String> {
+    agent_runtime_parts(state).map_err(|err| format!("agent runtime: {err}"))
+}
+
+pub(super) async fn list_detached_agents(State(state): State<AppState>) -> Response {
+    match tokio::task::spawn_blocking(move || {
         let hub = crate::runtime::host_agent::hub();
-        hub.installed_parts().and_then(|parts| hub.list(&parts))
+        hub_parts(&state).and_then(|parts| hub.list(&parts))
     })
     .await
     {
@@ -32,11 +66,13 @@ pub(super) async fn list_detached_agents() -> Response {
     }
 }
 
-pub(super) async fn get_detached_agent(Path(name): Path<String>) -> Response {
+pub(super) async fn get_detached_agent(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Response {
     match tokio::task::spawn_blocking(move || {
         let hub = crate::runtime::host_agent::hub();
-        hub.installed_parts()
-            .and_then(|parts| hub.status(&parts, &name))
+        hub_parts(&state).and_then(|parts| hub.status(&parts, &name))
     })
     .await
     {
@@ -60,14 +96,14 @@ pub(super) struct SendDetachedAgentBody {
 /// Deliver a named message into a detached agent's durable mailbox. A
 /// hibernating agent whose listen set matches is woken (resume-by-replay).
 pub(super) async fn send_detached_agent(
+    State(state): State<AppState>,
     Path(agent): Path<String>,
     Json(body): Json<SendDetachedAgentBody>,
 ) -> Response {
     let from = json!({ "kind": "external", "id": "http" });
     match tokio::task::spawn_blocking(move || {
         let hub = crate::runtime::host_agent::hub();
-        hub.installed_parts()
-            .and_then(|parts| hub.send(&parts, &agent, &body.name, body.payload, from))
+        hub_parts(&state).and_then(|parts| hub.send(&parts, &agent, &body.name, body.payload, from))
     })
     .await
     {
@@ -81,11 +117,13 @@ pub(super) async fn send_detached_agent(
     }
 }
 
-pub(super) async fn stop_detached_agent(Path(name): Path<String>) -> Response {
+pub(super) async fn stop_detached_agent(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Response {
     match tokio::task::spawn_blocking(move || {
         let hub = crate::runtime::host_agent::hub();
-        hub.installed_parts()
-            .and_then(|parts| hub.stop(&parts, &name))
+        hub_parts(&state).and_then(|parts| hub.stop(&parts, &name))
     })
     .await
     {
@@ -5,6 +5,7 @@ use std::sync::Arc;
 
 use serde_json::Value;
 
+use crate::runtime::cost::CostBudget;
 use crate::runtime::engine::Engine;
 use crate::tools::ToolRegistry;
 
@@ -35,17 +36,22 @@ pub(super) fn build_engine(app: &AppState, policy_profile: Option<&str>) -> Engi
     // The registry holds only externally-sourced tools (MCP servers). Agent
     // tools are defined in-VM with `defineTool` and never registered.
     let mut registry = ToolRegistry::new();
-    for def in app.mcp_tools.iter() {
-        registry.register(def.clone());
+    for def in app.mcp.tool_defs() {
+        registry.register(def);
     }
     // Default `chidori.workspace` to the served agent's project directory,
     // matching `chidori run` — an explicit CHIDORI_WORKSPACE_ROOT still wins
     // (it populates the context default, which the engine never overrides).
-    let workspace_root = app
-        .agent_path
-        .parent()
-        .unwrap_or_else(|| std::path::Path::new("."))
-        .to_path_buf();
+    // A tenant's runs get the tenant's own directory instead, which also
+    // roots their memory store.
+    let workspace_root = match &app.tenant {
+        Some(tenant) => tenant.root.clone(),
+        None => app
+            .agent_path
+            .parent()
+            .unwrap_or_else(|| std::path::Path::new("."))
+            .to_path_buf(),
+    };
     let workspace_root = std::fs::canonicalize(&workspace_root).unwrap_or(workspace_root);
     Engine::new(providers, app.template_engine.clone(), rt)
         .with_tools(Arc::new(registry))
@@ -55,21 +61,34 @@ pub(super) fn build_engine(app: &AppState, policy_profile: Option<&str>) -> Engi
         .with_workspace_root(workspace_root)
 }
 
-/// Layer a persisted run's recorded default model onto an engine, when its
-/// manifest carries one: resume/replay/approve re-runs of a session keep the
-/// model the run was recorded with, instead of silently switching to
-/// whatever the serving process's environment says.
-pub(super) fn with_manifest_model(engine: Engine, app: &AppState, run_id: &str) -> Engine {
-    let model = crate::runtime::snapshot::SnapshotStore::new(app.run_base.join(run_id))
-        .load_manifest()
-        .ok()
-        .and_then(|manifest| manifest.default_model);
-    match model {
+/// Layer a persisted run's recorded settings onto an engine, when its
+/// manifest carries them: resume/replay/approve re-runs of a session keep the
+/// model and cost budget the run was recorded with, instead of silently
+/// switching to whatever the serving process's environment says.
+pub(super) fn with_manifest_settings(engine: Engine, app: &AppState, run_id: &str) -> Engine {
+    let Ok(manifest) =
+        crate::runtime::snapshot::SnapshotStore::new(app.run_base.join(run_id)).load_manifest()
+    else {
+        return engine;
+    };
+    let engine = match manifest.default_model {
         Some(model) => engine.with_default_model(Some(model)),
         None => engine,
+    };
+    match manifest.budget {
+        Some(budget) => engine.with_budget(Some(budget)),
+        None => engine,
     }
 }
 
+/// The cost budget recorded in a persisted run's manifest, if any.
+pub(super) fn manifest_budget(app: &AppState, run_id: &str) -> Option<CostBudget> {
+    crate::runtime::snapshot::SnapshotStore::new(app.run_base.join(run_id))
+        .load_manifest()
+        .ok()
+        .and_then(|manifest| manifest.budget)
+}
+
 /// Synchronous one-shot runner used by the ACP endpoint. Runs the agent on
 /// the current thread (already inside spawn_blocking) and returns the output
 /// JSON. Any error is bubbled as an anyhow::Error.
@@ -85,7 +85,7 @@ pub(super) async fn handle_event(
     let mut header_map = serde_json::Map::new();
     for (key, value) in headers.iter() {
         if let Ok(v) = value.to_str() {
-            let v = if is_server_credential(v) {
+            let v = if is_server_credential(&state, v) {
                 REDACTED_CREDENTIAL
             } else {
                 v
@@ -131,11 +131,15 @@ pub(super) async fn handle_event(
         Ok(p) => p,
         Err(resp) => return resp,
     };
+    let budget = match super::tenants::run_budget(&state, None).await {
+        Ok(budget) => budget,
+        Err(resp) => return resp,
+    };
     let app_state = state.clone();
     let input_for_run = input.clone();
 
     let result = tokio::task::spawn_blocking(move || {
-        let engine = build_engine(&app_state, None);
+        let engine = build_engine(&app_state, None).with_budget(budget);
         engine.run_pausable(&app_state.agent_path, &input_for_run)
     })
     .await
@@ -168,6 +172,10 @@ pub(super) async fn handle_event(
         pending_approval: None,
         approvals: Vec::new(),
         policy_profile: None,
+        agent: Some(state.agent_path.display().to_string()),
+        recipe: None,
+        scheduled_for: None,
+        tenant: state.tenant_id(),
         created_at: chrono::Utc::now(),
     };
     apply_run_outcome(&mut session, run_result);
@@ -189,6 +197,14 @@ pub(super) async fn handle_event(
         return (StatusCode::ACCEPTED, Json(session_view(&session))).into_response();
     }
 
+    // A tenant under a cost quota keeps a row for every completed event run
+    // too: the quota is summed over its stored sessions.
+    if state.tenant.as_ref().is_some_and(|t| t.quota.is_some()) {
+        if let Some(err) = store_or_500(&state, &session) {
+            return err;
+        }
+    }
+
     // Completed: stay stateless (no session row for every stray probe) and
     // honor the documented response mapping: an output object of
     // `{status, body, headers?}` shapes the HTTP response; anything else is
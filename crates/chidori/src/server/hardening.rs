@@ -1,13 +1,18 @@
-//! Health endpoint and hardening layers: bearer-token auth, CORS, and the
-//! concurrency limit that caps simultaneous agent runs.
+//! Health and metrics endpoints and hardening layers: bearer-token auth,
+//! CORS, and the concurrency limit that caps simultaneous agent runs.
 
+use axum::extract::State;
 use axum::http::{header, HeaderValue, Request, StatusCode};
 use axum::middleware::Next;
 use axum::response::{IntoResponse, Json, Response};
 use serde_json::json;
+use tokio::sync::OwnedSemaphorePermit;
 use tower_http::cors::{AllowOrigin, Any as CorsAny, CorsLayer};
 
 use super::AppState;
+use crate::mcp::McpServerState;
+use crate::runtime::metrics::{self, Gauge};
+use crate::storage::SessionStatus;
 
 // ---------------------------------------------------------------------------
 // Health
@@ -96,8 +101,14 @@ pub(super) const REDACTED_CREDENTIAL: &str = "[redacted by chidori]";
 ///
 /// Scoped to values that actually contain a configured key, so a webhook's own
 /// shared secret in `Authorization` — a different value, which the agent
-/// legitimately needs to verify the sender — still arrives intact.
-pub(super) fn is_server_credential(value: &str) -> bool {
+/// legitimately needs to verify the sender — still arrives intact. A tenant's
+/// own keys count too: they reach every session the tenant owns.
+pub(super) fn is_server_credential(state: &AppState, value: &str) -> bool {
+    if let Some(tenant) = &state.tenant {
+        if carries_configured_key(value, &tenant.keys) {
+            return true;
+        }
+    }
     match std::env::var("CHIDORI_API_KEY") {
         Ok(raw_keys) => carries_configured_key(value, &raw_keys),
         Err(_) => false,
@@ -137,6 +148,31 @@ pub(super) fn allow_unauthenticated_from_env() -> bool {
     )
 }
 
+/// Fail closed on the dangerous combination: a network-reachable bind with
+/// no authentication means anyone who can route to the port can execute
+/// agent code. The default bind is loopback, so this only trips when the
+/// operator explicitly asked for a wider bind without setting a key.
+/// `has_tenants` counts as auth: with tenants configured, every request needs
+/// a tenant's key or `CHIDORI_API_KEY`.
+pub(super) fn refuse_unauthenticated_bind(
+    host: &str,
+    port: u16,
+    has_tenants: bool,
+) -> anyhow::Result<()> {
+    let auth_required = has_tenants || std::env::var("CHIDORI_API_KEY").is_ok();
+    if !is_loopback_host(host) && !auth_required && !allow_unauthenticated_from_env() {
+        anyhow::bail!(
+            "refusing to bind {host}:{port} without authentication: a non-loopback bind \
+             exposes this server — which executes agent code — to the network with no \
+             access control. Either set CHIDORI_API_KEY to require bearer auth, keep the \
+             default loopback bind (drop --host / CHIDORI_HOST), or set \
+             CHIDORI_ALLOW_UNAUTHENTICATED=1 if a reverse proxy or firewall in front of \
+             this server already controls access."
+        );
+    }
+    Ok(())
+}
+
 /// Build a CORS layer from `CHIDORI_CORS_ORIGINS`:
 ///
 ///  * unset     → no CORS headers emitted (same-origin only)
@@ -166,34 +202,151 @@ pub(super) fn build_cors_layer() -> CorsLayer {
         .allow_headers(CorsAny)
 }
 
-/// Acquire a run permit or return a `503 Service Unavailable` response
-/// after `state.acquire_timeout` elapses. The returned permit is bound to
-/// the semaphore via `acquire_owned`, so holding it across an `.await`
-/// (e.g. `spawn_blocking`) is fine — dropping the permit releases the
-/// slot automatically.
-pub(super) async fn acquire_run_slot(
-    state: &AppState,
-) -> std::result::Result<tokio::sync::OwnedSemaphorePermit, Response> {
+/// A run's hold on the concurrency limits: the server-wide slot and, for a
+/// tenant with `max_concurrent_runs`, the tenant's. Dropping it frees both.
+#[derive(Debug)]
+pub(super) struct RunSlot {
+    _tenant: Option<OwnedSemaphorePermit>,
+    _server: OwnedSemaphorePermit,
+}
+
+/// Acquire a run slot or return an error response after
+/// `state.acquire_timeout` elapses: `429 Too Many Requests` when the tenant's
+/// own cap is full, `503 Service Unavailable` when the server's is. The
+/// permits are bound to their semaphores via `acquire_owned`, so holding the
+/// slot across an `.await` (e.g. `spawn_blocking`) is fine — dropping it
+/// releases the slots automatically.
+pub(super) async fn acquire_run_slot(state: &AppState) -> std::result::Result<RunSlot, Response> {
+    let tenant_permit = match state.tenant.as_ref().and_then(|t| t.run_slots.clone()) {
+        Some(sem) => match tokio::time::timeout(state.acquire_timeout, sem.acquire_owned()).await {
+            Ok(Ok(permit)) => Some(permit),
+            Ok(Err(_)) => return Err(semaphore_closed()),
+            Err(_) => {
+                metrics::inc(metrics::CONCURRENCY_REJECTIONS, &[], 1.0);
+                return Err((
+                    StatusCode::TOO_MANY_REQUESTS,
+                    [(header::RETRY_AFTER, "1")],
+                    Json(json!({
+                        "error": "tenant busy; all of its concurrent-session slots are in use",
+                        "acquire_timeout_ms": state.acquire_timeout.as_millis() as u64,
+                    })),
+                )
+                    .into_response());
+            }
+        },
+        None => None,
+    };
     let sem = state.run_semaphore.clone();
     match tokio::time::timeout(state.acquire_timeout, sem.acquire_owned()).await {
-        Ok(Ok(permit)) => Ok(permit),
-        Ok(Err(_)) => Err((
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(json!({"error": "run semaphore closed"})),
-        )
-            .into_response()),
-        Err(_) => Err((
-            StatusCode::SERVICE_UNAVAILABLE,
-            [(header::RETRY_AFTER, "1")],
-            Json(json!({
-                "error": "server busy; all concurrent-session slots are in use",
-                "acquire_timeout_ms": state.acquire_timeout.as_millis() as u64,
-            })),
-        )
-            .into_response()),
+        Ok(Ok(permit)) => Ok(RunSlot {
+            _tenant: tenant_permit,
+            _server: permit,
+        }),
+        Ok(Err(_)) => Err(semaphore_closed()),
+        Err(_) => {
+            metrics::inc(metrics::CONCURRENCY_REJECTIONS, &[], 1.0);
+            Err((
+                StatusCode::SERVICE_UNAVAILABLE,
+                [(header::RETRY_AFTER, "1")],
+                Json(json!({
+                    "error": "server busy; all concurrent-session slots are in use",
+                    "acquire_timeout_ms": state.acquire_timeout.as_millis() as u64,
+                })),
+            )
+                .into_response())
+        }
     }
 }
 
-pub(super) async fn health() -> impl IntoResponse {
-    Json(json!({"status": "ok"}))
+fn semaphore_closed() -> Response {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(json!({"error": "run semaphore closed"})),
+    )
+        .into_response()
+}
+
+/// GET /health — always 200 while the process serves. `status` turns
+/// `degraded` while a configured MCP server is down (its supervisor is
+/// restarting it). Exempt from auth, so it says nothing more.
+pub(super) async fn health(State(state): State<AppState>) -> impl IntoResponse {
+    let degraded = state
+        .mcp
+        .status()
+        .values()
+        .any(|s| s.state == McpServerState::Down);
+    Json(json!({ "status": if degraded { "degraded" } else { "ok" } }))
+}
+
+/// GET /health/mcp — `/health`'s `status` plus each MCP server's state,
+/// transport and last error. Behind auth: the detail names internal
+/// services.
+pub(super) async fn mcp_health(State(state): State<AppState>) -> impl IntoResponse {
+    let mcp = state.mcp.status();
+    let degraded = mcp.values().any(|s| s.state == McpServerState::Down);
+    Json(json!({
+        "status": if degraded { "degraded" } else { "ok" },
+        "mcp": mcp,
+    }))
+}
+
+/// Every session status, so `chidori_sessions` reports zero for a status no
+/// session holds rather than dropping the series.
+const SESSION_STATUSES: [SessionStatus; 6] = [
+    SessionStatus::Running,
+    SessionStatus::Completed,
+    SessionStatus::Failed,
+    SessionStatus::Cancelled,
+    SessionStatus::Paused,
+    SessionStatus::AwaitingApproval,
+];
+
+/// GET /metrics — Prometheus text exposition of the process registry
+/// ([`metrics`]) plus two gauges read at scrape time: sessions by status
+/// from the session store, and the session runs executing in this process.
+/// Behind the same bearer auth as the rest of the API; a scraper sends
+/// `CHIDORI_API_KEY` as its `authorization` credential.
+pub(super) async fn get_metrics(State(state): State<AppState>) -> Response {
+    let store = state.session_store.clone();
+    let counts = tokio::task::spawn_blocking(move || store.count_by_status())
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|counts| counts);
+    let mut gauges = Vec::new();
+    match counts {
+        Ok(counts) => gauges.push(Gauge {
+            name: "chidori_sessions",
+            help: "Sessions in the session store by status.",
+            samples: SESSION_STATUSES
+                .iter()
+                .map(|status| {
+                    let n = counts
+                        .iter()
+                        .find(|(s, _)| s == status)
+                        .map_or(0, |(_, n)| *n);
+                    let name = serde_json::to_value(status)
+                        .ok()
+                        .and_then(|v| v.as_str().map(str::to_string))
+                        .unwrap_or_default();
+                    (vec![("status", name)], n as f64)
+                })
+                .collect(),
+        }),
+        // The registry is still worth serving without the store's view.
+        Err(e) => tracing::warn!("metrics: counting sessions: {e:#}"),
+    }
+    let active = state.active_sessions.lock().unwrap().len();
+    gauges.push(Gauge {
+        name: "chidori_sessions_active",
+        help: "Session runs executing in this process.",
+        samples: vec![(Vec::new(), active as f64)],
+    });
+    (
+        [(
+            header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        metrics::render(&gauges),
+    )
+        .into_response()
 }
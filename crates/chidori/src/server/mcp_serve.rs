@@ -0,0 +1,1267 @@
+//! `chidori mcp-serve`: the agents in a directory and the configured recipes,
+//! published as the tools of an MCP server over stdio or Streamable HTTP.
+//!
+//! A tool call goes through the same session handlers `POST /sessions` uses,
+//! so every invocation is a durable session in the directory's session store.
+//! A run that pauses is answered in-band when it can be: an `input()` pause
+//! or a policy approval becomes an MCP elicitation for clients that declare
+//! the capability. Otherwise — and for signal pauses, which wait on the
+//! outside world — the call returns a resumable result naming the session,
+//! and the built-in `chidori_resume` tool continues it.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::path::{Path as FsPath, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::{Duration, Instant};
+
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::middleware;
+use axum::response::sse::{Event, Sse};
+use axum::response::{IntoResponse, Json, Response};
+use axum::routing::{get, post};
+use axum::Router;
+use serde_json::{json, Map, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+use tokio::sync::{mpsc, oneshot, Semaphore};
+
+use crate::mcp::{jsonrpc_reply, McpManager, McpServersConfig, SupervisorSettings};
+use crate::policy::PolicyConfig;
+use crate::providers::ProviderRegistry;
+use crate::recipes::Recipe;
+use crate::runtime::engine::Engine;
+use crate::runtime::template::TemplateEngine;
+use crate::storage::{build_session_store, SessionStatus};
+
+use super::engine::build_engine;
+use super::hardening::{
+    auth_middleware, build_cors_layer, health, mcp_health, refuse_unauthenticated_bind,
+};
+use super::sessions::resume::{
+    approve_session, resume_session, signal_session, ApproveRequest, ResumeRequest, SignalRequest,
+};
+use super::sessions::{arm_signal_timeout, create_session, CreateSessionRequest};
+use super::{
+    acquire_timeout_ms_from_env, is_supported_agent_path, max_concurrent_from_env, session_view,
+    warm_evict_from_env, AppState,
+};
+
+/// The MCP revision this server speaks: the first with elicitation.
+const PROTOCOL_VERSION: &str = "2025-06-18";
+
+/// The built-in tool that continues a paused call.
+const RESUME_TOOL: &str = "chidori_resume";
+
+/// The Streamable HTTP session header.
+const SESSION_HEADER: &str = "mcp-session-id";
+
+/// How long an HTTP client session may sit unused before it is dropped.
+/// Clients that vanish without a DELETE would otherwise be kept forever.
+fn mcp_session_idle_from_env() -> Duration {
+    let ms = std::env::var("CHIDORI_MCP_SESSION_IDLE_MS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(1_800_000);
+    Duration::from_millis(ms)
+}
+
+/// How `mcp-serve` reaches its client.
+pub enum McpTransport {
+    /// Newline-delimited JSON-RPC on stdin/stdout; logs go to stderr.
+    Stdio,
+    /// Streamable HTTP on `POST /mcp`.
+    Http { host: String, port: u16 },
+}
+
+/// One published tool: an agent file, or a recipe over one.
+#[derive(Clone)]
+struct ServedTool {
+    name: String,
+    description: String,
+    input_schema: Value,
+    agent_path: PathBuf,
+    /// Set for recipe tools; its `inputs` are defaults the call's arguments
+    /// override.
+    recipe: Option<Recipe>,
+}
+
+#[derive(Clone)]
+struct McpServeState {
+    app: AppState,
+    tools: Arc<Vec<ServedTool>>,
+}
+
+/// Serve the agents in `dir` (and the recipes in `recipe_dir`) as MCP tools.
+#[allow(clippy::too_many_arguments)]
+pub async fn mcp_serve(
+    providers: Arc<ProviderRegistry>,
+    template_engine: Arc<TemplateEngine>,
+    dir: PathBuf,
+    recipe_dir: Option<PathBuf>,
+    transport: McpTransport,
+    policy: Arc<PolicyConfig>,
+    policy_posture: String,
+) -> anyhow::Result<()> {
+    if let McpTransport::Http { host, port } = &transport {
+        refuse_unauthenticated_bind(host, *port, false)?;
+    }
+
+    let mcp = Arc::new(McpManager::new());
+    let mcp_cfg = McpServersConfig::load_from_env().unwrap_or_default();
+    if let Err(e) = mcp.start_from_config(&mcp_cfg).await {
+        tracing::warn!("MCP startup: {}", e);
+    }
+    mcp.supervise(SupervisorSettings::from_env());
+    let recipes = recipe_dir
+        .as_ref()
+        .map(|d| Recipe::load_dir(d).unwrap_or_default())
+        .unwrap_or_default();
+
+    // Tool calls name their agent per call, so the app carries no default
+    // agent; `tool_app` points a copy of it at each tool's file.
+    let app = AppState {
+        providers,
+        template_engine,
+        agent_path: dir.join("__no_default_agent__.ts"),
+        has_default_agent: false,
+        run_base: dir.join(".chidori").join("runs"),
+        session_store: build_session_store(&dir)?,
+        policy,
+        mcp,
+        recipes: Arc::new(recipes.clone()),
+        run_semaphore: Arc::new(Semaphore::new(max_concurrent_from_env())),
+        acquire_timeout: std::time::Duration::from_millis(acquire_timeout_ms_from_env()),
+        active_sessions: Arc::new(StdMutex::new(HashMap::new())),
+        signal_inbox_locks: Arc::new(StdMutex::new(HashMap::new())),
+        warm_runs: Arc::new(StdMutex::new(HashMap::new())),
+        warm_evict: warm_evict_from_env(),
+        tenant: None,
+    };
+    if let Ok(sessions) = app.session_store.list() {
+        for session in &sessions {
+            arm_signal_timeout(&app, session);
+        }
+    }
+
+    // Describing an agent evaluates its module graph on the engine, so it
+    // runs off the async threads like any other run.
+    let tools = {
+        let app = app.clone();
+        tokio::task::spawn_blocking(move || discover_tools(&app, &dir, &recipes)).await?
+    };
+    eprintln!("MCP tools ({}):", tools.len());
+    for tool in &tools {
+        eprintln!("  {:<24} {}", tool.name, tool.agent_path.display());
+    }
+    eprintln!("  {:<24} continue a paused call", RESUME_TOOL);
+    eprintln!("Policy: {}", policy_posture);
+
+    let state = McpServeState {
+        app,
+        tools: Arc::new(tools),
+    };
+    match transport {
+        McpTransport::Stdio => serve_stdio(state).await,
+        McpTransport::Http { host, port } => serve_http(state, &host, port).await,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tool discovery
+// ---------------------------------------------------------------------------
+
+/// Every agent file in `dir` (helper modules without an entrypoint are
+/// skipped), then every recipe whose name is still free.
+fn discover_tools(app: &AppState, dir: &FsPath, recipes: &[Recipe]) -> Vec<ServedTool> {
+    let engine = build_engine(app, None);
+    let mut agents: Vec<PathBuf> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| is_supported_agent_path(path))
+        .collect();
+    agents.sort();
+
+    let mut tools: Vec<ServedTool> = Vec::new();
+    for path in agents {
+        if let Err(e) = engine.check(&path) {
+            tracing::debug!("mcp-serve: skipping {}: {e}", path.display());
+            continue;
+        }
+        let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let file = path.file_name().and_then(|s| s.to_str()).unwrap_or(stem);
+        tools.push(ServedTool {
+            name: tool_name(stem),
+            description: format!("Run the `{file}` agent as a durable session."),
+            input_schema: input_schema_of(&engine, &path),
+            agent_path: path.clone(),
+            recipe: None,
+        });
+    }
+    for recipe in recipes {
+        let name = tool_name(&recipe.name);
+        if name == RESUME_TOOL || tools.iter().any(|tool| tool.name == name) {
+            tracing::warn!(
+                "mcp-serve: recipe `{}` is shadowed by a tool of the same name",
+                recipe.name
+            );
+            continue;
+        }
+        let mut input_schema = input_schema_of(&engine, &recipe.agent);
+        // A field the recipe supplies is a default, not something the caller
+        // still owes.
+        if let (Some(required), Some(defaults)) = (
+            input_schema
+                .get_mut("required")
+                .and_then(Value::as_array_mut),
+            recipe.inputs.as_object(),
+        ) {
+            required.retain(|key| key.as_str().is_none_or(|key| !defaults.contains_key(key)));
+        }
+        tools.push(ServedTool {
+            name,
+            description: recipe
+                .description
+                .clone()
+                .unwrap_or_else(|| format!("Run the `{}` recipe.", recipe.name)),
+            input_schema,
+            agent_path: recipe.agent.clone(),
+            recipe: Some(recipe.clone()),
+        });
+    }
+    tools
+}
+
+/// MCP tool names are `[A-Za-z0-9_-]`; anything else becomes `_`.
+fn tool_name(raw: &str) -> String {
+    raw.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// The agent's declared input schema when it describes an object (tool
+/// arguments always are one), else an open object schema.
+fn input_schema_of(engine: &Engine, path: &FsPath) -> Value {
+    match engine.input_schema(path) {
+        Ok(Some(schema)) if schema.get("type") == Some(&json!("object")) => schema,
+        Ok(_) => json!({ "type": "object" }),
+        Err(e) => {
+            tracing::debug!("mcp-serve: describing {}: {e}", path.display());
+            json!({ "type": "object" })
+        }
+    }
+}
+
+fn tool_list(state: &McpServeState) -> Value {
+    let mut tools: Vec<Value> = state
+        .tools
+        .iter()
+        .map(|tool| {
+            json!({
+                "name": tool.name,
+                "description": tool.description,
+                "inputSchema": tool.input_schema,
+            })
+        })
+        .collect();
+    tools.push(json!({
+        "name": RESUME_TOOL,
+        "description": "Continue a tool call that paused. Pass the `tool` and `session_id` \
+            from the paused result and exactly one of `response` (an input pause), \
+            `signal` (a signal pause) or `decision` (an approval pause).",
+        "inputSchema": {
+            "type": "object",
+            "properties": {
+                "tool": { "type": "string", "description": "The tool that paused." },
+                "session_id": { "type": "string" },
+                "response": { "type": "string", "description": "Answer to an input pause." },
+                "signal": {
+                    "type": "object",
+                    "properties": { "name": { "type": "string" }, "payload": {} },
+                    "required": ["name"],
+                },
+                "decision": { "type": "string", "enum": ["allow", "deny"] },
+            },
+            "required": ["tool", "session_id"],
+        },
+    }));
+    Value::Array(tools)
+}
+
+// ---------------------------------------------------------------------------
+// Client connection
+// ---------------------------------------------------------------------------
+
+/// One connected MCP client: what it declared at `initialize`, and the
+/// server-to-client requests awaiting its reply.
+#[derive(Default)]
+struct ClientSession {
+    elicitation: AtomicBool,
+    next_request: AtomicU64,
+    awaiting: StdMutex<HashMap<String, oneshot::Sender<Value>>>,
+    /// When the client last sent anything; unset for stdio clients.
+    last_active: StdMutex<Option<Instant>>,
+}
+
+impl ClientSession {
+    fn touch(&self) {
+        *self.last_active.lock().unwrap() = Some(Instant::now());
+    }
+
+    fn idle_longer_than(&self, ttl: Duration) -> bool {
+        self.last_active
+            .lock()
+            .unwrap()
+            .is_some_and(|at| at.elapsed() > ttl)
+    }
+
+    /// Hand a client's reply to the request awaiting it. False when
+    /// `message` is not a reply at all.
+    fn route_reply(&self, message: &Value) -> bool {
+        if message.get("method").is_some()
+            || (message.get("result").is_none() && message.get("error").is_none())
+        {
+            return false;
+        }
+        let key = message.get("id").map(Value::to_string).unwrap_or_default();
+        if let Some(waiter) = self.awaiting.lock().unwrap().remove(&key) {
+            let _ = waiter.send(message.clone());
+        }
+        true
+    }
+}
+
+/// Where one request's server-to-client messages go: stdout for stdio, the
+/// request's SSE stream for HTTP.
+struct ClientPeer {
+    client: Arc<ClientSession>,
+    outbound: mpsc::UnboundedSender<Value>,
+}
+
+enum Elicitation {
+    Accept(Value),
+    Decline,
+    Cancel,
+}
+
+impl ClientPeer {
+    /// Ask the client for `schema`-shaped content. A client without the
+    /// capability, an error reply and a dropped connection all count as
+    /// `Cancel`: the run stays paused for `chidori_resume`.
+    async fn elicit(&self, message: &str, schema: Value) -> Elicitation {
+        if !self.client.elicitation.load(Ordering::Relaxed) {
+            return Elicitation::Cancel;
+        }
+        let id = json!(format!(
+            "chidori-{}",
+            self.client.next_request.fetch_add(1, Ordering::Relaxed)
+        ));
+        let (tx, rx) = oneshot::channel();
+        self.client
+            .awaiting
+            .lock()
+            .unwrap()
+            .insert(id.to_string(), tx);
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "elicitation/create",
+            "params": { "message": message, "requestedSchema": schema },
+        });
+        if self.outbound.send(request).is_err() {
+            self.client.awaiting.lock().unwrap().remove(&id.to_string());
+            return Elicitation::Cancel;
+        }
+        let reply = tokio::select! {
+            reply = rx => reply.ok(),
+            _ = self.outbound.closed() => None,
+        };
+        self.client.awaiting.lock().unwrap().remove(&id.to_string());
+        let Some(result) = reply.as_ref().and_then(|reply| reply.get("result")) else {
+            return Elicitation::Cancel;
+        };
+        match result.get("action").and_then(Value::as_str) {
+            Some("accept") => {
+                Elicitation::Accept(result.get("content").cloned().unwrap_or(Value::Null))
+            }
+            Some("decline") => Elicitation::Decline,
+            _ => Elicitation::Cancel,
+        }
+    }
+}
+
+/// Answer one client request; `None` for notifications.
+async fn handle_message(state: &McpServeState, peer: &ClientPeer, message: Value) -> Option<Value> {
+    let id = message.get("id").cloned()?;
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+    let answer = match message.get("method").and_then(Value::as_str).unwrap_or("") {
+        "initialize" => {
+            peer.client.elicitation.store(
+                params.pointer("/capabilities/elicitation").is_some(),
+                Ordering::Relaxed,
+            );
+            Ok(json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": { "tools": { "listChanged": false } },
+                "serverInfo": { "name": "chidori", "version": env!("CARGO_PKG_VERSION") },
+            }))
+        }
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": tool_list(state) })),
+        "tools/call" => call_tool(state, peer, &params).await,
+        other => Err((-32601, format!("method `{other}` not found"))),
+    };
+    Some(jsonrpc_reply(id, answer))
+}
+
+// ---------------------------------------------------------------------------
+// Tool calls
+// ---------------------------------------------------------------------------
+
+async fn call_tool(
+    state: &McpServeState,
+    peer: &ClientPeer,
+    params: &Value,
+) -> Result<Value, (i64, String)> {
+    let name = params.get("name").and_then(Value::as_str).unwrap_or("");
+    let arguments = params
+        .get("arguments")
+        .cloned()
+        .filter(Value::is_object)
+        .unwrap_or_else(|| json!({}));
+    if name == RESUME_TOOL {
+        return Ok(resume_call(state, peer, &arguments).await);
+    }
+    let Some(tool) = state.tools.iter().find(|tool| tool.name == name) else {
+        return Err((-32602, format!("unknown tool `{name}`")));
+    };
+    let app = tool_app(state, tool);
+    let (input, budget) = match &tool.recipe {
+        Some(recipe) => (recipe_input(recipe, arguments), recipe.budget),
+        None => (arguments, None),
+    };
+    let response = create_session(
+        State(app.clone()),
+        Json(CreateSessionRequest {
+            input,
+            session_id: None,
+            attempt_number: None,
+            replay_from: None,
+            agent: None,
+            policy_profile: None,
+            budget,
+        }),
+    )
+    .await;
+    Ok(settle(&app, &tool.name, peer, response).await)
+}
+
+/// `chidori_resume`: deliver an answer, a signal or an approval decision to
+/// a paused session and carry on from there.
+async fn resume_call(state: &McpServeState, peer: &ClientPeer, arguments: &Value) -> Value {
+    let field = |key: &str| arguments.get(key).and_then(Value::as_str);
+    let (Some(name), Some(id)) = (field("tool"), field("session_id")) else {
+        return error_result("`chidori_resume` needs the paused call's `tool` and `session_id`");
+    };
+    let Some(tool) = state.tools.iter().find(|tool| tool.name == name) else {
+        return error_result(&format!("unknown tool `{name}`"));
+    };
+    let app = tool_app(state, tool);
+    let id = id.to_string();
+    let response = if let Some(response) = field("response") {
+        resume_session(
+            State(app.clone()),
+            Path(id),
+            Json(ResumeRequest {
+                response: response.to_string(),
+                allow_source_change: false,
+            }),
+        )
+        .await
+    } else if let Some(signal) = arguments.get("signal") {
+        let Some(name) = signal.get("name").and_then(Value::as_str) else {
+            return error_result("`signal` needs a `name`");
+        };
+        signal_session(
+            State(app.clone()),
+            Path(id),
+            Json(SignalRequest {
+                name: name.to_string(),
+                payload: signal.get("payload").cloned().unwrap_or(Value::Null),
+                from: json!({ "via": "mcp" }),
+                allow_source_change: false,
+            }),
+        )
+        .await
+    } else if let Some(decision) = field("decision") {
+        approve_session(
+            State(app.clone()),
+            Path(id),
+            Json(ApproveRequest {
+                decision: decision.to_string(),
+                allow_source_change: false,
+            }),
+        )
+        .await
+    } else {
+        return error_result("pass one of `response`, `signal` or `decision`");
+    };
+    settle(&app, &tool.name, peer, response).await
+}
+
+/// Drive a session handler's answer to a tool result, answering input and
+/// approval pauses through elicitation for as long as the client does.
+async fn settle(app: &AppState, tool: &str, peer: &ClientPeer, mut response: Response) -> Value {
+    loop {
+        let session = match session_outcome(app, response).await {
+            Ok(session) => session,
+            Err(error) => return error_result(&error),
+        };
+        let id = session["id"].as_str().unwrap_or_default().to_string();
+        let status = serde_json::from_value::<SessionStatus>(session["status"].clone()).ok();
+        response = match status {
+            Some(SessionStatus::Completed) => return completed_result(&session["output"]),
+            Some(SessionStatus::Paused) if session["pending_prompt"].is_string() => {
+                let prompt = session["pending_prompt"].as_str().unwrap_or_default();
+                let schema = json!({
+                    "type": "object",
+                    "properties": { "response": { "type": "string", "description": prompt } },
+                    "required": ["response"],
+                });
+                let answer = match peer.elicit(prompt, schema).await {
+                    Elicitation::Accept(content) => match content.get("response") {
+                        Some(Value::String(text)) => text.clone(),
+                        Some(other) => other.to_string(),
+                        None => String::new(),
+                    },
+                    Elicitation::Decline | Elicitation::Cancel => {
+                        return paused_result(tool, &session)
+                    }
+                };
+                resume_session(
+                    State(app.clone()),
+                    Path(id),
+                    Json(ResumeRequest {
+                        response: answer,
+                        allow_source_change: false,
+                    }),
+                )
+                .await
+            }
+            Some(SessionStatus::AwaitingApproval) => {
+                let schema = json!({
+                    "type": "object",
+                    "properties": { "approve": { "type": "boolean", "title": "Approve" } },
+                    "required": ["approve"],
+                });
+                let message = approval_message(&session["pending_approval"]);
+                let decision = match peer.elicit(&message, schema).await {
+                    Elicitation::Accept(content) if content["approve"] == json!(true) => "allow",
+                    Elicitation::Accept(_) | Elicitation::Decline => "deny",
+                    Elicitation::Cancel => return paused_result(tool, &session),
+                };
+                approve_session(
+                    State(app.clone()),
+                    Path(id),
+                    Json(ApproveRequest {
+                        decision: decision.to_string(),
+                        allow_source_change: false,
+                    }),
+                )
+                .await
+            }
+            Some(SessionStatus::Paused) => return paused_result(tool, &session),
+            _ => {
+                let status = session["status"].as_str().unwrap_or("without a status");
+                return error_result(
+                    session["error"]
+                        .as_str()
+                        .unwrap_or(&format!("the run ended {status}")),
+                );
+            }
+        };
+    }
+}
+
+/// The session view a session handler answered with, or its error. A signal
+/// that was only queued (202) answers with the session as stored.
+async fn session_outcome(app: &AppState, response: Response) -> Result<Value, String> {
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .map_err(|e| e.to_string())?;
+    let body: Value = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+    if !status.is_success() {
+        return Err(body["error"]
+            .as_str()
+            .map(str::to_owned)
+            .unwrap_or_else(|| format!("session request failed ({status})")));
+    }
+    if status == StatusCode::ACCEPTED {
+        let id = body["id"].as_str().unwrap_or_default();
+        return match app.session_store.get(id) {
+            Ok(Some(session)) => Ok(session_view(&session)),
+            Ok(None) => Err("Session not found".to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+    }
+    Ok(body)
+}
+
+/// A copy of the server state whose agent is `tool`'s, so the session
+/// handlers create, resume and validate against the right file.
+fn tool_app(state: &McpServeState, tool: &ServedTool) -> AppState {
+    let mut app = state.app.clone();
+    app.agent_path = tool.agent_path.clone();
+    app.has_default_agent = true;
+    app
+}
+
+fn recipe_input(recipe: &Recipe, arguments: Value) -> Value {
+    let mut input = match &recipe.inputs {
+        Value::Object(defaults) => defaults.clone(),
+        _ => Map::new(),
+    };
+    if let Value::Object(arguments) = arguments {
+        input.extend(arguments);
+    }
+    Value::Object(input)
+}
+
+fn approval_message(approval: &Value) -> String {
+    let target = approval["target"].as_str().unwrap_or("a policy-gated call");
+    match approval["reason"].as_str() {
+        Some(reason) => format!("Allow `{target}`? {reason}"),
+        None => format!("Allow `{target}`?"),
+    }
+}
+
+fn completed_result(output: &Value) -> Value {
+    let text = match output {
+        Value::String(text) => text.clone(),
+        other => serde_json::to_string_pretty(other).unwrap_or_default(),
+    };
+    let mut result = json!({
+        "content": [{ "type": "text", "text": text }],
+        "isError": false,
+    });
+    if output.is_object() {
+        result["structuredContent"] = output.clone();
+    }
+    result
+}
+
+fn error_result(message: &str) -> Value {
+    json!({
+        "content": [{ "type": "text", "text": message }],
+        "isError": true,
+    })
+}
+
+/// A paused run as a (successful) tool result: what it waits on, and the
+/// `chidori_resume` arguments that continue it.
+fn paused_result(tool: &str, session: &Value) -> Value {
+    let id = session["id"].as_str().unwrap_or_default();
+    let signals = &session["pending_signal_names"];
+    let (waiting_for, answer) = if !session["pending_approval"].is_null() {
+        ("approval", "decision: \"allow\" | \"deny\"")
+    } else if signals.as_array().is_some_and(|names| !names.is_empty()) {
+        ("signal", "signal: { name, payload }")
+    } else {
+        ("input", "response")
+    };
+    let text = format!(
+        "The run paused waiting for {waiting_for}. Continue it with `{RESUME_TOOL}` \
+         ({{ tool: \"{tool}\", session_id: \"{id}\", {answer} }})."
+    );
+    json!({
+        "content": [{ "type": "text", "text": text }],
+        "structuredContent": {
+            "status": "paused",
+            "tool": tool,
+            "session_id": id,
+            "waiting_for": waiting_for,
+            "prompt": session["pending_prompt"],
+            "signals": signals,
+            "approval": session["pending_approval"],
+        },
+        "isError": false,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Transports
+// ---------------------------------------------------------------------------
+
+/// Newline-delimited JSON-RPC on stdin/stdout. Requests run concurrently, so
+/// a client can answer an elicitation while its tool call is in flight.
+async fn serve_stdio(state: McpServeState) -> anyhow::Result<()> {
+    let (outbound, mut outgoing) = mpsc::unbounded_channel::<Value>();
+    let writer = tokio::spawn(async move {
+        let mut stdout = tokio::io::stdout();
+        while let Some(message) = outgoing.recv().await {
+            let line = format!("{message}\n");
+            if stdout.write_all(line.as_bytes()).await.is_err() || stdout.flush().await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let client = Arc::new(ClientSession::default());
+    let mut lines = tokio::io::BufReader::new(tokio::io::stdin()).lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let message: Value = match serde_json::from_str(&line) {
+            Ok(message) => message,
+            Err(e) => {
+                let _ = outbound.send(jsonrpc_reply(
+                    Value::Null,
+                    Err((-32700, format!("parse error: {e}"))),
+                ));
+                continue;
+            }
+        };
+        if client.route_reply(&message) {
+            continue;
+        }
+        let state = state.clone();
+        let peer = ClientPeer {
+            client: client.clone(),
+            outbound: outbound.clone(),
+        };
+        tokio::spawn(async move {
+            if let Some(reply) = handle_message(&state, &peer, message).await {
+                let _ = peer.outbound.send(reply);
+            }
+        });
+    }
+    drop(outbound);
+    let _ = writer.await;
+    Ok(())
+}
+
+#[derive(Clone)]
+struct McpHttpState {
+    serve: McpServeState,
+    clients: Arc<StdMutex<HashMap<String, Arc<ClientSession>>>>,
+    idle_ttl: Duration,
+}
+
+impl McpHttpState {
+    /// Look up a session, first dropping every one idle past the TTL. A
+    /// session some in-flight call still holds is never dropped.
+    fn client(&self, id: &str) -> Option<Arc<ClientSession>> {
+        let mut clients = self.clients.lock().unwrap();
+        clients.retain(|_, client| {
+            Arc::strong_count(client) > 1 || !client.idle_longer_than(self.idle_ttl)
+        });
+        let client = clients.get(id).cloned()?;
+        client.touch();
+        Some(client)
+    }
+}
+
+async fn serve_http(state: McpServeState, host: &str, port: u16) -> anyhow::Result<()> {
+    let app = mcp_http_router(state, mcp_session_idle_from_env())
+        .layer(middleware::from_fn(auth_middleware))
+        .layer(build_cors_layer());
+    let addr = format!("{host}:{port}");
+    eprintln!("Listening on http://{addr}/mcp");
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+fn mcp_http_router(state: McpServeState, idle_ttl: Duration) -> Router {
+    Router::new()
+        .route(
+            "/health",
+            get(|State(http): State<McpHttpState>| health(State(http.serve.app))),
+        )
+        .route(
+            "/health/mcp",
+            get(|State(http): State<McpHttpState>| mcp_health(State(http.serve.app))),
+        )
+        .route(
+            "/mcp",
+            post(post_mcp)
+                .get(|| async { StatusCode::METHOD_NOT_ALLOWED })
+                .delete(delete_mcp),
+        )
+        .with_state(McpHttpState {
+            serve: state,
+            clients: Arc::new(StdMutex::new(HashMap::new())),
+            idle_ttl,
+        })
+}
+
+/// POST /mcp — one client message. Replies and notifications are accepted
+/// (202); a `tools/call` answers as an SSE stream carrying any elicitations
+/// before its result; every other request answers as plain JSON.
+/// `initialize` opens a session named by the `Mcp-Session-Id` header.
+async fn post_mcp(
+    State(http): State<McpHttpState>,
+    headers: HeaderMap,
+    Json(message): Json<Value>,
+) -> Response {
+    let client = match headers.get(SESSION_HEADER).and_then(|v| v.to_str().ok()) {
+        Some(id) => match http.client(id) {
+            Some(client) => client,
+            None => {
+                return (
+                    StatusCode::NOT_FOUND,
+                    Json(json!({"error": "unknown MCP session"})),
+                )
+                    .into_response()
+            }
+        },
+        None => Arc::new(ClientSession::default()),
+    };
+    if client.route_reply(&message) || message.get("id").is_none() {
+        return StatusCode::ACCEPTED.into_response();
+    }
+    let method = message["method"].as_str().unwrap_or_default().to_string();
+    let (outbound, mut outgoing) = mpsc::unbounded_channel();
+    let peer = ClientPeer {
+        client: client.clone(),
+        outbound,
+    };
+
+    if method == "tools/call" {
+        let serve = http.serve.clone();
+        tokio::spawn(async move {
+            if let Some(reply) = handle_message(&serve, &peer, message).await {
+                let _ = peer.outbound.send(reply);
+            }
+        });
+        // The stream ends when the call's task drops its peer.
+        let stream = async_stream::stream! {
+            while let Some(message) = outgoing.recv().await {
+                yield Ok::<_, Infallible>(Event::default().event("message").data(message.to_string()));
+            }
+        };
+        return Sse::new(stream).into_response();
+    }
+
+    let reply = handle_message(&http.serve, &peer, message)
+        .await
+        .unwrap_or(Value::Null);
+    let mut response = Json(reply).into_response();
+    if method == "initialize" {
+        let id = uuid::Uuid::new_v4().to_string();
+        if let Ok(value) = HeaderValue::from_str(&id) {
+            response.headers_mut().insert(SESSION_HEADER, value);
+        }
+        client.touch();
+        http.clients.lock().unwrap().insert(id, client);
+    }
+    response
+}
+
+/// DELETE /mcp — the client ends its session.
+async fn delete_mcp(State(http): State<McpHttpState>, headers: HeaderMap) -> StatusCode {
+    match headers.get(SESSION_HEADER).and_then(|v| v.to_str().ok()) {
+        Some(id) if http.clients.lock().unwrap().remove(id).is_some() => StatusCode::NO_CONTENT,
+        _ => StatusCode::NOT_FOUND,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn serve_state(dir: &FsPath, recipes: Vec<Recipe>) -> McpServeState {
+        let app = AppState {
+            providers: Arc::new(ProviderRegistry::new()),
+            template_engine: Arc::new(TemplateEngine::new(".")),
+            agent_path: dir.join("__no_default_agent__.ts"),
+            has_default_agent: false,
+            run_base: dir.join(".chidori").join("runs"),
+            session_store: Arc::new(crate::storage::MemoryStore::new()),
+            policy: PolicyConfig::from_env(),
+            mcp: Arc::new(McpManager::new()),
+            recipes: Arc::new(recipes.clone()),
+            run_semaphore: Arc::new(Semaphore::new(1)),
+            acquire_timeout: std::time::Duration::from_secs(5),
+            active_sessions: Arc::new(StdMutex::new(HashMap::new())),
+            signal_inbox_locks: Arc::new(StdMutex::new(HashMap::new())),
+            warm_runs: Arc::new(StdMutex::new(HashMap::new())),
+            warm_evict: warm_evict_from_env(),
+            tenant: None,
+        };
+        let tools = discover_tools(&app, dir, &recipes);
+        McpServeState {
+            app,
+            tools: Arc::new(tools),
+        }
+    }
+
+    fn peer_for(client: &Arc<ClientSession>) -> (ClientPeer, mpsc::UnboundedReceiver<Value>) {
+        let (outbound, outgoing) = mpsc::unbounded_channel();
+        let peer = ClientPeer {
+            client: client.clone(),
+            outbound,
+        };
+        (peer, outgoing)
+    }
+
+    async fn request(
+        state: &McpServeState,
+        peer: &ClientPeer,
+        method: &str,
+        params: Value,
+    ) -> Value {
+        let message = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        handle_message(state, peer, message).await.unwrap()["result"].clone()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn tool_calls_are_sessions_whose_pauses_elicit_or_resume() {
+        let dir = std::env::temp_dir().join(format!("chidori-mcp-serve-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let agent = dir.join("greet.ts");
+        std::fs::write(
+            &agent,
+            r#"
+                import { chidori, run } from "chidori:agent";
+                run(async (input: { salutation: string }) => {
+                    const name = await chidori.input("Name?");
+                    return { greeting: input.salutation + " " + name };
+                }, {
+                    inputSchema: {
+                        type: "object",
+                        properties: { salutation: { type: "string" } },
+                        required: ["salutation"],
+                    },
+                });
+            "#,
+        )
+        .unwrap();
+        // A helper module without an entrypoint is not a tool.
+        std::fs::write(dir.join("helper.ts"), "export const x = 1;\n").unwrap();
+        let recipe = Recipe {
+            name: "daily-greet".to_string(),
+            agent: agent.clone(),
+            schedule: None,
+            inputs: json!({ "salutation": "Hi" }),
+            description: None,
+            budget: None,
+            timing: Default::default(),
+        };
+        let state = {
+            let dir = dir.clone();
+            tokio::task::spawn_blocking(move || serve_state(&dir, vec![recipe]))
+                .await
+                .unwrap()
+        };
+
+        let client = Arc::new(ClientSession::default());
+        let (peer, mut outgoing) = peer_for(&client);
+        let init = request(
+            &state,
+            &peer,
+            "initialize",
+            json!({ "protocolVersion": PROTOCOL_VERSION, "capabilities": { "elicitation": {} } }),
+        )
+        .await;
+        assert_eq!(init["protocolVersion"], PROTOCOL_VERSION);
+
+        let tools = request(&state, &peer, "tools/list", json!({})).await;
+        let names: Vec<&str> = tools["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| tool["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["greet", "daily-greet", RESUME_TOOL]);
+        assert_eq!(
+            tools["tools"][0]["inputSchema"]["required"],
+            json!(["salutation"])
+        );
+        // The recipe supplies `salutation`, so its callers need not.
+        assert_eq!(tools["tools"][1]["inputSchema"]["required"], json!([]));
+
+        // An eliciting client answers the input pause in-band.
+        let answering = client.clone();
+        let answers = tokio::spawn(async move {
+            let mut prompts = Vec::new();
+            while let Some(message) = outgoing.recv().await {
+                if message["method"] == "elicitation/create" {
+                    prompts.push(message["params"]["message"].clone());
+                    answering.route_reply(&json!({
+                        "jsonrpc": "2.0",
+                        "id": message["id"],
+                        "result": { "action": "accept", "content": { "response": "Ada" } },
+                    }));
+                }
+            }
+            prompts
+        });
+        let result = request(
+            &state,
+            &peer,
+            "tools/call",
+            json!({ "name": "greet", "arguments": { "salutation": "Hello" } }),
+        )
+        .await;
+        assert_eq!(result["isError"], false, "{result}");
+        assert_eq!(
+            result["structuredContent"],
+            json!({ "greeting": "Hello Ada" })
+        );
+        drop(peer);
+        assert_eq!(answers.await.unwrap(), vec![json!("Name?")]);
+
+        // A client without elicitation gets a resumable result instead.
+        let (peer, _outgoing) = peer_for(&Arc::new(ClientSession::default()));
+        let paused = request(
+            &state,
+            &peer,
+            "tools/call",
+            json!({ "name": "daily-greet", "arguments": {} }),
+        )
+        .await;
+        let pause = &paused["structuredContent"];
+        assert_eq!(pause["status"], "paused", "{paused}");
+        assert_eq!(pause["waiting_for"], "input");
+        assert_eq!(pause["prompt"], "Name?");
+        let session_id = pause["session_id"].as_str().unwrap().to_string();
+
+        let resumed = request(
+            &state,
+            &peer,
+            "tools/call",
+            json!({
+                "name": RESUME_TOOL,
+                "arguments": { "tool": "daily-greet", "session_id": session_id, "response": "Grace" },
+            }),
+        )
+        .await;
+        assert_eq!(
+            resumed["structuredContent"],
+            json!({ "greeting": "Hi Grace" })
+        );
+        let stored = state.app.session_store.get(&session_id).unwrap().unwrap();
+        assert_eq!(stored.status, crate::storage::SessionStatus::Completed);
+        assert_eq!(stored.input, json!({ "salutation": "Hi" }));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn approval_pauses_elicit_a_decision_or_resume_later() {
+        let dir =
+            std::env::temp_dir().join(format!("chidori-mcp-approve-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("fetcher.ts"),
+            r#"
+                import { run } from "chidori:agent";
+                run(async () => {
+                    try {
+                        await fetch("https://example.invalid/");
+                        return { error: null };
+                    } catch (e) {
+                        return { error: String(e) };
+                    }
+                });
+            "#,
+        )
+        .unwrap();
+        let mut state = {
+            let dir = dir.clone();
+            tokio::task::spawn_blocking(move || serve_state(&dir, Vec::new()))
+                .await
+                .unwrap()
+        };
+        // Every gated effect asks first.
+        state.app.policy = Arc::new(crate::policy::builtin_profile("supervised").unwrap());
+
+        // Answer each elicitation with `reply`, recording what was asked.
+        let call = |reply: Value| {
+            let state = state.clone();
+            async move {
+                let client = Arc::new(ClientSession::default());
+                client.elicitation.store(true, Ordering::Relaxed);
+                let (peer, mut outgoing) = peer_for(&client);
+                let answering = client.clone();
+                let answers = tokio::spawn(async move {
+                    let mut asked = Vec::new();
+                    while let Some(message) = outgoing.recv().await {
+                        if message["method"] == "elicitation/create" {
+                            asked.push(message["params"]["message"].clone());
+                            answering.route_reply(&json!({
+                                "jsonrpc": "2.0",
+                                "id": message["id"],
+                                "result": reply.clone(),
+                            }));
+                        }
+                    }
+                    asked
+                });
+                let result = request(
+                    &state,
+                    &peer,
+                    "tools/call",
+                    json!({ "name": "fetcher", "arguments": {} }),
+                )
+                .await;
+                drop(peer);
+                (result, answers.await.unwrap())
+            }
+        };
+
+        // Accept: the approved call runs (and fails offline, inside the agent).
+        let (result, asked) =
+            call(json!({ "action": "accept", "content": { "approve": true } })).await;
+        assert_eq!(asked, vec![json!("Allow `http`?")], "{result}");
+        assert_eq!(result["isError"], false, "{result}");
+        let error = result["structuredContent"]["error"]
+            .as_str()
+            .unwrap_or_default();
+        assert!(!error.contains("denied"), "{result}");
+
+        // Decline: the operator's denial fails the run.
+        let (result, _) = call(json!({ "action": "decline" })).await;
+        assert_eq!(result["isError"], true, "{result}");
+        let error = result["content"][0]["text"].as_str().unwrap_or_default();
+        assert!(error.contains("denied by operator"), "{result}");
+
+        // Cancel: the run stays paused for `chidori_resume`.
+        let (result, _) = call(json!({ "action": "cancel" })).await;
+        let pause = &result["structuredContent"];
+        assert_eq!(pause["status"], "paused", "{result}");
+        assert_eq!(pause["waiting_for"], "approval");
+        let session_id = pause["session_id"].as_str().unwrap();
+        let stored = state.app.session_store.get(session_id).unwrap().unwrap();
+        assert_eq!(stored.status, SessionStatus::AwaitingApproval);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_transport_opens_sessions_and_streams_tool_results() {
+        use tower::ServiceExt as _;
+
+        let dir = std::env::temp_dir().join(format!("chidori-mcp-http-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("echo.ts"),
+            "import { run } from \"chidori:agent\";\nrun(async (input) => ({ echoed: input.text }));\n",
+        )
+        .unwrap();
+        let state = {
+            let dir = dir.clone();
+            tokio::task::spawn_blocking(move || serve_state(&dir, Vec::new()))
+                .await
+                .unwrap()
+        };
+        let router = mcp_http_router(state.clone(), Duration::from_secs(600));
+        let post = |session: Option<&str>, body: Value| {
+            let mut request =
+                axum::http::Request::post("/mcp").header("content-type", "application/json");
+            if let Some(session) = session {
+                request = request.header(SESSION_HEADER, session);
+            }
+            request
+                .body(axum::body::Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let response = router
+            .clone()
+            .oneshot(post(
+                None,
+                json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }),
+            ))
+            .await
+            .unwrap();
+        let session = response.headers()[SESSION_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let response = router
+            .clone()
+            .oneshot(post(
+                Some(&session),
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 2,
+                    "method": "tools/call",
+                    "params": { "name": "echo", "arguments": { "text": "hi" } },
+                }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.headers()["content-type"], "text/event-stream");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let event = String::from_utf8(body.to_vec()).unwrap();
+        let data = event
+            .lines()
+            .find_map(|line| line.strip_prefix("data: "))
+            .unwrap();
+        let reply: Value = serde_json::from_str(data).unwrap();
+        assert_eq!(reply["id"], 2);
+        assert_eq!(
+            reply["result"]["structuredContent"],
+            json!({ "echoed": "hi" })
+        );
+
+        let response = router
+            .clone()
+            .oneshot(post(
+                Some("no-such-session"),
+                json!({ "jsonrpc": "2.0", "id": 3, "method": "ping" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // A session left idle past the TTL is dropped on the next request.
+        let router = mcp_http_router(state, Duration::from_millis(20));
+        let response = router
+            .clone()
+            .oneshot(post(
+                None,
+                json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }),
+            ))
+            .await
+            .unwrap();
+        let session = response.headers()[SESSION_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let ping = json!({ "jsonrpc": "2.0", "id": 4, "method": "ping" });
+        let response = router
+            .clone()
+            .oneshot(post(Some(&session), ping.clone()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let response = router
+            .clone()
+            .oneshot(post(Some(&session), ping))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -11,13 +11,13 @@ use std::sync::{Arc, Mutex as StdMutex};
 use axum::http::StatusCode;
 use axum::middleware;
 use axum::response::{IntoResponse, Json, Response};
-use axum::routing::{any, get, post};
+use axum::routing::{any, delete, get, post};
 use axum::Router;
 use serde_json::{json, Value};
 use tokio::sync::Semaphore;
 
 use crate::acp::{self, AcpState};
-use crate::mcp::{McpManager, McpServersConfig};
+use crate::mcp::{McpManager, McpServersConfig, SupervisorSettings};
 use crate::policy::PolicyConfig;
 use crate::providers::ProviderRegistry;
 use crate::recipes::Recipe;
@@ -30,16 +30,18 @@ use crate::runtime::snapshot::{
 use crate::runtime::template::TemplateEngine;
 use crate::scheduler::{self, SchedulerDeps};
 use crate::storage::{build_session_store, SessionStatus, SessionStore, StoredSession};
-use crate::tools::{ToolDef, ToolRegistry};
 
 mod app_routes;
 mod detached;
 mod engine;
 mod events;
 mod hardening;
+mod mcp_serve;
 mod preflight;
 mod recipes;
+mod retention;
 mod sessions;
+mod tenants;
 #[cfg(test)]
 mod tests;
 
@@ -49,15 +51,19 @@ use detached::{
 use engine::run_agent_sync;
 use events::handle_event;
 use hardening::{
-    allow_unauthenticated_from_env, auth_middleware, build_cors_layer, health, is_loopback_host,
+    auth_middleware, build_cors_layer, get_metrics, health, is_loopback_host, mcp_health,
+    refuse_unauthenticated_bind,
 };
+pub use mcp_serve::{mcp_serve, McpTransport};
 use recipes::{list_recipes, run_recipe};
+pub use retention::{parse_age, RetentionPolicy};
 use sessions::resume::{approve_session, resume_session, signal_session};
-use sessions::stream::{attach_session_stream, stream_session, SessionEventLog};
+use sessions::stream::{attach_session_stream, stream_session, PauseAnswer, SessionEventLog};
+use sessions::ws::session_ws;
 use sessions::{
-    agent_error_string, arm_signal_timeout, cancel_session, create_session, get_checkpoint,
-    get_holdings, get_session, get_snapshot_manifest, list_agents, list_sessions, replay_session,
-    session_policy,
+    agent_error_string, arm_signal_timeout, cancel_session, create_session, delete_session,
+    get_audit, get_checkpoint, get_holdings, get_session, get_snapshot_manifest, list_agents,
+    list_sessions, replay_session,
 };
 
 // Test-only re-imports: they keep the flat namespace the test module's
@@ -90,8 +96,9 @@ struct AppState {
     run_base: PathBuf,
     session_store: Arc<dyn SessionStore>,
     policy: Arc<PolicyConfig>,
+    /// MCP servers and their live tool catalog (`McpManager::tool_defs`),
+    /// read each time a run builds its ToolRegistry.
     mcp: Arc<McpManager>,
-    mcp_tools: Arc<Vec<ToolDef>>,
     recipes: Arc<Vec<Recipe>>,
     /// Caps the number of agent runs executing concurrently.
     run_semaphore: Arc<Semaphore>,
@@ -115,6 +122,31 @@ struct AppState {
     /// How long a warm-parked run waits for its resume before evicting itself
     /// back to the unwind path (freeing the thread and VM).
     warm_evict: std::time::Duration,
+    /// The tenant this state serves (`server::tenants`); `None` for the
+    /// operator's.
+    tenant: Option<Arc<tenants::Tenant>>,
+}
+
+impl AppState {
+    /// The tenant id stamped on the sessions this state creates.
+    fn tenant_id(&self) -> Option<String> {
+        self.tenant.as_ref().map(|t| t.id.clone())
+    }
+
+    /// What the recipe scheduler needs to run this state's recipes: a
+    /// tenant's ticks and manual runs stay inside its directory and quota.
+    fn scheduler_deps(&self) -> SchedulerDeps {
+        SchedulerDeps {
+            providers: self.providers.clone(),
+            template_engine: self.template_engine.clone(),
+            session_store: self.session_store.clone(),
+            policy: self.policy.clone(),
+            mcp: self.mcp.clone(),
+            run_base: self.run_base.clone(),
+            workspace_root: self.tenant.as_ref().map(|t| t.root.clone()),
+            quota: self.tenant.as_ref().and_then(|t| t.quota),
+        }
+    }
 }
 
 /// One session's warm run: the channel its parked engine thread listens on
@@ -142,6 +174,24 @@ fn warm_evict_from_env() -> std::time::Duration {
     std::time::Duration::from_millis(ms)
 }
 
+/// Configurable concurrency cap. Default 8 is low enough to keep one LLM
+/// provider from being flooded and high enough that a small agent fleet can
+/// saturate. Exposed as an env var so ops can tune without a rebuild.
+fn max_concurrent_from_env() -> usize {
+    std::env::var("CHIDORI_MAX_CONCURRENT_SESSIONS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|n: &usize| *n > 0)
+        .unwrap_or(8)
+}
+
+fn acquire_timeout_ms_from_env() -> u64 {
+    std::env::var("CHIDORI_ACQUIRE_TIMEOUT_MS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(30_000)
+}
+
 /// Register a fresh warm run for `session_id` (replacing any stale entry) and
 /// build the [`WarmInputBridge`] its engine leg installs: on an `input()`
 /// pause the bridge surfaces a paused `RunResult` on the outcome channel —
@@ -273,6 +323,9 @@ struct ActiveSession {
     /// `GET /sessions/{id}/stream`, replay what it missed, and follow live.
     /// `None` for non-streaming runs (nothing is emitted to re-attach to).
     event_log: Option<Arc<SessionEventLog>>,
+    /// Upstream for input and approval answers into the supervisor, present
+    /// while a WebSocket client (`GET /sessions/{id}/ws`) drives the session.
+    answers: Option<tokio::sync::mpsc::UnboundedSender<PauseAnswer>>,
 }
 
 /// The live-delivery handle a streaming worker registers in `active_sessions`.
@@ -312,6 +365,11 @@ fn session_view(s: &StoredSession) -> Value {
         "pending_signal_deadline": s.pending_signal_deadline,
         "pending_approval": s.pending_approval,
         "policy_profile": s.policy_profile,
+        "agent": s.agent,
+        "recipe": s.recipe,
+        "scheduled_for": s.scheduled_for,
+        "tenant": s.tenant,
+        "created_at": s.created_at,
     })
 }
 
@@ -576,21 +634,23 @@ fn resolve_persisted_pending_host_operation(
     .map(|_| ())
 }
 
-/// Spawn the application manifest's `keep_alive` agents that aren't already
-/// live in the registry. Best-effort per agent: one bad entry logs and skips
+/// Spawn `state`'s `keep_alive` agents from the application manifest (a
+/// tenant's own, or the untenanted ones for the operator) that aren't already
+/// live in its registry. Best-effort per agent: one bad entry logs and skips
 /// rather than taking the whole boot down (the manifest itself was validated
 /// at load).
-fn boot_manifest_fleet(manifest: &crate::app_manifest::AppManifest) {
+fn boot_manifest_fleet(state: &AppState, manifest: &crate::app_manifest::AppManifest) {
     let hub = crate::runtime::host_agent::hub();
-    let parts = match hub.installed_parts() {
+    let parts = match detached::agent_runtime_parts(state) {
         Ok(parts) => parts,
         Err(err) => {
             tracing::warn!("app manifest fleet: {err}");
             return;
         }
     };
+    let tenant = state.tenant_id();
     let factory = crate::runtime::store::RunStoreFactory::shared(&parts.run_base);
-    for agent in manifest.fleet() {
+    for agent in manifest.fleet().filter(|agent| agent.tenant == tenant) {
         let live = factory
             .registry_get(&agent.name)
             .ok()
@@ -639,6 +699,138 @@ fn boot_manifest_fleet(manifest: &crate::app_manifest::AppManifest) {
     }
 }
 
+/// Bring one state's durable work back after a restart and keep it going:
+/// signal timeouts, retention, the detached-agent fleet, and the recipe
+/// schedules. Runs once for the operator's state and once per tenant.
+fn boot_state(
+    state: &AppState,
+    app_manifest: Option<&crate::app_manifest::AppManifest>,
+    retention: RetentionPolicy,
+) -> anyhow::Result<()> {
+    // Re-arm signal-pause timeout timers (`timeoutMs`, `docs/signals.md`
+    // Phase 2) for sessions persisted with a deadline by a previous server
+    // process. Deadlines already in the past fire (resolve to the timeout
+    // sentinel) immediately.
+    if let Ok(sessions) = state.session_store.list() {
+        for session in &sessions {
+            arm_signal_timeout(state, session);
+        }
+    }
+
+    // Delete settled sessions (and their runs) once they outlive the
+    // configured retention (`--retain-completed` and friends).
+    retention::spawn_sweeper(state.clone(), retention);
+
+    // Re-arm the detached-agent fleet (`docs/detached-agents.md`): install the
+    // hub's runtime parts, then wake agents that were mid-run when the
+    // previous process died and re-arm hibernating agents' alarm deadlines.
+    let parts = detached::agent_runtime_parts(state)?;
+    match crate::runtime::host_agent::hub().rearm_from_registry(parts) {
+        Ok(count) if count > 0 => match &state.tenant {
+            Some(tenant) => eprintln!(
+                "  Re-armed {count} detached agent(s) of tenant `{}` from the registry",
+                tenant.id
+            ),
+            None => eprintln!("  Re-armed {count} detached agent(s) from the registry"),
+        },
+        Ok(_) => {}
+        Err(err) => tracing::warn!("re-arming detached agents: {err}"),
+    }
+
+    // Boot the manifest's keep_alive fleet: spawn every declared agent whose
+    // name is not already live in the registry (live ones were just re-armed
+    // above; settled ones are replaced by a fresh spawn, mailbox migration
+    // included, exactly like a `chidori.agents.spawn` reusing the name).
+    if let Some(manifest) = app_manifest {
+        boot_manifest_fleet(state, manifest);
+    }
+
+    // Spawn cron loops for every recipe with a schedule.
+    scheduler::spawn_all(state.recipes.as_ref().clone(), state.scheduler_deps());
+    Ok(())
+}
+
+/// The session, agent and recipe API over `state` — what the operator's key
+/// and each tenant's keys reach.
+fn api_routes(state: AppState) -> Router {
+    Router::new()
+        // Session API
+        .route("/sessions", post(create_session))
+        .route("/sessions", get(list_sessions))
+        .route("/sessions/{id}", get(get_session))
+        .route("/sessions/{id}", delete(delete_session))
+        .route("/sessions/{id}/checkpoint", get(get_checkpoint))
+        .route("/sessions/{id}/snapshot", get(get_snapshot_manifest))
+        .route("/sessions/{id}/holdings", get(get_holdings))
+        .route("/sessions/{id}/audit", get(get_audit))
+        .route("/sessions/{id}/replay", post(replay_session))
+        .route("/sessions/{id}/resume", post(resume_session))
+        .route("/sessions/{id}/signal", post(signal_session))
+        .route("/sessions/{id}/approve", post(approve_session))
+        .route("/sessions/{id}/cancel", post(cancel_session))
+        .route("/sessions/stream", post(stream_session))
+        // SSE re-attach: catch up on a live streaming session's already-
+        // emitted events and follow it to settlement, or replay a settled
+        // session's journal. Same bearer auth as the other session routes
+        // (the auth middleware wraps the whole router).
+        .route("/sessions/{id}/stream", get(attach_session_stream))
+        // WebSocket transport: the same event stream downstream, input
+        // answers, signals, approvals and cancel upstream on one connection.
+        .route("/sessions/{id}/ws", get(session_ws))
+        // Example agent discovery — peer directory of the server's
+        // configured agent. Lets clients like util-trace-webgl pick an
+        // example to run without restarting the server.
+        .route("/agents", get(list_agents))
+        // Detached durable agents (docs/detached-agents.md): registry
+        // listing, status, mailbox delivery (which wakes a hibernating
+        // agent), and cooperative stop.
+        .route("/agents/detached", get(list_detached_agents))
+        .route("/agents/detached/{name}", get(get_detached_agent))
+        .route("/agents/detached/{name}/send", post(send_detached_agent))
+        .route("/agents/detached/{name}/stop", post(stop_detached_agent))
+        // Recipes + scheduler
+        .route("/recipes", get(list_recipes))
+        .route("/recipes/{name}/run", post(run_recipe))
+        .with_state(state)
+}
+
+/// Add the application manifest's webhook routes whose agent belongs to
+/// `state` (a tenant's own agents for a tenant's router, the untenanted ones
+/// for the operator's). Each path delivers into a detached agent's mailbox
+/// as a named signal; registered before the fallback/auth layers so they are
+/// real routes under the same bearer auth as everything else.
+fn with_manifest_routes(
+    mut app: Router,
+    state: &AppState,
+    app_manifest: Option<&crate::app_manifest::AppManifest>,
+) -> Router {
+    let Some(manifest) = app_manifest else {
+        return app;
+    };
+    let tenant = state.tenant_id();
+    for route in &manifest.routes {
+        let route_tenant = manifest
+            .agents
+            .iter()
+            .find(|agent| agent.name == route.agent)
+            .and_then(|agent| agent.tenant.clone());
+        if route_tenant != tenant {
+            continue;
+        }
+        let target = Arc::new(app_routes::AppRouteTarget {
+            path: route.path.clone(),
+            agent: route.agent.clone(),
+            signal: route.signal.clone(),
+            state: state.clone(),
+        });
+        app = app.route(
+            &route.path,
+            post(app_routes::deliver_app_route).with_state(target),
+        );
+    }
+    app
+}
+
 // ---------------------------------------------------------------------------
 // Server entry point
 // ---------------------------------------------------------------------------
@@ -652,6 +844,7 @@ pub async fn serve(
     policy: Arc<PolicyConfig>,
     policy_posture: String,
     app_manifest: Option<crate::app_manifest::AppManifest>,
+    retention: RetentionPolicy,
 ) -> anyhow::Result<()> {
     // No agent file → a fleet-only server: it re-arms and drives the
     // detached-agent fleet under the current directory, and every session
@@ -664,52 +857,46 @@ pub async fn serve(
     // Fail closed on the dangerous combination FIRST — before MCP servers
     // start, cron loops spawn, or the manifest fleet boots: a server that is
     // going to refuse its bind must not execute agent code on the way down.
-    // A network-reachable bind with no authentication means anyone who can
-    // route to the port can execute agent code. The default bind is
-    // loopback, so this only trips when the operator explicitly asked for a
-    // wider bind without setting a key.
+    let tenant_count = app_manifest.as_ref().map_or(0, |m| m.tenants.len());
+    refuse_unauthenticated_bind(&host, port, tenant_count > 0)?;
+    if tenant_count > 0 {
+        tenants::refuse_shared_roots()?;
+    }
     let auth_required = std::env::var("CHIDORI_API_KEY").is_ok();
     let loopback = is_loopback_host(&host);
-    if !loopback && !auth_required && !allow_unauthenticated_from_env() {
-        anyhow::bail!(
-            "refusing to bind {host}:{port} without authentication: a non-loopback bind \
-             exposes this server — which executes agent code — to the network with no \
-             access control. Either set CHIDORI_API_KEY to require bearer auth, keep the \
-             default loopback bind (drop --host / CHIDORI_HOST), or set \
-             CHIDORI_ALLOW_UNAUTHENTICATED=1 if a reverse proxy or firewall in front of \
-             this server already controls access."
-        );
-    }
-    // Configurable concurrency cap. Default 8 is low enough to keep one
-    // LLM provider from being flooded and high enough that a small agent
-    // fleet can saturate. Expose as env var so ops can tune without a
-    // rebuild.
-    let max_concurrent: usize = std::env::var("CHIDORI_MAX_CONCURRENT_SESSIONS")
-        .ok()
-        .and_then(|v| v.parse().ok())
-        .filter(|n: &usize| *n > 0)
-        .unwrap_or(8);
-    let acquire_timeout_ms: u64 = std::env::var("CHIDORI_ACQUIRE_TIMEOUT_MS")
-        .ok()
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(30_000);
+    let max_concurrent = max_concurrent_from_env();
+    let acquire_timeout_ms = acquire_timeout_ms_from_env();
 
     // Load the MCP servers, recipes, and session store up front so startup
     // errors happen before we bind the listener. The permission policy is
     // resolved by the caller (CLI flag or CHIDORI_POLICY* env vars).
     let mcp = Arc::new(McpManager::new());
     let mcp_cfg = McpServersConfig::load_from_env().unwrap_or_default();
-    let mcp_tools = mcp.start_from_config(&mcp_cfg).await.unwrap_or_else(|e| {
+    if let Err(e) = mcp.start_from_config(&mcp_cfg).await {
         tracing::warn!("MCP startup: {}", e);
-        Vec::new()
-    });
-    let mcp_tools = Arc::new(mcp_tools);
+    }
+    // Restart crashed servers, follow their tool-list changes, and pick up
+    // edits to the config file without a server restart.
+    mcp.supervise(SupervisorSettings::from_env());
+    if let Some(path) = McpServersConfig::path_from_env() {
+        mcp.watch_config(path);
+    }
 
     let base_dir = agent_path
         .parent()
         .unwrap_or_else(|| std::path::Path::new("."))
         .to_path_buf();
-    let session_store = build_session_store(&base_dir)?;
+    let shared_store = build_session_store(&base_dir)?;
+    // With tenants, the operator's view of the store leaves their sessions
+    // out, as each tenant's leaves out everyone else's.
+    let session_store: Arc<dyn SessionStore> = if tenant_count > 0 {
+        Arc::new(crate::storage::TenantScopedStore::new(
+            shared_store.clone(),
+            None,
+        ))
+    } else {
+        shared_store.clone()
+    };
     let run_base = base_dir.join(".chidori").join("runs");
 
     let recipe_dir = std::env::var("CHIDORI_RECIPE_DIR").ok().map(PathBuf::from);
@@ -723,7 +910,7 @@ pub async fn serve(
     // declarative config wins, and two cron loops for one name would run the
     // job twice per tick.
     if let Some(manifest) = &app_manifest {
-        for recipe in manifest.to_recipes() {
+        for recipe in manifest.to_recipes(None) {
             if let Some(existing) = recipes.iter().position(|r| r.name == recipe.name) {
                 tracing::warn!(
                     "app manifest schedule `{}` overrides the recipe of the same name from \
@@ -735,20 +922,6 @@ pub async fn serve(
             recipes.push(recipe);
         }
     }
-    let recipes_arc = Arc::new(recipes.clone());
-
-    // Spawn cron loops for every recipe with a schedule.
-    scheduler::spawn_all(
-        recipes,
-        SchedulerDeps {
-            providers: providers.clone(),
-            template_engine: template_engine.clone(),
-            session_store: session_store.clone(),
-            policy: policy.clone(),
-            mcp: mcp.clone(),
-            mcp_tools: (*mcp_tools).clone(),
-        },
-    );
 
     let state = AppState {
         providers,
@@ -759,61 +932,30 @@ pub async fn serve(
         session_store,
         policy,
         mcp,
-        mcp_tools,
-        recipes: recipes_arc,
+        recipes: Arc::new(recipes),
         run_semaphore: Arc::new(Semaphore::new(max_concurrent)),
         acquire_timeout: std::time::Duration::from_millis(acquire_timeout_ms),
         active_sessions: Arc::new(StdMutex::new(HashMap::new())),
         signal_inbox_locks: Arc::new(StdMutex::new(HashMap::new())),
         warm_runs: Arc::new(StdMutex::new(HashMap::new())),
         warm_evict: warm_evict_from_env(),
+        tenant: None,
     };
-
-    // Re-arm signal-pause timeout timers (`timeoutMs`, `docs/signals.md`
-    // Phase 2) for sessions persisted with a deadline by a previous server
-    // process. Deadlines already in the past fire (resolve to the timeout
-    // sentinel) immediately.
-    if let Ok(sessions) = state.session_store.list() {
-        for session in &sessions {
-            arm_signal_timeout(&state, session);
-        }
-    }
-
-    // Re-arm the detached-agent fleet (`docs/detached-agents.md`): install the
-    // hub's runtime parts, then wake agents that were mid-run when the
-    // previous process died and re-arm hibernating agents' alarm deadlines.
-    {
-        let rt = crate::scheduler::shared_tokio_runtime()?;
-        // The registry holds only externally-sourced tools (MCP servers).
-        // Agent tools are defined in-VM with `defineTool` and never registered.
-        let mut registry = ToolRegistry::new();
-        for def in state.mcp_tools.iter() {
-            registry.register(def.clone());
-        }
-        let parts = crate::runtime::host_agent::AgentRuntimeParts {
-            providers: state.providers.clone(),
-            template_engine: state.template_engine.clone(),
-            tokio_rt: rt,
-            policy: session_policy(&state, None),
-            tools: Arc::new(registry),
-            mcp: state.mcp.clone(),
-            run_base: state.run_base.clone(),
-        };
-        match crate::runtime::host_agent::hub().rearm_from_registry(parts) {
-            Ok(count) if count > 0 => {
-                eprintln!("  Re-armed {count} detached agent(s) from the registry");
-            }
-            Ok(_) => {}
-            Err(err) => tracing::warn!("re-arming detached agents: {err}"),
+    let mut tenant_states = Vec::with_capacity(tenant_count);
+    if let Some(manifest) = &app_manifest {
+        for tenant in &manifest.tenants {
+            tenant_states.push(tenants::tenant_state(
+                &state,
+                manifest,
+                tenant,
+                &base_dir,
+                shared_store.clone(),
+            )?);
         }
     }
 
-    // Boot the manifest's keep_alive fleet: spawn every declared agent whose
-    // name is not already live in the registry (live ones were just re-armed
-    // above; settled ones are replaced by a fresh spawn, mailbox migration
-    // included, exactly like a `chidori.agents.spawn` reusing the name).
-    if let Some(manifest) = &app_manifest {
-        boot_manifest_fleet(manifest);
+    for boot in tenant_states.iter().chain(std::iter::once(&state)) {
+        boot_state(boot, app_manifest.as_ref(), retention.clone())?;
     }
 
     let cors_layer = build_cors_layer();
@@ -831,63 +973,38 @@ pub async fn serve(
 
     let app = Router::new()
         .route("/health", get(health))
-        // Session API
-        .route("/sessions", post(create_session))
-        .route("/sessions", get(list_sessions))
-        .route("/sessions/{id}", get(get_session))
-        .route("/sessions/{id}/checkpoint", get(get_checkpoint))
-        .route("/sessions/{id}/snapshot", get(get_snapshot_manifest))
-        .route("/sessions/{id}/holdings", get(get_holdings))
-        .route("/sessions/{id}/replay", post(replay_session))
-        .route("/sessions/{id}/resume", post(resume_session))
-        .route("/sessions/{id}/signal", post(signal_session))
-        .route("/sessions/{id}/approve", post(approve_session))
-        .route("/sessions/{id}/cancel", post(cancel_session))
-        .route("/sessions/stream", post(stream_session))
-        // SSE re-attach: catch up on a live streaming session's already-
-        // emitted events and follow it to settlement, or replay a settled
-        // session's journal. Same bearer auth as the other session routes
-        // (the auth middleware wraps the whole router).
-        .route("/sessions/{id}/stream", get(attach_session_stream))
-        // Example agent discovery — peer directory of the server's
-        // configured agent. Lets clients like util-trace-webgl pick an
-        // example to run without restarting the server.
-        .route("/agents", get(list_agents))
-        // Detached durable agents (docs/detached-agents.md): registry
-        // listing, status, mailbox delivery (which wakes a hibernating
-        // agent), and cooperative stop.
-        .route("/agents/detached", get(list_detached_agents))
-        .route("/agents/detached/{name}", get(get_detached_agent))
-        .route("/agents/detached/{name}/send", post(send_detached_agent))
-        .route("/agents/detached/{name}/stop", post(stop_detached_agent))
-        // Recipes + scheduler
-        .route("/recipes", get(list_recipes))
-        .route("/recipes/{name}/run", post(run_recipe))
+        .route("/health/mcp", get(mcp_health))
+        .route("/metrics", get(get_metrics))
         .with_state(state.clone())
+        .merge(api_routes(state.clone()))
         // ACP endpoints (separate sub-router so it carries its own state).
         .merge(acp::router(acp_state));
-
-    // Manifest webhook routes: each path delivers into a detached agent's
-    // mailbox as a named signal. Registered before the fallback/auth layers
-    // so they are real routes under the same bearer auth as everything else.
-    let mut app = app;
-    if let Some(manifest) = &app_manifest {
-        for route in &manifest.routes {
-            let target = Arc::new(app_routes::AppRouteTarget {
-                path: route.path.clone(),
-                agent: route.agent.clone(),
-                signal: route.signal.clone(),
-            });
-            app = app.route(
-                &route.path,
-                post(app_routes::deliver_app_route).with_state(target),
-            );
-        }
-    }
-    let app = app
+    let app = with_manifest_routes(app, &state, app_manifest.as_ref())
         // Event-driven fallback
         .fallback(any(handle_event).with_state(state.clone()))
-        .layer(middleware::from_fn(auth_middleware))
+        .layer(middleware::from_fn(auth_middleware));
+
+    // Each tenant's keys reach its own copy of the API (checked ahead of the
+    // operator's bearer auth — see `tenants::tenant_middleware`).
+    let tenant_routes: Vec<tenants::TenantRoute> = tenant_states
+        .iter()
+        .map(|tenant_state| {
+            let router = with_manifest_routes(
+                api_routes(tenant_state.clone()),
+                tenant_state,
+                app_manifest.as_ref(),
+            )
+            .fallback(any(handle_event).with_state(tenant_state.clone()));
+            tenants::TenantRoute {
+                tenant: tenant_state.tenant.clone().expect("tenant state"),
+                router,
+            }
+        })
+        .collect();
+    let tenant_layer =
+        middleware::from_fn_with_state(Arc::new(tenant_routes), tenants::tenant_middleware);
+    let app = Router::new()
+        .fallback_service(tower::Layer::layer(&tenant_layer, app))
         .layer(cors_layer);
 
     let addr = format!("{host}:{port}");
@@ -914,6 +1031,13 @@ pub async fn serve(
              anyone who can reach the port can execute agents"
         }
     );
+    if let Some(manifest) = app_manifest.as_ref().filter(|m| !m.tenants.is_empty()) {
+        let ids: Vec<&str> = manifest.tenants.iter().map(|t| t.id.as_str()).collect();
+        eprintln!(
+            "  Tenants:     {} (each behind its own keys, in .chidori/tenants/<id>/)",
+            ids.join(", ")
+        );
+    }
     eprintln!("  Policy:      {}", policy_posture);
     // Effect preflight (warning only, see server/preflight.rs): agents don't
     // statically declare their effects, so scan the served agent's source for
@@ -948,7 +1072,10 @@ pub async fn serve(
     eprintln!(
         "              GET  /sessions/{{id}}/stream     → re-attach: replay + follow SSE events"
     );
+    eprintln!("              GET  /sessions/{{id}}/ws         → WebSocket: events + answers");
     eprintln!("  Health:     GET  /health");
+    eprintln!("              GET  /health/mcp                 → MCP server states (auth)");
+    eprintln!("  Metrics:    GET  /metrics                    → Prometheus text format");
 
     let listener = tokio::net::TcpListener::bind(&addr).await?;
     axum::serve(listener, app).await?;
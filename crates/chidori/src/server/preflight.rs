@@ -112,15 +112,16 @@ pub(super) fn static_effect_refs(source: &str) -> Vec<StaticEffectRef> {
 
 /// True when SOME argument shape could get a call to `target` past this
 /// config layer (i.e. resolve to a decision other than `NeverAllow`).
-/// Rules are first-match-wins: a rule with `match_args` only covers the args
-/// it matches, so scanning continues past it; a rule without `match_args`
-/// covers every remaining argument shape and ends the scan.
+/// Rules are first-match-wins: a conditional rule (`match_args`, a time
+/// window, or a rate limit) only covers some calls, so scanning continues
+/// past it; an unconditional rule covers every remaining call and ends the
+/// scan.
 fn local_may_allow(cfg: &PolicyConfig, target: &str) -> bool {
     for rule in &cfg.rules {
         if rule.target != target && rule.target != "*" {
             continue;
         }
-        if rule.match_args.is_some() {
+        if !rule.is_unconditional() {
             if rule.decision != Decision::NeverAllow {
                 return true;
             }
@@ -161,12 +162,47 @@ pub(super) fn denied_static_effects(
         .collect()
 }
 
+/// The time-window and rate-limit rules (in every layer) that govern a
+/// target the agent's source references, each described for the operator,
+/// e.g. "`http`: never_allow for more than 20 calls per 60s (egress cap)".
+pub(super) fn conditional_guardrails(source: &str, policy: &PolicyConfig) -> Vec<String> {
+    let refs = static_effect_refs(source);
+    let mut notes = Vec::new();
+    let mut layer = Some(policy);
+    while let Some(cfg) = layer {
+        for rule in &cfg.rules {
+            let Some(conditions) = rule.describe_conditions() else {
+                continue;
+            };
+            for r in refs
+                .iter()
+                .filter(|r| rule.target == r.target || rule.target == "*")
+            {
+                let decision = serde_json::to_value(rule.decision).unwrap_or_default();
+                notes.push(format!(
+                    "`{}`: {} for {conditions}{}",
+                    r.target,
+                    decision.as_str().unwrap_or_default(),
+                    rule.reason
+                        .as_deref()
+                        .map(|reason| format!(" ({reason})"))
+                        .unwrap_or_default(),
+                ));
+            }
+        }
+        layer = cfg.overlay.as_deref();
+    }
+    notes
+}
+
 /// Print a stderr warning for every effect surface the agent's source
 /// statically references that `policy` unconditionally denies. `posture` is
 /// the human-readable name of the active policy (the startup banner's policy
-/// posture, or a session's profile name). Warning only — never refuses.
-/// Unreadable agent files are silently skipped (the run itself will surface
-/// the real error).
+/// posture, or a session's profile name), followed by a note for each
+/// time-window or rate-limit guardrail on a referenced target — those don't
+/// deny outright, but an operator should know a run can hit them. Warning
+/// only — never refuses. Unreadable agent files are silently skipped (the
+/// run itself will surface the real error).
 pub(super) fn warn_denied_static_effects(agent_path: &Path, policy: &PolicyConfig, posture: &str) {
     let Ok(source) = std::fs::read_to_string(agent_path) else {
         return;
@@ -182,4 +218,7 @@ pub(super) fn warn_denied_static_effects(agent_path: &Path, policy: &PolicyConfi
                 .unwrap_or_default(),
         );
     }
+    for note in conditional_guardrails(&source, policy) {
+        eprintln!("note: the active policy ({posture}) limits {note}");
+    }
 }
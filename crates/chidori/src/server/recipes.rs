@@ -5,7 +5,7 @@ use axum::http::StatusCode;
 use axum::response::{IntoResponse, Json, Response};
 use serde_json::{json, Value};
 
-use crate::scheduler::{self, SchedulerDeps};
+use crate::scheduler::{self, RecipeSchedule};
 
 use super::AppState;
 
@@ -13,16 +13,35 @@ use super::AppState;
 // Recipes
 // ---------------------------------------------------------------------------
 
+/// GET /recipes — each recipe with its schedule settings, its next tick
+/// (before jitter), and the last scheduled tick's session and outcome.
 pub(super) async fn list_recipes(State(state): State<AppState>) -> impl IntoResponse {
+    let now = chrono::Utc::now();
     let recipes: Vec<Value> = state
         .recipes
         .iter()
         .map(|r| {
+            let next_tick = RecipeSchedule::for_recipe(r)
+                .ok()
+                .flatten()
+                .and_then(|schedule| schedule.next_after(now));
+            let last = state.session_store.latest_scheduled(&r.name).ok().flatten();
             json!({
                 "name": r.name,
                 "agent": r.agent,
                 "schedule": r.schedule,
                 "description": r.description,
+                "timezone": r.timing.timezone.as_deref().unwrap_or("UTC"),
+                "catch_up": r.timing.catch_up,
+                "overlap": r.timing.overlap,
+                "jitter_secs": r.timing.jitter_secs,
+                "next_tick": next_tick,
+                "last_tick": last.as_ref().and_then(|s| s.scheduled_for),
+                "last_outcome": last.map(|s| json!({
+                    "session_id": s.id,
+                    "status": s.status,
+                    "error": s.error,
+                })),
             })
         })
         .collect();
@@ -40,15 +59,7 @@ pub(super) async fn run_recipe(
         )
             .into_response();
     };
-    let deps = SchedulerDeps {
-        providers: state.providers.clone(),
-        template_engine: state.template_engine.clone(),
-        session_store: state.session_store.clone(),
-        policy: state.policy.clone(),
-        mcp: state.mcp.clone(),
-        mcp_tools: (*state.mcp_tools).clone(),
-    };
-    match scheduler::run_once(&recipe, &deps).await {
+    match scheduler::run_once(&recipe, &state.scheduler_deps()).await {
         Ok(id) => (StatusCode::CREATED, Json(json!({"session_id": id}))).into_response(),
         Err(e) => (
             StatusCode::INTERNAL_SERVER_ERROR,
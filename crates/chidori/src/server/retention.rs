@@ -0,0 +1,161 @@
+//! Session deletion and retention. `DELETE /sessions/{id}` and the retention
+//! sweeper share [`delete_session_and_run`]: the session row goes, and so
+//! does its run — journal and artifacts, through the run store, so a durable
+//! mirror forgets it too.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::Utc;
+
+use crate::runtime::store::RunStoreFactory;
+use crate::storage::{SessionQuery, SessionStatus, StoredSession};
+
+use super::AppState;
+
+/// How often the sweeper looks for sessions past their retention age.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Sessions deleted per sweeper query; the sweeper pages until none are left.
+const SWEEP_BATCH: usize = 200;
+
+/// How long settled sessions are kept, per terminal status (`chidori serve
+/// --retain-completed 30d`). `None` keeps that status forever. Paused and
+/// running sessions are never swept: they are still waiting on someone. Nor
+/// is each recipe's newest scheduled session, where the scheduler's catch-up
+/// resumes after a restart.
+#[derive(Debug, Clone, Default)]
+pub struct RetentionPolicy {
+    pub completed: Option<chrono::Duration>,
+    pub failed: Option<chrono::Duration>,
+    pub cancelled: Option<chrono::Duration>,
+}
+
+impl RetentionPolicy {
+    fn rules(&self) -> impl Iterator<Item = (SessionStatus, chrono::Duration)> + '_ {
+        [
+            (SessionStatus::Completed, self.completed),
+            (SessionStatus::Failed, self.failed),
+            (SessionStatus::Cancelled, self.cancelled),
+        ]
+        .into_iter()
+        .filter_map(|(status, age)| Some((status, age?)))
+    }
+}
+
+/// Parse a retention age: a whole number with a `s`, `m`, `h`, `d` or `w`
+/// suffix (`30d`, `12h`).
+pub fn parse_age(text: &str) -> std::result::Result<chrono::Duration, String> {
+    let text = text.trim();
+    let split = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let (number, unit) = text.split_at(split);
+    let number: i64 = number
+        .parse()
+        .map_err(|_| format!("`{text}`: expected a number and a unit, like `30d`"))?;
+    match unit {
+        "s" => Ok(chrono::Duration::seconds(number)),
+        "m" => Ok(chrono::Duration::minutes(number)),
+        "h" => Ok(chrono::Duration::hours(number)),
+        "d" => Ok(chrono::Duration::days(number)),
+        "w" => Ok(chrono::Duration::weeks(number)),
+        _ => Err(format!("`{text}`: unit must be one of s, m, h, d, w")),
+    }
+}
+
+/// Delete `session` and everything its run left behind: the run directory
+/// (and the mirror's copy), and for a scheduled session its tick's lease and
+/// done-marker. The row goes first, and only while it still holds the status
+/// `session` was read with: `Ok(false)` means a resume — on this replica or
+/// another — moved it on, and nothing was deleted. Blocking — the run store
+/// may be SQLite or a remote relay.
+pub(super) fn delete_session_and_run(state: &AppState, session: &StoredSession) -> Result<bool> {
+    if !state
+        .session_store
+        .delete_if(&session.id, &session.status)?
+    {
+        return Ok(false);
+    }
+    let factory = RunStoreFactory::shared(&state.run_base);
+    if let Some(run_id) = &session.run_id {
+        factory.store_for(run_id).delete_run()?;
+    }
+    if session.scheduled_for.is_some() {
+        factory.store_for(&session.id).delete_run()?;
+    }
+    state.warm_runs.lock().unwrap().remove(&session.id);
+    Ok(true)
+}
+
+/// Start the background sweeper. A no-op when the policy retains everything.
+pub fn spawn_sweeper(state: AppState, policy: RetentionPolicy) {
+    if policy.rules().next().is_none() {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut every = tokio::time::interval(SWEEP_INTERVAL);
+        loop {
+            every.tick().await;
+            let (state, policy) = (state.clone(), policy.clone());
+            match tokio::task::spawn_blocking(move || sweep(&state, &policy)).await {
+                Ok(Ok(0)) => {}
+                Ok(Ok(deleted)) => tracing::info!("retention: deleted {deleted} session(s)"),
+                Ok(Err(e)) => tracing::warn!("retention sweep failed: {:#}", e),
+                Err(e) => tracing::warn!("retention sweep panicked: {}", e),
+            }
+        }
+    });
+}
+
+/// Delete every session past its status's retention age. Returns how many
+/// were deleted.
+pub(super) fn sweep(state: &AppState, policy: &RetentionPolicy) -> Result<usize> {
+    let mut deleted = 0;
+    // Recipe → the id of its newest scheduled session.
+    let mut latest_ticks: HashMap<String, Option<String>> = HashMap::new();
+    for (status, age) in policy.rules() {
+        let mut query = SessionQuery {
+            status: vec![status],
+            created_before: Some(Utc::now() - age),
+            limit: SWEEP_BATCH,
+            summary: true,
+            ..Default::default()
+        };
+        loop {
+            let page = state.session_store.query(&query)?;
+            for session in &page.sessions {
+                // A session that was resumed since it was listed is live again.
+                if state
+                    .active_sessions
+                    .lock()
+                    .unwrap()
+                    .contains_key(&session.id)
+                {
+                    continue;
+                }
+                if let (Some(recipe), Some(_)) = (&session.recipe, session.scheduled_for) {
+                    let latest = match latest_ticks.get(recipe) {
+                        Some(latest) => latest.clone(),
+                        None => {
+                            let latest =
+                                state.session_store.latest_scheduled(recipe)?.map(|s| s.id);
+                            latest_ticks.insert(recipe.clone(), latest.clone());
+                            latest
+                        }
+                    };
+                    if latest.as_deref() == Some(session.id.as_str()) {
+                        continue;
+                    }
+                }
+                if delete_session_and_run(state, session)? {
+                    deleted += 1;
+                }
+            }
+            match page.next_cursor {
+                Some(cursor) => query.cursor = Some(cursor),
+                None => break,
+            }
+        }
+    }
+    Ok(deleted)
+}
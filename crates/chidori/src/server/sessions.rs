@@ -5,12 +5,13 @@
 
 pub(super) mod resume;
 pub(super) mod stream;
+pub(super) mod ws;
 
 use std::path::{Path as FsPath, PathBuf};
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Json, Response};
 use serde::Deserialize;
@@ -18,12 +19,13 @@ use serde_json::{json, Value};
 
 use crate::policy::PolicyConfig;
 use crate::runtime::call_log::CallRecord;
+use crate::runtime::cost::CostBudget;
 use crate::runtime::engine::RunResult;
 use crate::runtime::host_core::signal_timeout_sentinel;
 use crate::runtime::snapshot::PendingHostOperationKind;
-use crate::storage::{SessionStatus, StoredSession};
+use crate::storage::{SessionCursor, SessionQuery, SessionStatus, StoredSession, TenantFilter};
 
-use self::resume::{complete_pending_and_resume, signal_resolution_record};
+use self::resume::{complete_pending_and_resume, release_pause, signal_resolution_record};
 use super::engine::build_engine;
 use super::hardening::acquire_run_slot;
 use super::{
@@ -64,6 +66,22 @@ pub(super) struct CreateSessionRequest {
     /// and untrusted callers on one server.
     #[serde(default, alias = "policyProfile")]
     pub(super) policy_profile: Option<String>,
+    /// Optional: a cost budget for this session's run, overriding the
+    /// server's CHIDORI_MAX_COST_USD. Recorded in the run's manifest, so
+    /// resume/approve re-runs keep enforcing it against the session's total
+    /// spend.
+    #[serde(default)]
+    pub(super) budget: Option<CostBudget>,
+}
+
+/// Validate a client-supplied cost budget at session creation.
+pub(super) fn validate_budget(budget: Option<&CostBudget>) -> Result<(), (StatusCode, String)> {
+    match budget {
+        Some(budget) => budget
+            .validate()
+            .map_err(|msg| (StatusCode::BAD_REQUEST, msg)),
+        None => Ok(()),
+    }
 }
 
 /// Validate a client-supplied policy profile name at session creation.
@@ -202,7 +220,14 @@ pub(super) async fn create_session(
     if let Err((status, msg)) = validate_policy_profile(body.policy_profile.as_deref()) {
         return (status, Json(json!({"error": msg}))).into_response();
     }
+    if let Err((status, msg)) = validate_budget(body.budget.as_ref()) {
+        return (status, Json(json!({"error": msg}))).into_response();
+    }
     let policy_profile = body.policy_profile.clone();
+    let budget = match super::tenants::run_budget(&state, body.budget).await {
+        Ok(budget) => budget,
+        Err(resp) => return resp,
+    };
     // Resolve an optional per-session agent override before spawning
     // the blocking worker — cheaper to reject here than to take a
     // concurrency permit for an invalid request.
@@ -226,6 +251,7 @@ pub(super) async fn create_session(
         }
         None => state.agent_path.clone(),
     };
+    let agent = effective_agent_path.display().to_string();
     // A per-session profile can tighten the server policy past what the
     // startup preflight checked against — re-run the (warning-only) static
     // effect scan under the session's effective policy so the mismatch shows
@@ -249,8 +275,9 @@ pub(super) async fn create_session(
         let leg_profile = body.policy_profile.clone();
         tokio::spawn(async move {
             let result = tokio::task::spawn_blocking(move || {
-                let engine =
-                    build_engine(&app_state, leg_profile.as_deref()).with_warm_input_bridge(bridge);
+                let engine = build_engine(&app_state, leg_profile.as_deref())
+                    .with_budget(budget)
+                    .with_warm_input_bridge(bridge);
                 match replay_from {
                     Some(log) => engine.run_replay_pausable(&effective_agent_path, &leg_input, log),
                     None => engine.run_pausable(&effective_agent_path, &leg_input),
@@ -270,7 +297,8 @@ pub(super) async fn create_session(
         }
     } else {
         tokio::task::spawn_blocking(move || {
-            let engine = build_engine(&app_state, body.policy_profile.as_deref());
+            let engine =
+                build_engine(&app_state, body.policy_profile.as_deref()).with_budget(budget);
             match replay_from {
                 Some(log) => engine.run_replay_pausable(&effective_agent_path, &body.input, log),
                 None => engine.run_pausable(&effective_agent_path, &body.input),
@@ -297,6 +325,10 @@ pub(super) async fn create_session(
         pending_approval: None,
         approvals: Vec::new(),
         policy_profile,
+        agent: Some(agent),
+        recipe: None,
+        scheduled_for: None,
+        tenant: state.tenant_id(),
         created_at: chrono::Utc::now(),
     };
     match result {
@@ -341,13 +373,98 @@ fn is_input_validation_refusal(error: &str) -> bool {
     error.starts_with("JavaScript exception: InputValidationError: invalid input:")
 }
 
-/// GET /sessions — list all sessions.
-pub(super) async fn list_sessions(State(state): State<AppState>) -> Response {
-    match state.session_store.list() {
-        Ok(sessions) => {
-            let list: Vec<Value> = sessions
+/// Query parameters of `GET /sessions`. `status` takes a comma-separated
+/// list; `view=full` returns whole sessions instead of the summary rows.
+#[derive(Debug, Default, Deserialize)]
+pub(super) struct ListSessionsParams {
+    #[serde(default)]
+    pub(super) status: Option<String>,
+    #[serde(default)]
+    pub(super) agent: Option<String>,
+    #[serde(default)]
+    pub(super) recipe: Option<String>,
+    #[serde(default)]
+    pub(super) signal: Option<String>,
+    #[serde(default)]
+    pub(super) created_after: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    pub(super) created_before: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    pub(super) cursor: Option<String>,
+    #[serde(default)]
+    pub(super) limit: Option<usize>,
+    #[serde(default)]
+    pub(super) view: Option<String>,
+}
+
+impl ListSessionsParams {
+    fn to_query(&self) -> Result<SessionQuery, String> {
+        let status = match self.status.as_deref() {
+            Some(list) => list
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(|s| {
+                    serde_json::from_value(Value::String(s.to_string()))
+                        .map_err(|_| format!("unknown session status `{s}`"))
+                })
+                .collect::<Result<_, _>>()?,
+            None => Vec::new(),
+        };
+        let cursor = match self.cursor.as_deref() {
+            Some(token) => Some(SessionCursor::decode(token).map_err(|e| format!("{e:#}"))?),
+            None => None,
+        };
+        let full = match self.view.as_deref() {
+            None | Some("summary") => false,
+            Some("full") => true,
+            Some(other) => return Err(format!("unknown view `{other}` (summary or full)")),
+        };
+        Ok(SessionQuery {
+            status,
+            agent: self.agent.clone(),
+            recipe: self.recipe.clone(),
+            signal: self.signal.clone(),
+            created_after: self.created_after,
+            created_before: self.created_before,
+            cursor,
+            limit: self
+                .limit
+                .unwrap_or(SessionQuery::DEFAULT_LIMIT)
+                .clamp(1, SessionQuery::MAX_LIMIT),
+            summary: !full,
+            // A tenant's store narrows this to its own sessions.
+            tenant: TenantFilter::Any,
+        })
+    }
+}
+
+/// GET /sessions — one page of sessions, newest first, optionally filtered
+/// by status, agent, recipe, awaited signal and creation time. Follow
+/// `next_cursor` for the next page.
+pub(super) async fn list_sessions(
+    State(state): State<AppState>,
+    Query(params): Query<ListSessionsParams>,
+) -> Response {
+    let query = match params.to_query() {
+        Ok(query) => query,
+        Err(error) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": error}))).into_response();
+        }
+    };
+    let store = state.session_store.clone();
+    let page = tokio::task::spawn_blocking(move || store.query(&query).map(|page| (page, query)))
+        .await
+        .unwrap_or_else(|join_err| Err(anyhow::anyhow!("listing sessions panicked: {join_err}")));
+    match page {
+        Ok((page, query)) => {
+            let list: Vec<Value> = page
+                .sessions
                 .iter()
                 .map(|s| {
+                    if !query.summary {
+                        return session_view(s);
+                    }
                     // Carry enough for a dashboard to be useful without an
                     // N+1 detail fetch per row: the durable run directory this
                     // session journals into (`run_id` — deliberately distinct
@@ -358,14 +475,80 @@ pub(super) async fn list_sessions(State(state): State<AppState>) -> Response {
                         "status": s.status,
                         "error": s.error,
                         "created_at": s.created_at,
+                        "agent": s.agent,
+                        "recipe": s.recipe,
+                        "scheduled_for": s.scheduled_for,
                         "pending_prompt": s.pending_prompt,
                         "pending_signal_name": s.pending_signal_name,
                         "pending_signal_names": s.pending_signal_names,
                     })
                 })
                 .collect();
-            Json(json!({"sessions": list})).into_response()
+            Json(json!({
+                "sessions": list,
+                "next_cursor": page.next_cursor.map(|cursor| cursor.encode()),
+            }))
+            .into_response()
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": e.to_string()})),
+        )
+            .into_response(),
+    }
+}
+
+/// DELETE /sessions/:id — delete a session and its run directory. A session
+/// this server is still running must be cancelled first.
+pub(super) async fn delete_session(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Response {
+    if state.active_sessions.lock().unwrap().contains_key(&id) {
+        return (
+            StatusCode::CONFLICT,
+            Json(json!({"error": "session is running; cancel it before deleting it"})),
+        )
+            .into_response();
+    }
+    let session = match state.session_store.get(&id) {
+        Ok(Some(session)) => session,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({"error": "Session not found"})),
+            )
+                .into_response();
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": e.to_string()})),
+            )
+                .into_response();
         }
+    };
+    // Running on another replica: its run directory is still in use.
+    if session.status == SessionStatus::Running {
+        return (
+            StatusCode::CONFLICT,
+            Json(json!({"error": "session is running; cancel it before deleting it"})),
+        )
+            .into_response();
+    }
+    let app_state = state.clone();
+    let deleted = tokio::task::spawn_blocking(move || {
+        super::retention::delete_session_and_run(&app_state, &session)
+    })
+    .await
+    .unwrap_or_else(|join_err| Err(anyhow::anyhow!("deleting session panicked: {join_err}")));
+    match deleted {
+        Ok(true) => (StatusCode::OK, Json(json!({"deleted": id}))).into_response(),
+        Ok(false) => (
+            StatusCode::CONFLICT,
+            Json(json!({"error": "session changed while deleting it; try again"})),
+        )
+            .into_response(),
         Err(e) => (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(json!({"error": e.to_string()})),
@@ -491,13 +674,24 @@ pub(super) async fn replay_session(
     let vfs = load_persisted_vfs(&state.run_base, original.run_id.as_deref());
     let replay_run_id = original.run_id.clone();
     let policy_profile = original.policy_profile.clone();
+    // The recorded budget, capped by a tenant's cost quota.
+    let recorded_budget = replay_run_id
+        .as_deref()
+        .and_then(|run_id| crate::server::engine::manifest_budget(&state, run_id));
+    let budget = match super::tenants::run_budget(&state, recorded_budget).await {
+        Ok(budget) => budget,
+        Err(resp) => return resp,
+    };
     let app_state = state.clone();
 
     let result = tokio::task::spawn_blocking(move || {
         let mut engine =
             build_engine(&app_state, policy_profile.as_deref()).with_approvals(approvals);
         if let Some(ref run_id) = replay_run_id {
-            engine = crate::server::engine::with_manifest_model(engine, &app_state, run_id);
+            engine = crate::server::engine::with_manifest_settings(engine, &app_state, run_id);
+        }
+        if budget.is_some() {
+            engine = engine.with_budget(budget);
         }
         engine.run_with_replay_host_promises_and_vfs(
             &app_state.agent_path,
@@ -530,6 +724,10 @@ pub(super) async fn replay_session(
                 pending_approval: None,
                 approvals: original.approvals.clone(),
                 policy_profile: original.policy_profile.clone(),
+                agent: Some(state.agent_path.display().to_string()),
+                recipe: original.recipe.clone(),
+                scheduled_for: None,
+                tenant: state.tenant_id(),
                 created_at: chrono::Utc::now(),
             };
             if let Some(err) = store_or_500(&state, &session) {
@@ -607,6 +805,10 @@ pub(super) async fn cancel_session(
             pending_approval: None,
             approvals: Vec::new(),
             policy_profile: None,
+            agent: None,
+            recipe: None,
+            scheduled_for: None,
+            tenant: state.tenant_id(),
             created_at: chrono::Utc::now(),
         },
         Ok(None) => {
@@ -781,6 +983,66 @@ pub(super) async fn get_holdings(
     }
 }
 
+/// GET /sessions/:id/audit — every policy decision the session's run has
+/// evaluated (`runtime::policy_audit`), oldest first, with the policy
+/// profile the session layered over the server's policy.
+pub(super) async fn get_audit(State(state): State<AppState>, Path(id): Path<String>) -> Response {
+    let session = match state.session_store.get(&id) {
+        Ok(Some(session)) => session,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({"error": "Session not found"})),
+            )
+                .into_response();
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("session store: {}", e)})),
+            )
+                .into_response();
+        }
+    };
+    let Some(run_id) = session.run_id.clone() else {
+        return (
+            StatusCode::CONFLICT,
+            Json(json!({"error": "session has no run to inspect (it never started one)"})),
+        )
+            .into_response();
+    };
+
+    let run_base = state.run_base.clone();
+    let audit_run_id = run_id.clone();
+    let entries = tokio::task::spawn_blocking(move || {
+        let factory = crate::runtime::store::RunStoreFactory::shared(&run_base);
+        let _ = factory.hydrate(&audit_run_id);
+        let store = factory.store_for(&audit_run_id);
+        crate::runtime::policy_audit::load(store.as_ref())
+    })
+    .await;
+
+    match entries {
+        Ok(Ok(entries)) => Json(json!({
+            "session_id": session.id,
+            "run_id": run_id,
+            "policy_profile": session.policy_profile,
+            "entries": entries,
+        }))
+        .into_response(),
+        Ok(Err(e)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": format!("{e:#}")})),
+        )
+            .into_response(),
+        Err(join_err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": join_err.to_string()})),
+        )
+            .into_response(),
+    }
+}
+
 /// Render an agent-run error for a session's stored/returned `error` field.
 /// Uncaught-exception stack frames arrive from the engine in transpiled
 /// coordinates; remap them to positions in the original TypeScript against
@@ -889,6 +1151,16 @@ async fn fire_signal_timeout(state: &AppState, id: &str, seq: u64) {
     if state.active_sessions.lock().unwrap().contains_key(id) {
         return;
     }
+    // Every server sharing the session store re-arms this timer; the one
+    // that claims the pause resolves it.
+    if !matches!(
+        state
+            .session_store
+            .transition(id, &SessionStatus::Paused, &SessionStatus::Running),
+        Ok(true)
+    ) {
+        return;
+    }
 
     let sentinel = signal_timeout_sentinel(&names);
     let lock = state.signal_inbox_lock(session.run_id.as_deref().unwrap_or(id));
@@ -904,6 +1176,7 @@ async fn fire_signal_timeout(state: &AppState, id: &str, seq: u64) {
     // No matching pending op on disk means the pause was already resolved (or
     // never persisted); either way there is nothing to time out.
     let Ok(Some(pending)) = completed else {
+        release_pause(state, &session);
         return;
     };
     let mut call_log = session.call_log.clone();
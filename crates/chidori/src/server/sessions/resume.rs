@@ -9,6 +9,7 @@ use serde::Deserialize;
 use serde_json::{json, Value};
 
 use crate::runtime::call_log::CallRecord;
+use crate::runtime::context::PendingApproval;
 use crate::runtime::snapshot::{PendingHostOperation, PendingHostOperationKind};
 use crate::storage::{SessionStatus, StoredSession};
 
@@ -45,9 +46,95 @@ pub(super) fn signal_resolution_record(
         token_usage: None,
         timestamp: chrono::Utc::now(),
         error: None,
+        served_by: None,
     }
 }
 
+/// The synthetic `input` CallRecord an answer to an `input()` pause injects
+/// at the pending seq, so the replaying engine returns `response` to the
+/// agent's input call.
+pub(super) fn input_resolution_record(
+    session: &StoredSession,
+    seq: u64,
+    response: &str,
+) -> CallRecord {
+    CallRecord {
+        seq,
+        parent_seq: None,
+        function: "input".to_string(),
+        args: json!({ "prompt": session.pending_prompt.clone().unwrap_or_default() }),
+        result: Value::String(response.to_string()),
+        duration_ms: 0,
+        token_usage: None,
+        timestamp: chrono::Utc::now(),
+        error: None,
+        served_by: None,
+    }
+}
+
+/// Record the operator's answer to `pending` in the run's policy audit log.
+/// Best-effort, like every audit write.
+pub(super) fn audit_operator_answer(
+    state: &AppState,
+    session: &StoredSession,
+    pending: &PendingApproval,
+    allowed: bool,
+) {
+    let Some(run_id) = session.run_id.as_deref() else {
+        return;
+    };
+    let store = crate::runtime::store::RunStoreFactory::shared(&state.run_base).store_for(run_id);
+    if let Err(err) = crate::runtime::policy_audit::record_operator_answer(
+        store.as_ref(),
+        &pending.target,
+        &pending.args,
+        allowed,
+    ) {
+        tracing::warn!("policy audit: {err:#}");
+    }
+}
+
+/// Claim the pause `session` is in for this request by moving it to
+/// `Running` in the store. Another resume or approve answering the same pause
+/// — on this server or one sharing the session store — gets a 409 instead of
+/// replaying the run a second time.
+fn claim_pause(state: &AppState, session: &StoredSession) -> Option<Response> {
+    match state
+        .session_store
+        .transition(&session.id, &session.status, &SessionStatus::Running)
+    {
+        Ok(true) => None,
+        Ok(false) => Some(
+            (
+                StatusCode::CONFLICT,
+                Json(json!({"error": "Session is already being resumed"})),
+            )
+                .into_response(),
+        ),
+        Err(e) => Some(
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": e.to_string()})),
+            )
+                .into_response(),
+        ),
+    }
+}
+
+/// Hand back a [`claim_pause`] claim when the request fails before running
+/// anything, so the pause can be answered again.
+pub(super) fn release_pause(state: &AppState, session: &StoredSession) {
+    release_claim(state, &session.id, &session.status);
+}
+
+/// [`release_pause`] once the claimed session has been moved into the run's
+/// outcome: `paused` is the status the claim took it from.
+fn release_claim(state: &AppState, id: &str, paused: &SessionStatus) {
+    let _ = state
+        .session_store
+        .transition(id, &SessionStatus::Running, paused);
+}
+
 /// Shared tail of `resume_session` and `signal_session` (doc §9: "factor its
 /// shared tail into `complete_pending_and_resume(...)`"). The caller has already
 /// (1) resolved the persisted pending host operation and (2) appended the
@@ -71,6 +158,7 @@ pub(super) async fn complete_pending_and_resume(
         match load_persisted_host_promises(&state.run_base, original.run_id.as_deref()) {
             Ok(host_promises) => host_promises,
             Err(e) => {
+                release_pause(state, &original);
                 return (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     Json(json!({"error": e.to_string()})),
@@ -79,6 +167,7 @@ pub(super) async fn complete_pending_and_resume(
             }
         };
     let approvals = original.approvals.clone();
+    let paused = original.status.clone();
     let vfs = load_persisted_vfs(&state.run_base, original.run_id.as_deref());
     let signal_inbox = load_persisted_signal_inbox(&state.run_base, original.run_id.as_deref());
     let resume_run_id = original.run_id.clone();
@@ -98,7 +187,7 @@ pub(super) async fn complete_pending_and_resume(
         let mut engine =
             build_engine(&app_state, policy_profile.as_deref()).with_approvals(approvals);
         if let Some(ref run_id) = resume_run_id {
-            engine = crate::server::engine::with_manifest_model(engine, &app_state, run_id);
+            engine = crate::server::engine::with_manifest_settings(engine, &app_state, run_id);
         }
         if let Some(bridge) = bridge {
             engine = engine.with_warm_input_bridge(bridge);
@@ -161,6 +250,7 @@ pub(super) async fn complete_pending_and_resume(
             // inbox were already written to disk by the runtime safepoints).
             apply_run_outcome(&mut session, run_result);
             if let Some(err) = store_or_500(state, &session) {
+                release_claim(state, &session.id, &paused);
                 return err;
             }
             arm_signal_timeout(state, &session);
@@ -244,6 +334,10 @@ pub(in crate::server) async fn resume_session(
             .into_response();
     };
 
+    if let Some(conflict) = claim_pause(&state, &original) {
+        return conflict;
+    }
+
     // Warm fast path: this session's VM is parked on its thread awaiting
     // exactly this response — deliver it and await the leg's next outcome
     // (the next pause or the terminal result). The parked engine records the
@@ -260,6 +354,7 @@ pub(in crate::server) async fn resume_session(
                     let mut outcomes = warm.outcomes.lock().await;
                     outcomes.recv().await
                 };
+                let paused = original.status.clone();
                 let mut session = original;
                 match result {
                     Some(Ok(run_result)) => apply_run_outcome(&mut session, run_result),
@@ -273,6 +368,7 @@ pub(in crate::server) async fn resume_session(
                     }
                 }
                 if let Some(err) = store_or_500(&state, &session) {
+                    release_claim(&state, &session.id, &paused);
                     return err;
                 }
                 arm_signal_timeout(&state, &session);
@@ -293,6 +389,7 @@ pub(in crate::server) async fn resume_session(
         &state.agent_path,
         body.allow_source_change,
     ) {
+        release_pause(&state, &original);
         return (
             StatusCode::CONFLICT,
             Json(json!({"error": err.to_string()})),
@@ -308,6 +405,7 @@ pub(in crate::server) async fn resume_session(
     ) {
         Ok(pending) => pending,
         Err(err) => {
+            release_pause(&state, &original);
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(json!({"error": err.to_string()})),
@@ -319,17 +417,7 @@ pub(in crate::server) async fn resume_session(
     // Inject a synthetic `input` record at the pending seq so the replaying
     // engine returns the user's response to the agent's input() call.
     let mut call_log = original.call_log.clone();
-    call_log.push(CallRecord {
-        seq,
-        parent_seq: None,
-        function: "input".to_string(),
-        args: json!({ "prompt": original.pending_prompt.clone().unwrap_or_default() }),
-        result: Value::String(body.response.clone()),
-        duration_ms: 0,
-        token_usage: None,
-        timestamp: chrono::Utc::now(),
-        error: None,
-    });
+    call_log.push(input_resolution_record(&original, seq, &body.response));
 
     complete_pending_and_resume(&state, original, call_log).await
 }
@@ -472,6 +560,10 @@ pub(in crate::server) async fn signal_session(
                 .into_response();
         }
 
+        if let Some(conflict) = claim_pause(&state, &original) {
+            return conflict;
+        }
+
         // The recorded signal result freezes `{name,payload,from}` (doc §8.3) —
         // the match key on disk is `{name}` only.
         let value = json!({
@@ -505,13 +597,17 @@ pub(in crate::server) async fn signal_session(
             // to enqueueing so the signal is not lost.
             Ok(None) => {
                 drop(_guard);
+                release_pause(&state, &original);
                 enqueue_and_respond(&state, &original, &id, body)
             }
-            Err(err) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": err.to_string()})),
-            )
-                .into_response(),
+            Err(err) => {
+                release_pause(&state, &original);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({"error": err.to_string()})),
+                )
+                    .into_response()
+            }
         }
     } else {
         // Paused on a different name / input / approval, or Running: enqueue.
@@ -627,6 +723,12 @@ pub(in crate::server) async fn approve_session(
             .into_response();
     }
 
+    if let Some(conflict) = claim_pause(&state, &original) {
+        return conflict;
+    }
+
+    audit_operator_answer(&state, &original, &pending, body.decision == "allow");
+
     if body.decision != "allow" {
         let error = format!("policy: `{}` denied by operator", pending.target);
         if let Err(err) = complete_persisted_pending_host_operation(
@@ -635,6 +737,7 @@ pub(in crate::server) async fn approve_session(
             None,
             HostPromiseCompletion::Rejected(error.clone()),
         ) {
+            release_pause(&state, &original);
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(json!({"error": err.to_string()})),
@@ -666,6 +769,7 @@ pub(in crate::server) async fn approve_session(
             "target": pending.target,
         })),
     ) {
+        release_pause(&state, &original);
         return (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(json!({"error": err.to_string()})),
@@ -682,12 +786,13 @@ pub(in crate::server) async fn approve_session(
     // listen point drains a queued entry instead of pausing (doc §9, §3).
     let signal_inbox = load_persisted_signal_inbox(&state.run_base, original.run_id.as_deref());
     let policy_profile = original.policy_profile.clone();
+    let paused = original.status.clone();
     let app_state = state.clone();
     let result = tokio::task::spawn_blocking(move || {
         let mut engine =
             build_engine(&app_state, policy_profile.as_deref()).with_approvals(approvals);
         if let Some(ref run_id) = resume_run_id {
-            engine = crate::server::engine::with_manifest_model(engine, &app_state, run_id);
+            engine = crate::server::engine::with_manifest_settings(engine, &app_state, run_id);
         }
         // Replay the recorded call log (so any host calls the agent made before
         // the policy block — e.g. a prior `input()` — return their recorded
@@ -746,6 +851,7 @@ pub(in crate::server) async fn approve_session(
             }
             session.call_log = run_result.call_log.into_records();
             if let Some(err) = store_or_500(&state, &session) {
+                release_claim(&state, &session.id, &paused);
                 return err;
             }
             (StatusCode::OK, Json(session_view(&session))).into_response()
@@ -1,6 +1,8 @@
 //! POST /sessions/stream — the SSE streaming runner: forwards runtime events
 //! as Server-Sent Events and supervises live signal pauses so a delivery (or
-//! timeout) resumes the run in-process without an HTTP round-trip.
+//! timeout) resumes the run in-process without an HTTP round-trip. The same
+//! supervisor drives the WebSocket transport (`ws.rs`), which also answers
+//! input and approval pauses in-process.
 
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex as StdMutex};
@@ -8,26 +10,33 @@ use std::sync::{Arc, Mutex as StdMutex};
 use axum::extract::State;
 use axum::response::sse::{Event, KeepAlive, Sse};
 use axum::response::{IntoResponse, Json, Response};
+use futures::StreamExt;
 use serde_json::{json, Value};
+use tokio::sync::mpsc;
 
 use crate::runtime::context::{InputMode, RuntimeContext, RuntimeEvent};
 use crate::runtime::engine::RunResult;
 use crate::runtime::host_core::signal_timeout_sentinel;
-use crate::runtime::snapshot::PendingHostOperationKind;
+use crate::runtime::snapshot::{PendingHostOperationKind, QueuedSignal};
 use crate::storage::{SessionStatus, StoredSession};
 
 use super::super::engine::build_engine;
-use super::super::hardening::acquire_run_slot;
+use super::super::hardening::{acquire_run_slot, RunSlot};
 use super::super::{
-    complete_persisted_pending_host_operation, load_persisted_host_promises, load_persisted_vfs,
-    ActiveSession, AppState, HostPromiseCompletion, LiveSignalSession,
+    complete_persisted_pending_host_operation, load_persisted_host_promises,
+    load_persisted_signal_inbox, load_persisted_vfs, ActiveSession, AppState,
+    HostPromiseCompletion, LiveSignalSession,
+};
+use super::resume::{
+    audit_operator_answer, input_resolution_record, release_pause, signal_resolution_record,
+};
+use super::{
+    agent_error_string, apply_run_outcome, arm_signal_timeout, validate_budget,
+    validate_policy_profile, CreateSessionRequest,
 };
-use super::resume::signal_resolution_record;
-use super::{agent_error_string, apply_run_outcome, validate_policy_profile, CreateSessionRequest};
 
-/// POST /sessions/stream — run the agent and stream each host-function call
-/// as a Server-Sent Event while it executes. Final event has `event: done`
-/// carrying the session id and output.
+/// Stamp a streamed event payload with the client's attempt number, when it
+/// sent one.
 pub(in crate::server) fn stamp_attempt(mut value: Value, attempt_number: Option<u64>) -> Value {
     if let Some(attempt_number) = attempt_number {
         if let Some(object) = value.as_object_mut() {
@@ -175,19 +184,35 @@ impl SessionEventLog {
     }
 }
 
-/// Dropped when the POST stream's supervisor future ends. On the normal
-/// paths a real `done` event has already closed the log and this append is a
-/// no-op; if the originating client disconnected mid-run (the SSE body
-/// future dropped without reaching a terminal event), it closes the log with
-/// a synthetic `done` so re-attached clients don't hang on a feed nobody
-/// supervises anymore.
+/// Dropped when a supervisor future ends. On the normal paths a real `done`
+/// event has already closed the log and this append is a no-op; if the
+/// originating client disconnected mid-run (the body future dropped without
+/// reaching a terminal event), it closes the log with a synthetic `done` so
+/// re-attached clients don't hang on a feed nobody supervises anymore. A
+/// client that disconnects while the supervisor idles on a pause hands the
+/// pause back to the durable endpoints: the live entry goes, and a signal
+/// pause's `timeoutMs` deadline is re-armed server-side.
 struct EventLogCloseGuard {
     log: Arc<SessionEventLog>,
+    state: AppState,
     session_id: String,
+    /// The session while it idles on a pause with no run in flight.
+    idle: Option<StoredSession>,
 }
 
 impl Drop for EventLogCloseGuard {
     fn drop(&mut self) {
+        if let Some(session) = self.idle.take() {
+            self.state
+                .active_sessions
+                .lock()
+                .unwrap()
+                .remove(&self.session_id);
+            arm_signal_timeout(&self.state, &session);
+            self.log
+                .append("done", &final_session_event(&session).to_string());
+            return;
+        }
         let data = json!({
             "id": self.session_id,
             "status": "failed",
@@ -247,6 +272,52 @@ fn final_session_event(session: &StoredSession) -> Value {
     }
 }
 
+/// Every event of a live session's log: the ones already logged, then the
+/// live broadcast until the run settles (`done`).
+pub(in crate::server) fn follow_event_log(
+    log: Arc<SessionEventLog>,
+) -> impl futures::Stream<Item = (String, String)> {
+    let (snapshot, closed, mut rx) = log.snapshot_and_subscribe();
+    async_stream::stream! {
+        for entry in snapshot {
+            yield entry;
+        }
+        if !closed {
+            loop {
+                match rx.recv().await {
+                    Ok((name, data)) => {
+                        let is_done = name == "done";
+                        yield (name, data);
+                        if is_done {
+                            break;
+                        }
+                    }
+                    // Lagged: this attacher fell >capacity events behind;
+                    // skip the overwritten ones and keep following.
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        }
+    }
+}
+
+/// A stored session with no live supervisor as events: its logged call
+/// records, then a `done` carrying its current state.
+pub(in crate::server) fn replay_stored_session(
+    session: StoredSession,
+) -> impl futures::Stream<Item = (String, String)> {
+    async_stream::stream! {
+        for record in &session.call_log {
+            let data = serde_json::to_string(&json!(record)).unwrap_or_else(|_| "{}".into());
+            yield ("call".to_string(), data);
+        }
+        let data = serde_json::to_string(&final_session_event(&session))
+            .unwrap_or_else(|_| "{}".into());
+        yield ("done".to_string(), data);
+    }
+}
+
 /// GET /sessions/{id}/stream — (re-)attach to a session's SSE event stream.
 ///
 /// For a session with a live streaming supervisor (started via POST
@@ -269,32 +340,7 @@ pub(in crate::server) async fn attach_session_stream(
         .get(&id)
         .and_then(|active| active.event_log.clone());
     if let Some(log) = live_log {
-        let (snapshot, closed, mut rx) = log.snapshot_and_subscribe();
-        let stream = async_stream::stream! {
-            for (name, data) in snapshot {
-                yield Ok::<_, std::convert::Infallible>(Event::default().event(name).data(data));
-            }
-            if !closed {
-                loop {
-                    match rx.recv().await {
-                        Ok((name, data)) => {
-                            let is_done = name == "done";
-                            yield Ok::<_, std::convert::Infallible>(
-                                Event::default().event(name).data(data),
-                            );
-                            if is_done {
-                                break;
-                            }
-                        }
-                        // Lagged: this attacher fell >capacity events behind;
-                        // skip the overwritten ones and keep following.
-                        Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
-                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
-                    }
-                }
-            }
-        };
-        return Sse::new(stream)
+        return Sse::new(follow_event_log(log).map(sse_event))
             .keep_alive(KeepAlive::default())
             .into_response();
     }
@@ -318,148 +364,249 @@ pub(in crate::server) async fn attach_session_stream(
                 .into_response();
         }
     };
-    let stream = async_stream::stream! {
-        for record in &session.call_log {
-            let data = serde_json::to_string(&json!(record)).unwrap_or_else(|_| "{}".into());
-            yield Ok::<_, std::convert::Infallible>(Event::default().event("call").data(data));
-        }
-        let data = serde_json::to_string(&final_session_event(&session))
-            .unwrap_or_else(|_| "{}".into());
-        yield Ok::<_, std::convert::Infallible>(Event::default().event("done").data(data));
-    };
-    Sse::new(stream)
+    Sse::new(replay_stored_session(session).map(sse_event))
         .keep_alive(KeepAlive::default())
         .into_response()
 }
 
+fn sse_event<N: Into<String>>(
+    (name, data): (N, String),
+) -> Result<Event, std::convert::Infallible> {
+    Ok(Event::default().event(name.into()).data(data))
+}
+
 /// Spawn one blocking agent run for the streaming supervisor, reporting the
-/// engine result back on `result_tx`. Holds a clone of the shared run permit
-/// for the duration of the run.
+/// engine result back on `result_tx`. Holds a clone of the supervisor's run
+/// slot for the duration of the run. `approvals` seeds the policy cache, so a run
+/// resumed past an approved gate lets that call through.
 fn spawn_streaming_run(
     state: &AppState,
     policy_profile: Option<String>,
+    approvals: Vec<(String, Value)>,
     ctx: RuntimeContext,
     input: Value,
-    result_tx: tokio::sync::mpsc::UnboundedSender<anyhow::Result<RunResult>>,
-    permit: Arc<tokio::sync::OwnedSemaphorePermit>,
+    result_tx: mpsc::UnboundedSender<anyhow::Result<RunResult>>,
+    permit: Option<Arc<RunSlot>>,
 ) {
     let app_state = state.clone();
     let agent_path = state.agent_path.clone();
     tokio::task::spawn_blocking(move || {
         let _run_permit = permit;
-        let engine = build_engine(&app_state, policy_profile.as_deref());
+        let engine = build_engine(&app_state, policy_profile.as_deref()).with_approvals(approvals);
         let result = engine.run_with_prepared_context(&agent_path, &input, ctx);
         let _ = result_tx.send(result);
     });
 }
 
-/// Resolve a supervised signal pause in-process and kick off the resumed run
-/// (`docs/signals.md` Phase 3 — the fast resume trigger that skips the HTTP
-/// `/resume` round-trip). Completes the persisted pending `Signal` op with
-/// `value` (a delivered `{name,payload,from}` or the timeout sentinel),
-/// appends the synthetic resolution record, swaps a fresh replay context into
-/// the live slot — carrying over the in-memory mailbox so a delivery racing
-/// this resume is not lost — persists the session as Running, and spawns the
-/// blocking re-run, which reports back on `result_tx`. Returns false (leaving
-/// the pause supervised) when no matching pending op exists on disk.
-#[allow(clippy::too_many_arguments)]
-fn resume_signal_pause_in_process(
-    state: &AppState,
-    session: &mut StoredSession,
-    ctx_slot: &Arc<StdMutex<RuntimeContext>>,
-    event_tx: &tokio::sync::mpsc::UnboundedSender<RuntimeEvent>,
-    result_tx: &tokio::sync::mpsc::UnboundedSender<anyhow::Result<RunResult>>,
-    permit: &Arc<tokio::sync::OwnedSemaphorePermit>,
-    policy_profile: Option<String>,
-    seq: u64,
-    value: Value,
-) -> bool {
-    let run_id = session.run_id.clone().unwrap_or_else(|| session.id.clone());
-    let completed = {
-        let lock = state.signal_inbox_lock(&run_id);
-        let _guard = lock.lock().unwrap();
-        complete_persisted_pending_host_operation(
-            &state.run_base,
-            session.run_id.as_deref(),
-            Some((seq, PendingHostOperationKind::Signal)),
-            HostPromiseCompletion::Resolved(value.clone()),
-        )
-    };
-    let Ok(Some(pending)) = completed else {
-        return false;
-    };
-    let resolution = signal_resolution_record(&pending, seq, value);
-    // Emit the consumed signal on the live stream. The resumed run replays
-    // the log (replayed records deliberately re-emit nothing), so without
-    // this the one record that carries `{name, payload, from}` — who steered
-    // the run — would never reach a dashboard watching the SSE stream.
-    let _ = event_tx.send(RuntimeEvent::Call(resolution.clone()));
-    session.call_log.push(resolution);
-
-    let host_promises = load_persisted_host_promises(&state.run_base, session.run_id.as_deref())
-        .unwrap_or_default();
-    let vfs = load_persisted_vfs(&state.run_base, session.run_id.as_deref());
+/// An operator's answer to an `input()` or approval pause, sent into a live
+/// supervisor by a WebSocket client (`GET /sessions/{id}/ws`). The SSE
+/// stream has no upstream, so it hands those pauses to the durable
+/// resume/approve endpoints instead.
+pub(in crate::server) struct PauseAnswer {
+    pub(in crate::server) answer: Answer,
+    /// Told whether the supervisor took the answer, or why not.
+    pub(in crate::server) reply: tokio::sync::oneshot::Sender<Result<(), String>>,
+}
 
-    // Swap the resumed run's context into the live slot while holding it: the
-    // delivery endpoint enqueues into whatever context the slot currently
-    // names, so carrying the old context's in-memory mailbox into the new one
-    // under the lock means no delivery can fall between the two.
-    let ctx = {
-        let mut slot = ctx_slot.lock().unwrap();
-        let inbox = slot.signal_inbox();
-        let ctx = RuntimeContext::with_replay_host_promises_vfs_and_signals(
-            session.call_log.clone(),
-            host_promises,
-            vfs,
-            inbox,
-        );
-        ctx.set_run_id(run_id);
-        ctx.set_input_mode(InputMode::Pause);
-        ctx.set_event_sender(event_tx.clone());
-        *slot = ctx.clone();
-        ctx
-    };
+pub(in crate::server) enum Answer {
+    Input(String),
+    Approval { allow: bool },
+}
 
-    session.status = SessionStatus::Running;
-    session.pending_seq = None;
-    session.pending_signal_name = None;
-    session.pending_signal_names = Vec::new();
-    session.pending_signal_deadline = None;
-    let _ = state.session_store.put(session);
+/// The pause a supervisor idles on while no run is in flight.
+#[derive(Clone)]
+enum IdlePause {
+    /// A signal listen point: the pending seq and the listen set.
+    Signal(u64, Vec<String>),
+    Input(u64),
+    Approval,
+}
 
-    spawn_streaming_run(
-        state,
-        policy_profile,
-        ctx,
-        session.input.clone(),
-        result_tx.clone(),
-        permit.clone(),
-    );
-    true
+impl IdlePause {
+    fn of(session: &StoredSession) -> Self {
+        let seq = session.pending_seq.unwrap_or_default();
+        if session.status == SessionStatus::AwaitingApproval {
+            IdlePause::Approval
+        } else if !session.pending_signal_names.is_empty() {
+            IdlePause::Signal(seq, session.pending_signal_names.clone())
+        } else {
+            IdlePause::Input(seq)
+        }
+    }
 }
 
-pub(in crate::server) async fn stream_session(
-    State(state): State<AppState>,
-    Json(body): Json<CreateSessionRequest>,
-) -> Response {
-    use tokio::sync::mpsc;
+/// What answers a pause in-process.
+enum PauseResolution {
+    Signal { seq: u64, value: Value },
+    Input { seq: u64, response: String },
+    Approval { allow: bool },
+}
 
+/// How an in-process resume attempt ended.
+#[derive(Clone, Copy, PartialEq)]
+enum InProcessResume {
+    /// The pause is resolved and a run is in flight again.
+    Running,
+    /// No matching pending op on disk; the pause stays supervised.
+    NotPending,
+    /// The operator denied the gated call, failing the session.
+    Denied,
+    /// Another resume claimed the pause first; the session now holds the
+    /// stored state.
+    Lost,
+    /// No run slot came free within the acquire timeout; the pause stays
+    /// supervised.
+    Busy,
+}
+
+/// How long a supervisor that found no free run slot waits before trying a
+/// queued signal or an expired deadline again.
+const SLOT_RETRY: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// The handles an in-process resume needs to start the next run leg.
+struct LiveRun {
+    state: AppState,
+    ctx_slot: Arc<StdMutex<RuntimeContext>>,
+    event_tx: mpsc::UnboundedSender<RuntimeEvent>,
+    result_tx: mpsc::UnboundedSender<anyhow::Result<RunResult>>,
+    /// The run slot, held while a leg runs and given back while the session
+    /// idles on a pause.
+    permit: Option<Arc<RunSlot>>,
+}
+
+impl LiveRun {
+    /// Take a run slot for the next leg unless one is held. False when none
+    /// came free within the acquire timeout.
+    async fn hold_slot(&mut self) -> bool {
+        if self.permit.is_none() {
+            match acquire_run_slot(&self.state).await {
+                Ok(slot) => self.permit = Some(Arc::new(slot)),
+                Err(_) => return false,
+            }
+        }
+        true
+    }
+}
+
+/// A supervised streaming session, registered in `active_sessions` and
+/// ready for [`supervise`]: either a run just started
+/// ([`start_streaming_session`]) or a stored pause taken over
+/// ([`adopt_paused_session`]).
+pub(in crate::server) struct SupervisedRun {
+    live: LiveRun,
+    session: StoredSession,
+    attempt_number: Option<u64>,
+    event_log: Arc<SessionEventLog>,
+    cancelled: Arc<AtomicBool>,
+    event_rx: mpsc::UnboundedReceiver<RuntimeEvent>,
+    result_rx: mpsc::UnboundedReceiver<anyhow::Result<RunResult>>,
+    cancel_rx: mpsc::UnboundedReceiver<String>,
+    signal_rx: mpsc::UnboundedReceiver<(u64, String)>,
+    answers: Option<mpsc::UnboundedReceiver<PauseAnswer>>,
+    /// Start idle on the session's stored pause rather than on a run.
+    adopted: bool,
+}
+
+impl SupervisedRun {
+    /// Create the supervisor's channels and register it in
+    /// `active_sessions`, so the signal, cancel and re-attach endpoints find
+    /// it. `answers` opens the upstream for input and approval answers.
+    /// `None` when the session already has a supervisor: the check and the
+    /// insert happen under one lock, so two callers never both take it.
+    fn register(
+        state: &AppState,
+        session: StoredSession,
+        ctx: RuntimeContext,
+        attempt_number: Option<u64>,
+        permit: Option<Arc<RunSlot>>,
+        answers: bool,
+    ) -> Option<Self> {
+        let mut active = state.active_sessions.lock().unwrap();
+        let std::collections::hash_map::Entry::Vacant(entry) = active.entry(session.id.clone())
+        else {
+            return None;
+        };
+        let (event_tx, event_rx) = mpsc::unbounded_channel();
+        let (result_tx, result_rx) = mpsc::unbounded_channel();
+        let (cancel_tx, cancel_rx) = mpsc::unbounded_channel();
+        let (signal_tx, signal_rx) = mpsc::unbounded_channel();
+        let (answer_tx, answer_rx) = if answers {
+            let (tx, rx) = mpsc::unbounded_channel();
+            (Some(tx), Some(rx))
+        } else {
+            (None, None)
+        };
+        let cancelled = Arc::new(AtomicBool::new(false));
+        ctx.set_event_sender(event_tx.clone());
+        let ctx_slot = Arc::new(StdMutex::new(ctx));
+        // Journal every event this supervisor emits so a dropped client can
+        // re-attach via GET /sessions/{id}/stream, catch up, and follow live.
+        let event_log = Arc::new(SessionEventLog::new());
+        entry.insert(ActiveSession {
+            cancelled: cancelled.clone(),
+            cancel_tx,
+            attempt_number,
+            signals: Some(LiveSignalSession {
+                ctx_slot: ctx_slot.clone(),
+                signal_tx,
+            }),
+            event_log: Some(event_log.clone()),
+            answers: answer_tx,
+        });
+        Some(Self {
+            live: LiveRun {
+                state: state.clone(),
+                ctx_slot,
+                event_tx,
+                result_tx,
+                permit,
+            },
+            session,
+            attempt_number,
+            event_log,
+            cancelled,
+            event_rx,
+            result_rx,
+            cancel_rx,
+            signal_rx,
+            answers: answer_rx,
+            adopted: false,
+        })
+    }
+}
+
+fn already_live() -> Response {
+    (
+        axum::http::StatusCode::CONFLICT,
+        Json(json!({"error": "the session is already live"})),
+    )
+        .into_response()
+}
+
+/// Validate a `POST /sessions/stream` body, take a run slot, and start the
+/// session's first run under a registered supervisor. `answers` as in
+/// [`SupervisedRun::register`].
+pub(in crate::server) async fn start_streaming_session(
+    state: &AppState,
+    body: CreateSessionRequest,
+    answers: bool,
+) -> Result<SupervisedRun, Response> {
     // Gate on the concurrency semaphore. If we can't get a permit within
     // the acquire deadline, 503 before any streaming response headers are
     // committed so clients see the overflow cleanly. The permit is shared
-    // (Arc) between the supervisor stream and each blocking run, so the slot
-    // stays held across in-process signal resumes and is released when the
-    // last holder drops.
-    let permit = match acquire_run_slot(&state).await {
-        Ok(p) => Arc::new(p),
-        Err(resp) => return resp,
-    };
+    // (Arc) between the supervisor stream and each blocking run; the
+    // supervisor gives its share back while the session idles on a pause, so
+    // the slot is released once the run settles into one.
+    let permit = Arc::new(acquire_run_slot(state).await?);
 
     if let Err((status, msg)) = validate_policy_profile(body.policy_profile.as_deref()) {
-        return (status, Json(json!({"error": msg}))).into_response();
+        return Err((status, Json(json!({"error": msg}))).into_response());
+    }
+    if let Err((status, msg)) = validate_budget(body.budget.as_ref()) {
+        return Err((status, Json(json!({"error": msg}))).into_response());
     }
     if !state.has_default_agent {
-        return (
+        return Err((
             axum::http::StatusCode::BAD_REQUEST,
             Json(
                 json!({"error": "this server was started without an agent file \
@@ -467,7 +614,7 @@ pub(in crate::server) async fn stream_session(
                 server with an agent path"}),
             ),
         )
-            .into_response();
+            .into_response());
     }
     let policy_profile = body.policy_profile.clone();
     // Warning-only static effect preflight under the session's tightened
@@ -475,7 +622,7 @@ pub(in crate::server) async fn stream_session(
     if let Some(profile) = policy_profile.as_deref() {
         super::super::preflight::warn_denied_static_effects(
             &state.agent_path,
-            &super::session_policy(&state, Some(profile)),
+            &super::session_policy(state, Some(profile)),
             &format!("session policy profile '{profile}'"),
         );
     }
@@ -494,44 +641,19 @@ pub(in crate::server) async fn stream_session(
     });
     let input = body.input.clone();
 
-    let (event_tx, mut event_rx) =
-        mpsc::unbounded_channel::<crate::runtime::context::RuntimeEvent>();
-    let (result_tx, mut result_rx) = mpsc::unbounded_channel::<anyhow::Result<RunResult>>();
-    let (cancel_tx, mut cancel_rx) = mpsc::unbounded_channel::<String>();
-    let (signal_tx, mut signal_rx) = mpsc::unbounded_channel::<(u64, String)>();
-    let cancelled = Arc::new(AtomicBool::new(false));
-
     // Build the first run's context up front so the run id is known before the
     // agent starts and the delivery endpoint can enqueue into the live
     // in-memory mailbox from the first instant (`docs/signals.md` Phase 3).
     // Pause mode: an `input()` or approval gate surfaces as a paused session
-    // (handed to the durable HTTP endpoints) instead of blocking on stdin.
+    // instead of blocking on stdin.
     let ctx = RuntimeContext::new();
-    ctx.set_event_sender(event_tx.clone());
     ctx.set_input_mode(InputMode::Pause);
-    let run_id = ctx.run_id();
-    let ctx_slot = Arc::new(StdMutex::new(ctx.clone()));
-
-    // Journal every SSE event this stream emits so a dropped client can
-    // re-attach via GET /sessions/{id}/stream, catch up, and follow live.
-    let event_log = Arc::new(SessionEventLog::new());
-
-    state.active_sessions.lock().unwrap().insert(
-        session_id.clone(),
-        ActiveSession {
-            cancelled: cancelled.clone(),
-            cancel_tx,
-            attempt_number,
-            signals: Some(LiveSignalSession {
-                ctx_slot: ctx_slot.clone(),
-                signal_tx,
-            }),
-            event_log: Some(event_log.clone()),
-        },
-    );
-    let mut session = StoredSession {
-        id: session_id.clone(),
-        run_id: Some(run_id),
+    if let Some(budget) = super::super::tenants::run_budget(state, body.budget).await? {
+        ctx.set_budget(budget);
+    }
+    let session = StoredSession {
+        id: session_id,
+        run_id: Some(ctx.run_id()),
         status: SessionStatus::Running,
         input: input.clone(),
         output: None,
@@ -546,191 +668,580 @@ pub(in crate::server) async fn stream_session(
         pending_approval: None,
         approvals: Vec::new(),
         policy_profile: policy_profile.clone(),
+        agent: Some(state.agent_path.display().to_string()),
+        recipe: None,
+        scheduled_for: None,
+        tenant: state.tenant_id(),
         created_at: chrono::Utc::now(),
     };
-    let _ = state.session_store.put(&session);
+    let Some(run) = SupervisedRun::register(
+        state,
+        session,
+        ctx.clone(),
+        attempt_number,
+        Some(permit.clone()),
+        answers,
+    ) else {
+        return Err(already_live());
+    };
+    if let Some(err) = super::super::store_or_500(state, &run.session) {
+        state
+            .active_sessions
+            .lock()
+            .unwrap()
+            .remove(&run.session.id);
+        return Err(err);
+    }
+    spawn_streaming_run(
+        state,
+        policy_profile,
+        Vec::new(),
+        ctx,
+        input,
+        run.live.result_tx.clone(),
+        Some(permit),
+    );
+    Ok(run)
+}
+
+/// Take over supervision of a stored session paused on input, a signal or
+/// an approval, with no run in flight: [`supervise`] starts idle on the
+/// pause, and an answer resumes the run in-process. Takes no run slot while
+/// it idles; the resumed leg takes one. `None` when another connection
+/// already supervises the session.
+pub(in crate::server) fn adopt_paused_session(
+    state: &AppState,
+    session: StoredSession,
+) -> Option<SupervisedRun> {
+    // A warm run parked on this input pause unwinds into the same paused
+    // artifact the replay below resumes from.
+    state.warm_runs.lock().unwrap().remove(&session.id);
+    let run_id = session.run_id.clone().unwrap_or_else(|| session.id.clone());
+    let inbox = load_persisted_signal_inbox(&state.run_base, Some(&run_id));
+    let ctx = RuntimeContext::with_replay_host_promises_vfs_and_signals(
+        Vec::new(),
+        Vec::new(),
+        Default::default(),
+        inbox,
+    );
+    ctx.set_run_id(run_id.clone());
+    // Live deliveries write through to the run's mailbox even before a run
+    // leg starts.
+    ctx.enable_persistence_with_store(
+        state.run_base.join(&run_id),
+        crate::runtime::store::RunStoreFactory::shared(&state.run_base).store_for(&run_id),
+    );
+    let mut run = SupervisedRun::register(state, session, ctx, None, None, true)?;
+    run.adopted = true;
+    Some(run)
+}
+
+fn delivered_signal_value(entry: QueuedSignal) -> Value {
+    json!({
+        "name": entry.name,
+        "payload": entry.payload,
+        "from": entry.from,
+    })
+}
+
+/// Resolve a supervised pause in-process and kick off the resumed run
+/// (`docs/signals.md` Phase 3 — the fast resume trigger that skips the HTTP
+/// `/resume` round-trip). Claims the pause in the session store, completes
+/// the persisted pending op with the answer — a delivered `{name,payload,from}`
+/// or the timeout sentinel for a signal, the response for an `input()`, the
+/// operator's decision for an approval — and appends the synthetic resolution
+/// record. Then swaps a fresh replay context into the live slot, carrying
+/// over the in-memory mailbox so a delivery racing this resume is not lost,
+/// persists the session as Running, and spawns the blocking re-run, which
+/// reports back on `result_tx`.
+fn resume_pause_in_process(
+    live: &LiveRun,
+    session: &mut StoredSession,
+    resolution: PauseResolution,
+) -> InProcessResume {
+    let state = &live.state;
+    // The same claim the resume/approve endpoints take, so an HTTP answer
+    // to this pause — here or on a server sharing the store — can't also run it.
+    match state
+        .session_store
+        .transition(&session.id, &session.status, &SessionStatus::Running)
+    {
+        Ok(true) => {}
+        Ok(false) => {
+            if let Ok(Some(current)) = state.session_store.get(&session.id) {
+                *session = current;
+            }
+            return InProcessResume::Lost;
+        }
+        Err(e) => {
+            tracing::warn!("session `{}`: claiming the pause: {e:#}", session.id);
+            return InProcessResume::NotPending;
+        }
+    }
+    let run_id = session.run_id.clone().unwrap_or_else(|| session.id.clone());
+    let (record, replay_host_promises) = match resolution {
+        PauseResolution::Signal { seq, value } => {
+            let completed = {
+                let lock = state.signal_inbox_lock(&run_id);
+                let _guard = lock.lock().unwrap();
+                complete_persisted_pending_host_operation(
+                    &state.run_base,
+                    session.run_id.as_deref(),
+                    Some((seq, PendingHostOperationKind::Signal)),
+                    HostPromiseCompletion::Resolved(value.clone()),
+                )
+            };
+            let Ok(Some(pending)) = completed else {
+                release_pause(state, session);
+                return InProcessResume::NotPending;
+            };
+            (Some(signal_resolution_record(&pending, seq, value)), true)
+        }
+        PauseResolution::Input { seq, response } => {
+            let completed = complete_persisted_pending_host_operation(
+                &state.run_base,
+                session.run_id.as_deref(),
+                Some((seq, PendingHostOperationKind::Input)),
+                HostPromiseCompletion::Resolved(Value::String(response.clone())),
+            );
+            let Ok(Some(_)) = completed else {
+                release_pause(state, session);
+                return InProcessResume::NotPending;
+            };
+            (Some(input_resolution_record(session, seq, &response)), true)
+        }
+        PauseResolution::Approval { allow } => {
+            let Some(pending) = session.pending_approval.clone() else {
+                release_pause(state, session);
+                return InProcessResume::NotPending;
+            };
+            audit_operator_answer(state, session, &pending, allow);
+            if !allow {
+                let error = format!("policy: `{}` denied by operator", pending.target);
+                if complete_persisted_pending_host_operation(
+                    &state.run_base,
+                    session.run_id.as_deref(),
+                    None,
+                    HostPromiseCompletion::Rejected(error.clone()),
+                )
+                .is_err()
+                {
+                    release_pause(state, session);
+                    return InProcessResume::NotPending;
+                }
+                session.status = SessionStatus::Failed;
+                session.error = Some(error);
+                session.pending_approval = None;
+                let _ = state.session_store.put(session);
+                return InProcessResume::Denied;
+            }
+            if complete_persisted_pending_host_operation(
+                &state.run_base,
+                session.run_id.as_deref(),
+                None,
+                HostPromiseCompletion::Resolved(json!({
+                    "approved": true,
+                    "target": pending.target,
+                })),
+            )
+            .is_err()
+            {
+                release_pause(state, session);
+                return InProcessResume::NotPending;
+            }
+            // The gated call was never recorded: it re-executes live past the
+            // replayed log, now passing the seeded approval — so no record,
+            // and no host promises that would replay a placeholder for it.
+            session.approvals.push((pending.target, pending.args));
+            session.pending_approval = None;
+            (None, false)
+        }
+    };
+    if let Some(record) = record {
+        // Emit the resolution on the live stream. The resumed run replays
+        // the log (replayed records deliberately re-emit nothing), so without
+        // this the one record that carries the answer — who steered the run
+        // — would never reach a client watching the stream.
+        let _ = live.event_tx.send(RuntimeEvent::Call(record.clone()));
+        session.call_log.push(record);
+    }
+
+    let host_promises = if replay_host_promises {
+        load_persisted_host_promises(&state.run_base, session.run_id.as_deref()).unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    let vfs = load_persisted_vfs(&state.run_base, session.run_id.as_deref());
+
+    // Swap the resumed run's context into the live slot while holding it: the
+    // delivery endpoint enqueues into whatever context the slot currently
+    // names, so carrying the old context's in-memory mailbox into the new one
+    // under the lock means no delivery can fall between the two.
+    let ctx = {
+        let mut slot = live.ctx_slot.lock().unwrap();
+        let inbox = slot.signal_inbox();
+        let ctx = RuntimeContext::with_replay_host_promises_vfs_and_signals(
+            session.call_log.clone(),
+            host_promises,
+            vfs,
+            inbox,
+        );
+        if let Some(budget) = crate::server::engine::manifest_budget(state, &run_id) {
+            ctx.set_budget(budget);
+        }
+        ctx.set_run_id(run_id);
+        ctx.set_input_mode(InputMode::Pause);
+        ctx.set_event_sender(live.event_tx.clone());
+        *slot = ctx.clone();
+        ctx
+    };
+
+    session.status = SessionStatus::Running;
+    session.pending_seq = None;
+    session.pending_prompt = None;
+    session.pending_details = None;
+    session.pending_signal_name = None;
+    session.pending_signal_names = Vec::new();
+    session.pending_signal_deadline = None;
+    let _ = state.session_store.put(session);
 
     spawn_streaming_run(
-        &state,
-        policy_profile.clone(),
+        state,
+        session.policy_profile.clone(),
+        session.approvals.clone(),
         ctx,
-        input.clone(),
-        result_tx.clone(),
-        permit.clone(),
+        session.input.clone(),
+        live.result_tx.clone(),
+        live.permit.clone(),
     );
+    InProcessResume::Running
+}
+
+/// Resolve an idle signal pause with the oldest queued matching delivery.
+/// `None` when nothing queued matches; `Busy` when no run slot came free.
+async fn drain_queued_signal(
+    live: &mut LiveRun,
+    session: &mut StoredSession,
+    idle: Option<&IdlePause>,
+) -> Option<InProcessResume> {
+    let Some(IdlePause::Signal(seq, names)) = idle else {
+        return None;
+    };
+    let queued = live
+        .ctx_slot
+        .lock()
+        .unwrap()
+        .signal_inbox()
+        .iter()
+        .any(|entry| names.contains(&entry.name));
+    if !queued {
+        return None;
+    }
+    if !live.hold_slot().await {
+        return Some(InProcessResume::Busy);
+    }
+    let entry = live
+        .ctx_slot
+        .lock()
+        .unwrap()
+        .take_queued_signal_any(names)?;
+    Some(resume_pause_in_process(
+        live,
+        session,
+        PauseResolution::Signal {
+            seq: *seq,
+            value: delivered_signal_value(entry),
+        },
+    ))
+}
 
-    let state_for_stream = state.clone();
-    let stream = async_stream::stream! {
-        // The supervisor's share of the run slot (see acquire above).
-        let permit = permit;
+/// Drive a supervised session: forward its runtime events, keep it live
+/// across pauses it can resume in-process, and end with a `done` event once
+/// it settles or hands off. Every event is also appended to the session's
+/// event log for re-attaching clients.
+///
+/// A signal pause always stays live: a delivery or the `timeoutMs` deadline
+/// resumes it in-process. Input and approval pauses stay live only with an
+/// answer upstream (the WebSocket transport); otherwise they close the
+/// stream and the durable HTTP resume/approve endpoints take over. The run
+/// slot is given back while the session idles and taken again to resume it.
+pub(in crate::server) fn supervise(
+    run: SupervisedRun,
+) -> impl futures::Stream<Item = (&'static str, String)> {
+    let SupervisedRun {
+        mut live,
+        mut session,
+        attempt_number,
+        event_log,
+        cancelled,
+        mut event_rx,
+        mut result_rx,
+        mut cancel_rx,
+        mut signal_rx,
+        mut answers,
+        adopted,
+    } = run;
+    async_stream::stream! {
         // Closes the re-attach log with a synthetic `done` if this future is
         // dropped (client disconnect) before a real terminal event lands.
-        let _close_guard = EventLogCloseGuard {
+        let mut close_guard = EventLogCloseGuard {
             log: event_log.clone(),
+            state: live.state.clone(),
             session_id: session.id.clone(),
+            idle: None,
         };
-        // The signal pause currently supervised: (pending seq, listen set).
-        // While set, the run idles and a matching delivery (or the timeout
-        // deadline) resumes it in-process without an HTTP round-trip.
-        let mut supervising: Option<(u64, Vec<String>)> = None;
+        // The pause currently supervised with no run in flight. A matching
+        // delivery, answer or timeout deadline resumes it in-process.
+        let mut idle: Option<IdlePause> = None;
         let mut deadline: Option<tokio::time::Instant> = None;
+        // A pause to take up at the top of the loop: the adopted one, then
+        // each one a run settles into while staying live.
+        let mut paused = adopted;
+        // Drain the mailbox into the idle signal pause at the top of the loop:
+        // once on pausing, and again when a busy retry comes due.
+        let mut drain = false;
+        let mut retry: Option<tokio::time::Instant> = None;
         loop {
-            tokio::select! {
-                Some(evt) = event_rx.recv() => {
-                    let (name, data) = runtime_event_to_sse_parts(evt, attempt_number);
-                    event_log.append(name, &data);
-                    yield Ok::<_, std::convert::Infallible>(Event::default().event(name).data(data));
+            let mut outcome: Option<InProcessResume> = None;
+            if std::mem::take(&mut paused) {
+                // Idle runs hold no slot; a resume takes one again.
+                live.permit = None;
+                // Announce the pause, then for a signal listen point drain a
+                // signal that arrived while the run was unwinding (mailbox
+                // order: lowest delivery_seq first).
+                let data = serde_json::to_string(&stamp_attempt(
+                    final_session_event(&session),
+                    attempt_number,
+                ))
+                .unwrap_or_else(|_| "{}".into());
+                event_log.append("paused", &data);
+                yield ("paused", data);
+
+                let pause = IdlePause::of(&session);
+                if let IdlePause::Signal(..) = &pause {
+                    deadline = session.pending_signal_deadline.map(|d| {
+                        let wait = (d - chrono::Utc::now()).to_std().unwrap_or_default();
+                        tokio::time::Instant::now() + wait
+                    });
+                    drain = true;
                 }
-                Some(reason) = cancel_rx.recv() => {
-                    cancelled.store(true, Ordering::SeqCst);
-                    state_for_stream.active_sessions.lock().unwrap().remove(&session.id);
-                    // A run idling on a supervised signal pause has no blocking
-                    // task left to notice the flag — persist the cancellation
-                    // here. A still-executing run persists it when it returns.
-                    if supervising.is_some() {
-                        session.status = SessionStatus::Cancelled;
-                        session.error = Some(reason.clone());
-                        let _ = state_for_stream.session_store.put(&session);
+                idle = Some(pause);
+                close_guard.idle = Some(session.clone());
+            }
+            if std::mem::take(&mut drain) {
+                outcome = drain_queued_signal(&mut live, &mut session, idle.as_ref()).await;
+            }
+            if outcome.is_none() {
+                tokio::select! {
+                    Some(evt) = event_rx.recv() => {
+                        let (name, data) = runtime_event_to_sse_parts(evt, attempt_number);
+                        event_log.append(name, &data);
+                        yield (name, data);
                     }
-                    let final_event = stamp_attempt(json!({
-                        "id": session.id,
-                        "status": "cancelled",
-                        "error": reason,
-                    }), attempt_number);
-                    let data = serde_json::to_string(&final_event).unwrap_or_else(|_| "{}".into());
-                    event_log.append("done", &data);
-                    yield Ok::<_, std::convert::Infallible>(Event::default().event("done").data(data));
-                    break;
-                }
-                Some((delivery_seq, name)) = signal_rx.recv() => {
-                    // A delivery landed while we supervise. If it matches the
-                    // pause we're idling on, apply the pinned tie-break
-                    // (pending-pause-wins-with-newest): take THIS exact entry
-                    // back out of the mailbox and resolve the pause with it,
-                    // leaving older queued entries for later listen points.
-                    // Otherwise it stays durably queued for a future drain.
-                    if let Some((seq, names)) = supervising.clone() {
-                        if names.iter().any(|n| n == &name) {
-                            let entry = ctx_slot.lock().unwrap()
-                                .take_queued_signal_by_delivery_seq(delivery_seq);
-                            if let Some(entry) = entry {
-                                let value = json!({
-                                    "name": entry.name,
-                                    "payload": entry.payload,
-                                    "from": entry.from,
-                                });
-                                if resume_signal_pause_in_process(
-                                    &state_for_stream, &mut session, &ctx_slot,
-                                    &event_tx, &result_tx, &permit,
-                                    policy_profile.clone(), seq, value,
-                                ) {
-                                    supervising = None;
-                                    deadline = None;
+                    Some(reason) = cancel_rx.recv() => {
+                        cancelled.store(true, Ordering::SeqCst);
+                        close_guard.idle = None;
+                        live.state.active_sessions.lock().unwrap().remove(&session.id);
+                        // A run idling on a supervised pause has no blocking
+                        // task left to notice the flag — persist the
+                        // cancellation here. A still-executing run persists it
+                        // when it returns.
+                        if idle.is_some() {
+                            session.status = SessionStatus::Cancelled;
+                            session.error = Some(reason.clone());
+                            let _ = live.state.session_store.put(&session);
+                        }
+                        let final_event = stamp_attempt(json!({
+                            "id": session.id,
+                            "status": "cancelled",
+                            "error": reason,
+                        }), attempt_number);
+                        let data = serde_json::to_string(&final_event).unwrap_or_else(|_| "{}".into());
+                        event_log.append("done", &data);
+                        yield ("done", data);
+                        break;
+                    }
+                    Some((delivery_seq, name)) = signal_rx.recv() => {
+                        // A delivery landed while we supervise. If it matches the
+                        // pause we're idling on, apply the pinned tie-break
+                        // (pending-pause-wins-with-newest): take THIS exact entry
+                        // back out of the mailbox and resolve the pause with it,
+                        // leaving older queued entries for later listen points.
+                        // Otherwise it stays durably queued for a future drain.
+                        if let Some(IdlePause::Signal(seq, names)) = idle.clone() {
+                            if names.iter().any(|n| n == &name) {
+                                // With no slot free it stays queued for the
+                                // busy retry's drain.
+                                if !live.hold_slot().await {
+                                    outcome = Some(InProcessResume::Busy);
+                                } else {
+                                    let entry = live.ctx_slot.lock().unwrap()
+                                        .take_queued_signal_by_delivery_seq(delivery_seq);
+                                    if let Some(entry) = entry {
+                                        outcome = Some(resume_pause_in_process(
+                                            &live,
+                                            &mut session,
+                                            PauseResolution::Signal { seq, value: delivered_signal_value(entry) },
+                                        ));
+                                    }
                                 }
                             }
                         }
                     }
-                }
-                _ = async { tokio::time::sleep_until(deadline.unwrap()).await }, if deadline.is_some() => {
-                    // `timeoutMs` deadline passed with no matching delivery:
-                    // resolve the supervised pause with the timeout sentinel.
-                    if let Some((seq, names)) = supervising.clone() {
-                        let sentinel = signal_timeout_sentinel(&names);
-                        if resume_signal_pause_in_process(
-                            &state_for_stream, &mut session, &ctx_slot,
-                            &event_tx, &result_tx, &permit,
-                            policy_profile.clone(), seq, sentinel,
-                        ) {
-                            supervising = None;
+                    Some(PauseAnswer { answer, reply }) = async {
+                        match answers.as_mut() {
+                            Some(answers) => answers.recv().await,
+                            None => std::future::pending().await,
                         }
+                    } => {
+                        let resolution = match (answer, &idle) {
+                            (Answer::Input(response), Some(IdlePause::Input(seq))) => {
+                                Some(PauseResolution::Input { seq: *seq, response })
+                            }
+                            (Answer::Approval { allow }, Some(IdlePause::Approval)) => {
+                                Some(PauseResolution::Approval { allow })
+                            }
+                            _ => None,
+                        };
+                        let result = match resolution {
+                            Some(resolution) => {
+                                let resumed = if live.hold_slot().await {
+                                    resume_pause_in_process(&live, &mut session, resolution)
+                                } else {
+                                    InProcessResume::Busy
+                                };
+                                outcome = Some(resumed);
+                                match resumed {
+                                    InProcessResume::Running | InProcessResume::Denied => Ok(()),
+                                    InProcessResume::NotPending => {
+                                        Err("the pause has no pending operation to resolve".to_string())
+                                    }
+                                    InProcessResume::Lost => {
+                                        Err("Session is already being resumed".to_string())
+                                    }
+                                    InProcessResume::Busy => {
+                                        Err("server busy: no run slot came free to resume the session".to_string())
+                                    }
+                                }
+                            }
+                            None => Err(match &idle {
+                                Some(_) => "the session is paused on something else".to_string(),
+                                None => "the session is running, not paused".to_string(),
+                            }),
+                        };
+                        let _ = reply.send(result);
                     }
-                    deadline = None;
-                }
-                Some(result) = result_rx.recv() => {
-                    let was_cancelled = cancelled.load(Ordering::SeqCst);
-                    match result {
-                        Ok(run_result) => apply_run_outcome(&mut session, run_result),
-                        Err(e) => {
-                            session.status = SessionStatus::Failed;
-                            session.output = None;
-                            session.error =
-                                Some(agent_error_string(&state_for_stream.agent_path, &e));
+                    _ = async { tokio::time::sleep_until(deadline.unwrap()).await }, if deadline.is_some() => {
+                        // `timeoutMs` deadline passed with no matching delivery:
+                        // resolve the supervised pause with the timeout sentinel.
+                        deadline = None;
+                        if let Some(IdlePause::Signal(seq, names)) = idle.clone() {
+                            if live.hold_slot().await {
+                                let value = signal_timeout_sentinel(&names);
+                                outcome = Some(resume_pause_in_process(
+                                    &live,
+                                    &mut session,
+                                    PauseResolution::Signal { seq, value },
+                                ));
+                            } else {
+                                // Still due: fire again once a slot may be free.
+                                deadline = Some(tokio::time::Instant::now() + SLOT_RETRY);
+                            }
                         }
                     }
-                    if was_cancelled {
-                        session.status = SessionStatus::Cancelled;
-                        session.output = None;
-                        session.error = Some("session cancelled".to_string());
+                    _ = async { tokio::time::sleep_until(retry.unwrap()).await }, if retry.is_some() => {
+                        retry = None;
+                        drain = true;
                     }
-                    let _ = state_for_stream.session_store.put(&session);
-
-                    if session.status == SessionStatus::Paused
-                        && !session.pending_signal_names.is_empty()
-                    {
-                        // A signal listen point: stay live. Announce the pause,
-                        // then either drain a signal that arrived while the run
-                        // was unwinding (mailbox order: lowest delivery_seq
-                        // first) or idle until a delivery/timeout resumes us.
-                        let names = session.pending_signal_names.clone();
-                        let seq = session.pending_seq.unwrap_or_default();
-                        let paused_event = stamp_attempt(json!({
-                            "id": session.id,
-                            "status": "paused",
-                            "pending_seq": seq,
-                            "pending_signal_name": session.pending_signal_name,
-                            "pending_signal_names": names,
-                            "pending_signal_deadline": session.pending_signal_deadline,
-                        }), attempt_number);
-                        let data = serde_json::to_string(&paused_event).unwrap_or_else(|_| "{}".into());
-                        event_log.append("paused", &data);
-                        yield Ok::<_, std::convert::Infallible>(Event::default().event("paused").data(data));
-
-                        let queued = ctx_slot.lock().unwrap().take_queued_signal_any(&names);
-                        let mut resumed = false;
-                        if let Some(entry) = queued {
-                            let value = json!({
-                                "name": entry.name,
-                                "payload": entry.payload,
-                                "from": entry.from,
-                            });
-                            resumed = resume_signal_pause_in_process(
-                                &state_for_stream, &mut session, &ctx_slot,
-                                &event_tx, &result_tx, &permit,
-                                policy_profile.clone(), seq, value,
-                            );
+                    Some(result) = result_rx.recv() => {
+                        let was_cancelled = cancelled.load(Ordering::SeqCst);
+                        match result {
+                            Ok(run_result) => apply_run_outcome(&mut session, run_result),
+                            Err(e) => {
+                                session.status = SessionStatus::Failed;
+                                session.output = None;
+                                session.error =
+                                    Some(agent_error_string(&live.state.agent_path, &e));
+                            }
                         }
-                        if resumed {
-                            supervising = None;
-                            deadline = None;
-                        } else {
-                            supervising = Some((seq, names));
-                            deadline = session.pending_signal_deadline.map(|d| {
-                                let wait = (d - chrono::Utc::now()).to_std().unwrap_or_default();
-                                tokio::time::Instant::now() + wait
-                            });
+                        if was_cancelled {
+                            session.status = SessionStatus::Cancelled;
+                            session.output = None;
+                            session.error = Some("session cancelled".to_string());
                         }
-                        continue;
-                    }
+                        let _ = live.state.session_store.put(&session);
 
-                    // Anything else ends live supervision: terminal states
-                    // close the stream, and input/approval pauses hand off to
-                    // the durable HTTP resume/approve endpoints.
-                    state_for_stream.active_sessions.lock().unwrap().remove(&session.id);
+                        let signal_pause = session.status == SessionStatus::Paused
+                            && !session.pending_signal_names.is_empty();
+                        let answerable_pause = answers.is_some()
+                            && matches!(
+                                session.status,
+                                SessionStatus::Paused | SessionStatus::AwaitingApproval
+                            );
+                        if signal_pause || answerable_pause {
+                            paused = true;
+                            continue;
+                        }
+
+                        // Anything else ends live supervision: terminal states
+                        // close the stream, and input/approval pauses hand off to
+                        // the durable HTTP resume/approve endpoints.
+                        live.state.active_sessions.lock().unwrap().remove(&session.id);
+                        let final_event = final_session_event(&session);
+                        let data = serde_json::to_string(&stamp_attempt(final_event, attempt_number)).unwrap_or_else(|_| "{}".into());
+                        event_log.append("done", &data);
+                        yield ("done", data);
+                        break;
+                    }
+                    else => {
+                        live.state.active_sessions.lock().unwrap().remove(&session.id);
+                        break;
+                    },
+                }
+            }
+            match outcome {
+                Some(InProcessResume::Running) => {
+                    idle = None;
+                    deadline = None;
+                    retry = None;
+                    close_guard.idle = None;
+                }
+                Some(InProcessResume::Busy) => {
+                    retry = Some(tokio::time::Instant::now() + SLOT_RETRY);
+                }
+                // Settled without a run: close with the session's state.
+                Some(InProcessResume::Denied | InProcessResume::Lost) => {
+                    close_guard.idle = None;
+                    live.state.active_sessions.lock().unwrap().remove(&session.id);
                     let final_event = final_session_event(&session);
                     let data = serde_json::to_string(&stamp_attempt(final_event, attempt_number)).unwrap_or_else(|_| "{}".into());
                     event_log.append("done", &data);
-                    yield Ok::<_, std::convert::Infallible>(Event::default().event("done").data(data));
+                    yield ("done", data);
                     break;
                 }
-                else => {
-                    state_for_stream.active_sessions.lock().unwrap().remove(&session.id);
-                    break;
-                },
+                Some(InProcessResume::NotPending) | None => {
+                    // A slot taken for a resume that did not happen goes back.
+                    if idle.is_some() {
+                        live.permit = None;
+                    }
+                }
             }
         }
-    };
+    }
+}
 
-    Sse::new(stream)
+/// POST /sessions/stream — run the agent and stream each host-function call
+/// as a Server-Sent Event while it executes. Final event has `event: done`
+/// carrying the session id and output.
+pub(in crate::server) async fn stream_session(
+    State(state): State<AppState>,
+    Json(body): Json<CreateSessionRequest>,
+) -> Response {
+    let run = match start_streaming_session(&state, body, false).await {
+        Ok(run) => run,
+        Err(resp) => return resp,
+    };
+    Sse::new(supervise(run).map(sse_event))
         .keep_alive(KeepAlive::default())
         .into_response()
 }
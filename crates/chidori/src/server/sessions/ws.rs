@@ -0,0 +1,277 @@
+//! GET /sessions/{id}/ws — the WebSocket session transport: one connection
+//! carries a session's event stream down and the operator's answers up.
+//!
+//! Downstream, every text frame is `{"event": <name>, "data": <payload>}`
+//! with exactly the names and payloads of the SSE stream (`call`,
+//! `prompt_*`, `paused`, `done`), plus `reply` frames answering upstream
+//! messages: `{"event": "reply", "data": {"type", "status", "body"}}`, where
+//! `status` and `body` are what the equivalent HTTP endpoint would return.
+//!
+//! Upstream, text frames are JSON objects tagged by `type`:
+//! - `start` — the `POST /sessions/stream` body; starts the session under
+//!   the path's id when no session by that id exists yet.
+//! - `input` — `{"response"}`, answering an `input()` pause.
+//! - `signal` — `{"name", "payload", "from"}`, delivering a signal.
+//! - `approve` — `{"decision": "allow" | "deny"}`, answering an approval gate.
+//! - `cancel` — `{"reason"}`, cancelling the session.
+//!
+//! Which feed a connection gets depends on the session: a live streaming
+//! session is followed like `GET /sessions/{id}/stream`; a stored session
+//! paused on input, a signal or an approval is adopted by a supervisor that
+//! resumes it in-process when answered; any other stored session replays
+//! its journal and closes. While this connection's supervisor idles on a
+//! pause, `input` and `approve` resolve it in-process — the same path a live
+//! signal pause takes — instead of round-tripping through the durable
+//! resume/approve endpoints, which answer them otherwise.
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{Json, Response};
+use futures::future::BoxFuture;
+use futures::stream::{BoxStream, FuturesUnordered};
+use futures::{FutureExt, SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::storage::SessionStatus;
+
+use super::super::AppState;
+use super::resume::{
+    approve_session, resume_session, signal_session, ApproveRequest, ResumeRequest, SignalRequest,
+};
+use super::stream::{
+    adopt_paused_session, follow_event_log, replay_stored_session, start_streaming_session,
+    supervise, Answer, PauseAnswer,
+};
+use super::{cancel_session, CancelSessionRequest, CreateSessionRequest};
+
+/// An upstream frame.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    Start(CreateSessionRequest),
+    Input(ResumeRequest),
+    Signal(SignalRequest),
+    Approve(ApproveRequest),
+    Cancel(CancelSessionRequest),
+}
+
+impl ClientMessage {
+    fn kind(&self) -> &'static str {
+        match self {
+            ClientMessage::Start(_) => "start",
+            ClientMessage::Input(_) => "input",
+            ClientMessage::Signal(_) => "signal",
+            ClientMessage::Approve(_) => "approve",
+            ClientMessage::Cancel(_) => "cancel",
+        }
+    }
+}
+
+type Feed = BoxStream<'static, (String, String)>;
+
+pub(in crate::server) async fn session_ws(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    ws.on_upgrade(move |socket| serve_socket(state, id, socket))
+}
+
+/// The feed for an existing session, or `None` when no session by `id`
+/// exists yet (the client may `start` one). An error becomes the first
+/// frame, and the connection closes.
+async fn existing_feed(state: &AppState, id: &str) -> Result<Option<Feed>, Value> {
+    if let Some(feed) = live_feed(state, id) {
+        return Ok(Some(feed));
+    }
+    match state.session_store.get(id) {
+        Ok(Some(session))
+            if matches!(
+                session.status,
+                SessionStatus::Paused | SessionStatus::AwaitingApproval
+            ) =>
+        {
+            // Another connection may have adopted it in between: follow that
+            // supervisor instead.
+            match adopt_paused_session(state, session) {
+                Some(run) => Ok(Some(supervised_feed(run))),
+                None => live_feed(state, id).map(Some).ok_or_else(|| {
+                    json!({
+                        "type": "start",
+                        "status": StatusCode::CONFLICT.as_u16(),
+                        "body": {"error": "the session is already live"},
+                    })
+                }),
+            }
+        }
+        Ok(Some(session)) => Ok(Some(replay_stored_session(session).boxed())),
+        Ok(None) => Ok(None),
+        Err(e) => Err(json!({
+            "type": "start",
+            "status": StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+            "body": {"error": e.to_string()},
+        })),
+    }
+}
+
+/// Follow the event log of a session some supervisor is driving right now.
+fn live_feed(state: &AppState, id: &str) -> Option<Feed> {
+    let log = state
+        .active_sessions
+        .lock()
+        .unwrap()
+        .get(id)
+        .and_then(|active| active.event_log.clone())?;
+    Some(follow_event_log(log).boxed())
+}
+
+fn supervised_feed(run: super::stream::SupervisedRun) -> Feed {
+    supervise(run)
+        .map(|(name, data)| (name.to_string(), data))
+        .boxed()
+}
+
+async fn serve_socket(state: AppState, id: String, socket: WebSocket) {
+    let (mut sink, mut upstream) = socket.split();
+    let mut feed = match existing_feed(&state, &id).await {
+        Ok(feed) => feed,
+        Err(data) => {
+            let _ = sink.send(frame("reply", data)).await;
+            let _ = sink.send(Message::Close(None)).await;
+            return;
+        }
+    };
+    // Replies still in flight. An answer waits on the supervisor this loop
+    // drives, so replies are awaited alongside the feed, never inline.
+    let mut replies: FuturesUnordered<BoxFuture<'static, Value>> = FuturesUnordered::new();
+    loop {
+        tokio::select! {
+            item = async { feed.as_mut().unwrap().next().await }, if feed.is_some() => {
+                let Some((name, data)) = item else { break };
+                let data = serde_json::from_str(&data).unwrap_or(Value::Null);
+                if sink.send(frame(&name, data)).await.is_err() {
+                    return;
+                }
+            }
+            Some(data) = replies.next() => {
+                if sink.send(frame("reply", data)).await.is_err() {
+                    return;
+                }
+            }
+            message = upstream.next() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                    Some(Ok(_)) => continue,
+                };
+                let message = match serde_json::from_str::<ClientMessage>(text.as_str()) {
+                    Ok(message) => message,
+                    Err(e) => {
+                        replies.push(immediate("invalid", StatusCode::BAD_REQUEST, &e.to_string()));
+                        continue;
+                    }
+                };
+                if let ClientMessage::Start(mut body) = message {
+                    if feed.is_some() {
+                        replies.push(immediate("start", StatusCode::CONFLICT, "session already exists"));
+                        continue;
+                    }
+                    body.session_id = Some(id.clone());
+                    match start_streaming_session(&state, body, true).await {
+                        Ok(run) => feed = Some(supervised_feed(run)),
+                        Err(resp) => replies.push(reply_data("start", resp).boxed()),
+                    }
+                    continue;
+                }
+                replies.push(dispatch(state.clone(), id.clone(), message));
+            }
+        }
+    }
+    // The feed settled: flush outstanding replies, then close.
+    while let Some(data) = replies.next().await {
+        if sink.send(frame("reply", data)).await.is_err() {
+            return;
+        }
+    }
+    let _ = sink.send(Message::Close(None)).await;
+}
+
+/// Answer an upstream message: `input` and `approve` go to this session's
+/// in-process supervisor when it takes answers, everything else to the
+/// endpoint the HTTP API would use.
+fn dispatch(state: AppState, id: String, message: ClientMessage) -> BoxFuture<'static, Value> {
+    let kind = message.kind();
+    let answers = state
+        .active_sessions
+        .lock()
+        .unwrap()
+        .get(&id)
+        .and_then(|active| active.answers.clone());
+    let answer = match (&message, answers) {
+        (ClientMessage::Input(body), Some(answers)) => {
+            Some((answers, Answer::Input(body.response.clone())))
+        }
+        (ClientMessage::Approve(body), Some(answers)) => Some((
+            answers,
+            Answer::Approval {
+                allow: body.decision == "allow",
+            },
+        )),
+        _ => None,
+    };
+    if let Some((answers, answer)) = answer {
+        let (reply, answered) = tokio::sync::oneshot::channel();
+        if answers.send(PauseAnswer { answer, reply }).is_err() {
+            return immediate(kind, StatusCode::CONFLICT, "the session is no longer live");
+        }
+        return async move {
+            let (status, body) = match answered.await {
+                Ok(Ok(())) => (StatusCode::ACCEPTED, json!({"id": id})),
+                Ok(Err(error)) => (StatusCode::CONFLICT, json!({"error": error})),
+                Err(_) => (
+                    StatusCode::CONFLICT,
+                    json!({"error": "the session is no longer live"}),
+                ),
+            };
+            json!({"type": kind, "status": status.as_u16(), "body": body})
+        }
+        .boxed();
+    }
+    async move {
+        let response = match message {
+            ClientMessage::Input(body) => resume_session(State(state), Path(id), Json(body)).await,
+            ClientMessage::Signal(body) => signal_session(State(state), Path(id), Json(body)).await,
+            ClientMessage::Approve(body) => {
+                approve_session(State(state), Path(id), Json(body)).await
+            }
+            ClientMessage::Cancel(body) => {
+                cancel_session(State(state), Path(id), Some(Json(body))).await
+            }
+            ClientMessage::Start(_) => unreachable!("start is handled by the socket loop"),
+        };
+        reply_data(kind, response).await
+    }
+    .boxed()
+}
+
+/// A `reply` frame's data for an endpoint's response.
+async fn reply_data(kind: &'static str, response: Response) -> Value {
+    let status = response.status().as_u16();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or(Value::Null);
+    json!({"type": kind, "status": status, "body": body})
+}
+
+fn immediate(kind: &'static str, status: StatusCode, error: &str) -> BoxFuture<'static, Value> {
+    let data = json!({"type": kind, "status": status.as_u16(), "body": {"error": error}});
+    futures::future::ready(data).boxed()
+}
+
+fn frame(event: &str, data: Value) -> Message {
+    Message::Text(json!({"event": event, "data": data}).to_string().into())
+}
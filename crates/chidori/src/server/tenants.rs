@@ -0,0 +1,227 @@
+//! Tenants (`tenants:` in the app manifest): per-tenant API keys that reach
+//! the session, agent and recipe API through a tenant-scoped [`AppState`].
+//! A tenant's state has its own directory (`.chidori/tenants/<id>/`, holding
+//! its run directories, detached-agent registry, workspace and memory), a
+//! view of the session store holding only its sessions, its own recipes,
+//! its policy profile layered on the server policy, and its concurrency cap
+//! and cost quota.
+//!
+//! [`tenant_middleware`] sits in front of the bearer auth: a request carrying
+//! a tenant's key is answered by that tenant's router; anything else falls
+//! through to the operator's.
+
+use std::collections::HashMap;
+use std::path::{Path as FsPath, PathBuf};
+use std::sync::{Arc, Mutex as StdMutex};
+
+use axum::extract::State;
+use axum::http::{header, Request, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Json, Response};
+use axum::Router;
+use serde_json::json;
+use tokio::sync::Semaphore;
+use tower::ServiceExt as _;
+
+use crate::app_manifest::{AppManifest, ManifestTenant};
+use crate::runtime::cost::{CostBudget, CostQuota};
+use crate::storage::{SessionStore, TenantScopedStore};
+
+use super::hardening::bearer_token_matches;
+use super::AppState;
+
+/// One tenant's identity and limits, carried by its [`AppState`].
+pub(super) struct Tenant {
+    pub(super) id: String,
+    /// The comma-separated bearer keys, read from `keys_env` at boot.
+    pub(super) keys: String,
+    /// `.chidori/tenants/<id>/`: the tenant's workspace and memory root, with
+    /// its runs under `.chidori/runs/` inside it.
+    pub(super) root: PathBuf,
+    /// The tenant's own cap on concurrent runs, inside the server's.
+    pub(super) run_slots: Option<Arc<Semaphore>>,
+    pub(super) quota: Option<CostQuota>,
+}
+
+/// A tenant and the router its keys reach.
+pub(super) struct TenantRoute {
+    pub(super) tenant: Arc<Tenant>,
+    pub(super) router: Router,
+}
+
+/// Refuse tenants alongside settings that would share state between them:
+/// both pin one workspace or memory store for every run in the process.
+pub(super) fn refuse_shared_roots() -> anyhow::Result<()> {
+    for var in ["CHIDORI_WORKSPACE_ROOT", "CHIDORI_MEMORY_DIR"] {
+        if std::env::var_os(var).is_some_and(|v| !v.is_empty()) {
+            anyhow::bail!(
+                "{var} is set, but the app manifest declares tenants: it would give every \
+                 tenant the same workspace and memory. Unset it — each tenant's workspace and \
+                 memory live under .chidori/tenants/<id>/."
+            );
+        }
+    }
+    Ok(())
+}
+
+/// The state a tenant's requests, schedules and fleet run under, derived from
+/// the operator's: shared providers, MCP servers, server-wide run cap and
+/// session table; the tenant's own everything else.
+pub(super) fn tenant_state(
+    operator: &AppState,
+    manifest: &AppManifest,
+    tenant: &ManifestTenant,
+    base_dir: &FsPath,
+    shared_store: Arc<dyn SessionStore>,
+) -> anyhow::Result<AppState> {
+    let keys = std::env::var(&tenant.keys_env).unwrap_or_default();
+    if keys.split(',').all(|key| key.trim().is_empty()) {
+        anyhow::bail!(
+            "tenant `{}`: {} holds no API keys — set it to the tenant's comma-separated keys",
+            tenant.id,
+            tenant.keys_env
+        );
+    }
+    let window = match &tenant.cost_window {
+        Some(window) => Some(super::parse_age(window).map_err(anyhow::Error::msg)?),
+        None => None,
+    };
+    let root = base_dir.join(".chidori").join("tenants").join(&tenant.id);
+    std::fs::create_dir_all(&root)?;
+    let root = std::fs::canonicalize(&root)?;
+    let policy = match tenant.policy_profile.as_deref() {
+        Some(profile) => {
+            let profile = crate::policy::builtin_profile(profile)
+                .ok_or_else(|| anyhow::anyhow!("unknown policy profile `{profile}`"))?;
+            Arc::new(operator.policy.restricted_by(Arc::new(profile)))
+        }
+        None => operator.policy.clone(),
+    };
+    Ok(AppState {
+        run_base: root.join(".chidori").join("runs"),
+        session_store: Arc::new(TenantScopedStore::new(
+            shared_store,
+            Some(tenant.id.clone()),
+        )),
+        policy,
+        recipes: Arc::new(manifest.to_recipes(Some(&tenant.id))),
+        active_sessions: Arc::new(StdMutex::new(HashMap::new())),
+        signal_inbox_locks: Arc::new(StdMutex::new(HashMap::new())),
+        warm_runs: Arc::new(StdMutex::new(HashMap::new())),
+        tenant: Some(Arc::new(Tenant {
+            id: tenant.id.clone(),
+            keys,
+            root,
+            run_slots: tenant
+                .max_concurrent_runs
+                .map(|n| Arc::new(Semaphore::new(n))),
+            quota: tenant.max_cost_usd.map(|max_cost_usd| CostQuota {
+                max_cost_usd,
+                window,
+            }),
+        })),
+        ..operator.clone()
+    })
+}
+
+/// The budget a new run of `state` starts with: `requested`, capped at what
+/// the tenant's cost quota has left. 429 once the quota is spent. Runs
+/// outside a quota keep `requested`.
+pub(super) async fn run_budget(
+    state: &AppState,
+    requested: Option<CostBudget>,
+) -> Result<Option<CostBudget>, Response> {
+    let Some(tenant) = state.tenant.clone() else {
+        return Ok(requested);
+    };
+    let Some(quota) = tenant.quota else {
+        return Ok(requested);
+    };
+    let store = state.session_store.clone();
+    let spent = tokio::task::spawn_blocking(move || {
+        crate::storage::spend_since(&*store, quota.since(chrono::Utc::now()))
+    })
+    .await
+    .map_err(anyhow::Error::from)
+    .and_then(|spent| spent)
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": format!("session store: {e}")})),
+        )
+            .into_response()
+    })?;
+    match quota.budget_for(spent, requested) {
+        Some(budget) => Ok(Some(budget)),
+        None => Err((
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(json!({
+                "error": format!(
+                    "tenant `{}` has spent its ${:.2} cost quota",
+                    tenant.id, quota.max_cost_usd
+                ),
+                "spent_usd": spent,
+            })),
+        )
+            .into_response()),
+    }
+}
+
+/// Paths only the operator's key reaches: process-wide metrics and MCP
+/// health, and the ACP sub-router, which runs outside any tenant.
+fn operator_only(path: &str) -> bool {
+    path == "/metrics" || path == "/health/mcp" || path == "/acp" || path.starts_with("/acp/")
+}
+
+/// Middleware: answer a request that carries a tenant's key with that
+/// tenant's router. Every tenant's keys are compared, in constant time, so
+/// which tenant matched does not show in the timing. Other requests fall
+/// through to the operator's bearer auth — which, once tenants exist, never
+/// lets a request through unauthenticated.
+///
+/// It wraps the operator's router as a whole rather than via
+/// `Router::layer`, so it runs before routing and the tenant's router matches
+/// the path afresh.
+pub(super) async fn tenant_middleware(
+    State(tenants): State<Arc<Vec<TenantRoute>>>,
+    req: Request<axum::body::Body>,
+    next: Next,
+) -> Response {
+    if tenants.is_empty() || req.uri().path() == "/health" {
+        return next.run(req).await;
+    }
+    let presented = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+    let mut matched = None;
+    for route in tenants.iter() {
+        if bearer_token_matches(&presented, &route.tenant.keys) {
+            matched = Some(route);
+        }
+    }
+    if let Some(route) = matched {
+        if operator_only(req.uri().path()) {
+            return (
+                StatusCode::FORBIDDEN,
+                Json(json!({"error": "this endpoint needs the operator's key"})),
+            )
+                .into_response();
+        }
+        return match route.router.clone().oneshot(req).await {
+            Ok(response) => response,
+            Err(never) => match never {},
+        };
+    }
+    if std::env::var("CHIDORI_API_KEY").is_err() {
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+            Json(json!({"error": "missing or invalid bearer token"})),
+        )
+            .into_response();
+    }
+    next.run(req).await
+}
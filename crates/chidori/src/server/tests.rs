@@ -7,7 +7,7 @@ use crate::runtime::snapshot::{
     RuntimePolicy, SnapshotAbi, SourceFingerprint, HOST_PROMISE_TABLE_FILE,
 };
 use axum::body;
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
 use std::sync::atomic::Ordering;
 
 /// A failed session's `error` must carry stack frames in ORIGINAL
@@ -155,7 +155,6 @@ fn test_state(run_base: PathBuf, agent_path: PathBuf) -> AppState {
         session_store: Arc::new(crate::storage::MemoryStore::new()),
         policy: PolicyConfig::from_env(),
         mcp: Arc::new(McpManager::new()),
-        mcp_tools: Arc::new(Vec::new()),
         recipes: Arc::new(Vec::new()),
         run_semaphore: Arc::new(Semaphore::new(1)),
         acquire_timeout: std::time::Duration::from_millis(1),
@@ -163,6 +162,7 @@ fn test_state(run_base: PathBuf, agent_path: PathBuf) -> AppState {
         signal_inbox_locks: Arc::new(StdMutex::new(HashMap::new())),
         warm_runs: Arc::new(StdMutex::new(HashMap::new())),
         warm_evict: warm_evict_from_env(),
+        tenant: None,
     }
 }
 
@@ -181,6 +181,371 @@ fn stamp_attempt_adds_attempt_number_to_object_events() {
     assert_eq!(stamped["attempt_number"], 42);
 }
 
+#[tokio::test]
+async fn health_reports_each_mcp_server_and_degrades_while_one_is_down() {
+    let run_base = test_run_base("health_reports_each_mcp_server");
+    let mut state = test_state(run_base.clone(), run_base.join("agent.ts"));
+    let (status, body) = response_json(health(State(state.clone())).await.into_response()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({"status": "ok"}));
+    let (_, body) = response_json(mcp_health(State(state.clone())).await.into_response()).await;
+    assert_eq!(body, json!({"status": "ok", "mcp": {}}));
+
+    // Nothing listens on port 1: the server is configured but down.
+    let mcp = McpManager::new();
+    let cfg: McpServersConfig = serde_json::from_value(json!({
+        "servers": { "notes": { "transport": "http", "url": "http://127.0.0.1:1/mcp" } },
+    }))
+    .unwrap();
+    mcp.start_from_config(&cfg).await.unwrap();
+    state.mcp = Arc::new(mcp);
+    // The unauthenticated view names no server.
+    let (_, body) = response_json(health(State(state.clone())).await.into_response()).await;
+    assert_eq!(body, json!({"status": "degraded"}));
+    let (status, body) = response_json(mcp_health(State(state)).await.into_response()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "degraded");
+    assert_eq!(body["mcp"]["notes"]["state"], "down");
+    assert_eq!(body["mcp"]["notes"]["transport"], "http");
+    assert_eq!(body["mcp"]["notes"]["tools"], 0);
+    assert!(body["mcp"]["notes"]["lastError"].is_string());
+}
+
+#[tokio::test]
+async fn metrics_reports_session_gauges_and_slot_rejections() {
+    let run_base = test_run_base("metrics_reports_session_gauges");
+    let state = test_state(run_base.clone(), run_base.join("agent.ts"));
+    for session in [
+        listed_session("a", "paused", "2026-05-17T09:01:00Z"),
+        listed_session("b", "paused", "2026-05-17T09:02:00Z"),
+        listed_session("c", "completed", "2026-05-17T09:03:00Z"),
+    ] {
+        state.session_store.put(&session).unwrap();
+    }
+    // The one slot is taken, so the next run times out waiting for it.
+    let _permit = hardening::acquire_run_slot(&state).await.unwrap();
+    let refused = hardening::acquire_run_slot(&state).await.unwrap_err();
+    assert_eq!(refused.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    let response = get_metrics(State(state)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[axum::http::header::CONTENT_TYPE],
+        "text/plain; version=0.0.4; charset=utf-8"
+    );
+    let bytes = body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let text = String::from_utf8(bytes.to_vec()).unwrap();
+    assert!(
+        text.contains("chidori_sessions{status=\"paused\"} 2\n"),
+        "{text}"
+    );
+    assert!(text.contains("chidori_sessions{status=\"completed\"} 1\n"));
+    assert!(text.contains("chidori_sessions{status=\"failed\"} 0\n"));
+    assert!(text.contains("chidori_sessions_active 0\n"));
+    // The registry is process-wide and other tests bump it too.
+    let rejections = text
+        .lines()
+        .find_map(|l| l.strip_prefix("chidori_concurrency_rejections_total "))
+        .and_then(|n| n.parse::<f64>().ok())
+        .unwrap();
+    assert!(rejections >= 1.0, "{text}");
+}
+
+#[tokio::test]
+async fn list_recipes_reports_next_and_last_tick_with_outcome() {
+    let run_base = test_run_base("list_recipes_reports_ticks");
+    let mut state = test_state(run_base.clone(), run_base.join("agent.ts"));
+    let recipe: Recipe = serde_yaml::from_str(
+        "name: digest\nagent: digest.ts\nschedule: \"0 9 * * *\"\ntimezone: Asia/Tokyo\ncatch_up: latest\n",
+    )
+    .unwrap();
+    state.recipes = Arc::new(vec![recipe]);
+
+    let (_, body) = response_json(list_recipes(State(state.clone())).await.into_response()).await;
+    let listed = &body["recipes"][0];
+    assert_eq!(listed["timezone"], "Asia/Tokyo");
+    assert_eq!(listed["catch_up"], "latest");
+    assert_eq!(listed["overlap"], "skip");
+    // 09:00 in Tokyo is 00:00 UTC.
+    assert!(listed["next_tick"]
+        .as_str()
+        .unwrap()
+        .ends_with("T00:00:00Z"));
+    assert!(listed["last_tick"].is_null());
+    assert!(listed["last_outcome"].is_null());
+
+    let mut failed: StoredSession = serde_json::from_value(json!({
+        "id": "tick-1", "status": "failed", "input": {}, "output": null, "call_log": [],
+        "error": "boom", "pending_seq": null, "pending_prompt": null,
+        "recipe": "digest", "scheduled_for": "2026-05-17T00:00:00Z",
+        "created_at": "2026-05-17T00:00:01Z",
+    }))
+    .unwrap();
+    state.session_store.put(&failed).unwrap();
+    failed.id = "manual".to_string();
+    failed.scheduled_for = None;
+    state.session_store.put(&failed).unwrap();
+
+    let (_, body) = response_json(list_recipes(State(state)).await.into_response()).await;
+    let listed = &body["recipes"][0];
+    assert_eq!(listed["last_tick"], "2026-05-17T00:00:00Z");
+    assert_eq!(
+        listed["last_outcome"],
+        json!({"session_id": "tick-1", "status": "failed", "error": "boom"})
+    );
+}
+
+fn listed_session(id: &str, status: &str, created_at: &str) -> StoredSession {
+    serde_json::from_value(json!({
+        "id": id, "status": status, "input": {"big": "payload"}, "output": null,
+        "call_log": [], "error": null, "pending_seq": null, "pending_prompt": null,
+        "agent": "agents/triage.ts", "created_at": created_at,
+    }))
+    .unwrap()
+}
+
+#[tokio::test]
+async fn list_sessions_pages_filters_and_summarizes() {
+    let run_base = test_run_base("list_sessions_pages");
+    let state = test_state(run_base.clone(), run_base.join("agent.ts"));
+    let mut paused = listed_session("c", "paused", "2026-05-17T09:03:00Z");
+    paused.pending_signal_name = Some("approval".to_string());
+    paused.pending_signal_names = vec!["approval".to_string()];
+    for session in [
+        listed_session("a", "completed", "2026-05-17T09:01:00Z"),
+        listed_session("b", "failed", "2026-05-17T09:02:00Z"),
+        paused,
+    ] {
+        state.session_store.put(&session).unwrap();
+    }
+    let list = |params: sessions::ListSessionsParams| {
+        let state = state.clone();
+        async move { response_json(list_sessions(State(state), Query(params)).await).await }
+    };
+
+    let (status, first) = list(sessions::ListSessionsParams {
+        limit: Some(2),
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let ids = |body: &Value| -> Vec<String> {
+        body["sessions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|s| s["id"].as_str().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(ids(&first), ["c", "b"]);
+    // Summary rows leave the heavy fields out.
+    assert!(first["sessions"][0].get("input").is_none());
+    assert_eq!(first["sessions"][0]["agent"], "agents/triage.ts");
+    let (_, second) = list(sessions::ListSessionsParams {
+        limit: Some(2),
+        cursor: first["next_cursor"].as_str().map(str::to_string),
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(ids(&second), ["a"]);
+    assert!(second["next_cursor"].is_null());
+
+    let (_, filtered) = list(sessions::ListSessionsParams {
+        status: Some("completed,paused".to_string()),
+        signal: Some("approval".to_string()),
+        view: Some("full".to_string()),
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(ids(&filtered), ["c"]);
+    assert_eq!(filtered["sessions"][0]["input"], json!({"big": "payload"}));
+
+    let (status, _) = list(sessions::ListSessionsParams {
+        status: Some("sleeping".to_string()),
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn delete_session_removes_the_session_and_its_run_directory() {
+    let run_base = test_run_base("delete_session_removes_run");
+    let state = test_state(run_base.clone(), run_base.join("agent.ts"));
+    let mut session = listed_session("done", "completed", "2026-05-17T09:01:00Z");
+    session.run_id = Some("run-done".to_string());
+    state.session_store.put(&session).unwrap();
+    std::fs::create_dir_all(run_base.join("run-done")).unwrap();
+    std::fs::write(run_base.join("run-done").join("records.jsonl"), "").unwrap();
+
+    let (cancel_tx, _cancel_rx) = tokio::sync::mpsc::unbounded_channel();
+    state.active_sessions.lock().unwrap().insert(
+        "done".to_string(),
+        ActiveSession {
+            cancelled: Arc::new(AtomicBool::new(false)),
+            cancel_tx,
+            attempt_number: None,
+            signals: None,
+            event_log: None,
+            answers: None,
+        },
+    );
+    let deleted = |state: AppState| async move {
+        response_json(delete_session(State(state), Path("done".to_string())).await).await
+    };
+    let (status, _) = deleted(state.clone()).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    state.active_sessions.lock().unwrap().clear();
+
+    let (status, body) = deleted(state.clone()).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert!(state.session_store.get("done").unwrap().is_none());
+    assert!(!run_base.join("run-done").exists());
+    let (status, _) = deleted(state).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn delete_session_refuses_a_session_running_elsewhere() {
+    let run_base = test_run_base("delete_session_running_elsewhere");
+    let state = test_state(run_base.clone(), run_base.join("agent.ts"));
+    // Running on another replica: nothing in this process's active_sessions.
+    let mut session = listed_session("elsewhere", "running", "2026-05-17T09:01:00Z");
+    session.run_id = Some("run-elsewhere".to_string());
+    state.session_store.put(&session).unwrap();
+    std::fs::create_dir_all(run_base.join("run-elsewhere")).unwrap();
+
+    let (status, body) =
+        response_json(delete_session(State(state.clone()), Path("elsewhere".to_string())).await)
+            .await;
+    assert_eq!(status, StatusCode::CONFLICT, "{body}");
+    assert!(state.session_store.get("elsewhere").unwrap().is_some());
+    assert!(run_base.join("run-elsewhere").exists());
+
+    // A session read as paused that resumed before the delete landed stays.
+    let mut paused = session.clone();
+    paused.status = SessionStatus::Paused;
+    assert!(!retention::delete_session_and_run(&state, &paused).unwrap());
+    assert!(run_base.join("run-elsewhere").exists());
+}
+
+#[test]
+fn retention_sweep_deletes_only_expired_settled_sessions() {
+    let run_base = test_run_base("retention_sweep");
+    let state = test_state(run_base.clone(), run_base.join("agent.ts"));
+    let old = (chrono::Utc::now() - chrono::Duration::days(40)).to_rfc3339();
+    let recent = chrono::Utc::now().to_rfc3339();
+    let mut expired = listed_session("expired", "completed", &old);
+    expired.run_id = Some("run-expired".to_string());
+    std::fs::create_dir_all(run_base.join("run-expired")).unwrap();
+    for session in [
+        expired,
+        listed_session("recent", "completed", &recent),
+        listed_session("old-failure", "failed", &old),
+        listed_session("old-pause", "paused", &old),
+    ] {
+        state.session_store.put(&session).unwrap();
+    }
+
+    let policy = RetentionPolicy {
+        completed: Some(parse_age("30d").unwrap()),
+        ..Default::default()
+    };
+    assert_eq!(retention::sweep(&state, &policy).unwrap(), 1);
+    assert!(state.session_store.get("expired").unwrap().is_none());
+    assert!(!run_base.join("run-expired").exists());
+    let mut left: Vec<String> = state
+        .session_store
+        .list()
+        .unwrap()
+        .into_iter()
+        .map(|s| s.id)
+        .collect();
+    left.sort();
+    assert_eq!(left, ["old-failure", "old-pause", "recent"]);
+    assert!(parse_age("30 days").is_err());
+}
+
+/// The sweeper keeps each recipe's newest scheduled session, so a scheduler
+/// restarted after a sweep catches up from it instead of re-running ticks
+/// whose sessions (and done-markers) were swept.
+#[tokio::test(flavor = "multi_thread")]
+async fn retention_keeps_the_tick_catch_up_resumes_from() {
+    use chrono::{DurationRound, TimeDelta};
+    let run_base = test_run_base("retention_keeps_the_latest_tick");
+    let state = test_state(run_base.clone(), run_base.join("agent.ts"));
+    let minute = chrono::Utc::now()
+        .duration_trunc(TimeDelta::minutes(1))
+        .unwrap();
+    let tick = |id: &str, status: &str, ago: i64| -> StoredSession {
+        let at = (minute - TimeDelta::minutes(ago)).to_rfc3339();
+        let mut session = listed_session(id, status, &at);
+        session.recipe = Some("digest".to_string());
+        session.scheduled_for = Some(minute - TimeDelta::minutes(ago));
+        session
+    };
+    for session in [
+        tick("kept-failure", "failed", 3),
+        tick("older", "completed", 2),
+        tick("latest", "completed", 0),
+    ] {
+        state.session_store.put(&session).unwrap();
+    }
+
+    let policy = RetentionPolicy {
+        completed: Some(parse_age("1s").unwrap()),
+        ..Default::default()
+    };
+    if chrono::Utc::now() - minute < TimeDelta::seconds(2) {
+        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+    }
+    assert_eq!(retention::sweep(&state, &policy).unwrap(), 1);
+    assert!(state.session_store.get("older").unwrap().is_none());
+    assert!(state.session_store.get("latest").unwrap().is_some());
+
+    let agent = run_base.join("digest.ts");
+    std::fs::write(&agent, "run(async () => ({ ok: true }));\n").unwrap();
+    let recipe = crate::recipes::Recipe {
+        name: "digest".to_string(),
+        agent,
+        schedule: Some("0 * * * * *".to_string()),
+        inputs: json!({}),
+        description: None,
+        budget: None,
+        timing: crate::recipes::ScheduleTiming {
+            catch_up: crate::recipes::CatchUp::All,
+            ..Default::default()
+        },
+    };
+    crate::scheduler::spawn_all(
+        vec![recipe],
+        crate::scheduler::SchedulerDeps {
+            providers: state.providers.clone(),
+            template_engine: state.template_engine.clone(),
+            session_store: state.session_store.clone(),
+            policy: state.policy.clone(),
+            mcp: state.mcp.clone(),
+            run_base: run_base.clone(),
+            workspace_root: None,
+            quota: None,
+        },
+    );
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    // Ticks after `latest` may come due while the test runs; none before it.
+    let rerun: Vec<String> = state
+        .session_store
+        .list()
+        .unwrap()
+        .into_iter()
+        .filter(|s| s.scheduled_for.is_some_and(|at| at <= minute))
+        .map(|s| s.id)
+        .filter(|id| id != "kept-failure" && id != "latest")
+        .collect();
+    assert!(rerun.is_empty(), "re-ran swept ticks: {rerun:?}");
+}
+
 #[tokio::test]
 async fn cancel_session_marks_active_session_cancelled() {
     let run_base = test_run_base("cancel_session_marks_active_session_cancelled");
@@ -201,6 +566,7 @@ async fn cancel_session_marks_active_session_cancelled() {
             attempt_number: Some(7),
             signals: None,
             event_log: None,
+            answers: None,
         },
     );
     state
@@ -222,6 +588,10 @@ async fn cancel_session_marks_active_session_cancelled() {
             pending_approval: None,
             approvals: Vec::new(),
             policy_profile: None,
+            agent: None,
+            recipe: None,
+            scheduled_for: None,
+            tenant: None,
             created_at: chrono::Utc::now(),
         })
         .unwrap();
@@ -358,6 +728,10 @@ fn checkpoint_snapshot_manifest_is_loaded_by_run_id() {
         pending_approval: None,
         approvals: Vec::new(),
         policy_profile: None,
+        agent: None,
+        recipe: None,
+        scheduled_for: None,
+        tenant: None,
         created_at: chrono::Utc::now(),
     };
 
@@ -426,6 +800,10 @@ async fn replay_session_uses_completed_prompt_host_promise_without_provider() {
         pending_approval: None,
         approvals: Vec::new(),
         policy_profile: None,
+        agent: None,
+        recipe: None,
+        scheduled_for: None,
+        tenant: None,
         created_at: chrono::Utc::now(),
     };
     state.session_store.put(&session).unwrap();
@@ -496,6 +874,10 @@ async fn replay_session_uses_completed_tool_host_promise_without_tool_registry()
         pending_approval: None,
         approvals: Vec::new(),
         policy_profile: None,
+        agent: None,
+        recipe: None,
+        scheduled_for: None,
+        tenant: None,
         created_at: chrono::Utc::now(),
     };
     state.session_store.put(&session).unwrap();
@@ -570,6 +952,10 @@ async fn replay_session_uses_completed_call_agent_host_promise_without_child_fil
         pending_approval: None,
         approvals: Vec::new(),
         policy_profile: None,
+        agent: None,
+        recipe: None,
+        scheduled_for: None,
+        tenant: None,
         created_at: chrono::Utc::now(),
     };
     state.session_store.put(&session).unwrap();
@@ -660,6 +1046,10 @@ async fn resume_session_uses_completed_prompt_host_promise_without_provider() {
         pending_approval: None,
         approvals: Vec::new(),
         policy_profile: None,
+        agent: None,
+        recipe: None,
+        scheduled_for: None,
+        tenant: None,
         created_at: chrono::Utc::now(),
     };
     state.session_store.put(&session).unwrap();
@@ -687,6 +1077,78 @@ async fn resume_session_uses_completed_prompt_host_promise_without_provider() {
     let _ = std::fs::remove_dir_all(temp_dir);
 }
 
+#[tokio::test]
+async fn resume_that_fails_to_load_host_promises_leaves_the_session_paused() {
+    let temp_dir = std::env::temp_dir().join(format!(
+        "chidori-server-resume-release-{}",
+        uuid::Uuid::new_v4()
+    ));
+    std::fs::create_dir_all(&temp_dir).unwrap();
+    let agent_path = temp_dir.join("agent.ts");
+    std::fs::write(
+        &agent_path,
+        r#"
+            export async function agent(input, chidori) {
+                return { approved: await chidori.input("continue?") };
+            }
+        "#,
+    )
+    .unwrap();
+    let run_base = temp_dir.join(".chidori").join("runs");
+    let run_id = "old-run";
+    write_snapshot_manifest_for_agent(&run_base, run_id, &agent_path);
+    std::fs::write(
+        run_base.join(run_id).join(HOST_PROMISE_TABLE_FILE),
+        b"not json",
+    )
+    .unwrap();
+    let state = test_state(run_base, agent_path);
+    let session = StoredSession {
+        id: "session-1".to_string(),
+        run_id: Some(run_id.to_string()),
+        status: SessionStatus::Paused,
+        input: json!({}),
+        output: None,
+        call_log: Vec::new(),
+        error: None,
+        pending_seq: Some(1),
+        pending_prompt: Some("continue?".to_string()),
+        pending_details: None,
+        pending_signal_name: None,
+        pending_signal_names: Vec::new(),
+        pending_signal_deadline: None,
+        pending_approval: None,
+        approvals: Vec::new(),
+        policy_profile: None,
+        agent: None,
+        recipe: None,
+        scheduled_for: None,
+        tenant: None,
+        created_at: chrono::Utc::now(),
+    };
+    state.session_store.put(&session).unwrap();
+
+    let (status, _) = response_json(
+        resume_session(
+            State(state.clone()),
+            Path("session-1".to_string()),
+            Json(ResumeRequest {
+                response: "yes".to_string(),
+                allow_source_change: false,
+            }),
+        )
+        .await,
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    // The claim was handed back: the pause can be answered again.
+    let stored = state.session_store.get("session-1").unwrap().unwrap();
+    assert_eq!(stored.status, SessionStatus::Paused);
+
+    let _ = std::fs::remove_dir_all(temp_dir);
+}
+
 #[tokio::test]
 async fn resume_session_uses_completed_tool_host_promise_without_tool_registry() {
     let temp_dir = std::env::temp_dir().join(format!(
@@ -763,6 +1225,10 @@ async fn resume_session_uses_completed_tool_host_promise_without_tool_registry()
         pending_approval: None,
         approvals: Vec::new(),
         policy_profile: None,
+        agent: None,
+        recipe: None,
+        scheduled_for: None,
+        tenant: None,
         created_at: chrono::Utc::now(),
     };
     state.session_store.put(&session).unwrap();
@@ -871,6 +1337,10 @@ async fn resume_session_uses_completed_call_agent_host_promise_without_child_fil
         pending_approval: None,
         approvals: Vec::new(),
         policy_profile: None,
+        agent: None,
+        recipe: None,
+        scheduled_for: None,
+        tenant: None,
         created_at: chrono::Utc::now(),
     };
     state.session_store.put(&session).unwrap();
@@ -1342,6 +1812,7 @@ fn warm_create_request(session_id: &str) -> CreateSessionRequest {
         replay_from: None,
         agent: None,
         policy_profile: None,
+        budget: None,
     }
 }
 
@@ -1446,6 +1917,46 @@ async fn warm_resume_continues_parked_run_in_place() {
     let _ = std::fs::remove_dir_all(temp_dir);
 }
 
+/// Two answers to the same pause race: the first claims it, the second gets
+/// a 409 instead of replaying the run a second time.
+#[tokio::test]
+async fn concurrent_resumes_of_one_pause_run_it_once() {
+    let temp_dir = std::env::temp_dir().join(format!(
+        "chidori-server-resume-race-{}",
+        uuid::Uuid::new_v4()
+    ));
+    let agent_path = write_agent(&temp_dir, TWO_INPUT_AGENT);
+    let run_base = temp_dir.join(".chidori").join("runs");
+    std::fs::create_dir_all(&run_base).unwrap();
+    let state = test_state(run_base.clone(), agent_path);
+
+    let (status, _) = response_json(
+        create_session(State(state.clone()), Json(warm_create_request("race-1"))).await,
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (first, second) = tokio::join!(
+        resume_with(&state, "race-1", "A"),
+        resume_with(&state, "race-1", "B")
+    );
+    let mut statuses = [first.0, second.0];
+    statuses.sort();
+    assert_eq!(statuses, [StatusCode::OK, StatusCode::CONFLICT]);
+
+    let session = state.session_store.get("race-1").unwrap().unwrap();
+    assert_eq!(session.status, SessionStatus::Paused);
+    assert_eq!(session.pending_prompt.as_deref(), Some("second?"));
+    let journal = journal_shape(&run_base, &session.run_id.unwrap());
+    assert_eq!(journal.len(), 1, "the first pause is answered exactly once");
+
+    // Finish the run so its warm-parked engine thread exits with the test.
+    let (status, _) = resume_with(&state, "race-1", "C").await;
+    assert_eq!(status, StatusCode::OK);
+
+    let _ = std::fs::remove_dir_all(temp_dir);
+}
+
 /// The warm and replay paths must be interchangeable: force the fallback
 /// (retire the entry mid-pause, as a crash/restart would) and assert the
 /// journal comes out identical to the warm run's.
@@ -1550,6 +2061,7 @@ fn create_request(policy_profile: Option<&str>) -> CreateSessionRequest {
         replay_from: None,
         agent: None,
         policy_profile: policy_profile.map(ToOwned::to_owned),
+        budget: None,
     }
 }
 
@@ -1605,6 +2117,10 @@ async fn get_holdings_reports_pending_operation_and_session_overlay() {
         pending_approval: None,
         approvals: Vec::new(),
         policy_profile: None,
+        agent: None,
+        recipe: None,
+        scheduled_for: None,
+        tenant: None,
         created_at: chrono::Utc::now(),
     };
     state.session_store.put(&session).unwrap();
@@ -1801,6 +2317,59 @@ async fn supervised_session_pauses_for_approval_then_operator_denies() {
     let _ = std::fs::remove_dir_all(temp_dir);
 }
 
+#[tokio::test]
+async fn audit_records_the_policy_gate_and_the_operator_answer() {
+    let (temp_dir, state) = policy_test_project("chidori-server-policy-audit", HTTP_AGENT);
+
+    let (_, body) = response_json(
+        create_session(
+            State(state.clone()),
+            Json(create_request(Some("supervised"))),
+        )
+        .await,
+    )
+    .await;
+    assert_eq!(body["status"], json!("awaitingapproval"));
+    let id = body["id"].as_str().unwrap().to_string();
+
+    let (status, body) =
+        response_json(get_audit(State(state.clone()), Path(id.clone())).await).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["policy_profile"], json!("supervised"));
+    let entries = body["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 1, "{body}");
+    // The permissive server policy is layer 0; the session's profile is the
+    // layer that asked.
+    assert_eq!(entries[0]["target"], json!("http"));
+    assert_eq!(entries[0]["decision"], json!("ask_before"));
+    assert_eq!(entries[0]["layer"], json!(1));
+    assert_eq!(entries[0]["layer_label"], json!("supervised"));
+    assert_eq!(entries[0]["outcome"], json!("paused"));
+    assert_eq!(entries[0]["args"]["url"], json!("https://example.invalid/"));
+
+    let _ = approve_session(
+        State(state.clone()),
+        Path(id.clone()),
+        Json(ApproveRequest {
+            decision: "deny".to_string(),
+            allow_source_change: false,
+        }),
+    )
+    .await;
+
+    let (_, body) = response_json(get_audit(State(state.clone()), Path(id)).await).await;
+    let entries = body["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 2, "{body}");
+    assert_eq!(entries[1]["outcome"], json!("operator_denied"));
+    assert_eq!(entries[1]["approval"], json!("server"));
+    assert_eq!(entries[1]["seq"], entries[0]["seq"]);
+
+    let (status, _) = response_json(get_audit(State(state), Path("nope".to_string())).await).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let _ = std::fs::remove_dir_all(temp_dir);
+}
+
 // -----------------------------------------------------------------------
 // Signal delivery (`docs/signals.md` §9–§11) — Stage 2.
 // -----------------------------------------------------------------------
@@ -3151,6 +3720,193 @@ async fn attach_stream_catches_up_and_follows_live_run() {
     let _ = std::fs::remove_dir_all(temp_dir);
 }
 
+// -----------------------------------------------------------------------
+// WebSocket transport (GET /sessions/{id}/ws).
+// -----------------------------------------------------------------------
+
+type TestSocket =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// Serve just the WebSocket route on an ephemeral port and connect to
+/// session `id` over it.
+async fn connect_ws(state: &AppState, id: &str) -> TestSocket {
+    let app = Router::new()
+        .route("/sessions/{id}/ws", get(session_ws))
+        .with_state(state.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    let (socket, _) = tokio_tungstenite::connect_async(format!("ws://{addr}/sessions/{id}/ws"))
+        .await
+        .unwrap();
+    socket
+}
+
+async fn ws_send(socket: &mut TestSocket, message: Value) {
+    use futures::SinkExt;
+    socket
+        .send(tokio_tungstenite::tungstenite::Message::Text(
+            message.to_string().into(),
+        ))
+        .await
+        .unwrap();
+}
+
+/// The next downstream frame named `event`, skipping the others.
+async fn ws_next(socket: &mut TestSocket, event: &str) -> Value {
+    use futures::StreamExt;
+    let wait = async {
+        loop {
+            let message = socket.next().await.expect("socket closed").unwrap();
+            let tokio_tungstenite::tungstenite::Message::Text(text) = message else {
+                continue;
+            };
+            let frame: Value = serde_json::from_str(text.as_str()).unwrap();
+            if frame["event"] == event {
+                return frame["data"].clone();
+            }
+        }
+    };
+    tokio::time::timeout(std::time::Duration::from_secs(30), wait)
+        .await
+        .unwrap_or_else(|_| panic!("no `{event}` frame within 30s"))
+}
+
+/// A session started over the socket answers its `input()` pauses on the
+/// same connection: each answer is resolved in-process by the supervisor
+/// (no `/resume` round-trip), and the stream runs to `done`.
+#[tokio::test]
+async fn ws_session_answers_input_pauses_in_process() {
+    let temp_dir = std::env::temp_dir().join(format!("chidori-ws-input-{}", uuid::Uuid::new_v4()));
+    let agent_path = write_agent(&temp_dir, TWO_INPUT_AGENT);
+    let state = signal_test_state(&temp_dir, agent_path);
+
+    let mut socket = connect_ws(&state, "ws-1").await;
+    ws_send(&mut socket, json!({"type": "start", "input": {}})).await;
+
+    let paused = ws_next(&mut socket, "paused").await;
+    assert_eq!(paused["pending_prompt"], json!("first?"));
+    ws_send(&mut socket, json!({"type": "input", "response": "one"})).await;
+    let reply = ws_next(&mut socket, "reply").await;
+    assert_eq!(reply["status"], json!(202), "reply: {reply}");
+
+    let paused = ws_next(&mut socket, "paused").await;
+    assert_eq!(paused["pending_prompt"], json!("second?"));
+    // The supervisor keeps the session live across the pause.
+    assert!(state.active_sessions.lock().unwrap().contains_key("ws-1"));
+    ws_send(&mut socket, json!({"type": "input", "response": "two"})).await;
+
+    let done = ws_next(&mut socket, "done").await;
+    assert_eq!(done["status"], json!("completed"), "done: {done}");
+    assert_eq!(done["output"], json!({"a": "one", "b": "two"}));
+    let stored = state.session_store.get("ws-1").unwrap().unwrap();
+    assert_eq!(stored.status, SessionStatus::Completed);
+    assert!(!state.active_sessions.lock().unwrap().contains_key("ws-1"));
+
+    let _ = std::fs::remove_dir_all(temp_dir);
+}
+
+/// Connecting to a session already paused through the HTTP API adopts the
+/// pause: the socket announces it and answers it in-process, while signals
+/// and cancellation ride the same connection to their endpoints.
+#[tokio::test]
+async fn ws_adopts_a_stored_pause_and_routes_signals_and_cancel() {
+    let temp_dir = std::env::temp_dir().join(format!("chidori-ws-adopt-{}", uuid::Uuid::new_v4()));
+    let agent_path = write_agent(
+        &temp_dir,
+        r#"
+            export async function agent(input, chidori) {
+                const name = await chidori.input("name?");
+                const review = await chidori.signal("review");
+                await chidori.signal("never");
+                return { name, decision: review.payload.decision };
+            }
+        "#,
+    );
+    let state = signal_test_state(&temp_dir, agent_path);
+    let created = create_paused_session(&state, "ws-2", json!({})).await;
+    assert_eq!(created["status"], json!("paused"));
+
+    let mut socket = connect_ws(&state, "ws-2").await;
+    let paused = ws_next(&mut socket, "paused").await;
+    assert_eq!(paused["pending_prompt"], json!("name?"));
+    ws_send(&mut socket, json!({"type": "input", "response": "mara"})).await;
+
+    let paused = ws_next(&mut socket, "paused").await;
+    assert_eq!(paused["pending_signal_names"], json!(["review"]));
+    ws_send(
+        &mut socket,
+        json!({"type": "signal", "name": "review", "payload": {"decision": "approve"}}),
+    )
+    .await;
+    let call = ws_next(&mut socket, "call").await;
+    assert_eq!(call["function"], json!("signal"), "call: {call}");
+
+    let paused = ws_next(&mut socket, "paused").await;
+    assert_eq!(paused["pending_signal_names"], json!(["never"]));
+    ws_send(&mut socket, json!({"type": "cancel", "reason": "enough"})).await;
+    let done = ws_next(&mut socket, "done").await;
+    assert_eq!(done["status"], json!("cancelled"), "done: {done}");
+    let stored = state.session_store.get("ws-2").unwrap().unwrap();
+    assert_eq!(stored.status, SessionStatus::Cancelled);
+
+    let _ = std::fs::remove_dir_all(temp_dir);
+}
+
+/// Take the state's one run slot, waiting out a run that is still letting go
+/// of it.
+async fn take_free_slot(state: &AppState) -> hardening::RunSlot {
+    for _ in 0..100 {
+        if let Ok(slot) = hardening::acquire_run_slot(state).await {
+            return slot;
+        }
+    }
+    panic!("the run slot never came free");
+}
+
+/// An adopted pause idles without a run slot: adopting succeeds with every
+/// slot taken, an answer that finds none free is refused, and the slot goes
+/// back once the resumed run pauses again.
+#[tokio::test]
+async fn ws_adopted_pause_takes_a_run_slot_only_to_resume() {
+    let temp_dir = std::env::temp_dir().join(format!("chidori-ws-slot-{}", uuid::Uuid::new_v4()));
+    let agent_path = write_agent(&temp_dir, TWO_INPUT_AGENT);
+    let mut state = signal_test_state(&temp_dir, agent_path);
+    state.run_semaphore = Arc::new(Semaphore::new(1));
+    state.acquire_timeout = std::time::Duration::from_millis(200);
+    let created = create_paused_session(&state, "ws-3", json!({})).await;
+    assert_eq!(created["status"], json!("paused"));
+
+    let mut socket = connect_ws(&state, "ws-3").await;
+    let paused = ws_next(&mut socket, "paused").await;
+    assert_eq!(paused["pending_prompt"], json!("first?"));
+    let slot = take_free_slot(&state).await;
+    ws_send(&mut socket, json!({"type": "input", "response": "one"})).await;
+    let reply = ws_next(&mut socket, "reply").await;
+    assert_eq!(reply["status"], json!(409), "reply: {reply}");
+    let error = reply["body"]["error"].as_str().unwrap();
+    assert!(error.contains("no run slot"), "reply: {reply}");
+
+    drop(slot);
+    ws_send(&mut socket, json!({"type": "input", "response": "one"})).await;
+    let reply = ws_next(&mut socket, "reply").await;
+    assert_eq!(reply["status"], json!(202), "reply: {reply}");
+    let paused = ws_next(&mut socket, "paused").await;
+    assert_eq!(paused["pending_prompt"], json!("second?"));
+    // Idle again: the slot is free for other runs.
+    drop(take_free_slot(&state).await);
+
+    ws_send(&mut socket, json!({"type": "input", "response": "two"})).await;
+    let done = ws_next(&mut socket, "done").await;
+    assert_eq!(
+        done["output"],
+        json!({"a": "one", "b": "two"}),
+        "done: {done}"
+    );
+
+    let _ = std::fs::remove_dir_all(temp_dir);
+}
+
 // -----------------------------------------------------------------------
 // Static effect preflight (server/preflight.rs): agents declare no effects,
 // so the scan greps the source for gated-surface spellings and flags only
@@ -3222,3 +3978,212 @@ fn preflight_flags_only_unconditionally_denied_targets() {
     assert_eq!(denied.len(), 1, "denied: {denied:?}");
     assert_eq!(denied[0].0.target, "http");
 }
+
+#[test]
+fn preflight_treats_rate_and_time_rules_as_conditional_and_reports_them() {
+    // An unconditional-looking deny with a rate limit only throttles, so it
+    // must not be flagged as an outright denial — but it is reported.
+    let throttled: PolicyConfig = serde_json::from_value(json!({
+        "rules": [{
+            "target": "http",
+            "decision": "never_allow",
+            "rate_limit": { "max_calls": 20, "per_seconds": 60 },
+            "reason": "egress cap"
+        }, {
+            "target": "workspace:write",
+            "decision": "never_allow",
+            "time_window": { "start": "18:00", "end": "08:00" }
+        }],
+        "default": "always_allow"
+    }))
+    .unwrap();
+    assert!(preflight::denied_static_effects(HTTP_AGENT, &throttled).is_empty());
+    assert_eq!(
+        preflight::conditional_guardrails(HTTP_AGENT, &throttled),
+        ["`http`: never_allow for more than 20 calls per 60s (egress cap)"]
+    );
+    assert!(preflight::conditional_guardrails(
+        r#"await chidori.workspace.write("a.txt", "x");"#,
+        &throttled
+    )[0]
+    .contains("between 18:00 and 08:00 UTC"));
+}
+
+/// `base` as tenant `id`'s state over `shared`, built like
+/// `tenants::tenant_state` minus the env-held keys and manifest.
+fn test_tenant_state(
+    base: &AppState,
+    shared: Arc<dyn SessionStore>,
+    id: &str,
+    run_slots: Option<usize>,
+    quota: Option<crate::runtime::cost::CostQuota>,
+) -> AppState {
+    let root = base.run_base.join("tenants").join(id);
+    AppState {
+        run_base: root.join(".chidori").join("runs"),
+        session_store: Arc::new(crate::storage::TenantScopedStore::new(
+            shared,
+            Some(id.to_string()),
+        )),
+        active_sessions: Arc::new(StdMutex::new(HashMap::new())),
+        tenant: Some(Arc::new(tenants::Tenant {
+            id: id.to_string(),
+            keys: format!("{id}-key"),
+            root,
+            run_slots: run_slots.map(|n| Arc::new(Semaphore::new(n))),
+            quota,
+        })),
+        ..base.clone()
+    }
+}
+
+#[tokio::test]
+async fn tenant_keys_reach_only_their_own_sessions() {
+    use tower::ServiceExt as _;
+    let run_base = test_run_base("tenant_keys_reach_only_their_own");
+    let shared: Arc<dyn SessionStore> = Arc::new(crate::storage::MemoryStore::new());
+    let mut operator = test_state(run_base.clone(), run_base.join("agent.ts"));
+    operator.session_store = Arc::new(crate::storage::TenantScopedStore::new(shared.clone(), None));
+    let acme = test_tenant_state(&operator, shared.clone(), "acme", None, None);
+    let globex = test_tenant_state(&operator, shared.clone(), "globex", None, None);
+    operator
+        .session_store
+        .put(&listed_session("ops", "completed", "2026-05-17T09:01:00Z"))
+        .unwrap();
+    acme.session_store
+        .put(&listed_session(
+            "acme-1",
+            "completed",
+            "2026-05-17T09:02:00Z",
+        ))
+        .unwrap();
+    globex
+        .session_store
+        .put(&listed_session(
+            "globex-1",
+            "paused",
+            "2026-05-17T09:03:00Z",
+        ))
+        .unwrap();
+    // One id per store: another tenant cannot take (or overwrite) it.
+    assert!(acme
+        .session_store
+        .put(&listed_session(
+            "globex-1",
+            "completed",
+            "2026-05-17T09:04:00Z"
+        ))
+        .is_err());
+
+    let routes = [&acme, &globex]
+        .into_iter()
+        .map(|state| tenants::TenantRoute {
+            tenant: state.tenant.clone().unwrap(),
+            router: api_routes(state.clone()),
+        })
+        .collect::<Vec<_>>();
+    let tenant_layer = middleware::from_fn_with_state(Arc::new(routes), tenants::tenant_middleware);
+    let app = Router::new().fallback_service(tower::Layer::layer(
+        &tenant_layer,
+        api_routes(operator.clone()),
+    ));
+    let call = |path: &str, key: Option<&str>| {
+        let mut request = axum::http::Request::get(path);
+        if let Some(key) = key {
+            request = request.header("authorization", format!("Bearer {key}"));
+        }
+        let request = request.body(axum::body::Body::empty()).unwrap();
+        let app = app.clone();
+        async move { response_json(app.oneshot(request).await.unwrap()).await }
+    };
+
+    let (status, page) = call("/sessions", Some("acme-key")).await;
+    assert_eq!(status, StatusCode::OK);
+    let ids: Vec<&str> = page["sessions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, ["acme-1"]);
+    let (status, session) = call("/sessions/acme-1", Some("acme-key")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(session["tenant"], "acme");
+    let (status, _) = call("/sessions/globex-1", Some("acme-key")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = call("/sessions/globex-1", Some("globex-key")).await;
+    assert_eq!(status, StatusCode::OK);
+    // Operator-only endpoints refuse a tenant's key.
+    let (status, _) = call("/metrics", Some("acme-key")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    // With tenants declared, no key (and no CHIDORI_API_KEY) is a 401 —
+    // never the operator's API.
+    let (status, _) = call("/sessions", None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = call("/sessions", Some("wrong-key")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // The operator's own view leaves every tenant's sessions out.
+    let ids: Vec<String> = operator
+        .session_store
+        .list()
+        .unwrap()
+        .into_iter()
+        .map(|s| s.id)
+        .collect();
+    assert_eq!(ids, ["ops"]);
+    let _ = std::fs::remove_dir_all(run_base);
+}
+
+#[tokio::test]
+async fn tenant_run_cap_and_cost_quota_answer_429() {
+    let run_base = test_run_base("tenant_run_cap_and_cost_quota");
+    let shared: Arc<dyn SessionStore> = Arc::new(crate::storage::MemoryStore::new());
+    let mut operator = test_state(run_base.clone(), run_base.join("agent.ts"));
+    operator.run_semaphore = Arc::new(Semaphore::new(4));
+    let quota = crate::runtime::cost::CostQuota {
+        max_cost_usd: 20.0,
+        window: None,
+    };
+    let acme = test_tenant_state(&operator, shared.clone(), "acme", Some(1), Some(quota));
+
+    // The tenant's one slot is taken: 429, though the server has room.
+    let slot = hardening::acquire_run_slot(&acme).await.unwrap();
+    let refused = hardening::acquire_run_slot(&acme).await.unwrap_err();
+    assert_eq!(refused.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(hardening::acquire_run_slot(&operator).await.is_ok());
+    drop(slot);
+    assert!(hardening::acquire_run_slot(&acme).await.is_ok());
+
+    // $18 spent (1M in + 1M out on claude-sonnet-4-6): the next run may
+    // spend what is left.
+    let mut spent = listed_session("acme-1", "completed", "2026-05-17T09:01:00Z");
+    spent.call_log = vec![serde_json::from_value(json!({
+        "seq": 0, "function": "prompt", "args": {"model": "claude-sonnet-4-6"},
+        "result": "ok", "duration_ms": 1, "timestamp": "2026-05-17T09:01:00Z",
+        "token_usage": {"input_tokens": 1_000_000, "output_tokens": 1_000_000},
+    }))
+    .unwrap()];
+    acme.session_store.put(&spent).unwrap();
+    let budget = tenants::run_budget(&acme, None).await.unwrap().unwrap();
+    assert!((budget.max_cost_usd - 2.0).abs() < 1e-9);
+    // Another tenant's, or the operator's, spend is not acme's.
+    assert_eq!(tenants::run_budget(&operator, None).await.unwrap(), None);
+
+    spent.id = "acme-2".to_string();
+    acme.session_store.put(&spent).unwrap();
+    let refused = tenants::run_budget(&acme, None).await.unwrap_err();
+    assert_eq!(refused.status(), StatusCode::TOO_MANY_REQUESTS);
+    let request: CreateSessionRequest = serde_json::from_value(json!({"input": {}})).unwrap();
+    let (status, body) =
+        response_json(create_session(State(acme.clone()), Json(request)).await).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert!(body["error"].as_str().unwrap().contains("cost quota"));
+
+    // Deleting the sessions does not refund what they spent.
+    acme.session_store.delete("acme-1").unwrap();
+    acme.session_store.delete("acme-2").unwrap();
+    let refused = tenants::run_budget(&acme, None).await.unwrap_err();
+    assert_eq!(refused.status(), StatusCode::TOO_MANY_REQUESTS);
+    let _ = std::fs::remove_dir_all(run_base);
+}
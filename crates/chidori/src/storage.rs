@@ -1,8 +1,10 @@
 //! Session and memory persistence.
 //!
-//! Provides two backends:
+//! Provides three backends:
 //!   * SQLite (default: `.chidori/sessions.sqlite3` next to the agent's
 //!     `.chidori/runs/`; override the path with CHIDORI_DB_PATH)
+//!   * Postgres (CHIDORI_DB_PATH=postgres://…), shared by several servers
+//!     behind a load balancer
 //!   * in-memory (opt-in via CHIDORI_DB_PATH=:memory:, for dev loops that
 //!     should leave no state behind)
 //!
@@ -12,7 +14,7 @@
 //! everything.
 
 use std::path::PathBuf;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
@@ -20,6 +22,7 @@ use serde_json::Value;
 
 use crate::runtime::call_log::CallRecord;
 use crate::runtime::context::PendingApproval;
+use crate::runtime::store_pg::PgClient;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
@@ -85,27 +88,385 @@ pub struct StoredSession {
     /// replays — it can tighten the server policy but never relax it.
     #[serde(default)]
     pub policy_profile: Option<String>,
+    /// The agent file the session ran, as the server resolved it. Unset for
+    /// sessions stored before the field existed and for ACP threads.
+    #[serde(default)]
+    pub agent: Option<String>,
+    /// The recipe that launched this session (scheduled or run manually).
+    #[serde(default)]
+    pub recipe: Option<String>,
+    /// The cron tick this session ran for; unset for manual recipe runs. The
+    /// latest one per recipe is where the scheduler's catch-up resumes.
+    #[serde(default)]
+    pub scheduled_for: Option<chrono::DateTime<chrono::Utc>>,
+    /// The tenant whose API key owns the session (`tenants:` in the app
+    /// manifest). Stamped by [`TenantScopedStore`]; unset outside tenants.
+    #[serde(default)]
+    pub tenant: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Filters and a page window for [`SessionStore::query`]. Every filter is
+/// optional; the default query is the newest [`SessionQuery::DEFAULT_LIMIT`]
+/// sessions of any kind.
+#[derive(Debug, Clone)]
+pub struct SessionQuery {
+    /// Any of these statuses; empty means any status.
+    pub status: Vec<SessionStatus>,
+    /// Exactly this agent path (`StoredSession::agent`).
+    pub agent: Option<String>,
+    pub recipe: Option<String>,
+    /// Paused on a signal listen point awaiting this name.
+    pub signal: Option<String>,
+    /// Created at or after this instant.
+    pub created_after: Option<chrono::DateTime<chrono::Utc>>,
+    /// Created strictly before this instant.
+    pub created_before: Option<chrono::DateTime<chrono::Utc>>,
+    pub tenant: TenantFilter,
+    /// Continue after this position: the previous page's `next_cursor`.
+    pub cursor: Option<SessionCursor>,
+    pub limit: usize,
+    /// Return sessions with an empty `call_log` — list views never show it,
+    /// and it is most of a long session's bytes.
+    pub summary: bool,
+}
+
+impl SessionQuery {
+    pub const DEFAULT_LIMIT: usize = 50;
+    pub const MAX_LIMIT: usize = 500;
+
+    /// Whether `session` passes every filter and lies after the cursor.
+    pub fn matches(&self, session: &StoredSession) -> bool {
+        (self.status.is_empty() || self.status.contains(&session.status))
+            && self
+                .agent
+                .as_ref()
+                .is_none_or(|agent| session.agent.as_ref() == Some(agent))
+            && self
+                .recipe
+                .as_ref()
+                .is_none_or(|recipe| session.recipe.as_ref() == Some(recipe))
+            && self.signal.as_ref().is_none_or(|signal| {
+                session.pending_signal_name.as_ref() == Some(signal)
+                    || session.pending_signal_names.contains(signal)
+            })
+            && self
+                .created_after
+                .is_none_or(|after| session.created_at >= after)
+            && self
+                .created_before
+                .is_none_or(|before| session.created_at < before)
+            && self.tenant.admits(session.tenant.as_deref())
+            && self.cursor.as_ref().is_none_or(|cursor| {
+                (session.created_at, session.id.as_str()) < (cursor.created_at, cursor.id.as_str())
+            })
+    }
+}
+
+impl Default for SessionQuery {
+    fn default() -> Self {
+        Self {
+            status: Vec::new(),
+            agent: None,
+            recipe: None,
+            signal: None,
+            created_after: None,
+            created_before: None,
+            tenant: TenantFilter::Any,
+            cursor: None,
+            limit: Self::DEFAULT_LIMIT,
+            summary: false,
+        }
+    }
+}
+
+/// Which tenant's sessions a [`SessionQuery`] covers.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TenantFilter {
+    Any,
+    /// Only sessions outside every tenant.
+    Untenanted,
+    Tenant(String),
+}
+
+impl TenantFilter {
+    fn of(tenant: Option<&str>) -> Self {
+        match tenant {
+            Some(tenant) => TenantFilter::Tenant(tenant.to_string()),
+            None => TenantFilter::Untenanted,
+        }
+    }
+
+    fn admits(&self, tenant: Option<&str>) -> bool {
+        match self {
+            TenantFilter::Any => true,
+            TenantFilter::Untenanted => tenant.is_none(),
+            TenantFilter::Tenant(id) => tenant == Some(id.as_str()),
+        }
+    }
+}
+
+/// A position in the newest-first session order: the last session of a
+/// page. Ties on `created_at` break on `id`, so paging never skips or
+/// repeats a session.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionCursor {
     pub created_at: chrono::DateTime<chrono::Utc>,
+    pub id: String,
+}
+
+impl SessionCursor {
+    fn after(session: &StoredSession) -> Self {
+        Self {
+            created_at: session.created_at,
+            id: session.id.clone(),
+        }
+    }
+
+    /// The opaque token handed to API clients.
+    pub fn encode(&self) -> String {
+        use base64::Engine as _;
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(format!(
+            "{}|{}",
+            self.created_at.to_rfc3339(),
+            self.id
+        ))
+    }
+
+    pub fn decode(token: &str) -> Result<Self> {
+        use base64::Engine as _;
+        let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(token)
+            .context("cursor is not valid base64")?;
+        let text = String::from_utf8(bytes).context("cursor is not UTF-8")?;
+        let (created_at, id) = text.split_once('|').context("malformed cursor")?;
+        Ok(Self {
+            created_at: chrono::DateTime::parse_from_rfc3339(created_at)
+                .context("malformed cursor")?
+                .with_timezone(&chrono::Utc),
+            id: id.to_string(),
+        })
+    }
+}
+
+/// One page of [`SessionStore::query`] results, newest first.
+#[derive(Debug, Clone)]
+pub struct SessionPage {
+    pub sessions: Vec<StoredSession>,
+    /// Where the next page starts; `None` on the last page.
+    pub next_cursor: Option<SessionCursor>,
+}
+
+impl SessionPage {
+    /// Cut a newest-first result holding up to `limit + 1` sessions down to
+    /// one page, using the extra row only to tell whether another page exists.
+    fn from_rows(mut sessions: Vec<StoredSession>, limit: usize) -> Self {
+        let next_cursor = if sessions.len() > limit {
+            sessions.truncate(limit);
+            sessions.last().map(SessionCursor::after)
+        } else {
+            None
+        };
+        Self {
+            sessions,
+            next_cursor,
+        }
+    }
 }
 
 pub trait SessionStore: Send + Sync {
     fn put(&self, session: &StoredSession) -> Result<()>;
     fn get(&self, id: &str) -> Result<Option<StoredSession>>;
     fn list(&self) -> Result<Vec<StoredSession>>;
-    #[allow(dead_code)] // Exposed for cleanup tools; no current caller.
     fn delete(&self, id: &str) -> Result<()>;
+
+    /// Move session `id` from status `from` to `to`, only if it is still
+    /// `from`. `Ok(false)` means it was not — another request moved it first,
+    /// or it never was — and is a normal outcome. Resume and approve claim a
+    /// pause with this before replaying, so two requests answering the same
+    /// pause cannot both run it.
+    ///
+    /// The default is a read-compare-write and is **not** atomic; every
+    /// store in this file overrides it.
+    fn transition(&self, id: &str, from: &SessionStatus, to: &SessionStatus) -> Result<bool> {
+        match self.get(id)? {
+            Some(mut session) if &session.status == from => {
+                session.status = to.clone();
+                self.put(&session)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Delete session `id` only if it still holds `status`. `Ok(false)`
+    /// means it did not — a resume moved it on, here or on another replica —
+    /// and the row is left alone.
+    ///
+    /// The default is a read-compare-delete and is **not** atomic; every
+    /// store in this file overrides it.
+    fn delete_if(&self, id: &str, status: &SessionStatus) -> Result<bool> {
+        match self.get(id)? {
+            Some(session) if &session.status == status => {
+                self.delete(id)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// One page of the sessions matching `query`, newest first. The default
+    /// filters [`SessionStore::list`] in memory.
+    fn query(&self, query: &SessionQuery) -> Result<SessionPage> {
+        let mut sessions: Vec<StoredSession> = self
+            .list()?
+            .into_iter()
+            .filter(|s| query.matches(s))
+            .collect();
+        sessions.sort_by(|a, b| (b.created_at, &b.id).cmp(&(a.created_at, &a.id)));
+        sessions.truncate(query.limit + 1);
+        if query.summary {
+            for session in &mut sessions {
+                session.call_log.clear();
+            }
+        }
+        Ok(SessionPage::from_rows(sessions, query.limit))
+    }
+
+    /// The session for `recipe`'s most recent scheduled tick, if any.
+    fn latest_scheduled(&self, recipe: &str) -> Result<Option<StoredSession>> {
+        Ok(self
+            .list()?
+            .into_iter()
+            .filter(|s| s.recipe.as_deref() == Some(recipe) && s.scheduled_for.is_some())
+            .max_by_key(|s| s.scheduled_for))
+    }
+
+    /// How many sessions hold each status, for `GET /metrics`. Statuses no
+    /// session holds are left out. The default counts
+    /// [`SessionStore::list`]; the SQL stores, whose `list` is capped, count
+    /// the whole table instead.
+    fn count_by_status(&self) -> Result<Vec<(SessionStatus, u64)>> {
+        let mut counts: Vec<(SessionStatus, u64)> = Vec::new();
+        for session in self.list()? {
+            match counts.iter_mut().find(|(s, _)| *s == session.status) {
+                Some((_, n)) => *n += 1,
+                None => counts.push((session.status, 1)),
+            }
+        }
+        Ok(counts)
+    }
+
+    /// Write `session` unless its id belongs to a tenant other than
+    /// `session.tenant`. `Ok(false)` means the id is taken. A
+    /// [`TenantScopedStore`] writes through this, so two tenants creating the
+    /// same id cannot both win it.
+    ///
+    /// The default is a read-compare-write and is **not** atomic; every
+    /// store in this file overrides it.
+    fn put_owned(&self, session: &StoredSession) -> Result<bool> {
+        match self.get(&session.id)? {
+            Some(existing) if existing.tenant != session.tenant => Ok(false),
+            _ => {
+                self.put(session)?;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Estimated USD spent by the model calls of `tenant`'s sessions created
+    /// at or after `since` (all of them when `None`). The stores in this file
+    /// answer from a spend ledger every write keeps, one row per session that
+    /// outlives the session, so deleting a session does not refund its
+    /// spend. The default sums the sessions still stored.
+    fn spend_since(
+        &self,
+        tenant: &TenantFilter,
+        since: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<f64> {
+        let mut query = SessionQuery {
+            created_after: since,
+            tenant: tenant.clone(),
+            limit: SessionQuery::MAX_LIMIT,
+            ..Default::default()
+        };
+        let mut spent = 0.0;
+        loop {
+            let page = self.query(&query)?;
+            spent += page.sessions.iter().map(session_spend).sum::<f64>();
+            match page.next_cursor {
+                Some(cursor) => query.cursor = Some(cursor),
+                None => return Ok(spent),
+            }
+        }
+    }
+}
+
+/// Estimated USD the model calls in `session`'s call log cost.
+fn session_spend(session: &StoredSession) -> f64 {
+    let mut log = crate::runtime::call_log::CallLog::new();
+    for record in &session.call_log {
+        log.push(record.clone());
+    }
+    log.total_cost_usd()
+}
+
+/// Enter every stored session's spend through `record`, for a spend ledger
+/// created over an existing session table.
+fn backfill_spend(
+    store: &dyn SessionStore,
+    record: impl Fn(&StoredSession, f64) -> Result<()>,
+) -> Result<()> {
+    let mut query = SessionQuery {
+        limit: SessionQuery::MAX_LIMIT,
+        ..Default::default()
+    };
+    loop {
+        let page = store.query(&query)?;
+        for session in &page.sessions {
+            let usd = session_spend(session);
+            if usd > 0.0 {
+                record(session, usd)?;
+            }
+        }
+        match page.next_cursor {
+            Some(cursor) => query.cursor = Some(cursor),
+            None => return Ok(()),
+        }
+    }
+}
+
+/// One session's row in the in-memory spend ledger.
+struct SpendEntry {
+    tenant: Option<String>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    usd: f64,
 }
 
 /// In-memory store. Opt-in via `CHIDORI_DB_PATH=:memory:`, for dev loops that
 /// should leave no state behind.
 pub struct MemoryStore {
     inner: Mutex<std::collections::HashMap<String, StoredSession>>,
+    spend: Mutex<std::collections::HashMap<String, SpendEntry>>,
 }
 
 impl MemoryStore {
     pub fn new() -> Self {
         Self {
             inner: Mutex::new(std::collections::HashMap::new()),
+            spend: Mutex::new(std::collections::HashMap::new()),
+        }
+    }
+
+    fn record_spend(&self, s: &StoredSession) {
+        let usd = session_spend(s);
+        if usd > 0.0 {
+            let entry = SpendEntry {
+                tenant: s.tenant.clone(),
+                created_at: s.created_at,
+                usd,
+            };
+            self.spend.lock().unwrap().insert(s.id.clone(), entry);
         }
     }
 }
@@ -119,6 +480,7 @@ impl Default for MemoryStore {
 impl SessionStore for MemoryStore {
     fn put(&self, s: &StoredSession) -> Result<()> {
         self.inner.lock().unwrap().insert(s.id.clone(), s.clone());
+        self.record_spend(s);
         Ok(())
     }
     fn get(&self, id: &str) -> Result<Option<StoredSession>> {
@@ -131,12 +493,57 @@ impl SessionStore for MemoryStore {
         self.inner.lock().unwrap().remove(id);
         Ok(())
     }
+    fn transition(&self, id: &str, from: &SessionStatus, to: &SessionStatus) -> Result<bool> {
+        match self.inner.lock().unwrap().get_mut(id) {
+            Some(session) if &session.status == from => {
+                session.status = to.clone();
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+    fn delete_if(&self, id: &str, status: &SessionStatus) -> Result<bool> {
+        let mut sessions = self.inner.lock().unwrap();
+        if sessions.get(id).is_some_and(|s| &s.status == status) {
+            sessions.remove(id);
+            return Ok(true);
+        }
+        Ok(false)
+    }
+    fn put_owned(&self, s: &StoredSession) -> Result<bool> {
+        {
+            let mut sessions = self.inner.lock().unwrap();
+            if sessions.get(&s.id).is_some_and(|e| e.tenant != s.tenant) {
+                return Ok(false);
+            }
+            sessions.insert(s.id.clone(), s.clone());
+        }
+        self.record_spend(s);
+        Ok(true)
+    }
+    fn spend_since(
+        &self,
+        tenant: &TenantFilter,
+        since: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<f64> {
+        Ok(self
+            .spend
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|e| tenant.admits(e.tenant.as_deref()))
+            .filter(|e| since.is_none_or(|since| e.created_at >= since))
+            .map(|e| e.usd)
+            .sum())
+    }
 }
 
 /// SQLite-backed store. One table, sessions are stored as a single JSON blob
-/// per row. This is a deliberate shortcut: we don't need query-over-fields
-/// yet, and a blob column is the cheapest thing that durably persists across
-/// restarts.
+/// per row. This is a deliberate shortcut: a blob column is the cheapest thing
+/// that durably persists across restarts, and the few fields `query` filters
+/// on are read back out with `json_extract`. A second table, `session_spend`,
+/// is the spend ledger: one row per session with model spend, written with
+/// the session and kept when it is deleted.
 pub struct SqliteStore {
     #[allow(dead_code)] // Retained so `path()` can surface it to tracing / debug.
     path: PathBuf,
@@ -158,6 +565,12 @@ impl SqliteStore {
         // In WAL a put is one log append and readers never block on the writer.
         conn.pragma_update(None, "journal_mode", "WAL").ok();
         conn.pragma_update(None, "synchronous", "NORMAL").ok();
+        let ledger_exists: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master
+                            WHERE type = 'table' AND name = 'session_spend')",
+            [],
+            |row| row.get(0),
+        )?;
         conn.execute_batch(
             "CREATE TABLE IF NOT EXISTS sessions (
                  id TEXT PRIMARY KEY,
@@ -166,32 +579,74 @@ impl SqliteStore {
                  data TEXT NOT NULL
              );
              CREATE INDEX IF NOT EXISTS idx_sessions_created_at
-                 ON sessions(created_at DESC);",
+                 ON sessions(created_at DESC);
+             CREATE TABLE IF NOT EXISTS session_spend (
+                 session_id TEXT PRIMARY KEY,
+                 tenant TEXT,
+                 created_at TEXT NOT NULL,
+                 usd REAL NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS idx_session_spend_created_at
+                 ON session_spend(created_at);",
         )?;
-        Ok(Self {
+        let store = Self {
             path,
             conn: Mutex::new(conn),
-        })
+        };
+        if !ledger_exists {
+            backfill_spend(&store, |s, usd| {
+                Self::record_spend(&store.conn.lock().unwrap(), s, usd)
+            })?;
+        }
+        Ok(store)
     }
 
-    #[allow(dead_code)]
-    pub fn path(&self) -> &PathBuf {
-        &self.path
+    fn record_spend(conn: &rusqlite::Connection, s: &StoredSession, usd: f64) -> Result<()> {
+        conn.execute(
+            "INSERT INTO session_spend (session_id, tenant, created_at, usd)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(session_id) DO UPDATE SET usd = excluded.usd",
+            rusqlite::params![s.id, s.tenant, s.created_at.to_rfc3339(), usd],
+        )?;
+        Ok(())
     }
-}
 
-impl SessionStore for SqliteStore {
-    fn put(&self, s: &StoredSession) -> Result<()> {
+    /// Upsert `s` and its spend in one transaction. With `check_owner`, an
+    /// existing row owned by another tenant is left alone and the write
+    /// reports `false`.
+    fn write(&self, s: &StoredSession, check_owner: bool) -> Result<bool> {
         let data = serde_json::to_string(s)?;
         let status = serde_json::to_string(&s.status).unwrap_or_else(|_| "\"running\"".into());
         let created_at = s.created_at.to_rfc3339();
-        let conn = self.conn.lock().unwrap();
-        conn.execute(
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let written = tx.execute(
             "INSERT INTO sessions (id, created_at, status, data)
              VALUES (?1, ?2, ?3, ?4)
-             ON CONFLICT(id) DO UPDATE SET status = excluded.status, data = excluded.data",
-            rusqlite::params![s.id, created_at, status, data],
+             ON CONFLICT(id) DO UPDATE SET status = excluded.status, data = excluded.data
+             WHERE NOT ?6 OR json_extract(sessions.data, '$.tenant') IS ?5",
+            rusqlite::params![s.id, created_at, status, data, s.tenant, check_owner],
         )?;
+        if written == 0 {
+            return Ok(false);
+        }
+        let usd = session_spend(s);
+        if usd > 0.0 {
+            Self::record_spend(&tx, s, usd)?;
+        }
+        tx.commit()?;
+        Ok(true)
+    }
+
+    #[allow(dead_code)]
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+}
+
+impl SessionStore for SqliteStore {
+    fn put(&self, s: &StoredSession) -> Result<()> {
+        self.write(s, false)?;
         Ok(())
     }
 
@@ -230,12 +685,608 @@ impl SessionStore for SqliteStore {
             .execute("DELETE FROM sessions WHERE id = ?1", rusqlite::params![id])?;
         Ok(())
     }
+
+    // One conditional UPDATE: SQLite serializes writers, so the status check
+    // and the write cannot interleave with another process's.
+    fn transition(&self, id: &str, from: &SessionStatus, to: &SessionStatus) -> Result<bool> {
+        let to_value = serde_json::to_value(to)?;
+        let changed = self.conn.lock().unwrap().execute(
+            "UPDATE sessions SET status = ?3, data = json_set(data, '$.status', ?4)
+             WHERE id = ?1 AND status = ?2",
+            rusqlite::params![
+                id,
+                serde_json::to_string(from)?,
+                serde_json::to_string(to)?,
+                to_value.as_str().unwrap_or_default(),
+            ],
+        )?;
+        Ok(changed == 1)
+    }
+
+    fn delete_if(&self, id: &str, status: &SessionStatus) -> Result<bool> {
+        let deleted = self.conn.lock().unwrap().execute(
+            "DELETE FROM sessions WHERE id = ?1 AND status = ?2",
+            rusqlite::params![id, serde_json::to_string(status)?],
+        )?;
+        Ok(deleted == 1)
+    }
+
+    // Filters and the cursor run in SQL, so a page costs one indexed range
+    // scan instead of deserializing every session (`list` also stops at the
+    // newest 200). Summary pages blank `call_log` before it leaves SQLite.
+    fn query(&self, query: &SessionQuery) -> Result<SessionPage> {
+        use rusqlite::types::Value as Param;
+        let mut sql = String::from(if query.summary {
+            "SELECT json_set(data, '$.call_log', json('[]')) FROM sessions WHERE 1 = 1"
+        } else {
+            "SELECT data FROM sessions WHERE 1 = 1"
+        });
+        let mut params: Vec<Param> = Vec::new();
+        if !query.status.is_empty() {
+            let mut marks = Vec::new();
+            for status in &query.status {
+                params.push(Param::Text(serde_json::to_string(status)?));
+                marks.push(format!("?{}", params.len()));
+            }
+            sql.push_str(&format!(" AND status IN ({})", marks.join(", ")));
+        }
+        if let Some(agent) = &query.agent {
+            params.push(Param::Text(agent.clone()));
+            sql.push_str(&format!(
+                " AND json_extract(data, '$.agent') = ?{}",
+                params.len()
+            ));
+        }
+        if let Some(recipe) = &query.recipe {
+            params.push(Param::Text(recipe.clone()));
+            sql.push_str(&format!(
+                " AND json_extract(data, '$.recipe') = ?{}",
+                params.len()
+            ));
+        }
+        if let Some(signal) = &query.signal {
+            params.push(Param::Text(signal.clone()));
+            let n = params.len();
+            sql.push_str(&format!(
+                " AND (json_extract(data, '$.pending_signal_name') = ?{n}
+                      OR EXISTS (SELECT 1 FROM json_each(data, '$.pending_signal_names')
+                                 WHERE value = ?{n}))"
+            ));
+        }
+        match &query.tenant {
+            TenantFilter::Any => {}
+            TenantFilter::Untenanted => sql.push_str(" AND json_extract(data, '$.tenant') IS NULL"),
+            TenantFilter::Tenant(tenant) => {
+                params.push(Param::Text(tenant.clone()));
+                sql.push_str(&format!(
+                    " AND json_extract(data, '$.tenant') = ?{}",
+                    params.len()
+                ));
+            }
+        }
+        // `created_at` holds `to_rfc3339()` of a UTC time, which sorts as text
+        // in time order, so bounds and the cursor compare in the same form.
+        if let Some(after) = query.created_after {
+            params.push(Param::Text(after.to_rfc3339()));
+            sql.push_str(&format!(" AND created_at >= ?{}", params.len()));
+        }
+        if let Some(before) = query.created_before {
+            params.push(Param::Text(before.to_rfc3339()));
+            sql.push_str(&format!(" AND created_at < ?{}", params.len()));
+        }
+        if let Some(cursor) = &query.cursor {
+            params.push(Param::Text(cursor.created_at.to_rfc3339()));
+            params.push(Param::Text(cursor.id.clone()));
+            let (at, id) = (params.len() - 1, params.len());
+            sql.push_str(&format!(
+                " AND (created_at < ?{at} OR (created_at = ?{at} AND id < ?{id}))"
+            ));
+        }
+        params.push(Param::Integer(query.limit as i64 + 1));
+        sql.push_str(&format!(
+            " ORDER BY created_at DESC, id DESC LIMIT ?{}",
+            params.len()
+        ));
+
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(params), |row| {
+            row.get::<_, String>(0)
+        })?;
+        let mut sessions = Vec::new();
+        for row in rows {
+            if let Ok(s) = serde_json::from_str::<StoredSession>(&row?) {
+                sessions.push(s);
+            }
+        }
+        Ok(SessionPage::from_rows(sessions, query.limit))
+    }
+
+    // `list` stops at the newest 200 sessions, which a busy server's latest
+    // tick can fall outside of; query the blob directly instead.
+    fn latest_scheduled(&self, recipe: &str) -> Result<Option<StoredSession>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT data FROM sessions
+             WHERE json_extract(data, '$.recipe') = ?1
+               AND json_extract(data, '$.scheduled_for') IS NOT NULL
+             ORDER BY json_extract(data, '$.scheduled_for') DESC
+             LIMIT 1",
+        )?;
+        let mut rows = stmt.query(rusqlite::params![recipe])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(serde_json::from_str(&row.get::<_, String>(0)?)?)),
+            None => Ok(None),
+        }
+    }
+
+    // The conditional upsert checks the owner and writes in one statement,
+    // which SQLite serializes against every other writer.
+    fn put_owned(&self, s: &StoredSession) -> Result<bool> {
+        self.write(s, true)
+    }
+
+    fn spend_since(
+        &self,
+        tenant: &TenantFilter,
+        since: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<f64> {
+        use rusqlite::types::Value as Param;
+        let mut sql = String::from("SELECT COALESCE(SUM(usd), 0.0) FROM session_spend WHERE 1 = 1");
+        let mut params: Vec<Param> = Vec::new();
+        match tenant {
+            TenantFilter::Any => {}
+            TenantFilter::Untenanted => sql.push_str(" AND tenant IS NULL"),
+            TenantFilter::Tenant(tenant) => {
+                params.push(Param::Text(tenant.clone()));
+                sql.push_str(&format!(" AND tenant = ?{}", params.len()));
+            }
+        }
+        if let Some(since) = since {
+            params.push(Param::Text(since.to_rfc3339()));
+            sql.push_str(&format!(" AND created_at >= ?{}", params.len()));
+        }
+        let conn = self.conn.lock().unwrap();
+        Ok(conn.query_row(&sql, rusqlite::params_from_iter(params), |row| row.get(0))?)
+    }
+
+    fn count_by_status(&self) -> Result<Vec<(SessionStatus, u64)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT status, COUNT(*) FROM sessions GROUP BY status")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })?;
+        let mut out = Vec::new();
+        for row in rows {
+            let (status, n) = row?;
+            // The column holds the JSON-quoted status `put` wrote.
+            if let Ok(status) = serde_json::from_str(&status) {
+                out.push((status, n as u64));
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Postgres-backed store (`CHIDORI_DB_PATH=postgres://…`), for several servers
+/// sharing one session table. Same row shape as [`SqliteStore`], with `data`
+/// as `JSONB` so `query` filters on it in SQL, and the same `session_spend`
+/// ledger. Calls run on the connection's worker thread ([`PgClient`]).
+pub struct PostgresStore {
+    client: Arc<PgClient>,
+}
+
+type PgParam = Box<dyn postgres::types::ToSql + Send + Sync>;
+
+/// A status as the `status` column holds it: the bare serde name.
+fn status_name(status: &SessionStatus) -> Result<String> {
+    Ok(serde_json::to_value(status)?
+        .as_str()
+        .unwrap_or_default()
+        .to_string())
+}
+
+impl PostgresStore {
+    pub fn connect(url: &str) -> Result<Self> {
+        let client = PgClient::connect(url)?;
+        let ledger_exists = client.run(|client| {
+            let row = client.query_one("SELECT to_regclass('session_spend') IS NOT NULL", &[])?;
+            Ok(row.get::<_, bool>(0))
+        })?;
+        client.create_schema(
+            "CREATE TABLE IF NOT EXISTS sessions (
+                 id TEXT PRIMARY KEY,
+                 created_at TIMESTAMPTZ NOT NULL,
+                 status TEXT NOT NULL,
+                 data JSONB NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS idx_sessions_created_at
+                 ON sessions(created_at DESC, id DESC);
+             CREATE TABLE IF NOT EXISTS session_spend (
+                 session_id TEXT PRIMARY KEY,
+                 tenant TEXT,
+                 created_at TIMESTAMPTZ NOT NULL,
+                 usd DOUBLE PRECISION NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS idx_session_spend_created_at
+                 ON session_spend(created_at);",
+        )?;
+        let store = Self { client };
+        if !ledger_exists {
+            backfill_spend(&store, |s, usd| {
+                let (id, tenant, created_at) = (s.id.clone(), s.tenant.clone(), s.created_at);
+                store
+                    .client
+                    .run(move |client| Self::record_spend(client, &id, &tenant, &created_at, usd))
+            })?;
+        }
+        Ok(store)
+    }
+
+    fn record_spend(
+        client: &mut impl postgres::GenericClient,
+        id: &String,
+        tenant: &Option<String>,
+        created_at: &chrono::DateTime<chrono::Utc>,
+        usd: f64,
+    ) -> Result<()> {
+        client.execute(
+            "INSERT INTO session_spend (session_id, tenant, created_at, usd)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (session_id) DO UPDATE SET usd = excluded.usd",
+            &[id, tenant, created_at, &usd],
+        )?;
+        Ok(())
+    }
+
+    /// Upsert `s` and its spend in one transaction. With `check_owner`, an
+    /// existing row owned by another tenant is left alone and the write
+    /// reports `false`.
+    fn write(&self, s: &StoredSession, check_owner: bool) -> Result<bool> {
+        let (id, created_at, tenant) = (s.id.clone(), s.created_at, s.tenant.clone());
+        let status = status_name(&s.status)?;
+        let data = serde_json::to_value(s)?;
+        let usd = session_spend(s);
+        self.client.run(move |client| {
+            let mut tx = client.transaction()?;
+            let written = tx.execute(
+                "INSERT INTO sessions (id, created_at, status, data) VALUES ($1, $2, $3, $4)
+                 ON CONFLICT (id) DO UPDATE SET status = excluded.status, data = excluded.data
+                 WHERE NOT $6 OR sessions.data->>'tenant' IS NOT DISTINCT FROM $5",
+                &[&id, &created_at, &status, &data, &tenant, &check_owner],
+            )?;
+            if written == 0 {
+                return Ok(false);
+            }
+            if usd > 0.0 {
+                Self::record_spend(&mut tx, &id, &tenant, &created_at, usd)?;
+            }
+            tx.commit()?;
+            Ok(true)
+        })
+    }
+
+    fn select(&self, sql: String, params: Vec<PgParam>) -> Result<Vec<StoredSession>> {
+        let rows = self.client.run(move |client| {
+            let params: Vec<&(dyn postgres::types::ToSql + Sync)> =
+                params.iter().map(|p| p.as_ref() as _).collect();
+            Ok(client.query(&sql, &params)?)
+        })?;
+        let mut out = Vec::with_capacity(rows.len());
+        for row in rows {
+            if let Ok(s) = serde_json::from_value::<StoredSession>(row.get(0)) {
+                out.push(s);
+            }
+        }
+        Ok(out)
+    }
+}
+
+impl SessionStore for PostgresStore {
+    fn put(&self, s: &StoredSession) -> Result<()> {
+        self.write(s, false)?;
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> Result<Option<StoredSession>> {
+        let id = id.to_string();
+        let row = self.client.run(move |client| {
+            Ok(client.query_opt("SELECT data FROM sessions WHERE id = $1", &[&id])?)
+        })?;
+        match row {
+            Some(row) => Ok(Some(serde_json::from_value(row.get(0))?)),
+            None => Ok(None),
+        }
+    }
+
+    fn list(&self) -> Result<Vec<StoredSession>> {
+        self.select(
+            "SELECT data FROM sessions ORDER BY created_at DESC LIMIT 200".to_string(),
+            Vec::new(),
+        )
+    }
+
+    fn delete(&self, id: &str) -> Result<()> {
+        let id = id.to_string();
+        self.client.run(move |client| {
+            client.execute("DELETE FROM sessions WHERE id = $1", &[&id])?;
+            Ok(())
+        })
+    }
+
+    // `SELECT … FOR UPDATE` locks the row for the transaction, so a resume or
+    // approve racing on another server blocks here, then sees the new status
+    // and loses cleanly.
+    fn transition(&self, id: &str, from: &SessionStatus, to: &SessionStatus) -> Result<bool> {
+        let (id, from, to) = (id.to_string(), status_name(from)?, status_name(to)?);
+        self.client.run(move |client| {
+            let mut tx = client.transaction()?;
+            let current = tx.query_opt("SELECT status FROM sessions WHERE id = $1 FOR UPDATE", &[&id])?;
+            if current.map(|row| row.get::<_, String>(0)) != Some(from) {
+                return Ok(false);
+            }
+            tx.execute(
+                "UPDATE sessions SET status = $2, data = jsonb_set(data, '{status}', to_jsonb($2::text))
+                 WHERE id = $1",
+                &[&id, &to],
+            )?;
+            tx.commit()?;
+            Ok(true)
+        })
+    }
+
+    fn delete_if(&self, id: &str, status: &SessionStatus) -> Result<bool> {
+        let (id, status) = (id.to_string(), status_name(status)?);
+        self.client.run(move |client| {
+            let deleted = client.execute(
+                "DELETE FROM sessions WHERE id = $1 AND status = $2",
+                &[&id, &status],
+            )?;
+            Ok(deleted == 1)
+        })
+    }
+
+    fn query(&self, query: &SessionQuery) -> Result<SessionPage> {
+        let mut sql = String::from(if query.summary {
+            "SELECT jsonb_set(data, '{call_log}', '[]'::jsonb) FROM sessions WHERE true"
+        } else {
+            "SELECT data FROM sessions WHERE true"
+        });
+        let mut params: Vec<PgParam> = Vec::new();
+        if !query.status.is_empty() {
+            let statuses = query
+                .status
+                .iter()
+                .map(status_name)
+                .collect::<Result<Vec<_>>>()?;
+            params.push(Box::new(statuses));
+            sql.push_str(&format!(" AND status = ANY(${})", params.len()));
+        }
+        if let Some(agent) = &query.agent {
+            params.push(Box::new(agent.clone()));
+            sql.push_str(&format!(" AND data->>'agent' = ${}", params.len()));
+        }
+        if let Some(recipe) = &query.recipe {
+            params.push(Box::new(recipe.clone()));
+            sql.push_str(&format!(" AND data->>'recipe' = ${}", params.len()));
+        }
+        if let Some(signal) = &query.signal {
+            params.push(Box::new(signal.clone()));
+            let n = params.len();
+            sql.push_str(&format!(
+                " AND (data->>'pending_signal_name' = ${n}
+                      OR data->'pending_signal_names' ? ${n})"
+            ));
+        }
+        match &query.tenant {
+            TenantFilter::Any => {}
+            TenantFilter::Untenanted => sql.push_str(" AND data->>'tenant' IS NULL"),
+            TenantFilter::Tenant(tenant) => {
+                params.push(Box::new(tenant.clone()));
+                sql.push_str(&format!(" AND data->>'tenant' = ${}", params.len()));
+            }
+        }
+        if let Some(after) = query.created_after {
+            params.push(Box::new(after));
+            sql.push_str(&format!(" AND created_at >= ${}", params.len()));
+        }
+        if let Some(before) = query.created_before {
+            params.push(Box::new(before));
+            sql.push_str(&format!(" AND created_at < ${}", params.len()));
+        }
+        if let Some(cursor) = &query.cursor {
+            // The column keeps microseconds; the cursor comes from the JSON
+            // copy, which keeps nanoseconds. Compare at the column's precision
+            // or the cursor's own row would sort before it and repeat.
+            use chrono::SubsecRound as _;
+            params.push(Box::new(cursor.created_at.trunc_subsecs(6)));
+            params.push(Box::new(cursor.id.clone()));
+            let (at, id) = (params.len() - 1, params.len());
+            sql.push_str(&format!(" AND (created_at, id) < (${at}, ${id})"));
+        }
+        params.push(Box::new(query.limit as i64 + 1));
+        sql.push_str(&format!(
+            " ORDER BY created_at DESC, id DESC LIMIT ${}",
+            params.len()
+        ));
+        let sessions = self.select(sql, params)?;
+        Ok(SessionPage::from_rows(sessions, query.limit))
+    }
+
+    fn latest_scheduled(&self, recipe: &str) -> Result<Option<StoredSession>> {
+        let sessions = self.select(
+            "SELECT data FROM sessions
+             WHERE data->>'recipe' = $1 AND data->>'scheduled_for' IS NOT NULL
+             ORDER BY (data->>'scheduled_for')::timestamptz DESC
+             LIMIT 1"
+                .to_string(),
+            vec![Box::new(recipe.to_string())],
+        )?;
+        Ok(sessions.into_iter().next())
+    }
+
+    // `ON CONFLICT … DO UPDATE … WHERE` checks the owner under the row lock
+    // the conflicting insert takes, so a racing writer on another server
+    // waits and then sees the winner's tenant.
+    fn put_owned(&self, s: &StoredSession) -> Result<bool> {
+        self.write(s, true)
+    }
+
+    fn spend_since(
+        &self,
+        tenant: &TenantFilter,
+        since: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<f64> {
+        let mut sql =
+            String::from("SELECT COALESCE(SUM(usd), 0)::float8 FROM session_spend WHERE true");
+        let mut params: Vec<PgParam> = Vec::new();
+        match tenant {
+            TenantFilter::Any => {}
+            TenantFilter::Untenanted => sql.push_str(" AND tenant IS NULL"),
+            TenantFilter::Tenant(tenant) => {
+                params.push(Box::new(tenant.clone()));
+                sql.push_str(&format!(" AND tenant = ${}", params.len()));
+            }
+        }
+        if let Some(since) = since {
+            params.push(Box::new(since));
+            sql.push_str(&format!(" AND created_at >= ${}", params.len()));
+        }
+        self.client.run(move |client| {
+            let params: Vec<&(dyn postgres::types::ToSql + Sync)> =
+                params.iter().map(|p| p.as_ref() as _).collect();
+            Ok(client.query_one(&sql, &params)?.get::<_, f64>(0))
+        })
+    }
+
+    fn count_by_status(&self) -> Result<Vec<(SessionStatus, u64)>> {
+        let rows = self.client.run(|client| {
+            Ok(client.query("SELECT status, COUNT(*) FROM sessions GROUP BY status", &[])?)
+        })?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                let status = serde_json::from_value(Value::String(row.get(0))).ok()?;
+                Some((status, row.get::<_, i64>(1) as u64))
+            })
+            .collect())
+    }
+}
+
+/// One tenant's view of a shared store (`tenants:` in the app manifest).
+/// Sessions it writes are stamped with its tenant, and it reads, moves and
+/// deletes only those; a session id another tenant owns is taken. `None` is
+/// the view of sessions outside every tenant — the operator's, once tenants
+/// are configured.
+pub struct TenantScopedStore {
+    inner: Arc<dyn SessionStore>,
+    tenant: Option<String>,
+}
+
+impl TenantScopedStore {
+    pub fn new(inner: Arc<dyn SessionStore>, tenant: Option<String>) -> Self {
+        Self { inner, tenant }
+    }
+
+    fn owns(&self, session: &StoredSession) -> bool {
+        session.tenant == self.tenant
+    }
+}
+
+impl SessionStore for TenantScopedStore {
+    fn put(&self, session: &StoredSession) -> Result<()> {
+        if !self.put_owned(session)? {
+            anyhow::bail!("session id `{}` is taken", session.id);
+        }
+        Ok(())
+    }
+
+    // Whatever tenant `session` names, this view writes it as its own.
+    fn put_owned(&self, session: &StoredSession) -> Result<bool> {
+        let mut session = session.clone();
+        session.tenant = self.tenant.clone();
+        self.inner.put_owned(&session)
+    }
+
+    fn get(&self, id: &str) -> Result<Option<StoredSession>> {
+        Ok(self.inner.get(id)?.filter(|s| self.owns(s)))
+    }
+
+    // The newest 200, like the SQL stores' own `list`.
+    fn list(&self) -> Result<Vec<StoredSession>> {
+        let query = SessionQuery {
+            limit: 200,
+            ..Default::default()
+        };
+        Ok(self.query(&query)?.sessions)
+    }
+
+    fn delete(&self, id: &str) -> Result<()> {
+        match self.get(id)? {
+            Some(_) => self.inner.delete(id),
+            None => Ok(()),
+        }
+    }
+
+    fn transition(&self, id: &str, from: &SessionStatus, to: &SessionStatus) -> Result<bool> {
+        match self.get(id)? {
+            Some(_) => self.inner.transition(id, from, to),
+            None => Ok(false),
+        }
+    }
+
+    fn delete_if(&self, id: &str, status: &SessionStatus) -> Result<bool> {
+        match self.get(id)? {
+            Some(_) => self.inner.delete_if(id, status),
+            None => Ok(false),
+        }
+    }
+
+    fn query(&self, query: &SessionQuery) -> Result<SessionPage> {
+        let mut query = query.clone();
+        query.tenant = TenantFilter::of(self.tenant.as_deref());
+        self.inner.query(&query)
+    }
+
+    // Recipe names are unique across tenants (one manifest declares them
+    // all), so the shared store's latest tick is this view's or nobody's.
+    fn latest_scheduled(&self, recipe: &str) -> Result<Option<StoredSession>> {
+        Ok(self
+            .inner
+            .latest_scheduled(recipe)?
+            .filter(|s| self.owns(s)))
+    }
+
+    // `GET /metrics` is the operator's view of the whole server.
+    fn count_by_status(&self) -> Result<Vec<(SessionStatus, u64)>> {
+        self.inner.count_by_status()
+    }
+
+    fn spend_since(
+        &self,
+        _tenant: &TenantFilter,
+        since: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<f64> {
+        self.inner
+            .spend_since(&TenantFilter::of(self.tenant.as_deref()), since)
+    }
+}
+
+/// Estimated USD spent by the model calls of the sessions `store` shows
+/// that were created at or after `since` (all of them when `None`),
+/// including sessions since deleted.
+pub fn spend_since(
+    store: &dyn SessionStore,
+    since: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<f64> {
+    store.spend_since(&TenantFilter::Any, since)
 }
 
 /// Build the SessionStore configured by env. Durable by default: sessions go
 /// to SQLite at `CHIDORI_DB_PATH`, or `<base_dir>/.chidori/sessions.sqlite3`
-/// when unset. `CHIDORI_DB_PATH=:memory:` (or `memory`) opts into the
-/// non-durable in-memory store. A SQLite store that fails to open is a hard
+/// when unset. A `postgres://` value selects [`PostgresStore`].
+/// `CHIDORI_DB_PATH=:memory:` (or `memory`) opts into the non-durable
+/// in-memory store. A SQLite store that fails to open is a hard
 /// startup error — a durability framework must not silently downgrade to a
 /// store that loses every session on restart.
 pub fn build_session_store(base_dir: &std::path::Path) -> Result<std::sync::Arc<dyn SessionStore>> {
@@ -244,6 +1295,13 @@ pub fn build_session_store(base_dir: &std::path::Path) -> Result<std::sync::Arc<
             tracing::info!("session store: in-memory (CHIDORI_DB_PATH=:memory:)");
             return Ok(std::sync::Arc::new(MemoryStore::new()));
         }
+        Ok(v) if v.starts_with("postgres://") || v.starts_with("postgresql://") => {
+            let store = PostgresStore::connect(v.trim())
+                .context("connecting to the Postgres session store (CHIDORI_DB_PATH)")?;
+            // The URL carries credentials; don't log it.
+            tracing::info!("session store: postgres");
+            return Ok(std::sync::Arc::new(store));
+        }
         Ok(v) => PathBuf::from(v),
         Err(_) => base_dir.join(".chidori").join("sessions.sqlite3"),
     };
@@ -302,10 +1360,30 @@ mod tests {
             pending_approval: None,
             approvals: Vec::new(),
             policy_profile: None,
+            agent: None,
+            recipe: None,
+            scheduled_for: None,
+            tenant: None,
             created_at: chrono::Utc::now(),
         }
     }
 
+    /// `CHIDORI_TEST_POSTGRES_URL` scoped to a fresh schema, so each test
+    /// sees an empty `sessions` table. `None` when the variable is unset: the
+    /// cross-store tests then cover the other stores, and the Postgres-only
+    /// ones are `#[ignore]`d for CI's `postgres` job.
+    fn postgres_test_url() -> Option<String> {
+        let url = std::env::var("CHIDORI_TEST_POSTGRES_URL").ok()?;
+        let schema = format!("chidori_test_{}", uuid::Uuid::new_v4().simple());
+        let ddl = format!("CREATE SCHEMA {schema}");
+        PgClient::connect(&url)
+            .unwrap()
+            .run(move |client| Ok(client.batch_execute(&ddl)?))
+            .unwrap();
+        let sep = if url.contains('?') { '&' } else { '?' };
+        Some(format!("{url}{sep}options=-csearch_path%3D{schema}"))
+    }
+
     #[test]
     fn sqlite_store_persists_across_reopen() {
         // The durable default must actually be durable: a session written
@@ -325,6 +1403,314 @@ mod tests {
         assert_eq!(got.status, SessionStatus::Completed);
     }
 
+    #[test]
+    fn latest_scheduled_picks_the_newest_tick_of_the_recipe() {
+        let dir = tempfile::tempdir().unwrap();
+        let sqlite = SqliteStore::open(dir.path().join("sessions.sqlite3")).unwrap();
+        let memory = MemoryStore::new();
+        let tick = |hour: u32| {
+            chrono::DateTime::parse_from_rfc3339(&format!("2026-05-17T{hour:02}:00:00Z"))
+                .unwrap()
+                .with_timezone(&chrono::Utc)
+        };
+        let mut sessions = Vec::new();
+        for (id, recipe, scheduled_for) in [
+            ("a", "digest", Some(tick(9))),
+            ("b", "digest", Some(tick(11))),
+            ("c", "digest", None),
+            ("d", "other", Some(tick(12))),
+        ] {
+            let mut session = sample_session(id);
+            session.recipe = Some(recipe.to_string());
+            session.scheduled_for = scheduled_for;
+            sessions.push(session);
+        }
+        let postgres = postgres_test_url().map(|url| PostgresStore::connect(&url).unwrap());
+        let mut stores: Vec<&dyn SessionStore> = vec![&sqlite, &memory];
+        stores.extend(postgres.as_ref().map(|s| s as &dyn SessionStore));
+        for store in stores {
+            for session in &sessions {
+                store.put(session).unwrap();
+            }
+            let latest = store.latest_scheduled("digest").unwrap().unwrap();
+            assert_eq!(latest.id, "b");
+            assert_eq!(latest.scheduled_for, Some(tick(11)));
+            assert!(store.latest_scheduled("missing").unwrap().is_none());
+        }
+    }
+
+    #[test]
+    fn query_pages_and_filters_the_same_in_every_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let sqlite = SqliteStore::open(dir.path().join("sessions.sqlite3")).unwrap();
+        let memory = MemoryStore::new();
+        let at = |minute: u32| {
+            chrono::DateTime::parse_from_rfc3339(&format!("2026-05-17T09:{minute:02}:00Z"))
+                .unwrap()
+                .with_timezone(&chrono::Utc)
+        };
+        let mut sessions = Vec::new();
+        // `c` and `d` share a timestamp: the id breaks the tie.
+        for (id, minute, status, signal) in [
+            ("a", 1, SessionStatus::Completed, None),
+            ("b", 2, SessionStatus::Paused, Some("approval")),
+            ("c", 3, SessionStatus::Failed, None),
+            ("d", 3, SessionStatus::Paused, Some("webhook")),
+            ("e", 4, SessionStatus::Completed, None),
+        ] {
+            let mut session = sample_session(id);
+            session.created_at = at(minute);
+            session.status = status;
+            session.agent = Some(format!("agents/{}.ts", if minute < 3 { "x" } else { "y" }));
+            if let Some(signal) = signal {
+                session.pending_signal_names = vec![signal.to_string()];
+            }
+            sessions.push(session);
+        }
+        let postgres = postgres_test_url().map(|url| PostgresStore::connect(&url).unwrap());
+        let mut stores: Vec<&dyn SessionStore> = vec![&sqlite, &memory];
+        stores.extend(postgres.as_ref().map(|s| s as &dyn SessionStore));
+        for store in stores {
+            for session in &sessions {
+                store.put(session).unwrap();
+            }
+            let ids = |query: &SessionQuery| -> Vec<String> {
+                let page = store.query(query).unwrap();
+                page.sessions.into_iter().map(|s| s.id).collect()
+            };
+
+            // Two at a time, newest first, through an encoded cursor.
+            let mut query = SessionQuery {
+                limit: 2,
+                ..Default::default()
+            };
+            let mut seen = Vec::new();
+            loop {
+                let page = store.query(&query).unwrap();
+                seen.extend(page.sessions.into_iter().map(|s| s.id));
+                let Some(next) = page.next_cursor else { break };
+                query.cursor = Some(SessionCursor::decode(&next.encode()).unwrap());
+            }
+            assert_eq!(seen, ["e", "d", "c", "b", "a"]);
+
+            let paused = SessionQuery {
+                status: vec![SessionStatus::Paused],
+                ..Default::default()
+            };
+            assert_eq!(ids(&paused), ["d", "b"]);
+            let webhook = SessionQuery {
+                signal: Some("webhook".to_string()),
+                ..Default::default()
+            };
+            assert_eq!(ids(&webhook), ["d"]);
+            let agent_x = SessionQuery {
+                agent: Some("agents/x.ts".to_string()),
+                ..Default::default()
+            };
+            assert_eq!(ids(&agent_x), ["b", "a"]);
+            let window = SessionQuery {
+                created_after: Some(at(2)),
+                created_before: Some(at(4)),
+                status: vec![SessionStatus::Paused, SessionStatus::Failed],
+                ..Default::default()
+            };
+            assert_eq!(ids(&window), ["d", "c", "b"]);
+
+            let counts = store.count_by_status().unwrap();
+            let count =
+                |status: SessionStatus| counts.iter().find(|(s, _)| *s == status).map(|(_, n)| *n);
+            assert_eq!(counts.len(), 3, "{counts:?}");
+            assert_eq!(count(SessionStatus::Completed), Some(2));
+            assert_eq!(count(SessionStatus::Paused), Some(2));
+            assert_eq!(count(SessionStatus::Failed), Some(1));
+        }
+    }
+
+    #[test]
+    fn transition_claims_a_pause_once_in_every_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let sqlite = SqliteStore::open(dir.path().join("sessions.sqlite3")).unwrap();
+        let memory = MemoryStore::new();
+        let postgres = postgres_test_url().map(|url| PostgresStore::connect(&url).unwrap());
+        let mut stores: Vec<&dyn SessionStore> = vec![&sqlite, &memory];
+        stores.extend(postgres.as_ref().map(|s| s as &dyn SessionStore));
+        let (paused, running) = (SessionStatus::Paused, SessionStatus::Running);
+        for store in stores {
+            let mut session = sample_session("s-1");
+            session.status = SessionStatus::Paused;
+            store.put(&session).unwrap();
+            assert!(store.transition("s-1", &paused, &running).unwrap());
+            assert!(!store.transition("s-1", &paused, &running).unwrap());
+            assert!(!store.transition("missing", &paused, &running).unwrap());
+            assert_eq!(store.get("s-1").unwrap().unwrap().status, running);
+            // The status column moves with the blob, so queries agree.
+            let query = SessionQuery {
+                status: vec![SessionStatus::Running],
+                ..Default::default()
+            };
+            assert_eq!(store.query(&query).unwrap().sessions.len(), 1);
+        }
+    }
+
+    #[test]
+    fn tenant_scoped_stores_see_only_their_own_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let sqlite: Arc<dyn SessionStore> =
+            Arc::new(SqliteStore::open(dir.path().join("sessions.sqlite3")).unwrap());
+        let memory: Arc<dyn SessionStore> = Arc::new(MemoryStore::new());
+        let postgres = postgres_test_url()
+            .map(|url| Arc::new(PostgresStore::connect(&url).unwrap()) as Arc<dyn SessionStore>);
+        let (paused, running) = (SessionStatus::Paused, SessionStatus::Running);
+        for shared in [Some(sqlite), Some(memory), postgres].into_iter().flatten() {
+            let acme = TenantScopedStore::new(shared.clone(), Some("acme".to_string()));
+            let globex = TenantScopedStore::new(shared.clone(), Some("globex".to_string()));
+            let operator = TenantScopedStore::new(shared.clone(), None);
+            let mut session = sample_session("a-1");
+            session.status = SessionStatus::Paused;
+            acme.put(&session).unwrap();
+            operator.put(&sample_session("o-1")).unwrap();
+
+            assert_eq!(
+                shared.get("a-1").unwrap().unwrap().tenant.as_deref(),
+                Some("acme")
+            );
+            assert!(globex.get("a-1").unwrap().is_none());
+            assert!(operator.get("a-1").unwrap().is_none());
+            let err = globex.put(&sample_session("a-1")).unwrap_err();
+            assert!(err.to_string().contains("is taken"), "{err}");
+            assert!(!globex.transition("a-1", &paused, &running).unwrap());
+            globex.delete("a-1").unwrap();
+            assert!(acme.get("a-1").unwrap().is_some());
+
+            let ids = |store: &TenantScopedStore| -> Vec<String> {
+                store.list().unwrap().into_iter().map(|s| s.id).collect()
+            };
+            assert_eq!(ids(&acme), ["a-1"]);
+            assert!(ids(&globex).is_empty());
+            assert_eq!(ids(&operator), ["o-1"]);
+            assert!(acme.transition("a-1", &paused, &running).unwrap());
+        }
+    }
+
+    #[test]
+    fn spend_outlives_deleted_sessions_in_every_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let sqlite_path = dir.path().join("sessions.sqlite3");
+        let sqlite: Arc<dyn SessionStore> =
+            Arc::new(SqliteStore::open(sqlite_path.clone()).unwrap());
+        let memory: Arc<dyn SessionStore> = Arc::new(MemoryStore::new());
+        let postgres = postgres_test_url()
+            .map(|url| Arc::new(PostgresStore::connect(&url).unwrap()) as Arc<dyn SessionStore>);
+        // 1M in + 1M out on claude-sonnet-4-6: $18.
+        let spent = |id: &str| {
+            let mut session = sample_session(id);
+            session.call_log = vec![serde_json::from_value(serde_json::json!({
+                "seq": 0, "function": "prompt", "args": {"model": "claude-sonnet-4-6"},
+                "result": "ok", "duration_ms": 1, "timestamp": "2026-05-17T09:01:00Z",
+                "token_usage": {"input_tokens": 1_000_000, "output_tokens": 1_000_000},
+            }))
+            .unwrap()];
+            session
+        };
+        for shared in [Some(sqlite), Some(memory), postgres].into_iter().flatten() {
+            let acme = TenantScopedStore::new(shared.clone(), Some("acme".to_string()));
+            let globex = TenantScopedStore::new(shared.clone(), Some("globex".to_string()));
+            acme.put(&spent("a-1")).unwrap();
+            // Writing the session again replaces its ledger row.
+            acme.put(&spent("a-1")).unwrap();
+            acme.put(&spent("a-2")).unwrap();
+            acme.delete("a-1").unwrap();
+            shared.delete("a-2").unwrap();
+
+            assert!((spend_since(&acme, None).unwrap() - 36.0).abs() < 1e-9);
+            assert_eq!(spend_since(&globex, None).unwrap(), 0.0);
+            assert!((spend_since(&*shared, None).unwrap() - 36.0).abs() < 1e-9);
+            let later = chrono::Utc::now() + chrono::Duration::hours(1);
+            assert_eq!(spend_since(&acme, Some(later)).unwrap(), 0.0);
+
+            // Ownership is checked in the write itself.
+            let mut taken = sample_session("a-4");
+            taken.tenant = Some("acme".to_string());
+            assert!(shared.put_owned(&taken).unwrap());
+            taken.tenant = Some("globex".to_string());
+            assert!(!shared.put_owned(&taken).unwrap());
+            assert_eq!(
+                shared.get("a-4").unwrap().unwrap().tenant.as_deref(),
+                Some("acme")
+            );
+        }
+
+        // A ledger created over an existing session table starts from the
+        // sessions already in it.
+        SqliteStore::open(sqlite_path.clone())
+            .unwrap()
+            .put(&spent("b-1"))
+            .unwrap();
+        let conn = rusqlite::Connection::open(&sqlite_path).unwrap();
+        conn.execute_batch("DROP TABLE session_spend").unwrap();
+        drop(conn);
+        let reopened = SqliteStore::open(sqlite_path).unwrap();
+        assert!((spend_since(&reopened, None).unwrap() - 18.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn delete_if_leaves_a_session_whose_status_moved_on() {
+        let dir = tempfile::tempdir().unwrap();
+        let sqlite: Arc<dyn SessionStore> =
+            Arc::new(SqliteStore::open(dir.path().join("sessions.sqlite3")).unwrap());
+        let memory: Arc<dyn SessionStore> = Arc::new(MemoryStore::new());
+        let postgres = postgres_test_url()
+            .map(|url| Arc::new(PostgresStore::connect(&url).unwrap()) as Arc<dyn SessionStore>);
+        for store in [Some(sqlite), Some(memory), postgres].into_iter().flatten() {
+            let mut session = sample_session("deletable");
+            session.status = SessionStatus::Paused;
+            store.put(&session).unwrap();
+            assert!(store
+                .transition("deletable", &SessionStatus::Paused, &SessionStatus::Running)
+                .unwrap());
+            assert!(!store
+                .delete_if("deletable", &SessionStatus::Paused)
+                .unwrap());
+            assert!(store.get("deletable").unwrap().is_some());
+            assert!(store
+                .delete_if("deletable", &SessionStatus::Running)
+                .unwrap());
+            assert!(store.get("deletable").unwrap().is_none());
+        }
+    }
+
+    #[test]
+    #[ignore = "needs CHIDORI_TEST_POSTGRES_URL"]
+    fn postgres_transition_has_one_winner_across_connections() {
+        let url =
+            postgres_test_url().expect("CHIDORI_TEST_POSTGRES_URL must name a scratch database");
+        let mut session = sample_session("contended");
+        session.status = SessionStatus::AwaitingApproval;
+        PostgresStore::connect(&url).unwrap().put(&session).unwrap();
+        // One connection per thread, as separate servers would have.
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let url = url.clone();
+                std::thread::spawn(move || {
+                    PostgresStore::connect(&url)
+                        .unwrap()
+                        .transition(
+                            "contended",
+                            &SessionStatus::AwaitingApproval,
+                            &SessionStatus::Running,
+                        )
+                        .unwrap()
+                })
+            })
+            .collect();
+        let winners = handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .filter(|won| *won)
+            .count();
+        assert_eq!(winners, 1);
+    }
+
     #[test]
     fn sqlite_store_open_fails_loudly_on_unusable_path() {
         // The old behavior silently fell back to the in-memory store when
@@ -1335,3 +1335,69 @@ fn cli_export_refuses_leased_run() {
 
     fs::remove_dir_all(dir).ok();
 }
+
+#[test]
+fn cli_run_writes_coverage_for_a_failed_run() {
+    let dir = temp_project("coverage-failed-run");
+    let agent = dir.join("agent.ts");
+    fs::write(
+        &agent,
+        r#"
+            export async function agent(input, chidori) {
+                await chidori.log("about to fail");
+                throw new Error("agent gave up");
+            }
+        "#,
+    )
+    .unwrap();
+    let coverage = dir.join("coverage");
+
+    let output = run_chidori(
+        &[
+            "run",
+            agent.to_str().unwrap(),
+            "--coverage",
+            coverage.to_str().unwrap(),
+        ],
+        &dir,
+    );
+    assert_failure(&output);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("agent gave up"));
+    let lcov = fs::read_to_string(coverage.join("lcov.info")).unwrap();
+    assert!(lcov.contains("agent.ts"), "{lcov}");
+
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn cli_run_writes_the_profile_of_a_failed_run() {
+    let dir = temp_project("profile-failed-run");
+    let agent = dir.join("agent.ts");
+    fs::write(
+        &agent,
+        r#"
+            export async function agent(input, chidori) {
+                await chidori.log("about to fail");
+                throw new Error("agent gave up");
+            }
+        "#,
+    )
+    .unwrap();
+    let profile = dir.join("agent.cpuprofile");
+
+    let output = run_chidori(
+        &[
+            "run",
+            agent.to_str().unwrap(),
+            "--profile",
+            profile.to_str().unwrap(),
+        ],
+        &dir,
+    );
+    assert_failure(&output);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("agent gave up"));
+    let written: serde_json::Value = serde_json::from_slice(&fs::read(&profile).unwrap()).unwrap();
+    assert_eq!(written["nodes"][0]["callFrame"]["functionName"], "(root)");
+
+    fs::remove_dir_all(dir).ok();
+}
@@ -281,7 +281,12 @@ class AgentClient:
         """Check server health."""
         return self._get("/health")
 
-    def run(self, input: dict, policy_profile: str | None = None) -> Session:
+    def run(
+        self,
+        input: dict,
+        policy_profile: str | None = None,
+        budget: dict | None = None,
+    ) -> Session:
         """Create a new session and run the agent with the given input.
 
         Returns a Session with the output, status, and call log. If the
@@ -295,10 +300,16 @@ class AgentClient:
         it can tighten what the operator allows, never relax it. Under
         "supervised", gated calls pause the session as "awaitingapproval";
         approve or deny them via the server's /approve endpoint.
+
+        `budget` caps the session's estimated spend, e.g.
+        `{"max_cost_usd": 2.0, "on_exceed": "ask"}` — overriding the
+        server's CHIDORI_MAX_COST_USD and holding across resumes.
         """
         body: dict[str, Any] = {"input": input}
         if policy_profile is not None:
             body["policy_profile"] = policy_profile
+        if budget is not None:
+            body["budget"] = budget
         data = self._post("/sessions", body)
         return Session(
             id=data["id"],
@@ -96,6 +96,27 @@ export type PromptStreamType = "progress" | "draft" | "subagent" | "final" | (st
 /** Provider prompt-cache lifetime for a cached prefix. */
 export type CacheTtl = "5m" | "1h";
 
+/**
+ * One part of a multimodal user turn. Media comes from a workspace file
+ * (`path`, media type inferred from the extension) or inline base64 `data`
+ * (requires `mediaType`). The call log records each attachment by content
+ * hash, never its bytes, so replay stays byte-identical without bloating
+ * the journal.
+ */
+export type ContentPart =
+  | string
+  | { type: "text"; text: string }
+  | { type: "image"; path: string; mediaType?: string }
+  | { type: "image"; data: string; mediaType: string }
+  | { type: "document"; path: string; mediaType?: string; title?: string }
+  | { type: "document"; data: string; mediaType: string; title?: string };
+
+/** Options for `chidori.embed()`. */
+export interface EmbedOptions {
+  /** Embedding model (default `CHIDORI_EMBEDDING_MODEL`, then `text-embedding-3-small`). */
+  model?: string;
+}
+
 export interface PromptOptions {
   type?: PromptStreamType;
   system?: string;
@@ -128,6 +149,26 @@ export interface PromptOptions {
    * empty reply. Off by default: an empty reply resolves to `""`.
    */
   nonEmpty?: boolean;
+  /**
+   * Structured output: a JSON Schema or a Standard Schema validator the reply
+   * must satisfy. The JSON Schema form is enforced natively where the
+   * provider supports it; the reply is then parsed and validated, and on
+   * failure re-asked with the validation errors up to `schemaRetries` times.
+   * Resolves to the validated value; throws `StructuredOutputError` (with
+   * `issues` and the last `reply`) once retries are exhausted. Supersedes
+   * `format` / `strict`.
+   */
+  schema?: StandardSchemaLike | JsonObject;
+  /** Re-asks after a reply fails `schema` validation. Default 2. */
+  schemaRetries?: number;
+  /**
+   * Extended thinking / reasoning effort for models that support it. Set a
+   * thinking `budgetTokens`, an `effort` level, or both; each is derived from
+   * the other when only one is given. The budget is on top of `maxTokens`.
+   * Anthropic returns thinking blocks (kept verbatim across tool turns),
+   * OpenAI reasoning models get `reasoning_effort`, Gemini a thinking budget.
+   */
+  reasoning?: { budgetTokens?: number; effort?: "minimal" | "low" | "medium" | "high" };
   stream?: boolean;
   /**
    * Prompt-cache posture. Defaults to on (`"5m"`): the runtime marks the
@@ -137,6 +178,11 @@ export interface PromptOptions {
    * Caching never changes a response — only how it is billed.
    */
   cache?: boolean | CacheTtl | { ttl?: CacheTtl };
+  /**
+   * Images and documents sent alongside the prompt text in the same user
+   * turn (`chidori.prompt` only; contexts use `user([...parts])`).
+   */
+  attachments?: ContentPart[];
 }
 
 /** Structured response from `Context.respond()` — mirrors the provider turn. */
@@ -152,10 +198,13 @@ export interface LlmResponseJson {
   cacheCreationTokens: number;
   cacheReadTokens: number;
   /** Hidden reasoning text from reasoning models (OpenAI-compatible
-   * `reasoning_content`, e.g. DeepSeek). Present only when the provider
-   * reported it; never part of the conversation. Useful for inspecting why
-   * a model spent output budget before its visible answer. */
+   * `reasoning_content`, e.g. DeepSeek, or Anthropic thinking). Present only
+   * when the provider reported it; never part of the visible content. Useful
+   * for inspecting why a model spent output budget before its visible answer. */
   reasoning?: string;
+  /** Output tokens spent on reasoning (already included in `outputTokens`).
+   * Present only when the provider reported a non-zero count. */
+  reasoningTokens?: number;
 }
 
 /** Options for `Context.compact()` — explicit, opt-in window compaction. */
@@ -206,7 +255,8 @@ export interface Context {
   tools(list: Array<string | ToolHandle>): Context;
   /** A large stable reference block, labelled for the trace. */
   doc(label: string, text: string): Context;
-  user(text: string): Context;
+  /** A user turn: plain text, or text mixed with images and documents. */
+  user(content: string | ContentPart[]): Context;
   assistant(text: string): Context;
   toolResult(id: string, content: string, isError?: boolean): Context;
   /**
@@ -739,6 +789,41 @@ export interface MemoryStore {
   /** List entries, optionally filtered with `options.prefix`. */
   list(options?: JsonObject): Promise<AgentJson[]>;
   clear(options?: JsonObject): Promise<void>;
+  /** Embed `text` and index it under `key` for `search` (replaces an existing entry). */
+  upsert(key: string, text: string, options?: MemoryUpsertOptions): Promise<void>;
+  /** The `k` indexed entries most similar to `query`, best first. */
+  search<M extends JsonObject = JsonObject>(
+    query: string,
+    options?: MemorySearchOptions,
+  ): Promise<MemoryHit<M>[]>;
+}
+
+/** Options for `chidori.memory.upsert()`. */
+export interface MemoryUpsertOptions {
+  namespace?: string;
+  /** Stored with the entry; returned on hits and matched by `filter`. */
+  metadata?: JsonObject;
+  /** Embedding model (see `EmbedOptions.model`); search with the same one. */
+  model?: string;
+}
+
+/** Options for `chidori.memory.search()`. */
+export interface MemorySearchOptions {
+  namespace?: string;
+  /** Number of hits (default 5). */
+  k?: number;
+  /** Metadata fields to match: equal to the value, or to any element of an array. */
+  filter?: JsonObject;
+  model?: string;
+}
+
+/** One `chidori.memory.search()` result. */
+export interface MemoryHit<M extends JsonObject = JsonObject> {
+  key: string;
+  text: string;
+  metadata: M;
+  /** Cosine similarity to the query. */
+  score: number;
 }
 
 /**
@@ -766,6 +851,35 @@ export interface AppData {
   query(sql: string, params?: AgentJson[]): Promise<AgentJson>;
 }
 
+/** One entry of an MCP `resources/read` result. */
+export interface McpResourceContents {
+  uri: string;
+  mimeType?: string;
+  /** Text resources. */
+  text?: string;
+  /** Binary resources, base64-encoded. */
+  blob?: string;
+}
+
+/** An MCP `prompts/get` result: ready-made conversation turns. */
+export interface McpPrompt {
+  description?: string;
+  messages: { role: "user" | "assistant"; content: JsonObject }[];
+}
+
+/**
+ * `chidori.mcp` — resources and prompts on a connected MCP server, addressed
+ * by its config id. Each call is journaled and replays without the server.
+ * Sampling requests the server makes meanwhile are answered with the run's
+ * own providers and journaled under the originating call.
+ */
+export interface McpNamespace {
+  listResources(server: string): Promise<JsonObject[]>;
+  readResource(server: string, uri: string): Promise<McpResourceContents[]>;
+  listPrompts(server: string): Promise<JsonObject[]>;
+  getPrompt(server: string, name: string, args?: Record<string, string>): Promise<McpPrompt>;
+}
+
 export interface TryCallResult<T> {
   ok: boolean;
   value?: T;
@@ -834,6 +948,14 @@ export interface Chidori {
    * an interactive `input()` loop with `chat.loop()`.
    */
   conversation(options?: ConversationOptions): Conversation;
+  /**
+   * With `schema`, resolves to the validated value: a Standard Schema
+   * validator's output type, else `AgentJson`.
+   */
+  prompt<S extends StandardSchemaLike | JsonObject>(
+    text: string,
+    options: PromptOptions & { schema: S },
+  ): Promise<SchemaOutput<S>>;
   /**
    * `format: "json"` parses the reply as JSON before returning it, so the
    * resolved value is structured data (`AgentJson`), not a string. Narrow it
@@ -843,6 +965,13 @@ export interface Chidori {
    */
   prompt(text: string, options: PromptOptions & { format: "json" }): Promise<AgentJson>;
   prompt(text: string, options?: PromptOptions): Promise<string>;
+  /**
+   * Embed text as vectors: a string resolves to one vector, an array to one
+   * vector per input (same order). One durable host call per invocation;
+   * replay returns the recorded vectors.
+   */
+  embed(text: string, options?: EmbedOptions): Promise<number[]>;
+  embed(texts: string[], options?: EmbedOptions): Promise<number[][]>;
   input(message: string, options?: InputOptions): Promise<string>;
   /**
    * Pause at a named listen point until a matching signal is delivered (or one
@@ -971,6 +1100,8 @@ export interface Chidori {
   memory: MemoryStore;
   /** The journaled agent-run application-data store (generative-UI runs). */
   appData: AppData;
+  /** Resources and prompts on connected MCP servers. */
+  mcp: McpNamespace;
   /**
    * Record a labelled trace marker in the call log — an annotation, nothing
    * more. (The durable VALUE checkpoint is `chidori.step`.)
@@ -1023,9 +1154,17 @@ export interface StandardSchemaLike {
     version?: number;
     vendor?: string;
     validate: (value: unknown) => unknown;
+    types?: { input: unknown; output: unknown };
   };
 }
 
+/** The value a `prompt({ schema })` resolves to. */
+export type SchemaOutput<S> = S extends { "~standard": { types?: infer T } }
+  ? NonNullable<T> extends { output: infer O }
+    ? O
+    : AgentJson
+  : AgentJson;
+
 export interface RunOptions {
   /**
    * Validate the run input before the handler executes — deterministically,
@@ -27,6 +27,7 @@ export type {
   Chidori,
   ChidoriUtil,
   CompactOptions,
+  ContentPart,
   Context,
   Conversation,
   ConversationLoopOptions,
@@ -37,6 +38,7 @@ export type {
   DetachedAgentOutcome,
   DetachedAgents,
   DetachedAgentStatus,
+  EmbedOptions,
   InputOptions,
   JoinActorOptions,
   JsonObject,
@@ -44,7 +46,13 @@ export type {
   LlmResponseJson,
   LogFields,
   MapSetSnapshotPolicy,
+  McpNamespace,
+  McpPrompt,
+  McpResourceContents,
+  MemoryHit,
+  MemorySearchOptions,
   MemoryStore,
+  MemoryUpsertOptions,
   ParallelOptions,
   PromptOptions,
   PromptStreamType,
@@ -98,6 +106,17 @@ export type SessionStatus =
  */
 export type PolicyProfile = "untrusted" | "supervised";
 
+/**
+ * A cost ceiling for one session, in estimated USD. Before each live prompt
+ * the runtime prices the session's recorded token usage; once it reaches
+ * `maxCostUsd` the prompt fails (`onExceed: "fail"`, the default) or pauses
+ * the session for approval on the `budget` target (`onExceed: "ask"`).
+ */
+export interface CostBudget {
+  maxCostUsd: number;
+  onExceed?: "fail" | "ask";
+}
+
 /** A single host function call recorded during an agent run. */
 export interface CallRecord {
   seq: number;
@@ -523,12 +542,21 @@ export class AgentClient {
    * can tighten what the operator allows, never relax it. Under
    * "supervised", gated calls pause the session as "awaitingapproval";
    * approve or deny them via the server's /approve endpoint.
+   *
+   * `options.budget` caps the session's estimated spend, overriding the
+   * server's `CHIDORI_MAX_COST_USD`; it holds across resumes.
    */
-  async run(input: Json, options?: { policyProfile?: PolicyProfile }): Promise<Session> {
+  async run(
+    input: Json,
+    options?: { policyProfile?: PolicyProfile; budget?: CostBudget },
+  ): Promise<Session> {
     const body: Record<string, unknown> = { input };
     if (options?.policyProfile) {
       body.policy_profile = options.policyProfile;
     }
+    if (options?.budget) {
+      body.budget = options.budget;
+    }
     const data = await this.postJSON("/sessions", body);
     return this.sessionFrom(data, input);
   }
@@ -628,12 +656,11 @@ export class AgentClient {
    * `done` event. Prompt events include `prompt_type` so UIs can filter
    * progress streams separately from final-answer streams.
    *
-   * `options.policyProfile` mirrors `run()`: a built-in profile layered on
-   * the server policy with stricter-wins semantics for this session.
+   * `options.policyProfile` and `options.budget` mirror `run()`.
    */
   async *stream(
     input: Json,
-    options?: { policyProfile?: PolicyProfile },
+    options?: { policyProfile?: PolicyProfile; budget?: CostBudget },
   ): AsyncGenerator<StreamEvent, void, void> {
     // The timeout covers connection establishment (until response headers
     // arrive), not the open event stream — a healthy run may stream for a
@@ -645,6 +672,9 @@ export class AgentClient {
     if (options?.policyProfile) {
       body.policy_profile = options.policyProfile;
     }
+    if (options?.budget) {
+      body.budget = options.budget;
+    }
     let resp: Response;
     try {
       resp = await fetch(`${this.baseUrl}/sessions/stream`, {